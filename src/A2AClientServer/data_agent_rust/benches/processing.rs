@@ -0,0 +1,122 @@
+//! Processing throughput benchmarks
+//!
+//! Measures validation, CSV generation and end-to-end `send_task` with the
+//! in-memory store, for orders of 10, 100 and 1,000 lines. After the run a
+//! summary of every measurement is written to `target/criterion/processing-summary.csv`.
+//!
+//! Run with `cargo bench --bench processing`.
+
+use a2a::{A2AProtocol, Message};
+use criterion::{criterion_group, BenchmarkId, Criterion, Throughput};
+use data_agent_rust::{testing, ProcessingResult, PurchaseOrderAgent};
+use serde_json::Value;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+const LINE_COUNTS: [usize; 3] = [10, 100, 1_000];
+const GROUPS: [&str; 3] = ["validation", "csv_generation", "send_task"];
+
+fn message(lines: usize) -> Message {
+    let mut po = testing::huge_order(lines);
+    po.po_number = "PO-BENCH".to_string();
+    po.notes = Some("Deliver to the loading dock".to_string());
+    testing::message(&po)
+}
+
+fn bench_validation(c: &mut Criterion) {
+    let agent = PurchaseOrderAgent::new();
+    let mut group = c.benchmark_group("validation");
+    for lines in LINE_COUNTS {
+        let message = message(lines);
+        group.throughput(Throughput::Elements(lines as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(lines), &message, |b, message| {
+            b.iter(|| agent.validate(message).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn bench_csv_generation(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let agent = PurchaseOrderAgent::new();
+    let mut group = c.benchmark_group("csv_generation");
+    for lines in LINE_COUNTS {
+        let task = runtime.block_on(agent.send_task(message(lines))).unwrap();
+        let result = ProcessingResult::from_status(&task.status).unwrap();
+        group.bench_with_input(BenchmarkId::from_parameter(lines), &result, |b, result| b.iter(|| result.csv_row()));
+    }
+    group.finish();
+}
+
+fn bench_send_task(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let mut group = c.benchmark_group("send_task");
+    for lines in LINE_COUNTS {
+        // A fresh agent per size, so the store does not grow across sizes
+        let agent = PurchaseOrderAgent::new();
+        let message = message(lines);
+        group.throughput(Throughput::Elements(1));
+        group.bench_with_input(BenchmarkId::from_parameter(lines), &message, |b, message| {
+            b.iter(|| runtime.block_on(agent.send_task(message.clone())).unwrap())
+        });
+    }
+    group.finish();
+}
+
+/// Where criterion writes its results
+fn criterion_dir() -> PathBuf {
+    let target = std::env::var_os("CARGO_TARGET_DIR").map_or_else(|| PathBuf::from("target"), PathBuf::from);
+    target.join("criterion")
+}
+
+fn read_json(path: &Path) -> Option<Value> {
+    serde_json::from_slice(&std::fs::read(path).ok()?).ok()
+}
+
+/// Collect criterion's per-benchmark estimates into one CSV, one row per measurement
+fn write_summary() {
+    let dir = criterion_dir();
+    let mut csv = String::from("group,lines,mean_ns,mean_low_ns,mean_high_ns,median_ns,elements_per_second\n");
+    for group in GROUPS {
+        for lines in LINE_COUNTS {
+            let bench = dir.join(group).join(lines.to_string());
+            let (Some(estimates), Some(benchmark)) =
+                (read_json(&bench.join("new/estimates.json")), read_json(&bench.join("new/benchmark.json")))
+            else {
+                continue;
+            };
+            let mean = &estimates["mean"];
+            let ns = |value: &Value| value.as_f64().unwrap_or(f64::NAN);
+            let mean_ns = ns(&mean["point_estimate"]);
+            let rate = benchmark["throughput"]["Elements"].as_f64().map(|n| n * 1e9 / mean_ns);
+            let _ = writeln!(
+                csv,
+                "{},{},{:.0},{:.0},{:.0},{:.0},{}",
+                group,
+                lines,
+                mean_ns,
+                ns(&mean["confidence_interval"]["lower_bound"]),
+                ns(&mean["confidence_interval"]["upper_bound"]),
+                ns(&estimates["median"]["point_estimate"]),
+                rate.map(|rate| format!("{:.1}", rate)).unwrap_or_default(),
+            );
+        }
+    }
+    let path = dir.join("processing-summary.csv");
+    match std::fs::create_dir_all(&dir).and_then(|_| std::fs::write(&path, csv)) {
+        Ok(()) => println!("Summary written to {}", path.display()),
+        Err(e) => eprintln!("Could not write {}: {}", path.display(), e),
+    }
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(30);
+    targets = bench_validation, bench_csv_generation, bench_send_task
+}
+
+fn main() {
+    benches();
+    Criterion::default().configure_from_args().final_summary();
+    write_summary();
+}
@@ -0,0 +1,104 @@
+//! Concurrency benchmark for the in-memory task store
+//!
+//! Compares `MemoryTaskStore` against a single `Mutex<HashMap>` (the previous
+//! implementation) under a mixed read/write load from several threads.
+//!
+//! Run with `cargo bench --bench task_store`.
+
+use a2a::TaskState;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use data_agent_rust::store::{StoreResult, TaskRecord, TaskStore};
+use data_agent_rust::MemoryTaskStore;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+const TASKS: usize = 1_000;
+const OPS_PER_THREAD: usize = 2_000;
+
+/// The single-lock store this crate used before `MemoryTaskStore` went concurrent
+#[derive(Default)]
+struct MutexTaskStore {
+    records: Mutex<HashMap<String, TaskRecord>>,
+}
+
+impl TaskStore for MutexTaskStore {
+    fn put(&self, record: TaskRecord) -> StoreResult<()> {
+        self.records.lock().unwrap().insert(record.task.id.clone(), record);
+        Ok(())
+    }
+
+    fn get(&self, task_id: &str) -> StoreResult<Option<TaskRecord>> {
+        Ok(self.records.lock().unwrap().get(task_id).cloned())
+    }
+
+    fn update(&self, task_id: &str, update: &mut dyn FnMut(&mut TaskRecord)) -> StoreResult<Option<TaskRecord>> {
+        Ok(self.records.lock().unwrap().get_mut(task_id).map(|record| {
+            update(record);
+            record.clone()
+        }))
+    }
+
+    fn remove(&self, task_id: &str) -> StoreResult<Option<TaskRecord>> {
+        Ok(self.records.lock().unwrap().remove(task_id))
+    }
+
+    fn list(&self) -> StoreResult<Vec<TaskRecord>> {
+        Ok(self.records.lock().unwrap().values().cloned().collect())
+    }
+
+    fn remove_where(&self, predicate: &dyn Fn(&TaskRecord) -> bool) -> StoreResult<usize> {
+        let mut records = self.records.lock().unwrap();
+        let before = records.len();
+        records.retain(|_, record| !predicate(record));
+        Ok(before - records.len())
+    }
+}
+
+fn record(id: usize) -> TaskRecord {
+    data_agent_rust::testing::task_record(&format!("task-{}", id), TaskState::Completed)
+}
+
+fn seeded(store: Arc<dyn TaskStore>) -> Arc<dyn TaskStore> {
+    for id in 0..TASKS {
+        store.put(record(id)).unwrap();
+    }
+    store
+}
+
+/// Each thread does 90% reads and 10% updates over the seeded tasks
+fn mixed_load(store: &Arc<dyn TaskStore>, threads: usize) {
+    std::thread::scope(|scope| {
+        for worker in 0..threads {
+            let store = store.clone();
+            scope.spawn(move || {
+                for op in 0..OPS_PER_THREAD {
+                    let id = format!("task-{}", (worker * 7919 + op * 31) % TASKS);
+                    if op % 10 == 0 {
+                        store.update(&id, &mut |record| record.task.status.timestamp.clear()).unwrap();
+                    } else {
+                        std::hint::black_box(store.get(&id).unwrap());
+                    }
+                }
+            });
+        }
+    });
+}
+
+fn bench_concurrent_access(c: &mut Criterion) {
+    let mut group = c.benchmark_group("task_store_mixed_load");
+    for threads in [1, 4, 16] {
+        let memory = seeded(Arc::new(MemoryTaskStore::new()));
+        group.bench_with_input(BenchmarkId::new("memory", threads), &threads, |b, &threads| {
+            b.iter(|| mixed_load(&memory, threads))
+        });
+
+        let mutex = seeded(Arc::new(MutexTaskStore::default()));
+        group.bench_with_input(BenchmarkId::new("mutex_hashmap", threads), &threads, |b, &threads| {
+            b.iter(|| mixed_load(&mutex, threads))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_concurrent_access);
+criterion_main!(benches);
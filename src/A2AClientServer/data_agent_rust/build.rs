@@ -0,0 +1,25 @@
+//! Records the git commit the server is built from, reported on `GET /status`
+//!
+//! `GIT_SHA` in the build environment wins, for builds from a source archive;
+//! otherwise the commit is read from git. Without either, none is reported.
+
+use std::process::Command;
+
+fn main() {
+    println!("cargo:rerun-if-env-changed=GIT_SHA");
+    let sha = std::env::var("GIT_SHA").ok().filter(|sha| !sha.is_empty()).or_else(|| {
+        let output = Command::new("git").args(["rev-parse", "--short=12", "HEAD"]).output().ok()?;
+        output.status.success().then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+    });
+    if let Some(sha) = sha {
+        println!("cargo:rustc-env=PO_AGENT_GIT_SHA={}", sha);
+    }
+    // Rebuild when HEAD moves, so the reported commit stays current
+    if let Ok(output) = Command::new("git").args(["rev-parse", "--git-dir"]).output()
+        && output.status.success()
+    {
+        let git_dir = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        println!("cargo:rerun-if-changed={}/HEAD", git_dir);
+        println!("cargo:rerun-if-changed={}/refs/heads", git_dir);
+    }
+}
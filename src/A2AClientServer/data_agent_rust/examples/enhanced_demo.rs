@@ -1,7 +1,6 @@
 use data_agent_rust::{PurchaseOrderAgent, PurchaseOrderWrapper, PurchaseOrder, PurchaseOrderItem};
 use a2a::{A2AProtocol, Message, Part};
 use std::error::Error;
-use serde_json;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
@@ -38,6 +37,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
             supplier_state: "CA".to_string(),
             supplier_postal_code: "90210".to_string(),
             supplier_country: "USA".to_string(),
+            supplier_tax_id: None,
             items: vec![
                 PurchaseOrderItem {
                     item_code: "bk-2345".to_string(),
@@ -57,6 +57,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
             po_number: "MMS-80085".to_string(),
             created_by: "J.J. Schmidt".to_string(),
             buyer_department: "Marketing".to_string(),
+            ship_to: None,
             notes: Some("thanks for the order! Happy learning!! :)".to_string()),
             tax_rate: 0.07,
             sub_total: 194.94,
@@ -64,6 +65,9 @@ async fn main() -> Result<(), Box<dyn Error>> {
             grand_total: 208.59,
             is_approved: true,
             approval_reason: Some("Approved: Grand Total $208.59 is below $1000, Supplier Name is provided, and Buyer Department 'Marketing' is an authorized department.".to_string()),
+            blanket: None,
+            blanket_po_number: None,
+            currency: None,
         }
     };
     
@@ -82,11 +86,11 @@ async fn main() -> Result<(), Box<dyn Error>> {
         if let Part::Text { text } = &msg.parts[0] {
             println!("   CSV Output: {}", text);
         }
-        if msg.parts.len() > 1 {
-            if let Part::Data { data } = &msg.parts[1] {
-                println!("   Detailed Result:");
-                println!("{}", serde_json::to_string_pretty(data)?);
-            }
+        if msg.parts.len() > 1
+            && let Part::Data { data } = &msg.parts[1]
+        {
+            println!("   Detailed Result:");
+            println!("{}", serde_json::to_string_pretty(data)?);
         }
     }
     println!();
@@ -102,10 +106,12 @@ async fn main() -> Result<(), Box<dyn Error>> {
             supplier_state: "CA".to_string(),
             supplier_postal_code: "12345".to_string(),
             supplier_country: "USA".to_string(),
+            supplier_tax_id: None,
             items: vec![], // Invalid: no items
             po_number: "".to_string(), // Invalid: empty
             created_by: "Test User".to_string(),
             buyer_department: "InvalidDept".to_string(), // Warning: unauthorized dept
+            ship_to: None,
             notes: None,
             tax_rate: 0.07,
             sub_total: 0.0,
@@ -113,6 +119,9 @@ async fn main() -> Result<(), Box<dyn Error>> {
             grand_total: 0.0,
             is_approved: false,
             approval_reason: None,
+            blanket: None,
+            blanket_po_number: None,
+            currency: None,
         }
     };
     
@@ -127,10 +136,10 @@ async fn main() -> Result<(), Box<dyn Error>> {
     println!("   Task ID: {}", task2.id);
     println!("   Status: {:?} (Expected: Failed due to validation errors)", task2.status.state);
     
-    if let Some(msg) = &task2.status.message {
-        if let Part::Text { text } = &msg.parts[0] {
-            println!("   CSV Output: {}", text);
-        }
+    if let Some(msg) = &task2.status.message
+        && let Part::Text { text } = &msg.parts[0]
+    {
+        println!("   CSV Output: {}", text);
     }
     println!();
     
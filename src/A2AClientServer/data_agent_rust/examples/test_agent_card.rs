@@ -1,5 +1,4 @@
 use data_agent_rust::PurchaseOrderAgent;
-use serde_json;
 
 fn main() {
     println!("🧪 Testing A2A Agent Card Implementation");
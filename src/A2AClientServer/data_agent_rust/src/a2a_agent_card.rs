@@ -1,15 +1,34 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// A2A protocol version the card is written for
+pub const PROTOCOL_VERSION: &str = "0.3.0";
+
+/// Transport of the agent's REST endpoints, as named by the A2A spec
+pub const HTTP_JSON_TRANSPORT: &str = "HTTP+JSON";
 
 /// A2A Protocol compliant AgentCard structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct A2AAgentCard {
+    /// A2A protocol version the card follows; cards without one predate the field
+    #[serde(default = "default_protocol_version")]
+    pub protocol_version: String,
     /// Human-readable name for the Agent
     pub name: String,
     /// Human-readable description of the Agent's function
     pub description: String,
     /// URL address where the Agent is hosted
     pub url: String,
+    /// Transport spoken at `url`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preferred_transport: Option<String>,
+    /// Other URLs the Agent can be reached at, and their transports
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub additional_interfaces: Vec<AgentInterface>,
+    /// URL of an icon for the Agent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon_url: Option<String>,
     /// Service provider information for the Agent
     pub provider: Option<ProviderInfo>,
     /// Version of the Agent
@@ -18,8 +37,14 @@ pub struct A2AAgentCard {
     pub documentation_url: Option<String>,
     /// Optional capabilities supported by the Agent
     pub capabilities: Capabilities,
-    /// Authentication requirements for the Agent
+    /// Authentication requirements for the Agent, kept for clients predating `securitySchemes`
     pub authentication: Authentication,
+    /// Security schemes the Agent accepts, keyed by name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub security_schemes: BTreeMap<String, SecurityScheme>,
+    /// Alternative sets of schemes, any one of which satisfies the Agent, with required scopes
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub security: Vec<BTreeMap<String, Vec<String>>>,
     /// Default interaction modes supported by the Agent across all skills
     pub default_input_modes: Vec<String>,
     /// Default interaction modes supported by the Agent across all skills
@@ -28,6 +53,62 @@ pub struct A2AAgentCard {
     pub skills: Vec<Skill>,
 }
 
+fn default_protocol_version() -> String {
+    PROTOCOL_VERSION.to_string()
+}
+
+/// Another URL and transport the agent can be reached at
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AgentInterface {
+    pub url: String,
+    /// e.g. `JSONRPC`, `GRPC` or `HTTP+JSON`
+    pub transport: String,
+}
+
+/// Security scheme declaration, following OpenAPI 3
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum SecurityScheme {
+    /// API key sent in a header, query parameter or cookie
+    #[serde(rename_all = "camelCase")]
+    ApiKey {
+        name: String,
+        #[serde(rename = "in")]
+        location: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+    },
+    /// HTTP authentication such as `bearer` or `basic`
+    #[serde(rename_all = "camelCase")]
+    Http {
+        scheme: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        bearer_format: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+    },
+    /// OAuth 2.0; `flows` is passed through as declared
+    #[serde(rename = "oauth2", rename_all = "camelCase")]
+    OAuth2 {
+        flows: serde_json::Value,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+    },
+    /// OpenID Connect discovery
+    #[serde(rename_all = "camelCase")]
+    OpenIdConnect {
+        open_id_connect_url: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+    },
+    /// TLS client certificates
+    #[serde(rename = "mutualTLS")]
+    MutualTls {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+    },
+}
+
 /// Provider information for the agent
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProviderInfo {
@@ -80,9 +161,13 @@ impl A2AAgentCard {
     /// Create a new A2A compliant AgentCard for the Purchase Order Processing Agent
     pub fn new_purchase_order_agent(base_url: &str) -> Self {
         Self {
+            protocol_version: PROTOCOL_VERSION.to_string(),
             name: "Purchase Order Processing Agent".to_string(),
             description: "Specialized A2A agent for processing, validating, and managing purchase orders with comprehensive business rules checking, financial validation, and approval workflows.".to_string(),
             url: base_url.to_string(),
+            preferred_transport: Some(HTTP_JSON_TRANSPORT.to_string()),
+            additional_interfaces: Vec::new(),
+            icon_url: None,
             provider: Some(ProviderInfo {
                 organization: "A2A Protocol Framework".to_string(),
                 url: "https://agent2agent.info".to_string(),
@@ -98,6 +183,8 @@ impl A2AAgentCard {
                 schemes: vec!["none".to_string()], // No authentication required for this demo
                 credentials: None,
             },
+            security_schemes: BTreeMap::new(),
+            security: Vec::new(),
             default_input_modes: vec![
                 "application/json".to_string(),
                 "text/plain".to_string(),
@@ -168,6 +255,91 @@ impl A2AAgentCard {
                         "application/json".to_string(),
                     ]),
                 },
+                Skill {
+                    id: "invoice-matching".to_string(),
+                    name: "Invoice Matching".to_string(),
+                    description: "Match supplier invoices against processed purchase orders and the goods received on them, reporting quantity and price variances per line.".to_string(),
+                    tags: vec![
+                        "finance".to_string(),
+                        "accounts-payable".to_string(),
+                        "invoice".to_string(),
+                        "two-way-match".to_string(),
+                        "three-way-match".to_string(),
+                    ],
+                    examples: Some(vec![
+                        "Match invoice INV-1001 against purchase order MMS-80085".to_string(),
+                        "Check an invoice for price variances above 1%".to_string(),
+                    ]),
+                    input_modes: Some(vec!["application/json".to_string()]),
+                    output_modes: Some(vec!["application/json".to_string()]),
+                },
+                Skill {
+                    id: "invoice-processing".to_string(),
+                    name: "Invoice Processing".to_string(),
+                    description: "Validate supplier invoices, their due dates against their payment terms, and the purchase orders they bill, approving them for payment.".to_string(),
+                    tags: vec![
+                        "finance".to_string(),
+                        "accounts-payable".to_string(),
+                        "invoice".to_string(),
+                        "payment-terms".to_string(),
+                    ],
+                    examples: Some(vec![
+                        "Approve invoice INV-1001 on 2/10 Net 30 terms for payment".to_string(),
+                        "Check whether an invoice's due date matches its payment terms".to_string(),
+                    ]),
+                    input_modes: Some(vec!["application/json".to_string()]),
+                    output_modes: Some(vec!["application/json".to_string()]),
+                },
+                Skill {
+                    id: "requisition-conversion".to_string(),
+                    name: "Requisition Conversion".to_string(),
+                    description: "Convert a purchase requisition into a priced purchase order using the supplier and item catalog.".to_string(),
+                    tags: vec![
+                        "procurement".to_string(),
+                        "requisition".to_string(),
+                        "purchase-order".to_string(),
+                    ],
+                    examples: Some(vec![
+                        "Turn requisition REQ-7 into a purchase order".to_string(),
+                        "Price a requisition from the catalog and pick its supplier".to_string(),
+                    ]),
+                    input_modes: Some(vec!["application/json".to_string()]),
+                    output_modes: Some(vec!["application/json".to_string()]),
+                },
+                Skill {
+                    id: "expense-report-validation".to_string(),
+                    name: "Expense Report Validation".to_string(),
+                    description: "Validate employee expense reports against per-category limits, receipt requirements and the mileage rate, and route them for approval.".to_string(),
+                    tags: vec![
+                        "finance".to_string(),
+                        "expenses".to_string(),
+                        "reimbursement".to_string(),
+                        "approval".to_string(),
+                    ],
+                    examples: Some(vec![
+                        "Validate expense report EXP-42 for a client visit".to_string(),
+                        "Check which expense lines are missing receipts".to_string(),
+                    ]),
+                    input_modes: Some(vec!["application/json".to_string()]),
+                    output_modes: Some(vec!["application/json".to_string()]),
+                },
+                Skill {
+                    id: "supplier-onboarding".to_string(),
+                    name: "Supplier Onboarding".to_string(),
+                    description: "Validate a new supplier's address, tax ID and bank account, check it against existing suppliers for duplicates, and add it to the supplier master data.".to_string(),
+                    tags: vec![
+                        "procurement".to_string(),
+                        "supplier".to_string(),
+                        "onboarding".to_string(),
+                        "master-data".to_string(),
+                    ],
+                    examples: Some(vec![
+                        "Onboard supplier Muster GmbH with its IBAN".to_string(),
+                        "Check whether a new supplier duplicates an existing one".to_string(),
+                    ]),
+                    input_modes: Some(vec!["application/json".to_string()]),
+                    output_modes: Some(vec!["application/json".to_string()]),
+                },
             ],
         }
     }
@@ -181,53 +353,269 @@ impl A2AAgentCard {
         provider_org: Option<&str>,
         provider_url: Option<&str>,
     ) -> Self {
-        let provider = if let (Some(org), Some(purl)) = (provider_org, provider_url) {
-            Some(ProviderInfo {
-                organization: org.to_string(),
-                url: purl.to_string(),
-            })
-        } else {
-            None
+        let mut builder = Self::builder(name, description, url, version)
+            .with_documentation_url(&format!("{}/docs", url))
+            .with_capability(Capability::StateTransitionHistory)
+            .with_input_modes(&["application/json", "text/plain"])
+            .with_output_modes(&["text/csv", "application/json", "text/plain"])
+            .with_skill(Skill {
+                id: "purchase-order-processing".to_string(),
+                name: "Purchase Order Processing".to_string(),
+                description: "Process and validate purchase orders".to_string(),
+                tags: vec!["finance".to_string(), "procurement".to_string()],
+                examples: Some(vec![
+                    "Process a purchase order".to_string(),
+                ]),
+                input_modes: None,
+                output_modes: None,
+            });
+        if let (Some(org), Some(purl)) = (provider_org, provider_url) {
+            builder = builder.with_provider(org, purl);
+        }
+        builder.build()
+    }
+
+    /// Start a card for an agent other than the built-in purchase order agent
+    pub fn builder(name: &str, description: &str, url: &str, version: &str) -> A2AAgentCardBuilder {
+        A2AAgentCardBuilder::new(name, description, url, version)
+    }
+
+    /// Declare a security scheme and require it, alongside any already required
+    ///
+    /// The scheme is also listed in the older `authentication` block.
+    pub fn require_security_scheme(&mut self, name: &str, scheme: SecurityScheme) {
+        if self.authentication.schemes == ["none"] {
+            self.authentication.schemes.clear();
+        }
+        self.authentication.schemes.push(scheme.legacy_name());
+        self.security_schemes.insert(name.to_string(), scheme);
+        if self.security.is_empty() {
+            self.security.push(BTreeMap::new());
+        }
+        for requirement in &mut self.security {
+            requirement.insert(name.to_string(), Vec::new());
+        }
+    }
+}
+
+impl SecurityScheme {
+    /// Name of the scheme in the older `authentication.schemes` list
+    fn legacy_name(&self) -> String {
+        match self {
+            SecurityScheme::ApiKey { .. } => "ApiKey".to_string(),
+            SecurityScheme::Http { scheme, .. } => {
+                let mut chars = scheme.chars();
+                chars.next().map(|first| first.to_ascii_uppercase().to_string() + chars.as_str()).unwrap_or_default()
+            }
+            SecurityScheme::OAuth2 { .. } => "OAuth2".to_string(),
+            SecurityScheme::OpenIdConnect { .. } => "OpenIdConnect".to_string(),
+            SecurityScheme::MutualTls { .. } => "mTLS".to_string(),
+        }
+    }
+}
+
+/// An optional capability an agent can declare on its card
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Capability {
+    Streaming,
+    PushNotifications,
+    StateTransitionHistory,
+}
+
+/// Fluent construction of an [`A2AAgentCard`]
+///
+/// Starts from a card with no capabilities, no authentication, no skills and JSON
+/// input and output; each `with_` call adds to it.
+///
+/// ```
+/// use data_agent_rust::{A2AAgentCard, Capability, SecurityScheme, Skill};
+///
+/// let card = A2AAgentCard::builder("Invoice Agent", "Matches invoices", "https://invoices.example.com", "1.0.0")
+///     .with_provider("Example Corp", "https://example.com")
+///     .with_capability(Capability::PushNotifications)
+///     .with_auth_scheme("bearer", SecurityScheme::Http {
+///         scheme: "bearer".to_string(),
+///         bearer_format: Some("JWT".to_string()),
+///         description: None,
+///     })
+///     .with_skill(Skill::new("invoice-matching", "Invoice Matching", "Match invoices to purchase orders"))
+///     .build();
+/// assert_eq!(card.authentication.schemes, ["Bearer"]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct A2AAgentCardBuilder {
+    card: A2AAgentCard,
+}
+
+impl A2AAgentCardBuilder {
+    pub fn new(name: &str, description: &str, url: &str, version: &str) -> Self {
+        Self {
+            card: A2AAgentCard {
+                protocol_version: PROTOCOL_VERSION.to_string(),
+                name: name.to_string(),
+                description: description.to_string(),
+                url: url.to_string(),
+                preferred_transport: Some(HTTP_JSON_TRANSPORT.to_string()),
+                additional_interfaces: Vec::new(),
+                icon_url: None,
+                provider: None,
+                version: version.to_string(),
+                documentation_url: None,
+                capabilities: Capabilities {
+                    streaming: Some(false),
+                    push_notifications: Some(false),
+                    state_transition_history: Some(false),
+                },
+                authentication: Authentication {
+                    schemes: vec!["none".to_string()],
+                    credentials: None,
+                },
+                security_schemes: BTreeMap::new(),
+                security: Vec::new(),
+                default_input_modes: vec!["application/json".to_string()],
+                default_output_modes: vec!["application/json".to_string()],
+                skills: Vec::new(),
+            },
+        }
+    }
+
+    pub fn with_provider(mut self, organization: &str, url: &str) -> Self {
+        self.card.provider = Some(ProviderInfo { organization: organization.to_string(), url: url.to_string() });
+        self
+    }
+
+    pub fn with_skill(mut self, skill: Skill) -> Self {
+        self.card.skills.push(skill);
+        self
+    }
+
+    pub fn with_capability(mut self, capability: Capability) -> Self {
+        let flag = match capability {
+            Capability::Streaming => &mut self.card.capabilities.streaming,
+            Capability::PushNotifications => &mut self.card.capabilities.push_notifications,
+            Capability::StateTransitionHistory => &mut self.card.capabilities.state_transition_history,
         };
+        *flag = Some(true);
+        self
+    }
 
+    /// Declare and require a security scheme; see [`A2AAgentCard::require_security_scheme`]
+    pub fn with_auth_scheme(mut self, name: &str, scheme: SecurityScheme) -> Self {
+        self.card.require_security_scheme(name, scheme);
+        self
+    }
+
+    pub fn with_documentation_url(mut self, url: &str) -> Self {
+        self.card.documentation_url = Some(url.to_string());
+        self
+    }
+
+    pub fn with_icon_url(mut self, url: &str) -> Self {
+        self.card.icon_url = Some(url.to_string());
+        self
+    }
+
+    /// Another URL the agent can be reached at, e.g. a JSON-RPC endpoint
+    pub fn with_interface(mut self, url: &str, transport: &str) -> Self {
+        self.card.additional_interfaces.push(AgentInterface { url: url.to_string(), transport: transport.to_string() });
+        self
+    }
+
+    /// MIME types accepted by skills that do not declare their own
+    pub fn with_input_modes(mut self, modes: &[&str]) -> Self {
+        self.card.default_input_modes = modes.iter().map(|mode| mode.to_string()).collect();
+        self
+    }
+
+    /// MIME types produced by skills that do not declare their own
+    pub fn with_output_modes(mut self, modes: &[&str]) -> Self {
+        self.card.default_output_modes = modes.iter().map(|mode| mode.to_string()).collect();
+        self
+    }
+
+    pub fn build(self) -> A2AAgentCard {
+        self.card
+    }
+}
+
+impl Skill {
+    /// A skill with no tags or examples, using the card's default modes
+    pub fn new(id: &str, name: &str, description: &str) -> Self {
         Self {
+            id: id.to_string(),
             name: name.to_string(),
             description: description.to_string(),
-            url: url.to_string(),
-            provider,
-            version: version.to_string(),
-            documentation_url: Some(format!("{}/docs", url)),
-            capabilities: Capabilities {
-                streaming: Some(false),
-                push_notifications: Some(false),
-                state_transition_history: Some(true),
-            },
-            authentication: Authentication {
-                schemes: vec!["none".to_string()],
-                credentials: None,
-            },
-            default_input_modes: vec![
-                "application/json".to_string(),
-                "text/plain".to_string(),
-            ],
-            default_output_modes: vec![
-                "text/csv".to_string(),
-                "application/json".to_string(),
-                "text/plain".to_string(),
-            ],
-            skills: vec![
-                Skill {
-                    id: "purchase-order-processing".to_string(),
-                    name: "Purchase Order Processing".to_string(),
-                    description: "Process and validate purchase orders".to_string(),
-                    tags: vec!["finance".to_string(), "procurement".to_string()],
-                    examples: Some(vec![
-                        "Process a purchase order".to_string(),
-                    ]),
-                    input_modes: None,
-                    output_modes: None,
-                },
-            ],
+            tags: Vec::new(),
+            examples: None,
+            input_modes: None,
+            output_modes: None,
         }
     }
-}
\ No newline at end of file
+
+    pub fn with_tags(mut self, tags: &[&str]) -> Self {
+        self.tags = tags.iter().map(|tag| tag.to_string()).collect();
+        self
+    }
+
+    pub fn with_examples(mut self, examples: &[&str]) -> Self {
+        self.examples = Some(examples.iter().map(|example| example.to_string()).collect());
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_card_round_trips_spec_fields_and_reads_older_cards() {
+        let mut card = A2AAgentCard::new_purchase_order_agent("http://localhost:8080");
+        card.security_schemes.insert(
+            "bearer".to_string(),
+            SecurityScheme::Http { scheme: "bearer".to_string(), bearer_format: Some("JWT".to_string()), description: None },
+        );
+        card.security.push(BTreeMap::from([("bearer".to_string(), Vec::new())]));
+
+        let json = serde_json::to_value(&card).unwrap();
+        assert_eq!(json["protocolVersion"], PROTOCOL_VERSION);
+        assert_eq!(json["preferredTransport"], "HTTP+JSON");
+        assert_eq!(json["securitySchemes"]["bearer"]["type"], "http");
+        assert_eq!(json["securitySchemes"]["bearer"]["bearerFormat"], "JWT");
+        assert_eq!(json["security"][0]["bearer"], serde_json::json!([]));
+        assert!(json.get("iconUrl").is_none() && json.get("additionalInterfaces").is_none());
+        assert_eq!(json["authentication"]["schemes"][0], "none");
+
+        let mut older = json.clone();
+        for field in ["protocolVersion", "preferredTransport", "securitySchemes", "security"] {
+            older.as_object_mut().unwrap().remove(field);
+        }
+        let older: A2AAgentCard = serde_json::from_value(older).unwrap();
+        assert_eq!(older.protocol_version, PROTOCOL_VERSION);
+        assert!(older.preferred_transport.is_none() && older.security_schemes.is_empty());
+    }
+
+    #[test]
+    fn test_builder_assembles_card() {
+        let card = A2AAgentCard::builder("Invoice Agent", "Matches invoices", "https://invoices.example.com", "2.0.0")
+            .with_provider("Example Corp", "https://example.com")
+            .with_capability(Capability::Streaming)
+            .with_auth_scheme("bearer", SecurityScheme::Http { scheme: "bearer".to_string(), bearer_format: None, description: None })
+            .with_auth_scheme("key", SecurityScheme::ApiKey { name: "x-api-key".to_string(), location: "header".to_string(), description: None })
+            .with_skill(Skill::new("invoice-matching", "Invoice Matching", "Match invoices").with_tags(&["finance"]))
+            .build();
+
+        assert_eq!(card.provider.unwrap().organization, "Example Corp");
+        assert_eq!(card.capabilities.streaming, Some(true));
+        assert_eq!(card.capabilities.push_notifications, Some(false));
+        assert_eq!(card.authentication.schemes, ["Bearer", "ApiKey"]);
+        assert_eq!(card.security, [BTreeMap::from([("bearer".to_string(), vec![]), ("key".to_string(), vec![])])]);
+        assert_eq!(card.skills[0].tags, ["finance"]);
+        assert_eq!(card.default_input_modes, ["application/json"]);
+
+        let custom = A2AAgentCard::new_custom("Agent", "Does things", "http://agent", "1.0.0", Some("Org"), None);
+        assert!(custom.provider.is_none());
+        assert_eq!(custom.documentation_url.as_deref(), Some("http://agent/docs"));
+        assert_eq!(custom.capabilities.state_transition_history, Some(true));
+        assert_eq!(custom.authentication.schemes, ["none"]);
+    }
+}
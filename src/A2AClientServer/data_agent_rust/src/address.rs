@@ -0,0 +1,443 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use crate::agent::PurchaseOrder;
+use crate::severity::{Check, Findings};
+
+/// A postal address, such as where an order is shipped
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Address {
+    /// Person or team to deliver to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attention: Option<String>,
+    pub address_line1: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address_line2: Option<String>,
+    pub city: String,
+    #[serde(default)]
+    pub state: String,
+    pub postal_code: String,
+    pub country: String,
+}
+
+/// ISO 3166-1 alpha-2 code, alpha-3 code and English name(s) of every country,
+/// one per line; extra names are separated with `;`
+const COUNTRIES: &str = "\
+AD AND Andorra
+AE ARE United Arab Emirates;UAE
+AF AFG Afghanistan
+AG ATG Antigua and Barbuda
+AI AIA Anguilla
+AL ALB Albania
+AM ARM Armenia
+AO AGO Angola
+AQ ATA Antarctica
+AR ARG Argentina
+AS ASM American Samoa
+AT AUT Austria
+AU AUS Australia
+AW ABW Aruba
+AX ALA Aland Islands
+AZ AZE Azerbaijan
+BA BIH Bosnia and Herzegovina
+BB BRB Barbados
+BD BGD Bangladesh
+BE BEL Belgium
+BF BFA Burkina Faso
+BG BGR Bulgaria
+BH BHR Bahrain
+BI BDI Burundi
+BJ BEN Benin
+BL BLM Saint Barthelemy
+BM BMU Bermuda
+BN BRN Brunei;Brunei Darussalam
+BO BOL Bolivia
+BQ BES Bonaire, Sint Eustatius and Saba
+BR BRA Brazil
+BS BHS Bahamas
+BT BTN Bhutan
+BV BVT Bouvet Island
+BW BWA Botswana
+BY BLR Belarus
+BZ BLZ Belize
+CA CAN Canada
+CC CCK Cocos (Keeling) Islands
+CD COD Democratic Republic of the Congo;DR Congo
+CF CAF Central African Republic
+CG COG Congo;Republic of the Congo
+CH CHE Switzerland
+CI CIV Cote d'Ivoire;Ivory Coast
+CK COK Cook Islands
+CL CHL Chile
+CM CMR Cameroon
+CN CHN China
+CO COL Colombia
+CR CRI Costa Rica
+CU CUB Cuba
+CV CPV Cabo Verde;Cape Verde
+CW CUW Curacao
+CX CXR Christmas Island
+CY CYP Cyprus
+CZ CZE Czechia;Czech Republic
+DE DEU Germany
+DJ DJI Djibouti
+DK DNK Denmark
+DM DMA Dominica
+DO DOM Dominican Republic
+DZ DZA Algeria
+EC ECU Ecuador
+EE EST Estonia
+EG EGY Egypt
+EH ESH Western Sahara
+ER ERI Eritrea
+ES ESP Spain
+ET ETH Ethiopia
+FI FIN Finland
+FJ FJI Fiji
+FK FLK Falkland Islands
+FM FSM Micronesia
+FO FRO Faroe Islands
+FR FRA France
+GA GAB Gabon
+GB GBR United Kingdom;UK;Great Britain
+GD GRD Grenada
+GE GEO Georgia
+GF GUF French Guiana
+GG GGY Guernsey
+GH GHA Ghana
+GI GIB Gibraltar
+GL GRL Greenland
+GM GMB Gambia
+GN GIN Guinea
+GP GLP Guadeloupe
+GQ GNQ Equatorial Guinea
+GR GRC Greece
+GS SGS South Georgia and the South Sandwich Islands
+GT GTM Guatemala
+GU GUM Guam
+GW GNB Guinea-Bissau
+GY GUY Guyana
+HK HKG Hong Kong
+HM HMD Heard Island and McDonald Islands
+HN HND Honduras
+HR HRV Croatia
+HT HTI Haiti
+HU HUN Hungary
+ID IDN Indonesia
+IE IRL Ireland
+IL ISR Israel
+IM IMN Isle of Man
+IN IND India
+IO IOT British Indian Ocean Territory
+IQ IRQ Iraq
+IR IRN Iran
+IS ISL Iceland
+IT ITA Italy
+JE JEY Jersey
+JM JAM Jamaica
+JO JOR Jordan
+JP JPN Japan
+KE KEN Kenya
+KG KGZ Kyrgyzstan
+KH KHM Cambodia
+KI KIR Kiribati
+KM COM Comoros
+KN KNA Saint Kitts and Nevis
+KP PRK North Korea
+KR KOR South Korea;Korea
+KW KWT Kuwait
+KY CYM Cayman Islands
+KZ KAZ Kazakhstan
+LA LAO Laos
+LB LBN Lebanon
+LC LCA Saint Lucia
+LI LIE Liechtenstein
+LK LKA Sri Lanka
+LR LBR Liberia
+LS LSO Lesotho
+LT LTU Lithuania
+LU LUX Luxembourg
+LV LVA Latvia
+LY LBY Libya
+MA MAR Morocco
+MC MCO Monaco
+MD MDA Moldova
+ME MNE Montenegro
+MF MAF Saint Martin
+MG MDG Madagascar
+MH MHL Marshall Islands
+MK MKD North Macedonia
+ML MLI Mali
+MM MMR Myanmar
+MN MNG Mongolia
+MO MAC Macao;Macau
+MP MNP Northern Mariana Islands
+MQ MTQ Martinique
+MR MRT Mauritania
+MS MSR Montserrat
+MT MLT Malta
+MU MUS Mauritius
+MV MDV Maldives
+MW MWI Malawi
+MX MEX Mexico
+MY MYS Malaysia
+MZ MOZ Mozambique
+NA NAM Namibia
+NC NCL New Caledonia
+NE NER Niger
+NF NFK Norfolk Island
+NG NGA Nigeria
+NI NIC Nicaragua
+NL NLD Netherlands;The Netherlands;Holland
+NO NOR Norway
+NP NPL Nepal
+NR NRU Nauru
+NU NIU Niue
+NZ NZL New Zealand
+OM OMN Oman
+PA PAN Panama
+PE PER Peru
+PF PYF French Polynesia
+PG PNG Papua New Guinea
+PH PHL Philippines
+PK PAK Pakistan
+PL POL Poland
+PM SPM Saint Pierre and Miquelon
+PN PCN Pitcairn
+PR PRI Puerto Rico
+PS PSE Palestine
+PT PRT Portugal
+PW PLW Palau
+PY PRY Paraguay
+QA QAT Qatar
+RE REU Reunion
+RO ROU Romania
+RS SRB Serbia
+RU RUS Russia;Russian Federation
+RW RWA Rwanda
+SA SAU Saudi Arabia
+SB SLB Solomon Islands
+SC SYC Seychelles
+SD SDN Sudan
+SE SWE Sweden
+SG SGP Singapore
+SH SHN Saint Helena
+SI SVN Slovenia
+SJ SJM Svalbard and Jan Mayen
+SK SVK Slovakia
+SL SLE Sierra Leone
+SM SMR San Marino
+SN SEN Senegal
+SO SOM Somalia
+SR SUR Suriname
+SS SSD South Sudan
+ST STP Sao Tome and Principe
+SV SLV El Salvador
+SX SXM Sint Maarten
+SY SYR Syria
+SZ SWZ Eswatini;Swaziland
+TC TCA Turks and Caicos Islands
+TD TCD Chad
+TF ATF French Southern Territories
+TG TGO Togo
+TH THA Thailand
+TJ TJK Tajikistan
+TK TKL Tokelau
+TL TLS Timor-Leste;East Timor
+TM TKM Turkmenistan
+TN TUN Tunisia
+TO TON Tonga
+TR TUR Turkey;Turkiye
+TT TTO Trinidad and Tobago
+TV TUV Tuvalu
+TW TWN Taiwan
+TZ TZA Tanzania
+UA UKR Ukraine
+UG UGA Uganda
+UM UMI United States Minor Outlying Islands
+US USA United States;United States of America
+UY URY Uruguay
+UZ UZB Uzbekistan
+VA VAT Holy See;Vatican City
+VC VCT Saint Vincent and the Grenadines
+VE VEN Venezuela
+VG VGB British Virgin Islands
+VI VIR U.S. Virgin Islands
+VN VNM Vietnam;Viet Nam
+VU VUT Vanuatu
+WF WLF Wallis and Futuna
+WS WSM Samoa
+YE YEM Yemen
+YT MYT Mayotte
+ZA ZAF South Africa
+ZM ZMB Zambia
+ZW ZWE Zimbabwe";
+
+/// Upper-cased codes and names of every country, mapped to its alpha-2 code
+static COUNTRY_CODES: LazyLock<HashMap<String, &'static str>> = LazyLock::new(|| {
+    let mut codes = HashMap::new();
+    for line in COUNTRIES.lines() {
+        let mut fields = line.splitn(3, ' ');
+        let (Some(alpha2), Some(alpha3), Some(names)) = (fields.next(), fields.next(), fields.next()) else {
+            continue;
+        };
+        codes.insert(alpha2.to_string(), alpha2);
+        codes.insert(alpha3.to_string(), alpha2);
+        for name in names.split(';') {
+            codes.insert(name.to_ascii_uppercase(), alpha2);
+        }
+    }
+    codes
+});
+
+/// ISO alpha-2 code for a country given as an alpha-2 or alpha-3 code or an English name
+pub fn country_code(country: &str) -> Option<&'static str> {
+    COUNTRY_CODES.get(&country.trim().to_ascii_uppercase()).copied()
+}
+
+/// Postal code format of countries that have a fixed one, and its description
+const POSTAL_CODES: [(&str, &str, &str); 36] = [
+    ("US", r"\d{5}(-\d{4})?", "NNNNN or NNNNN-NNNN"),
+    ("CA", r"[A-Z]\d[A-Z] ?\d[A-Z]\d", "ANA NAN"),
+    ("GB", r"[A-Z]{1,2}\d[A-Z\d]? ?\d[A-Z]{2}", "e.g. SW1A 1AA"),
+    ("IE", r"[A-Z]\d[\dW] ?[A-Z\d]{4}", "e.g. D02 X285"),
+    ("DE", r"\d{5}", "NNNNN"),
+    ("FR", r"\d{5}", "NNNNN"),
+    ("IT", r"\d{5}", "NNNNN"),
+    ("ES", r"\d{5}", "NNNNN"),
+    ("FI", r"\d{5}", "NNNNN"),
+    ("EE", r"\d{5}", "NNNNN"),
+    ("HR", r"\d{5}", "NNNNN"),
+    ("MX", r"\d{5}", "NNNNN"),
+    ("NL", r"\d{4} ?[A-Z]{2}", "NNNN AA"),
+    ("BE", r"\d{4}", "NNNN"),
+    ("AT", r"\d{4}", "NNNN"),
+    ("CH", r"\d{4}", "NNNN"),
+    ("DK", r"\d{4}", "NNNN"),
+    ("NO", r"\d{4}", "NNNN"),
+    ("LU", r"\d{4}", "NNNN"),
+    ("HU", r"\d{4}", "NNNN"),
+    ("AU", r"\d{4}", "NNNN"),
+    ("NZ", r"\d{4}", "NNNN"),
+    ("ZA", r"\d{4}", "NNNN"),
+    ("SE", r"\d{3} ?\d{2}", "NNN NN"),
+    ("CZ", r"\d{3} ?\d{2}", "NNN NN"),
+    ("SK", r"\d{3} ?\d{2}", "NNN NN"),
+    ("GR", r"\d{3} ?\d{2}", "NNN NN"),
+    ("PL", r"\d{2}-\d{3}", "NN-NNN"),
+    ("PT", r"\d{4}-\d{3}", "NNNN-NNN"),
+    ("JP", r"\d{3}-?\d{4}", "NNN-NNNN"),
+    ("BR", r"\d{5}-?\d{3}", "NNNNN-NNN"),
+    ("CN", r"\d{6}", "NNNNNN"),
+    ("IN", r"\d{6}", "NNNNNN"),
+    ("SG", r"\d{6}", "NNNNNN"),
+    ("RU", r"\d{6}", "NNNNNN"),
+    ("KR", r"\d{5}", "NNNNN"),
+];
+
+static POSTAL_CODE_PATTERNS: LazyLock<HashMap<&'static str, (Regex, &'static str)>> = LazyLock::new(|| {
+    POSTAL_CODES
+        .iter()
+        .map(|(code, pattern, format)| {
+            let regex = Regex::new(&format!("^(?:{})$", pattern)).expect("valid postal code pattern");
+            (*code, (regex, *format))
+        })
+        .collect()
+});
+
+const US_STATES: [&str; 56] = [
+    "AL", "AK", "AZ", "AR", "CA", "CO", "CT", "DE", "DC", "FL", "GA", "HI", "ID", "IL", "IN", "IA", "KS", "KY",
+    "LA", "ME", "MD", "MA", "MI", "MN", "MS", "MO", "MT", "NE", "NV", "NH", "NJ", "NM", "NY", "NC", "ND", "OH",
+    "OK", "OR", "PA", "RI", "SC", "SD", "TN", "TX", "UT", "VT", "VA", "WA", "WV", "WI", "WY", "AS", "GU", "MP",
+    "PR", "VI",
+];
+
+const CA_PROVINCES: [&str; 13] = ["AB", "BC", "MB", "NB", "NL", "NS", "NT", "NU", "ON", "PE", "QC", "SK", "YT"];
+
+/// Countries whose addresses must name a state or province
+const STATE_REQUIRED: [&str; 6] = ["US", "CA", "AU", "BR", "MX", "IN"];
+
+/// Check one address, with messages prefixed by `label`
+///
+/// The country must be recognisable as an ISO 3166-1 code or name, and the state
+/// or province must be given where addresses need one (and valid, for the US and
+/// Canada). The postal code must follow the country's format, where it has one.
+pub fn validate(label: &str, country: &str, state: &str, postal_code: &str, findings: &mut Findings) {
+    let Some(code) = country_code(country) else {
+        if country.trim().is_empty() {
+            findings.push(Check::AddressCountry, format!("{} country is required", label));
+        } else {
+            findings.push(Check::AddressCountry, format!("{} country '{}' is not a recognised ISO 3166 country", label, country));
+        }
+        return;
+    };
+
+    let state = state.trim().to_ascii_uppercase();
+    if STATE_REQUIRED.contains(&code) && state.is_empty() {
+        findings.push(Check::AddressState, format!("{} state/province is required for {}", label, code));
+    } else if (code == "US" && !US_STATES.contains(&state.as_str())) || (code == "CA" && !CA_PROVINCES.contains(&state.as_str())) {
+        findings.push(Check::AddressState, format!("{} state/province '{}' is not valid for {}", label, state, code));
+    }
+
+    let postal_code = postal_code.trim().to_ascii_uppercase();
+    if let Some((pattern, format)) = POSTAL_CODE_PATTERNS.get(code) {
+        if postal_code.is_empty() {
+            findings.push(Check::AddressPostalCode, format!("{} postal code is missing", label));
+        } else if !pattern.is_match(&postal_code) {
+            findings.push(
+                Check::AddressPostalCode,
+                format!("{} postal code '{}' does not match the {} format ({})", label, postal_code, code, format),
+            );
+        }
+    }
+}
+
+/// Check the supplier and ship-to addresses of `po`
+pub fn validate_order(po: &PurchaseOrder, findings: &mut Findings) {
+    validate("Supplier", &po.supplier_country, &po.supplier_state, &po.supplier_postal_code, findings);
+    if let Some(ship_to) = &po.ship_to {
+        if ship_to.address_line1.trim().is_empty() {
+            findings.push(Check::ShipToIncomplete, "Ship-to address line 1 is required");
+        }
+        if ship_to.city.trim().is_empty() {
+            findings.push(Check::ShipToIncomplete, "Ship-to city is required");
+        }
+        validate("Ship-to", &ship_to.country, &ship_to.state, &ship_to.postal_code, findings);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::severity::SeverityPolicy;
+
+    fn check(country: &str, state: &str, postal_code: &str) -> (Vec<String>, Vec<String>) {
+        let policy = SeverityPolicy::default();
+        let mut findings = Findings::new(&policy);
+        validate("Supplier", country, state, postal_code, &mut findings);
+        findings.into_parts()
+    }
+
+    #[test]
+    fn test_addresses_follow_country_rules() {
+        assert_eq!(country_code("usa"), Some("US"));
+        assert_eq!(country_code(" United Kingdom "), Some("GB"));
+        assert_eq!(country_code("DEU"), Some("DE"));
+
+        assert_eq!(check("USA", "IL", "62701"), (vec![], vec![]));
+        assert_eq!(check("US", "CA", "90210-1234"), (vec![], vec![]));
+        assert_eq!(check("banana", "IL", "62701").0, ["Supplier country 'banana' is not a recognised ISO 3166 country"]);
+        assert_eq!(check("USA", "", "62701").0, ["Supplier state/province is required for US"]);
+        assert_eq!(check("USA", "ZZ", "62701").0.len(), 1);
+        assert!(check("USA", "IL", "627").1[0].contains("NNNNN"));
+
+        assert_eq!(check("Canada", "ON", "k1a 0b1"), (vec![], vec![]));
+        assert_eq!(check("GB", "", "SW1A 1AA"), (vec![], vec![]));
+        assert_eq!(check("Germany", "", "1011").1.len(), 1);
+        assert_eq!(check("Japan", "", "100-0001"), (vec![], vec![]));
+        assert_eq!(check("Kenya", "", "anything"), (vec![], vec![]), "countries without a fixed format pass");
+    }
+}
@@ -0,0 +1,658 @@
+use axum::{
+    body::Bytes,
+    extract::{DefaultBodyLimit, Extension, Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Json, Response},
+    routing::{get, post},
+    Router,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::{info, warn};
+
+use crate::auth::AuthContext;
+use crate::dead_letter::{self, DeadLetter, ReplayError, ReplayRequest};
+use crate::export::{self, ResultRow};
+use crate::labels;
+use crate::scheduled_exports::{self, ExportFormat, ExportRange, ExportRun, RunTrigger};
+use crate::server::{AppState, TaskResponse};
+use crate::signing_keys::{self, KeyError, RotateRequest, SigningKey};
+use crate::snapshot::{self, SnapshotConfig, SnapshotFormat};
+use crate::store::{StoreStats, TaskRecord};
+
+/// Query parameters for purging old tasks
+#[derive(Debug, Deserialize)]
+pub struct PurgeQuery {
+    /// Remove tasks last updated more than this many days ago
+    pub older_than_days: u32,
+}
+
+/// Result of a purge operation
+#[derive(Debug, Serialize)]
+pub struct PurgeResponse {
+    pub removed: usize,
+    pub remaining: usize,
+}
+
+/// Optional body for force-failing a task
+#[derive(Debug, Default, Deserialize)]
+pub struct ForceFailRequest {
+    pub reason: Option<String>,
+}
+
+/// Query parameters selecting a snapshot encoding
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct SnapshotQuery {
+    pub format: SnapshotFormat,
+}
+
+/// Which tasks a results export covers
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ExportQuery {
+    /// Only tasks updated at or after this time, for incremental pulls
+    pub since: Option<DateTime<Utc>>,
+    /// Only tasks in this state, e.g. `completed`
+    pub state: Option<String>,
+    /// Only tasks with these labels, e.g. `source:sap,period:2026-Q3`
+    pub labels: Option<String>,
+}
+
+/// Result of restoring or saving a snapshot
+#[derive(Debug, Serialize)]
+pub struct SnapshotResponse {
+    /// Records restored or written
+    pub tasks: usize,
+    /// Records in the store afterwards
+    pub total_tasks: usize,
+    /// File written, for saves
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+}
+
+/// An export job with when it runs next and how it last went
+#[derive(Debug, Serialize)]
+pub struct ExportJobResponse {
+    pub id: String,
+    pub cron: String,
+    pub enabled: bool,
+    pub format: ExportFormat,
+    pub range: ExportRange,
+    /// Blob key template or webhook URL
+    pub destination: String,
+    pub next_run: Option<DateTime<Utc>>,
+    pub last_run: Option<ExportRun>,
+}
+
+/// Which export runs to list
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ExportRunsQuery {
+    /// Only runs of this job
+    pub job: Option<String>,
+}
+
+/// A signing key created by a rotation, with the secret receivers need
+#[derive(Debug, Serialize)]
+pub struct RotatedKey {
+    #[serde(flatten)]
+    pub key: SigningKey,
+    pub secret: String,
+}
+
+type AdminError = (StatusCode, Json<serde_json::Value>);
+
+fn admin_error(status: StatusCode, message: impl ToString) -> AdminError {
+    (status, Json(serde_json::json!({ "error": message.to_string() })))
+}
+
+/// Admin routes; the caller is responsible for layering auth and the admin permission
+pub fn admin_routes(snapshot: &SnapshotConfig) -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/admin/stats", get(store_stats))
+        .route("/admin/tasks/purge", post(purge_tasks))
+        .route("/admin/tasks/:task_id/fail", post(force_fail_task))
+        .route("/admin/tasks/:task_id/rerun", post(rerun_task))
+        .route("/admin/export", get(export_results))
+        .route("/admin/snapshot", get(export_snapshot))
+        // A snapshot of any real store is far past axum's 2 MiB default
+        .route(
+            "/admin/snapshot/restore",
+            post(restore_snapshot).layer(DefaultBodyLimit::max(snapshot.max_restore_bytes)),
+        )
+        .route("/admin/snapshot/save", post(save_snapshot))
+        .route("/admin/signing-keys", get(list_signing_keys))
+        .route("/admin/signing-keys/rotate", post(rotate_signing_key))
+        .route("/admin/signing-keys/:key_id/retire", post(retire_signing_key))
+        .route("/admin/dead-letters", get(list_dead_letters))
+        .route("/admin/dead-letters/:id", get(get_dead_letter))
+        .route("/admin/dead-letters/:id/replay", post(replay_dead_letter))
+        .route("/admin/exports", get(list_export_jobs))
+        .route("/admin/exports/runs", get(list_export_runs))
+        .route("/admin/exports/:id/run", post(run_export_job))
+}
+
+fn key_error(e: KeyError) -> AdminError {
+    let status = match e {
+        KeyError::NotFound(_) => StatusCode::NOT_FOUND,
+        KeyError::Invalid(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        KeyError::Store(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    admin_error(status, e)
+}
+
+/// List the configured export jobs
+async fn list_export_jobs(State(state): State<Arc<AppState>>) -> Json<Vec<ExportJobResponse>> {
+    let now = Utc::now();
+    let jobs = state
+        .exports
+        .jobs
+        .iter()
+        .map(|job| ExportJobResponse {
+            id: job.id.clone(),
+            cron: job.cron.to_string(),
+            enabled: job.enabled,
+            format: job.format,
+            range: job.range,
+            destination: job.destination.describe(),
+            next_run: job.enabled.then(|| job.cron.next_after(now)).flatten(),
+            last_run: state.agent.export_runs().list(Some(&job.id)).into_iter().next(),
+        })
+        .collect();
+    Json(jobs)
+}
+
+/// List recent export runs, newest first
+async fn list_export_runs(State(state): State<Arc<AppState>>, Query(query): Query<ExportRunsQuery>) -> Json<Vec<ExportRun>> {
+    Json(state.agent.export_runs().list(query.job.as_deref()))
+}
+
+/// Run an export job now, for the period before now
+async fn run_export_job(State(state): State<Arc<AppState>>, Path(id): Path<String>) -> Result<Json<ExportRun>, AdminError> {
+    let Some(job) = state.exports.job(&id) else {
+        return Err(admin_error(StatusCode::NOT_FOUND, format!("Export job {} not found", id)));
+    };
+    let run = scheduled_exports::run_once(&state.agent, job, Utc::now(), RunTrigger::Manual).await;
+    match &run.error {
+        None => {
+            info!(job = %id, rows = run.rows, "export run by an administrator");
+            Ok(Json(run))
+        }
+        Some(e) => Err(admin_error(StatusCode::BAD_GATEWAY, format!("Export job {} failed: {}", id, e))),
+    }
+}
+
+/// List the messages no purchase order could be parsed from, newest first
+async fn list_dead_letters(State(state): State<Arc<AppState>>) -> Result<Json<Vec<DeadLetter>>, AdminError> {
+    state
+        .agent
+        .dead_letters()
+        .list()
+        .map(Json)
+        .map_err(|e| admin_error(StatusCode::INTERNAL_SERVER_ERROR, e))
+}
+
+async fn get_dead_letter(State(state): State<Arc<AppState>>, Path(id): Path<String>) -> Result<Json<DeadLetter>, AdminError> {
+    match state.agent.dead_letters().get(&id) {
+        Ok(Some(letter)) => Ok(Json(letter)),
+        Ok(None) => Err(admin_error(StatusCode::NOT_FOUND, format!("Dead letter {} not found", id))),
+        Err(e) => Err(admin_error(StatusCode::INTERNAL_SERVER_ERROR, e)),
+    }
+}
+
+/// Process a dead letter again, as kept or with the corrected message in the body
+async fn replay_dead_letter(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    auth: Option<Extension<AuthContext>>,
+    body: Option<Json<ReplayRequest>>,
+) -> Result<Json<TaskResponse>, AdminError> {
+    let request = body.map(|Json(body)| body).unwrap_or_default();
+    match dead_letter::replay(&state.agent, &id, request).await {
+        Ok(task) => {
+            info!(dead_letter = %id, task_id = %task.id, "dead letter replayed");
+            Ok(Json(state.task_response(auth.as_ref(), task)))
+        }
+        Err(e) => {
+            let status = match e {
+                ReplayError::NotFound(_) => StatusCode::NOT_FOUND,
+                ReplayError::AlreadyReplayed(_) => StatusCode::CONFLICT,
+                ReplayError::Processing(_) => StatusCode::UNPROCESSABLE_ENTITY,
+                ReplayError::Store(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            warn!(dead_letter = %id, error = %e, "dead letter replay refused");
+            Err(admin_error(status, e))
+        }
+    }
+}
+
+/// List the webhook signing keys, without their secrets
+async fn list_signing_keys(State(state): State<Arc<AppState>>) -> Result<Json<Vec<SigningKey>>, AdminError> {
+    state
+        .agent
+        .signing_keys()
+        .list()
+        .map(Json)
+        .map_err(|e| admin_error(StatusCode::INTERNAL_SERVER_ERROR, e))
+}
+
+/// Add a webhook signing key; the previous ones keep signing for the overlap
+async fn rotate_signing_key(
+    State(state): State<Arc<AppState>>,
+    body: Bytes,
+) -> Result<(StatusCode, Json<RotatedKey>), AdminError> {
+    let request: RotateRequest = if body.is_empty() {
+        RotateRequest::default()
+    } else {
+        serde_json::from_slice(&body).map_err(|e| admin_error(StatusCode::BAD_REQUEST, format!("Invalid rotation request: {}", e)))?
+    };
+    let key = signing_keys::rotate(state.agent.signing_keys().as_ref(), request, state.rotation_overlap, Utc::now())
+        .map_err(key_error)?;
+    info!(key_id = %key.id, "webhook signing key rotated");
+    let secret = key.secret.clone();
+    Ok((StatusCode::CREATED, Json(RotatedKey { key, secret })))
+}
+
+/// Stop a webhook signing key signing at once
+async fn retire_signing_key(
+    State(state): State<Arc<AppState>>,
+    Path(key_id): Path<String>,
+) -> Result<Json<SigningKey>, AdminError> {
+    let key = signing_keys::retire(state.agent.signing_keys().as_ref(), &key_id, Utc::now()).map_err(key_error)?;
+    info!(key_id = %key.id, "webhook signing key retired");
+    Ok(Json(key))
+}
+
+/// View task store statistics
+async fn store_stats(State(state): State<Arc<AppState>>) -> Result<Json<StoreStats>, AdminError> {
+    state
+        .agent
+        .task_store()
+        .stats()
+        .map(Json)
+        .map_err(|e| admin_error(StatusCode::INTERNAL_SERVER_ERROR, e))
+}
+
+/// Purge tasks older than N days
+async fn purge_tasks(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<PurgeQuery>,
+) -> Result<Json<PurgeResponse>, AdminError> {
+    let cutoff = Utc::now() - Duration::days(query.older_than_days.into());
+    let store = state.agent.task_store();
+
+    let removed = store
+        .remove_where(&|record| record.updated_at < cutoff)
+        .map_err(|e| admin_error(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    let remaining = store
+        .stats()
+        .map_err(|e| admin_error(StatusCode::INTERNAL_SERVER_ERROR, e))?
+        .total_tasks;
+
+    state
+        .agent
+        .prune_event_log()
+        .map_err(|e| admin_error(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    info!(removed, remaining, older_than_days = query.older_than_days, "tasks purged");
+    Ok(Json(PurgeResponse { removed, remaining }))
+}
+
+/// Force a stuck task into the Failed state
+async fn force_fail_task(
+    State(state): State<Arc<AppState>>,
+    Path(task_id): Path<String>,
+    auth: Option<Extension<AuthContext>>,
+    body: Option<Json<ForceFailRequest>>,
+) -> Result<Json<TaskResponse>, AdminError> {
+    ensure_exists(&state, &task_id)?;
+    let reason = body
+        .and_then(|Json(body)| body.reason)
+        .unwrap_or_else(|| "no reason given".to_string());
+
+    match state.agent.force_fail_task(&task_id, &reason) {
+        Ok(task) => Ok(Json(state.task_response(auth.as_ref(), task))),
+        Err(e) => {
+            warn!(task_id = %task_id, error = %e, "force-fail refused");
+            Err(admin_error(StatusCode::CONFLICT, e))
+        }
+    }
+}
+
+/// Re-run a failed task from its original request
+async fn rerun_task(
+    State(state): State<Arc<AppState>>,
+    Path(task_id): Path<String>,
+    auth: Option<Extension<AuthContext>>,
+) -> Result<Json<TaskResponse>, AdminError> {
+    ensure_exists(&state, &task_id)?;
+
+    let result = state.agent.rerun_task(&task_id).await.map_err(|e| e.to_string());
+    match result {
+        Ok(task) => Ok(Json(state.task_response(auth.as_ref(), task))),
+        Err(e) => {
+            warn!(task_id = %task_id, error = %e, "re-run refused");
+            Err(admin_error(StatusCode::CONFLICT, e))
+        }
+    }
+}
+
+/// Download the whole task store as JSON or NDJSON
+///
+/// Both are streamed as they are encoded, a batch of records at a time.
+async fn export_snapshot(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<SnapshotQuery>,
+) -> Response {
+    info!(format = ?query.format, "snapshot export started");
+    let (store, redaction) = (state.agent.task_store().clone(), state.agent.redaction().clone());
+    let body = match query.format {
+        SnapshotFormat::Ndjson => export::ndjson(store, redaction, |_| true, Clone::clone),
+        SnapshotFormat::Json => export::json_array(store, redaction, |_| true, Clone::clone),
+    };
+    ([(header::CONTENT_TYPE, query.format.content_type())], body).into_response()
+}
+
+/// Stream the outcome of every task, oldest first, as NDJSON
+///
+/// Results are masked for callers that may not see full data.
+async fn export_results(
+    State(state): State<Arc<AppState>>,
+    auth: Option<Extension<AuthContext>>,
+    Query(query): Query<ExportQuery>,
+) -> Response {
+    let selector = match labels::parse_selector(query.labels.as_deref().unwrap_or("")) {
+        Ok(selector) => selector,
+        Err(e) => return admin_error(StatusCode::BAD_REQUEST, e).into_response(),
+    };
+    info!(since = ?query.since, state = ?query.state, labels = ?query.labels, "results export started");
+    let filter = move |record: &TaskRecord| {
+        query.since.is_none_or(|since| record.updated_at >= since)
+            && query.state.as_deref().is_none_or(|state| record.state_name() == state)
+            && labels::matches(&record.labels, &selector)
+    };
+    let masking = state.masking.applies(&state.rbac, auth.as_ref().map(|Extension(context)| context)).then(|| state.masking.clone());
+    let line = move |record: &TaskRecord| match &masking {
+        Some(masking) => ResultRow::from_record(&masking.record(record)),
+        None => ResultRow::from_record(record),
+    };
+    let body = export::ndjson(state.agent.task_store().clone(), state.agent.redaction().clone(), filter, line);
+    ([(header::CONTENT_TYPE, SnapshotFormat::Ndjson.content_type())], body).into_response()
+}
+
+/// Load a previously exported snapshot into the task store
+async fn restore_snapshot(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<SnapshotQuery>,
+    body: Bytes,
+) -> Result<Json<SnapshotResponse>, AdminError> {
+    // Parsing and loading the whole store is blocking work
+    let store = state.agent.task_store().clone();
+    let (tasks, total_tasks) = tokio::task::spawn_blocking(move || {
+        let records = snapshot::decode(&body, query.format).map_err(|e| admin_error(StatusCode::BAD_REQUEST, e))?;
+        let tasks = store.restore(records).map_err(|e| admin_error(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+        let total_tasks = store.stats().map_err(|e| admin_error(StatusCode::INTERNAL_SERVER_ERROR, e))?.total_tasks;
+        Ok::<_, AdminError>((tasks, total_tasks))
+    })
+    .await
+    .map_err(|e| admin_error(StatusCode::INTERNAL_SERVER_ERROR, e))??;
+
+    info!(tasks, total_tasks, "snapshot restored");
+    Ok(Json(SnapshotResponse { tasks, total_tasks, path: None }))
+}
+
+/// Write a snapshot to the configured `[snapshot] path`
+async fn save_snapshot(State(state): State<Arc<AppState>>) -> Result<Json<SnapshotResponse>, AdminError> {
+    let Some(path) = state.snapshot.path.clone() else {
+        return Err(admin_error(StatusCode::CONFLICT, "No snapshot path configured"));
+    };
+    let store = state.agent.task_store().clone();
+    let redaction = state.agent.redaction().clone();
+
+    let save_path = path.clone();
+    let tasks = tokio::task::spawn_blocking(move || snapshot::save(store.as_ref(), &save_path, &redaction))
+        .await
+        .map_err(|e| admin_error(StatusCode::INTERNAL_SERVER_ERROR, e))?
+        .map_err(|e| admin_error(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    info!(tasks, path = %path.display(), "snapshot saved");
+    Ok(Json(SnapshotResponse {
+        tasks,
+        total_tasks: tasks,
+        path: Some(path.display().to_string()),
+    }))
+}
+
+fn ensure_exists(state: &AppState, task_id: &str) -> Result<(), AdminError> {
+    match state.agent.task_store().get(task_id) {
+        Ok(Some(_)) => Ok(()),
+        Ok(None) => Err(admin_error(StatusCode::NOT_FOUND, format!("Task {} not found", task_id))),
+        Err(e) => Err(admin_error(StatusCode::INTERNAL_SERVER_ERROR, e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{create_router, PurchaseOrderAgent};
+    use crate::testing::aged_task_record;
+    use a2a::{A2AProtocol, TaskState};
+    use axum::body::{to_bytes, Body};
+    use axum::http::Request;
+    use tower::ServiceExt;
+
+    async fn json(router: Router, request: Request<Body>) -> (StatusCode, serde_json::Value) {
+        let response = router.oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or_default())
+    }
+
+    #[tokio::test]
+    async fn test_results_export_filters_tasks() {
+        let agent = Arc::new(PurchaseOrderAgent::new());
+        agent.task_store().put(aged_task_record("old", TaskState::Completed, Duration::days(40))).unwrap();
+        agent.task_store().put(aged_task_record("failed", TaskState::Failed, Duration::days(2))).unwrap();
+        agent.task_store().put(aged_task_record("new", TaskState::Completed, Duration::days(1))).unwrap();
+
+        let since = (Utc::now() - Duration::days(10)).to_rfc3339().replace('+', "%2B");
+        let request = Request::get(format!("/admin/export?state=completed&since={}", since)).body(Body::empty()).unwrap();
+        let response = create_router(agent).oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/x-ndjson");
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let rows: Vec<serde_json::Value> = body.split(|&b| b == b'\n').filter(|line| !line.is_empty()).map(|line| serde_json::from_slice(line).unwrap()).collect();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0]["task_id"], "new");
+    }
+
+    #[tokio::test]
+    async fn test_stats_and_purge() {
+        let agent = Arc::new(PurchaseOrderAgent::new());
+        agent.task_store().put(aged_task_record("old", TaskState::Completed, Duration::days(40))).unwrap();
+        agent.task_store().put(aged_task_record("new", TaskState::Failed, Duration::days(1))).unwrap();
+        let router = create_router(agent.clone());
+
+        let (status, stats) = json(router.clone(), Request::get("/admin/stats").body(Body::empty()).unwrap()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(stats["total_tasks"], 2);
+        assert_eq!(stats["tasks_by_state"]["completed"], 1);
+
+        let purge = Request::post("/admin/tasks/purge?older_than_days=30").body(Body::empty()).unwrap();
+        let (status, purged) = json(router, purge).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(purged["removed"], 1);
+        assert_eq!(purged["remaining"], 1);
+        assert!(agent.task_store().get("old").unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_force_fail_only_applies_to_stuck_tasks() {
+        let agent = Arc::new(PurchaseOrderAgent::new());
+        agent.task_store().put(aged_task_record("stuck", TaskState::Working, Duration::days(0))).unwrap();
+        agent.task_store().put(aged_task_record("done", TaskState::Completed, Duration::days(0))).unwrap();
+        let router = create_router(agent.clone());
+
+        let fail = |id: &str| Request::post(format!("/admin/tasks/{}/fail", id)).body(Body::empty()).unwrap();
+
+        let (status, body) = json(router.clone(), fail("stuck")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["status"], "failed");
+
+        let (status, _) = json(router.clone(), fail("done")).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+
+        let (status, _) = json(router, fail("missing")).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_signing_key_rotation() {
+        let router = create_router(Arc::new(PurchaseOrderAgent::new()));
+        let rotate = |body: serde_json::Value| {
+            Request::post("/admin/signing-keys/rotate")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+
+        let (status, first) = json(router.clone(), rotate(serde_json::json!({ "id": "k1" }))).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(first["secret"].as_str().unwrap().len(), 64);
+        let (status, _) = json(router.clone(), rotate(serde_json::json!({ "id": "k2", "secret": "s2", "overlap": "1h" }))).await;
+        assert_eq!(status, StatusCode::CREATED);
+        let (status, _) = json(router.clone(), rotate(serde_json::json!({ "id": "k2" }))).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+        let (_, keys) = json(router.clone(), Request::get("/admin/signing-keys").body(Body::empty()).unwrap()).await;
+        assert_eq!(keys[0]["id"], "k1");
+        assert!(keys[0]["expires_at"].is_string() && keys[1]["expires_at"].is_null());
+        assert!(keys[1].get("secret").is_none());
+
+        let retire = |id: &str| Request::post(format!("/admin/signing-keys/{}/retire", id)).body(Body::empty()).unwrap();
+        let (status, _) = json(router.clone(), retire("k1")).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = json(router, retire("k9")).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_export_and_restore() {
+        let source = Arc::new(PurchaseOrderAgent::new());
+        source.task_store().put(aged_task_record("a", TaskState::Completed, Duration::days(2))).unwrap();
+        source.task_store().put(aged_task_record("b", TaskState::Failed, Duration::days(1))).unwrap();
+
+        let export = Request::get("/admin/snapshot?format=ndjson").body(Body::empty()).unwrap();
+        let response = create_router(source).oneshot(export).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/x-ndjson");
+        let dump = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(dump.iter().filter(|&&b| b == b'\n').count(), 2);
+
+        let target = Arc::new(PurchaseOrderAgent::new());
+        let restore = Request::post("/admin/snapshot/restore?format=ndjson").body(Body::from(dump)).unwrap();
+        let (status, body) = json(create_router(target.clone()), restore).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["tasks"], 2);
+        assert!(target.task_store().get("b").unwrap().is_some());
+
+        let garbage = Request::post("/admin/snapshot/restore").body(Body::from("nope")).unwrap();
+        let (status, _) = json(create_router(target), garbage).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_snapshots_larger_than_the_default_body_limit_are_restored() {
+        let source = Arc::new(PurchaseOrderAgent::new());
+        let task = source.send_task(crate::testing::message(&crate::testing::huge_order(30_000))).await.unwrap();
+        let export = Request::get("/admin/snapshot").body(Body::empty()).unwrap();
+        let response = create_router(source).oneshot(export).await.unwrap();
+        let dump = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert!(dump.len() > 2 * 1024 * 1024, "{} bytes", dump.len());
+
+        let target = Arc::new(PurchaseOrderAgent::new());
+        let restore = Request::post("/admin/snapshot/restore").body(Body::from(dump)).unwrap();
+        let (status, body) = json(create_router(target.clone()), restore).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert!(target.task_store().get(&task.id).unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_rerun_requires_failed_task() {
+        let agent = Arc::new(PurchaseOrderAgent::new());
+        agent.task_store().put(aged_task_record("working", TaskState::Working, Duration::days(0))).unwrap();
+        let router = create_router(agent);
+
+        let rerun = Request::post("/admin/tasks/working/rerun").body(Body::empty()).unwrap();
+        let (status, _) = json(router, rerun).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+    }
+
+    #[tokio::test]
+    async fn test_dead_letters_are_listed_and_replayed() {
+        let agent = Arc::new(PurchaseOrderAgent::new());
+        let router = create_router(agent.clone());
+        let garbled = Request::post("/agent/task")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(r#"{"message":{"role":"user","parts":[{"type":"data","data":{"order":"PO-1"}}]}}"#))
+            .unwrap();
+        router.clone().oneshot(garbled).await.unwrap();
+
+        let (status, letters) = json(router.clone(), Request::get("/admin/dead-letters").body(Body::empty()).unwrap()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(letters.as_array().unwrap().len(), 1);
+        assert_eq!(letters[0]["message"]["parts"][0]["data"]["order"], "PO-1");
+        let id = letters[0]["id"].as_str().unwrap();
+
+        let fixed = serde_json::json!({ "message": crate::testing::message(&crate::testing::valid_order()) });
+        let replay = || {
+            Request::post(format!("/admin/dead-letters/{}/replay", id))
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(fixed.to_string()))
+                .unwrap()
+        };
+        let (status, task) = json(router.clone(), replay()).await;
+        assert_eq!(status, StatusCode::OK);
+        let (_, letter) = json(router.clone(), Request::get(format!("/admin/dead-letters/{}", id)).body(Body::empty()).unwrap()).await;
+        assert_eq!(letter["replayed_task_id"], task["task_id"]);
+        assert_eq!(json(router, replay()).await.0, StatusCode::CONFLICT);
+    }
+
+    #[tokio::test]
+    async fn test_export_jobs_run_on_demand_with_history() {
+        let blobs = Arc::new(crate::blob::MemoryBlobStore::default());
+        let agent = Arc::new(PurchaseOrderAgent::new().with_blob_store(blobs));
+        agent.task_store().put(aged_task_record("yesterday", TaskState::Completed, Duration::days(1))).unwrap();
+        let config = crate::ServerConfig {
+            exports: toml::from_str(
+                r#"
+                [[jobs]]
+                id = "nightly"
+                cron = "0 2 * * *"
+                format = "parquet"
+                destination = { type = "blob", key = "exports/{date}.{ext}" }
+                "#,
+            )
+            .unwrap(),
+            ..crate::ServerConfig::default()
+        };
+        let router = crate::create_router_with_config(agent, &config);
+
+        let (status, jobs) = json(router.clone(), Request::get("/admin/exports").body(Body::empty()).unwrap()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(jobs[0]["destination"], "blob:exports/{date}.{ext}");
+        assert!(jobs[0]["next_run"].is_string() && jobs[0]["last_run"].is_null());
+
+        let (status, run) = json(router.clone(), Request::post("/admin/exports/nightly/run").body(Body::empty()).unwrap()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(run["rows"], 1);
+        assert_eq!(run["trigger"], "manual");
+        assert_eq!(json(router.clone(), Request::post("/admin/exports/missing/run").body(Body::empty()).unwrap()).await.0, StatusCode::NOT_FOUND);
+
+        let (_, runs) = json(router, Request::get("/admin/exports/runs?job=nightly").body(Body::empty()).unwrap()).await;
+        assert_eq!(runs.as_array().unwrap().len(), 1);
+        assert_eq!(runs[0]["destination"], format!("exports/{}.parquet", (Utc::now() - Duration::days(1)).format("%Y-%m-%d")));
+    }
+}
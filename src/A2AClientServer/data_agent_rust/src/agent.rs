@@ -1,13 +1,70 @@
-use a2a::{A2AProtocol, AgentCard, Message, Task, TaskStatus, TaskState, Part, InMemoryTaskStore};
+use a2a::{A2AProtocol, AgentCard, Message, Task, TaskStatus, TaskState, Part};
 use async_trait::async_trait;
+use std::collections::BTreeMap;
 use std::error::Error;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, RwLock};
 use uuid::Uuid;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use chrono::{DateTime, Utc};
+use tokio::sync::broadcast;
+use tracing::{debug, info, warn};
 
 use crate::a2a_agent_card::A2AAgentCard;
+use crate::address::Address;
+use crate::amendments::{AmendmentStore, MemoryAmendmentStore};
+use crate::approval::{self, ApprovalConfig, ApprovalRequirement, LineDisposition, LineRejection};
+use crate::approval_reason::{self, ApprovalReason};
+use crate::artifacts;
+use crate::assembly::{self, Assemblies};
+use crate::attachments::{AttachmentStore, AttachmentsConfig, MemoryAttachmentStore};
+use crate::blob::{BlobConfig, BlobStore};
+use crate::blanket::{self, BlanketStore, BlanketTerms, MemoryBlanketStore};
+use crate::breaker::CircuitBreakers;
+use crate::catalog::{self, CatalogStore, MemoryCatalogStore, MemorySupplierStore, SupplierStore};
+use crate::deadline;
+use crate::directory::Directory;
+use crate::dry_run;
+use crate::deferred::DeferredTasks;
+use crate::idoc::{self, IdocConfig};
+use crate::ingest::{self, UnparseableMessage};
+use crate::labels::Labels;
+use crate::events::{EventBus, EventLog, MemoryEventLog, TaskEvent, TaskEventKind};
+use crate::expense::{self, ExpenseReview};
+use crate::metrics::Metrics;
+use crate::normalize::{self, Adjustment};
+use crate::order_builder::PurchaseOrderBuilder;
+use crate::parts::{self, PartSchema};
+use crate::plugins::{self, Plugins};
+use crate::dead_letter::{self, DeadLetterStore, MemoryDeadLetterStore};
+use crate::price_history::{self, MemoryPriceHistoryStore, PriceHistoryConfig, PriceHistoryStore};
+use crate::processing::{Priority, SubmissionQueue};
+use crate::risk::{RiskAssessment, RiskScorer};
+use crate::rounding::ReconciliationConfig;
+use crate::sales_tax::SalesTaxConfig;
+use crate::receiving::{MemoryReceiptStore, ReceiptStore};
+use crate::screening::{DeniedPartyList, ScreeningMatch};
+use crate::readiness::Dependencies;
+use crate::scheduled_exports::ExportRuns;
+use crate::searches::{MemorySavedSearchStore, SavedSearchStore};
+use crate::session;
+use crate::redaction::RedactionConfig;
+use crate::result_cache::{ResultCache, ResultCacheConfig};
+use crate::result_schema;
+use crate::returns::{self, ReturnsConfig};
+use crate::severity::{Check, Findings, SeverityPolicy};
+use crate::tax_id::{self, TaxIdConfig, TaxIdFinding};
+use crate::thresholds::HighValueConfig;
+use crate::validation::{self, ValidationConfig};
+use crate::skills::{self, SkillDispatcher, SkillHandler, ValidationReport};
+use crate::request_options::OutputFormat;
+use crate::store::{is_terminal, MemoryTaskStore, TaskRecord, TaskStore};
+use crate::signing_keys::{MemorySigningKeyStore, SigningKeyStore};
+use crate::webhooks::{self, MemoryWebhookOutbox, WebhookOutbox};
+
+/// Task events buffered for each live subscriber before the oldest are dropped
+pub const LIVE_EVENT_CAPACITY: usize = 1024;
 
 /// Purchase Order Item structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -31,10 +88,18 @@ pub struct PurchaseOrder {
     pub supplier_state: String,
     pub supplier_postal_code: String,
     pub supplier_country: String,
+    /// VAT number or EIN; required of international suppliers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub supplier_tax_id: Option<String>,
+    /// May be left out of a header whose items follow in later parts
+    #[serde(default)]
     pub items: Vec<PurchaseOrderItem>,
     pub po_number: String,
     pub created_by: String,
     pub buyer_department: String,
+    /// Where the order is delivered, when not to the buyer's usual address
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ship_to: Option<Address>,
     pub notes: Option<String>,
     pub tax_rate: f64,
     pub sub_total: f64,
@@ -42,6 +107,36 @@ pub struct PurchaseOrder {
     pub grand_total: f64,
     pub is_approved: bool,
     pub approval_reason: Option<String>,
+    /// Not-to-exceed terms, present when this is a blanket order
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blanket: Option<BlanketTerms>,
+    /// Blanket order this is a release against
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blanket_po_number: Option<String>,
+    /// ISO 4217 currency of the amounts; the configured default currency when absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
+}
+
+impl PurchaseOrder {
+    /// Start an order whose amounts are computed from its items
+    pub fn builder(po_number: &str) -> PurchaseOrderBuilder {
+        PurchaseOrderBuilder::new(po_number)
+    }
+
+    /// The order as canonical JSON: keys sorted, no whitespace, every amount written as a float
+    ///
+    /// Payloads that differ only in key order, formatting or unknown fields
+    /// have the same canonical JSON.
+    pub fn canonical_json(&self) -> String {
+        // serde_json maps sort their keys
+        serde_json::to_value(self).map(|value| value.to_string()).expect("purchase orders serialize")
+    }
+
+    /// Hex SHA-256 of the canonical JSON, identifying the order in deduplication and caching
+    pub fn fingerprint(&self) -> String {
+        hex::encode(Sha256::digest(self.canonical_json().as_bytes()))
+    }
 }
 
 /// Wrapper for the incoming purchase order data
@@ -54,6 +149,9 @@ pub struct PurchaseOrderWrapper {
 /// Complete processing result for a purchase order
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProcessingResult {
+    /// Version of this contract; see [`result_schema`]
+    #[serde(default = "result_schema::unversioned")]
+    pub schema_version: u32,
     pub status: String,
     pub po_number: String,
     pub validation_errors: Vec<String>,
@@ -67,6 +165,73 @@ pub struct ProcessingResult {
     pub sub_total: f64,
     pub tax: f64,
     pub grand_total: f64,
+    /// Supplier risk, when a risk scorer is configured
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub risk: Option<RiskAssessment>,
+    /// Denied parties the supplier matched; any match fails validation
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub screening_matches: Vec<ScreeningMatch>,
+    /// Problems with the supplier tax ID, also reported as warnings
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tax_id_findings: Vec<TaxIdFinding>,
+    /// The order with recomputed totals, when normalization was requested and changed it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub normalized_order: Option<PurchaseOrder>,
+    /// Amounts corrected by normalization
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub adjustments: Vec<Adjustment>,
+    /// What became of each line, when an approver rejected some of them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub line_dispositions: Vec<LineDisposition>,
+    /// Approvers the approval matrix requires, when one is configured
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approval_requirement: Option<ApprovalRequirement>,
+    /// Whether the order is a credit or return order with negative amounts
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub is_return: bool,
+    /// Whether the result was processed for an identical earlier order and returned from the result cache
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub cached: bool,
+    /// Fingerprint of the order as submitted, see [`PurchaseOrder::fingerprint`]
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub fingerprint: String,
+    /// The status and its reasons in one English sentence
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approval_reason: Option<String>,
+    /// Rules that decided the status, see [`approval_reason`]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub approval_reasons: Vec<ApprovalReason>,
+    /// Output of each output transformer plugin, by plugin name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub plugin_outputs: BTreeMap<String, serde_json::Value>,
+}
+
+impl ProcessingResult {
+    /// Extract the processing result carried in a task status message's Data part
+    pub fn from_status(status: &TaskStatus) -> Option<Self> {
+        status.message.as_ref()?.parts.iter().find_map(|part| match part {
+            Part::Data { data } => result_schema::upgrade(data.clone()).ok().and_then(|data| Self::deserialize(data).ok()),
+            _ => None,
+        })
+    }
+
+    /// CSV row reported for this result
+    pub fn csv_row(&self) -> String {
+        let notes_escaped = self.notes.as_ref()
+            .map(|n| n.replace("\"", "\"\""))  // Escape quotes in CSV
+            .unwrap_or_default();
+        
+        format!(
+            "{},{},{},{},{},{},\"{}\"",
+            self.po_number,
+            self.sub_total,
+            self.tax,
+            self.grand_total,
+            self.supplier_name,
+            self.buyer_department,
+            notes_escaped
+        )
+    }
 }
 
 /// Summary information about the processed purchase order
@@ -82,11 +247,82 @@ pub struct PurchaseOrderSummary {
     pub is_approved: bool,
 }
 
+/// How a task is to be run, beyond the message it is created from
+#[derive(Debug, Clone, Default)]
+pub struct TaskOptions {
+    /// Skill to run instead of the one the message names
+    pub skill_id: Option<String>,
+    /// Lane the task waits in when processed in the background
+    pub priority: Priority,
+    /// Hold the task as scheduled and do not process it before this time
+    pub process_after: Option<DateTime<Utc>>,
+    /// Conversation the task belongs to, collecting the chunks of a purchase order sent over several messages
+    pub session_id: Option<String>,
+    /// Return the result without storing a task or changing anything, see [`dry_run`]
+    pub dry_run: bool,
+    /// Labels to mark the task with, see [`crate::labels`]
+    pub labels: Labels,
+    /// Artifact formats to produce; empty produces all of them
+    pub outputs: Vec<OutputFormat>,
+    /// Language tag of the submitter, kept with the task
+    pub locale: Option<String>,
+    /// URL to post the result to once the task is terminal, see [`webhooks::queue_callback`]
+    pub callback_url: Option<String>,
+}
+
+impl TaskOptions {
+    /// When the task is to be processed, if that is still in the future
+    pub fn deferred_until(&self) -> Option<DateTime<Utc>> {
+        self.process_after.filter(|at| *at > Utc::now())
+    }
+}
+
 /// A specialized A2A agent for processing Purchase Orders
 pub struct PurchaseOrderAgent {
     agent_card: AgentCard,
     a2a_agent_card: A2AAgentCard,
-    task_store: Arc<Mutex<InMemoryTaskStore>>,
+    task_store: Arc<dyn TaskStore>,
+    event_log: Arc<dyn EventLog>,
+    event_bus: Option<EventBus>,
+    webhook_outbox: Arc<dyn WebhookOutbox>,
+    signing_keys: Arc<dyn SigningKeyStore>,
+    metrics: Arc<Metrics>,
+    skills: SkillDispatcher,
+    receipts: Arc<dyn ReceiptStore>,
+    suppliers: Arc<dyn SupplierStore>,
+    catalog: Arc<dyn CatalogStore>,
+    amendments: Arc<dyn AmendmentStore>,
+    attachments: Arc<dyn AttachmentStore>,
+    attachments_config: AttachmentsConfig,
+    blob_store: Option<Arc<dyn BlobStore>>,
+    download_url_ttl: Duration,
+    blankets: Arc<dyn BlanketStore>,
+    risk_scorer: Option<Arc<dyn RiskScorer>>,
+    denied_parties: Option<Arc<DeniedPartyList>>,
+    validation: ValidationConfig,
+    redaction: RedactionConfig,
+    idoc: IdocConfig,
+    submission_queue: Option<SubmissionQueue>,
+    breakers: CircuitBreakers,
+    dependencies: Arc<Dependencies>,
+    deferred: DeferredTasks,
+    approval: ApprovalConfig,
+    result_cache: Arc<ResultCache>,
+    price_history: Arc<dyn PriceHistoryStore>,
+    price_history_config: PriceHistoryConfig,
+    dead_letters: Arc<dyn DeadLetterStore>,
+    saved_searches: Arc<dyn SavedSearchStore>,
+    plugins: Plugins,
+    export_runs: Arc<ExportRuns>,
+    assemblies: Assemblies,
+    directory: RwLock<Arc<Directory>>,
+    live_events: broadcast::Sender<TaskEvent>,
+}
+
+impl Default for PurchaseOrderAgent {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl PurchaseOrderAgent {
@@ -105,7 +341,42 @@ impl PurchaseOrderAgent {
         Self {
             agent_card,
             a2a_agent_card,
-            task_store: Arc::new(Mutex::new(InMemoryTaskStore::new())),
+            task_store: Arc::new(MemoryTaskStore::new()),
+            event_log: Arc::new(MemoryEventLog::new()),
+            event_bus: None,
+            webhook_outbox: Arc::new(MemoryWebhookOutbox::new()),
+            signing_keys: Arc::new(MemorySigningKeyStore::new()),
+            metrics: Arc::new(Metrics::new()),
+            skills: SkillDispatcher::builtin(),
+            receipts: Arc::new(MemoryReceiptStore::new()),
+            suppliers: Arc::new(MemorySupplierStore::new()),
+            catalog: Arc::new(MemoryCatalogStore::new()),
+            amendments: Arc::new(MemoryAmendmentStore::new()),
+            attachments: Arc::new(MemoryAttachmentStore::new()),
+            attachments_config: AttachmentsConfig::default(),
+            blob_store: None,
+            download_url_ttl: BlobConfig::default().url_ttl,
+            blankets: Arc::new(MemoryBlanketStore::new()),
+            risk_scorer: None,
+            denied_parties: None,
+            validation: ValidationConfig::default(),
+            redaction: RedactionConfig::default(),
+            idoc: IdocConfig::default(),
+            submission_queue: None,
+            breakers: CircuitBreakers::new(),
+            dependencies: Arc::new(Dependencies::new()),
+            deferred: DeferredTasks::new(),
+            approval: ApprovalConfig::default(),
+            result_cache: Arc::new(ResultCache::default()),
+            price_history: Arc::new(MemoryPriceHistoryStore::new()),
+            price_history_config: PriceHistoryConfig::default(),
+            dead_letters: Arc::new(MemoryDeadLetterStore::default()),
+            saved_searches: Arc::new(MemorySavedSearchStore::new()),
+            plugins: Plugins::new(),
+            export_runs: Arc::new(ExportRuns::default()),
+            assemblies: Assemblies::default(),
+            directory: RwLock::new(Arc::new(Directory::builtin())),
+            live_events: broadcast::channel(LIVE_EVENT_CAPACITY).0,
         }
     }
 
@@ -127,11 +398,7 @@ impl PurchaseOrderAgent {
             Some("https://agent2agent.info"),
         );
 
-        Self {
-            agent_card,
-            a2a_agent_card,
-            task_store: Arc::new(Mutex::new(InMemoryTaskStore::new())),
-        }
+        Self { agent_card, a2a_agent_card, ..Self::new() }
     }
 
     /// Get the agent's card information
@@ -144,99 +411,1082 @@ impl PurchaseOrderAgent {
         &self.a2a_agent_card
     }
 
-    /// Validate a purchase order and return any errors or warnings
-    fn validate_purchase_order(&self, po: &PurchaseOrder) -> (Vec<String>, Vec<String>) {
-        let mut errors = Vec::new();
-        let mut warnings = Vec::new();
+    /// Use a different task store backend
+    pub fn with_task_store(mut self, task_store: Arc<dyn TaskStore>) -> Self {
+        self.task_store = task_store;
+        self
+    }
+
+    /// Get the task store backing this agent
+    pub fn task_store(&self) -> &Arc<dyn TaskStore> {
+        &self.task_store
+    }
+
+    /// Use a different event log backend
+    pub fn with_event_log(mut self, event_log: Arc<dyn EventLog>) -> Self {
+        self.event_log = event_log;
+        self
+    }
+
+    /// Get the event log recording every task's history
+    pub fn event_log(&self) -> &Arc<dyn EventLog> {
+        &self.event_log
+    }
+
+    /// Publish every recorded task event to `event_bus`
+    pub fn with_event_bus(mut self, event_bus: EventBus) -> Self {
+        self.event_bus = Some(event_bus);
+        self
+    }
+
+    /// Use a different webhook outbox backend
+    pub fn with_webhook_outbox(mut self, webhook_outbox: Arc<dyn WebhookOutbox>) -> Self {
+        self.webhook_outbox = webhook_outbox;
+        self
+    }
+
+    /// Get the outbox tracking webhook deliveries
+    pub fn webhook_outbox(&self) -> &Arc<dyn WebhookOutbox> {
+        &self.webhook_outbox
+    }
 
-        // Required field validation
-        if po.supplier_name.trim().is_empty() {
-            errors.push("Supplier name is required".to_string());
+    /// Use a different store for the keys signing webhook payloads
+    pub fn with_signing_key_store(mut self, signing_keys: Arc<dyn SigningKeyStore>) -> Self {
+        self.signing_keys = signing_keys;
+        self
+    }
+
+    /// Get the keys signing webhook payloads
+    pub fn signing_keys(&self) -> &Arc<dyn SigningKeyStore> {
+        &self.signing_keys
+    }
+
+    /// Append an event to a task's stream and publish it on the event bus, if any
+    pub fn record_event(&self, task_id: &str, kind: TaskEventKind) -> Result<TaskEvent, Box<dyn Error>> {
+        if let TaskEventKind::StateChanged { status } = &kind {
+            self.write_idoc(task_id, status);
         }
-        if po.po_number.trim().is_empty() {
-            errors.push("PO number is required".to_string());
+        let event = self.event_log.append(task_id, kind)?;
+        debug!(task_id = %task_id, sequence = event.sequence, "task event recorded");
+        if let Some(bus) = &self.event_bus {
+            bus.publish(event.clone());
         }
-        if po.created_by.trim().is_empty() {
-            errors.push("Created by field is required".to_string());
+        // Sending only fails when nobody is listening
+        let _ = self.live_events.send(event.clone());
+
+        if let TaskEventKind::StateChanged { status } = &event.kind
+            && self.approval.is_enabled()
+            && approval::awaits_approval(status)
+            && let Err(e) = approval::start(self, &self.approval, task_id)
+        {
+            warn!(task_id = %task_id, error = %e, "approval assignment failed");
         }
-        if po.buyer_department.trim().is_empty() {
-            errors.push("Buyer department is required".to_string());
+        if let TaskEventKind::StateChanged { status } = &event.kind
+            && is_terminal(&status.state)
+            && let Ok(Some(record)) = self.task_store.get(task_id)
+            && record.callback_url.is_some()
+            && let Err(e) = webhooks::queue_callback(self.webhook_outbox.as_ref(), &event, &record)
+        {
+            warn!(task_id = %task_id, error = %e, "failed to queue the task's callback");
         }
+        Ok(event)
+    }
 
-        // Items validation
-        if po.items.is_empty() {
-            errors.push("Purchase order must contain at least one item".to_string());
-        } else {
-            for (index, item) in po.items.iter().enumerate() {
-                if item.item_code.trim().is_empty() {
-                    errors.push(format!("Item {} is missing item code", index + 1));
-                }
-                if item.description.trim().is_empty() {
-                    errors.push(format!("Item {} is missing description", index + 1));
-                }
-                if item.quantity == 0 {
-                    errors.push(format!("Item {} has zero quantity", index + 1));
+    /// Receive every task event recorded from now on
+    ///
+    /// A receiver more than [`LIVE_EVENT_CAPACITY`] events behind skips the oldest
+    /// ones and is told how many it missed.
+    pub fn subscribe_events(&self) -> broadcast::Receiver<TaskEvent> {
+        self.live_events.subscribe()
+    }
+
+    /// Drop the event streams, goods receipts, revisions, attachments and settled webhook deliveries of tasks that are no longer in the task store
+    ///
+    /// Returns how many event streams were dropped.
+    pub fn prune_event_log(&self) -> Result<usize, Box<dyn Error>> {
+        let store = &self.task_store;
+        let keep = |task_id: &str| store.get(task_id).map_or(true, |record| record.is_some());
+        self.receipts.retain_tasks(&keep)?;
+        self.amendments.retain_tasks(&keep)?;
+        self.attachments.retain_tasks(&keep)?;
+        self.webhook_outbox.retain_tasks(&keep)?;
+        Ok(self.event_log.retain_tasks(&keep)?)
+    }
+
+    /// Use a different goods receipt store backend
+    pub fn with_receipt_store(mut self, receipts: Arc<dyn ReceiptStore>) -> Self {
+        self.receipts = receipts;
+        self
+    }
+
+    /// Get the store of goods received against processed orders
+    pub fn receipts(&self) -> &Arc<dyn ReceiptStore> {
+        &self.receipts
+    }
+
+    /// Use a different supplier master data backend
+    pub fn with_supplier_store(mut self, suppliers: Arc<dyn SupplierStore>) -> Self {
+        self.suppliers = suppliers;
+        self
+    }
+
+    /// Get the supplier master data
+    pub fn suppliers(&self) -> &Arc<dyn SupplierStore> {
+        &self.suppliers
+    }
+
+    /// Use a different item catalog backend
+    pub fn with_catalog_store(mut self, catalog: Arc<dyn CatalogStore>) -> Self {
+        self.catalog = catalog;
+        self
+    }
+
+    /// Get the catalog of orderable items
+    pub fn catalog(&self) -> &Arc<dyn CatalogStore> {
+        &self.catalog
+    }
+
+    /// Use a different amendment store backend
+    pub fn with_amendment_store(mut self, amendments: Arc<dyn AmendmentStore>) -> Self {
+        self.amendments = amendments;
+        self
+    }
+
+    /// Get the store of purchase order revision chains
+    pub fn amendments(&self) -> &Arc<dyn AmendmentStore> {
+        &self.amendments
+    }
+
+    /// Use a different attachment store backend
+    pub fn with_attachment_store(mut self, attachments: Arc<dyn AttachmentStore>) -> Self {
+        self.attachments = attachments;
+        self
+    }
+
+    /// Get the store of documents attached to tasks
+    pub fn attachments(&self) -> &Arc<dyn AttachmentStore> {
+        &self.attachments
+    }
+
+    /// Limit the files attached to tasks
+    pub fn with_attachments_config(mut self, config: AttachmentsConfig) -> Self {
+        self.attachments_config = config;
+        self
+    }
+
+    /// Limits on attached files
+    pub fn attachments_config(&self) -> &AttachmentsConfig {
+        &self.attachments_config
+    }
+
+    /// Keep large payloads in a blob store, which also serves presigned downloads
+    pub fn with_blob_store(mut self, blobs: Arc<dyn BlobStore>) -> Self {
+        self.blob_store = Some(blobs);
+        self
+    }
+
+    /// Get the blob store, if one is configured
+    pub fn blob_store(&self) -> Option<&Arc<dyn BlobStore>> {
+        self.blob_store.as_ref()
+    }
+
+    /// Let presigned download URLs live for `ttl`
+    pub fn with_download_url_ttl(mut self, ttl: Duration) -> Self {
+        self.download_url_ttl = ttl;
+        self
+    }
+
+    /// How long presigned download URLs stay valid
+    pub fn download_url_ttl(&self) -> Duration {
+        self.download_url_ttl
+    }
+
+    /// Use a different blanket order store backend
+    pub fn with_blanket_store(mut self, blankets: Arc<dyn BlanketStore>) -> Self {
+        self.blankets = blankets;
+        self
+    }
+
+    /// Get the store of blanket orders and their releases
+    pub fn blankets(&self) -> &Arc<dyn BlanketStore> {
+        &self.blankets
+    }
+
+    /// Score supplier risk with `risk_scorer` while processing orders
+    pub fn with_risk_scorer(mut self, risk_scorer: Arc<dyn RiskScorer>) -> Self {
+        self.risk_scorer = Some(risk_scorer);
+        self
+    }
+
+    /// Screen suppliers against `denied_parties` while validating orders
+    pub fn with_denied_parties(mut self, denied_parties: Arc<DeniedPartyList>) -> Self {
+        self.denied_parties = Some(denied_parties);
+        self
+    }
+
+    /// Check supplier tax IDs with `tax_id_config` instead of the defaults
+    pub fn with_tax_id_config(mut self, tax_id_config: TaxIdConfig) -> Self {
+        self.validation.tax_id = tax_id_config;
+        self
+    }
+
+    /// Report validation checks with the severities of `severity` instead of their defaults
+    pub fn with_severity_policy(mut self, severity: SeverityPolicy) -> Self {
+        self.validation.severity = severity;
+        self
+    }
+
+    /// Redact personal data in logs and snapshots as `redaction` says
+    pub fn with_redaction(mut self, redaction: RedactionConfig) -> Self {
+        self.redaction = redaction;
+        self
+    }
+
+    /// Personal data redaction settings
+    pub fn redaction(&self) -> &RedactionConfig {
+        &self.redaction
+    }
+
+    /// Export approved orders as SAP IDocs as `idoc` says
+    pub fn with_idoc_config(mut self, idoc: IdocConfig) -> Self {
+        self.idoc = idoc;
+        self
+    }
+
+    /// IDoc export settings
+    pub fn idoc_config(&self) -> &IdocConfig {
+        &self.idoc
+    }
+
+    /// ORDERS05 IDoc of a task, or `None` when the task is not an approved order
+    pub fn idoc(&self, record: &TaskRecord) -> Result<Option<String>, Box<dyn Error>> {
+        self.approved_idoc(record, &record.task.status)
+    }
+
+    fn approved_idoc(&self, record: &TaskRecord, status: &TaskStatus) -> Result<Option<String>, Box<dyn Error>> {
+        let Some(result) = ProcessingResult::from_status(status).filter(|result| result.status == "APPROVED") else {
+            return Ok(None);
+        };
+        let po = match result.normalized_order {
+            Some(po) => po,
+            None => self.extract_purchase_order(&record.request)?,
+        };
+        let document_number = idoc::document_number(&record.task.id);
+        Ok(Some(idoc::orders05(&self.idoc, &document_number, &po, result.processed_at)))
+    }
+
+    /// Write the IDoc of a task that became an approved order to the IDoc output directory
+    fn write_idoc(&self, task_id: &str, status: &TaskStatus) {
+        let Some(dir) = &self.idoc.output_dir else { return };
+        let written = self.task_store.get(task_id).map_err(Box::<dyn Error>::from).and_then(|record| {
+            let Some(record) = record else { return Ok(None) };
+            let Some(idoc) = self.approved_idoc(&record, status)? else { return Ok(None) };
+            let path = dir.join(format!("{}.idoc", idoc::document_number(task_id)));
+            std::fs::create_dir_all(dir)?;
+            // Written aside and renamed, so the middleware polling the directory never picks up half a file
+            let partial = path.with_extension("partial");
+            std::fs::write(&partial, idoc)?;
+            std::fs::rename(&partial, &path)?;
+            Ok(Some(path))
+        });
+        match written {
+            Ok(Some(path)) => {
+                info!(task_id = %task_id, path = %path.display(), "IDoc written");
+                self.metrics.increment("po_agent_idocs_written_total", "Approved orders exported as IDoc files", &[], 1);
+            }
+            Ok(None) => {}
+            Err(e) => warn!(task_id = %task_id, error = %e, "IDoc export failed"),
+        }
+    }
+
+    /// Queue submitted tasks for background processing instead of processing them while the client waits
+    pub fn with_submission_queue(mut self, queue: SubmissionQueue) -> Self {
+        self.submission_queue = Some(queue);
+        self
+    }
+
+    /// The background processing queue, when background processing is enabled
+    pub fn submission_queue(&self) -> Option<&SubmissionQueue> {
+        self.submission_queue.as_ref()
+    }
+
+    /// Circuit breakers of the downstream integrations this agent calls
+    pub fn breakers(&self) -> &CircuitBreakers {
+        &self.breakers
+    }
+
+    /// Outcomes of the calls made to each downstream, as reported on `/ready`
+    pub fn dependencies(&self) -> &Arc<Dependencies> {
+        &self.dependencies
+    }
+
+    /// Flag orders above the thresholds `config` sets as high value
+    pub fn with_high_value(mut self, config: HighValueConfig) -> Self {
+        self.validation.high_value = config;
+        self
+    }
+
+    /// High-value thresholds per department and currency
+    pub fn high_value_config(&self) -> &HighValueConfig {
+        &self.validation.high_value
+    }
+
+    /// Accept or refuse credit and return orders as `config` says
+    pub fn with_returns(mut self, config: ReturnsConfig) -> Self {
+        self.validation.returns = config;
+        self
+    }
+
+    /// Whether credit and return orders are accepted
+    pub fn returns_config(&self) -> &ReturnsConfig {
+        &self.validation.returns
+    }
+
+    /// Round and compare amounts in the reconciliation checks and normalization as `config` says
+    pub fn with_reconciliation(mut self, config: ReconciliationConfig) -> Self {
+        self.validation.reconciliation = config;
+        self
+    }
+
+    /// Rounding and tolerance of the reconciliation checks
+    pub fn reconciliation_config(&self) -> &ReconciliationConfig {
+        &self.validation.reconciliation
+    }
+
+    /// Check order tax rates against the state sales tax rates of `config`
+    pub fn with_sales_tax(mut self, config: SalesTaxConfig) -> Self {
+        self.validation.sales_tax = config;
+        self
+    }
+
+    /// US sales tax rates per state
+    pub fn sales_tax_config(&self) -> &SalesTaxConfig {
+        &self.validation.sales_tax
+    }
+
+    /// Configuration of the checks that need nothing but the order, for [`validation::validate`]
+    pub fn validation_config(&self) -> &ValidationConfig {
+        &self.validation
+    }
+
+    /// Return cached results for orders identical to ones processed recently
+    pub fn with_result_cache(mut self, config: ResultCacheConfig) -> Self {
+        self.result_cache = Arc::new(ResultCache::new(config));
+        self
+    }
+
+    /// Results of recently processed orders
+    pub fn result_cache(&self) -> &Arc<ResultCache> {
+        &self.result_cache
+    }
+
+    /// Use a different price history backend
+    pub fn with_price_history_store(mut self, price_history: Arc<dyn PriceHistoryStore>) -> Self {
+        self.price_history = price_history;
+        self
+    }
+
+    /// Compare unit prices with the trailing average as `config` says
+    pub fn with_price_history(mut self, config: PriceHistoryConfig) -> Self {
+        self.price_history_config = config;
+        self
+    }
+
+    /// Unit prices paid per item code by processed orders
+    pub fn price_history(&self) -> &Arc<dyn PriceHistoryStore> {
+        &self.price_history
+    }
+
+    pub fn price_history_config(&self) -> &PriceHistoryConfig {
+        &self.price_history_config
+    }
+
+    /// Use a different store for messages no purchase order could be parsed from
+    pub fn with_dead_letter_store(mut self, dead_letters: Arc<dyn DeadLetterStore>) -> Self {
+        self.dead_letters = dead_letters;
+        self
+    }
+
+    /// Get the store of messages no purchase order could be parsed from
+    pub fn dead_letters(&self) -> &Arc<dyn DeadLetterStore> {
+        &self.dead_letters
+    }
+
+    /// Use a different store for saved searches of the task list
+    pub fn with_saved_search_store(mut self, saved_searches: Arc<dyn SavedSearchStore>) -> Self {
+        self.saved_searches = saved_searches;
+        self
+    }
+
+    /// Get the store of saved searches of the task list
+    pub fn saved_searches(&self) -> &Arc<dyn SavedSearchStore> {
+        &self.saved_searches
+    }
+
+    /// Run the validation rules and output transformers of `plugins` on every order
+    pub fn with_plugins(mut self, plugins: Plugins) -> Self {
+        self.plugins = plugins;
+        self
+    }
+
+    /// Get the loaded validation and output plugins
+    pub fn plugins(&self) -> &Plugins {
+        &self.plugins
+    }
+
+    /// Get the history of scheduled export runs
+    pub fn export_runs(&self) -> &Arc<ExportRuns> {
+        &self.export_runs
+    }
+
+    /// Check departments and requesters against `directory` instead of the built-in departments
+    pub fn with_directory(self, directory: Directory) -> Self {
+        self.set_directory(directory);
+        self
+    }
+
+    /// Replace the directory, e.g. once it is reloaded
+    pub fn set_directory(&self, directory: Directory) {
+        if let Ok(mut current) = self.directory.write() {
+            *current = Arc::new(directory);
+        }
+    }
+
+    /// The directory departments and requesters are checked against
+    pub fn directory(&self) -> Arc<Directory> {
+        self.directory.read().map(|directory| directory.clone()).unwrap_or_else(|_| Arc::new(Directory::builtin()))
+    }
+
+    /// Tasks waiting for more messages of a purchase order sent in chunks
+    pub fn assemblies(&self) -> &Assemblies {
+        &self.assemblies
+    }
+
+    /// Keep a message as a dead letter when `error` says it holds no purchase order
+    fn record_dead_letter(&self, task_id: Option<&str>, skill_id: &str, priority: Priority, message: &Message, error: &(dyn Error + 'static)) {
+        match dead_letter::record(self, task_id, Some(skill_id), priority, message, error) {
+            Ok(Some(id)) => warn!(dead_letter = %id, task_id = ?task_id, error = %error, "unparseable message kept as a dead letter"),
+            Ok(None) => {}
+            Err(e) => warn!(task_id = ?task_id, error = %e, "failed to record dead letter"),
+        }
+    }
+
+    /// Assign orders pending approval to the approvers `config` names
+    pub fn with_approval(mut self, config: ApprovalConfig) -> Self {
+        self.approval = config;
+        self
+    }
+
+    /// Approver chain and escalation settings
+    pub fn approval_config(&self) -> &ApprovalConfig {
+        &self.approval
+    }
+
+    /// Tasks held until their `process_after` time
+    pub fn deferred(&self) -> &DeferredTasks {
+        &self.deferred
+    }
+
+    /// Problems with the supplier tax ID of `po`
+    pub fn check_tax_id(&self, po: &PurchaseOrder) -> Vec<TaxIdFinding> {
+        tax_id::check(&self.validation.tax_id, po)
+    }
+
+    /// Denied parties the supplier of `po` matches, if screening is configured
+    pub fn screen(&self, po: &PurchaseOrder) -> Vec<ScreeningMatch> {
+        self.denied_parties.as_ref().map(|list| list.screen(po)).unwrap_or_default()
+    }
+
+    /// Audit the denied-party matches reported in `status`, if any
+    fn record_screening(&self, task_id: &str, status: &TaskStatus) -> Result<(), Box<dyn Error>> {
+        let Some(result) = ProcessingResult::from_status(status) else {
+            return Ok(());
+        };
+        if result.screening_matches.is_empty() {
+            return Ok(());
+        }
+        warn!(
+            task_id = %task_id,
+            po_number = %result.po_number,
+            supplier = %result.supplier_name,
+            matches = result.screening_matches.len(),
+            "supplier matched the denied-party list"
+        );
+        self.metrics.increment(
+            "po_agent_screening_matches_total",
+            "Purchase orders whose supplier matched the denied-party list",
+            &[],
+            1,
+        );
+        self.record_event(task_id, TaskEventKind::ScreeningMatched { matches: result.screening_matches })?;
+        Ok(())
+    }
+
+    /// Handle tasks for `handler`'s skill ID, replacing any handler registered for it
+    pub fn with_skill(mut self, handler: Arc<dyn SkillHandler>) -> Self {
+        self.skills.register(handler);
+        self
+    }
+
+    /// Stop serving the skill `skill_id`, e.g. when it is switched off in `[flags.skills]`
+    pub fn without_skill(mut self, skill_id: &str) -> Result<Self, Box<dyn Error>> {
+        self.skills.unregister(skill_id)?;
+        Ok(self)
+    }
+
+    /// Get the dispatcher routing tasks to skill handlers
+    pub fn skills(&self) -> &SkillDispatcher {
+        &self.skills
+    }
+
+    /// Get the metrics registry this agent records into
+    pub fn metrics(&self) -> &Arc<Metrics> {
+        &self.metrics
+    }
+
+    /// Re-run a failed task from its original request, replacing its status in place
+    pub async fn rerun_task(&self, task_id: &str) -> Result<Task, Box<dyn Error>> {
+        let record = self
+            .task_store
+            .get(task_id)?
+            .ok_or_else(|| format!("Task {} not found", task_id))?;
+        if !matches!(record.task.status.state, TaskState::Failed) {
+            return Err(format!("Task {} is {}, only failed tasks can be re-run", task_id, record.state_name()).into());
+        }
+
+        let skill = self.skill_for(&record.request, record.skill_id.as_deref())?;
+        let status = skill.handle(self, &record.request).await?;
+        let updated = self
+            .task_store
+            .update(task_id, &mut |record| record.set_status(status.clone()))?
+            .ok_or_else(|| format!("Task {} not found", task_id))?;
+        self.record_screening(task_id, &status)?;
+        self.record_event(task_id, TaskEventKind::StateChanged { status })?;
+
+        info!(task_id = %task_id, state = updated.state_name(), "task re-run");
+        Ok(updated.task)
+    }
+
+    /// Force a task that is stuck (not yet terminal) into the Failed state
+    pub fn force_fail_task(&self, task_id: &str, reason: &str) -> Result<Task, Box<dyn Error>> {
+        let record = self
+            .task_store
+            .get(task_id)?
+            .ok_or_else(|| format!("Task {} not found", task_id))?;
+        if is_terminal(&record.task.status.state) {
+            return Err(format!("Task {} is already {}", task_id, record.state_name()).into());
+        }
+
+        let status = TaskStatus {
+            state: TaskState::Failed,
+            message: Some(Message {
+                role: "system".to_string(),
+                parts: vec![Part::Text { text: format!("Task force-failed by administrator: {}", reason) }],
+            }),
+            timestamp: self.current_timestamp(),
+        };
+        let updated = self
+            .task_store
+            .update(task_id, &mut |record| record.set_status(status.clone()))?
+            .ok_or_else(|| format!("Task {} not found", task_id))?;
+        self.record_event(task_id, TaskEventKind::StateChanged { status })?;
+
+        info!(task_id = %task_id, reason = %reason, "task force-failed");
+        Ok(updated.task)
+    }
+
+    /// Fail a task that is still working once its processing deadline has passed
+    ///
+    /// Returns `None`, changing nothing, when the task is no longer working.
+    pub fn time_out_task(&self, task_id: &str, deadline: Duration) -> Result<Option<Task>, Box<dyn Error>> {
+        let text = format!("Task did not finish within its processing deadline of {}", humantime_serde::re::humantime::format_duration(deadline));
+        let status = TaskStatus {
+            state: TaskState::Failed,
+            message: Some(Message {
+                role: "system".to_string(),
+                parts: vec![
+                    Part::Text { text: text.clone() },
+                    Part::Data {
+                        data: serde_json::json!({
+                            "finding": deadline::TIMEOUT_FINDING,
+                            "message": text,
+                            "deadline_seconds": deadline.as_secs(),
+                        }),
+                    },
+                ],
+            }),
+            timestamp: self.current_timestamp(),
+        };
+        let mut timed_out = false;
+        let updated = self
+            .task_store
+            .update(task_id, &mut |record| {
+                if matches!(record.task.status.state, TaskState::Working) {
+                    record.set_status(status.clone());
+                    timed_out = true;
                 }
-                if item.unit_price <= 0.0 {
-                    errors.push(format!("Item {} has invalid unit price", index + 1));
+            })?
+            .ok_or_else(|| format!("Task {} not found", task_id))?;
+        if !timed_out {
+            return Ok(None);
+        }
+        self.record_event(task_id, TaskEventKind::StateChanged { status })?;
+        self.metrics.increment(
+            "po_agent_task_timeouts_total",
+            "Tasks failed for staying in the working state past their deadline",
+            &[],
+            1,
+        );
+
+        warn!(task_id = %task_id, deadline = ?deadline, "task timed out");
+        Ok(Some(updated.task))
+    }
+
+    /// Send a task to the agent, routed to `skill_id` instead of the skill named in the message
+    pub async fn send_task_with_skill(&self, message: Message, skill_id: Option<&str>) -> Result<Task, Box<dyn Error>> {
+        let options = TaskOptions { skill_id: skill_id.map(str::to_string), ..TaskOptions::default() };
+        self.send_task_with_options(message, &options).await
+    }
+
+    /// Send a task to the agent and process it straight away, as `options` say
+    ///
+    /// A task with a future `process_after` is stored as scheduled instead, see `accept_task`.
+    pub async fn send_task_with_options(&self, message: Message, options: &TaskOptions) -> Result<Task, Box<dyn Error>> {
+        debug!(role = %message.role, "received purchase order processing task");
+        let message = session::resolve(self, message, options.session_id.as_deref())?;
+        if options.dry_run {
+            return dry_run::run(self, message, options).await;
+        }
+        if options.deferred_until().is_some() {
+            return self.accept_task(message, options);
+        }
+        let chunk = self.receive_chunk(&message, options)?;
+        if let Some(task) = chunk {
+            return match task.status.state {
+                TaskState::Submitted => self.process_submitted_task(&task.id).await,
+                _ => Ok(task),
+            };
+        }
+        
+        // Generate a unique task ID
+        let task_id = Uuid::new_v4().to_string();
+
+        // Process the purchase order with the requested skill
+        let skill = self.skill_for(&message, options.skill_id.as_deref())?;
+        let status = match skill.handle(self, &message).await {
+            Ok(status) => status,
+            Err(e) => {
+                self.record_dead_letter(None, skill.id(), options.priority, &message, e.as_ref());
+                return Err(e);
+            }
+        };
+
+        // Create the task
+        let task = Task {
+            id: task_id.clone(),
+            session_id: options.session_id.clone(),
+            artifacts: artifacts::select(artifacts::from_status(&status), &options.outputs),
+            status,
+        };
+
+        // Store the task along with the request it was created from, before
+        // event subscribers can look it up
+        self.store_new_task(&task, message, skill.id(), options)?;
+        self.record_screening(&task_id, &task.status)?;
+        self.record_event(&task_id, TaskEventKind::StateChanged { status: task.status.clone() })?;
+        
+        info!(task_id = %task_id, skill = skill.id(), "purchase order task completed");
+        
+        Ok(task)
+    }
+
+    /// Store a task as submitted, to be processed later by `process_submitted_task`
+    ///
+    /// A task with a future `process_after` is handed to the deferred task timer;
+    /// any other is for the caller to queue. Fails without storing anything when
+    /// the options name an unknown skill.
+    pub fn accept_task(&self, message: Message, options: &TaskOptions) -> Result<Task, Box<dyn Error>> {
+        let message = session::resolve(self, message, options.session_id.as_deref())?;
+        if let Some(task) = self.receive_chunk(&message, options)? {
+            return Ok(task);
+        }
+        let task_id = Uuid::new_v4().to_string();
+        let skill = self.skill_for(&message, options.skill_id.as_deref())?;
+        let process_after = options.deferred_until();
+        let text = match process_after {
+            Some(at) => format!("Purchase order scheduled for processing after {}", at.to_rfc3339()),
+            None => "Purchase order queued for processing".to_string(),
+        };
+
+        let task = Task {
+            id: task_id.clone(),
+            session_id: options.session_id.clone(),
+            status: TaskStatus {
+                state: TaskState::Submitted,
+                message: Some(Message {
+                    role: "system".to_string(),
+                    parts: vec![Part::Text { text }],
+                }),
+                timestamp: self.current_timestamp(),
+            },
+            artifacts: None,
+        };
+        self.store_new_task(&task, message, skill.id(), options)?;
+
+        if let Some(at) = process_after {
+            self.record_event(&task_id, TaskEventKind::Deferred { process_after: at })?;
+            self.deferred.schedule(&task_id, at);
+            info!(task_id = %task_id, skill = skill.id(), process_after = %at, "purchase order task scheduled");
+        } else {
+            info!(task_id = %task_id, skill = skill.id(), priority = options.priority.as_str(), "purchase order task queued");
+        }
+        Ok(task)
+    }
+
+    /// Store a new task with the request it was created from, and record its creation
+    fn store_new_task(&self, task: &Task, message: Message, skill_id: &str, options: &TaskOptions) -> Result<(), Box<dyn Error>> {
+        let mut record = TaskRecord::new(task.clone(), message.clone());
+        record.skill_id = Some(skill_id.to_string());
+        record.priority = options.priority;
+        record.process_after = options.deferred_until();
+        record.labels = options.labels.clone();
+        record.outputs = options.outputs.clone();
+        record.locale = options.locale.clone();
+        record.callback_url = options.callback_url.clone();
+        self.task_store.put(record)?;
+        self.record_event(&task.id, TaskEventKind::Created {
+            request: message,
+            session_id: task.session_id.clone(),
+            skill_id: Some(skill_id.to_string()),
+            priority: options.priority,
+        })?;
+        if !options.labels.is_empty() {
+            self.record_event(&task.id, TaskEventKind::LabelsChanged { labels: options.labels.clone() })?;
+        }
+        Ok(())
+    }
+
+    /// Add a message to the purchase order being sent in chunks in its session
+    ///
+    /// Returns `None` for a message that is not part of such an order. The
+    /// order's task waits for input until a message without `moreItems`
+    /// completes it; it is then submitted with the assembled request, to be
+    /// processed by the caller, or scheduled when `process_after` is ahead.
+    fn receive_chunk(&self, message: &Message, options: &TaskOptions) -> Result<Option<Task>, Box<dyn Error>> {
+        let more = assembly::has_more(message);
+        let pending = options.session_id.as_deref().and_then(|session_id| self.assemblies.pending(session_id)).filter(|task_id| {
+            matches!(self.task_store.get(task_id), Ok(Some(record)) if matches!(record.task.status.state, TaskState::InputRequired))
+        });
+        if !more && pending.is_none() {
+            return Ok(None);
+        }
+        let Some(session_id) = options.session_id.as_deref() else {
+            return Err(format!("A purchase order sent in chunks with `{}` needs a session_id", assembly::MORE_KEY).into());
+        };
+
+        let status = |request: &Message, messages: usize| {
+            let items = assembly::item_count(request);
+            let (state, text) = if more {
+                (TaskState::InputRequired, format!("Received {} items in {} messages, waiting for more", items, messages))
+            } else {
+                (TaskState::Submitted, format!("Purchase order of {} items assembled from {} messages", items, messages))
+            };
+            TaskStatus {
+                state,
+                message: Some(Message { role: "system".to_string(), parts: vec![Part::Text { text }] }),
+                timestamp: self.current_timestamp(),
+            }
+        };
+        let process_after = if more { None } else { options.deferred_until() };
+        let task = match pending {
+            None => {
+                let skill = self.skill_for(message, options.skill_id.as_deref())?;
+                let task = Task {
+                    id: Uuid::new_v4().to_string(),
+                    session_id: Some(session_id.to_string()),
+                    status: status(message, 1),
+                    artifacts: None,
+                };
+                self.store_new_task(&task, message.clone(), skill.id(), options)?;
+                task
+            }
+            Some(task_id) => {
+                // The first message, the ones added since and this one
+                let added = self.event_log.events(&task_id)?;
+                let messages = added.iter().filter(|event| matches!(event.kind, TaskEventKind::ChunkReceived { .. })).count() + 2;
+                let updated = self
+                    .task_store
+                    .update(&task_id, &mut |record| {
+                        record.request = parts::concat(&record.request, message);
+                        record.set_status(status(&record.request, messages));
+                        record.process_after = process_after;
+                    })?
+                    .ok_or_else(|| format!("Task {} not found", task_id))?;
+                self.record_event(&task_id, TaskEventKind::ChunkReceived { message: message.clone() })?;
+                updated.task
+            }
+        };
+        self.record_event(&task.id, TaskEventKind::StateChanged { status: task.status.clone() })?;
+
+        if more {
+            self.assemblies.hold(session_id, &task.id);
+            debug!(task_id = %task.id, session_id, "purchase order chunk held");
+        } else {
+            self.assemblies.release(session_id);
+            if let Some(at) = process_after {
+                self.record_event(&task.id, TaskEventKind::Deferred { process_after: at })?;
+                self.deferred.schedule(&task.id, at);
+            }
+            info!(task_id = %task.id, session_id, "purchase order assembled");
+        }
+        Ok(Some(task))
+    }
+
+    /// Process a task stored by `accept_task`
+    ///
+    /// The task is working while its skill runs. Tasks that are no longer
+    /// submitted, e.g. cancelled while queued, are left as they are. A skill
+    /// error fails the task with the error as its message.
+    pub async fn process_submitted_task(&self, task_id: &str) -> Result<Task, Box<dyn Error>> {
+        let record = self
+            .task_store
+            .get(task_id)?
+            .ok_or_else(|| format!("Task {} not found", task_id))?;
+        if !matches!(record.task.status.state, TaskState::Submitted) {
+            debug!(task_id = %task_id, state = record.state_name(), "queued task skipped");
+            return Ok(record.task);
+        }
+
+        let skill = self.skill_for(&record.request, record.skill_id.as_deref())?;
+        let working = TaskStatus {
+            state: TaskState::Working,
+            message: Some(Message {
+                role: "system".to_string(),
+                parts: vec![Part::Text { text: "Processing purchase order".to_string() }],
+            }),
+            timestamp: self.current_timestamp(),
+        };
+        let mut started = false;
+        self.task_store.update(task_id, &mut |record| {
+            if matches!(record.task.status.state, TaskState::Submitted) {
+                record.set_status(working.clone());
+                started = true;
+            }
+        })?;
+        if !started {
+            return self.get_task(task_id).await;
+        }
+        self.record_event(task_id, TaskEventKind::StateChanged { status: working })?;
+
+        let status = match skill.handle(self, &record.request).await {
+            Ok(status) => status,
+            Err(e) => {
+                self.record_dead_letter(Some(task_id), skill.id(), record.priority, &record.request, e.as_ref());
+                TaskStatus {
+                    state: TaskState::Failed,
+                    message: Some(Message {
+                        role: "system".to_string(),
+                        parts: vec![Part::Text { text: e.to_string() }],
+                    }),
+                    timestamp: self.current_timestamp(),
                 }
-                
-                // Verify line total calculation
-                let expected_total = item.quantity as f64 * item.unit_price;
-                if (item.line_total - expected_total).abs() > 0.01 {
-                    warnings.push(format!(
-                        "Item {} line total mismatch: expected {:.2}, got {:.2}",
-                        index + 1, expected_total, item.line_total
-                    ));
+            }
+        };
+        // A task cancelled or timed out while it was being processed stays that way
+        let mut processed = false;
+        let updated = self
+            .task_store
+            .update(task_id, &mut |record| {
+                if matches!(record.task.status.state, TaskState::Working) {
+                    record.set_status(status.clone());
+                    processed = true;
                 }
+            })?
+            .ok_or_else(|| format!("Task {} not found", task_id))?;
+        if !processed {
+            return Ok(updated.task);
+        }
+        self.record_screening(task_id, &status)?;
+        self.record_event(task_id, TaskEventKind::StateChanged { status })?;
+
+        info!(task_id = %task_id, state = updated.state_name(), "queued purchase order task processed");
+        Ok(updated.task)
+    }
+
+    /// The latest successfully processed purchase order with this PO number, and its task ID
+    pub fn find_purchase_order(&self, po_number: &str) -> Result<Option<(String, PurchaseOrder)>, Box<dyn Error>> {
+        let latest = self
+            .task_store
+            .list()?
+            .into_iter()
+            .filter(|record| record.skill_id.as_deref().is_none_or(|id| id == skills::PROCESSING_SKILL))
+            .filter(|record| matches!(record.task.status.state, TaskState::Completed))
+            .filter(|record| {
+                ProcessingResult::from_status(&record.task.status).is_some_and(|result| result.po_number == po_number)
+            })
+            .max_by_key(|record| record.created_at);
+
+        match latest {
+            Some(record) => Ok(Some((record.task.id.clone(), self.processed_purchase_order(&record)?))),
+            None => Ok(None),
+        }
+    }
+
+    /// The purchase order a task processed: the normalized order when totals were
+    /// corrected, else the one in its request
+    pub fn processed_purchase_order(&self, record: &TaskRecord) -> Result<PurchaseOrder, Box<dyn Error>> {
+        match ProcessingResult::from_status(&record.task.status).and_then(|result| result.normalized_order) {
+            Some(po) => Ok(po),
+            None => self.extract_purchase_order(&record.request),
+        }
+    }
+
+    /// Validate the purchase order carried by a message without creating a task
+    pub fn validate(&self, message: &Message) -> Result<ValidationReport, Box<dyn Error>> {
+        let po = self.extract_purchase_order(message)?;
+        let (validation_errors, warnings) = self.validate_purchase_order(&po);
+        Ok(ValidationReport {
+            tax_id_findings: self.check_tax_id(&po),
+            po_number: po.po_number,
+            valid: validation_errors.is_empty(),
+            validation_errors,
+            warnings,
+        })
+    }
+
+    /// Record an approver's decision on a purchase order pending approval
+    ///
+    /// An approved order completes as `APPROVED`; a rejected one fails as `REJECTED`.
+    pub fn record_approval(
+        &self,
+        task_id: &str,
+        approver: &str,
+        approved: bool,
+        comment: Option<String>,
+    ) -> Result<Task, Box<dyn Error>> {
+        self.record_line_approval(task_id, approver, approved, comment, &[])
+    }
+
+    /// Record an approver's decision, approving the order without its `rejected_lines`
+    ///
+    /// The rejected lines are dropped from the order, its totals are recomputed and
+    /// the result lists the disposition of every line. Rejecting every line rejects the order.
+    /// Expense reports are decided as a whole.
+    pub fn record_line_approval(
+        &self,
+        task_id: &str,
+        approver: &str,
+        mut approved: bool,
+        comment: Option<String>,
+        rejected_lines: &[LineRejection],
+    ) -> Result<Task, Box<dyn Error>> {
+        let record = self
+            .task_store
+            .get(task_id)?
+            .ok_or_else(|| format!("Task {} not found", task_id))?;
+        let Some(mut result) = ProcessingResult::from_status(&record.task.status) else {
+            let review = ExpenseReview::from_status(&record.task.status)
+                .ok_or_else(|| format!("Task {} has no processing result", task_id))?;
+            if !rejected_lines.is_empty() {
+                return Err("Lines of expense reports cannot be rejected".into());
             }
+            return expense::record_approval(self, task_id, review, approver, approved, comment);
+        };
+        if result.status != "PENDING_APPROVAL" {
+            return Err(format!("Task {} is {}, not pending approval", task_id, result.status).into());
         }
 
-        // Financial calculations validation
-        let calculated_subtotal: f64 = po.items.iter().map(|item| item.line_total).sum();
-        if (po.sub_total - calculated_subtotal).abs() > 0.01 {
-            warnings.push(format!(
-                "Subtotal mismatch: expected {:.2}, got {:.2}",
-                calculated_subtotal, po.sub_total
-            ));
+        if approved && !rejected_lines.is_empty() {
+            let (po, dispositions) = approval::exclude_lines(&self.processed_purchase_order(&record)?, rejected_lines)?;
+            result.line_dispositions = dispositions;
+            if po.items.is_empty() {
+                approved = false;
+            } else {
+                // The release now draws only the approved lines' total
+                blanket::record(self.blankets.as_ref(), &po)?;
+                result.summary = self.create_summary(&po);
+                result.sub_total = po.sub_total;
+                result.tax = po.tax;
+                result.grand_total = po.grand_total;
+                result.normalized_order = Some(po);
+            }
         }
 
-        let calculated_tax = po.sub_total * po.tax_rate;
-        if (po.tax - calculated_tax).abs() > 0.01 {
-            warnings.push(format!(
-                "Tax calculation mismatch: expected {:.2}, got {:.2}",
-                calculated_tax, po.tax
-            ));
+        // A rejected release no longer draws on its blanket order
+        if !approved
+            && let Ok(po) = self.extract_purchase_order(&record.request)
+            && let Some(blanket_po_number) = &po.blanket_po_number
+        {
+            self.blankets.cancel_release(blanket_po_number, &po.po_number)?;
         }
 
-        let calculated_grand_total = po.sub_total + po.tax;
-        if (po.grand_total - calculated_grand_total).abs() > 0.01 {
-            warnings.push(format!(
-                "Grand total mismatch: expected {:.2}, got {:.2}",
-                calculated_grand_total, po.grand_total
-            ));
+        result.status = if approved { "APPROVED" } else { "REJECTED" }.to_string();
+        result.summary.is_approved = approved;
+        approval_reason::record_decision(&mut result, approver, approved);
+        let status = self.status_for_result(&result)?;
+        let updated = self
+            .task_store
+            .update(task_id, &mut |record| record.set_status(status.clone()))?
+            .ok_or_else(|| format!("Task {} not found", task_id))?;
+        self.record_event(
+            task_id,
+            TaskEventKind::ApprovalRecorded {
+                approver: approver.to_string(),
+                approved,
+                comment,
+                rejected_lines: rejected_lines.iter().map(|rejection| rejection.line).collect(),
+            },
+        )?;
+        self.record_event(task_id, TaskEventKind::StateChanged { status })?;
+
+        info!(task_id = %task_id, approver = %self.redaction.log(approver), approved, "approval recorded");
+        Ok(updated.task)
+    }
+
+    /// Validate a purchase order and return any errors or warnings
+    ///
+    /// Runs [`validation::validate`], then the checks backed by the agent's
+    /// stores. Each failed check is an error, a warning or dropped according
+    /// to the severity policy.
+    pub(crate) fn validate_purchase_order(&self, po: &PurchaseOrder) -> (Vec<String>, Vec<String>) {
+        self.purchase_order_findings(po).into_parts()
+    }
+
+    /// The findings of [`validate_purchase_order`](Self::validate_purchase_order), with the check behind each
+    pub(crate) fn purchase_order_findings(&self, po: &PurchaseOrder) -> Findings<'_> {
+        let mut findings = validation::validate(po, &self.validation);
+
+        // Buyer department and requester against the organization's directory
+        self.directory().check(&po.buyer_department, Some(&po.created_by), &mut findings);
+
+        // Minimum order quantities and pack sizes from the catalog
+        if !po.items.is_empty() && let Err(e) = catalog::validate_quantities(self.catalog.as_ref(), po, &mut findings) {
+            warn!(po_number = %po.po_number, error = %e, "catalog lookup failed during validation");
         }
 
-        // Business rules warnings
-        if po.grand_total > 10000.0 {
-            warnings.push("High value purchase order - may require additional approval".to_string());
+        // Blanket order terms, and the limits of the blanket order a release draws on
+        for error in blanket::validate(self.blankets.as_ref(), po, Utc::now().date_naive()) {
+            findings.push(Check::Blanket, error);
         }
 
-        if po.tax_rate < 0.0 || po.tax_rate > 0.2 {
-            warnings.push("Unusual tax rate detected".to_string());
+        // Denied-party screening of the supplier
+        for screening_match in self.screen(po) {
+            findings.push(Check::DeniedParty, screening_match.error());
         }
 
-        // Department validation (example authorized departments)
-        let authorized_departments = ["Marketing", "Sales", "IT", "Finance", "Operations", "HR"];
-        if !authorized_departments.contains(&po.buyer_department.as_str()) {
-            warnings.push(format!("Department '{}' may not be authorized for purchases", po.buyer_department));
+        // Unit prices against the trailing average of earlier orders
+        if let Err(e) = price_history::validate(self.price_history.as_ref(), &self.price_history_config, po, &mut findings) {
+            warn!(po_number = %po.po_number, error = %e, "price history lookup failed during validation");
         }
 
-        (errors, warnings)
+        // Business-unit rules loaded as plugins
+        plugins::validate(&self.plugins, &self.metrics, po, &mut findings);
+
+        findings
     }
 
     /// Create a summary of the purchase order
-    fn create_summary(&self, po: &PurchaseOrder) -> PurchaseOrderSummary {
+    pub(crate) fn create_summary(&self, po: &PurchaseOrder) -> PurchaseOrderSummary {
         PurchaseOrderSummary {
             total_items: po.items.len(),
             total_quantity: po.items.iter().map(|item| item.quantity).sum(),
@@ -250,59 +1500,106 @@ impl PurchaseOrderAgent {
     }
 
     /// Process a purchase order message
-    async fn process_purchase_order(&self, message: &Message) -> Result<ProcessingResult, Box<dyn Error>> {
-        println!("🛒 Processing purchase order message from role: {}", message.role);
-
-        // Look for purchase order data in message parts
-        let mut purchase_order: Option<PurchaseOrder> = None;
-
-        for part in &message.parts {
-            match part {
-                Part::Data { data } => {
-                    // Try to parse as PurchaseOrderWrapper first
-                    if let Ok(wrapper) = serde_json::from_value::<PurchaseOrderWrapper>(data.clone()) {
-                        purchase_order = Some(wrapper.purchase_order);
-                        break;
-                    }
-                    // Try to parse as direct PurchaseOrder
-                    else if let Ok(po) = serde_json::from_value::<PurchaseOrder>(data.clone()) {
-                        purchase_order = Some(po);
-                        break;
-                    }
-                }
-                Part::Text { text } => {
-                    // Try to parse JSON from text
-                    if let Ok(wrapper) = serde_json::from_str::<PurchaseOrderWrapper>(text) {
-                        purchase_order = Some(wrapper.purchase_order);
-                        break;
-                    }
-                    else if let Ok(po) = serde_json::from_str::<PurchaseOrder>(text) {
-                        purchase_order = Some(po);
-                        break;
-                    }
+    ///
+    /// A dry run neither draws on blanket orders, records prices nor uses the result cache.
+    async fn process_purchase_order(&self, message: &Message, dry_run: bool) -> Result<ProcessingResult, Box<dyn Error>> {
+        debug!(role = %message.role, "processing purchase order message");
+        let mut po = self.extract_purchase_order(message)?;
+        let fingerprint = po.fingerprint();
+        let (normalize, merge) = (normalize::requested(message), normalize::merge_requested(message));
+        let cache_key = (self.result_cache.is_enabled() && !dry_run).then(|| ResultCache::key(&fingerprint, normalize, merge));
+        if let Some(mut cached) = cache_key.as_deref().and_then(|key| self.result_cache.get(key)) {
+            debug!(po_number = %po.po_number, "returning cached processing result");
+            self.metrics.increment(
+                "po_agent_result_cache_hits_total",
+                "Orders answered from the result cache",
+                &[],
+                1,
+            );
+            cached.cached = true;
+            return Ok(cached);
+        }
+
+        let mut adjustments = Vec::new();
+        if normalize {
+            if merge {
+                (po, adjustments) = normalize::merge_duplicates(&po);
+            }
+            let (normalized, corrected) = normalize::normalize(&po, &self.validation.reconciliation);
+            po = normalized;
+            adjustments.extend(corrected);
+        }
+        let mut result = self.evaluate_purchase_order(&po);
+        if !adjustments.is_empty() {
+            result.normalized_order = Some(po.clone());
+            result.adjustments = adjustments;
+            result.fingerprint = fingerprint;
+        }
+        if result.validation_errors.is_empty() && !dry_run {
+            blanket::record(self.blankets.as_ref(), &po)?;
+            price_history::record(self.price_history.as_ref(), &po)?;
+        }
+
+        // A failing scorer leaves the order unscored rather than failing it
+        if let Some(scorer) = &self.risk_scorer {
+            match scorer.score(self, &po).await {
+                Ok(risk) => {
+                    self.metrics.increment(
+                        "po_agent_risk_assessments_total",
+                        "Supplier risk assessments by level",
+                        &[("level", risk.level.name())],
+                        1,
+                    );
+                    result.risk = Some(risk);
                 }
-                _ => continue,
+                Err(e) => warn!(po_number = %po.po_number, error = %e, "supplier risk scoring failed"),
             }
         }
+        result.plugin_outputs = plugins::transform(&self.plugins, &self.metrics, &result);
+        if let Some(key) = cache_key {
+            self.result_cache.put(key, result.clone());
+        }
+        Ok(result)
+    }
 
-        let po = purchase_order.ok_or("No valid purchase order found in message")?;
+    /// Find the purchase order carried by a message; see [`extract_purchase_order`]
+    pub(crate) fn extract_purchase_order(&self, message: &Message) -> Result<PurchaseOrder, Box<dyn Error>> {
+        extract_purchase_order(message)
+    }
 
+    /// Validate a purchase order and determine its processing status
+    pub(crate) fn evaluate_purchase_order(&self, po: &PurchaseOrder) -> ProcessingResult {
         // Validate the purchase order
-        let (validation_errors, warnings) = self.validate_purchase_order(&po);
+        let (validation_errors, warnings) = self.validate_purchase_order(po);
 
         // Create summary
-        let summary = self.create_summary(&po);
+        let mut summary = self.create_summary(po);
+
+        // The approval matrix, when there is one, decides approval instead of the order itself
+        let approval_requirement = if validation_errors.is_empty() {
+            self.approval.requirement(&po.buyer_department, po.grand_total)
+        } else {
+            None
+        };
+        if let Some(requirement) = &approval_requirement {
+            summary.is_approved = requirement.is_automatic();
+        }
 
         // Determine processing status
         let status = if !validation_errors.is_empty() {
             "VALIDATION_FAILED".to_string()
+        } else if let Some(requirement) = &approval_requirement {
+            if requirement.is_automatic() { "APPROVED" } else { "PENDING_APPROVAL" }.to_string()
         } else if po.is_approved {
             "APPROVED".to_string()
         } else {
             "PENDING_APPROVAL".to_string()
         };
+        let approval_reasons = approval_reason::reasons(self, po, validation_errors.len(), approval_requirement.as_ref());
+        let approval_reason = Some(approval_reason::sentence(&status, &approval_reasons));
 
         let result = ProcessingResult {
+            schema_version: result_schema::CURRENT_VERSION,
             status,
             po_number: po.po_number.clone(),
             validation_errors,
@@ -316,53 +1613,53 @@ impl PurchaseOrderAgent {
             sub_total: po.sub_total,
             tax: po.tax,
             grand_total: po.grand_total,
+            risk: None,
+            screening_matches: self.screen(po),
+            tax_id_findings: self.check_tax_id(po),
+            normalized_order: None,
+            adjustments: Vec::new(),
+            line_dispositions: Vec::new(),
+            approval_requirement,
+            is_return: returns::is_return(po),
+            cached: false,
+            fingerprint: po.fingerprint(),
+            approval_reason,
+            approval_reasons,
+            plugin_outputs: BTreeMap::new(),
         };
 
-        println!("✅ Purchase order {} processed with status: {}", result.po_number, result.status);
+        info!(
+            po_number = %result.po_number,
+            status = %result.status,
+            created_by = %self.redaction.log(&po.created_by),
+            "purchase order processed"
+        );
 
-        Ok(result)
+        result
     }
 
-    /// Get current timestamp as string
-    fn current_timestamp(&self) -> String {
-        SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs()
-            .to_string()
+    /// Pick the skill for a message: `skill_id` if given, else the one the message names, else the default
+    pub(crate) fn skill_for(&self, message: &Message, skill_id: Option<&str>) -> Result<Arc<dyn SkillHandler>, Box<dyn Error>> {
+        let requested = skill_id.map(str::to_string).or_else(|| skills::skill_id(message));
+        self.skills.resolve(requested.as_deref())
     }
-}
 
-#[async_trait]
-impl A2AProtocol for PurchaseOrderAgent {
-    /// Send a task to the agent for processing
-    async fn send_task(
-        &self,
-        message: Message,
-    ) -> Result<Task, Box<dyn Error>> {
-        println!("📨 Received purchase order processing task from role: {}", message.role);
-        
-        // Generate a unique task ID
-        let task_id = Uuid::new_v4().to_string();
-        
-        // Process the purchase order
-        let processing_result = self.process_purchase_order(&message).await?;
-        
+    /// Run the full processing flow and report the result
+    pub(crate) async fn process_message(&self, message: &Message) -> Result<TaskStatus, Box<dyn Error>> {
+        let processing_result = self.process_purchase_order(message, false).await?;
+        self.status_for_result(&processing_result)
+    }
+
+    /// Run the full processing flow without changing anything, and report the result
+    pub(crate) async fn dry_run_message(&self, message: &Message) -> Result<TaskStatus, Box<dyn Error>> {
+        let processing_result = self.process_purchase_order(message, true).await?;
+        self.status_for_result(&processing_result)
+    }
+
+    /// Build the task status reporting a processing result
+    pub(crate) fn status_for_result(&self, processing_result: &ProcessingResult) -> Result<TaskStatus, Box<dyn Error>> {
         // Create CSV format response as requested
-        let notes_escaped = processing_result.notes.as_ref()
-            .map(|n| n.replace("\"", "\"\""))  // Escape quotes in CSV
-            .unwrap_or_else(|| "".to_string());
-        
-        let csv_response = format!(
-            "{},{},{},{},{},{},\"{}\"",
-            processing_result.po_number,
-            processing_result.sub_total,
-            processing_result.tax,
-            processing_result.grand_total,
-            processing_result.supplier_name,
-            processing_result.buyer_department,
-            notes_escaped
-        );
+        let csv_response = processing_result.csv_row();
 
         // Create response message with CSV format and detailed result
         let response_message = Message {
@@ -371,52 +1668,50 @@ impl A2AProtocol for PurchaseOrderAgent {
                 Part::Text { 
                     text: csv_response
                 },
-                Part::Data { data: serde_json::to_value(&processing_result)? }
+                Part::Data { data: serde_json::to_value(processing_result)? }
             ],
         };
 
         // Create task status
-        let status = TaskStatus {
-            state: if processing_result.validation_errors.is_empty() {
+        Ok(TaskStatus {
+            state: if processing_result.validation_errors.is_empty() && processing_result.status != "REJECTED" {
                 TaskState::Completed
             } else {
                 TaskState::Failed
             },
             message: Some(response_message),
             timestamp: self.current_timestamp(),
-        };
+        })
+    }
 
-        // Create the task
-        let task = Task {
-            id: task_id.clone(),
-            session_id: None,
-            status,
-            artifacts: None,
-        };
+    /// Get current timestamp as string
+    pub(crate) fn current_timestamp(&self) -> String {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            .to_string()
+    }
+}
 
-        // Store the task
-        {
-            let mut store = self.task_store.lock().map_err(|_| "Failed to acquire task store lock")?;
-            store.store_task(task.clone());
-        }
-        
-        println!("✅ Purchase order task {} completed", task_id);
-        
-        Ok(task)
+#[async_trait]
+impl A2AProtocol for PurchaseOrderAgent {
+    /// Send a task to the agent for processing
+    async fn send_task(
+        &self,
+        message: Message,
+    ) -> Result<Task, Box<dyn Error>> {
+        self.send_task_with_skill(message, None).await
     }
 
     /// Retrieve a task by its ID
     async fn get_task(&self, task_id: &str) -> Result<Task, Box<dyn Error>> {
-        println!("🔍 Looking up task: {}", task_id);
+        debug!(task_id = %task_id, "looking up task");
         
-        let store = self.task_store.lock().map_err(|_| "Failed to acquire task store lock")?;
-        match store.get_task(task_id) {
-            Some(task) => {
-                println!("✅ Found task: {}", task_id);
-                Ok(task)
-            }
+        match self.task_store.get(task_id)? {
+            Some(record) => Ok(record.task),
             None => {
-                println!("❌ Task not found: {}", task_id);
+                debug!(task_id = %task_id, "task not found");
                 Err(format!("Task {} not found", task_id).into())
             }
         }
@@ -424,34 +1719,69 @@ impl A2AProtocol for PurchaseOrderAgent {
 
     /// Cancel a task by its ID
     async fn cancel_task(&self, task_id: &str) -> Result<Task, Box<dyn Error>> {
-        println!("🛑 Attempting to cancel task: {}", task_id);
-        
-        let mut store = self.task_store.lock().map_err(|_| "Failed to acquire task store lock")?;
-        
-        // Retrieve the existing task
-        let existing_task = match store.get_task(task_id) {
-            Some(task) => task,
-            None => return Err(format!("Task {} not found", task_id).into()),
-        };
+        debug!(task_id = %task_id, "cancelling task");
         
+        let timestamp = self.current_timestamp();
+
         // Update task status to cancelled
-        let mut updated_task = existing_task.clone();
-        updated_task.status.state = TaskState::Failed;
-        updated_task.status.message = Some(Message {
-            role: "system".to_string(),
-            parts: vec![Part::Text { text: "Purchase order processing task was cancelled by user request".to_string() }],
-        });
-        updated_task.status.timestamp = self.current_timestamp();
+        let updated = self.task_store.update(task_id, &mut |record| {
+            record.task.status.state = TaskState::Failed;
+            record.task.status.message = Some(Message {
+                role: "system".to_string(),
+                parts: vec![Part::Text { text: "Purchase order processing task was cancelled by user request".to_string() }],
+            });
+            record.task.status.timestamp = timestamp.clone();
+        })?;
 
-        // Store the updated task
-        store.store_task(updated_task.clone());
-        
-        println!("✅ Task {} cancelled successfully", task_id);
-        
-        Ok(updated_task)
+        let Some(record) = updated else {
+            return Err(format!("Task {} not found", task_id).into());
+        };
+        self.record_event(task_id, TaskEventKind::StateChanged { status: record.task.status.clone() })?;
+
+        info!(task_id = %task_id, "task cancelled");
+
+        Ok(record.task)
     }
 }
 
+/// Find the purchase order carried by a message, as JSON in a Data or Text part
+///
+/// Each part is parsed once, wrapped or bare, without copying its data. Parts
+/// declaring the `purchase-order` schema in their metadata are the only ones
+/// parsed, and the first of them must parse. A message without one fails with
+/// an [`UnparseableMessage`] giving each part's error.
+pub(crate) fn extract_purchase_order(message: &Message) -> Result<PurchaseOrder, Box<dyn Error>> {
+    let candidates = match parts::candidates(message, PartSchema::PurchaseOrder) {
+        Ok(candidates) => candidates,
+        Err(e) => return Err(Box::new(UnparseableMessage { errors: vec![e] })),
+    };
+    let mut errors = Vec::new();
+    for candidate in candidates {
+        if assembly::is_items(candidate.part) {
+            continue;
+        }
+        let parsed = match candidate.part {
+            Part::Data { data } => ingest::from_value(data),
+            Part::Text { text } => ingest::from_str(text),
+            _ => continue,
+        };
+        match parsed {
+            Ok(po) => {
+                return assembly::append_items(po, message)
+                    .map_err(|e| -> Box<dyn Error> { Box::new(UnparseableMessage { errors: vec![e] }) });
+            }
+            Err(e) => errors.push(format!("part {}: {}", candidate.index + 1, e)),
+        }
+        if candidate.declared {
+            break;
+        }
+    }
+    if errors.is_empty() {
+        errors.push("the message has no Data or Text part".to_string());
+    }
+    Err(Box::new(UnparseableMessage { errors }))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -467,6 +1797,7 @@ mod tests {
                 supplier_state: "CA".to_string(),
                 supplier_postal_code: "90210".to_string(),
                 supplier_country: "USA".to_string(),
+                supplier_tax_id: None,
                 items: vec![
                     PurchaseOrderItem {
                         item_code: "bk-2345".to_string(),
@@ -486,6 +1817,7 @@ mod tests {
                 po_number: "MMS-80085".to_string(),
                 created_by: "J.J. Schmidt".to_string(),
                 buyer_department: "Marketing".to_string(),
+                ship_to: None,
                 notes: Some("thanks for the order! Happy learning!! :)".to_string()),
                 tax_rate: 0.07,
                 sub_total: 194.94,
@@ -493,6 +1825,9 @@ mod tests {
                 grand_total: 208.59,
                 is_approved: true,
                 approval_reason: Some("Approved: Grand Total $208.59 is below $1000, Supplier Name is provided, and Buyer Department 'Marketing' is an authorized department.".to_string()),
+                blanket: None,
+                blanket_po_number: None,
+                currency: None,
             }
         }
     }
@@ -581,6 +1916,20 @@ mod tests {
         assert!(matches!(task.status.state, TaskState::Completed));
     }
 
+    #[test]
+    fn test_fingerprint_ignores_key_order_and_formatting() {
+        let po = create_sample_purchase_order().purchase_order;
+        let mut value = serde_json::to_value(&po).unwrap();
+        value["unknownField"] = serde_json::json!(true);
+        let reparsed: PurchaseOrder = serde_json::from_str(&serde_json::to_string_pretty(&value).unwrap()).unwrap();
+        assert_eq!(reparsed.fingerprint(), po.fingerprint());
+        assert!(po.canonical_json().starts_with(r#"{"approvalReason":"#));
+
+        let mut changed = po.clone();
+        changed.items[1].quantity = 4;
+        assert_ne!(changed.fingerprint(), po.fingerprint());
+    }
+
     #[tokio::test]
     async fn test_get_task() {
         let agent = PurchaseOrderAgent::new();
@@ -632,6 +1981,51 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_record_approval_rejects_pending_order() {
+        let agent = PurchaseOrderAgent::new();
+        let mut po = create_sample_purchase_order();
+        po.purchase_order.is_approved = false;
+        let message = Message {
+            role: "user".to_string(),
+            parts: vec![Part::Data { data: serde_json::to_value(po).unwrap() }],
+        };
+        let task = agent.send_task(message).await.unwrap();
+
+        let rejected = agent
+            .record_approval(&task.id, "approvals", false, Some("over budget".to_string()))
+            .unwrap();
+        assert!(matches!(rejected.status.state, TaskState::Failed));
+        let result = ProcessingResult::from_status(&rejected.status).unwrap();
+        assert_eq!(result.status, "REJECTED");
+        assert!(!result.summary.is_approved);
+
+        assert!(agent.record_approval(&task.id, "approvals", true, None).is_err(), "already decided");
+    }
+
+    #[tokio::test]
+    async fn test_event_stream_rebuilds_stored_task() {
+        let agent = PurchaseOrderAgent::new();
+        let message = Message {
+            role: "user".to_string(),
+            parts: vec![
+                Part::Data { data: serde_json::to_value(create_sample_purchase_order()).unwrap() }
+            ],
+        };
+
+        let task = agent.send_task(message).await.unwrap();
+        agent.cancel_task(&task.id).await.unwrap();
+
+        let events = agent.event_log().events(&task.id).unwrap();
+        assert_eq!(events.len(), 3);
+        assert!(matches!(events[0].kind, TaskEventKind::Created { .. }));
+
+        let rebuilt = crate::events::rebuild(&events).unwrap();
+        let stored = agent.task_store().get(&task.id).unwrap().unwrap();
+        assert_eq!(rebuilt.state_name(), stored.state_name());
+        assert_eq!(rebuilt.task.status.timestamp, stored.task.status.timestamp);
+    }
+
     #[tokio::test]
     async fn test_get_nonexistent_task() {
         let agent = PurchaseOrderAgent::new();
@@ -657,10 +2051,12 @@ mod tests {
                 supplier_state: "CA".to_string(),
                 supplier_postal_code: "12345".to_string(),
                 supplier_country: "USA".to_string(),
+                supplier_tax_id: None,
                 items: vec![], // Invalid: no items
                 po_number: "".to_string(), // Invalid: empty
                 created_by: "Tester".to_string(),
                 buyer_department: "Marketing".to_string(),
+                ship_to: None,
                 notes: None,
                 tax_rate: 0.07,
                 sub_total: 0.0,
@@ -668,6 +2064,9 @@ mod tests {
                 grand_total: 0.0,
                 is_approved: false,
                 approval_reason: None,
+                blanket: None,
+                blanket_po_number: None,
+                currency: None,
             }
         };
         
@@ -0,0 +1,510 @@
+use a2a::{Message, Part, Task};
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::agent::{ProcessingResult, PurchaseOrder, PurchaseOrderAgent, PurchaseOrderItem};
+use crate::events::TaskEventKind;
+use crate::skills::PROCESSING_SKILL;
+use crate::store::{StoreError, StoreResult};
+
+/// One revision of a purchase order and the task that processed it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Revision {
+    pub po_number: String,
+    /// Starts at 1 for the order as first submitted
+    pub revision: u32,
+    pub task_id: String,
+    /// Task of the revision this one amends; absent for revision 1
+    pub previous_task_id: Option<String>,
+    /// Changes against the previous revision; absent for revision 1
+    pub diff: Option<OrderDiff>,
+    pub amended_by: Option<String>,
+    pub reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Body of `POST /agent/orders/{po_number}/amendments`
+#[derive(Debug, Clone, Deserialize)]
+pub struct AmendmentRequest {
+    /// The order as it should now read, in full
+    #[serde(rename = "purchaseOrder")]
+    pub purchase_order: PurchaseOrder,
+    #[serde(default)]
+    pub amended_by: Option<String>,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// A top-level field whose value changed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldChange {
+    /// Field name as serialized on the purchase order, e.g. `grandTotal`
+    pub field: String,
+    pub before: serde_json::Value,
+    pub after: serde_json::Value,
+}
+
+/// How a line item differs between two revisions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// A line item that was added, removed or changed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ItemChange {
+    pub item_code: String,
+    pub change: ItemChangeKind,
+    pub before: Option<PurchaseOrderItem>,
+    pub after: Option<PurchaseOrderItem>,
+    /// Fields of a changed item that differ
+    pub fields: Vec<FieldChange>,
+}
+
+/// Field-level differences between two versions of a purchase order
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OrderDiff {
+    pub fields: Vec<FieldChange>,
+    pub items: Vec<ItemChange>,
+}
+
+impl OrderDiff {
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty() && self.items.is_empty()
+    }
+}
+
+/// An order total that differs between two tasks
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TotalChange {
+    /// `subTotal`, `tax` or `grandTotal`
+    pub field: String,
+    pub before: f64,
+    pub after: f64,
+    /// `after` less `before`, rounded to cents
+    pub delta: f64,
+}
+
+/// Differences between the purchase orders two tasks processed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskDiff {
+    pub po_number: String,
+    pub from_task_id: String,
+    pub to_task_id: String,
+    /// Revisions of the two tasks, when they are part of the PO's revision chain
+    pub from_revision: Option<u32>,
+    pub to_revision: Option<u32>,
+    /// Totals that changed, also listed among `diff.fields`
+    pub totals: Vec<TotalChange>,
+    pub diff: OrderDiff,
+}
+
+fn field_changes(before: serde_json::Value, after: serde_json::Value, skip: &[&str]) -> Vec<FieldChange> {
+    let (serde_json::Value::Object(before), serde_json::Value::Object(mut after)) = (before, after) else {
+        return Vec::new();
+    };
+    // Optional fields are left out when unset, so a key on either side may be missing
+    let paired: Vec<_> = before
+        .into_iter()
+        .map(|(field, before)| {
+            let after = after.remove(&field).unwrap_or(serde_json::Value::Null);
+            (field, before, after)
+        })
+        .collect();
+    let added = after.into_iter().map(|(field, after)| (field, serde_json::Value::Null, after));
+    let mut changes: Vec<FieldChange> = paired
+        .into_iter()
+        .chain(added)
+        .filter(|(field, before, after)| !skip.contains(&field.as_str()) && before != after)
+        .map(|(field, before, after)| FieldChange { field, before, after })
+        .collect();
+    changes.sort_by(|a, b| a.field.cmp(&b.field));
+    changes
+}
+
+/// Compare two versions of a purchase order
+///
+/// Line items are paired by item code, compared case-insensitively; repeated
+/// codes are paired in the order they appear.
+pub fn diff_orders(before: &PurchaseOrder, after: &PurchaseOrder) -> OrderDiff {
+    let to_value = |po: &PurchaseOrder| serde_json::to_value(po).unwrap_or_default();
+    let fields = field_changes(to_value(before), to_value(after), &["items"]);
+
+    let mut by_code: BTreeMap<String, (Vec<&PurchaseOrderItem>, Vec<&PurchaseOrderItem>)> = BTreeMap::new();
+    for item in &before.items {
+        by_code.entry(item.item_code.trim().to_lowercase()).or_default().0.push(item);
+    }
+    for item in &after.items {
+        by_code.entry(item.item_code.trim().to_lowercase()).or_default().1.push(item);
+    }
+
+    let mut items = Vec::new();
+    for (olds, news) in by_code.into_values() {
+        for i in 0..olds.len().max(news.len()) {
+            let (old, new) = (olds.get(i).copied(), news.get(i).copied());
+            let change = match (old, new) {
+                (Some(old), Some(new)) => {
+                    let to_value = |item: &PurchaseOrderItem| serde_json::to_value(item).unwrap_or_default();
+                    let fields = field_changes(to_value(old), to_value(new), &[]);
+                    if fields.is_empty() {
+                        continue;
+                    }
+                    ItemChange {
+                        item_code: new.item_code.clone(),
+                        change: ItemChangeKind::Changed,
+                        before: Some(old.clone()),
+                        after: Some(new.clone()),
+                        fields,
+                    }
+                }
+                (None, Some(new)) => ItemChange {
+                    item_code: new.item_code.clone(),
+                    change: ItemChangeKind::Added,
+                    before: None,
+                    after: Some(new.clone()),
+                    fields: Vec::new(),
+                },
+                (Some(old), None) => ItemChange {
+                    item_code: old.item_code.clone(),
+                    change: ItemChangeKind::Removed,
+                    before: Some(old.clone()),
+                    after: None,
+                    fields: Vec::new(),
+                },
+                (None, None) => continue,
+            };
+            items.push(change);
+        }
+    }
+
+    OrderDiff { fields, items }
+}
+
+const TOTAL_FIELDS: [&str; 3] = ["subTotal", "tax", "grandTotal"];
+
+/// Compare the purchase orders processed by tasks `from` and `to`
+///
+/// Both tasks must have processed the same PO number, e.g. two of its revisions.
+pub fn diff_tasks(agent: &PurchaseOrderAgent, from: &str, to: &str) -> Result<TaskDiff, AmendmentError> {
+    let order = |task_id: &str| -> Result<PurchaseOrder, AmendmentError> {
+        let record = agent
+            .task_store()
+            .get(task_id)?
+            .ok_or_else(|| AmendmentError::NotFound(format!("Task {} not found", task_id)))?;
+        if ProcessingResult::from_status(&record.task.status).is_none() {
+            return Err(AmendmentError::Invalid(format!("Task {} has no processed purchase order", task_id)));
+        }
+        agent
+            .processed_purchase_order(&record)
+            .map_err(|e| AmendmentError::Invalid(format!("Task {}: {}", task_id, e)))
+    };
+    let (before, after) = (order(from)?, order(to)?);
+    if before.po_number != after.po_number {
+        return Err(AmendmentError::Invalid(format!(
+            "Task {} processed PO {} but task {} processed PO {}",
+            from, before.po_number, to, after.po_number
+        )));
+    }
+
+    let diff = diff_orders(&before, &after);
+    let totals = diff
+        .fields
+        .iter()
+        .filter(|change| TOTAL_FIELDS.contains(&change.field.as_str()))
+        .filter_map(|change| {
+            let (before, after) = (change.before.as_f64()?, change.after.as_f64()?);
+            Some(TotalChange { field: change.field.clone(), before, after, delta: ((after - before) * 100.0).round() / 100.0 })
+        })
+        .collect();
+    // A PO whose chain cannot be read is still diffed, just without revision numbers
+    let chain = revisions(agent, &after.po_number).unwrap_or_default();
+    let revision_of = |task_id: &str| chain.iter().find(|r| r.task_id == task_id).map(|r| r.revision);
+
+    Ok(TaskDiff {
+        from_revision: revision_of(from),
+        to_revision: revision_of(to),
+        po_number: after.po_number,
+        from_task_id: from.to_string(),
+        to_task_id: to.to_string(),
+        totals,
+        diff,
+    })
+}
+
+/// Storage of purchase order revision chains
+pub trait AmendmentStore: Send + Sync {
+    fn put(&self, revision: Revision) -> StoreResult<()>;
+
+    /// Every recorded revision of a PO number, oldest first
+    fn chain(&self, po_number: &str) -> StoreResult<Vec<Revision>>;
+
+    /// Drop revisions whose task does not match `keep`, returning how many were dropped
+    fn retain_tasks(&self, keep: &dyn Fn(&str) -> bool) -> StoreResult<usize>;
+}
+
+/// In-memory amendment store used by default
+#[derive(Default)]
+pub struct MemoryAmendmentStore {
+    chains: DashMap<String, Vec<Revision>>,
+}
+
+impl MemoryAmendmentStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl AmendmentStore for MemoryAmendmentStore {
+    fn put(&self, revision: Revision) -> StoreResult<()> {
+        let mut chain = self.chains.entry(revision.po_number.clone()).or_default();
+        chain.retain(|r| r.revision != revision.revision);
+        chain.push(revision);
+        chain.sort_by_key(|r| r.revision);
+        Ok(())
+    }
+
+    fn chain(&self, po_number: &str) -> StoreResult<Vec<Revision>> {
+        Ok(self.chains.get(po_number).map(|c| c.clone()).unwrap_or_default())
+    }
+
+    fn retain_tasks(&self, keep: &dyn Fn(&str) -> bool) -> StoreResult<usize> {
+        let mut dropped = 0;
+        for mut chain in self.chains.iter_mut() {
+            let before = chain.len();
+            chain.retain(|r| keep(&r.task_id));
+            dropped += before - chain.len();
+        }
+        self.chains.retain(|_, chain| !chain.is_empty());
+        Ok(dropped)
+    }
+}
+
+/// Why an amendment could not be recorded or a revision chain read
+#[derive(Debug)]
+pub enum AmendmentError {
+    /// No processed order with that PO number
+    NotFound(String),
+    /// The amendment cannot be applied
+    Invalid(String),
+    Store(StoreError),
+    /// Re-processing the amended order failed
+    Processing(String),
+}
+
+impl fmt::Display for AmendmentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AmendmentError::NotFound(message)
+            | AmendmentError::Invalid(message)
+            | AmendmentError::Processing(message) => f.write_str(message),
+            AmendmentError::Store(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for AmendmentError {}
+
+impl From<StoreError> for AmendmentError {
+    fn from(e: StoreError) -> Self {
+        AmendmentError::Store(e)
+    }
+}
+
+/// The revision chain of a PO number
+///
+/// Before any amendment the chain is the latest processed submission alone, as revision 1.
+pub fn revisions(agent: &PurchaseOrderAgent, po_number: &str) -> Result<Vec<Revision>, AmendmentError> {
+    let chain = agent.amendments().chain(po_number)?;
+    if !chain.is_empty() {
+        return Ok(chain);
+    }
+
+    let original = agent
+        .task_store()
+        .list()?
+        .into_iter()
+        .filter(|record| record.skill_id.as_deref().is_none_or(|id| id == PROCESSING_SKILL))
+        .filter(|record| {
+            ProcessingResult::from_status(&record.task.status).is_some_and(|result| result.po_number == po_number)
+        })
+        .max_by_key(|record| record.created_at)
+        .ok_or_else(|| AmendmentError::NotFound(format!("No processed purchase order {}", po_number)))?;
+
+    Ok(vec![Revision {
+        po_number: po_number.to_string(),
+        revision: 1,
+        task_id: original.task.id,
+        previous_task_id: None,
+        diff: None,
+        amended_by: None,
+        reason: None,
+        created_at: original.created_at,
+    }])
+}
+
+/// Amend a processed purchase order
+///
+/// The amended order is processed as a new task, so it is validated again, and
+/// recorded as the next revision with its differences from the previous one.
+pub async fn amend(
+    agent: &PurchaseOrderAgent,
+    po_number: &str,
+    request: AmendmentRequest,
+) -> Result<(Revision, Task), AmendmentError> {
+    if request.purchase_order.po_number != po_number {
+        return Err(AmendmentError::Invalid(format!(
+            "Amendment is for PO {} but was submitted against {}",
+            request.purchase_order.po_number, po_number
+        )));
+    }
+
+    let chain = revisions(agent, po_number)?;
+    let Some(previous) = chain.last() else {
+        return Err(AmendmentError::NotFound(format!("No processed purchase order {}", po_number)));
+    };
+    let previous_record = agent
+        .task_store()
+        .get(&previous.task_id)?
+        .ok_or_else(|| AmendmentError::NotFound(format!("Task {} of revision {} no longer exists", previous.task_id, previous.revision)))?;
+    let previous_po = agent
+        .processed_purchase_order(&previous_record)
+        .map_err(|e| AmendmentError::Invalid(e.to_string()))?;
+
+    let diff = diff_orders(&previous_po, &request.purchase_order);
+    if diff.is_empty() {
+        return Err(AmendmentError::Invalid(format!(
+            "Amendment does not change revision {} of PO {}",
+            previous.revision, po_number
+        )));
+    }
+
+    let message = Message {
+        role: "user".to_string(),
+        parts: vec![Part::Data { data: serde_json::json!({ "purchaseOrder": request.purchase_order }) }],
+    };
+    let task = agent
+        .send_task_with_skill(message, Some(PROCESSING_SKILL))
+        .await
+        .map_err(|e| AmendmentError::Processing(e.to_string()))?;
+
+    // The first amendment pins down which submission the chain starts from
+    if chain.len() == 1 {
+        agent.amendments().put(previous.clone())?;
+    }
+    let revision = Revision {
+        po_number: po_number.to_string(),
+        revision: previous.revision + 1,
+        task_id: task.id.clone(),
+        previous_task_id: Some(previous.task_id.clone()),
+        diff: Some(diff),
+        amended_by: request.amended_by,
+        reason: request.reason,
+        created_at: Utc::now(),
+    };
+    agent.amendments().put(revision.clone())?;
+    agent
+        .record_event(
+            &task.id,
+            TaskEventKind::Amended {
+                po_number: po_number.to_string(),
+                revision: revision.revision,
+                previous_task_id: previous.task_id.clone(),
+            },
+        )
+        .map_err(|e| AmendmentError::Processing(e.to_string()))?;
+
+    Ok((revision, task))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use a2a::A2AProtocol;
+
+    fn po(quantity: u32, notes: Option<&str>) -> PurchaseOrder {
+        let mut po = crate::testing::order_of(0.1, &[("P-1", "Paper", quantity, 5.0)]);
+        po.notes = notes.map(str::to_string);
+        po
+    }
+
+    fn amendment(po: PurchaseOrder) -> AmendmentRequest {
+        AmendmentRequest { purchase_order: po, amended_by: Some("jo".to_string()), reason: None }
+    }
+
+    #[test]
+    fn test_diff_reports_fields_and_items() {
+        let mut after = po(4, Some("rush"));
+        after.items.push(after.items[0].clone());
+        after.items[1].item_code = "t-2".to_string();
+
+        let diff = diff_orders(&po(2, None), &after);
+        let fields: Vec<&str> = diff.fields.iter().map(|c| c.field.as_str()).collect();
+        assert_eq!(fields, ["grandTotal", "notes", "subTotal", "tax"]);
+        assert_eq!(diff.items.len(), 2);
+        assert_eq!(diff.items[0].change, ItemChangeKind::Changed);
+        assert_eq!(diff.items[0].fields.iter().map(|c| c.field.as_str()).collect::<Vec<_>>(), ["lineTotal", "quantity"]);
+        assert_eq!(diff.items[1].change, ItemChangeKind::Added);
+        assert!(diff_orders(&po(2, None), &po(2, None)).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_amendments_extend_the_revision_chain() {
+        let agent = PurchaseOrderAgent::new();
+        let message = Message {
+            role: "user".to_string(),
+            parts: vec![Part::Data { data: serde_json::to_value(po(2, None)).unwrap() }],
+        };
+        let original = agent.send_task(message).await.unwrap();
+        assert_eq!(revisions(&agent, "PO-1").unwrap()[0].task_id, original.id);
+
+        let (second, _) = amend(&agent, "PO-1", amendment(po(4, None))).await.unwrap();
+        assert_eq!(second.revision, 2);
+        assert_eq!(second.previous_task_id.as_deref(), Some(original.id.as_str()));
+
+        let (third, _) = amend(&agent, "PO-1", amendment(po(4, Some("rush")))).await.unwrap();
+        assert_eq!(third.revision, 3);
+        assert_eq!(third.diff.unwrap().fields[0].field, "notes");
+
+        let chain = revisions(&agent, "PO-1").unwrap();
+        assert_eq!(chain.iter().map(|r| r.revision).collect::<Vec<_>>(), [1, 2, 3]);
+        assert_eq!(chain[0].task_id, original.id, "the chain stays rooted at the original");
+
+        // Setting an optional field that was left out is a change too
+        let mut priced = po(4, Some("rush"));
+        priced.currency = Some("USD".to_string());
+        let (fourth, _) = amend(&agent, "PO-1", amendment(priced.clone())).await.unwrap();
+        let fields = fourth.diff.unwrap().fields;
+        assert_eq!((fields.len(), fields[0].field.as_str(), &fields[0].before), (1, "currency", &serde_json::Value::Null));
+
+        let unchanged = amend(&agent, "PO-1", amendment(priced)).await.unwrap_err();
+        assert!(matches!(unchanged, AmendmentError::Invalid(_)));
+        assert!(matches!(amend(&agent, "PO-2", amendment(po(1, None))).await.unwrap_err(), AmendmentError::Invalid(_)));
+    }
+
+    #[tokio::test]
+    async fn test_task_diff_highlights_totals_and_items() {
+        let agent = PurchaseOrderAgent::new();
+        let message = |po: PurchaseOrder| Message { role: "user".to_string(), parts: vec![Part::Data { data: serde_json::to_value(po).unwrap() }] };
+        let original = agent.send_task(message(po(2, None))).await.unwrap();
+        let (revision, _) = amend(&agent, "PO-1", amendment(po(4, None))).await.unwrap();
+
+        let diff = diff_tasks(&agent, &original.id, &revision.task_id).unwrap();
+        assert_eq!((diff.from_revision, diff.to_revision), (Some(1), Some(2)));
+        let grand_total = diff.totals.iter().find(|t| t.field == "grandTotal").unwrap();
+        assert_eq!(grand_total.delta, 11.0);
+        assert_eq!(diff.totals.len(), 3);
+        assert_eq!(diff.diff.items[0].change, ItemChangeKind::Changed);
+
+        let mut other = po(1, None);
+        other.po_number = "PO-2".to_string();
+        let other = agent.send_task(message(other)).await.unwrap();
+        assert!(matches!(diff_tasks(&agent, &original.id, &other.id), Err(AmendmentError::Invalid(_))));
+        assert!(matches!(diff_tasks(&agent, &original.id, "missing"), Err(AmendmentError::NotFound(_))));
+    }
+}
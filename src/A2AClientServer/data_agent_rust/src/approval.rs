@@ -0,0 +1,667 @@
+//! Human approval of purchase orders pending approval
+//!
+//! Each order that ends up `PENDING_APPROVAL` is assigned to the first approver
+//! of the configured chain. The assignee decides it, or delegates it to another
+//! user who then acts in their place. An assignment left undecided for
+//! `escalate_after` moves on to the next approver in the chain. Every hop is an
+//! `approval_assigned` event in the task's history, published like any other
+//! event so webhooks and the event bus can notify the new assignee.
+//!
+//! With an approval matrix, the department and grand total of an order pick
+//! the roles and number of approvers it needs, or approve it automatically.
+//! Approvals are collected until the requirement is met.
+//!
+//! An approver can reject individual lines of an otherwise fine order. The
+//! rejected lines are dropped, the totals recomputed, and the result records
+//! the disposition of every line.
+
+use a2a::{Task, TaskStatus};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+use crate::agent::{ProcessingResult, PurchaseOrder, PurchaseOrderAgent};
+use crate::events::TaskEventKind;
+use crate::expense::ExpenseReview;
+use crate::invoice::round2;
+use crate::store::{StoreError, TaskRecord};
+
+/// Processing status of orders that wait for an approver
+pub const PENDING_APPROVAL: &str = "PENDING_APPROVAL";
+
+/// Who approves orders pending approval, and when undecided ones escalate
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ApprovalConfig {
+    /// Approvers in escalation order; the first is assigned every order pending approval
+    pub chain: Vec<String>,
+    /// Hand an undecided approval to the next approver in the chain after this long
+    #[serde(with = "humantime_serde")]
+    pub escalate_after: Option<Duration>,
+    /// How often assignments are checked for escalation
+    #[serde(with = "humantime_serde")]
+    pub check_interval: Duration,
+    /// Bands of department and amount, the first matching one deciding what an order needs
+    pub matrix: Vec<ApprovalRule>,
+}
+
+impl Default for ApprovalConfig {
+    fn default() -> Self {
+        Self {
+            chain: Vec::new(),
+            escalate_after: None,
+            check_interval: Duration::from_secs(60),
+            matrix: Vec::new(),
+        }
+    }
+}
+
+impl ApprovalConfig {
+    /// Assignment is on when an approver chain is configured
+    pub fn is_enabled(&self) -> bool {
+        !self.chain.is_empty()
+    }
+
+    /// What the matrix requires of an order, or `None` without a matrix
+    ///
+    /// An order no band covers needs one approval from any approver.
+    pub fn requirement(&self, department: &str, amount: f64) -> Option<ApprovalRequirement> {
+        if self.matrix.is_empty() {
+            return None;
+        }
+        let requirement = match self.matrix.iter().find(|rule| rule.matches(department, amount)) {
+            Some(rule) => ApprovalRequirement { rule: rule.name.clone(), roles: rule.roles.clone(), approvals: rule.approvals },
+            None => ApprovalRequirement { rule: None, roles: Vec::new(), approvals: 1 },
+        };
+        Some(requirement)
+    }
+}
+
+/// One band of the approval matrix
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ApprovalRule {
+    /// Shown in the requirement of the orders the band covers
+    pub name: Option<String>,
+    /// Departments covered, compared case-insensitively; empty for every department
+    pub departments: Vec<String>,
+    /// Lowest grand total covered
+    pub min_amount: f64,
+    /// Grand totals from this one up are not covered; unset for no upper bound
+    pub max_amount: Option<f64>,
+    /// Roles that must each be held by one of the approvers
+    pub roles: Vec<String>,
+    /// Distinct approvers needed; 0 approves orders automatically
+    pub approvals: usize,
+}
+
+impl Default for ApprovalRule {
+    fn default() -> Self {
+        Self {
+            name: None,
+            departments: Vec::new(),
+            min_amount: 0.0,
+            max_amount: None,
+            roles: Vec::new(),
+            approvals: 1,
+        }
+    }
+}
+
+impl ApprovalRule {
+    fn matches(&self, department: &str, amount: f64) -> bool {
+        (self.departments.is_empty() || self.departments.iter().any(|d| d.eq_ignore_ascii_case(department)))
+            && amount >= self.min_amount
+            && self.max_amount.is_none_or(|max| amount < max)
+    }
+}
+
+/// What an order needs before it is approved, as the approval matrix decided it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApprovalRequirement {
+    /// Name of the band that matched
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rule: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub roles: Vec<String>,
+    pub approvals: usize,
+}
+
+impl ApprovalRequirement {
+    /// Whether the order is approved without anyone deciding it
+    pub fn is_automatic(&self) -> bool {
+        self.approvals == 0
+    }
+
+    /// Whether an approver holding `roles` may approve
+    fn accepts(&self, roles: &[String]) -> bool {
+        self.roles.is_empty() || roles.iter().any(|role| self.roles.contains(role))
+    }
+
+    /// Whether `votes` approve the order
+    pub fn is_met(&self, votes: &[ApprovalVote]) -> bool {
+        votes.len() >= self.approvals && self.roles.iter().all(|role| votes.iter().any(|vote| vote.roles.contains(role)))
+    }
+}
+
+/// An approval given towards a requirement that needs more than one
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApprovalVote {
+    pub approver: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub roles: Vec<String>,
+    pub at: DateTime<Utc>,
+}
+
+/// The approver an order pending approval currently waits for
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApprovalAssignment {
+    pub approver: String,
+    /// Position in the chain whose turn it is; a delegate acts for that position
+    pub level: usize,
+    pub assigned_at: DateTime<Utc>,
+    /// Approver who handed the approval over, when it was delegated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delegated_by: Option<String>,
+}
+
+/// Why an approval moved to its current assignee
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AssignmentReason {
+    /// The order became pending approval
+    Assigned,
+    /// The previous assignee handed it over
+    Delegated,
+    /// The previous assignee did not decide in time
+    Escalated,
+}
+
+impl AssignmentReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AssignmentReason::Assigned => "assigned",
+            AssignmentReason::Delegated => "delegated",
+            AssignmentReason::Escalated => "escalated",
+        }
+    }
+}
+
+/// A line the approver takes out of the order
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LineRejection {
+    /// Position of the line, counting from 1
+    pub line: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// What became of one line of an order approved with some lines rejected
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LineDisposition {
+    /// Position of the line in the order as submitted, counting from 1
+    pub line: usize,
+    pub item_code: String,
+    pub approved: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// The order without the `rejected` lines, with its totals recomputed, and the disposition of every line
+pub fn exclude_lines(po: &PurchaseOrder, rejected: &[LineRejection]) -> Result<(PurchaseOrder, Vec<LineDisposition>), String> {
+    if let Some(unknown) = rejected.iter().find(|rejection| rejection.line == 0 || rejection.line > po.items.len()) {
+        return Err(format!("Order {} has no line {}", po.po_number, unknown.line));
+    }
+
+    let mut kept = po.clone();
+    kept.items.clear();
+    let mut dispositions = Vec::with_capacity(po.items.len());
+    for (index, item) in po.items.iter().enumerate() {
+        let rejection = rejected.iter().find(|rejection| rejection.line == index + 1);
+        dispositions.push(LineDisposition {
+            line: index + 1,
+            item_code: item.item_code.clone(),
+            approved: rejection.is_none(),
+            reason: rejection.and_then(|rejection| rejection.reason.clone()),
+        });
+        if rejection.is_none() {
+            kept.items.push(item.clone());
+        }
+    }
+    kept.sub_total = round2(kept.items.iter().map(|item| item.line_total).sum());
+    kept.tax = round2(kept.sub_total * kept.tax_rate);
+    kept.grand_total = round2(kept.sub_total + kept.tax);
+    Ok((kept, dispositions))
+}
+
+/// Body of `POST /agent/task/{id}/approval`
+#[derive(Debug, Clone, Deserialize)]
+pub struct DecisionRequest {
+    pub approved: bool,
+    #[serde(default)]
+    pub comment: Option<String>,
+    /// Lines approved orders are to go ahead without
+    #[serde(default)]
+    pub rejected_lines: Vec<LineRejection>,
+    /// Who decides; ignored in favour of the authenticated subject
+    #[serde(default)]
+    pub approver: Option<String>,
+    /// Roles of the approver; ignored in favour of the authenticated roles
+    #[serde(default)]
+    pub roles: Vec<String>,
+}
+
+/// Body of `POST /agent/task/{id}/approval/delegate`
+#[derive(Debug, Clone, Deserialize)]
+pub struct DelegationRequest {
+    /// User taking over the approval
+    pub to: String,
+    /// Who delegates; ignored in favour of the authenticated subject
+    #[serde(default)]
+    pub approver: Option<String>,
+}
+
+/// Why an approval action was refused
+#[derive(Debug)]
+pub enum ApprovalError {
+    NotFound(String),
+    /// The order is not waiting for approval
+    NotPending(String),
+    /// The approval is assigned to someone else
+    NotAssignee(String),
+    /// The approver holds none of the roles the order needs
+    NotAuthorized(String),
+    Invalid(String),
+    Store(StoreError),
+    Other(String),
+}
+
+impl fmt::Display for ApprovalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApprovalError::NotFound(message)
+            | ApprovalError::NotPending(message)
+            | ApprovalError::NotAssignee(message)
+            | ApprovalError::NotAuthorized(message)
+            | ApprovalError::Invalid(message)
+            | ApprovalError::Other(message) => f.write_str(message),
+            ApprovalError::Store(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for ApprovalError {}
+
+impl From<StoreError> for ApprovalError {
+    fn from(e: StoreError) -> Self {
+        ApprovalError::Store(e)
+    }
+}
+
+/// Status and approval requirement of the order or expense report a task status carries
+fn outcome(status: &TaskStatus) -> Option<(String, Option<ApprovalRequirement>)> {
+    match ProcessingResult::from_status(status) {
+        Some(result) => Some((result.status, result.approval_requirement)),
+        None => ExpenseReview::from_status(status).map(|review| (review.status, review.approval_requirement)),
+    }
+}
+
+/// Whether a task status leaves its order or expense report waiting for an approver
+pub fn awaits_approval(status: &TaskStatus) -> bool {
+    outcome(status).is_some_and(|(status, _)| status == PENDING_APPROVAL)
+}
+
+/// Whether the task's order or expense report waits for an approver
+pub fn is_pending(record: &TaskRecord) -> bool {
+    awaits_approval(&record.task.status)
+}
+
+/// What the approval matrix required of the task's order or expense report when it was processed
+pub fn requirement(record: &TaskRecord) -> Option<ApprovalRequirement> {
+    outcome(&record.task.status)?.1
+}
+
+fn pending_record(agent: &PurchaseOrderAgent, task_id: &str) -> Result<TaskRecord, ApprovalError> {
+    let record = agent
+        .task_store()
+        .get(task_id)?
+        .ok_or_else(|| ApprovalError::NotFound(format!("Task {} not found", task_id)))?;
+    if !is_pending(&record) {
+        return Err(ApprovalError::NotPending(format!("Task {} is not pending approval", task_id)));
+    }
+    Ok(record)
+}
+
+fn has_voted(record: &TaskRecord, approver: &str) -> bool {
+    record.approvals.iter().any(|vote| vote.approver.eq_ignore_ascii_case(approver))
+}
+
+/// Refuse `approver` unless the approval is theirs to act on
+///
+/// Once the assignee has given their approval, anyone may add the remaining ones.
+fn ensure_assignee(record: &TaskRecord, approver: &str) -> Result<(), ApprovalError> {
+    match &record.approval {
+        Some(assignment) if !assignment.approver.eq_ignore_ascii_case(approver) && !has_voted(record, &assignment.approver) => Err(ApprovalError::NotAssignee(format!(
+            "Task {} is assigned to {}",
+            record.task.id, assignment.approver
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Hand the approval of `task_id` to `approver` and record the hop
+fn assign(
+    agent: &PurchaseOrderAgent,
+    task_id: &str,
+    approver: &str,
+    level: usize,
+    reason: AssignmentReason,
+    by: Option<&str>,
+) -> Result<ApprovalAssignment, ApprovalError> {
+    let assignment = ApprovalAssignment {
+        approver: approver.to_string(),
+        level,
+        assigned_at: Utc::now(),
+        delegated_by: by.filter(|_| reason == AssignmentReason::Delegated).map(str::to_string),
+    };
+    agent
+        .task_store()
+        .update(task_id, &mut |record| record.approval = Some(assignment.clone()))?
+        .ok_or_else(|| ApprovalError::NotFound(format!("Task {} not found", task_id)))?;
+    agent
+        .record_event(
+            task_id,
+            TaskEventKind::ApprovalAssigned { approver: approver.to_string(), level, reason, by: by.map(str::to_string) },
+        )
+        .map_err(|e| ApprovalError::Other(e.to_string()))?;
+    agent.metrics().increment(
+        "po_agent_approval_assignments_total",
+        "Approvals handed to an approver",
+        &[("reason", reason.as_str())],
+        1,
+    );
+    info!(task_id = %task_id, approver = %agent.redaction().log(approver), level, reason = reason.as_str(), "approval assigned");
+    Ok(assignment)
+}
+
+/// Assign a task that just became pending approval to the first approver of the chain
+///
+/// Does nothing without a chain, or when the task is already assigned.
+pub fn start(agent: &PurchaseOrderAgent, config: &ApprovalConfig, task_id: &str) -> Result<(), ApprovalError> {
+    let Some(first) = config.chain.first() else {
+        return Ok(());
+    };
+    let record = pending_record(agent, task_id)?;
+    if record.approval.is_none() {
+        assign(agent, task_id, first, 0, AssignmentReason::Assigned, None)?;
+    }
+    Ok(())
+}
+
+/// Record `approver`'s decision on an order pending approval
+///
+/// Once the order is assigned, only its assignee may decide. A rejection is
+/// final. When the matrix requires several approvals, an approval is counted
+/// and the order stays pending, handed to the next approver in the chain,
+/// until the requirement is met. `rejected_lines` are left out of the order
+/// by the approval that approves it.
+pub fn decide(
+    agent: &PurchaseOrderAgent,
+    task_id: &str,
+    approver: &str,
+    roles: &[String],
+    approved: bool,
+    comment: Option<String>,
+    rejected_lines: &[LineRejection],
+) -> Result<Task, ApprovalError> {
+    let record = pending_record(agent, task_id)?;
+    ensure_assignee(&record, approver)?;
+    if !rejected_lines.is_empty() {
+        if !approved {
+            return Err(ApprovalError::Invalid("Lines can only be rejected from an approved order".to_string()));
+        }
+        let po = agent.processed_purchase_order(&record).map_err(|e| ApprovalError::Other(e.to_string()))?;
+        exclude_lines(&po, rejected_lines).map_err(ApprovalError::Invalid)?;
+    }
+    let final_decision = |comment| {
+        agent.record_line_approval(task_id, approver, approved, comment, rejected_lines).map_err(|e| ApprovalError::Other(e.to_string()))
+    };
+    let Some(requirement) = requirement(&record).filter(|_| approved) else {
+        return final_decision(comment);
+    };
+
+    if !requirement.accepts(roles) {
+        return Err(ApprovalError::NotAuthorized(format!(
+            "Task {} needs an approver with one of the roles {}",
+            task_id,
+            requirement.roles.join(", ")
+        )));
+    }
+    if has_voted(&record, approver) {
+        return Err(ApprovalError::Invalid(format!("{} already approved task {}", approver, task_id)));
+    }
+    let vote = ApprovalVote { approver: approver.to_string(), roles: roles.to_vec(), at: Utc::now() };
+    let mut votes = record.approvals.clone();
+    votes.push(vote.clone());
+    if !rejected_lines.is_empty() && !requirement.is_met(&votes) {
+        return Err(ApprovalError::Invalid("Lines can only be rejected by the approval that completes the requirement".to_string()));
+    }
+    let updated = agent
+        .task_store()
+        .update(task_id, &mut |record| record.approvals.push(vote.clone()))?
+        .ok_or_else(|| ApprovalError::NotFound(format!("Task {} not found", task_id)))?;
+    agent
+        .record_event(task_id, TaskEventKind::ApprovalGranted { approver: approver.to_string(), roles: roles.to_vec() })
+        .map_err(|e| ApprovalError::Other(e.to_string()))?;
+
+    if requirement.is_met(&updated.approvals) {
+        return final_decision(comment);
+    }
+    info!(task_id = %task_id, approvals = updated.approvals.len(), needed = requirement.approvals, "approval counted");
+    if let Some(assignment) = &updated.approval
+        && let Some(next) = agent.approval_config().chain.get(assignment.level + 1)
+    {
+        assign(agent, task_id, next, assignment.level + 1, AssignmentReason::Assigned, Some(approver))?;
+    }
+    Ok(updated.task)
+}
+
+/// Hand an approval from its assignee `from` over to `to`
+///
+/// The delegate acts for the same position in the chain, so escalation carries on from there.
+pub fn delegate(agent: &PurchaseOrderAgent, task_id: &str, from: &str, to: &str) -> Result<ApprovalAssignment, ApprovalError> {
+    let to = to.trim();
+    if to.is_empty() {
+        return Err(ApprovalError::Invalid("Delegate must be named".to_string()));
+    }
+    if to.eq_ignore_ascii_case(from) {
+        return Err(ApprovalError::Invalid("An approval cannot be delegated to its assignee".to_string()));
+    }
+    let record = pending_record(agent, task_id)?;
+    ensure_assignee(&record, from)?;
+    let level = record.approval.map_or(0, |assignment| assignment.level);
+    assign(agent, task_id, to, level, AssignmentReason::Delegated, Some(from))
+}
+
+/// Escalate every assignment left undecided past `escalate_after` to the next approver
+///
+/// An assignment at the end of the chain stays where it is. Returns the number
+/// of approvals escalated.
+pub fn escalate_overdue(agent: &PurchaseOrderAgent, config: &ApprovalConfig, now: DateTime<Utc>) -> Result<usize, ApprovalError> {
+    let Some(escalate_after) = config.escalate_after else {
+        return Ok(0);
+    };
+    let mut escalated = 0;
+    for record in agent.task_store().list()? {
+        let Some(assignment) = &record.approval else { continue };
+        let Some(next) = config.chain.get(assignment.level + 1) else { continue };
+        let overdue = now.signed_duration_since(assignment.assigned_at).to_std().is_ok_and(|age| age >= escalate_after);
+        if overdue && is_pending(&record) {
+            assign(agent, &record.task.id, next, assignment.level + 1, AssignmentReason::Escalated, Some(&assignment.approver))?;
+            escalated += 1;
+        }
+    }
+    Ok(escalated)
+}
+
+/// Spawn the escalation timer; returns `None` when approvals never escalate
+pub fn spawn_escalator(agent: Arc<PurchaseOrderAgent>) -> Option<JoinHandle<()>> {
+    let config = agent.approval_config().clone();
+    if !config.is_enabled() || config.escalate_after.is_none() {
+        return None;
+    }
+
+    info!(approvers = config.chain.len(), escalate_after = ?config.escalate_after, "approval escalation started");
+
+    Some(tokio::spawn(async move {
+        let mut interval = tokio::time::interval(config.check_interval);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            match escalate_overdue(&agent, &config, Utc::now()) {
+                Ok(0) => {}
+                Ok(escalated) => info!(escalated, "overdue approvals escalated"),
+                Err(e) => warn!(error = %e, "approval escalation failed"),
+            }
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use a2a::{A2AProtocol, Message};
+
+    fn pending_order(department: &str, amount: f64) -> Message {
+        let mut po = crate::testing::order_of(0.0, &[("P-1", "Paper", 1, amount)]);
+        po.buyer_department = department.to_string();
+        (po.is_approved, po.approval_reason) = (false, None);
+        crate::testing::message(&po)
+    }
+
+    #[tokio::test]
+    async fn test_approvals_are_assigned_delegated_and_escalated() {
+        let config = ApprovalConfig {
+            chain: vec!["pat".to_string(), "lee".to_string()],
+            escalate_after: Some(Duration::from_secs(3600)),
+            ..ApprovalConfig::default()
+        };
+        let agent = PurchaseOrderAgent::new().with_approval(config.clone());
+        let task = agent.send_task(pending_order("Marketing", 10.0)).await.unwrap();
+        let assignment = agent.task_store().get(&task.id).unwrap().unwrap().approval.unwrap();
+        assert_eq!((assignment.approver.as_str(), assignment.level), ("pat", 0));
+
+        assert!(matches!(decide(&agent, &task.id, "lee", &[], true, None, &[]), Err(ApprovalError::NotAssignee(_))));
+        let delegated = delegate(&agent, &task.id, "Pat", "sam").unwrap();
+        assert_eq!((delegated.approver.as_str(), delegated.level, delegated.delegated_by.as_deref()), ("sam", 0, Some("Pat")));
+        assert!(matches!(delegate(&agent, &task.id, "pat", "kim"), Err(ApprovalError::NotAssignee(_))));
+
+        assert_eq!(escalate_overdue(&agent, &config, Utc::now()).unwrap(), 0);
+        let later = Utc::now() + chrono::Duration::hours(2);
+        assert_eq!(escalate_overdue(&agent, &config, later).unwrap(), 1);
+        assert_eq!(escalate_overdue(&agent, &config, later + chrono::Duration::hours(2)).unwrap(), 0, "end of the chain");
+
+        let task = decide(&agent, &task.id, "lee", &[], true, Some("ok".to_string()), &[]).unwrap();
+        assert_eq!(ProcessingResult::from_status(&task.status).unwrap().status, "APPROVED");
+        assert!(matches!(decide(&agent, &task.id, "lee", &[], true, None, &[]), Err(ApprovalError::NotPending(_))));
+
+        let hops: Vec<_> = agent
+            .event_log()
+            .events(&task.id)
+            .unwrap()
+            .into_iter()
+            .filter_map(|event| match event.kind {
+                TaskEventKind::ApprovalAssigned { approver, reason, .. } => Some((approver, reason)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            hops,
+            [
+                ("pat".to_string(), AssignmentReason::Assigned),
+                ("sam".to_string(), AssignmentReason::Delegated),
+                ("lee".to_string(), AssignmentReason::Escalated),
+            ]
+        );
+        assert_eq!(agent.metrics().value("po_agent_approval_assignments_total", &[("reason", "escalated")]), Some(1.0));
+    }
+
+    #[tokio::test]
+    async fn test_matrix_decides_required_approvers() {
+        let roles = |roles: &[&str]| roles.iter().map(|role| role.to_string()).collect::<Vec<_>>();
+        let config = ApprovalConfig {
+            chain: vec!["pat".to_string(), "lee".to_string()],
+            matrix: vec![
+                ApprovalRule { name: Some("small".to_string()), max_amount: Some(1000.0), approvals: 0, ..ApprovalRule::default() },
+                ApprovalRule {
+                    name: Some("it-capital".to_string()),
+                    departments: vec!["it".to_string()],
+                    min_amount: 1000.0,
+                    roles: roles(&["manager", "finance"]),
+                    approvals: 2,
+                    ..ApprovalRule::default()
+                },
+            ],
+            ..ApprovalConfig::default()
+        };
+        let agent = PurchaseOrderAgent::new().with_approval(config);
+
+        let small = agent.send_task(pending_order("Marketing", 10.0)).await.unwrap();
+        let result = ProcessingResult::from_status(&small.status).unwrap();
+        assert_eq!(result.status, "APPROVED");
+        assert!(result.summary.is_approved);
+        assert_eq!(result.approval_requirement.unwrap().rule.as_deref(), Some("small"));
+
+        let other = agent.send_task(pending_order("Sales", 5000.0)).await.unwrap();
+        let result = ProcessingResult::from_status(&other.status).unwrap();
+        assert_eq!(result.status, PENDING_APPROVAL);
+        assert_eq!(result.approval_requirement.unwrap(), ApprovalRequirement { rule: None, roles: Vec::new(), approvals: 1 });
+
+        let task = agent.send_task(pending_order("IT", 5000.0)).await.unwrap();
+        assert!(matches!(decide(&agent, &task.id, "pat", &roles(&["buyer"]), true, None, &[]), Err(ApprovalError::NotAuthorized(_))));
+        let task = decide(&agent, &task.id, "pat", &roles(&["manager"]), true, None, &[]).unwrap();
+        assert_eq!(ProcessingResult::from_status(&task.status).unwrap().status, PENDING_APPROVAL);
+        let record = agent.task_store().get(&task.id).unwrap().unwrap();
+        assert_eq!(record.approval.unwrap().approver, "lee");
+        assert!(matches!(decide(&agent, &task.id, "pat", &roles(&["finance"]), true, None, &[]), Err(ApprovalError::NotAssignee(_))));
+
+        let task = decide(&agent, &task.id, "lee", &roles(&["finance"]), true, Some("ok".to_string()), &[]).unwrap();
+        assert_eq!(ProcessingResult::from_status(&task.status).unwrap().status, "APPROVED");
+        let granted = agent.event_log().events(&task.id).unwrap().iter().filter(|event| event.kind.name() == "approval_granted").count();
+        assert_eq!(granted, 2);
+    }
+
+    #[tokio::test]
+    async fn test_rejected_lines_are_excluded_and_totals_recomputed() {
+        let mut po = crate::testing::order_of(0.1, &[("P-1", "Paper", 1, 10.0), ("C-9", "Chair", 2, 45.0)]);
+        (po.is_approved, po.approval_reason) = (false, None);
+        let message = crate::testing::message(&po);
+        let agent = PurchaseOrderAgent::new();
+        let task = agent.send_task(message).await.unwrap();
+
+        let chair = LineRejection { line: 2, reason: Some("Furniture is out of policy".to_string()) };
+        let unknown = LineRejection { line: 3, reason: None };
+        assert!(matches!(decide(&agent, &task.id, "pat", &[], true, None, &[unknown]), Err(ApprovalError::Invalid(_))));
+        assert!(matches!(decide(&agent, &task.id, "pat", &[], false, None, std::slice::from_ref(&chair)), Err(ApprovalError::Invalid(_))));
+
+        let task = decide(&agent, &task.id, "pat", &[], true, None, std::slice::from_ref(&chair)).unwrap();
+        let result = ProcessingResult::from_status(&task.status).unwrap();
+        assert_eq!(result.status, "APPROVED");
+        assert_eq!((result.sub_total, result.tax, result.grand_total), (10.0, 1.0, 11.0));
+        assert_eq!(result.summary.total_items, 1);
+        assert_eq!(result.normalized_order.unwrap().items[0].item_code, "P-1");
+        assert_eq!(
+            result.line_dispositions,
+            [
+                LineDisposition { line: 1, item_code: "P-1".to_string(), approved: true, reason: None },
+                LineDisposition { line: 2, item_code: "C-9".to_string(), approved: false, reason: chair.reason },
+            ]
+        );
+        let events = agent.event_log().events(&task.id).unwrap();
+        assert!(events.iter().any(|event| matches!(&event.kind, TaskEventKind::ApprovalRecorded { rejected_lines, .. } if *rejected_lines == [2])));
+    }
+}
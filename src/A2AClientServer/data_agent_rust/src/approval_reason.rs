@@ -0,0 +1,253 @@
+//! Why an order was approved, held or refused
+//!
+//! The processing result explains its status with the rules that decided it,
+//! not with the `approvalReason` the client sent: whether validation passed,
+//! the grand total against its high-value threshold, whether the buyer
+//! department is in the directory and the supplier in the supplier master
+//! data, and what the approval matrix or the order's own approval flag
+//! required. Each reason has a stable `code` for machines and a `message` in
+//! English, and `approval_reason` joins the messages into one sentence. An
+//! approver's decision adds a reason of its own.
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::agent::{ProcessingResult, PurchaseOrder, PurchaseOrderAgent};
+use crate::approval::ApprovalRequirement;
+
+/// A rule that fired while the order was evaluated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReasonCode {
+    ValidationFailed,
+    WithinThreshold,
+    AboveThreshold,
+    DepartmentAuthorized,
+    DepartmentUnknown,
+    SupplierKnown,
+    SupplierUnknown,
+    /// The approval matrix approves the order without an approver
+    MatrixAutomatic,
+    /// The approval matrix requires approvers
+    MatrixApprovers,
+    /// Without a matrix, the order's `isApproved` flag decides
+    OrderApproved,
+    OrderNotApproved,
+    ApproverApproved,
+    ApproverRejected,
+}
+
+impl ReasonCode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ReasonCode::ValidationFailed => "validation_failed",
+            ReasonCode::WithinThreshold => "within_threshold",
+            ReasonCode::AboveThreshold => "above_threshold",
+            ReasonCode::DepartmentAuthorized => "department_authorized",
+            ReasonCode::DepartmentUnknown => "department_unknown",
+            ReasonCode::SupplierKnown => "supplier_known",
+            ReasonCode::SupplierUnknown => "supplier_unknown",
+            ReasonCode::MatrixAutomatic => "matrix_automatic",
+            ReasonCode::MatrixApprovers => "matrix_approvers",
+            ReasonCode::OrderApproved => "order_approved",
+            ReasonCode::OrderNotApproved => "order_not_approved",
+            ReasonCode::ApproverApproved => "approver_approved",
+            ReasonCode::ApproverRejected => "approver_rejected",
+        }
+    }
+}
+
+/// One reason of a result, in code and in words
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApprovalReason {
+    pub code: ReasonCode,
+    pub message: String,
+}
+
+impl ApprovalReason {
+    fn new(code: ReasonCode, message: String) -> Self {
+        Self { code, message }
+    }
+}
+
+/// The reasons behind the status of `po`, in the order the rules are applied
+pub fn reasons(
+    agent: &PurchaseOrderAgent,
+    po: &PurchaseOrder,
+    error_count: usize,
+    requirement: Option<&ApprovalRequirement>,
+) -> Vec<ApprovalReason> {
+    let mut reasons = Vec::new();
+    if error_count > 0 {
+        let noun = if error_count == 1 { "error" } else { "errors" };
+        reasons.push(ApprovalReason::new(ReasonCode::ValidationFailed, format!("validation found {} {}", error_count, noun)));
+    }
+
+    let threshold = agent.high_value_config().threshold(&po.buyer_department, po.currency.as_deref());
+    let scope = threshold.department.as_ref().map(|department| format!(" for {}", department)).unwrap_or_default();
+    reasons.push(if po.grand_total <= threshold.amount {
+        ApprovalReason::new(
+            ReasonCode::WithinThreshold,
+            format!(
+                "grand total {:.2} {} is within the {:.2} {} threshold{}",
+                po.grand_total, threshold.currency, threshold.amount, threshold.currency, scope
+            ),
+        )
+    } else {
+        ApprovalReason::new(
+            ReasonCode::AboveThreshold,
+            format!(
+                "grand total {:.2} {} exceeds the {:.2} {} threshold{}",
+                po.grand_total, threshold.currency, threshold.amount, threshold.currency, scope
+            ),
+        )
+    });
+
+    reasons.push(match agent.directory().department(&po.buyer_department) {
+        Some(department) => {
+            ApprovalReason::new(ReasonCode::DepartmentAuthorized, format!("buyer department '{}' is authorized", department))
+        }
+        None => ApprovalReason::new(
+            ReasonCode::DepartmentUnknown,
+            format!("buyer department '{}' is not in the directory", po.buyer_department),
+        ),
+    });
+
+    // A failing lookup leaves the supplier out of the reasons rather than failing the order
+    match agent.suppliers().get(&po.supplier_name) {
+        Ok(Some(_)) => reasons.push(ApprovalReason::new(
+            ReasonCode::SupplierKnown,
+            format!("supplier '{}' is in the supplier master data", po.supplier_name),
+        )),
+        Ok(None) => reasons.push(ApprovalReason::new(
+            ReasonCode::SupplierUnknown,
+            format!("supplier '{}' is not in the supplier master data", po.supplier_name),
+        )),
+        Err(e) => warn!(po_number = %po.po_number, error = %e, "supplier lookup failed while explaining approval"),
+    }
+
+    if error_count > 0 {
+        return reasons;
+    }
+    reasons.push(match requirement {
+        Some(requirement) => {
+            let band = match &requirement.rule {
+                Some(rule) => format!("approval matrix band '{}'", rule),
+                None => "no approval matrix band covers it, so the default".to_string(),
+            };
+            if requirement.is_automatic() {
+                ApprovalReason::new(ReasonCode::MatrixAutomatic, format!("{} approves it automatically", band))
+            } else {
+                let noun = if requirement.approvals == 1 { "approver" } else { "approvers" };
+                let roles = match requirement.roles.as_slice() {
+                    [] => String::new(),
+                    roles => format!(" including {}", join(roles)),
+                };
+                ApprovalReason::new(
+                    ReasonCode::MatrixApprovers,
+                    format!("{} requires {} {}{}", band, requirement.approvals, noun, roles),
+                )
+            }
+        }
+        None if po.is_approved => ApprovalReason::new(ReasonCode::OrderApproved, "the order is marked approved".to_string()),
+        None => ApprovalReason::new(ReasonCode::OrderNotApproved, "the order is not marked approved".to_string()),
+    });
+    reasons
+}
+
+/// `items` joined as an English list
+fn join(items: &[String]) -> String {
+    match items {
+        [] => String::new(),
+        [only] => only.clone(),
+        [first, second] => format!("{} and {}", first, second),
+        [init @ .., last] => format!("{}, and {}", init.join(", "), last),
+    }
+}
+
+/// One sentence giving a result's status and its reasons
+pub fn sentence(status: &str, reasons: &[ApprovalReason]) -> String {
+    let verdict = match status {
+        "APPROVED" => "Approved",
+        "PENDING_APPROVAL" => "Pending approval",
+        "VALIDATION_FAILED" => "Not approved",
+        "REJECTED" => "Rejected",
+        other => other,
+    };
+    let messages: Vec<String> = reasons.iter().map(|reason| reason.message.clone()).collect();
+    if messages.is_empty() {
+        return format!("{}.", verdict);
+    }
+    format!("{}: {}.", verdict, join(&messages))
+}
+
+/// Record an approver's decision among the reasons of `result`, whose status is already decided
+pub fn record_decision(result: &mut ProcessingResult, approver: &str, approved: bool) {
+    result.approval_reasons.push(if approved {
+        ApprovalReason::new(ReasonCode::ApproverApproved, format!("approved by {}", approver))
+    } else {
+        ApprovalReason::new(ReasonCode::ApproverRejected, format!("rejected by {}", approver))
+    });
+    result.approval_reason = Some(sentence(&result.status, &result.approval_reasons));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::approval::{ApprovalConfig, ApprovalRule};
+    use crate::catalog::Supplier;
+
+    fn codes(result: &ProcessingResult) -> Vec<&'static str> {
+        result.approval_reasons.iter().map(|reason| reason.code.name()).collect()
+    }
+
+    #[test]
+    fn test_reasons_follow_the_rules_that_fired() {
+        let agent = PurchaseOrderAgent::new();
+        let mut po = crate::testing::valid_order();
+        po.approval_reason = Some("Trust me".to_string());
+        let result = agent.evaluate_purchase_order(&po);
+        assert_eq!(codes(&result), ["within_threshold", "department_authorized", "supplier_unknown", "order_approved"]);
+        assert_eq!(
+            result.approval_reason.as_deref(),
+            Some(
+                "Approved: grand total 86.60 USD is within the 10000.00 USD threshold, buyer department 'Marketing' is authorized, \
+                 supplier 'Acme Office Supply' is not in the supplier master data, and the order is marked approved."
+            )
+        );
+
+        let mut empty = crate::testing::unauthorized_department_order();
+        empty.items.clear();
+        let failed = agent.evaluate_purchase_order(&empty);
+        assert_eq!(codes(&failed)[..2], ["validation_failed", "within_threshold"]);
+        assert!(failed.approval_reason.unwrap().starts_with("Not approved: validation found"));
+
+        let approval = ApprovalConfig {
+            matrix: vec![ApprovalRule {
+                name: Some("standard".to_string()),
+                roles: vec!["manager".to_string()],
+                approvals: 1,
+                ..ApprovalRule::default()
+            }],
+            ..ApprovalConfig::default()
+        };
+        let agent = PurchaseOrderAgent::new().with_approval(approval);
+        let supplier = Supplier {
+            name: po.supplier_name.clone(),
+            address_line1: po.supplier_address_line1.clone(),
+            address_line2: None,
+            city: po.supplier_city.clone(),
+            state: po.supplier_state.clone(),
+            postal_code: po.supplier_postal_code.clone(),
+            country: po.supplier_country.clone(),
+            tax_id: None,
+            bank_account: None,
+        };
+        agent.suppliers().put(supplier).unwrap();
+        let mut result = agent.evaluate_purchase_order(&po);
+        assert_eq!(codes(&result)[2..], ["supplier_known", "matrix_approvers"]);
+        result.status = "APPROVED".to_string();
+        record_decision(&mut result, "alice", true);
+        assert!(result.approval_reason.unwrap().ends_with("approval matrix band 'standard' requires 1 approver including manager, and approved by alice."));
+    }
+}
@@ -0,0 +1,162 @@
+use a2a::{Artifact, Part, TaskStatus};
+use serde::Deserialize;
+
+use crate::agent::ProcessingResult;
+use crate::request_options::OutputFormat;
+
+/// Header of the CSV artifact, naming the columns of the CSV row
+pub const CSV_HEADER: &str = "PONumber,Subtotal,Tax,GrandTotal,SupplierName,BuyerDepartment,Notes";
+
+/// Artifacts of a task with this status
+///
+/// A processing result yields a CSV artifact (header and row), a JSON artifact
+/// with the full result, and a JSON artifact per output plugin. Other skills' results yield a JSON artifact of their Data
+/// part. A status without a Data part, such as a failure message, has none.
+pub fn from_status(status: &TaskStatus) -> Option<Vec<Artifact>> {
+    let parts = &status.message.as_ref()?.parts;
+    let data = parts.iter().find_map(|part| match part {
+        Part::Data { data } => Some(data),
+        _ => None,
+    })?;
+
+    let mut artifacts = Vec::new();
+    if let Ok(result) = ProcessingResult::deserialize(data) {
+        let row = parts.iter().find_map(|part| match part {
+            Part::Text { text } => Some(text.as_str()),
+            _ => None,
+        });
+        if let Some(row) = row {
+            artifacts.push(Artifact {
+                name: Some(format!("{}.csv", file_stem(&result.po_number))),
+                description: Some("Processed purchase order as CSV".to_string()),
+                parts: vec![Part::Text { text: format!("{}\n{}\n", CSV_HEADER, row) }],
+            });
+        }
+        artifacts.push(Artifact {
+            name: Some(format!("{}.json", file_stem(&result.po_number))),
+            description: Some("Detailed processing result".to_string()),
+            parts: vec![Part::Data { data: data.clone() }],
+        });
+        for (plugin, output) in &result.plugin_outputs {
+            artifacts.push(Artifact {
+                name: Some(format!("{}.{}.json", file_stem(&result.po_number), file_stem(plugin))),
+                description: Some(format!("Output of the {} plugin", plugin)),
+                parts: vec![Part::Data { data: output.clone() }],
+            });
+        }
+    } else {
+        artifacts.push(Artifact {
+            name: Some("result.json".to_string()),
+            description: Some("Skill result".to_string()),
+            parts: vec![Part::Data { data: data.clone() }],
+        });
+    }
+    Some(artifacts)
+}
+
+/// The `artifacts` in one of `formats`, or all of them when `formats` is empty
+pub fn select(artifacts: Option<Vec<Artifact>>, formats: &[OutputFormat]) -> Option<Vec<Artifact>> {
+    if formats.is_empty() {
+        return artifacts;
+    }
+    artifacts.map(|artifacts| artifacts.into_iter().filter(|artifact| formats.iter().any(|format| format.matches(artifact))).collect())
+}
+
+/// File name stem for a PO number, keeping it safe to save as-is
+fn file_stem(po_number: &str) -> String {
+    let stem: String = po_number
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') { c } else { '_' })
+        .collect();
+    if stem.is_empty() { "purchase-order".to_string() } else { stem }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blob::{BlobStore, MemoryBlobStore, PresignedUrl};
+    use crate::store::StoreResult;
+    use crate::{create_router, PurchaseOrderAgent};
+    use a2a::{A2AProtocol, Message};
+    use axum::body::{to_bytes, Body};
+    use axum::http::{Request, StatusCode};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_processed_task_has_csv_and_json_artifacts() {
+        let agent = Arc::new(PurchaseOrderAgent::new());
+        let mut po = crate::testing::order_of(0.0, &[("P-1", "Paper", 1, 10.0)]);
+        po.po_number = "PO/7".to_string();
+        let task = agent
+            .send_task(Message { role: "user".to_string(), parts: vec![Part::Data { data: serde_json::to_value(po).unwrap() }] })
+            .await
+            .unwrap();
+
+        let artifacts = task.artifacts.unwrap();
+        let names: Vec<_> = artifacts.iter().filter_map(|a| a.name.as_deref()).collect();
+        assert_eq!(names, ["PO_7.csv", "PO_7.json"]);
+        let Part::Text { text } = &artifacts[0].parts[0] else { panic!("CSV artifact is text") };
+        assert!(text.starts_with(CSV_HEADER));
+        assert!(text.contains("\nPO/7,10,0,10,Acme Office Supply,Marketing,\"\"\n"));
+
+        let app = create_router(agent);
+        let get = |uri: String| Request::builder().uri(uri).body(Body::empty()).unwrap();
+        let response = app.clone().oneshot(get(format!("/agent/task/{}/artifacts/1", task.id))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: serde_json::Value = serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await.unwrap()).unwrap();
+        assert_eq!(body["name"], "PO_7.json");
+        assert_eq!(body["parts"][0]["data"]["status"], "APPROVED");
+
+        let response = app.clone().oneshot(get(format!("/agent/task/{}/artifacts/2", task.id))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let response = app.clone().oneshot(get("/agent/task/missing/artifacts/0".to_string())).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        // Without a blob store there is nothing to presign
+        let response = app.oneshot(get(format!("/agent/task/{}/artifacts/1?presign=true", task.id))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_IMPLEMENTED);
+    }
+
+    /// Memory blobs with URLs naming their key
+    struct PresigningBlobStore(MemoryBlobStore);
+
+    impl BlobStore for PresigningBlobStore {
+        fn name(&self) -> &str {
+            "presigning"
+        }
+
+        fn put(&self, key: &str, content: &[u8]) -> StoreResult<()> {
+            self.0.put(key, content)
+        }
+
+        fn get(&self, key: &str) -> StoreResult<Option<Vec<u8>>> {
+            self.0.get(key)
+        }
+
+        fn delete(&self, key: &str) -> StoreResult<()> {
+            self.0.delete(key)
+        }
+
+        fn presign(&self, key: &str, _expires_in: std::time::Duration) -> StoreResult<Option<String>> {
+            Ok(Some(format!("https://blobs.example.com/{}", key)))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_artifacts_are_published_for_presigned_download() {
+        let blobs = Arc::new(PresigningBlobStore(MemoryBlobStore::new()));
+        let agent = Arc::new(PurchaseOrderAgent::new().with_blob_store(blobs.clone()));
+        let task = agent.send_task(crate::testing::message(&crate::testing::valid_order())).await.unwrap();
+
+        let app = create_router(agent);
+        let uri = format!("/agent/task/{}/artifacts/1?presign=true", task.id);
+        let response = app.oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: PresignedUrl = serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await.unwrap()).unwrap();
+        let key = format!("artifacts/{}/1.json", task.id);
+        assert_eq!(body.url, format!("https://blobs.example.com/{}", key));
+        assert!(body.expires_at > chrono::Utc::now());
+        let published: serde_json::Value = serde_json::from_slice(&blobs.get(&key).unwrap().unwrap()).unwrap();
+        assert_eq!(published["name"], "PO-1.json");
+    }
+}
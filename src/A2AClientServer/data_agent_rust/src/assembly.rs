@@ -0,0 +1,144 @@
+//! Purchase orders sent in chunks
+//!
+//! Large orders from an ERP arrive split up: a header, the order without all
+//! of its items, then Data parts of `{"purchaseOrderItems": [...]}` holding the
+//! rest. Within one message, the items of every such part are appended to the
+//! header's, in order, before the order is validated.
+//!
+//! Chunks can also span several messages of a session. A message with
+//! `"moreItems": true` in a Data part, next to the purchase order or its items,
+//! is held: its task waits for input, and the next messages sent with the same
+//! `session_id` add their parts to its request. The first message without
+//! `moreItems` completes the order, which is then processed as one task.
+
+use a2a::{Message, Part};
+use dashmap::DashMap;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::agent::{PurchaseOrder, PurchaseOrderItem};
+use crate::ingest::WRAPPER_KEY;
+
+/// Key of a Data part holding more items of the purchase order
+pub const ITEMS_KEY: &str = "purchaseOrderItems";
+
+/// Key flagging that more messages of the order follow in the session
+pub const MORE_KEY: &str = "moreItems";
+
+/// Whether a part holds items of a purchase order rather than the order itself
+pub fn is_items(part: &Part) -> bool {
+    matches!(part, Part::Data { data } if data.get(ITEMS_KEY).is_some())
+}
+
+/// Whether a message says more messages of its purchase order follow
+pub fn has_more(message: &Message) -> bool {
+    message.parts.iter().any(|part| match part {
+        Part::Data { data } => data.get(MORE_KEY).and_then(Value::as_bool).unwrap_or(false),
+        _ => false,
+    })
+}
+
+/// Items of a message, counted without parsing them: the header's and those of its item parts
+pub fn item_count(message: &Message) -> usize {
+    let count = |items: Option<&Value>| items.and_then(Value::as_array).map_or(0, Vec::len);
+    message
+        .parts
+        .iter()
+        .map(|part| match part {
+            Part::Data { data } if is_items(part) => count(data.get(ITEMS_KEY)),
+            Part::Data { data } => count(data.get(WRAPPER_KEY).unwrap_or(data).get("items")),
+            _ => 0,
+        })
+        .sum()
+}
+
+/// `po` with the items of every item part of `message` appended
+pub fn append_items(mut po: PurchaseOrder, message: &Message) -> Result<PurchaseOrder, String> {
+    for (index, part) in message.parts.iter().enumerate() {
+        let Part::Data { data } = part else { continue };
+        let Some(items) = data.get(ITEMS_KEY) else { continue };
+        let items = Vec::<PurchaseOrderItem>::deserialize(items)
+            .map_err(|e| format!("part {}: invalid purchase order items: {}", index + 1, e))?;
+        po.items.extend(items);
+    }
+    Ok(po)
+}
+
+/// Tasks waiting for more messages of their purchase order, by session
+#[derive(Default)]
+pub struct Assemblies {
+    pending: DashMap<String, String>,
+}
+
+impl Assemblies {
+    /// Task of the order being assembled in `session_id`
+    pub fn pending(&self, session_id: &str) -> Option<String> {
+        self.pending.get(session_id).map(|task_id| task_id.clone())
+    }
+
+    pub(crate) fn hold(&self, session_id: &str, task_id: &str) {
+        self.pending.insert(session_id.to_string(), task_id.to_string());
+    }
+
+    pub(crate) fn release(&self, session_id: &str) {
+        self.pending.remove(session_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::{ProcessingResult, PurchaseOrderAgent, TaskOptions};
+    use a2a::{A2AProtocol, TaskState};
+    use serde_json::json;
+
+    fn data(data: Value) -> Message {
+        Message { role: "user".to_string(), parts: vec![Part::Data { data }] }
+    }
+
+    #[tokio::test]
+    async fn test_items_in_later_parts_are_appended() {
+        let agent = PurchaseOrderAgent::new();
+        let po = crate::testing::valid_order();
+        let mut header = po.clone();
+        let toner = header.items.pop().unwrap();
+        let mut message = crate::testing::message(&header);
+        message.parts.push(Part::Data { data: json!({ ITEMS_KEY: [toner] }) });
+        assert_eq!(item_count(&message), 2);
+
+        let task = agent.send_task(message.clone()).await.unwrap();
+        assert!(matches!(task.status.state, TaskState::Completed));
+        assert_eq!(ProcessingResult::from_status(&task.status).unwrap().summary.total_items, 2);
+
+        message.parts.push(Part::Data { data: json!({ ITEMS_KEY: [{ "itemCode": "X" }] }) });
+        let err = agent.send_task(message).await.unwrap_err();
+        assert!(err.to_string().contains("part 3: invalid purchase order items"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_orders_are_assembled_across_a_session() {
+        let agent = PurchaseOrderAgent::new();
+        let po = crate::testing::valid_order();
+        let mut header = serde_json::to_value(&po).unwrap();
+        header["items"] = json!([po.items[0]]);
+        let options = TaskOptions { session_id: Some("erp-1".to_string()), ..TaskOptions::default() };
+
+        let first = agent.send_task_with_options(data(json!({ WRAPPER_KEY: header, MORE_KEY: true })), &options).await.unwrap();
+        assert!(matches!(first.status.state, TaskState::InputRequired));
+        assert_eq!(first.session_id.as_deref(), Some("erp-1"));
+        assert_eq!(agent.assemblies().pending("erp-1").as_deref(), Some(first.id.as_str()));
+
+        let last = agent.send_task_with_options(data(json!({ ITEMS_KEY: [po.items[1]] })), &options).await.unwrap();
+        assert_eq!(last.id, first.id);
+        assert!(matches!(last.status.state, TaskState::Completed), "{:?}", last.status);
+        assert_eq!(ProcessingResult::from_status(&last.status).unwrap().summary.total_items, 2);
+        assert!(agent.assemblies().pending("erp-1").is_none());
+
+        // The assembled request is rebuilt from the event stream
+        let rebuilt = crate::events::rebuild(&agent.event_log().events(&first.id).unwrap()).unwrap();
+        assert_eq!(rebuilt.request.parts.len(), 2);
+
+        let err = agent.send_task(data(json!({ WRAPPER_KEY: po, MORE_KEY: true }))).await.unwrap_err();
+        assert!(err.to_string().contains("session_id"), "{}", err);
+    }
+}
@@ -0,0 +1,346 @@
+//! Supporting documents attached to tasks
+//!
+//! Quotes, contracts and other backup approvers ask for are attached to a task
+//! after it was submitted, either as A2A File parts or as a raw upload. The
+//! files are kept in an attachment store next to the task, listed with the
+//! task's details and served back for download.
+//!
+//! With a blob store configured, the content of each file goes there instead,
+//! under `attachments/{task_id}/{attachment_id}`, and the download endpoint can
+//! answer with a presigned URL of it.
+
+use a2a::{FileContent, Part};
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::agent::PurchaseOrderAgent;
+use crate::blob::BlobStore;
+use crate::events::TaskEventKind;
+use crate::store::{StoreError, StoreResult};
+
+/// Limits on attached files
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct AttachmentsConfig {
+    /// Largest file accepted, in bytes
+    pub max_bytes: usize,
+}
+
+impl Default for AttachmentsConfig {
+    fn default() -> Self {
+        Self { max_bytes: 10 * 1024 * 1024 }
+    }
+}
+
+/// A file attached to a task; its content is fetched separately
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Attachment {
+    pub id: String,
+    pub task_id: String,
+    pub name: String,
+    pub mime_type: String,
+    pub size: usize,
+    /// Hex SHA-256 of the content
+    pub sha256: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uploaded_by: Option<String>,
+    pub attached_at: DateTime<Utc>,
+}
+
+/// A file to attach
+#[derive(Debug, Clone)]
+pub struct NewAttachment {
+    pub name: String,
+    pub mime_type: String,
+    pub bytes: Vec<u8>,
+}
+
+/// Body of `POST /agent/task/{id}/attachments` when it is JSON
+#[derive(Debug, Clone, Deserialize)]
+pub struct AttachRequest {
+    /// A2A File parts carrying base64 `bytes`
+    pub parts: Vec<Part>,
+}
+
+/// Storage of attached files
+pub trait AttachmentStore: Send + Sync {
+    fn put(&self, attachment: Attachment, content: Vec<u8>) -> StoreResult<()>;
+
+    /// Attachments of a task, oldest first
+    fn for_task(&self, task_id: &str) -> StoreResult<Vec<Attachment>>;
+
+    /// One attachment of a task and its content
+    fn content(&self, task_id: &str, attachment_id: &str) -> StoreResult<Option<(Attachment, Vec<u8>)>>;
+
+    /// Drop the attachments of tasks not matching `keep`, returning how many tasks were dropped
+    fn retain_tasks(&self, keep: &dyn Fn(&str) -> bool) -> StoreResult<usize>;
+
+    /// Key of an attachment's content in the agent's blob store, when this store keeps it there
+    fn blob_key(&self, _task_id: &str, _attachment_id: &str) -> Option<String> {
+        None
+    }
+}
+
+/// Blob key the content of an attachment is kept or published under
+pub fn content_key(task_id: &str, attachment_id: &str) -> String {
+    format!("attachments/{}/{}", task_id, attachment_id)
+}
+
+/// In-memory attachment store used by default
+#[derive(Default)]
+pub struct MemoryAttachmentStore {
+    attachments: DashMap<String, Vec<(Attachment, Vec<u8>)>>,
+}
+
+impl MemoryAttachmentStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl AttachmentStore for MemoryAttachmentStore {
+    fn put(&self, attachment: Attachment, content: Vec<u8>) -> StoreResult<()> {
+        self.attachments.entry(attachment.task_id.clone()).or_default().push((attachment, content));
+        Ok(())
+    }
+
+    fn for_task(&self, task_id: &str) -> StoreResult<Vec<Attachment>> {
+        let mut attachments: Vec<_> = self
+            .attachments
+            .get(task_id)
+            .map(|stored| stored.iter().map(|(attachment, _)| attachment.clone()).collect())
+            .unwrap_or_default();
+        attachments.sort_by_key(|attachment| attachment.attached_at);
+        Ok(attachments)
+    }
+
+    fn content(&self, task_id: &str, attachment_id: &str) -> StoreResult<Option<(Attachment, Vec<u8>)>> {
+        Ok(self
+            .attachments
+            .get(task_id)
+            .and_then(|stored| stored.iter().find(|(attachment, _)| attachment.id == attachment_id).cloned()))
+    }
+
+    fn retain_tasks(&self, keep: &dyn Fn(&str) -> bool) -> StoreResult<usize> {
+        let before = self.attachments.len();
+        self.attachments.retain(|task_id, _| keep(task_id));
+        Ok(before - self.attachments.len())
+    }
+}
+
+/// Attachment store keeping file content in a blob store and the details in memory
+pub struct BlobAttachmentStore {
+    attachments: DashMap<String, Vec<Attachment>>,
+    blobs: Arc<dyn BlobStore>,
+}
+
+impl BlobAttachmentStore {
+    pub fn new(blobs: Arc<dyn BlobStore>) -> Self {
+        Self { attachments: DashMap::new(), blobs }
+    }
+}
+
+impl AttachmentStore for BlobAttachmentStore {
+    fn put(&self, attachment: Attachment, content: Vec<u8>) -> StoreResult<()> {
+        self.blobs.put(&content_key(&attachment.task_id, &attachment.id), &content)?;
+        self.attachments.entry(attachment.task_id.clone()).or_default().push(attachment);
+        Ok(())
+    }
+
+    fn for_task(&self, task_id: &str) -> StoreResult<Vec<Attachment>> {
+        let mut attachments = self.attachments.get(task_id).map(|stored| stored.clone()).unwrap_or_default();
+        attachments.sort_by_key(|attachment| attachment.attached_at);
+        Ok(attachments)
+    }
+
+    fn content(&self, task_id: &str, attachment_id: &str) -> StoreResult<Option<(Attachment, Vec<u8>)>> {
+        let Some(attachment) = self
+            .attachments
+            .get(task_id)
+            .and_then(|stored| stored.iter().find(|attachment| attachment.id == attachment_id).cloned())
+        else {
+            return Ok(None);
+        };
+        let key = content_key(task_id, attachment_id);
+        let content = self.blobs.get(&key)?.ok_or_else(|| StoreError(format!("Blob {} is missing", key)))?;
+        Ok(Some((attachment, content)))
+    }
+
+    fn retain_tasks(&self, keep: &dyn Fn(&str) -> bool) -> StoreResult<usize> {
+        let dropped: Vec<String> = self.attachments.iter().map(|entry| entry.key().clone()).filter(|task_id| !keep(task_id)).collect();
+        for task_id in &dropped {
+            if let Some((_, attachments)) = self.attachments.remove(task_id) {
+                for attachment in attachments {
+                    self.blobs.delete(&content_key(task_id, &attachment.id))?;
+                }
+            }
+        }
+        Ok(dropped.len())
+    }
+
+    fn blob_key(&self, task_id: &str, attachment_id: &str) -> Option<String> {
+        let stored = self.attachments.get(task_id)?;
+        stored.iter().any(|attachment| attachment.id == attachment_id).then(|| content_key(task_id, attachment_id))
+    }
+}
+
+/// Why files could not be attached or fetched
+#[derive(Debug)]
+pub enum AttachmentError {
+    NotFound(String),
+    /// The file is missing, malformed or too large
+    Invalid(String),
+    Store(StoreError),
+    Other(String),
+}
+
+impl fmt::Display for AttachmentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AttachmentError::NotFound(message) | AttachmentError::Invalid(message) | AttachmentError::Other(message) => {
+                f.write_str(message)
+            }
+            AttachmentError::Store(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for AttachmentError {}
+
+impl From<StoreError> for AttachmentError {
+    fn from(e: StoreError) -> Self {
+        AttachmentError::Store(e)
+    }
+}
+
+/// The file carried by an A2A File part
+pub fn from_part(part: &Part) -> Result<NewAttachment, AttachmentError> {
+    let Part::File { file: FileContent { name, mime_type, bytes, .. } } = part else {
+        return Err(AttachmentError::Invalid("Only file parts can be attached".to_string()));
+    };
+    let bytes = bytes
+        .as_deref()
+        .ok_or_else(|| AttachmentError::Invalid("File parts must carry their content in bytes".to_string()))?;
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(bytes)
+        .map_err(|e| AttachmentError::Invalid(format!("File bytes are not valid base64: {}", e)))?;
+    Ok(NewAttachment {
+        name: name.clone().unwrap_or_else(|| "attachment".to_string()),
+        mime_type: mime_type.clone().unwrap_or_else(|| "application/octet-stream".to_string()),
+        bytes,
+    })
+}
+
+/// Attach `files` to a task, recording an `attachment_added` event for each
+///
+/// Nothing is attached when one of the files is refused.
+pub fn attach(
+    agent: &PurchaseOrderAgent,
+    task_id: &str,
+    files: Vec<NewAttachment>,
+    uploaded_by: Option<String>,
+) -> Result<Vec<Attachment>, AttachmentError> {
+    if agent.task_store().get(task_id)?.is_none() {
+        return Err(AttachmentError::NotFound(format!("Task {} not found", task_id)));
+    }
+    if files.is_empty() {
+        return Err(AttachmentError::Invalid("No file to attach".to_string()));
+    }
+    let max_bytes = agent.attachments_config().max_bytes;
+    if let Some(file) = files.iter().find(|file| file.bytes.len() > max_bytes) {
+        return Err(AttachmentError::Invalid(format!("{} is larger than {} bytes", file.name, max_bytes)));
+    }
+
+    let mut attached = Vec::with_capacity(files.len());
+    for file in files {
+        let attachment = Attachment {
+            id: Uuid::new_v4().to_string(),
+            task_id: task_id.to_string(),
+            name: file.name,
+            mime_type: file.mime_type,
+            size: file.bytes.len(),
+            sha256: hex::encode(Sha256::digest(&file.bytes)),
+            uploaded_by: uploaded_by.clone(),
+            attached_at: Utc::now(),
+        };
+        agent.attachments().put(attachment.clone(), file.bytes)?;
+        agent
+            .record_event(
+                task_id,
+                TaskEventKind::AttachmentAdded {
+                    attachment_id: attachment.id.clone(),
+                    name: attachment.name.clone(),
+                    size: attachment.size,
+                },
+            )
+            .map_err(|e| AttachmentError::Other(e.to_string()))?;
+        attached.push(attachment);
+    }
+    Ok(attached)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::TaskOptions;
+    use a2a::Message;
+
+    #[tokio::test]
+    async fn test_file_parts_are_attached_and_listed() {
+        let agent = PurchaseOrderAgent::new();
+        let message = Message { role: "user".to_string(), parts: vec![Part::Text { text: "not a purchase order".to_string() }] };
+        let task = agent.accept_task(message, &TaskOptions::default()).unwrap();
+        let quote = Part::File {
+            file: FileContent {
+                name: Some("quote.pdf".to_string()),
+                mime_type: Some("application/pdf".to_string()),
+                bytes: Some(base64::engine::general_purpose::STANDARD.encode(b"%PDF-1.7")),
+                uri: None,
+            },
+        };
+
+        let attached = attach(&agent, &task.id, vec![from_part(&quote).unwrap()], Some("sam".to_string())).unwrap();
+        assert_eq!((attached[0].name.as_str(), attached[0].size), ("quote.pdf", 8));
+        assert_eq!(agent.attachments().for_task(&task.id).unwrap(), attached);
+        let (_, content) = agent.attachments().content(&task.id, &attached[0].id).unwrap().unwrap();
+        assert_eq!(content, b"%PDF-1.7");
+        let events = agent.event_log().events(&task.id).unwrap();
+        assert_eq!(events.last().unwrap().kind.name(), "attachment_added");
+
+        let link = Part::File { file: FileContent { name: None, mime_type: None, bytes: None, uri: Some("https://example.com/q".to_string()) } };
+        assert!(matches!(from_part(&link), Err(AttachmentError::Invalid(_))));
+        let large = NewAttachment { name: "scan.tif".to_string(), mime_type: "image/tiff".to_string(), bytes: vec![0; 11 * 1024 * 1024] };
+        assert!(matches!(attach(&agent, &task.id, vec![large], None), Err(AttachmentError::Invalid(_))));
+        assert!(matches!(attach(&agent, "missing", vec![from_part(&quote).unwrap()], None), Err(AttachmentError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_blob_attachments_keep_content_in_the_blob_store() {
+        let blobs = Arc::new(crate::blob::MemoryBlobStore::new());
+        let store = BlobAttachmentStore::new(blobs.clone());
+        let attachment = Attachment {
+            id: "a-1".to_string(),
+            task_id: "t-1".to_string(),
+            name: "quote.pdf".to_string(),
+            mime_type: "application/pdf".to_string(),
+            size: 8,
+            sha256: hex::encode(Sha256::digest(b"%PDF-1.7")),
+            uploaded_by: None,
+            attached_at: Utc::now(),
+        };
+        store.put(attachment.clone(), b"%PDF-1.7".to_vec()).unwrap();
+        assert_eq!(blobs.get("attachments/t-1/a-1").unwrap().as_deref(), Some(&b"%PDF-1.7"[..]));
+        assert_eq!(store.content("t-1", "a-1").unwrap(), Some((attachment, b"%PDF-1.7".to_vec())));
+        assert_eq!(store.blob_key("t-1", "a-1").as_deref(), Some("attachments/t-1/a-1"));
+        assert!(store.blob_key("t-1", "a-2").is_none());
+
+        assert_eq!(store.retain_tasks(&|_| false).unwrap(), 1);
+        assert!(blobs.is_empty() && store.for_task("t-1").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,444 @@
+use axum::{
+    extract::{Request, State},
+    http::{header, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use jsonwebtoken::{decode, decode_header, jwk::JwkSet, Algorithm, DecodingKey, Validation};
+use serde::Deserialize;
+use serde_json::Value;
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, RwLock};
+use tracing::warn;
+
+use crate::server::AppState;
+
+/// Bearer token authentication settings
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct AuthConfig {
+    /// Shared secret for HMAC-signed tokens (HS256/HS384/HS512)
+    pub jwt_secret: Option<String>,
+    /// URL of a JWKS document used to verify asymmetrically signed tokens
+    pub jwks_url: Option<String>,
+    /// Expected `iss` claim, if any
+    pub issuer: Option<String>,
+    /// Expected `aud` claim, if any
+    pub audience: Option<String>,
+    /// Claim holding the caller's roles; dotted paths reach into nested objects
+    pub roles_claim: String,
+    /// How long a fetched JWKS document is trusted before it is refreshed
+    pub jwks_cache_seconds: u64,
+    /// Shortest time between two fetches of the JWKS document, however many
+    /// tokens name a `kid` it does not hold
+    pub jwks_min_refresh_seconds: u64,
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        Self {
+            jwt_secret: None,
+            jwks_url: None,
+            issuer: None,
+            audience: None,
+            roles_claim: "roles".to_string(),
+            jwks_cache_seconds: 300,
+            jwks_min_refresh_seconds: 30,
+        }
+    }
+}
+
+impl AuthConfig {
+    /// Authentication is enabled as soon as a secret or JWKS URL is configured
+    pub fn is_enabled(&self) -> bool {
+        self.jwt_secret.is_some() || self.jwks_url.is_some()
+    }
+}
+
+/// Identity extracted from a validated bearer token, stored in request extensions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthContext {
+    pub subject: String,
+    pub roles: Vec<String>,
+}
+
+impl AuthContext {
+    pub fn has_role(&self, role: &str) -> bool {
+        self.roles.iter().any(|r| r == role)
+    }
+}
+
+/// Reasons a bearer token can be rejected
+#[derive(Debug)]
+pub enum AuthError {
+    MissingToken,
+    InvalidToken(String),
+    UnknownKey(String),
+    KeySource(String),
+}
+
+impl fmt::Display for AuthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuthError::MissingToken => write!(f, "Missing bearer token"),
+            AuthError::InvalidToken(reason) => write!(f, "Invalid token: {}", reason),
+            AuthError::UnknownKey(kid) => write!(f, "No signing key found for kid '{}'", kid),
+            AuthError::KeySource(reason) => write!(f, "Unable to load signing keys: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for AuthError {}
+
+impl IntoResponse for AuthError {
+    fn into_response(self) -> Response {
+        let status = match self {
+            AuthError::KeySource(_) => StatusCode::SERVICE_UNAVAILABLE,
+            _ => StatusCode::UNAUTHORIZED,
+        };
+        (
+            status,
+            [(header::WWW_AUTHENTICATE, "Bearer")],
+            axum::Json(serde_json::json!({ "error": self.to_string() })),
+        )
+            .into_response()
+    }
+}
+
+/// Validates JWTs against a shared secret or a (cached) JWKS document
+pub struct JwtValidator {
+    config: AuthConfig,
+    http: reqwest::Client,
+    jwks: RwLock<Option<(JwkSet, Instant)>>,
+    /// When the JWKS document was last requested, whether or not that worked;
+    /// held while fetching so concurrent requests share one fetch
+    last_fetch: Mutex<Option<Instant>>,
+}
+
+impl JwtValidator {
+    pub fn new(config: AuthConfig) -> Self {
+        Self {
+            config,
+            http: reqwest::Client::new(),
+            jwks: RwLock::new(None),
+            last_fetch: Mutex::new(None),
+        }
+    }
+
+    /// Validate a raw token and extract the caller's identity
+    pub async fn validate(&self, token: &str) -> Result<AuthContext, AuthError> {
+        let header = decode_header(token).map_err(|e| AuthError::InvalidToken(e.to_string()))?;
+
+        let key = match (&self.config.jwt_secret, header.alg) {
+            (Some(secret), Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512) => {
+                DecodingKey::from_secret(secret.as_bytes())
+            }
+            _ if self.config.jwks_url.is_some() => {
+                let kid = header
+                    .kid
+                    .clone()
+                    .ok_or_else(|| AuthError::InvalidToken("token header has no kid".to_string()))?;
+                self.jwks_key(&kid).await?
+            }
+            _ => {
+                return Err(AuthError::InvalidToken(format!(
+                    "unsupported algorithm {:?}",
+                    header.alg
+                )))
+            }
+        };
+
+        let mut validation = Validation::new(header.alg);
+        match &self.config.audience {
+            Some(aud) => validation.set_audience(&[aud]),
+            None => validation.validate_aud = false,
+        }
+        if let Some(iss) = &self.config.issuer {
+            validation.set_issuer(&[iss]);
+        }
+
+        let claims = decode::<Value>(token, &key, &validation)
+            .map_err(|e| AuthError::InvalidToken(e.to_string()))?
+            .claims;
+
+        let subject = claims
+            .get("sub")
+            .and_then(Value::as_str)
+            .ok_or_else(|| AuthError::InvalidToken("token has no sub claim".to_string()))?
+            .to_string();
+
+        Ok(AuthContext {
+            subject,
+            roles: extract_roles(&claims, &self.config.roles_claim),
+        })
+    }
+
+    /// Look up a decoding key by kid, refreshing the JWKS when it is stale or the kid is unknown
+    ///
+    /// The document is fetched at most once per `jwks_min_refresh_seconds`; in
+    /// between, a kid it does not hold is unknown without asking again, so
+    /// tokens with made-up kids cannot flood the identity provider.
+    async fn jwks_key(&self, kid: &str) -> Result<DecodingKey, AuthError> {
+        let max_age = Duration::from_secs(self.config.jwks_cache_seconds);
+        let min_refresh = Duration::from_secs(self.config.jwks_min_refresh_seconds);
+        if let Some(key) = self.cached_key(kid, max_age).await? {
+            return Ok(key);
+        }
+
+        let mut last_fetch = self.last_fetch.lock().await;
+        // Another request may have refreshed the document while this one waited
+        if let Some(key) = self.cached_key(kid, max_age).await? {
+            return Ok(key);
+        }
+        if let Some(at) = *last_fetch
+            && at.elapsed() < min_refresh
+        {
+            return match self.cached_key(kid, Duration::MAX).await? {
+                Some(key) => Ok(key),
+                None if self.jwks.read().await.is_some() => Err(AuthError::UnknownKey(kid.to_string())),
+                None => Err(AuthError::KeySource(format!(
+                    "the JWKS fetch failed less than {}s ago",
+                    self.config.jwks_min_refresh_seconds
+                ))),
+            };
+        }
+
+        *last_fetch = Some(Instant::now());
+        let set = self.fetch_jwks().await?;
+        let key = set
+            .find(kid)
+            .map(DecodingKey::from_jwk)
+            .transpose()
+            .map_err(|e| AuthError::KeySource(e.to_string()))?;
+        *self.jwks.write().await = Some((set, Instant::now()));
+
+        key.ok_or_else(|| AuthError::UnknownKey(kid.to_string()))
+    }
+
+    /// The key for `kid` in a cached document younger than `max_age`
+    async fn cached_key(&self, kid: &str, max_age: Duration) -> Result<Option<DecodingKey>, AuthError> {
+        let cached = self.jwks.read().await;
+        match cached.as_ref() {
+            Some((set, fetched_at)) if fetched_at.elapsed() < max_age => {
+                set.find(kid).map(DecodingKey::from_jwk).transpose().map_err(|e| AuthError::KeySource(e.to_string()))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    async fn fetch_jwks(&self) -> Result<JwkSet, AuthError> {
+        let url = self.config.jwks_url.as_deref().unwrap_or_default();
+        self.http
+            .get(url)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| AuthError::KeySource(e.to_string()))?
+            .json::<JwkSet>()
+            .await
+            .map_err(|e| AuthError::KeySource(e.to_string()))
+    }
+}
+
+/// Read roles from a claim given as a JSON array or a space-separated string
+fn extract_roles(claims: &Value, claim_path: &str) -> Vec<String> {
+    let value = claim_path
+        .split('.')
+        .try_fold(claims, |node, key| node.get(key));
+
+    match value {
+        Some(Value::Array(items)) => items
+            .iter()
+            .filter_map(Value::as_str)
+            .map(str::to_string)
+            .collect(),
+        Some(Value::String(s)) => s.split_whitespace().map(str::to_string).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// The token of an `Authorization` value using the Bearer scheme, whose name is case-insensitive
+fn bearer_token(value: &str) -> Option<&str> {
+    let (scheme, token) = value.split_once(' ')?;
+    scheme.eq_ignore_ascii_case("Bearer").then_some(token)
+}
+
+/// Middleware rejecting requests without a valid bearer token
+///
+/// When authentication is not configured every request passes through untouched.
+/// Otherwise the validated [`AuthContext`] is inserted into the request extensions.
+pub async fn require_auth(
+    State(state): State<Arc<AppState>>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, AuthError> {
+    let Some(validator) = state.jwt.as_ref() else {
+        return Ok(next.run(request).await);
+    };
+
+    let token = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(bearer_token)
+        .ok_or(AuthError::MissingToken)?;
+
+    let context = validator.validate(token.trim()).await.inspect_err(|e| {
+        warn!(reason = %e, "bearer token rejected");
+    })?;
+
+    request.extensions_mut().insert(context);
+    Ok(next.run(request).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonwebtoken::{encode, EncodingKey, Header};
+
+    const SECRET: &str = "test-secret";
+
+    fn secret_config() -> AuthConfig {
+        AuthConfig {
+            jwt_secret: Some(SECRET.to_string()),
+            ..AuthConfig::default()
+        }
+    }
+
+    fn token(claims: Value) -> String {
+        encode(&Header::default(), &claims, &EncodingKey::from_secret(SECRET.as_bytes())).unwrap()
+    }
+
+    fn expiry() -> i64 {
+        chrono::Utc::now().timestamp() + 3600
+    }
+
+    #[tokio::test]
+    async fn test_valid_token_extracts_subject_and_roles() {
+        let validator = JwtValidator::new(secret_config());
+        let jwt = token(serde_json::json!({
+            "sub": "jj.schmidt",
+            "roles": ["submitter", "approver"],
+            "exp": expiry(),
+        }));
+
+        let context = validator.validate(&jwt).await.unwrap();
+        assert_eq!(context.subject, "jj.schmidt");
+        assert!(context.has_role("submitter"));
+        assert!(context.has_role("approver"));
+        assert!(!context.has_role("admin"));
+    }
+
+    #[tokio::test]
+    async fn test_wrong_secret_is_rejected() {
+        let validator = JwtValidator::new(AuthConfig {
+            jwt_secret: Some("another-secret".to_string()),
+            ..AuthConfig::default()
+        });
+        let jwt = token(serde_json::json!({ "sub": "someone", "exp": expiry() }));
+
+        assert!(matches!(validator.validate(&jwt).await, Err(AuthError::InvalidToken(_))));
+    }
+
+    #[tokio::test]
+    async fn test_expired_token_is_rejected() {
+        let validator = JwtValidator::new(secret_config());
+        let jwt = token(serde_json::json!({ "sub": "someone", "exp": 1_000 }));
+
+        assert!(validator.validate(&jwt).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_issuer_and_audience_are_enforced() {
+        let validator = JwtValidator::new(AuthConfig {
+            issuer: Some("https://login.example.com".to_string()),
+            audience: Some("po-agent".to_string()),
+            ..secret_config()
+        });
+
+        let wrong_aud = token(serde_json::json!({
+            "sub": "someone",
+            "iss": "https://login.example.com",
+            "aud": "other-agent",
+            "exp": expiry(),
+        }));
+        assert!(validator.validate(&wrong_aud).await.is_err());
+
+        let good = token(serde_json::json!({
+            "sub": "someone",
+            "iss": "https://login.example.com",
+            "aud": "po-agent",
+            "exp": expiry(),
+        }));
+        assert!(validator.validate(&good).await.is_ok());
+    }
+
+    #[test]
+    fn test_extract_roles_from_nested_and_string_claims() {
+        let claims = serde_json::json!({
+            "realm_access": { "roles": ["admin"] },
+            "scope": "submitter approver",
+        });
+
+        assert_eq!(extract_roles(&claims, "realm_access.roles"), vec!["admin"]);
+        assert_eq!(extract_roles(&claims, "scope"), vec!["submitter", "approver"]);
+        assert!(extract_roles(&claims, "missing").is_empty());
+    }
+
+    #[test]
+    fn test_bearer_scheme_is_case_insensitive() {
+        assert_eq!(bearer_token("Bearer abc"), Some("abc"));
+        assert_eq!(bearer_token("bearer abc"), Some("abc"));
+        assert_eq!(bearer_token("BEARER abc"), Some("abc"));
+        assert_eq!(bearer_token("Basic abc"), None);
+        assert_eq!(bearer_token("Bearerabc"), None);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_kids_do_not_refetch_the_jwks() {
+        use axum::{routing::get, Router};
+        use base64::Engine;
+        use std::sync::atomic::{AtomicU32, Ordering};
+
+        // A JWKS endpoint holding one HMAC key, counting its fetches
+        let fetches = Arc::new(AtomicU32::new(0));
+        let jwks = serde_json::json!({
+            "keys": [{
+                "kty": "oct",
+                "kid": "k1",
+                "alg": "HS256",
+                "k": base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(SECRET),
+            }],
+        });
+        let app = Router::new().route(
+            "/jwks",
+            get({
+                let fetches = fetches.clone();
+                move || async move {
+                    fetches.fetch_add(1, Ordering::SeqCst);
+                    axum::Json(jwks)
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/jwks", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let validator = JwtValidator::new(AuthConfig { jwks_url: Some(url), ..AuthConfig::default() });
+        let signed = |kid: &str| {
+            let header = Header { kid: Some(kid.to_string()), ..Header::default() };
+            let claims = serde_json::json!({ "sub": "someone", "exp": expiry() });
+            encode(&header, &claims, &EncodingKey::from_secret(SECRET.as_bytes())).unwrap()
+        };
+
+        assert!(validator.validate(&signed("k1")).await.is_ok());
+        for n in 0..5 {
+            let result = validator.validate(&signed(&format!("random-{}", n))).await;
+            assert!(matches!(result, Err(AuthError::UnknownKey(_))));
+        }
+        assert!(validator.validate(&signed("k1")).await.is_ok());
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,123 @@
+//! Validation of many purchase orders at once
+//!
+//! `POST /agent/validate/batch` pre-flights a large import: every order is
+//! validated as `/agent/validate` would, without creating tasks, and the
+//! report gives the findings of each order along with how often each check
+//! failed across the batch. An order that cannot be parsed is reported in its
+//! place, so one bad entry does not hide the others.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+use crate::agent::PurchaseOrderAgent;
+use crate::intake::message_from_json;
+use crate::severity::{Check, Finding, Severity};
+
+/// Most orders one batch may hold
+pub const MAX_BATCH: usize = 10_000;
+
+/// Body of `POST /agent/validate/batch`
+#[derive(Debug, Clone, Deserialize)]
+pub struct BatchRequest {
+    /// Each an A2A message, a task request or a bare purchase order
+    pub orders: Vec<Value>,
+}
+
+/// Findings of one order of a batch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchEntry {
+    /// Position of the order in the request, from 0
+    pub index: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub po_number: Option<String>,
+    pub valid: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub findings: Vec<Finding>,
+    /// Why the order could not be parsed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// How often one check failed across a batch
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CheckCount {
+    pub severity: Severity,
+    /// Findings of the check
+    pub findings: usize,
+    /// Orders with at least one of them
+    pub orders: usize,
+}
+
+/// Response of `POST /agent/validate/batch`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchReport {
+    pub total: usize,
+    pub valid: usize,
+    /// Orders that parsed but failed validation
+    pub invalid: usize,
+    pub unparseable: usize,
+    /// By check, e.g. `subtotal_mismatch`
+    pub counts: BTreeMap<Check, CheckCount>,
+    /// In request order
+    pub orders: Vec<BatchEntry>,
+}
+
+/// Validate every order of a batch
+pub fn validate(agent: &PurchaseOrderAgent, orders: Vec<Value>) -> BatchReport {
+    let mut report = BatchReport { total: orders.len(), valid: 0, invalid: 0, unparseable: 0, counts: BTreeMap::new(), orders: Vec::new() };
+    for (index, order) in orders.into_iter().enumerate() {
+        let parsed = message_from_json(order).and_then(|message| agent.extract_purchase_order(&message).map_err(|e| e.to_string()));
+        let po = match parsed {
+            Ok(po) => po,
+            Err(e) => {
+                report.unparseable += 1;
+                report.orders.push(BatchEntry { index, po_number: None, valid: false, findings: Vec::new(), error: Some(e) });
+                continue;
+            }
+        };
+
+        let findings = agent.purchase_order_findings(&po).reported().to_vec();
+        let valid = findings.iter().all(|finding| finding.severity != Severity::Error);
+        if valid {
+            report.valid += 1;
+        } else {
+            report.invalid += 1;
+        }
+        let mut seen = Vec::new();
+        for finding in &findings {
+            let count = report.counts.entry(finding.check).or_insert(CheckCount { severity: finding.severity, findings: 0, orders: 0 });
+            count.findings += 1;
+            if !seen.contains(&finding.check) {
+                seen.push(finding.check);
+                count.orders += 1;
+            }
+        }
+        report.orders.push(BatchEntry { index, po_number: Some(po.po_number), valid, findings, error: None });
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batches_are_counted_by_check() {
+        let agent = PurchaseOrderAgent::new();
+        let valid = serde_json::to_value(crate::testing::valid_order()).unwrap();
+        let mut broken = crate::testing::valid_order();
+        broken.po_number = "PO-2".to_string();
+        broken.sub_total += 1.0;
+        broken.items[0].quantity = 0;
+        let broken = serde_json::json!({ "purchaseOrder": broken });
+
+        let report = validate(&agent, vec![valid, broken, serde_json::json!({ "nope": 1 })]);
+        assert_eq!((report.total, report.valid, report.invalid, report.unparseable), (3, 1, 1, 1));
+        assert_eq!(report.counts[&Check::ItemZeroQuantity], CheckCount { severity: Severity::Error, findings: 1, orders: 1 });
+        assert_eq!(report.counts[&Check::SubtotalMismatch].severity, Severity::Warning);
+        assert_eq!(report.orders[1].po_number.as_deref(), Some("PO-2"));
+        assert!(report.orders[1].findings.iter().any(|finding| finding.check == Check::ItemZeroQuantity));
+        assert!(report.orders[2].error.is_some());
+    }
+}
@@ -0,0 +1,271 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::agent::PurchaseOrder;
+use crate::store::StoreResult;
+
+/// Spending limit and validity period of a blanket purchase order
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlanketTerms {
+    /// Cumulative grand total that releases may not exceed
+    pub not_to_exceed: f64,
+    /// First day releases are accepted
+    pub valid_from: NaiveDate,
+    /// Last day releases are accepted
+    pub valid_until: NaiveDate,
+}
+
+/// A release order drawn against a blanket order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Release {
+    pub po_number: String,
+    pub amount: f64,
+    pub released_at: DateTime<Utc>,
+}
+
+/// A blanket order and the releases drawn against it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlanketOrder {
+    pub po_number: String,
+    pub supplier_name: String,
+    pub terms: BlanketTerms,
+    /// One entry per release PO number; re-processing a release replaces its amount
+    pub releases: Vec<Release>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl BlanketOrder {
+    /// Total released so far, leaving out `except` (a release being re-processed)
+    pub fn released(&self, except: Option<&str>) -> f64 {
+        self.releases
+            .iter()
+            .filter(|r| except.is_none_or(|po| r.po_number != po))
+            .map(|r| r.amount)
+            .sum()
+    }
+
+    pub fn status(&self, today: NaiveDate) -> BlanketStatus {
+        if today < self.terms.valid_from {
+            BlanketStatus::NotYetValid
+        } else if today > self.terms.valid_until {
+            BlanketStatus::Expired
+        } else if self.released(None) >= self.terms.not_to_exceed {
+            BlanketStatus::Exhausted
+        } else {
+            BlanketStatus::Active
+        }
+    }
+
+    /// Summary of spend against the limit as of `today`
+    pub fn report(&self, today: NaiveDate) -> BlanketReport {
+        let released = self.released(None);
+        BlanketReport {
+            order: self.clone(),
+            status: self.status(today),
+            released,
+            remaining: (self.terms.not_to_exceed - released).max(0.0),
+        }
+    }
+}
+
+/// Whether a blanket order accepts releases
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BlanketStatus {
+    NotYetValid,
+    Active,
+    /// The limit has been reached
+    Exhausted,
+    Expired,
+}
+
+/// Response of `GET /agent/blankets/{po_number}`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlanketReport {
+    #[serde(flatten)]
+    pub order: BlanketOrder,
+    pub status: BlanketStatus,
+    pub released: f64,
+    pub remaining: f64,
+}
+
+/// Storage of blanket orders and their releases
+pub trait BlanketStore: Send + Sync {
+    fn get(&self, po_number: &str) -> StoreResult<Option<BlanketOrder>>;
+
+    /// Insert a blanket order, or update the supplier and terms of an existing one keeping its releases
+    fn put(&self, po_number: &str, supplier_name: &str, terms: BlanketTerms) -> StoreResult<()>;
+
+    /// Record a release against a blanket order, replacing any with the same PO number
+    ///
+    /// Returns `false` when there is no such blanket order.
+    fn release(&self, blanket_po_number: &str, release: Release) -> StoreResult<bool>;
+
+    /// Withdraw a release, e.g. when it is rejected after being recorded
+    fn cancel_release(&self, blanket_po_number: &str, po_number: &str) -> StoreResult<()>;
+}
+
+/// In-memory blanket order store used by default
+#[derive(Default)]
+pub struct MemoryBlanketStore {
+    orders: DashMap<String, BlanketOrder>,
+}
+
+impl MemoryBlanketStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl BlanketStore for MemoryBlanketStore {
+    fn get(&self, po_number: &str) -> StoreResult<Option<BlanketOrder>> {
+        Ok(self.orders.get(po_number).map(|o| o.clone()))
+    }
+
+    fn put(&self, po_number: &str, supplier_name: &str, terms: BlanketTerms) -> StoreResult<()> {
+        let mut order = self.orders.entry(po_number.to_string()).or_insert_with(|| BlanketOrder {
+            po_number: po_number.to_string(),
+            supplier_name: supplier_name.to_string(),
+            terms: terms.clone(),
+            releases: Vec::new(),
+            created_at: Utc::now(),
+        });
+        order.supplier_name = supplier_name.to_string();
+        order.terms = terms;
+        Ok(())
+    }
+
+    fn release(&self, blanket_po_number: &str, release: Release) -> StoreResult<bool> {
+        let Some(mut order) = self.orders.get_mut(blanket_po_number) else {
+            return Ok(false);
+        };
+        order.releases.retain(|r| r.po_number != release.po_number);
+        order.releases.push(release);
+        Ok(true)
+    }
+
+    fn cancel_release(&self, blanket_po_number: &str, po_number: &str) -> StoreResult<()> {
+        if let Some(mut order) = self.orders.get_mut(blanket_po_number) {
+            order.releases.retain(|r| r.po_number != po_number);
+        }
+        Ok(())
+    }
+}
+
+/// Validation errors for a blanket order or a release drawn against one, as of `today`
+pub fn validate(store: &dyn BlanketStore, po: &PurchaseOrder, today: NaiveDate) -> Vec<String> {
+    let mut errors = Vec::new();
+
+    if let Some(terms) = &po.blanket {
+        if po.blanket_po_number.is_some() {
+            errors.push("A blanket order cannot also be a release against another blanket order".to_string());
+        }
+        if terms.not_to_exceed <= 0.0 {
+            errors.push("Blanket order not-to-exceed amount must be positive".to_string());
+        }
+        if terms.valid_until < terms.valid_from {
+            errors.push(format!(
+                "Blanket order validity ends ({}) before it starts ({})",
+                terms.valid_until, terms.valid_from
+            ));
+        }
+    }
+
+    if let Some(blanket_po_number) = &po.blanket_po_number {
+        let blanket = match store.get(blanket_po_number) {
+            Ok(Some(blanket)) => blanket,
+            Ok(None) => {
+                errors.push(format!("Blanket order {} not found", blanket_po_number));
+                return errors;
+            }
+            Err(e) => {
+                errors.push(format!("Blanket order {} could not be read: {}", blanket_po_number, e));
+                return errors;
+            }
+        };
+
+        if !blanket.supplier_name.trim().eq_ignore_ascii_case(po.supplier_name.trim()) {
+            errors.push(format!(
+                "Release supplier '{}' does not match blanket order supplier '{}'",
+                po.supplier_name, blanket.supplier_name
+            ));
+        }
+        if today < blanket.terms.valid_from {
+            errors.push(format!("Blanket order {} is not valid until {}", blanket_po_number, blanket.terms.valid_from));
+        }
+        if today > blanket.terms.valid_until {
+            errors.push(format!("Blanket order {} expired on {}", blanket_po_number, blanket.terms.valid_until));
+        }
+        let remaining = blanket.terms.not_to_exceed - blanket.released(Some(&po.po_number));
+        if po.grand_total > remaining + 0.005 {
+            errors.push(format!(
+                "Release of {:.2} exceeds the {:.2} remaining on blanket order {}",
+                po.grand_total,
+                remaining.max(0.0),
+                blanket_po_number
+            ));
+        }
+    }
+
+    errors
+}
+
+/// Record a processed blanket order, or a processed release against one
+pub fn record(store: &dyn BlanketStore, po: &PurchaseOrder) -> StoreResult<()> {
+    if let Some(terms) = &po.blanket {
+        store.put(&po.po_number, &po.supplier_name, terms.clone())?;
+    }
+    if let Some(blanket_po_number) = &po.blanket_po_number {
+        let release = Release { po_number: po.po_number.clone(), amount: po.grand_total, released_at: Utc::now() };
+        store.release(blanket_po_number, release)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        s.parse().unwrap()
+    }
+
+    fn release(po_number: &str, supplier: &str, grand_total: f64) -> PurchaseOrder {
+        let mut po = crate::testing::valid_order();
+        po.po_number = po_number.to_string();
+        po.supplier_name = supplier.to_string();
+        po.grand_total = grand_total;
+        po.blanket_po_number = Some("BPO-1".to_string());
+        po
+    }
+
+    #[test]
+    fn test_releases_are_limited_by_amount_and_validity() {
+        let store = MemoryBlanketStore::new();
+        let terms = BlanketTerms { not_to_exceed: 1000.0, valid_from: date("2026-01-01"), valid_until: date("2026-12-31") };
+        let today = date("2026-06-01");
+
+        assert_eq!(validate(&store, &release("R-1", "Acme", 100.0), today), ["Blanket order BPO-1 not found"]);
+        store.put("BPO-1", "Acme", terms).unwrap();
+
+        let first = release("R-1", "acme", 600.0);
+        assert!(validate(&store, &first, today).is_empty());
+        record(&store, &first).unwrap();
+
+        let second = release("R-2", "Acme", 500.0);
+        assert!(validate(&store, &second, today)[0].contains("400.00 remaining"));
+        assert!(validate(&store, &release("R-1", "Acme", 900.0), today).is_empty(), "re-processing replaces a release");
+        assert!(validate(&store, &release("R-3", "Globex", 10.0), today)[0].contains("does not match"));
+        assert!(validate(&store, &release("R-3", "Acme", 10.0), date("2027-01-01"))[0].contains("expired"));
+
+        record(&store, &release("R-2", "Acme", 400.0)).unwrap();
+        let report = store.get("BPO-1").unwrap().unwrap().report(today);
+        assert_eq!(report.status, BlanketStatus::Exhausted);
+        assert_eq!(report.remaining, 0.0);
+
+        store.cancel_release("BPO-1", "R-2").unwrap();
+        assert_eq!(store.get("BPO-1").unwrap().unwrap().report(today).remaining, 400.0);
+    }
+}
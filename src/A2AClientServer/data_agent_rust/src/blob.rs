@@ -0,0 +1,653 @@
+//! Storage of large payloads outside the task store
+//!
+//! Orders with tens of thousands of lines make for task records of many
+//! megabytes: the request, the detailed result in the status message and the
+//! JSON artifact each hold a copy. With a blob backend configured, the task
+//! store keeps only a reference and a summary of every part larger than
+//! `offload_bytes`, and the part itself is written to the backend. Records are
+//! put back together whenever they are read, so nothing else changes.
+//!
+//! Blobs are keyed by task and by the SHA-256 of the part, so the detailed
+//! result and its identical JSON artifact share one blob. A blob no longer
+//! referenced once a task is updated or removed is deleted.
+//!
+//! Besides memory and the filesystem, blobs can live in Amazon S3 (or an
+//! S3-compatible store such as MinIO) with the `s3` feature, or in Azure Blob
+//! Storage with the `azure` feature. These two can also hand out presigned
+//! URLs, so artifacts and attachments are downloaded straight from the bucket.
+
+use a2a::{Message, Part, TaskStatus};
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::store::{same_record, StoreError, StoreResult, StoreStats, TaskRecord, TaskStore};
+
+#[cfg(feature = "azure")]
+pub mod azure;
+#[cfg(any(feature = "s3", feature = "azure"))]
+mod http;
+#[cfg(feature = "s3")]
+pub mod s3;
+
+/// Key of the Data part that stands in for an offloaded part
+pub const REF_KEY: &str = "blobRef";
+
+/// Key, next to the reference, of the offloaded part's small top-level fields
+pub const SUMMARY_KEY: &str = "summary";
+
+/// Largest field kept in a summary, as JSON
+const SUMMARY_FIELD_BYTES: usize = 512;
+
+/// Where large payloads are kept
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BlobBackend {
+    /// Payloads stay in the task store
+    #[default]
+    None,
+    Memory,
+    Filesystem,
+    /// Amazon S3 or an S3-compatible store (requires the `s3` feature)
+    S3,
+    /// Azure Blob Storage (requires the `azure` feature)
+    Azure,
+}
+
+/// The `[blobs]` section
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct BlobConfig {
+    pub backend: BlobBackend,
+    /// Directory of the `filesystem` backend
+    pub path: Option<PathBuf>,
+    /// Parts larger than this many bytes of JSON are offloaded
+    pub offload_bytes: usize,
+    /// How long presigned download URLs stay valid
+    #[serde(with = "humantime_serde")]
+    pub url_ttl: Duration,
+    /// Timeout of each request to a cloud backend
+    #[serde(with = "humantime_serde")]
+    pub timeout: Duration,
+    pub s3: Option<S3BlobConfig>,
+    pub azure: Option<AzureBlobConfig>,
+}
+
+impl Default for BlobConfig {
+    fn default() -> Self {
+        Self {
+            backend: BlobBackend::None,
+            path: None,
+            offload_bytes: 256 * 1024,
+            url_ttl: Duration::from_secs(15 * 60),
+            timeout: Duration::from_secs(30),
+            s3: None,
+            azure: None,
+        }
+    }
+}
+
+/// The `[blobs.s3]` section
+#[derive(Debug, Clone, Deserialize)]
+pub struct S3BlobConfig {
+    pub bucket: String,
+    #[serde(default = "default_s3_region")]
+    pub region: String,
+    /// Endpoint of an S3-compatible store such as MinIO, addressed path-style;
+    /// AWS is addressed by bucket host name when unset
+    #[serde(default)]
+    pub endpoint: Option<String>,
+    /// Prepended to every key, e.g. `po-agent/`
+    #[serde(default)]
+    pub prefix: Option<String>,
+    /// Static credentials; the `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY` and
+    /// `AWS_SESSION_TOKEN` environment variables are used when unset
+    #[serde(default)]
+    pub access_key_id: Option<String>,
+    #[serde(default)]
+    pub secret_access_key: Option<String>,
+    #[serde(default)]
+    pub session_token: Option<String>,
+}
+
+fn default_s3_region() -> String {
+    "us-east-1".to_string()
+}
+
+/// The `[blobs.azure]` section
+#[derive(Debug, Clone, Deserialize)]
+pub struct AzureBlobConfig {
+    /// Storage account name
+    pub account: String,
+    pub container: String,
+    /// Base64 account key; the `AZURE_STORAGE_KEY` environment variable is used when unset
+    #[serde(default)]
+    pub account_key: Option<String>,
+    /// Blob service endpoint, e.g. Azurite's `http://127.0.0.1:10000/devstoreaccount1`;
+    /// `https://{account}.blob.core.windows.net` when unset
+    #[serde(default)]
+    pub endpoint: Option<String>,
+    /// Prepended to every key, e.g. `po-agent/`
+    #[serde(default)]
+    pub prefix: Option<String>,
+}
+
+/// A store of opaque payloads by key
+///
+/// Keys are `/`-separated paths such as `tasks/{task_id}/{sha256}`.
+pub trait BlobStore: Send + Sync {
+    /// Name shown in logs
+    fn name(&self) -> &str;
+
+    /// Write a blob, replacing any with the same key
+    fn put(&self, key: &str, content: &[u8]) -> StoreResult<()>;
+
+    fn get(&self, key: &str) -> StoreResult<Option<Vec<u8>>>;
+
+    /// Delete a blob; deleting a missing one is not an error
+    fn delete(&self, key: &str) -> StoreResult<()>;
+
+    /// A URL anyone holding it can download the blob from for `expires_in`,
+    /// or `None` when the backend cannot hand them out
+    fn presign(&self, _key: &str, _expires_in: Duration) -> StoreResult<Option<String>> {
+        Ok(None)
+    }
+}
+
+/// Refuse keys with empty, `.` or `..` segments, so no backend resolves them elsewhere
+fn check_key(key: &str) -> StoreResult<()> {
+    let invalid = key.split('/').any(|segment| segment.is_empty() || segment == "." || segment == ".." || segment.contains('\\'));
+    if invalid {
+        return Err(StoreError(format!("Invalid blob key {}", key)));
+    }
+    Ok(())
+}
+
+/// In-memory blob store, mostly for tests
+#[derive(Default)]
+pub struct MemoryBlobStore {
+    blobs: DashMap<String, Vec<u8>>,
+}
+
+impl MemoryBlobStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of blobs held
+    pub fn len(&self) -> usize {
+        self.blobs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blobs.is_empty()
+    }
+}
+
+impl BlobStore for MemoryBlobStore {
+    fn name(&self) -> &str {
+        "memory"
+    }
+
+    fn put(&self, key: &str, content: &[u8]) -> StoreResult<()> {
+        self.blobs.insert(key.to_string(), content.to_vec());
+        Ok(())
+    }
+
+    fn get(&self, key: &str) -> StoreResult<Option<Vec<u8>>> {
+        Ok(self.blobs.get(key).map(|content| content.clone()))
+    }
+
+    fn delete(&self, key: &str) -> StoreResult<()> {
+        self.blobs.remove(key);
+        Ok(())
+    }
+}
+
+/// Blobs kept as files under a directory, one per key
+pub struct FileBlobStore {
+    root: PathBuf,
+}
+
+impl FileBlobStore {
+    pub fn new(root: impl Into<PathBuf>) -> StoreResult<Self> {
+        let root = root.into();
+        std::fs::create_dir_all(&root).map_err(|e| StoreError(format!("Failed to create {}: {}", root.display(), e)))?;
+        Ok(Self { root })
+    }
+
+    /// File of a key, refusing keys that would leave the root
+    fn path(&self, key: &str) -> StoreResult<PathBuf> {
+        check_key(key)?;
+        let mut path = self.root.clone();
+        path.extend(key.split('/'));
+        Ok(path)
+    }
+}
+
+fn io_error(path: &Path, e: std::io::Error) -> StoreError {
+    StoreError(format!("Blob {}: {}", path.display(), e))
+}
+
+impl BlobStore for FileBlobStore {
+    fn name(&self) -> &str {
+        "filesystem"
+    }
+
+    fn put(&self, key: &str, content: &[u8]) -> StoreResult<()> {
+        let path = self.path(key)?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| io_error(parent, e))?;
+        }
+        // Written aside and renamed, so a reader never sees half a blob
+        let partial = path.with_extension("partial");
+        std::fs::write(&partial, content).map_err(|e| io_error(&partial, e))?;
+        std::fs::rename(&partial, &path).map_err(|e| io_error(&path, e))
+    }
+
+    fn get(&self, key: &str) -> StoreResult<Option<Vec<u8>>> {
+        let path = self.path(key)?;
+        match std::fs::read(&path) {
+            Ok(content) => Ok(Some(content)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(io_error(&path, e)),
+        }
+    }
+
+    fn delete(&self, key: &str) -> StoreResult<()> {
+        let path = self.path(key)?;
+        match std::fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(io_error(&path, e)),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The blob store `config` selects, or `None` when payloads stay in the task store
+pub fn store(config: &BlobConfig) -> Result<Option<Arc<dyn BlobStore>>, String> {
+    Ok(match config.backend {
+        BlobBackend::None => None,
+        BlobBackend::Memory => Some(Arc::new(MemoryBlobStore::new())),
+        BlobBackend::Filesystem => {
+            let path = config.path.as_ref().ok_or("The filesystem blob backend needs a path")?;
+            Some(Arc::new(FileBlobStore::new(path).map_err(|e| e.to_string())?))
+        }
+        BlobBackend::S3 => {
+            let s3 = config.s3.as_ref().ok_or("The s3 blob backend needs a [blobs.s3] section")?;
+            Some(s3_store(s3, config.timeout)?)
+        }
+        BlobBackend::Azure => {
+            let azure = config.azure.as_ref().ok_or("The azure blob backend needs a [blobs.azure] section")?;
+            Some(azure_store(azure, config.timeout)?)
+        }
+    })
+}
+
+#[cfg(feature = "s3")]
+fn s3_store(config: &S3BlobConfig, timeout: Duration) -> Result<Arc<dyn BlobStore>, String> {
+    Ok(Arc::new(s3::S3BlobStore::new(config, timeout)?))
+}
+
+#[cfg(not(feature = "s3"))]
+fn s3_store(_config: &S3BlobConfig, _timeout: Duration) -> Result<Arc<dyn BlobStore>, String> {
+    Err("The s3 blob backend is configured but this binary was built without the `s3` feature".to_string())
+}
+
+#[cfg(feature = "azure")]
+fn azure_store(config: &AzureBlobConfig, timeout: Duration) -> Result<Arc<dyn BlobStore>, String> {
+    Ok(Arc::new(azure::AzureBlobStore::new(config, timeout)?))
+}
+
+#[cfg(not(feature = "azure"))]
+fn azure_store(_config: &AzureBlobConfig, _timeout: Duration) -> Result<Arc<dyn BlobStore>, String> {
+    Err("The azure blob backend is configured but this binary was built without the `azure` feature".to_string())
+}
+
+/// A temporary URL a blob can be downloaded from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PresignedUrl {
+    pub url: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// A presigned URL of `key`, valid for `ttl`, or `None` when the store cannot hand them out
+///
+/// `content` is written to `key` first; leave it out when the blob is already there.
+pub fn download_url(blobs: &dyn BlobStore, key: &str, content: Option<&[u8]>, ttl: Duration) -> StoreResult<Option<PresignedUrl>> {
+    let expires_at = Utc::now() + chrono::Duration::from_std(ttl).map_err(|e| StoreError(format!("Invalid URL lifetime: {}", e)))?;
+    let Some(url) = blobs.presign(key, ttl)? else {
+        return Ok(None);
+    };
+    if let Some(content) = content {
+        blobs.put(key, content)?;
+    }
+    Ok(Some(PresignedUrl { url, expires_at }))
+}
+
+/// Reference to an offloaded part, as stored in its place
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlobRef {
+    pub key: String,
+    /// Bytes of the part's JSON
+    pub size: usize,
+    /// Hex SHA-256 of the part's JSON
+    pub sha256: String,
+}
+
+/// The blob reference a stored part stands for, if it is one
+pub fn reference(part: &Part) -> Option<BlobRef> {
+    match part {
+        Part::Data { data } => BlobRef::deserialize(data.get(REF_KEY)?).ok(),
+        _ => None,
+    }
+}
+
+/// Small top-level fields of a part, and of a single wrapped object such as `purchaseOrder`
+fn summary(part: &Part) -> Value {
+    fn small(object: &Map<String, Value>, depth: usize) -> Map<String, Value> {
+        let mut summary = Map::new();
+        for (key, value) in object {
+            let size = serde_json::to_vec(value).map_or(usize::MAX, |json| json.len());
+            if size <= SUMMARY_FIELD_BYTES {
+                summary.insert(key.clone(), value.clone());
+            } else if let (Value::Object(inner), 0) = (value, depth) {
+                summary.insert(key.clone(), Value::Object(small(inner, 1)));
+            }
+        }
+        summary
+    }
+    match part {
+        Part::Data { data: Value::Object(object) } => Value::Object(small(object, 0)),
+        _ => Value::Object(Map::new()),
+    }
+}
+
+/// A task store that keeps large parts of its records in a blob store
+pub struct OffloadingTaskStore {
+    inner: Arc<dyn TaskStore>,
+    blobs: Arc<dyn BlobStore>,
+    offload_bytes: usize,
+}
+
+impl OffloadingTaskStore {
+    pub fn new(inner: Arc<dyn TaskStore>, blobs: Arc<dyn BlobStore>, offload_bytes: usize) -> Self {
+        Self { inner, blobs, offload_bytes }
+    }
+
+    /// The blob store parts are offloaded to
+    pub fn blobs(&self) -> &Arc<dyn BlobStore> {
+        &self.blobs
+    }
+
+    fn offload(&self, task_id: &str, part: &mut Part) -> StoreResult<()> {
+        let json = serde_json::to_vec(part).map_err(|e| StoreError(format!("Failed to serialize part: {}", e)))?;
+        // A client part that looks like a reference is offloaded too, so every stored reference is one of ours
+        if json.len() <= self.offload_bytes && reference(part).is_none() {
+            return Ok(());
+        }
+        let sha256 = hex::encode(Sha256::digest(&json));
+        let blob = BlobRef { key: format!("tasks/{}/{}", task_id, sha256), size: json.len(), sha256 };
+        self.blobs.put(&blob.key, &json)?;
+        let mut data = Map::new();
+        data.insert(SUMMARY_KEY.to_string(), summary(part));
+        data.insert(REF_KEY.to_string(), serde_json::to_value(&blob).expect("blob references serialize"));
+        *part = Part::Data { data: Value::Object(data) };
+        Ok(())
+    }
+
+    fn load(&self, part: &mut Part) -> StoreResult<()> {
+        let Some(blob) = reference(part) else {
+            return Ok(());
+        };
+        let json = self.blobs.get(&blob.key)?.ok_or_else(|| StoreError(format!("Blob {} is missing", blob.key)))?;
+        if hex::encode(Sha256::digest(&json)) != blob.sha256 {
+            return Err(StoreError(format!("Blob {} does not match its checksum", blob.key)));
+        }
+        *part = serde_json::from_slice(&json).map_err(|e| StoreError(format!("Blob {} is not a part: {}", blob.key, e)))?;
+        Ok(())
+    }
+
+    /// `record` with its large parts replaced by references
+    fn dehydrate(&self, mut record: TaskRecord) -> StoreResult<TaskRecord> {
+        let task_id = record.task.id.clone();
+        for part in parts_mut(&mut record) {
+            self.offload(&task_id, part)?;
+        }
+        Ok(record)
+    }
+
+    /// `record` with its offloaded parts read back
+    fn hydrate(&self, mut record: TaskRecord) -> StoreResult<TaskRecord> {
+        for part in parts_mut(&mut record) {
+            self.load(part)?;
+        }
+        Ok(record)
+    }
+
+    /// Delete the blobs `before` referenced that `after` no longer does
+    fn collect(&self, before: &TaskRecord, after: Option<&TaskRecord>) -> StoreResult<()> {
+        let kept = after.map(keys).unwrap_or_default();
+        for key in keys(before).difference(&kept) {
+            self.blobs.delete(key)?;
+        }
+        Ok(())
+    }
+}
+
+fn message_parts(message: &mut Option<Message>) -> impl Iterator<Item = &mut Part> {
+    message.iter_mut().flat_map(|message| message.parts.iter_mut())
+}
+
+/// The request, status message and artifact parts of a record
+fn parts_mut(record: &mut TaskRecord) -> impl Iterator<Item = &mut Part> {
+    let TaskStatus { message, .. } = &mut record.task.status;
+    let artifacts = record.task.artifacts.iter_mut().flatten().flat_map(|artifact| artifact.parts.iter_mut());
+    record.request.parts.iter_mut().chain(message_parts(message)).chain(artifacts)
+}
+
+/// Blob keys a stored record refers to
+fn keys(record: &TaskRecord) -> BTreeSet<String> {
+    let mut record = record.clone();
+    parts_mut(&mut record).filter_map(|part| reference(part)).map(|blob| blob.key).collect()
+}
+
+impl TaskStore for OffloadingTaskStore {
+    fn put(&self, record: TaskRecord) -> StoreResult<()> {
+        let previous = self.inner.get(&record.task.id)?;
+        let stored = self.dehydrate(record)?;
+        self.inner.put(stored.clone())?;
+        match previous {
+            Some(previous) => self.collect(&previous, Some(&stored)),
+            None => Ok(()),
+        }
+    }
+
+    fn get(&self, task_id: &str) -> StoreResult<Option<TaskRecord>> {
+        self.inner.get(task_id)?.map(|record| self.hydrate(record)).transpose()
+    }
+
+    fn update(&self, task_id: &str, update: &mut dyn FnMut(&mut TaskRecord)) -> StoreResult<Option<TaskRecord>> {
+        // Blobs are read and written outside the inner store's locks, and the
+        // result swapped in only if no one changed the record meanwhile
+        loop {
+            let Some(stored) = self.inner.get(task_id)? else {
+                return Ok(None);
+            };
+            let mut record = match self.hydrate(stored.clone()) {
+                Ok(record) => record,
+                // A concurrent update may have deleted the blobs of what was read
+                Err(_) if self.inner.get(task_id)?.is_none_or(|current| !same_record(&current, &stored)) => continue,
+                Err(e) => return Err(e),
+            };
+            update(&mut record);
+            let replacement = self.dehydrate(record.clone())?;
+            if let Some(updated) = self.inner.compare_and_swap(&stored, replacement.clone())? {
+                self.collect(&stored, Some(&updated))?;
+                record.updated_at = updated.updated_at;
+                return Ok(Some(record));
+            }
+            // Lost the race: delete the blobs only this attempt wrote, then start over
+            let mut kept = keys(&stored);
+            if let Some(current) = self.inner.get(task_id)? {
+                kept.extend(keys(&current));
+            }
+            for key in keys(&replacement).difference(&kept) {
+                self.blobs.delete(key)?;
+            }
+        }
+    }
+
+    fn remove(&self, task_id: &str) -> StoreResult<Option<TaskRecord>> {
+        let Some(stored) = self.inner.remove(task_id)? else {
+            return Ok(None);
+        };
+        let record = self.hydrate(stored.clone())?;
+        self.collect(&stored, None)?;
+        Ok(Some(record))
+    }
+
+    fn list(&self) -> StoreResult<Vec<TaskRecord>> {
+        self.inner.list()?.into_iter().map(|record| self.hydrate(record)).collect()
+    }
+
+    fn remove_where(&self, predicate: &dyn Fn(&TaskRecord) -> bool) -> StoreResult<usize> {
+        let mut removed = 0;
+        for record in self.list()? {
+            if predicate(&record) && self.remove(&record.task.id)?.is_some() {
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+
+    fn stats(&self) -> StoreResult<StoreStats> {
+        self.inner.stats()
+    }
+
+    fn snapshot_ids(&self) -> StoreResult<Vec<String>> {
+        self.inner.snapshot_ids()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::{ProcessingResult, PurchaseOrderAgent};
+    use crate::store::MemoryTaskStore;
+    use a2a::A2AProtocol;
+
+    #[tokio::test]
+    async fn test_large_parts_are_offloaded_and_read_back() {
+        let inner = Arc::new(MemoryTaskStore::new());
+        let blobs = Arc::new(MemoryBlobStore::new());
+        let store = Arc::new(OffloadingTaskStore::new(inner.clone(), blobs.clone(), 1024));
+        let agent = PurchaseOrderAgent::new().with_task_store(store.clone());
+
+        let po = crate::testing::huge_order(40);
+        let task = agent.send_task(crate::testing::message(&po)).await.unwrap();
+
+        // The task store holds references and summaries of the request and the result
+        let stored = inner.get(&task.id).unwrap().unwrap();
+        let request = reference(&stored.request.parts[0]).unwrap();
+        assert_eq!(request.key, format!("tasks/{}/{}", task.id, request.sha256));
+        let Part::Data { data } = &stored.request.parts[0] else { unreachable!() };
+        assert_eq!(data[SUMMARY_KEY]["purchaseOrder"]["poNumber"], po.po_number.as_str());
+        assert!(data[SUMMARY_KEY]["purchaseOrder"].get("items").is_none());
+        // The detailed result and its JSON artifact share a blob
+        assert_eq!(keys(&stored).len(), 2);
+        assert_eq!(blobs.len(), 2);
+
+        let record = agent.task_store().get(&task.id).unwrap().unwrap();
+        let request = serde_json::to_value(&record.request).unwrap();
+        assert_eq!(request, serde_json::to_value(crate::testing::message(&po)).unwrap());
+        assert_eq!(ProcessingResult::from_status(&record.task.status).unwrap().summary.total_items, 40);
+
+        store.remove(&task.id).unwrap();
+        assert!(blobs.is_empty());
+    }
+
+    /// Reads the task of every blob it writes, as a backend indexing blobs by task might
+    struct TaskReadingBlobStore {
+        blobs: MemoryBlobStore,
+        tasks: Arc<MemoryTaskStore>,
+    }
+
+    impl BlobStore for TaskReadingBlobStore {
+        fn name(&self) -> &str {
+            "task-reading"
+        }
+
+        fn put(&self, key: &str, content: &[u8]) -> StoreResult<()> {
+            self.tasks.get(key.split('/').nth(1).unwrap_or_default())?;
+            self.blobs.put(key, content)
+        }
+
+        fn get(&self, key: &str) -> StoreResult<Option<Vec<u8>>> {
+            self.blobs.get(key)
+        }
+
+        fn delete(&self, key: &str) -> StoreResult<()> {
+            self.blobs.delete(key)
+        }
+    }
+
+    #[test]
+    fn test_updates_touch_blobs_outside_the_store_lock() {
+        let inner = Arc::new(MemoryTaskStore::new());
+        let blobs = Arc::new(TaskReadingBlobStore { blobs: MemoryBlobStore::new(), tasks: inner.clone() });
+        let store = Arc::new(OffloadingTaskStore::new(inner.clone(), blobs.clone(), 64));
+        let status = TaskStatus { state: a2a::TaskState::Working, message: None, timestamp: String::new() };
+        let task = a2a::Task { id: "po-1".to_string(), session_id: None, status, artifacts: None };
+        let request = crate::testing::message(&crate::testing::huge_order(5));
+        store.put(TaskRecord::new(task, request.clone())).unwrap();
+
+        // Each update writes a new status message blob, and none is lost to a concurrent one
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let store = store.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..10 {
+                        store
+                            .update("po-1", &mut |record| {
+                                record.task.status.timestamp.push('x');
+                                record.task.status.message = Some(Message {
+                                    role: "agent".to_string(),
+                                    parts: vec![Part::Text { text: record.task.status.timestamp.repeat(8) }],
+                                });
+                            })
+                            .unwrap();
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let record = store.get("po-1").unwrap().unwrap();
+        assert_eq!(record.task.status.timestamp.len(), 40);
+        assert_eq!(serde_json::to_value(&record.request).unwrap(), serde_json::to_value(&request).unwrap());
+        // Only the request and the latest status message are left in the blob store
+        assert_eq!(keys(&inner.get("po-1").unwrap().unwrap()).len(), 2);
+        assert_eq!(blobs.blobs.len(), 2);
+    }
+
+    #[test]
+    fn test_file_blobs_stay_under_the_root() {
+        let root = std::env::temp_dir().join(format!("po-blobs-{}", uuid::Uuid::new_v4()));
+        let blobs = FileBlobStore::new(&root).unwrap();
+        blobs.put("tasks/t-1/abc", b"payload").unwrap();
+        assert_eq!(blobs.get("tasks/t-1/abc").unwrap().as_deref(), Some(&b"payload"[..]));
+        blobs.delete("tasks/t-1/abc").unwrap();
+        blobs.delete("tasks/t-1/abc").unwrap();
+        assert!(blobs.get("tasks/t-1/abc").unwrap().is_none());
+        assert!(blobs.put("tasks/../../etc/passwd", b"x").is_err());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,98 @@
+//! Blocking HTTP for the cloud blob stores
+//!
+//! Blob stores are synchronous, like the task store they sit behind, but are
+//! called from async handlers, where `block_on` would panic. Requests are run
+//! by a runtime of their own on a dedicated thread while the caller blocks for
+//! the reply. A caller on a worker of the multi-threaded runtime blocks inside
+//! `block_in_place`, so Tokio moves the worker's other tasks to another thread
+//! rather than leaving them waiting out the round trip.
+
+use std::sync::mpsc as std_mpsc;
+use std::time::Duration;
+use tokio::runtime::{Handle, RuntimeFlavor};
+use tokio::sync::mpsc;
+
+use crate::store::{StoreError, StoreResult};
+
+/// Status and body of a response
+pub(super) struct Reply {
+    pub status: reqwest::StatusCode,
+    pub body: Vec<u8>,
+}
+
+impl Reply {
+    /// The start of the body, for error messages
+    pub fn excerpt(&self) -> String {
+        let text = String::from_utf8_lossy(&self.body);
+        text.chars().take(300).collect()
+    }
+}
+
+type Job = (reqwest::Request, std_mpsc::Sender<Result<Reply, String>>);
+
+/// Runs requests on a thread of its own; the thread ends when this is dropped
+pub(super) struct HttpBridge {
+    jobs: mpsc::UnboundedSender<Job>,
+}
+
+impl HttpBridge {
+    pub fn new(name: &str, timeout: Duration) -> Result<Self, String> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| format!("Failed to start the {} HTTP runtime: {}", name, e))?;
+        let client = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .map_err(|e| format!("Failed to build the {} HTTP client: {}", name, e))?;
+        let (jobs, mut receiver) = mpsc::unbounded_channel::<Job>();
+        std::thread::Builder::new()
+            .name(format!("{}-http", name))
+            .spawn(move || {
+                runtime.block_on(async move {
+                    while let Some((request, reply)) = receiver.recv().await {
+                        let client = client.clone();
+                        tokio::spawn(async move {
+                            let outcome = async {
+                                let response = client.execute(request).await?;
+                                let status = response.status();
+                                let body = response.bytes().await?.to_vec();
+                                Ok::<_, reqwest::Error>(Reply { status, body })
+                            }
+                            .await;
+                            // The caller may have given up waiting
+                            let _ = reply.send(outcome.map_err(|e| e.to_string()));
+                        });
+                    }
+                })
+            })
+            .map_err(|e| format!("Failed to start the {} HTTP thread: {}", name, e))?;
+        Ok(Self { jobs })
+    }
+
+    /// Send `request` and wait for its response
+    pub fn execute(&self, request: reqwest::Request) -> StoreResult<Reply> {
+        let (reply, response) = std_mpsc::channel();
+        self.jobs.send((request, reply)).map_err(|_| StoreError("The blob HTTP thread has stopped".to_string()))?;
+        let wait = || response.recv();
+        // block_in_place panics on a current-thread runtime, which has no other worker to hand tasks to
+        let received = match Handle::try_current() {
+            Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => tokio::task::block_in_place(wait),
+            _ => wait(),
+        };
+        received.map_err(|_| StoreError("The blob HTTP thread has stopped".to_string()))?.map_err(StoreError)
+    }
+}
+
+/// `value` percent-encoded, leaving only unreserved characters and, if `keep_slash`, `/`
+pub(super) fn encode(value: &str, keep_slash: bool) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => encoded.push(byte as char),
+            b'/' if keep_slash => encoded.push('/'),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
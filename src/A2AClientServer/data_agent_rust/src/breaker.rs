@@ -0,0 +1,202 @@
+//! Circuit breakers for downstream integrations
+//!
+//! A breaker counts consecutive failed calls to one downstream target. Once
+//! `failure_threshold` is reached it opens, and calls fail fast without being
+//! made. After `open_for` it lets a single probe through: success closes the
+//! breaker, failure opens it again.
+
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// When a breaker opens and how long it stays open
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct BreakerConfig {
+    /// Consecutive failures that open the breaker; 0 disables it
+    pub failure_threshold: u32,
+    /// How long an open breaker fails calls before letting a probe through
+    #[serde(with = "humantime_serde")]
+    pub open_for: Duration,
+}
+
+impl Default for BreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            open_for: Duration::from_secs(30),
+        }
+    }
+}
+
+/// State of a breaker as reported on `/health`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BreakerState {
+    Closed,
+    Open,
+    /// A probe call is allowed, or in flight, to test whether the target recovered
+    HalfOpen,
+}
+
+/// A breaker's state and failure count
+#[derive(Debug, Clone, Serialize)]
+pub struct BreakerStatus {
+    pub state: BreakerState,
+    pub consecutive_failures: u32,
+}
+
+#[derive(Debug)]
+enum Inner {
+    Closed { failures: u32 },
+    Open { until: Instant, failures: u32 },
+    HalfOpen { probing: bool, failures: u32 },
+}
+
+/// The breaker guarding one downstream target
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    name: String,
+    config: BreakerConfig,
+    inner: Mutex<Inner>,
+}
+
+impl CircuitBreaker {
+    pub fn new(name: &str, config: BreakerConfig) -> Self {
+        Self {
+            name: name.to_string(),
+            config,
+            inner: Mutex::new(Inner::Closed { failures: 0 }),
+        }
+    }
+
+    /// Whether a call may be made now; `Err` with the time left while the breaker is open
+    ///
+    /// An allowed call must be followed by `record_success` or `record_failure`.
+    pub fn try_acquire(&self) -> Result<(), Duration> {
+        self.try_acquire_at(Instant::now())
+    }
+
+    fn try_acquire_at(&self, now: Instant) -> Result<(), Duration> {
+        let mut inner = self.inner.lock().unwrap();
+        match *inner {
+            Inner::Closed { .. } => Ok(()),
+            Inner::Open { until, failures } if now >= until => {
+                *inner = Inner::HalfOpen { probing: true, failures };
+                info!(breaker = %self.name, "circuit half-open, probing");
+                Ok(())
+            }
+            Inner::Open { until, .. } => Err(until - now),
+            Inner::HalfOpen { probing: false, failures } => {
+                *inner = Inner::HalfOpen { probing: true, failures };
+                Ok(())
+            }
+            Inner::HalfOpen { probing: true, .. } => Err(self.config.open_for),
+        }
+    }
+
+    /// The call went through; the breaker closes
+    pub fn record_success(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        if matches!(*inner, Inner::HalfOpen { .. } | Inner::Open { .. }) {
+            info!(breaker = %self.name, "circuit closed");
+        }
+        *inner = Inner::Closed { failures: 0 };
+    }
+
+    /// The call failed; the breaker opens once the threshold is reached, or again after a failed probe
+    pub fn record_failure(&self) {
+        self.record_failure_at(Instant::now())
+    }
+
+    fn record_failure_at(&self, now: Instant) {
+        let mut inner = self.inner.lock().unwrap();
+        let failures = match *inner {
+            Inner::Closed { failures } | Inner::Open { failures, .. } | Inner::HalfOpen { failures, .. } => failures + 1,
+        };
+        let threshold = self.config.failure_threshold;
+        let probe_failed = matches!(*inner, Inner::HalfOpen { .. } | Inner::Open { .. });
+        *inner = if threshold > 0 && (probe_failed || failures >= threshold) {
+            warn!(breaker = %self.name, failures, open_for = ?self.config.open_for, "circuit open");
+            Inner::Open { until: now + self.config.open_for, failures }
+        } else {
+            Inner::Closed { failures }
+        };
+    }
+
+    pub fn status(&self) -> BreakerStatus {
+        match *self.inner.lock().unwrap() {
+            Inner::Closed { failures } => BreakerStatus { state: BreakerState::Closed, consecutive_failures: failures },
+            Inner::Open { failures, .. } => BreakerStatus { state: BreakerState::Open, consecutive_failures: failures },
+            Inner::HalfOpen { failures, .. } => BreakerStatus { state: BreakerState::HalfOpen, consecutive_failures: failures },
+        }
+    }
+}
+
+/// Every breaker of the agent, created on first use and keyed by target
+#[derive(Debug, Default)]
+pub struct CircuitBreakers {
+    breakers: DashMap<String, Arc<CircuitBreaker>>,
+}
+
+impl CircuitBreakers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The breaker named `name`, created with `config` if it does not exist yet
+    pub fn get(&self, name: &str, config: &BreakerConfig) -> Arc<CircuitBreaker> {
+        self.breakers
+            .entry(name.to_string())
+            .or_insert_with(|| Arc::new(CircuitBreaker::new(name, config.clone())))
+            .clone()
+    }
+
+    /// Status of every breaker by name
+    pub fn statuses(&self) -> BTreeMap<String, BreakerStatus> {
+        self.breakers.iter().map(|entry| (entry.key().clone(), entry.value().status())).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_opens_after_threshold_and_probes_after_cooldown() {
+        let breaker = CircuitBreaker::new("webhook:erp", BreakerConfig { failure_threshold: 3, open_for: Duration::from_secs(10) });
+        let start = Instant::now();
+
+        for _ in 0..2 {
+            assert!(breaker.try_acquire_at(start).is_ok());
+            breaker.record_failure_at(start);
+        }
+        assert_eq!(breaker.status().state, BreakerState::Closed);
+        breaker.record_failure_at(start);
+        assert_eq!(breaker.status().state, BreakerState::Open);
+        assert_eq!(breaker.try_acquire_at(start + Duration::from_secs(4)), Err(Duration::from_secs(6)));
+
+        // One probe after the cooldown; a failed probe reopens at once
+        let later = start + Duration::from_secs(10);
+        assert!(breaker.try_acquire_at(later).is_ok());
+        assert_eq!(breaker.status().state, BreakerState::HalfOpen);
+        assert!(breaker.try_acquire_at(later).is_err());
+        breaker.record_failure_at(later);
+        assert_eq!(breaker.status().state, BreakerState::Open);
+
+        let recovered = later + Duration::from_secs(10);
+        assert!(breaker.try_acquire_at(recovered).is_ok());
+        breaker.record_success();
+        assert_eq!(breaker.status().state, BreakerState::Closed);
+        assert_eq!(breaker.status().consecutive_failures, 0);
+
+        let disabled = CircuitBreaker::new("off", BreakerConfig { failure_threshold: 0, ..Default::default() });
+        for _ in 0..10 {
+            disabled.record_failure_at(start);
+        }
+        assert!(disabled.try_acquire_at(start).is_ok());
+    }
+}
@@ -0,0 +1,249 @@
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::agent::PurchaseOrder;
+use crate::onboarding::BankAccount;
+use crate::severity::{Check, Findings};
+use crate::store::StoreResult;
+
+/// Supplier master data
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Supplier {
+    pub name: String,
+    pub address_line1: String,
+    #[serde(default)]
+    pub address_line2: Option<String>,
+    pub city: String,
+    pub state: String,
+    pub postal_code: String,
+    pub country: String,
+    /// VAT number or EIN, copied onto converted purchase orders
+    #[serde(default)]
+    pub tax_id: Option<String>,
+    /// Account the supplier is paid into, checked for duplicates when suppliers are onboarded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bank_account: Option<BankAccount>,
+}
+
+/// An orderable item and its current price
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CatalogItem {
+    pub item_code: String,
+    pub description: String,
+    pub unit_price: f64,
+    /// Name of the supplier the item is bought from
+    #[serde(default)]
+    pub supplier: Option<String>,
+    /// Fewest units the supplier accepts on one line
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_order_quantity: Option<u32>,
+    /// The item is sold in packs of this many units, so quantities must be multiples of it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pack_size: Option<u32>,
+}
+
+impl CatalogItem {
+    /// The smallest quantity of at least `quantity` meeting the minimum order quantity
+    /// and pack size
+    pub fn order_quantity(&self, quantity: u32) -> u32 {
+        let quantity = quantity.max(self.min_order_quantity.unwrap_or(0));
+        match self.pack_size {
+            Some(pack) if pack > 1 => quantity.div_ceil(pack) * pack,
+            _ => quantity,
+        }
+    }
+}
+
+/// Supplier and item master data loaded at startup
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct CatalogConfig {
+    pub suppliers: Vec<Supplier>,
+    pub items: Vec<CatalogItem>,
+}
+
+/// Storage of supplier master data, keyed case-insensitively by name
+pub trait SupplierStore: Send + Sync {
+    fn get(&self, name: &str) -> StoreResult<Option<Supplier>>;
+
+    /// Insert or replace the supplier with the same name
+    fn put(&self, supplier: Supplier) -> StoreResult<()>;
+
+    /// Every supplier, ordered by name
+    fn list(&self) -> StoreResult<Vec<Supplier>>;
+}
+
+/// Storage of catalog items, keyed case-insensitively by item code
+pub trait CatalogStore: Send + Sync {
+    fn get(&self, item_code: &str) -> StoreResult<Option<CatalogItem>>;
+
+    /// Insert or replace the item with the same code
+    fn put(&self, item: CatalogItem) -> StoreResult<()>;
+
+    /// Every item, ordered by item code
+    fn list(&self) -> StoreResult<Vec<CatalogItem>>;
+}
+
+fn key(value: &str) -> String {
+    value.trim().to_lowercase()
+}
+
+/// Flag lines ordering less than their catalog item's minimum order quantity or
+/// a quantity that is not a whole number of packs, suggesting the quantity to order
+///
+/// Lines whose item is not in the catalog, or that order nothing, are left alone.
+pub fn validate_quantities(catalog: &dyn CatalogStore, po: &PurchaseOrder, findings: &mut Findings) -> StoreResult<()> {
+    for (index, line) in po.items.iter().enumerate() {
+        if line.quantity == 0 || line.item_code.trim().is_empty() {
+            continue;
+        }
+        let Some(item) = catalog.get(&line.item_code)? else {
+            continue;
+        };
+        let suggested = item.order_quantity(line.quantity);
+        if suggested == line.quantity {
+            continue;
+        }
+        let rule = match item.min_order_quantity {
+            Some(minimum) if line.quantity < minimum => format!("is below the minimum order quantity of {}", minimum),
+            _ => format!("is not a multiple of the pack size of {}", item.pack_size.unwrap_or(1)),
+        };
+        findings.push(
+            Check::OrderQuantity,
+            format!(
+                "Item {} quantity {} {} for {}; order {} instead",
+                index + 1,
+                line.quantity,
+                rule,
+                item.item_code,
+                suggested
+            ),
+        );
+    }
+    Ok(())
+}
+
+/// In-memory supplier store used by default
+#[derive(Default)]
+pub struct MemorySupplierStore {
+    suppliers: DashMap<String, Supplier>,
+}
+
+impl MemorySupplierStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Store seeded with the configured suppliers
+    pub fn from_config(config: &CatalogConfig) -> Self {
+        let store = Self::new();
+        for supplier in &config.suppliers {
+            store.suppliers.insert(key(&supplier.name), supplier.clone());
+        }
+        store
+    }
+}
+
+impl SupplierStore for MemorySupplierStore {
+    fn get(&self, name: &str) -> StoreResult<Option<Supplier>> {
+        Ok(self.suppliers.get(&key(name)).map(|s| s.clone()))
+    }
+
+    fn put(&self, supplier: Supplier) -> StoreResult<()> {
+        self.suppliers.insert(key(&supplier.name), supplier);
+        Ok(())
+    }
+
+    fn list(&self) -> StoreResult<Vec<Supplier>> {
+        let mut suppliers: Vec<_> = self.suppliers.iter().map(|s| s.value().clone()).collect();
+        suppliers.sort_by_key(|s| key(&s.name));
+        Ok(suppliers)
+    }
+}
+
+/// In-memory catalog store used by default
+#[derive(Default)]
+pub struct MemoryCatalogStore {
+    items: DashMap<String, CatalogItem>,
+}
+
+impl MemoryCatalogStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Store seeded with the configured items
+    pub fn from_config(config: &CatalogConfig) -> Self {
+        let store = Self::new();
+        for item in &config.items {
+            store.items.insert(key(&item.item_code), item.clone());
+        }
+        store
+    }
+}
+
+impl CatalogStore for MemoryCatalogStore {
+    fn get(&self, item_code: &str) -> StoreResult<Option<CatalogItem>> {
+        Ok(self.items.get(&key(item_code)).map(|i| i.clone()))
+    }
+
+    fn put(&self, item: CatalogItem) -> StoreResult<()> {
+        self.items.insert(key(&item.item_code), item);
+        Ok(())
+    }
+
+    fn list(&self) -> StoreResult<Vec<CatalogItem>> {
+        let mut items: Vec<_> = self.items.iter().map(|i| i.value().clone()).collect();
+        items.sort_by_key(|i| key(&i.item_code));
+        Ok(items)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_order_quantity_rounds_up_to_minimum_and_packs() {
+        let item = CatalogItem {
+            item_code: "P-1".to_string(),
+            description: "Paper".to_string(),
+            unit_price: 4.99,
+            supplier: None,
+            min_order_quantity: Some(10),
+            pack_size: Some(4),
+        };
+        assert_eq!(item.order_quantity(3), 12);
+        assert_eq!(item.order_quantity(13), 16);
+        assert_eq!(item.order_quantity(20), 20);
+        let loose = CatalogItem { min_order_quantity: None, pack_size: None, ..item };
+        assert_eq!(loose.order_quantity(3), 3);
+    }
+
+    #[test]
+    fn test_validation_suggests_catalog_quantities() {
+        let catalog = MemoryCatalogStore::new();
+        let item = |code: &str, min_order_quantity, pack_size| CatalogItem {
+            item_code: code.to_string(),
+            description: code.to_string(),
+            unit_price: 1.0,
+            supplier: None,
+            min_order_quantity,
+            pack_size,
+        };
+        catalog.put(item("P-1", Some(10), None)).unwrap();
+        catalog.put(item("T-2", None, Some(6))).unwrap();
+        let agent = crate::agent::PurchaseOrderAgent::new().with_catalog_store(std::sync::Arc::new(catalog));
+        let po = crate::testing::order_of(0.0825, &[("p-1", "Paper", 4, 1.0), ("T-2", "Toner", 8, 1.0), ("X-9", "Other", 1, 1.0)]);
+
+        let (errors, warnings) = agent.validate_purchase_order(&po);
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(
+            warnings,
+            [
+                "Item 1 quantity 4 is below the minimum order quantity of 10 for P-1; order 10 instead",
+                "Item 2 quantity 8 is not a multiple of the pack size of 6 for T-2; order 12 instead",
+            ]
+        );
+    }
+}
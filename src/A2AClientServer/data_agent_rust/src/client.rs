@@ -0,0 +1,409 @@
+use a2a::Message;
+use chrono::Utc;
+use reqwest::{Method, StatusCode, Url};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::Duration;
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+use crate::a2a_agent_card::A2AAgentCard;
+use crate::idempotency::IDEMPOTENCY_KEY_HEADER;
+use crate::server::TaskResponse;
+use crate::signing::{self, SIGNATURE_HEADER, TIMESTAMP_HEADER};
+use crate::webhooks::backoff;
+
+/// Path other agents publish their agent card at
+pub const AGENT_CARD_PATH: &str = "/.well-known/agent.json";
+
+/// Settings for calling another agent over HTTP
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ClientConfig {
+    /// Per-request timeout
+    #[serde(with = "humantime_serde")]
+    pub timeout: Duration,
+    /// Attempts per call, including the first
+    pub max_attempts: u32,
+    /// Delay before the first retry; doubles on every further attempt
+    #[serde(with = "humantime_serde")]
+    pub initial_backoff: Duration,
+    /// Upper bound on the delay between attempts
+    #[serde(with = "humantime_serde")]
+    pub max_backoff: Duration,
+    /// Bearer token sent with every request
+    pub bearer_token: Option<String>,
+    /// Shared secret used to sign task submissions (see `[signing]`)
+    pub signing_secret: Option<String>,
+    /// When failed task submissions are retried
+    pub submission_retries: SubmissionRetries,
+    /// Settings for agents at particular base URLs, overriding the ones above
+    pub targets: BTreeMap<String, TargetPolicy>,
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(30),
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(5),
+            bearer_token: None,
+            signing_secret: None,
+            submission_retries: SubmissionRetries::default(),
+            targets: BTreeMap::new(),
+        }
+    }
+}
+
+impl ClientConfig {
+    /// The settings for the agent at `base_url`, with its entry under `targets` applied
+    pub fn for_target(&self, base_url: &str) -> ClientConfig {
+        let mut config = self.clone();
+        let base_url = base_url.trim_end_matches('/');
+        let Some(policy) = self.targets.iter().find(|(url, _)| url.trim_end_matches('/') == base_url).map(|(_, policy)| policy) else {
+            return config;
+        };
+        config.timeout = policy.timeout.unwrap_or(config.timeout);
+        config.max_attempts = policy.max_attempts.unwrap_or(config.max_attempts);
+        config.initial_backoff = policy.initial_backoff.unwrap_or(config.initial_backoff);
+        config.max_backoff = policy.max_backoff.unwrap_or(config.max_backoff);
+        config.submission_retries = policy.submission_retries.unwrap_or(config.submission_retries);
+        config
+    }
+}
+
+/// When a failed task submission is tried again
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SubmissionRetries {
+    /// Never; a failed submission is reported straight away
+    Never,
+    /// Only when it cannot have been processed: connection failures and `429`/`503` responses
+    #[default]
+    Safe,
+    /// Whenever a read would be, sending an `Idempotency-Key` so the agent processes it at most once
+    ///
+    /// The remote agent must honour `Idempotency-Key`, as this crate's server does.
+    Idempotent,
+}
+
+/// Retry and timeout settings for one agent; unset fields keep the defaults they override
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct TargetPolicy {
+    #[serde(with = "humantime_serde")]
+    pub timeout: Option<Duration>,
+    pub max_attempts: Option<u32>,
+    #[serde(with = "humantime_serde")]
+    pub initial_backoff: Option<Duration>,
+    #[serde(with = "humantime_serde")]
+    pub max_backoff: Option<Duration>,
+    pub submission_retries: Option<SubmissionRetries>,
+}
+
+/// Error returned by [`A2AClient`] calls
+#[derive(Debug)]
+pub enum ClientError {
+    /// The request could not be sent or the response not read
+    Http(reqwest::Error),
+    /// The agent answered with a non-success status
+    Status { status: StatusCode, body: String },
+    /// The agent accepted the request but reported an error
+    Remote(String),
+    /// The URL or response body was not understood
+    Invalid(String),
+}
+
+impl ClientError {
+    /// Whether trying the same request again might succeed
+    fn is_transient(&self, idempotent: bool) -> bool {
+        match self {
+            // A timed-out submission may still have been processed
+            ClientError::Http(e) => e.is_connect() || (idempotent && (e.is_timeout() || e.is_request())),
+            ClientError::Status { status, .. } => {
+                *status == StatusCode::TOO_MANY_REQUESTS
+                    || *status == StatusCode::SERVICE_UNAVAILABLE
+                    || (idempotent && (status.is_server_error() || *status == StatusCode::REQUEST_TIMEOUT))
+                    // An earlier attempt with the same idempotency key is still being processed
+                    || (idempotent && *status == StatusCode::CONFLICT)
+            }
+            ClientError::Remote(_) | ClientError::Invalid(_) => false,
+        }
+    }
+
+    /// Whether the error points at the agent being down rather than at the request
+    pub fn is_outage(&self) -> bool {
+        match self {
+            ClientError::Http(_) => true,
+            ClientError::Status { status, .. } => {
+                status.is_server_error() || *status == StatusCode::TOO_MANY_REQUESTS || *status == StatusCode::REQUEST_TIMEOUT
+            }
+            ClientError::Remote(_) | ClientError::Invalid(_) => false,
+        }
+    }
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::Http(e) => write!(f, "A2A request failed: {}", e),
+            ClientError::Status { status, body } => write!(f, "A2A agent responded {}: {}", status, body),
+            ClientError::Remote(message) => write!(f, "A2A agent reported an error: {}", message),
+            ClientError::Invalid(message) => write!(f, "Invalid A2A exchange: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+impl From<reqwest::Error> for ClientError {
+    fn from(e: reqwest::Error) -> Self {
+        ClientError::Http(e)
+    }
+}
+
+/// HTTP client for another agent built on this crate's task API
+///
+/// Reads are retried on connection errors, timeouts and `5xx`/`408`/`429`
+/// responses. Task submissions are retried as `submission_retries` says; by
+/// default only when they cannot have been processed. Settings listed under
+/// `targets` for the agent's base URL take precedence.
+#[derive(Debug, Clone)]
+pub struct A2AClient {
+    http: reqwest::Client,
+    base_url: Url,
+    config: ClientConfig,
+}
+
+impl A2AClient {
+    pub fn new(base_url: &str, config: ClientConfig) -> Result<Self, ClientError> {
+        let config = config.for_target(base_url);
+        let base_url = Url::parse(base_url).map_err(|e| ClientError::Invalid(format!("{}: {}", base_url, e)))?;
+        let http = reqwest::Client::builder().timeout(config.timeout).build()?;
+        Ok(Self { http, base_url, config })
+    }
+
+    /// Base URL of the remote agent
+    pub fn base_url(&self) -> &Url {
+        &self.base_url
+    }
+
+    /// Fetch the remote agent's card from `/.well-known/agent.json`
+    pub async fn discover(&self) -> Result<A2AAgentCard, ClientError> {
+        self.call(Method::GET, AGENT_CARD_PATH, None).await
+    }
+
+    /// Fetch the remote agent's health report
+    pub async fn health(&self) -> Result<serde_json::Value, ClientError> {
+        self.call(Method::GET, "/health", None).await
+    }
+
+    /// Submit a message as a new task
+    pub async fn send_task(&self, message: Message) -> Result<TaskResponse, ClientError> {
+        let body = serde_json::to_vec(&serde_json::json!({ "message": message }))
+            .map_err(|e| ClientError::Invalid(e.to_string()))?;
+        let (key, max_attempts) = match self.config.submission_retries {
+            SubmissionRetries::Never => (None, 1),
+            SubmissionRetries::Safe => (None, self.config.max_attempts),
+            SubmissionRetries::Idempotent => (Some(Uuid::new_v4().to_string()), self.config.max_attempts),
+        };
+        let response: TaskResponse = self.call_with(Method::POST, "/agent/task", Some(body), key.as_deref(), max_attempts).await?;
+        match response.error {
+            Some(error) => Err(ClientError::Remote(error)),
+            None => Ok(response),
+        }
+    }
+
+    /// Fetch a task by ID
+    pub async fn get_task(&self, task_id: &str) -> Result<TaskResponse, ClientError> {
+        self.call(Method::GET, &format!("/agent/task/{}", task_id), None).await
+    }
+
+    /// Cancel a task by ID
+    pub async fn cancel_task(&self, task_id: &str) -> Result<TaskResponse, ClientError> {
+        self.call(Method::POST, &format!("/agent/task/{}/cancel", task_id), Some(Vec::new())).await
+    }
+
+    async fn call<T: DeserializeOwned>(&self, method: Method, path: &str, body: Option<Vec<u8>>) -> Result<T, ClientError> {
+        self.call_with(method, path, body, None, self.config.max_attempts).await
+    }
+
+    /// Make a call, retrying transient failures; a request with an idempotency key is retried like a read
+    async fn call_with<T: DeserializeOwned>(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<Vec<u8>>,
+        idempotency_key: Option<&str>,
+        max_attempts: u32,
+    ) -> Result<T, ClientError> {
+        let url = self
+            .base_url
+            .join(path)
+            .map_err(|e| ClientError::Invalid(format!("{}: {}", path, e)))?;
+        let idempotent = method == Method::GET || idempotency_key.is_some();
+
+        let mut attempt = 1;
+        loop {
+            match self.attempt(method.clone(), url.clone(), body.as_deref(), idempotency_key).await {
+                Ok(value) => return Ok(value),
+                Err(e) if attempt < max_attempts && e.is_transient(idempotent) => {
+                    let mut delay = backoff(attempt, self.config.initial_backoff, self.config.max_backoff);
+                    // A signed retry waits for the next second, so its signature differs and is not refused as a replay
+                    if body.is_some() && self.config.signing_secret.is_some() {
+                        let millis = 1000 - u64::from(Utc::now().timestamp_subsec_millis().min(999));
+                        delay = delay.max(Duration::from_millis(millis));
+                    }
+                    warn!(url = %url, attempt, error = %e, delay_ms = delay.as_millis() as u64, "A2A call failed, retrying");
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    async fn attempt<T: DeserializeOwned>(
+        &self,
+        method: Method,
+        url: Url,
+        body: Option<&[u8]>,
+        idempotency_key: Option<&str>,
+    ) -> Result<T, ClientError> {
+        debug!(method = %method, url = %url, "A2A call");
+        let mut request = self.http.request(method, url);
+        if let Some(token) = &self.config.bearer_token {
+            request = request.bearer_auth(token);
+        }
+        if let Some(key) = idempotency_key {
+            request = request.header(IDEMPOTENCY_KEY_HEADER, key);
+        }
+        if let Some(body) = body {
+            if let Some(secret) = &self.config.signing_secret {
+                let timestamp = Utc::now().timestamp();
+                request = request
+                    .header(TIMESTAMP_HEADER, timestamp.to_string())
+                    .header(SIGNATURE_HEADER, signing::sign(secret, timestamp, body));
+            }
+            request = request
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .body(body.to_vec());
+        }
+
+        let response = request.send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(ClientError::Status { status, body });
+        }
+        let bytes = response.bytes().await?;
+        serde_json::from_slice(&bytes).map_err(|e| ClientError::Invalid(format!("unexpected response body: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::PurchaseOrderAgent;
+    use crate::server::create_router;
+    use axum::routing::get;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+
+    async fn serve(router: axum::Router) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+        format!("http://{}", addr)
+    }
+
+    fn fast_retries() -> ClientConfig {
+        ClientConfig {
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(5),
+            ..ClientConfig::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_discover_and_task_round_trip() {
+        let url = serve(create_router(Arc::new(PurchaseOrderAgent::new()))).await;
+        let client = A2AClient::new(&url, fast_retries()).unwrap();
+
+        let card = client.discover().await.unwrap();
+        assert!(!card.skills.is_empty());
+
+        let err = client.send_task(Message { role: "user".to_string(), parts: vec![] }).await.unwrap_err();
+        assert!(matches!(err, ClientError::Remote(_)), "{}", err);
+
+        let missing = client.get_task("missing").await.unwrap_err();
+        assert!(matches!(missing, ClientError::Status { status: StatusCode::NOT_FOUND, .. }));
+    }
+
+    #[tokio::test]
+    async fn test_reads_retry_transient_failures() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let counter = calls.clone();
+        let router = axum::Router::new().route(
+            "/health",
+            get(move || {
+                let counter = counter.clone();
+                async move {
+                    if counter.fetch_add(1, Ordering::SeqCst) == 0 {
+                        Err(axum::http::StatusCode::BAD_GATEWAY)
+                    } else {
+                        Ok(axum::Json(serde_json::json!({ "status": "healthy" })))
+                    }
+                }
+            }),
+        );
+        let url = serve(router).await;
+
+        let health = A2AClient::new(&url, fast_retries()).unwrap().health().await.unwrap();
+        assert_eq!(health["status"], "healthy");
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_idempotent_submissions_retry_with_one_key() {
+        let keys = Arc::new(std::sync::Mutex::new(Vec::<Option<String>>::new()));
+        let seen = keys.clone();
+        let router = axum::Router::new().route(
+            "/agent/task",
+            axum::routing::post(move |headers: axum::http::HeaderMap| {
+                let seen = seen.clone();
+                async move {
+                    let mut seen = seen.lock().unwrap();
+                    seen.push(headers.get(IDEMPOTENCY_KEY_HEADER).map(|key| key.to_str().unwrap().to_string()));
+                    if seen.len() % 2 == 1 {
+                        Err(axum::http::StatusCode::BAD_GATEWAY)
+                    } else {
+                        Ok(axum::Json(serde_json::json!({ "task_id": "t-1", "status": "completed" })))
+                    }
+                }
+            }),
+        );
+        let url = serve(router).await;
+        let message = || Message { role: "user".to_string(), parts: vec![] };
+
+        // By default a submission that may have been processed is not retried
+        let err = A2AClient::new(&url, fast_retries()).unwrap().send_task(message()).await.unwrap_err();
+        assert!(matches!(err, ClientError::Status { status: StatusCode::BAD_GATEWAY, .. }), "{}", err);
+        keys.lock().unwrap().clear();
+
+        let mut config = fast_retries();
+        let policy = TargetPolicy { submission_retries: Some(SubmissionRetries::Idempotent), ..TargetPolicy::default() };
+        config.targets.insert(format!("{}/", url), policy);
+        assert_eq!(config.for_target("http://elsewhere").submission_retries, SubmissionRetries::Safe);
+
+        let task = A2AClient::new(&url, config).unwrap().send_task(message()).await.unwrap();
+        assert_eq!(task.task_id, "t-1");
+        let keys = keys.lock().unwrap();
+        assert_eq!(keys.len(), 2);
+        assert!(keys[0].is_some());
+        assert_eq!(keys[0], keys[1]);
+    }
+}
@@ -0,0 +1,261 @@
+use serde::Deserialize;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::approval::ApprovalConfig;
+use crate::attachments::AttachmentsConfig;
+use crate::auth::AuthConfig;
+use crate::catalog::CatalogConfig;
+use crate::dead_letter::DeadLetterConfig;
+use crate::directory::DirectoryConfig;
+use crate::deadline::DeadlineConfig;
+use crate::delegation::DelegationConfig;
+use crate::email::EmailConfig;
+use crate::events::EventsConfig;
+use crate::expense::ExpenseConfig;
+use crate::flags::FeatureFlags;
+use crate::intake::IntakeConfig;
+use crate::invoice::InvoiceMatchConfig;
+use crate::invoice_agent::InvoiceProcessingConfig;
+use crate::monthly_close::MonthlyCloseConfig;
+use crate::onboarding::OnboardingConfig;
+use crate::plugins::PluginsConfig;
+use crate::price_history::PriceHistoryConfig;
+use crate::processing::ProcessingConfig;
+use crate::rbac::RbacConfig;
+use crate::registry::RegistryConfig;
+use crate::requisition::RequisitionConfig;
+use crate::result_cache::ResultCacheConfig;
+use crate::retention::RetentionConfig;
+use crate::returns::ReturnsConfig;
+use crate::risk::RiskConfig;
+use crate::rounding::ReconciliationConfig;
+use crate::schedule::SchedulerConfig;
+use crate::sales_tax::SalesTaxConfig;
+use crate::screening::ScreeningConfig;
+use crate::redaction::RedactionConfig;
+use crate::idoc::IdocConfig;
+use crate::severity::SeverityPolicy;
+use crate::signing::SigningConfig;
+use crate::snapshot::SnapshotConfig;
+use crate::blob::BlobConfig;
+use crate::masking::MaskingConfig;
+use crate::searches::SearchesConfig;
+use crate::scheduled_exports::ExportsConfig;
+use crate::tax_id::TaxIdConfig;
+use crate::thresholds::HighValueConfig;
+use crate::webhooks::WebhooksConfig;
+
+/// Environment variable pointing at the server configuration file
+pub const CONFIG_PATH_ENV: &str = "PO_AGENT_CONFIG";
+
+/// Configuration file looked up in the working directory when no path is given
+pub const DEFAULT_CONFIG_PATH: &str = "config.toml";
+
+/// Top-level server configuration
+///
+/// Every section is optional; a missing file or section falls back to the
+/// defaults, which leave all optional features switched off.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ServerConfig {
+    /// Listener settings
+    pub server: ListenConfig,
+    /// Bearer token authentication settings
+    pub auth: AuthConfig,
+    /// Role-based access control for authenticated endpoints
+    pub rbac: RbacConfig,
+    /// HMAC signature verification for inbound task submissions
+    pub signing: SigningConfig,
+    /// Expiry of finished tasks
+    pub retention: RetentionConfig,
+    /// Persistence of the task store between restarts
+    pub snapshot: SnapshotConfig,
+    /// Outbound publishing of task lifecycle events
+    pub events: EventsConfig,
+    /// Queue consumers feeding purchase orders into the agent
+    pub intake: IntakeConfig,
+    /// Outbound webhook notifications
+    pub webhooks: WebhooksConfig,
+    /// Email notifications for purchase orders needing attention
+    pub email: EmailConfig,
+    /// Forwarding of purchase orders to a downstream approval agent
+    pub delegation: DelegationConfig,
+    /// Other agents whose cards are resolved and cached
+    pub registry: RegistryConfig,
+    /// Tolerances of the invoice matching skill
+    pub invoice_match: InvoiceMatchConfig,
+    /// Payment terms checks of the invoice processing skill
+    pub invoice_processing: InvoiceProcessingConfig,
+    /// Spending policy of the expense report skill
+    pub expenses: ExpenseConfig,
+    /// Supplier and item master data
+    pub catalog: CatalogConfig,
+    /// Checks and registration of new suppliers
+    pub onboarding: OnboardingConfig,
+    /// Conversion of purchase requisitions into purchase orders
+    pub requisition: RequisitionConfig,
+    /// Recurring purchase orders generated on a schedule
+    pub scheduler: SchedulerConfig,
+    /// Supplier risk scoring during processing
+    pub risk: RiskConfig,
+    /// Denied-party screening of suppliers
+    pub screening: ScreeningConfig,
+    /// Supplier tax ID checks
+    pub tax_id: TaxIdConfig,
+    /// Severity overrides of validation checks
+    pub severity: SeverityPolicy,
+    /// Redaction of personal data in logs and snapshots
+    pub redaction: RedactionConfig,
+    /// SAP IDoc export of approved orders
+    pub idoc: IdocConfig,
+    /// Background processing of submitted tasks and its queue limits
+    pub processing: ProcessingConfig,
+    /// Deadline after which working tasks are failed
+    pub deadline: DeadlineConfig,
+    /// Human approval chain and escalation
+    pub approval: ApprovalConfig,
+    /// Limits on documents attached to tasks
+    pub attachments: AttachmentsConfig,
+    /// High-value thresholds per department and currency
+    pub high_value: HighValueConfig,
+    /// Whether credit and return orders with negative amounts are accepted
+    pub returns: ReturnsConfig,
+    /// Rounding and tolerance of the line total, tax and grand total checks
+    pub reconciliation: ReconciliationConfig,
+    /// US sales tax rates the order tax rate is checked against
+    pub sales_tax: SalesTaxConfig,
+    /// Caching of processing results for identical resubmitted orders
+    pub result_cache: ResultCacheConfig,
+    /// Warnings on unit prices above the trailing average of earlier orders
+    pub price_history: PriceHistoryConfig,
+    /// Month-end close reports, and the directory they are written to on a schedule
+    pub monthly_close: MonthlyCloseConfig,
+    /// Messages kept because no purchase order could be parsed from them
+    pub dead_letters: DeadLetterConfig,
+    /// Departments and users orders are checked against
+    pub directory: DirectoryConfig,
+    /// Where large payloads are kept instead of the task store
+    pub blobs: BlobConfig,
+    /// Sensitive fields masked in responses to low-privilege callers
+    pub masking: MaskingConfig,
+    /// Where saved searches of the task list are kept
+    pub searches: SearchesConfig,
+    /// Results exported on a schedule to the blob store or a webhook
+    pub exports: ExportsConfig,
+    /// Optional subsystems and skills switched on or off
+    pub flags: FeatureFlags,
+    /// WebAssembly validation rules and output transformers loaded at startup
+    pub plugins: PluginsConfig,
+}
+
+/// Listener settings for main_server
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ListenConfig {
+    /// Socket address to bind, unless a socket is passed by socket activation
+    pub bind_address: String,
+    /// Bind with `SO_REUSEPORT`, so a new process can bind the address while the old one drains
+    pub reuse_port: bool,
+    /// Listen on `bind_address`; turn off to serve only on `unix_socket`
+    pub tcp: bool,
+    /// Also serve plain HTTP on a Unix domain socket at this path (Unix only)
+    pub unix_socket: Option<PathBuf>,
+    /// Permissions of the socket file, e.g. `0o660` to let a proxy in the same group connect
+    pub unix_socket_mode: Option<u32>,
+    /// Serve HTTPS instead of plain HTTP when present (requires the `tls` feature)
+    pub tls: Option<TlsConfig>,
+    /// `max-age` advertised to clients caching the agent card
+    pub agent_card_max_age_seconds: u64,
+    /// URL clients reach the agent at, advertised on the agent card; derived from
+    /// the bind address when unset, which only suits local use behind no proxy
+    pub public_url: Option<String>,
+    /// Documentation link advertised on the agent card
+    pub documentation_url: Option<String>,
+    /// Largest request body accepted by the submission endpoints, in bytes
+    pub max_request_bytes: usize,
+    /// How long an `Idempotency-Key` keeps returning the task it created
+    #[serde(with = "humantime_serde")]
+    pub idempotency_window: Duration,
+}
+
+impl Default for ListenConfig {
+    fn default() -> Self {
+        Self {
+            bind_address: "0.0.0.0:8080".to_string(),
+            reuse_port: false,
+            tcp: true,
+            unix_socket: None,
+            unix_socket_mode: None,
+            tls: None,
+            agent_card_max_age_seconds: 300,
+            public_url: None,
+            documentation_url: None,
+            max_request_bytes: 64 * 1024 * 1024,
+            idempotency_window: Duration::from_secs(24 * 60 * 60),
+        }
+    }
+}
+
+impl ListenConfig {
+    /// URL advertised on the agent card
+    pub fn advertised_url(&self) -> String {
+        if let Some(url) = &self.public_url {
+            return url.trim_end_matches('/').to_string();
+        }
+        let scheme = if self.tls.is_some() { "https" } else { "http" };
+        let address = match self.bind_address.rsplit_once(':') {
+            Some(("0.0.0.0" | "[::]", port)) => format!("localhost:{}", port),
+            _ => self.bind_address.clone(),
+        };
+        format!("{}://{}", scheme, address)
+    }
+}
+
+/// Certificate settings for native HTTPS serving
+#[derive(Debug, Clone, Deserialize)]
+pub struct TlsConfig {
+    /// PEM file holding the server certificate chain
+    pub cert_path: String,
+    /// PEM file holding the server private key
+    pub key_path: String,
+    /// PEM bundle of CAs trusted to sign client certificates; enables mTLS when set
+    #[serde(default)]
+    pub client_ca_path: Option<String>,
+    /// Reject connections without a client certificate (only with `client_ca_path`)
+    #[serde(default = "default_true")]
+    pub require_client_cert: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl ServerConfig {
+    /// Load configuration from a TOML file
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read config file {}: {}", path.display(), e))?;
+        Self::from_toml(&contents)
+            .map_err(|e| format!("Invalid config file {}: {}", path.display(), e).into())
+    }
+
+    /// Parse configuration from a TOML string, clearing the sections of subsystems switched off in `[flags]`
+    pub fn from_toml(contents: &str) -> Result<Self, Box<dyn Error>> {
+        let mut config: Self = toml::from_str(contents)?;
+        FeatureFlags::apply(&mut config);
+        Ok(config)
+    }
+
+    /// Load configuration from `$PO_AGENT_CONFIG`, falling back to `config.toml`
+    /// in the working directory, or the defaults if neither exists
+    pub fn load() -> Result<Self, Box<dyn Error>> {
+        match std::env::var(CONFIG_PATH_ENV) {
+            Ok(path) => Self::from_file(path),
+            Err(_) if Path::new(DEFAULT_CONFIG_PATH).exists() => Self::from_file(DEFAULT_CONFIG_PATH),
+            Err(_) => Ok(Self::default()),
+        }
+    }
+}
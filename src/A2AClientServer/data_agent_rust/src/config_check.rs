@@ -0,0 +1,236 @@
+//! Startup self-test of a configuration, run by `main_server --check-config`
+//!
+//! Everything the server would load or open before serving is tried in turn:
+//! the directory, the denied-party list, saved searches, plugins, blob and task stores,
+//! TLS certificates, the Unix socket, snapshot, and the optional integrations
+//! the binary must have been built with. Nothing is started and no task is written. Each step
+//! is reported on its own line, so a bad deploy fails with the exact problem
+//! before the first request reaches it.
+
+use std::fmt;
+use std::path::Path;
+
+use crate::blob::{self, BlobBackend};
+use crate::config::ServerConfig;
+use crate::directory;
+use crate::email;
+use crate::plugins;
+use crate::risk;
+use crate::screening::DeniedPartyList;
+use crate::searches;
+use crate::snapshot;
+use crate::store::{MemoryTaskStore, TaskStore};
+
+/// Blob key read to check that the blob store answers
+const PROBE_BLOB_KEY: &str = "config-check-probe";
+
+/// Outcome of one step of the check
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckStep {
+    /// What was checked, e.g. `directory` or `screening`
+    pub name: &'static str,
+    /// What was found, or why it failed
+    pub detail: String,
+    pub passed: bool,
+}
+
+/// Every step of a check, in the order they ran
+#[derive(Debug, Clone, Default)]
+pub struct CheckReport {
+    pub steps: Vec<CheckStep>,
+}
+
+impl CheckReport {
+    pub fn pass(&mut self, name: &'static str, detail: impl Into<String>) {
+        self.steps.push(CheckStep { name, detail: detail.into(), passed: true });
+    }
+
+    pub fn fail(&mut self, name: &'static str, detail: impl ToString) {
+        self.steps.push(CheckStep { name, detail: detail.to_string(), passed: false });
+    }
+
+    fn record<T, E: fmt::Display>(&mut self, name: &'static str, result: Result<T, E>, detail: impl FnOnce(T) -> String) {
+        match result {
+            Ok(value) => self.pass(name, detail(value)),
+            Err(e) => self.fail(name, e),
+        }
+    }
+
+    /// Whether every step passed
+    pub fn passed(&self) -> bool {
+        self.steps.iter().all(|step| step.passed)
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &CheckStep> {
+        self.steps.iter().filter(|step| !step.passed)
+    }
+}
+
+impl fmt::Display for CheckReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for step in &self.steps {
+            writeln!(f, "{} {:<16} {}", if step.passed { "ok  " } else { "FAIL" }, step.name, step.detail)?;
+        }
+        let failures = self.failures().count();
+        match failures {
+            0 => write!(f, "Configuration OK: {} checks passed", self.steps.len()),
+            _ => write!(f, "Configuration has {} problem(s) in {} checks", failures, self.steps.len()),
+        }
+    }
+}
+
+/// Check everything `config` sets up, as the server would at startup
+pub async fn run(config: &ServerConfig) -> CheckReport {
+    let mut report = CheckReport::default();
+
+    match directory::provider(&config.directory) {
+        Ok(provider) => {
+            let name = provider.name().to_string();
+            report.record("directory", provider.load().await, |loaded| {
+                format!("{} departments and {} users from {}", loaded.departments.len(), loaded.users.len(), name)
+            });
+        }
+        Err(e) => report.fail("directory", e),
+    }
+
+    if let Some(path) = &config.screening.list {
+        report.record("screening", DeniedPartyList::load(path), |list| {
+            format!("{} denied parties from {}", list.len(), path.display())
+        });
+    }
+
+    report.record("searches", searches::store(&config.searches), |store| match (&config.searches.path, store.list()) {
+        (Some(path), Ok(searches)) => format!("{} saved searches from {}", searches.len(), path.display()),
+        _ => "kept in memory".to_string(),
+    });
+
+    if let Some(directory) = &config.plugins.directory {
+        report.record("plugins", plugins::load(&config.plugins), |loaded| {
+            format!("{} validation rules and {} output transformers from {}", loaded.rule_names().len(), loaded.transformer_names().len(), directory.display())
+        });
+    }
+
+    report.record("risk", risk::scorer(&config.risk), |scorer| match (scorer, &config.risk.url) {
+        (None, _) => "disabled".to_string(),
+        (Some(_), Some(url)) => format!("scored by {}", url),
+        (Some(_), None) => "built-in rules".to_string(),
+    });
+
+    report.record("email", email::publisher(&config.email), |publisher| match publisher {
+        Some(_) => format!("sending from {}", config.email.from),
+        None => "disabled".to_string(),
+    });
+
+    let blobs = match blob::store(&config.blobs) {
+        Ok(Some(blobs)) => match blobs.get(PROBE_BLOB_KEY) {
+            Ok(_) => {
+                report.pass("blobs", format!("{} backend answers", blobs.name()));
+                true
+            }
+            Err(e) => {
+                report.fail("blobs", format!("{} backend did not answer: {}", blobs.name(), e));
+                true
+            }
+        },
+        Ok(None) => {
+            report.pass("blobs", "payloads stay in the task store");
+            false
+        }
+        Err(e) => {
+            report.fail("blobs", e);
+            config.blobs.backend != BlobBackend::None
+        }
+    };
+
+    // main_server keeps tasks in memory; a snapshot restored at startup must parse
+    let store = MemoryTaskStore::new();
+    match (&config.snapshot.path, config.snapshot.load_on_startup) {
+        (Some(path), true) => report.record("store", snapshot::load(&store, path), |restored| {
+            format!("{} tasks would be restored from {}", restored, path.display())
+        }),
+        _ => report.record("store", store.stats(), |_| "in memory".to_string()),
+    }
+
+    report.record("exports", config.exports.validate(blobs), |_| format!("{} jobs", config.exports.jobs.len()));
+
+    if let Some(directory) = &config.monthly_close.directory {
+        check_directory(&mut report, "monthly_close", directory);
+    }
+
+    match (&config.server.unix_socket, config.server.tcp) {
+        (Some(path), _) => match path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            Some(parent) if !parent.is_dir() => report.fail("unix_socket", format!("{} is not a directory", parent.display())),
+            _ if !cfg!(unix) => report.fail("unix_socket", "Unix domain sockets are only supported on Unix"),
+            _ => report.pass("unix_socket", format!("serving on {}", path.display())),
+        },
+        (None, false) => report.fail("unix_socket", "[server] tcp is off and no unix_socket is configured"),
+        (None, true) => {}
+    }
+
+    if let Some(tls) = &config.server.tls {
+        #[cfg(feature = "tls")]
+        report.record("tls", crate::tls::rustls_config(tls), |_| format!("certificate {}", tls.cert_path));
+        #[cfg(not(feature = "tls"))]
+        {
+            let _ = tls;
+            report.fail("tls", "[server.tls] is configured but this binary was built without the `tls` feature");
+        }
+    }
+
+    for (name, configured, built) in [
+        ("kafka", config.events.kafka.is_some(), cfg!(feature = "kafka")),
+        ("nats", config.events.nats.is_some(), cfg!(feature = "nats")),
+        ("amqp", config.intake.amqp.is_some(), cfg!(feature = "amqp")),
+        ("sqs", config.intake.sqs.is_some(), cfg!(feature = "sqs")),
+    ] {
+        match (configured, built) {
+            (true, true) => report.pass(name, "configured"),
+            (true, false) => report.fail(name, format!("configured but this binary was built without the `{}` feature", name)),
+            (false, _) => {}
+        }
+    }
+
+    report
+}
+
+fn check_directory(report: &mut CheckReport, name: &'static str, directory: &Path) {
+    match std::fs::metadata(directory) {
+        Ok(metadata) if metadata.is_dir() => report.pass(name, format!("writing to {}", directory.display())),
+        Ok(_) => report.fail(name, format!("{} is not a directory", directory.display())),
+        Err(e) => report.fail(name, format!("{}: {}", directory.display(), e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_each_problem_is_reported() {
+        let report = run(&ServerConfig::default()).await;
+        assert!(report.passed(), "{}", report);
+
+        let missing = std::env::temp_dir().join("po-agent-check-config-missing");
+        let config: ServerConfig = toml::from_str(&format!(
+            r#"
+            [screening]
+            list = "{list}"
+
+            [monthly_close]
+            directory = "{list}"
+
+            [[exports.jobs]]
+            id = "nightly"
+            cron = "0 2 * * *"
+            destination = {{ type = "blob", key = "exports/{{date}}.csv" }}
+            "#,
+            list = missing.join("denied.csv").display()
+        ))
+        .unwrap();
+        let report = run(&config).await;
+        let failed: Vec<&str> = report.failures().map(|step| step.name).collect();
+        assert_eq!(failed, ["screening", "exports", "monthly_close"]);
+        assert!(report.to_string().contains("Failed to read denied-party list"));
+        assert!(report.to_string().ends_with("Configuration has 3 problem(s) in 9 checks"));
+    }
+}
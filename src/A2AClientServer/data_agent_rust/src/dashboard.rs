@@ -0,0 +1,467 @@
+use a2a::{Part, TaskStatus};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{Html, IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use chrono::{DateTime, Utc};
+use maud::{html, Markup, PreEscaped, DOCTYPE};
+use serde::Deserialize;
+use std::sync::Arc;
+
+use crate::agent::ProcessingResult;
+use crate::labels;
+use crate::processing::Priority;
+use crate::searches::{Period, SearchFilter};
+use crate::server::AppState;
+use crate::skills::ValidationReport;
+use crate::store::{task_state_name, TaskRecord};
+
+/// Tasks listed when the query does not ask for a number
+pub const DEFAULT_LIMIT: usize = 50;
+
+const STATES: [&str; 7] = ["scheduled", "submitted", "working", "input-required", "completed", "canceled", "failed"];
+
+const STYLE: &str = "body{font-family:system-ui,sans-serif;margin:2rem;color:#222}\
+table{border-collapse:collapse;width:100%}th,td{text-align:left;padding:.35rem .6rem;border-bottom:1px solid #ddd}\
+th{background:#f4f4f4}.completed{color:#1a7f37}.failed,.canceled{color:#cf222e}.working,.submitted{color:#9a6700}\
+.errors li{color:#cf222e}.warnings li{color:#9a6700}form{margin:1rem 0}pre{background:#f6f8fa;padding:1rem;overflow:auto}";
+
+/// Filters of the task list
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct DashboardQuery {
+    /// Task state, e.g. `failed`
+    pub state: Option<String>,
+    /// Skill that handled the task
+    pub skill: Option<String>,
+    /// Queue priority, e.g. `urgent`
+    pub priority: Option<String>,
+    /// Case-insensitive text matched against PO number, supplier and task ID
+    pub q: Option<String>,
+    /// Buyer department of the order
+    pub department: Option<String>,
+    /// When the task was created: `today`, `last_7_days`, `this_month` or `last_month`
+    pub period: Option<String>,
+    /// Label selector such as `source:sap,period:2026-Q3`
+    pub labels: Option<String>,
+    /// Saved search whose filters apply, under any given here
+    pub view: Option<String>,
+    pub limit: usize,
+}
+
+impl Default for DashboardQuery {
+    fn default() -> Self {
+        Self {
+            state: None,
+            skill: None,
+            priority: None,
+            q: None,
+            department: None,
+            period: None,
+            labels: None,
+            view: None,
+            limit: DEFAULT_LIMIT,
+        }
+    }
+}
+
+impl DashboardQuery {
+    /// The filters given as query parameters, without any saved search
+    pub fn filter(&self) -> Result<SearchFilter, String> {
+        let period = self.period.as_deref().filter(|s| !s.is_empty()).map(str::parse::<Period>).transpose()?;
+        Ok(SearchFilter {
+            state: self.state.clone(),
+            skill: self.skill.clone(),
+            priority: self.priority.clone(),
+            q: self.q.clone(),
+            department: self.department.clone(),
+            period,
+            labels: labels::parse_selector(self.labels.as_deref().unwrap_or(""))?,
+        })
+    }
+}
+
+/// Dashboard routes; the caller is responsible for layering auth and the read permission
+pub fn dashboard_routes() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/dashboard", get(task_list))
+        .route("/dashboard/tasks/:task_id", get(task_detail))
+}
+
+/// What the agent decided about a task, from whichever result its status carries
+struct Outcome {
+    po_number: Option<String>,
+    supplier: Option<String>,
+    department: Option<String>,
+    grand_total: Option<f64>,
+    decision: String,
+    errors: Vec<String>,
+    warnings: Vec<String>,
+}
+
+impl Outcome {
+    fn of(status: &TaskStatus) -> Self {
+        if let Some(result) = ProcessingResult::from_status(status) {
+            return Self {
+                po_number: Some(result.po_number),
+                supplier: Some(result.supplier_name),
+                department: Some(result.buyer_department),
+                grand_total: Some(result.grand_total),
+                decision: result.status,
+                errors: result.validation_errors,
+                warnings: result.warnings,
+            };
+        }
+        let parts = status.message.as_ref().map(|message| message.parts.as_slice()).unwrap_or_default();
+        let report = parts.iter().find_map(|part| match part {
+            Part::Data { data } => ValidationReport::deserialize(data).ok(),
+            _ => None,
+        });
+        if let Some(report) = report {
+            return Self {
+                po_number: Some(report.po_number),
+                supplier: None,
+                department: None,
+                grand_total: None,
+                decision: if report.valid { "VALID" } else { "INVALID" }.to_string(),
+                errors: report.validation_errors,
+                warnings: report.warnings,
+            };
+        }
+        // A failure without a result reports its reason as text
+        let text = parts.iter().find_map(|part| match part {
+            Part::Text { text } => Some(text.clone()),
+            _ => None,
+        });
+        let failed = task_state_name(&status.state) == "failed";
+        Self {
+            po_number: None,
+            supplier: None,
+            department: None,
+            grand_total: None,
+            decision: String::new(),
+            errors: if failed { text.into_iter().collect() } else { Vec::new() },
+            warnings: Vec::new(),
+        }
+    }
+}
+
+/// Time stamps as the dashboard shows them, in UTC
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+fn page(title: &str, body: Markup) -> Html<String> {
+    let markup = html! {
+        (DOCTYPE)
+        html lang="en" {
+            head {
+                meta charset="utf-8";
+                title { (title) }
+                style { (PreEscaped(STYLE)) }
+            }
+            body { (body) }
+        }
+    };
+    Html(markup.into_string())
+}
+
+fn options(selected: Option<&str>, values: &[&str]) -> Markup {
+    html! {
+        option value="" { "any" }
+        @for value in values {
+            option selected[selected == Some(*value)] { (value) }
+        }
+    }
+}
+
+fn list_items(class: &str, items: &[String]) -> Markup {
+    html! {
+        ul class=(class) {
+            @for item in items {
+                li { (item) }
+            }
+        }
+    }
+}
+
+fn matches(record: &TaskRecord, outcome: &Outcome, filter: &SearchFilter, now: DateTime<Utc>) -> bool {
+    let state = filter.state.as_deref().filter(|s| !s.is_empty());
+    let skill = filter.skill.as_deref().filter(|s| !s.is_empty());
+    let priority = filter.priority.as_deref().filter(|s| !s.is_empty());
+    let text = filter.q.as_deref().map(str::trim).filter(|s| !s.is_empty()).map(str::to_lowercase);
+    let department = filter.department.as_deref().map(str::trim).filter(|s| !s.is_empty());
+    state.is_none_or(|state| record.state_name() == state)
+        && skill.is_none_or(|skill| record.skill_id.as_deref() == Some(skill))
+        && priority.is_none_or(|priority| record.priority.as_str() == priority)
+        && text.is_none_or(|text| {
+            [Some(&record.task.id), outcome.po_number.as_ref(), outcome.supplier.as_ref()]
+                .into_iter()
+                .flatten()
+                .any(|field| field.to_lowercase().contains(&text))
+        })
+        && department.is_none_or(|department| outcome.department.as_deref().is_some_and(|d| d.eq_ignore_ascii_case(department)))
+        && filter.period.is_none_or(|period| period.contains(record.created_at, now))
+        && labels::matches(&record.labels, &filter.labels)
+}
+
+/// Recent tasks, newest first, with their state and what the agent decided
+async fn task_list(State(state): State<Arc<AppState>>, Query(query): Query<DashboardQuery>) -> Response {
+    let mut filter = match query.filter() {
+        Ok(filter) => filter,
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
+    let view = query.view.as_deref().filter(|s| !s.is_empty());
+    if let Some(name) = view {
+        match state.agent.saved_searches().get(name) {
+            Ok(Some(search)) => filter = search.filter.overridden_by(filter),
+            Ok(None) => return (StatusCode::NOT_FOUND, format!("No saved search named {}", name)).into_response(),
+            Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+        }
+    }
+    let views = state.agent.saved_searches().list().unwrap_or_default();
+    let now = Utc::now();
+    let mut records = match state.agent.task_store().list() {
+        Ok(records) => records,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+    records.sort_by_key(|record| std::cmp::Reverse(record.updated_at));
+
+    let mut rows = Vec::new();
+    for record in &records {
+        let outcome = Outcome::of(&record.task.status);
+        if !matches(record, &outcome, &filter, now) {
+            continue;
+        }
+        if rows.len() == query.limit {
+            break;
+        }
+        rows.push((record, outcome));
+    }
+
+    let skills = state.agent.skills().ids();
+    let views: Vec<&str> = views.iter().map(|search| search.name.as_str()).collect();
+    let body = html! {
+        h1 { "Purchase order tasks" }
+        form method="get" action="/dashboard" {
+            label { "View " select name="view" { (options(view, &views)) } } " "
+            label { "State " select name="state" { (options(query.state.as_deref(), &STATES)) } } " "
+            label { "Skill " select name="skill" { (options(query.skill.as_deref(), &skills)) } } " "
+            label {
+                "Priority "
+                select name="priority" { (options(query.priority.as_deref(), &Priority::ALL.map(|priority| priority.as_str()))) }
+            } " "
+            label { "Search " input name="q" value=(query.q.as_deref().unwrap_or("")) placeholder="PO number, supplier or task ID"; } " "
+            label { "Department " input name="department" value=(query.department.as_deref().unwrap_or("")); } " "
+            label { "Created " select name="period" { (options(query.period.as_deref(), &Period::ALL.map(Period::as_str))) } } " "
+            label { "Labels " input name="labels" value=(query.labels.as_deref().unwrap_or("")) placeholder="source:sap,period:2026-Q3"; } " "
+            button type="submit" { "Filter" }
+        }
+        p { "Showing " (rows.len()) " of " (records.len()) " tasks" }
+        table {
+            thead {
+                tr {
+                    th { "Task" } th { "Updated (UTC)" } th { "State" } th { "Skill" } th { "PO number" }
+                    th { "Supplier" } th { "Grand total" } th { "Decision" } th { "Errors" } th { "Warnings" }
+                }
+            }
+            tbody {
+                @for (record, outcome) in &rows {
+                    tr {
+                        td { a href={ "/dashboard/tasks/" (record.task.id) } { (record.task.id) } }
+                        td { (record.updated_at.format(TIME_FORMAT)) }
+                        td class=(record.state_name()) { (record.state_name()) }
+                        td { (record.skill_id.as_deref().unwrap_or("")) }
+                        td { (outcome.po_number.as_deref().unwrap_or("")) }
+                        td { (outcome.supplier.as_deref().unwrap_or("")) }
+                        td {
+                            @if let Some(total) = outcome.grand_total {
+                                (format!("{:.2}", total))
+                            }
+                        }
+                        td { (outcome.decision) }
+                        td { (outcome.errors.len()) }
+                        td { (outcome.warnings.len()) }
+                    }
+                }
+            }
+        }
+    };
+    page("Purchase order tasks", body).into_response()
+}
+
+/// One task: its validation findings, event history, artifacts and raw result
+async fn task_detail(State(state): State<Arc<AppState>>, Path(task_id): Path<String>) -> Response {
+    let record = match state.agent.task_store().get(&task_id) {
+        Ok(Some(record)) => record,
+        Ok(None) => {
+            let body = html! {
+                h1 { "Task not found" }
+                p { "No task " (task_id) ". " a href="/dashboard" { "Back to tasks" } }
+            };
+            return (StatusCode::NOT_FOUND, page("Task not found", body)).into_response();
+        }
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+    let outcome = Outcome::of(&record.task.status);
+    let result = ProcessingResult::from_status(&record.task.status);
+    let id = &record.task.id;
+    let labels = record.labels.iter().map(|(key, value)| format!("{}:{}", key, value)).collect::<Vec<_>>().join(", ");
+    let attachments = state.agent.attachments().for_task(id).unwrap_or_default();
+    let events = state.agent.event_log().events(id).unwrap_or_default();
+    let raw = serde_json::to_string_pretty(&record.task.status).unwrap_or_default();
+
+    let body = html! {
+        p { a href="/dashboard" { "← All tasks" } }
+        h1 { "Task " (id) }
+        table {
+            tr { th { "State" } td class=(record.state_name()) { (record.state_name()) } }
+            tr { th { "Skill" } td { (record.skill_id.as_deref().unwrap_or("")) } }
+            tr { th { "PO number" } td { (outcome.po_number.as_deref().unwrap_or("")) } }
+            tr { th { "Decision" } td { (outcome.decision) } }
+            tr { th { "Created (UTC)" } td { (record.created_at.format(TIME_FORMAT)) } }
+            tr { th { "Updated (UTC)" } td { (record.updated_at.format(TIME_FORMAT)) } }
+            tr { th { "Labels" } td { (labels) } }
+        }
+
+        h2 { "Validation errors" }
+        @if outcome.errors.is_empty() { p { "None" } } @else { (list_items("errors", &outcome.errors)) }
+        h2 { "Warnings" }
+        @if outcome.warnings.is_empty() { p { "None" } } @else { (list_items("warnings", &outcome.warnings)) }
+
+        @if let Some(result) = &result {
+            @if let Some(risk) = &result.risk {
+                h2 { "Supplier risk" }
+                p { (format!("{:.0} ({:?}, scored by ", risk.score, risk.level)) (risk.scorer) ")" }
+                (list_items("", &risk.rationale))
+            }
+            @if !result.screening_matches.is_empty() {
+                h2 { "Denied-party matches" }
+                (list_items("errors", &result.screening_matches.iter().map(|m| m.error()).collect::<Vec<_>>()))
+            }
+            @if !result.adjustments.is_empty() {
+                h2 { "Normalized amounts" }
+                (list_items("", &result.adjustments.iter().map(|a| format!("{}: {} → {}", a.field, a.from, a.to)).collect::<Vec<_>>()))
+            }
+        }
+
+        @if let Some(artifacts) = &record.task.artifacts {
+            h2 { "Artifacts" }
+            ul {
+                @for (index, artifact) in artifacts.iter().enumerate() {
+                    li { a href={ "/agent/task/" (id) "/artifacts/" (index) } { (artifact.name.as_deref().unwrap_or("artifact")) } }
+                }
+            }
+        }
+
+        @if !attachments.is_empty() {
+            h2 { "Attachments" }
+            ul {
+                @for attachment in &attachments {
+                    li {
+                        a href={ "/agent/task/" (id) "/attachments/" (attachment.id) } { (attachment.name) }
+                        " (" (attachment.mime_type) ", " (attachment.size) " bytes)"
+                    }
+                }
+            }
+        }
+
+        @if !events.is_empty() {
+            h2 { "History" }
+            table {
+                thead { tr { th { "#" } th { "At (UTC)" } th { "Event" } } }
+                tbody {
+                    @for event in &events {
+                        tr { td { (event.sequence) } td { (event.at.format(TIME_FORMAT)) } td { (event.kind.name()) } }
+                    }
+                }
+            }
+        }
+
+        h2 { "Raw status" }
+        pre { (raw) }
+    };
+    page(&format!("Task {}", id), body).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{create_router, PurchaseOrderAgent};
+    use a2a::{A2AProtocol, Message};
+    use axum::body::{to_bytes, Body};
+    use axum::http::{Request, StatusCode};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    fn order(po_number: &str, supplier_name: &str) -> Message {
+        let mut po = crate::testing::valid_order();
+        po.po_number = po_number.to_string();
+        po.supplier_name = supplier_name.to_string();
+        crate::testing::message(&po)
+    }
+
+    async fn html(app: &axum::Router, uri: &str) -> (StatusCode, String) {
+        let response = app.clone().oneshot(Request::get(uri).body(Body::empty()).unwrap()).await.unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_dashboard_lists_filters_and_details_tasks() {
+        let agent = Arc::new(PurchaseOrderAgent::new());
+        let approved = agent.send_task(order("PO-1", "Acme <Office> Supply")).await.unwrap();
+        let failed = agent.send_task(order("PO-2", "")).await.unwrap();
+        let app = create_router(agent);
+
+        let (status, body) = html(&app, "/dashboard").await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.contains(&approved.id) && body.contains(&failed.id));
+        assert!(body.contains("Acme &lt;Office&gt; Supply"), "supplier is escaped");
+
+        let (_, body) = html(&app, "/dashboard?state=failed").await;
+        assert!(body.contains(&failed.id) && !body.contains(&approved.id));
+        let (_, body) = html(&app, "/dashboard?q=po-1").await;
+        assert!(body.contains(&approved.id) && !body.contains(&failed.id));
+
+        let (status, body) = html(&app, &format!("/dashboard/tasks/{}", failed.id)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.contains("VALIDATION_FAILED"));
+        assert!(body.contains("<ul class=\"errors\"><li>"));
+        assert!(body.contains("<td>created</td>"));
+
+        let (status, _) = html(&app, "/dashboard/tasks/missing").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_saved_searches_are_applied_by_name() {
+        let agent = Arc::new(PurchaseOrderAgent::new());
+        let approved = agent.send_task(order("PO-1", "Acme <Office> Supply")).await.unwrap();
+        let failed = agent.send_task(order("PO-2", "")).await.unwrap();
+        let app = create_router(agent);
+
+        let search = serde_json::json!({
+            "name": "marketing-failed",
+            "filter": { "state": "failed", "department": "marketing", "period": "this_month" }
+        });
+        let save = Request::post("/agent/searches")
+            .header("content-type", "application/json")
+            .body(Body::from(search.to_string()))
+            .unwrap();
+        assert_eq!(app.clone().oneshot(save).await.unwrap().status(), StatusCode::CREATED);
+
+        let (_, body) = html(&app, "/dashboard?view=marketing-failed").await;
+        assert!(body.contains(&failed.id) && !body.contains(&approved.id));
+        let (_, body) = html(&app, "/dashboard?view=marketing-failed&state=completed").await;
+        assert!(body.contains(&approved.id) && !body.contains(&failed.id), "query parameters override the saved filters");
+        let (_, body) = html(&app, "/dashboard?department=Finance").await;
+        assert!(!body.contains(&approved.id) && !body.contains(&failed.id));
+
+        let (status, _) = html(&app, "/dashboard?view=missing").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = html(&app, "/dashboard?period=next_year").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+}
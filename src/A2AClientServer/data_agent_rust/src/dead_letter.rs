@@ -0,0 +1,250 @@
+//! Dead letters: messages no purchase order could be parsed from
+//!
+//! A message whose parts all fail to parse is kept with the parse error of
+//! each part, whether it came in over HTTP, A2A or queue intake, so it is not
+//! lost with the failed request. Once the sender fixes their format, or the
+//! parser learns to read it, an administrator replays the dead letter, as sent
+//! or with a corrected message, through normal processing.
+
+use a2a::{Message, Part, Task};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+use std::sync::Mutex;
+use uuid::Uuid;
+
+use crate::agent::{PurchaseOrderAgent, TaskOptions};
+use crate::ingest::UnparseableMessage;
+use crate::processing::Priority;
+use crate::store::{StoreError, StoreResult};
+
+/// Retention of dead letters
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct DeadLetterConfig {
+    /// Dead letters kept; the oldest is dropped to make room
+    pub max_entries: usize,
+}
+
+impl Default for DeadLetterConfig {
+    fn default() -> Self {
+        Self { max_entries: 1000 }
+    }
+}
+
+/// A message kept because no purchase order could be parsed from it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeadLetter {
+    pub id: String,
+    /// Task that failed on the message, when it was queued before being parsed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub task_id: Option<String>,
+    /// Skill the message was sent to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skill_id: Option<String>,
+    #[serde(default)]
+    pub priority: Priority,
+    /// The message as received; a queued payload that is not a message is kept in a Text part
+    pub message: Message,
+    /// Why each part failed to parse
+    pub errors: Vec<String>,
+    pub received_at: DateTime<Utc>,
+    /// Task the dead letter was replayed as, once it was replayed successfully
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replayed_task_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replayed_at: Option<DateTime<Utc>>,
+}
+
+/// Storage of dead letters
+pub trait DeadLetterStore: Send + Sync {
+    /// Insert or replace a dead letter
+    fn put(&self, letter: DeadLetter) -> StoreResult<()>;
+
+    fn get(&self, id: &str) -> StoreResult<Option<DeadLetter>>;
+
+    /// Every dead letter, newest first
+    fn list(&self) -> StoreResult<Vec<DeadLetter>>;
+}
+
+/// In-memory dead letters used by default, holding at most `max_entries`
+pub struct MemoryDeadLetterStore {
+    letters: Mutex<VecDeque<DeadLetter>>,
+    max_entries: usize,
+}
+
+impl MemoryDeadLetterStore {
+    pub fn new(config: &DeadLetterConfig) -> Self {
+        Self { letters: Mutex::new(VecDeque::new()), max_entries: config.max_entries }
+    }
+}
+
+impl Default for MemoryDeadLetterStore {
+    fn default() -> Self {
+        Self::new(&DeadLetterConfig::default())
+    }
+}
+
+impl DeadLetterStore for MemoryDeadLetterStore {
+    fn put(&self, letter: DeadLetter) -> StoreResult<()> {
+        let mut letters = self.letters.lock().map_err(|_| StoreError("dead letter store poisoned".to_string()))?;
+        if let Some(existing) = letters.iter_mut().find(|existing| existing.id == letter.id) {
+            *existing = letter;
+            return Ok(());
+        }
+        letters.push_back(letter);
+        while letters.len() > self.max_entries {
+            letters.pop_front();
+        }
+        Ok(())
+    }
+
+    fn get(&self, id: &str) -> StoreResult<Option<DeadLetter>> {
+        let letters = self.letters.lock().map_err(|_| StoreError("dead letter store poisoned".to_string()))?;
+        Ok(letters.iter().find(|letter| letter.id == id).cloned())
+    }
+
+    fn list(&self) -> StoreResult<Vec<DeadLetter>> {
+        let letters = self.letters.lock().map_err(|_| StoreError("dead letter store poisoned".to_string()))?;
+        Ok(letters.iter().rev().cloned().collect())
+    }
+}
+
+/// Keep `message` as a dead letter when `error` says no purchase order could be parsed from it
+///
+/// Returns the dead letter's ID, or `None` for any other error.
+pub fn record(
+    agent: &PurchaseOrderAgent,
+    task_id: Option<&str>,
+    skill_id: Option<&str>,
+    priority: Priority,
+    message: &Message,
+    error: &(dyn Error + 'static),
+) -> StoreResult<Option<String>> {
+    let Some(unparseable) = error.downcast_ref::<UnparseableMessage>() else {
+        return Ok(None);
+    };
+    let letter = DeadLetter {
+        id: Uuid::new_v4().to_string(),
+        task_id: task_id.map(str::to_string),
+        skill_id: skill_id.map(str::to_string),
+        priority,
+        message: message.clone(),
+        errors: unparseable.errors.clone(),
+        received_at: Utc::now(),
+        replayed_task_id: None,
+        replayed_at: None,
+    };
+    let id = letter.id.clone();
+    agent.dead_letters().put(letter)?;
+    agent.metrics().increment(
+        "po_agent_dead_letters_total",
+        "Messages kept as dead letters because no purchase order could be parsed from them",
+        &[],
+        1,
+    );
+    Ok(Some(id))
+}
+
+/// Keep a queued payload that is not even a message, as a Text part
+pub fn record_payload(agent: &PurchaseOrderAgent, payload: &[u8], error: String) -> StoreResult<Option<String>> {
+    let message = Message {
+        role: "user".to_string(),
+        parts: vec![Part::Text { text: String::from_utf8_lossy(payload).into_owned() }],
+    };
+    let error = UnparseableMessage { errors: vec![error] };
+    record(agent, None, None, Priority::default(), &message, &error)
+}
+
+/// Body of `POST /admin/dead-letters/{id}/replay`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ReplayRequest {
+    /// Message to process in place of the one kept, e.g. as the sender now formats it
+    #[serde(deserialize_with = "crate::parts::deserialize_optional_message")]
+    pub message: Option<Message>,
+}
+
+/// Why a dead letter could not be replayed
+#[derive(Debug)]
+pub enum ReplayError {
+    NotFound(String),
+    /// A dead letter is replayed at most once
+    AlreadyReplayed(String),
+    Store(StoreError),
+    /// Processing the replayed message failed; a message that still does not parse is a new dead letter
+    Processing(String),
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::NotFound(message) | ReplayError::AlreadyReplayed(message) | ReplayError::Processing(message) => {
+                f.write_str(message)
+            }
+            ReplayError::Store(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl From<StoreError> for ReplayError {
+    fn from(e: StoreError) -> Self {
+        ReplayError::Store(e)
+    }
+}
+
+/// Process a dead letter's message again, with its original skill and priority, as a new task
+pub async fn replay(agent: &PurchaseOrderAgent, id: &str, request: ReplayRequest) -> Result<Task, ReplayError> {
+    let mut letter = agent
+        .dead_letters()
+        .get(id)?
+        .ok_or_else(|| ReplayError::NotFound(format!("Dead letter {} not found", id)))?;
+    if let Some(task_id) = &letter.replayed_task_id {
+        return Err(ReplayError::AlreadyReplayed(format!("Dead letter {} was already replayed as task {}", id, task_id)));
+    }
+
+    let message = request.message.unwrap_or_else(|| letter.message.clone());
+    let options = TaskOptions { skill_id: letter.skill_id.clone(), priority: letter.priority, ..TaskOptions::default() };
+    let task = agent
+        .send_task_with_options(message, &options)
+        .await
+        .map_err(|e| ReplayError::Processing(e.to_string()))?;
+
+    letter.replayed_task_id = Some(task.id.clone());
+    letter.replayed_at = Some(Utc::now());
+    agent.dead_letters().put(letter)?;
+    Ok(task)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use a2a::A2AProtocol;
+
+    #[tokio::test]
+    async fn test_unparseable_messages_are_kept_and_replayed() {
+        let agent = PurchaseOrderAgent::new();
+        let garbled = Message {
+            role: "user".to_string(),
+            parts: vec![Part::Text { text: r#"{"purchase_order": {"po_number": "PO-1"}}"#.to_string() }],
+        };
+        assert!(agent.send_task(garbled).await.is_err());
+        let letters = agent.dead_letters().list().unwrap();
+        assert_eq!(letters.len(), 1);
+        assert!(letters[0].errors[0].starts_with("part 1: missing field"), "{:?}", letters[0].errors);
+
+        // Replaying as sent fails again, leaving a second dead letter and the first unreplayed
+        assert!(matches!(replay(&agent, &letters[0].id, ReplayRequest::default()).await, Err(ReplayError::Processing(_))));
+        assert_eq!(agent.dead_letters().list().unwrap().len(), 2);
+
+        let fixed = ReplayRequest { message: Some(crate::testing::message(&crate::testing::valid_order())) };
+        let task = replay(&agent, &letters[0].id, fixed.clone()).await.unwrap();
+        let replayed = agent.dead_letters().get(&letters[0].id).unwrap().unwrap();
+        assert_eq!(replayed.replayed_task_id.as_deref(), Some(task.id.as_str()));
+        assert!(matches!(replay(&agent, &letters[0].id, fixed).await, Err(ReplayError::AlreadyReplayed(_))));
+    }
+}
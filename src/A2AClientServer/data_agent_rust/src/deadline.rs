@@ -0,0 +1,157 @@
+//! Processing deadline for working tasks
+//!
+//! A task that stays in the working state longer than the deadline, e.g. because
+//! a downstream agent never answers, is failed with a `TIMEOUT` finding. The
+//! state change is published like any other, so webhooks and the event bus hear
+//! about it.
+
+use a2a::TaskState;
+use chrono::{DateTime, TimeZone, Utc};
+use serde::Deserialize;
+use std::error::Error;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+use crate::agent::PurchaseOrderAgent;
+use crate::store::TaskRecord;
+
+/// Code of the finding a timed-out task fails with
+pub const TIMEOUT_FINDING: &str = "TIMEOUT";
+
+/// How long a task may stay working before it is failed
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct DeadlineConfig {
+    /// Longest time a task may spend in the working state; unset disables the deadline
+    #[serde(with = "humantime_serde")]
+    pub working: Option<Duration>,
+    /// How often working tasks are checked against the deadline
+    #[serde(with = "humantime_serde")]
+    pub check_interval: Duration,
+}
+
+impl Default for DeadlineConfig {
+    fn default() -> Self {
+        Self {
+            working: None,
+            check_interval: Duration::from_secs(30),
+        }
+    }
+}
+
+impl DeadlineConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.working.is_some()
+    }
+
+    fn is_overdue(&self, record: &TaskRecord, now: DateTime<Utc>) -> bool {
+        let Some(deadline) = self.working else {
+            return false;
+        };
+        if !matches!(record.task.status.state, TaskState::Working) {
+            return false;
+        }
+        now.signed_duration_since(working_since(record)).to_std().is_ok_and(|age| age >= deadline)
+    }
+}
+
+/// When the task started working, from its status timestamp, or its last update if that does not parse
+fn working_since(record: &TaskRecord) -> DateTime<Utc> {
+    record
+        .task
+        .status
+        .timestamp
+        .parse::<i64>()
+        .ok()
+        .and_then(|secs| Utc.timestamp_opt(secs, 0).single())
+        .unwrap_or(record.updated_at)
+}
+
+/// Fail every task that has been working past the deadline
+///
+/// Returns the number of tasks timed out.
+pub fn expire_overdue(agent: &PurchaseOrderAgent, config: &DeadlineConfig, now: DateTime<Utc>) -> Result<usize, Box<dyn Error>> {
+    let Some(deadline) = config.working else {
+        return Ok(0);
+    };
+    let overdue: Vec<String> = agent
+        .task_store()
+        .list()?
+        .into_iter()
+        .filter(|record| config.is_overdue(record, now))
+        .map(|record| record.task.id)
+        .collect();
+
+    let mut timed_out = 0;
+    for task_id in overdue {
+        if agent.time_out_task(&task_id, deadline)?.is_some() {
+            timed_out += 1;
+        }
+    }
+    Ok(timed_out)
+}
+
+/// Spawn the deadline watchdog; returns `None` when no deadline is configured
+pub fn spawn_watchdog(agent: Arc<PurchaseOrderAgent>, config: DeadlineConfig) -> Option<JoinHandle<()>> {
+    if !config.is_enabled() {
+        return None;
+    }
+
+    info!(working = ?config.working, interval = ?config.check_interval, "task deadline watchdog started");
+
+    Some(tokio::spawn(async move {
+        let mut interval = tokio::time::interval(config.check_interval);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            match expire_overdue(&agent, &config, Utc::now()) {
+                Ok(0) => {}
+                Ok(timed_out) => info!(timed_out, "working tasks timed out"),
+                Err(e) => warn!(error = %e, "deadline check failed"),
+            }
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use a2a::{A2AProtocol, Message, Part, Task, TaskStatus};
+
+    fn working(id: &str, started: DateTime<Utc>) -> TaskRecord {
+        let task = Task {
+            id: id.to_string(),
+            session_id: None,
+            status: TaskStatus { state: TaskState::Working, message: None, timestamp: started.timestamp().to_string() },
+            artifacts: None,
+        };
+        TaskRecord::new(task, Message { role: "user".to_string(), parts: vec![] })
+    }
+
+    #[tokio::test]
+    async fn test_overdue_working_tasks_fail_with_timeout() {
+        let agent = PurchaseOrderAgent::new();
+        let now = Utc::now();
+        agent.task_store().put(working("stuck", now - chrono::Duration::minutes(20))).unwrap();
+        agent.task_store().put(working("busy", now - chrono::Duration::minutes(5))).unwrap();
+        let config = DeadlineConfig { working: Some(Duration::from_secs(600)), ..DeadlineConfig::default() };
+
+        assert_eq!(expire_overdue(&agent, &config, now).unwrap(), 1);
+        let stuck = agent.get_task("stuck").await.unwrap();
+        assert!(matches!(stuck.status.state, TaskState::Failed));
+        let finding = stuck.status.message.unwrap().parts.into_iter().find_map(|part| match part {
+            Part::Data { data } => Some(data),
+            _ => None,
+        });
+        assert_eq!(finding.unwrap()["finding"], TIMEOUT_FINDING);
+        assert!(matches!(agent.get_task("busy").await.unwrap().status.state, TaskState::Working));
+        assert_eq!(agent.metrics().value("po_agent_task_timeouts_total", &[]), Some(1.0));
+
+        let events = agent.event_log().events("stuck").unwrap();
+        assert_eq!(events.last().unwrap().kind.name(), "state_changed");
+        assert_eq!(expire_overdue(&agent, &config, now).unwrap(), 0);
+        assert_eq!(expire_overdue(&agent, &DeadlineConfig::default(), now).unwrap(), 0);
+    }
+}
@@ -0,0 +1,180 @@
+//! Deferred processing of tasks submitted with `process_after`
+//!
+//! A deferred task is stored as submitted and reported as `scheduled` until its
+//! time comes. The timer then hands it to the background workers, or processes
+//! it straight away when background processing is off.
+
+use a2a::TaskState;
+use chrono::{DateTime, Utc};
+use std::collections::BTreeSet;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+use crate::agent::PurchaseOrderAgent;
+
+/// The timer wakes at least this often, so clock adjustments are picked up
+const MAX_SLEEP: Duration = Duration::from_secs(60);
+
+/// Tasks waiting for their `process_after` time, earliest first
+#[derive(Debug, Default)]
+pub struct DeferredTasks {
+    due: Mutex<BTreeSet<(DateTime<Utc>, String)>>,
+    wake: Notify,
+}
+
+impl DeferredTasks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Process `task_id` once `at` has passed
+    pub fn schedule(&self, task_id: &str, at: DateTime<Utc>) {
+        self.due.lock().unwrap().insert((at, task_id.to_string()));
+        self.wake.notify_one();
+    }
+
+    /// Tasks still waiting for their time
+    pub fn len(&self) -> usize {
+        self.due.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Remove and return the tasks due at `now`
+    fn take_due(&self, now: DateTime<Utc>) -> Vec<String> {
+        let mut due = self.due.lock().unwrap();
+        let mut ready = Vec::new();
+        while due.first().is_some_and(|(at, _)| *at <= now) {
+            if let Some((_, task_id)) = due.pop_first() {
+                ready.push(task_id);
+            }
+        }
+        ready
+    }
+
+    fn next_due(&self) -> Option<DateTime<Utc>> {
+        self.due.lock().unwrap().first().map(|(at, _)| *at)
+    }
+}
+
+/// Release deferred tasks as their time comes
+///
+/// Deferred tasks already in the store, e.g. restored from a snapshot, are
+/// picked up first; ones whose time passed while the process was down run at once.
+pub fn spawn_timer(agent: Arc<PurchaseOrderAgent>) -> JoinHandle<()> {
+    match agent.task_store().list() {
+        Ok(records) => {
+            for record in records {
+                if let (TaskState::Submitted, Some(at)) = (&record.task.status.state, record.process_after) {
+                    agent.deferred().schedule(&record.task.id, at);
+                }
+            }
+        }
+        Err(e) => warn!(error = %e, "could not restore deferred tasks"),
+    }
+    info!(waiting = agent.deferred().len(), "deferred task timer started");
+
+    tokio::spawn(async move {
+        loop {
+            for task_id in agent.deferred().take_due(Utc::now()) {
+                release(&agent, task_id);
+            }
+            let wait = agent
+                .deferred()
+                .next_due()
+                .map(|due| (due - Utc::now()).to_std().unwrap_or_default().min(MAX_SLEEP))
+                .unwrap_or(MAX_SLEEP);
+            tokio::select! {
+                _ = tokio::time::sleep(wait) => {}
+                _ = agent.deferred().wake.notified() => {}
+            }
+        }
+    })
+}
+
+/// Queue a due task in its priority's lane, or process it when there is no queue
+fn release(agent: &Arc<PurchaseOrderAgent>, task_id: String) {
+    debug!(task_id = %task_id, "deferred task due");
+    let Some(queue) = agent.submission_queue() else {
+        let agent = agent.clone();
+        tokio::spawn(async move {
+            if let Err(e) = agent.process_submitted_task(&task_id).await {
+                warn!(task_id = %task_id, error = %e, "deferred processing failed");
+            }
+        });
+        return;
+    };
+
+    let priority = match agent.task_store().get(&task_id) {
+        Ok(Some(record)) => record.priority,
+        Ok(None) => return,
+        Err(e) => {
+            warn!(task_id = %task_id, error = %e, "deferred task lookup failed");
+            return;
+        }
+    };
+    match queue.reserve() {
+        Some(slot) => slot.send(task_id, priority),
+        None => {
+            // The queue is full; try again once it has had time to drain
+            let retry_at = Utc::now() + chrono::Duration::seconds(queue.retry_after_seconds() as i64);
+            agent.deferred().schedule(&task_id, retry_at);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::TaskOptions;
+    use a2a::{A2AProtocol, Message, Part};
+    use chrono::Duration as ChronoDuration;
+
+    #[test]
+    fn test_take_due_returns_tasks_up_to_now() {
+        let deferred = DeferredTasks::new();
+        let now = Utc::now();
+        deferred.schedule("later", now + ChronoDuration::hours(1));
+        deferred.schedule("now", now);
+        deferred.schedule("earlier", now - ChronoDuration::minutes(5));
+
+        assert_eq!(deferred.take_due(now), vec!["earlier".to_string(), "now".to_string()]);
+        assert_eq!(deferred.len(), 1);
+        assert_eq!(deferred.next_due(), Some(now + ChronoDuration::hours(1)));
+        assert!(deferred.take_due(now).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_scheduled_task_is_processed_when_due() {
+        let agent = Arc::new(PurchaseOrderAgent::new());
+        let message = Message {
+            role: "user".to_string(),
+            parts: vec![Part::Text { text: "not a purchase order".to_string() }],
+        };
+        let options = TaskOptions { process_after: Some(Utc::now() + ChronoDuration::milliseconds(200)), ..TaskOptions::default() };
+        let task = agent.send_task_with_options(message, &options).await.unwrap();
+        assert!(matches!(task.status.state, TaskState::Submitted));
+        let record = agent.task_store().get(&task.id).unwrap().unwrap();
+        assert_eq!(record.state_name(), "scheduled");
+        assert_eq!(agent.deferred().len(), 1);
+
+        let _timer = spawn_timer(agent.clone());
+        let mut task = agent.get_task(&task.id).await.unwrap();
+        for _ in 0..100 {
+            if !matches!(task.status.state, TaskState::Submitted) {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            task = agent.get_task(&task.id).await.unwrap();
+        }
+        assert!(matches!(task.status.state, TaskState::Failed), "{:?}", task.status.state);
+        assert!(agent.deferred().is_empty());
+        let events = agent.event_log().events(&task.id).unwrap();
+        assert!(events.iter().any(|event| event.kind.name() == "deferred"));
+    }
+}
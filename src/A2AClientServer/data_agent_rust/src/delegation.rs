@@ -0,0 +1,381 @@
+use a2a::TaskState;
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use tracing::{debug, info, warn};
+
+use crate::agent::{ProcessingResult, PurchaseOrderAgent};
+use crate::breaker::{BreakerConfig, CircuitBreaker};
+use crate::client::{A2AClient, ClientConfig, ClientError};
+use crate::events::{EventPublisher, PublishError, TaskEvent, TaskEventKind};
+use crate::readiness::Dependencies;
+use crate::registry::AgentRegistry;
+use crate::server::TaskResponse;
+
+/// Forwarding of validated purchase orders to a downstream approval agent
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct DelegationConfig {
+    /// Base URL of the downstream agent
+    pub url: Option<String>,
+    /// Without `url`, delegate to a registered agent offering this skill ID
+    pub skill: Option<String>,
+    /// Without `url`, delegate to a registered agent with a skill carrying all of these tags
+    pub tags: Vec<String>,
+    /// Name recorded as the approver; defaults to the downstream agent card's name
+    pub approver: Option<String>,
+    /// Processing statuses that are delegated
+    pub statuses: Vec<String>,
+    /// How often the downstream task is polled until it finishes
+    #[serde(with = "humantime_serde")]
+    pub poll_interval: Duration,
+    /// How long to wait for the downstream decision before giving up
+    #[serde(with = "humantime_serde")]
+    pub wait_timeout: Duration,
+    /// Timeouts, retries and credentials for calling the downstream agent
+    pub client: ClientConfig,
+    /// Stop calling a downstream agent that keeps failing; applies to each agent separately
+    pub breaker: BreakerConfig,
+}
+
+impl Default for DelegationConfig {
+    fn default() -> Self {
+        Self {
+            url: None,
+            skill: None,
+            tags: Vec::new(),
+            approver: None,
+            statuses: vec!["PENDING_APPROVAL".to_string()],
+            poll_interval: Duration::from_secs(2),
+            wait_timeout: Duration::from_secs(600),
+            client: ClientConfig::default(),
+            breaker: BreakerConfig::default(),
+        }
+    }
+}
+
+impl DelegationConfig {
+    /// Delegation is on when a downstream URL, skill or tags are configured
+    pub fn is_enabled(&self) -> bool {
+        self.url.is_some() || self.skill.is_some() || !self.tags.is_empty()
+    }
+}
+
+/// Decision read from a finished downstream task
+#[derive(Debug, Clone, PartialEq)]
+pub struct Decision {
+    pub approved: bool,
+    pub comment: Option<String>,
+}
+
+/// Interpret a downstream task, or `None` while it has not decided yet
+///
+/// A completed task approves the order unless its detailed result carries
+/// `"approved": false` or a `status` other than `APPROVED`; a failed or
+/// cancelled task rejects it. A downstream `PENDING_APPROVAL` is still undecided.
+pub fn decision(response: &TaskResponse) -> Option<Decision> {
+    let detail = response.detailed_result.as_ref();
+    let comment = detail
+        .and_then(|d| d.get("comment").or_else(|| d.get("approval_reason")))
+        .and_then(|c| c.as_str())
+        .map(str::to_string)
+        .or_else(|| response.error.clone());
+
+    match response.status.as_str() {
+        "completed" => {
+            let status = detail.and_then(|d| d.get("status")).and_then(|s| s.as_str());
+            if status == Some("PENDING_APPROVAL") {
+                return None;
+            }
+            let approved = match detail.and_then(|d| d.get("approved")).and_then(|a| a.as_bool()) {
+                Some(approved) => approved,
+                None => status.is_none_or(|s| s == "APPROVED"),
+            };
+            Some(Decision { approved, comment })
+        }
+        "failed" | "cancelled" => Some(Decision { approved: false, comment }),
+        _ => None,
+    }
+}
+
+/// Queues tasks whose processing result should be delegated
+pub struct DelegationPublisher {
+    statuses: Vec<String>,
+    queue: mpsc::UnboundedSender<String>,
+}
+
+/// Create the publisher feeding the delegation worker and the worker's queue
+pub fn queue(config: &DelegationConfig) -> (DelegationPublisher, mpsc::UnboundedReceiver<String>) {
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let publisher = DelegationPublisher {
+        statuses: config.statuses.clone(),
+        queue: sender,
+    };
+    (publisher, receiver)
+}
+
+#[async_trait]
+impl EventPublisher for DelegationPublisher {
+    fn name(&self) -> &str {
+        "delegation"
+    }
+
+    async fn publish(&self, event: &TaskEvent) -> Result<(), PublishError> {
+        let TaskEventKind::StateChanged { status } = &event.kind else {
+            return Ok(());
+        };
+        if !matches!(status.state, TaskState::Completed) {
+            return Ok(());
+        }
+        if let Some(result) = ProcessingResult::from_status(status)
+            && self.statuses.contains(&result.status)
+        {
+            self.queue
+                .send(event.task_id.clone())
+                .map_err(|_| PublishError("delegation worker has stopped".to_string()))?;
+        }
+        Ok(())
+    }
+}
+
+/// Delegate queued tasks to the downstream agent until the process exits
+pub fn spawn_worker(
+    agent: Arc<PurchaseOrderAgent>,
+    config: DelegationConfig,
+    registry: Arc<AgentRegistry>,
+    mut queue: mpsc::UnboundedReceiver<String>,
+) -> Option<JoinHandle<()>> {
+    if !config.is_enabled() {
+        return None;
+    }
+    match &config.url {
+        Some(url) => info!(url = %url, "approval delegation enabled"),
+        None => info!(skill = ?config.skill, tags = ?config.tags, "approval delegation enabled via agent registry"),
+    }
+
+    let config = Arc::new(config);
+    Some(tokio::spawn(async move {
+        while let Some(task_id) = queue.recv().await {
+            let (agent, config, registry) = (agent.clone(), config.clone(), registry.clone());
+            tokio::spawn(async move {
+                let outcome = match delegate(&agent, &config, &registry, &task_id).await {
+                    Ok(true) => "approved",
+                    Ok(false) => "rejected",
+                    Err(e) => {
+                        warn!(task_id = %task_id, error = %e, "approval delegation failed");
+                        "error"
+                    }
+                };
+                agent.metrics().increment(
+                    "po_agent_delegations_total",
+                    "Purchase orders delegated for approval",
+                    &[("outcome", outcome)],
+                    1,
+                );
+            });
+        }
+    }))
+}
+
+/// Base URL to delegate to: the configured URL, or the first registered agent offering the skill
+fn target(config: &DelegationConfig, registry: &AgentRegistry) -> Result<String, String> {
+    if let Some(url) = &config.url {
+        return Ok(url.clone());
+    }
+    registry
+        .select(config.skill.as_deref(), &config.tags)
+        .into_iter()
+        .next()
+        .map(|agent| agent.url)
+        .ok_or_else(|| format!("no registered agent offers skill {:?} with tags {:?}", config.skill, config.tags))
+}
+
+/// Forward one task, wait for the downstream decision and merge it; returns whether it was approved
+async fn delegate(
+    agent: &PurchaseOrderAgent,
+    config: &DelegationConfig,
+    registry: &AgentRegistry,
+    task_id: &str,
+) -> Result<bool, String> {
+    let record = agent
+        .task_store()
+        .get(task_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Task {} not found", task_id))?;
+
+    let url = target(config, registry)?;
+    let approver = match &config.approver {
+        Some(approver) => approver.clone(),
+        None => match registry.resolve(&url).await {
+            Ok(card) => card.name,
+            Err(_) => url.clone(),
+        },
+    };
+    let client = A2AClient::new(&url, config.client.clone()).map_err(|e| e.to_string())?;
+    let name = format!("delegation:{}", url);
+    let breaker = agent.breakers().get(&name, &config.breaker);
+    let downstream = Downstream { name: &name, breaker: &breaker, dependencies: agent.dependencies() };
+
+    let mut remote = downstream.call(client.send_task(record.request)).await?;
+    agent
+        .record_event(
+            task_id,
+            TaskEventKind::Delegated {
+                agent_url: client.base_url().to_string(),
+                remote_task_id: remote.task_id.clone(),
+            },
+        )
+        .map_err(|e| e.to_string())?;
+    debug!(task_id = %task_id, remote_task_id = %remote.task_id, "purchase order delegated");
+
+    let deadline = Instant::now() + config.wait_timeout;
+    let decision = loop {
+        if let Some(decision) = decision(&remote) {
+            break decision;
+        }
+        if Instant::now() + config.poll_interval > deadline {
+            return Err(format!("no decision from {} within {:?}", client.base_url(), config.wait_timeout));
+        }
+        tokio::time::sleep(config.poll_interval).await;
+        remote = downstream.call(client.get_task(&remote.task_id)).await?;
+    };
+
+    agent
+        .record_approval(task_id, &approver, decision.approved, decision.comment)
+        .map_err(|e| e.to_string())?;
+    Ok(decision.approved)
+}
+
+/// The downstream agent a delegation calls
+struct Downstream<'a> {
+    name: &'a str,
+    breaker: &'a CircuitBreaker,
+    dependencies: &'a Dependencies,
+}
+
+impl Downstream<'_> {
+    /// Make a call unless the agent's circuit is open, recording whether the agent was reachable
+    async fn call<T>(&self, call: impl std::future::Future<Output = Result<T, ClientError>>) -> Result<T, String> {
+        self.breaker
+            .try_acquire()
+            .map_err(|wait| format!("circuit open after repeated failures, retry in {:?}", wait))?;
+        match call.await {
+            Ok(value) => {
+                self.breaker.record_success();
+                self.dependencies.record_success(self.name);
+                Ok(value)
+            }
+            Err(e) => {
+                if e.is_outage() {
+                    self.breaker.record_failure();
+                    self.dependencies.record_failure(self.name, &e);
+                } else {
+                    self.breaker.record_success();
+                    self.dependencies.record_success(self.name);
+                }
+                Err(e.to_string())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::EventBus;
+    use crate::metrics::Metrics;
+    use crate::registry::RegistryConfig;
+    use a2a::A2AProtocol;
+    use axum::routing::{get, post};
+    use axum::Json;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn response(status: &str, detail: Option<serde_json::Value>) -> TaskResponse {
+        TaskResponse {
+            task_id: "r".to_string(),
+            status: status.to_string(),
+            csv_output: None,
+            detailed_result: detail,
+            error: None,
+            artifacts: None,
+            attachments: Vec::new(),
+            dry_run: false,
+        }
+    }
+
+    #[test]
+    fn test_decision_from_downstream_task() {
+        assert_eq!(decision(&response("working", None)), None);
+        assert_eq!(decision(&response("completed", None)), Some(Decision { approved: true, comment: None }));
+        assert_eq!(
+            decision(&response("completed", Some(serde_json::json!({ "approved": false, "comment": "over budget" })))),
+            Some(Decision { approved: false, comment: Some("over budget".to_string()) })
+        );
+        assert_eq!(decision(&response("completed", Some(serde_json::json!({ "status": "PENDING_APPROVAL" })))), None);
+        assert!(!decision(&response("cancelled", None)).unwrap().approved);
+    }
+
+    #[tokio::test]
+    async fn test_pending_order_is_approved_downstream() {
+        let polls = Arc::new(AtomicU32::new(0));
+        let counter = polls.clone();
+        let downstream = axum::Router::new()
+            .route(
+                "/agent/task",
+                post(|| async { Json(serde_json::json!({ "task_id": "remote-1", "status": "working" })) }),
+            )
+            .route(
+                "/agent/task/:id",
+                get(move || {
+                    let counter = counter.clone();
+                    async move {
+                        let status = if counter.fetch_add(1, Ordering::SeqCst) == 0 { "working" } else { "completed" };
+                        Json(serde_json::json!({
+                            "task_id": "remote-1",
+                            "status": status,
+                            "detailed_result": { "approved": true, "comment": "within budget" },
+                        }))
+                    }
+                }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, downstream).await.unwrap() });
+
+        let config = DelegationConfig {
+            url: Some(url.clone()),
+            approver: Some("approvals".to_string()),
+            poll_interval: Duration::from_millis(10),
+            ..DelegationConfig::default()
+        };
+        let (publisher, receiver) = queue(&config);
+        let (bus, _dispatcher) = EventBus::spawn(vec![Arc::new(publisher)], 16, Arc::new(Metrics::new()), Arc::new(Dependencies::new()));
+        let agent = Arc::new(PurchaseOrderAgent::new().with_event_bus(bus));
+        let registry = Arc::new(AgentRegistry::new(RegistryConfig::default()));
+        spawn_worker(agent.clone(), config, registry, receiver).unwrap();
+
+        let task = agent.send_task(crate::testing::message(&crate::testing::pending_order())).await.unwrap();
+
+        let mut result = None;
+        for _ in 0..200 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            let status = agent.get_task(&task.id).await.unwrap().status;
+            result = ProcessingResult::from_status(&status).filter(|r| r.status != "PENDING_APPROVAL");
+            if result.is_some() {
+                break;
+            }
+        }
+        let result = result.expect("delegated decision merged");
+        assert_eq!(result.status, "APPROVED");
+        assert!(result.summary.is_approved);
+
+        let events = agent.event_log().events(&task.id).unwrap();
+        let kinds: Vec<_> = events.iter().map(|e| e.kind.name()).collect();
+        assert_eq!(kinds, ["created", "state_changed", "delegated", "approval_recorded", "state_changed"]);
+        assert_eq!(agent.metrics().value("po_agent_delegations_total", &[("outcome", "approved")]), Some(1.0));
+    }
+}
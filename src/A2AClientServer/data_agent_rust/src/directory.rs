@@ -0,0 +1,314 @@
+//! Department and user directory
+//!
+//! Orders are checked against the organization's own data: the buyer
+//! department must be one of the directory's departments, and when the
+//! directory lists users, `created_by` must be an active user, normally of the
+//! buyer department. The directory comes from a [`DirectoryProvider`]: the
+//! `[directory]` lists themselves, a JSON or TOML file, or a REST endpoint
+//! returning the same JSON. File and REST directories are reloaded every
+//! `refresh`; a reload that fails keeps the directory loaded last. Other
+//! sources, such as LDAP, plug in by implementing the trait.
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+use crate::agent::PurchaseOrderAgent;
+use crate::severity::{Check, Findings};
+
+/// Departments allowed to purchase when no directory is configured
+pub const DEFAULT_DEPARTMENTS: [&str; 6] = ["Marketing", "Sales", "IT", "Finance", "Operations", "HR"];
+
+/// Where the directory comes from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProviderKind {
+    /// The `departments` and `users` of the configuration
+    #[default]
+    Static,
+    File,
+    Rest,
+}
+
+/// The `[directory]` section
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct DirectoryConfig {
+    pub provider: ProviderKind,
+    /// JSON or TOML file of the `file` provider
+    pub path: Option<PathBuf>,
+    /// Endpoint of the `rest` provider, returning the directory as JSON
+    pub url: Option<String>,
+    /// Bearer token sent to the endpoint
+    pub bearer_token: Option<String>,
+    #[serde(with = "humantime_serde")]
+    pub timeout: Duration,
+    /// How often file and REST directories are reloaded
+    #[serde(with = "humantime_serde")]
+    pub refresh: Duration,
+    /// Departments of the `static` provider; the built-in six when empty
+    pub departments: Vec<String>,
+    /// Users of the `static` provider; `created_by` is not checked without any
+    pub users: Vec<DirectoryUser>,
+}
+
+impl Default for DirectoryConfig {
+    fn default() -> Self {
+        Self {
+            provider: ProviderKind::Static,
+            path: None,
+            url: None,
+            bearer_token: None,
+            timeout: Duration::from_secs(10),
+            refresh: Duration::from_secs(15 * 60),
+            departments: Vec::new(),
+            users: Vec::new(),
+        }
+    }
+}
+
+/// A person who may create purchase orders
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DirectoryUser {
+    /// Login or name `created_by` gives
+    pub id: String,
+    /// Display name, also accepted as `created_by`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub department: Option<String>,
+    #[serde(default = "active")]
+    pub active: bool,
+}
+
+fn active() -> bool {
+    true
+}
+
+/// Departments and users of the organization
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Directory {
+    pub departments: Vec<String>,
+    pub users: Vec<DirectoryUser>,
+}
+
+fn same(a: &str, b: &str) -> bool {
+    a.trim().eq_ignore_ascii_case(b.trim())
+}
+
+impl Directory {
+    /// The built-in departments, without users
+    pub fn builtin() -> Self {
+        Self { departments: DEFAULT_DEPARTMENTS.map(str::to_string).to_vec(), users: Vec::new() }
+    }
+
+    /// The department named `name`, compared case-insensitively
+    pub fn department(&self, name: &str) -> Option<&str> {
+        self.departments.iter().map(String::as_str).find(|department| same(department, name))
+    }
+
+    /// The user whose ID or name is `name`, compared case-insensitively
+    pub fn user(&self, name: &str) -> Option<&DirectoryUser> {
+        self.users.iter().find(|user| same(&user.id, name) || user.name.as_deref().is_some_and(|n| same(n, name)))
+    }
+
+    /// Check a department, and the user who placed an order or report for it
+    ///
+    /// Empty fields are left to the required-field checks.
+    pub fn check(&self, department: &str, created_by: Option<&str>, findings: &mut Findings) {
+        if !department.trim().is_empty() && self.department(department).is_none() {
+            findings.push(
+                Check::UnauthorizedDepartment,
+                format!("Department '{}' may not be authorized for purchases", department),
+            );
+        }
+        let Some(created_by) = created_by.filter(|name| !name.trim().is_empty() && !self.users.is_empty()) else {
+            return;
+        };
+        match self.user(created_by) {
+            None => findings.push(Check::UnknownRequester, format!("Requester '{}' is not in the directory", created_by)),
+            Some(user) if !user.active => {
+                findings.push(Check::UnknownRequester, format!("Requester '{}' is no longer active", created_by))
+            }
+            Some(DirectoryUser { department: Some(home), .. }) if !department.trim().is_empty() && !same(home, department) => {
+                findings.push(
+                    Check::RequesterDepartment,
+                    format!("Requester '{}' belongs to {}, not {}", created_by, home, department),
+                )
+            }
+            Some(_) => {}
+        }
+    }
+}
+
+/// A source of the directory
+#[async_trait]
+pub trait DirectoryProvider: Send + Sync {
+    /// Name shown in logs and metrics
+    fn name(&self) -> &str;
+
+    async fn load(&self) -> Result<Directory, Box<dyn Error + Send + Sync>>;
+}
+
+/// The directory of the configuration
+pub struct StaticDirectory(Directory);
+
+#[async_trait]
+impl DirectoryProvider for StaticDirectory {
+    fn name(&self) -> &str {
+        "static"
+    }
+
+    async fn load(&self) -> Result<Directory, Box<dyn Error + Send + Sync>> {
+        Ok(self.0.clone())
+    }
+}
+
+/// A directory read from a file, as TOML if it ends in `.toml` and as JSON otherwise
+pub struct FileDirectory {
+    pub path: PathBuf,
+}
+
+#[async_trait]
+impl DirectoryProvider for FileDirectory {
+    fn name(&self) -> &str {
+        "file"
+    }
+
+    async fn load(&self) -> Result<Directory, Box<dyn Error + Send + Sync>> {
+        let contents = tokio::fs::read_to_string(&self.path)
+            .await
+            .map_err(|e| format!("Failed to read directory {}: {}", self.path.display(), e))?;
+        let directory = match self.path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("toml") => toml::from_str(&contents).map_err(|e| e.to_string()),
+            _ => serde_json::from_str(&contents).map_err(|e| e.to_string()),
+        };
+        directory.map_err(|e| format!("Failed to parse directory {}: {}", self.path.display(), e).into())
+    }
+}
+
+/// A directory served as JSON by an HTTP endpoint, e.g. a bridge to the HR system or LDAP
+pub struct RestDirectory {
+    url: String,
+    bearer_token: Option<String>,
+    client: reqwest::Client,
+}
+
+impl RestDirectory {
+    pub fn new(url: &str, bearer_token: Option<String>, timeout: Duration) -> Result<Self, reqwest::Error> {
+        let client = reqwest::Client::builder().timeout(timeout).build()?;
+        Ok(Self { url: url.to_string(), bearer_token, client })
+    }
+}
+
+#[async_trait]
+impl DirectoryProvider for RestDirectory {
+    fn name(&self) -> &str {
+        "rest"
+    }
+
+    async fn load(&self) -> Result<Directory, Box<dyn Error + Send + Sync>> {
+        let mut request = self.client.get(&self.url);
+        if let Some(token) = &self.bearer_token {
+            request = request.bearer_auth(token);
+        }
+        let response = request.send().await?.error_for_status()?;
+        Ok(response.json::<Directory>().await?)
+    }
+}
+
+/// The provider `config` selects
+pub fn provider(config: &DirectoryConfig) -> Result<Arc<dyn DirectoryProvider>, String> {
+    Ok(match config.provider {
+        ProviderKind::Static if config.departments.is_empty() => {
+            Arc::new(StaticDirectory(Directory { users: config.users.clone(), ..Directory::builtin() }))
+        }
+        ProviderKind::Static => {
+            Arc::new(StaticDirectory(Directory { departments: config.departments.clone(), users: config.users.clone() }))
+        }
+        ProviderKind::File => {
+            let path = config.path.clone().ok_or("The file directory provider needs a path")?;
+            Arc::new(FileDirectory { path })
+        }
+        ProviderKind::Rest => {
+            let url = config.url.as_deref().ok_or("The rest directory provider needs a url")?;
+            Arc::new(RestDirectory::new(url, config.bearer_token.clone(), config.timeout).map_err(|e| e.to_string())?)
+        }
+    })
+}
+
+/// Reload the directory from `provider` every `refresh`, keeping the last one when a reload fails
+pub fn spawn_refresh(agent: Arc<PurchaseOrderAgent>, provider: Arc<dyn DirectoryProvider>, refresh: Duration) -> JoinHandle<()> {
+    info!(provider = provider.name(), refresh = ?refresh, "directory refresh scheduled");
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(refresh.max(Duration::from_secs(1)));
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            let outcome = match provider.load().await {
+                Ok(directory) => {
+                    agent.set_directory(directory);
+                    "loaded"
+                }
+                Err(e) => {
+                    warn!(provider = provider.name(), error = %e, "directory refresh failed, keeping the last one");
+                    "error"
+                }
+            };
+            agent.metrics().increment(
+                "po_agent_directory_refreshes_total",
+                "Reloads of the department and user directory",
+                &[("provider", provider.name()), ("outcome", outcome)],
+                1,
+            );
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::severity::SeverityPolicy;
+
+    #[tokio::test]
+    async fn test_orders_are_checked_against_the_directory() {
+        let path = std::env::temp_dir().join(format!("po-directory-{}.toml", uuid::Uuid::new_v4()));
+        std::fs::write(&path, r#"
+            departments = ["Marketing", "Facilities"]
+            users = [
+                { id = "sam", name = "Sam Carter", department = "Marketing" },
+                { id = "lee", department = "Facilities", active = false },
+            ]
+        "#).unwrap();
+        let config = DirectoryConfig { provider: ProviderKind::File, path: Some(path.clone()), ..DirectoryConfig::default() };
+        let directory = provider(&config).unwrap().load().await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let policy = SeverityPolicy::default();
+        let warnings = |department: &str, created_by: &str| {
+            let mut findings = Findings::new(&policy);
+            directory.check(department, Some(created_by), &mut findings);
+            findings.into_parts().1
+        };
+        assert!(warnings("facilities", "Sam Carter").contains(&"Requester 'Sam Carter' belongs to Marketing, not facilities".to_string()));
+        assert!(warnings("Marketing", "SAM").is_empty());
+        assert_eq!(warnings("Sales", "lee"), [
+            "Department 'Sales' may not be authorized for purchases",
+            "Requester 'lee' is no longer active",
+        ]);
+        assert_eq!(warnings("Marketing", "kim"), ["Requester 'kim' is not in the directory"]);
+
+        // The agent checks orders against the directory it is given
+        let agent = PurchaseOrderAgent::new().with_directory(directory);
+        let (_, warnings) = agent.validate_purchase_order(&crate::testing::unauthorized_department_order());
+        assert_eq!(warnings, ["Requester 'Sam' belongs to Marketing, not Facilities"]);
+
+        assert!(provider(&DirectoryConfig { provider: ProviderKind::Rest, ..DirectoryConfig::default() }).is_err());
+    }
+}
@@ -0,0 +1,87 @@
+//! What-if submissions
+//!
+//! A submission with `dry_run` set runs the full pipeline, validation, the
+//! approval decision and the CSV and artifact renderings, and returns the
+//! would-be task inline. Nothing is kept: no task is stored, no events are
+//! recorded, so no webhooks, emails or event bus messages go out, blanket
+//! orders are not drawn on, prices are not recorded, and the result cache is
+//! neither read nor filled. The task returned has no ID.
+//!
+//! Session deltas resolve against the session as usual. Deferred processing
+//! does not apply, and a message that holds back items for later chunks is
+//! refused, since the order would be incomplete.
+
+use a2a::{Message, Task};
+use std::error::Error;
+use tracing::info;
+
+use crate::agent::{PurchaseOrderAgent, TaskOptions};
+use crate::artifacts;
+use crate::assembly;
+use crate::skills::{PROCESSING_SKILL, REPORTING_SKILL, VALIDATION_SKILL};
+
+/// Process `message` as `options` ask, without storing or changing anything
+pub(crate) async fn run(agent: &PurchaseOrderAgent, message: Message, options: &TaskOptions) -> Result<Task, Box<dyn Error>> {
+    if assembly::has_more(&message) {
+        return Err(format!("A dry run needs the complete purchase order, without `{}`", assembly::MORE_KEY).into());
+    }
+    let skill = agent.skill_for(&message, options.skill_id.as_deref())?;
+    let status = match skill.id() {
+        PROCESSING_SKILL => agent.dry_run_message(&message).await?,
+        // These only read the order
+        VALIDATION_SKILL | REPORTING_SKILL => skill.handle(agent, &message).await?,
+        other => {
+            return Err(format!(
+                "Skill {} does not support dry runs; {}, {} and {} do",
+                other, PROCESSING_SKILL, VALIDATION_SKILL, REPORTING_SKILL
+            )
+            .into());
+        }
+    };
+    agent.metrics().increment("po_agent_dry_runs_total", "Submissions processed as dry runs", &[("skill", skill.id())], 1);
+    info!(skill = skill.id(), "dry run processed");
+    Ok(Task {
+        id: String::new(),
+        session_id: options.session_id.clone(),
+        artifacts: artifacts::select(artifacts::from_status(&status), &options.outputs),
+        status,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::agent::{ProcessingResult, PurchaseOrderAgent, TaskOptions};
+    use crate::blanket::BlanketTerms;
+    use a2a::TaskState;
+    use chrono::NaiveDate;
+
+    #[tokio::test]
+    async fn test_dry_runs_change_nothing() {
+        let agent = PurchaseOrderAgent::new();
+        let options = TaskOptions { dry_run: true, ..TaskOptions::default() };
+        let task = agent.send_task_with_options(crate::testing::message(&crate::testing::valid_order()), &options).await.unwrap();
+        assert!(task.id.is_empty() && matches!(task.status.state, TaskState::Completed));
+        let result = ProcessingResult::from_status(&task.status).unwrap();
+        assert_eq!((result.status.as_str(), result.po_number.as_str()), ("APPROVED", "PO-1"));
+        assert!(task.artifacts.is_some());
+        assert!(agent.task_store().list().unwrap().is_empty());
+
+        // A release against a blanket order leaves its remaining amount alone
+        let mut blanket = crate::testing::valid_order();
+        blanket.po_number = "BPO-1".to_string();
+        blanket.blanket = Some(BlanketTerms {
+            not_to_exceed: 1000.0,
+            valid_from: NaiveDate::from_ymd_opt(2000, 1, 1).unwrap(),
+            valid_until: NaiveDate::from_ymd_opt(2100, 1, 1).unwrap(),
+        });
+        agent.send_task_with_options(crate::testing::message(&blanket), &TaskOptions::default()).await.unwrap();
+        let mut release = crate::testing::valid_order();
+        release.blanket_po_number = Some("BPO-1".to_string());
+        agent.send_task_with_options(crate::testing::message(&release), &options).await.unwrap();
+        assert!(agent.blankets().get("BPO-1").unwrap().unwrap().releases.is_empty());
+
+        let onboarding = TaskOptions { skill_id: Some(crate::onboarding::ONBOARDING_SKILL.to_string()), ..options };
+        let err = agent.send_task_with_options(crate::testing::message(&release), &onboarding).await.unwrap_err();
+        assert!(err.to_string().contains("does not support dry runs"), "{}", err);
+    }
+}
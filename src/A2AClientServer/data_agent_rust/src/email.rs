@@ -0,0 +1,302 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::Arc;
+
+use crate::agent::ProcessingResult;
+use crate::events::EventPublisher;
+
+/// Email notifications about purchase orders that need a human
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct EmailConfig {
+    /// SMTP relay; notifications are off when unset (requires the `email` feature)
+    pub smtp: Option<SmtpConfig>,
+    /// Sender address, e.g. `PO Agent <po-agent@example.com>`
+    pub from: String,
+    /// Processing statuses that trigger an email
+    pub statuses: Vec<String>,
+    /// Recipients for departments without their own entry in `departments`
+    pub recipients: Vec<String>,
+    /// Per-department recipients, keyed by buyer department (case-insensitive)
+    pub departments: HashMap<String, Vec<String>>,
+    /// Base URL used to link to the task, e.g. `https://po-agent.example.com`
+    pub task_base_url: String,
+    /// Subject template; see [`render`] for placeholders
+    pub subject: String,
+    /// Plain-text body template; see [`render`] for placeholders
+    pub body: String,
+}
+
+impl Default for EmailConfig {
+    fn default() -> Self {
+        Self {
+            smtp: None,
+            from: "PO Agent <po-agent@localhost>".to_string(),
+            statuses: vec!["PENDING_APPROVAL".to_string(), "VALIDATION_FAILED".to_string()],
+            recipients: Vec::new(),
+            departments: HashMap::new(),
+            task_base_url: "http://localhost:8080".to_string(),
+            subject: "[{status}] Purchase order {po_number} from {supplier}".to_string(),
+            body: DEFAULT_BODY.to_string(),
+        }
+    }
+}
+
+const DEFAULT_BODY: &str = "\
+Purchase order {po_number} is {status}.
+
+Supplier:   {supplier}
+Department: {department}
+Items:      {total_items}
+Grand total: {grand_total}
+
+Errors:
+{errors}
+
+Warnings:
+{warnings}
+
+Task: {task_url}
+";
+
+/// SMTP relay settings
+#[derive(Debug, Clone, Deserialize)]
+pub struct SmtpConfig {
+    pub host: String,
+    #[serde(default = "default_smtp_port")]
+    pub port: u16,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    /// Connection security: `starttls` (default), `tls` or `none`
+    #[serde(default)]
+    pub security: SmtpSecurity,
+}
+
+fn default_smtp_port() -> u16 {
+    587
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpSecurity {
+    #[default]
+    Starttls,
+    Tls,
+    None,
+}
+
+/// A composed notification, ready to send
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmailNotification {
+    pub to: Vec<String>,
+    pub subject: String,
+    pub body: String,
+}
+
+impl EmailConfig {
+    /// Recipients for a buyer department, falling back to the default recipients
+    pub fn recipients_for(&self, department: &str) -> &[String] {
+        self.departments
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(department.trim()))
+            .map(|(_, recipients)| recipients.as_slice())
+            .unwrap_or(&self.recipients)
+    }
+
+    /// Compose the notification for a result, or `None` when its status is not
+    /// notified or nobody would receive it
+    pub fn compose(&self, task_id: &str, result: &ProcessingResult) -> Option<EmailNotification> {
+        if !self.statuses.iter().any(|s| s == &result.status) {
+            return None;
+        }
+        let to = self.recipients_for(&result.buyer_department);
+        if to.is_empty() {
+            return None;
+        }
+
+        let task_url = format!("{}/agent/task/{}", self.task_base_url.trim_end_matches('/'), task_id);
+        Some(EmailNotification {
+            to: to.to_vec(),
+            subject: render(&self.subject, result, &task_url),
+            body: render(&self.body, result, &task_url),
+        })
+    }
+}
+
+/// Fill a template from a processing result
+///
+/// Placeholders: `{po_number}`, `{status}`, `{supplier}`, `{department}`,
+/// `{total_items}`, `{sub_total}`, `{tax}`, `{grand_total}`, `{errors}`,
+/// `{warnings}` (one `- ` bullet per line, or `- none`) and `{task_url}`.
+pub fn render(template: &str, result: &ProcessingResult, task_url: &str) -> String {
+    let bullets = |lines: &[String]| {
+        if lines.is_empty() {
+            "- none".to_string()
+        } else {
+            lines.iter().map(|l| format!("- {}", l)).collect::<Vec<_>>().join("\n")
+        }
+    };
+    template
+        .replace("{po_number}", &result.po_number)
+        .replace("{status}", &result.status)
+        .replace("{supplier}", &result.supplier_name)
+        .replace("{department}", &result.buyer_department)
+        .replace("{total_items}", &result.summary.total_items.to_string())
+        .replace("{sub_total}", &format!("{:.2}", result.sub_total))
+        .replace("{tax}", &format!("{:.2}", result.tax))
+        .replace("{grand_total}", &format!("{:.2}", result.grand_total))
+        .replace("{errors}", &bullets(&result.validation_errors))
+        .replace("{warnings}", &bullets(&result.warnings))
+        .replace("{task_url}", task_url)
+}
+
+/// Build the email publisher for the event bus when SMTP is configured
+pub fn publisher(config: &EmailConfig) -> Result<Option<Arc<dyn EventPublisher>>, Box<dyn Error>> {
+    let Some(smtp) = &config.smtp else {
+        return Ok(None);
+    };
+    #[cfg(feature = "email")]
+    {
+        Ok(Some(Arc::new(smtp::EmailPublisher::new(smtp, config.clone())?)))
+    }
+    #[cfg(not(feature = "email"))]
+    {
+        let _ = smtp;
+        Err("[email.smtp] is configured but this binary was built without the `email` feature".into())
+    }
+}
+
+#[cfg(feature = "email")]
+mod smtp {
+    use a2a::TaskState;
+    use async_trait::async_trait;
+    use lettre::message::{header::ContentType, Mailbox};
+    use lettre::transport::smtp::authentication::Credentials;
+    use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+    use std::error::Error;
+    use tracing::info;
+
+    use super::{EmailConfig, SmtpConfig, SmtpSecurity};
+    use crate::agent::ProcessingResult;
+    use crate::events::{EventPublisher, PublishError, TaskEvent, TaskEventKind};
+
+    /// Sends an email for every task whose processing result has a notified status
+    pub struct EmailPublisher {
+        transport: AsyncSmtpTransport<Tokio1Executor>,
+        from: Mailbox,
+        config: EmailConfig,
+    }
+
+    impl EmailPublisher {
+        pub fn new(smtp: &SmtpConfig, config: EmailConfig) -> Result<Self, Box<dyn Error>> {
+            let mut builder = match smtp.security {
+                SmtpSecurity::Starttls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&smtp.host)?,
+                SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&smtp.host)?,
+                SmtpSecurity::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&smtp.host),
+            }
+            .port(smtp.port);
+            if let (Some(username), Some(password)) = (&smtp.username, &smtp.password) {
+                builder = builder.credentials(Credentials::new(username.clone(), password.clone()));
+            }
+
+            info!(host = %smtp.host, port = smtp.port, "email notifications enabled");
+            Ok(Self {
+                transport: builder.build(),
+                from: config.from.parse()?,
+                config,
+            })
+        }
+    }
+
+    #[async_trait]
+    impl EventPublisher for EmailPublisher {
+        fn name(&self) -> &str {
+            "email"
+        }
+
+        async fn publish(&self, event: &TaskEvent) -> Result<(), PublishError> {
+            let TaskEventKind::StateChanged { status } = &event.kind else {
+                return Ok(());
+            };
+            if matches!(status.state, TaskState::Canceled) {
+                return Ok(());
+            }
+            let Some(notification) =
+                ProcessingResult::from_status(status).and_then(|result| self.config.compose(&event.task_id, &result))
+            else {
+                return Ok(());
+            };
+
+            let mut message = Message::builder()
+                .from(self.from.clone())
+                .subject(notification.subject)
+                .header(ContentType::TEXT_PLAIN);
+            for recipient in &notification.to {
+                let mailbox: Mailbox = recipient.parse().map_err(|e| PublishError(format!("{}: {}", recipient, e)))?;
+                message = message.to(mailbox);
+            }
+            let message = message.body(notification.body).map_err(|e| PublishError(e.to_string()))?;
+
+            self.transport
+                .send(message)
+                .await
+                .map(|_| ())
+                .map_err(|e| PublishError(e.to_string()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(status: &str, department: &str) -> ProcessingResult {
+        serde_json::from_value(serde_json::json!({
+            "status": status,
+            "po_number": "PO-7",
+            "validation_errors": ["Missing supplier address"],
+            "warnings": [],
+            "summary": {
+                "total_items": 2, "total_quantity": 5, "sub_total": 100.0, "tax": 7.0,
+                "grand_total": 107.0, "supplier": "Acme", "department": department, "is_approved": false
+            },
+            "processed_at": "2026-01-01T00:00:00Z",
+            "supplier_name": "Acme",
+            "buyer_department": department,
+            "notes": null,
+            "sub_total": 100.0,
+            "tax": 7.0,
+            "grand_total": 107.0
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_compose_routes_by_department() {
+        let config: EmailConfig = toml::from_str(
+            r#"
+            recipients = ["procurement@example.com"]
+            task_base_url = "https://po.example.com/"
+
+            [departments]
+            marketing = ["cmo@example.com"]
+            "#,
+        )
+        .unwrap();
+
+        let marketing = config.compose("t-1", &result("VALIDATION_FAILED", "Marketing")).unwrap();
+        assert_eq!(marketing.to, ["cmo@example.com"]);
+        assert_eq!(marketing.subject, "[VALIDATION_FAILED] Purchase order PO-7 from Acme");
+        assert!(marketing.body.contains("- Missing supplier address"));
+        assert!(marketing.body.contains("Warnings:\n- none"));
+        assert!(marketing.body.contains("https://po.example.com/agent/task/t-1"));
+
+        let other = config.compose("t-2", &result("PENDING_APPROVAL", "Finance")).unwrap();
+        assert_eq!(other.to, ["procurement@example.com"]);
+
+        assert!(config.compose("t-3", &result("APPROVED", "Finance")).is_none());
+    }
+}
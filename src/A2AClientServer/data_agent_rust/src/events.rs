@@ -0,0 +1,299 @@
+use a2a::{Message, Task, TaskState, TaskStatus};
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::approval::{ApprovalAssignment, ApprovalVote, AssignmentReason};
+use crate::labels::Labels;
+use crate::processing::Priority;
+use crate::receiving::ReceiptLine;
+use crate::screening::ScreeningMatch;
+use crate::store::{StoreResult, TaskRecord};
+
+pub mod bus;
+#[cfg(feature = "kafka")]
+pub mod kafka;
+#[cfg(feature = "nats")]
+pub mod nats;
+
+pub use bus::{EventBus, EventPublisher, EventsConfig, KafkaConfig, NatsConfig, PublishError};
+
+/// Something that happened to a task
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TaskEventKind {
+    /// The task was accepted from `request`
+    Created {
+        request: Message,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        session_id: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        skill_id: Option<String>,
+        #[serde(default, skip_serializing_if = "Priority::is_normal")]
+        priority: Priority,
+    },
+    /// The task moved to a new status
+    StateChanged { status: TaskStatus },
+    /// An approver signed off on or rejected the purchase order
+    ApprovalRecorded {
+        approver: String,
+        approved: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        comment: Option<String>,
+        /// Lines left out of the approved order, counting from 1
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        rejected_lines: Vec<usize>,
+    },
+    /// A notification about the task reached its destination
+    NotificationDelivered { channel: String, target: String },
+    /// The approval was handed to `approver`, at position `level` of the approver chain
+    ApprovalAssigned {
+        approver: String,
+        level: usize,
+        reason: AssignmentReason,
+        /// Previous assignee, for delegations and escalations
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        by: Option<String>,
+    },
+    /// `approver` approved an order that needs more than one approval
+    ApprovalGranted {
+        approver: String,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        roles: Vec<String>,
+    },
+    /// A supporting document was attached to the task
+    AttachmentAdded { attachment_id: String, name: String, size: usize },
+    /// The purchase order was forwarded to another agent for approval
+    Delegated { agent_url: String, remote_task_id: String },
+    /// Goods were received against the purchase order
+    GoodsReceived { receipt_id: String, lines: Vec<ReceiptLine> },
+    /// The task processed an amendment of the order processed by `previous_task_id`
+    Amended { po_number: String, revision: u32, previous_task_id: String },
+    /// The failed task was retried as `child_task_id`
+    Retried { child_task_id: String },
+    /// The task retries the failed task `parent_task_id`
+    RetriedFrom { parent_task_id: String },
+    /// The task is held until `process_after` before it is processed
+    Deferred { process_after: DateTime<Utc> },
+    /// The task was generated by a recurring order schedule for its run at `occurrence`
+    Scheduled { schedule_id: String, occurrence: DateTime<Utc> },
+    /// The supplier matched the denied-party list while the order was validated
+    ScreeningMatched { matches: Vec<ScreeningMatch> },
+    /// Another message of a purchase order sent in chunks, whose parts were added to the request
+    ChunkReceived { message: Message },
+    /// The task's labels were set or changed, to `labels`
+    LabelsChanged { labels: Labels },
+}
+
+impl TaskEventKind {
+    /// Wire name of the event type, as serialized in the `type` field
+    pub fn name(&self) -> &'static str {
+        match self {
+            TaskEventKind::Created { .. } => "created",
+            TaskEventKind::StateChanged { .. } => "state_changed",
+            TaskEventKind::ApprovalRecorded { .. } => "approval_recorded",
+            TaskEventKind::ApprovalAssigned { .. } => "approval_assigned",
+            TaskEventKind::ApprovalGranted { .. } => "approval_granted",
+            TaskEventKind::AttachmentAdded { .. } => "attachment_added",
+            TaskEventKind::NotificationDelivered { .. } => "notification_delivered",
+            TaskEventKind::Delegated { .. } => "delegated",
+            TaskEventKind::GoodsReceived { .. } => "goods_received",
+            TaskEventKind::Amended { .. } => "amended",
+            TaskEventKind::Retried { .. } => "retried",
+            TaskEventKind::RetriedFrom { .. } => "retried_from",
+            TaskEventKind::Deferred { .. } => "deferred",
+            TaskEventKind::Scheduled { .. } => "scheduled",
+            TaskEventKind::ScreeningMatched { .. } => "screening_matched",
+            TaskEventKind::ChunkReceived { .. } => "chunk_received",
+            TaskEventKind::LabelsChanged { .. } => "labels_changed",
+        }
+    }
+}
+
+/// One entry in a task's event stream
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskEvent {
+    /// Position in the task's stream, starting at 1
+    pub sequence: u64,
+    pub task_id: String,
+    pub at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub kind: TaskEventKind,
+}
+
+/// Append-only storage of task event streams
+pub trait EventLog: Send + Sync {
+    /// Append an event to a task's stream, assigning it the next sequence number
+    fn append(&self, task_id: &str, kind: TaskEventKind) -> StoreResult<TaskEvent>;
+
+    /// Every event recorded for a task, in order
+    fn events(&self, task_id: &str) -> StoreResult<Vec<TaskEvent>>;
+
+    /// Drop the streams of tasks not matching `keep`, returning how many were dropped
+    fn retain_tasks(&self, keep: &dyn Fn(&str) -> bool) -> StoreResult<usize>;
+}
+
+/// In-memory event log used by default
+#[derive(Default)]
+pub struct MemoryEventLog {
+    streams: DashMap<String, Vec<TaskEvent>>,
+}
+
+impl MemoryEventLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl EventLog for MemoryEventLog {
+    fn append(&self, task_id: &str, kind: TaskEventKind) -> StoreResult<TaskEvent> {
+        let mut stream = self.streams.entry(task_id.to_string()).or_default();
+        let event = TaskEvent {
+            sequence: stream.len() as u64 + 1,
+            task_id: task_id.to_string(),
+            at: Utc::now(),
+            kind,
+        };
+        stream.push(event.clone());
+        Ok(event)
+    }
+
+    fn events(&self, task_id: &str) -> StoreResult<Vec<TaskEvent>> {
+        Ok(self.streams.get(task_id).map(|stream| stream.clone()).unwrap_or_default())
+    }
+
+    fn retain_tasks(&self, keep: &dyn Fn(&str) -> bool) -> StoreResult<usize> {
+        let before = self.streams.len();
+        self.streams.retain(|task_id, _| keep(task_id));
+        Ok(before - self.streams.len())
+    }
+}
+
+/// Rebuild a task record by replaying its event stream
+///
+/// Returns `None` when the stream does not start with a `Created` event.
+pub fn rebuild(events: &[TaskEvent]) -> Option<TaskRecord> {
+    let (first, rest) = events.split_first()?;
+    let TaskEventKind::Created { request, session_id, skill_id, priority } = &first.kind else {
+        return None;
+    };
+
+    let task = Task {
+        id: first.task_id.clone(),
+        session_id: session_id.clone(),
+        status: TaskStatus {
+            state: TaskState::Submitted,
+            message: None,
+            timestamp: first.at.timestamp().to_string(),
+        },
+        artifacts: None,
+    };
+    let mut record = TaskRecord::new(task, request.clone());
+    record.skill_id = skill_id.clone();
+    record.priority = *priority;
+    record.created_at = first.at;
+    record.updated_at = first.at;
+
+    for event in rest {
+        match &event.kind {
+            TaskEventKind::StateChanged { status } => record.set_status(status.clone()),
+            TaskEventKind::Deferred { process_after } => record.process_after = Some(*process_after),
+            TaskEventKind::ChunkReceived { message } => record.request = crate::parts::concat(&record.request, message),
+            TaskEventKind::RetriedFrom { parent_task_id } => record.retried_from = Some(parent_task_id.clone()),
+            TaskEventKind::LabelsChanged { labels } => record.labels = labels.clone(),
+            TaskEventKind::ApprovalGranted { approver, roles } => record.approvals.push(ApprovalVote {
+                approver: approver.clone(),
+                roles: roles.clone(),
+                at: event.at,
+            }),
+            TaskEventKind::ApprovalAssigned { approver, level, reason, by } => {
+                record.approval = Some(ApprovalAssignment {
+                    approver: approver.clone(),
+                    level: *level,
+                    assigned_at: event.at,
+                    delegated_by: by.clone().filter(|_| *reason == AssignmentReason::Delegated),
+                })
+            }
+            _ => {}
+        }
+        record.updated_at = event.at;
+    }
+    Some(record)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use a2a::Part;
+
+    fn request() -> Message {
+        Message {
+            role: "user".to_string(),
+            parts: vec![Part::Text { text: "{}".to_string() }],
+        }
+    }
+
+    fn status(state: TaskState) -> TaskStatus {
+        TaskStatus { state, message: None, timestamp: "0".to_string() }
+    }
+
+    #[test]
+    fn test_append_assigns_sequence_per_task() {
+        let log = MemoryEventLog::new();
+        log.append("a", TaskEventKind::Created { request: request(), session_id: None, skill_id: None, priority: Priority::Normal }).unwrap();
+        log.append("b", TaskEventKind::Created { request: request(), session_id: None, skill_id: None, priority: Priority::Normal }).unwrap();
+        let second = log
+            .append("a", TaskEventKind::StateChanged { status: status(TaskState::Completed) })
+            .unwrap();
+
+        assert_eq!(second.sequence, 2);
+        assert_eq!(log.events("a").unwrap().len(), 2);
+        assert_eq!(log.events("b").unwrap()[0].sequence, 1);
+        assert!(log.events("missing").unwrap().is_empty());
+
+        assert_eq!(log.retain_tasks(&|id| id == "a").unwrap(), 1);
+        assert!(log.events("b").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_rebuild_replays_state_changes() {
+        let log = MemoryEventLog::new();
+        log.append("a", TaskEventKind::Created { request: request(), session_id: Some("s".to_string()), skill_id: None, priority: Priority::Urgent })
+            .unwrap();
+        log.append("a", TaskEventKind::StateChanged { status: status(TaskState::Failed) }).unwrap();
+        log.append(
+            "a",
+            TaskEventKind::ApprovalRecorded { approver: "jo".to_string(), approved: true, comment: None, rejected_lines: Vec::new() },
+        )
+        .unwrap();
+        log.append("a", TaskEventKind::StateChanged { status: status(TaskState::Completed) }).unwrap();
+
+        let events = log.events("a").unwrap();
+        let record = rebuild(&events).unwrap();
+        assert_eq!(record.task.id, "a");
+        assert_eq!(record.task.session_id.as_deref(), Some("s"));
+        assert_eq!(record.state_name(), "completed");
+        assert_eq!(record.updated_at, events[3].at);
+
+        assert!(rebuild(&events[1..]).is_none(), "stream must start with creation");
+    }
+
+    #[test]
+    fn test_event_wire_format() {
+        let event = TaskEvent {
+            sequence: 1,
+            task_id: "a".to_string(),
+            at: Utc::now(),
+            kind: TaskEventKind::NotificationDelivered {
+                channel: "webhook".to_string(),
+                target: "https://erp.example.com/hook".to_string(),
+            },
+        };
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["type"], event.kind.name());
+        assert_eq!(json["channel"], "webhook");
+
+        let parsed: TaskEvent = serde_json::from_value(json).unwrap();
+        assert!(matches!(parsed.kind, TaskEventKind::NotificationDelivered { .. }));
+    }
+}
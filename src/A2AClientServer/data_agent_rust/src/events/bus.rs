@@ -0,0 +1,305 @@
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::error::Error;
+use std::fmt;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+use super::TaskEvent;
+use crate::agent::ProcessingResult;
+use crate::metrics::Metrics;
+use crate::readiness::Dependencies;
+
+/// Error raised by an event publisher
+#[derive(Debug)]
+pub struct PublishError(pub String);
+
+impl fmt::Display for PublishError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Event publish error: {}", self.0)
+    }
+}
+
+impl Error for PublishError {}
+
+/// Destination for task lifecycle events, e.g. a Kafka topic
+#[async_trait]
+pub trait EventPublisher: Send + Sync {
+    /// Short name used in logs and metric labels
+    fn name(&self) -> &str;
+
+    /// Deliver one event
+    async fn publish(&self, event: &TaskEvent) -> Result<(), PublishError>;
+}
+
+/// Outbound event bus settings
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct EventsConfig {
+    /// Events buffered for publishing before new ones are dropped
+    pub queue_capacity: usize,
+    /// Publish to Kafka when present (requires the `kafka` feature)
+    pub kafka: Option<KafkaConfig>,
+    /// Publish processing results to NATS when present (requires the `nats` feature)
+    pub nats: Option<NatsConfig>,
+}
+
+impl Default for EventsConfig {
+    fn default() -> Self {
+        Self {
+            queue_capacity: 1024,
+            kafka: None,
+            nats: None,
+        }
+    }
+}
+
+/// Kafka producer settings
+#[derive(Debug, Clone, Deserialize)]
+pub struct KafkaConfig {
+    /// Bootstrap brokers, `host:port`
+    pub brokers: Vec<String>,
+    /// Topic task events are produced to
+    #[serde(default = "default_kafka_topic")]
+    pub topic: String,
+    /// Partition written to
+    #[serde(default)]
+    pub partition: i32,
+    /// Client ID reported to the brokers
+    #[serde(default = "default_kafka_client_id")]
+    pub client_id: String,
+}
+
+fn default_kafka_topic() -> String {
+    "po-agent.task-events".to_string()
+}
+
+fn default_kafka_client_id() -> String {
+    "po-agent".to_string()
+}
+
+/// NATS publisher settings
+#[derive(Debug, Clone, Deserialize)]
+pub struct NatsConfig {
+    /// Server URL(s), comma separated
+    #[serde(default = "default_nats_url")]
+    pub url: String,
+    /// Subject template; `{department}`, `{status}` and `{po_number}` are filled from the result
+    #[serde(default = "default_nats_subject")]
+    pub subject: String,
+    /// Connection name reported to the server
+    #[serde(default = "default_nats_client_name")]
+    pub client_name: String,
+}
+
+fn default_nats_url() -> String {
+    "nats://localhost:4222".to_string()
+}
+
+fn default_nats_subject() -> String {
+    "po.processed.{department}".to_string()
+}
+
+fn default_nats_client_name() -> String {
+    "po-agent".to_string()
+}
+
+impl NatsConfig {
+    /// Subject a processing result is published to
+    ///
+    /// Placeholder values are reduced to a single lower-case subject token, so a
+    /// department like "Field Ops" publishes to `po.processed.field-ops`.
+    pub fn subject_for(&self, result: &ProcessingResult) -> String {
+        self.subject
+            .replace("{department}", &subject_token(&result.buyer_department))
+            .replace("{status}", &subject_token(&result.status))
+            .replace("{po_number}", &subject_token(&result.po_number))
+    }
+}
+
+fn subject_token(value: &str) -> String {
+    let token: String = value
+        .trim()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' { c.to_ascii_lowercase() } else { '-' })
+        .collect();
+    if token.is_empty() { "unknown".to_string() } else { token }
+}
+
+/// Connect every publisher enabled in `config`
+pub async fn connect_publishers(config: &EventsConfig) -> Result<Vec<Arc<dyn EventPublisher>>, Box<dyn Error>> {
+    #[allow(unused_mut)]
+    let mut publishers: Vec<Arc<dyn EventPublisher>> = Vec::new();
+
+    if let Some(kafka) = &config.kafka {
+        #[cfg(feature = "kafka")]
+        publishers.push(Arc::new(super::kafka::KafkaPublisher::connect(kafka).await?));
+        #[cfg(not(feature = "kafka"))]
+        {
+            let _ = kafka;
+            return Err("[events.kafka] is configured but this binary was built without the `kafka` feature".into());
+        }
+    }
+
+    if let Some(nats) = &config.nats {
+        #[cfg(feature = "nats")]
+        publishers.push(Arc::new(super::nats::NatsPublisher::connect(nats).await?));
+        #[cfg(not(feature = "nats"))]
+        {
+            let _ = nats;
+            return Err("[events.nats] is configured but this binary was built without the `nats` feature".into());
+        }
+    }
+
+    Ok(publishers)
+}
+
+/// Fans task events out to publishers in the background
+///
+/// Publishing never blocks the request path: events are queued and delivered
+/// in order by a dispatcher task. When the queue is full, new events are dropped
+/// and counted in `po_agent_events_dropped_total`.
+#[derive(Clone)]
+pub struct EventBus {
+    sender: mpsc::Sender<TaskEvent>,
+    metrics: Arc<Metrics>,
+}
+
+impl EventBus {
+    /// Start the dispatcher; it stops once every clone of the bus is dropped
+    pub fn spawn(
+        publishers: Vec<Arc<dyn EventPublisher>>,
+        queue_capacity: usize,
+        metrics: Arc<Metrics>,
+        dependencies: Arc<Dependencies>,
+    ) -> (Self, JoinHandle<()>) {
+        let (sender, mut receiver) = mpsc::channel::<TaskEvent>(queue_capacity.max(1));
+        let dispatch_metrics = metrics.clone();
+
+        let handle = tokio::spawn(async move {
+            while let Some(event) = receiver.recv().await {
+                for publisher in &publishers {
+                    let labels = [("publisher", publisher.name())];
+                    match publisher.publish(&event).await {
+                        Ok(()) => {
+                            debug!(task_id = %event.task_id, sequence = event.sequence, publisher = publisher.name(), "task event published");
+                            dispatch_metrics.increment("po_agent_events_published_total", "Task events published", &labels, 1);
+                            dependencies.record_success(publisher.name());
+                        }
+                        Err(e) => {
+                            warn!(task_id = %event.task_id, publisher = publisher.name(), error = %e, "task event publish failed");
+                            dependencies.record_failure(publisher.name(), &e);
+                            dispatch_metrics.increment(
+                                "po_agent_events_publish_failures_total",
+                                "Task events that failed to publish",
+                                &labels,
+                                1,
+                            );
+                        }
+                    }
+                }
+            }
+        });
+
+        (Self { sender, metrics }, handle)
+    }
+
+    /// Queue an event for publishing
+    pub fn publish(&self, event: TaskEvent) {
+        if let Err(e) = self.sender.try_send(event) {
+            let event = match e {
+                mpsc::error::TrySendError::Full(event) | mpsc::error::TrySendError::Closed(event) => event,
+            };
+            warn!(task_id = %event.task_id, sequence = event.sequence, "event bus queue unavailable, event dropped");
+            self.metrics.increment("po_agent_events_dropped_total", "Task events dropped before publishing", &[], 1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::TaskEventKind;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct Recorder {
+        events: Mutex<Vec<TaskEvent>>,
+    }
+
+    #[async_trait]
+    impl EventPublisher for Recorder {
+        fn name(&self) -> &str {
+            "recorder"
+        }
+
+        async fn publish(&self, event: &TaskEvent) -> Result<(), PublishError> {
+            self.events.lock().unwrap().push(event.clone());
+            Ok(())
+        }
+    }
+
+    fn event(sequence: u64) -> TaskEvent {
+        TaskEvent {
+            sequence,
+            task_id: "po-1".to_string(),
+            at: chrono::Utc::now(),
+            kind: TaskEventKind::NotificationDelivered { channel: "test".to_string(), target: "t".to_string() },
+        }
+    }
+
+    #[tokio::test]
+    async fn test_bus_delivers_in_order_and_counts() {
+        let recorder = Arc::new(Recorder::default());
+        let metrics = Arc::new(Metrics::new());
+        let (bus, dispatcher) = EventBus::spawn(vec![recorder.clone()], 8, metrics.clone(), Arc::new(Dependencies::new()));
+
+        bus.publish(event(1));
+        bus.publish(event(2));
+        drop(bus);
+        dispatcher.await.unwrap();
+
+        let sequences: Vec<_> = recorder.events.lock().unwrap().iter().map(|e| e.sequence).collect();
+        assert_eq!(sequences, [1, 2]);
+        assert_eq!(metrics.value("po_agent_events_published_total", &[("publisher", "recorder")]), Some(2.0));
+    }
+
+    #[test]
+    fn test_kafka_config_defaults() {
+        let config: EventsConfig = toml::from_str("[kafka]\nbrokers = [\"localhost:9092\"]").unwrap();
+        let kafka = config.kafka.unwrap();
+        assert_eq!(kafka.topic, "po-agent.task-events");
+        assert_eq!(kafka.partition, 0);
+        assert_eq!(config.queue_capacity, 1024);
+    }
+
+    #[test]
+    fn test_nats_subject_uses_sanitized_department() {
+        let config: EventsConfig = toml::from_str("[nats]").unwrap();
+        let nats = config.nats.unwrap();
+        let mut result: ProcessingResult = serde_json::from_value(serde_json::json!({
+            "status": "processed",
+            "po_number": "PO-1",
+            "validation_errors": [],
+            "warnings": [],
+            "summary": {
+                "total_items": 0, "total_quantity": 0, "sub_total": 0.0, "tax": 0.0,
+                "grand_total": 0.0, "supplier": "s", "department": "d", "is_approved": true
+            },
+            "processed_at": "2026-01-01T00:00:00Z",
+            "supplier_name": "s",
+            "buyer_department": "Field Ops.West",
+            "notes": null,
+            "sub_total": 0.0,
+            "tax": 0.0,
+            "grand_total": 0.0
+        }))
+        .unwrap();
+
+        assert_eq!(nats.subject_for(&result), "po.processed.field-ops-west");
+        result.buyer_department = " ".to_string();
+        assert_eq!(nats.subject_for(&result), "po.processed.unknown");
+    }
+}
@@ -0,0 +1,61 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use rskafka::client::partition::{Compression, PartitionClient, UnknownTopicHandling};
+use rskafka::client::ClientBuilder;
+use rskafka::record::Record;
+use std::collections::BTreeMap;
+use std::error::Error;
+use tracing::info;
+
+use super::bus::{EventPublisher, KafkaConfig, PublishError};
+use super::TaskEvent;
+
+/// Produces task events to a Kafka topic, keyed by task ID
+///
+/// Each record carries the JSON-encoded event as its value and the event type
+/// in an `event-type` header so consumers can filter without decoding.
+pub struct KafkaPublisher {
+    client: PartitionClient,
+    name: String,
+}
+
+impl KafkaPublisher {
+    /// Connect to the brokers and resolve the configured topic partition
+    pub async fn connect(config: &KafkaConfig) -> Result<Self, Box<dyn Error>> {
+        let client = ClientBuilder::new(config.brokers.clone())
+            .client_id(config.client_id.as_str())
+            .build()
+            .await?
+            .partition_client(config.topic.clone(), config.partition, UnknownTopicHandling::Retry)
+            .await?;
+
+        info!(topic = %config.topic, partition = config.partition, "Kafka event publisher connected");
+        Ok(Self {
+            client,
+            name: format!("kafka:{}", config.topic),
+        })
+    }
+}
+
+#[async_trait]
+impl EventPublisher for KafkaPublisher {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn publish(&self, event: &TaskEvent) -> Result<(), PublishError> {
+        let value = serde_json::to_vec(event).map_err(|e| PublishError(e.to_string()))?;
+        let record = Record {
+            key: Some(event.task_id.clone().into_bytes()),
+            value: Some(value),
+            headers: BTreeMap::from([("event-type".to_string(), event.kind.name().as_bytes().to_vec())]),
+            timestamp: Utc::now(),
+        };
+
+        self.client
+            .produce(vec![record], Compression::NoCompression)
+            .await
+            .map(|_| ())
+            .map_err(|e| PublishError(e.to_string()))
+    }
+}
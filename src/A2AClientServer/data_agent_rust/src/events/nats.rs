@@ -0,0 +1,66 @@
+use async_trait::async_trait;
+use std::error::Error;
+use tracing::info;
+
+use super::bus::{EventPublisher, NatsConfig, PublishError};
+use super::{TaskEvent, TaskEventKind};
+use crate::agent::ProcessingResult;
+use a2a::TaskState;
+
+/// Publishes the processing result of each completed task to NATS
+///
+/// Only `state_changed` events into the completed state are published; the
+/// message body is the JSON `ProcessingResult` and the subject comes from
+/// [`NatsConfig::subject_for`]. A `Nats-Msg-Id` header of `{task_id}-{sequence}`
+/// lets JetStream de-duplicate redeliveries.
+pub struct NatsPublisher {
+    client: async_nats::Client,
+    config: NatsConfig,
+    name: String,
+}
+
+impl NatsPublisher {
+    /// Connect to the configured NATS server(s)
+    pub async fn connect(config: &NatsConfig) -> Result<Self, Box<dyn Error>> {
+        let client = async_nats::ConnectOptions::new()
+            .name(&config.client_name)
+            .connect(config.url.as_str())
+            .await?;
+
+        info!(url = %config.url, subject = %config.subject, "NATS result publisher connected");
+        Ok(Self {
+            client,
+            config: config.clone(),
+            name: "nats".to_string(),
+        })
+    }
+}
+
+#[async_trait]
+impl EventPublisher for NatsPublisher {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn publish(&self, event: &TaskEvent) -> Result<(), PublishError> {
+        let TaskEventKind::StateChanged { status } = &event.kind else {
+            return Ok(());
+        };
+        if !matches!(status.state, TaskState::Completed) {
+            return Ok(());
+        }
+        let Some(result) = ProcessingResult::from_status(status) else {
+            return Ok(());
+        };
+
+        let payload = serde_json::to_vec(&result).map_err(|e| PublishError(e.to_string()))?;
+        let mut headers = async_nats::HeaderMap::new();
+        headers.insert("Nats-Msg-Id", format!("{}-{}", event.task_id, event.sequence).as_str());
+        headers.insert("Task-Id", event.task_id.as_str());
+
+        self.client
+            .publish_with_headers(self.config.subject_for(&result), headers, payload.into())
+            .await
+            .map_err(|e| PublishError(e.to_string()))
+    }
+}
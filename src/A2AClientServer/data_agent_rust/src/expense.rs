@@ -0,0 +1,431 @@
+//! Expense report validation skill
+//!
+//! Employees' expense reports go through the same endpoint, findings and
+//! approval workflow as purchase orders. Each line is checked against the
+//! limit of its category and the amount above which a receipt is required;
+//! mileage is priced at the configured rate per mile. Failed checks are
+//! [`Check`]s, so `[severity]` decides which fail a report. The approval
+//! matrix then decides who approves it, by department and total; without a
+//! matrix, reports with warnings wait for an approver and the others are
+//! approved. Reports pending approval are decided through the approval API
+//! like orders, except that their lines cannot be rejected one by one.
+
+use a2a::{Message, Part, Task, TaskState, TaskStatus};
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+use crate::agent::PurchaseOrderAgent;
+use crate::approval::{ApprovalConfig, ApprovalRequirement, PENDING_APPROVAL};
+use crate::directory::Directory;
+use crate::events::TaskEventKind;
+use crate::invoice::round2;
+use crate::parts::{self, PartSchema};
+use crate::severity::{Check, Findings, SeverityPolicy};
+use crate::skills::SkillHandler;
+
+/// Skill validating employee expense reports
+pub const EXPENSE_SKILL: &str = "expense-report-validation";
+
+/// What an expense was for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExpenseCategory {
+    Meals,
+    Lodging,
+    Airfare,
+    GroundTransport,
+    /// Driving a personal vehicle, reimbursed per mile
+    Mileage,
+    Supplies,
+    Other,
+}
+
+impl fmt::Display for ExpenseCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = serde_json::to_value(self).ok().and_then(|value| value.as_str().map(str::to_string));
+        f.write_str(&name.unwrap_or_default())
+    }
+}
+
+/// An employee's claim for expenses paid on the company's behalf
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExpenseReport {
+    pub report_number: String,
+    pub employee: String,
+    pub department: String,
+    pub lines: Vec<ExpenseLine>,
+    /// Total claimed, checked against the sum of the lines when given
+    #[serde(default)]
+    pub total: Option<f64>,
+    #[serde(default)]
+    pub notes: Option<String>,
+}
+
+/// One expense claimed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExpenseLine {
+    pub date: NaiveDate,
+    pub category: ExpenseCategory,
+    pub description: String,
+    pub amount: f64,
+    /// Whether a receipt is attached
+    #[serde(default)]
+    pub receipt: bool,
+    /// Distance driven, required of mileage lines
+    #[serde(default)]
+    pub miles: Option<f64>,
+}
+
+/// Wrapper for the incoming expense report data
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExpenseReportWrapper {
+    pub expense_report: ExpenseReport,
+}
+
+/// Spending policy expense reports are checked against
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ExpenseConfig {
+    /// Most reimbursed per line, by category; categories not listed have no limit
+    pub limits: HashMap<ExpenseCategory, f64>,
+    /// Lines of this amount or more need a receipt; mileage never does
+    pub receipt_threshold: f64,
+    /// Reimbursement per mile driven
+    pub mileage_rate: f64,
+}
+
+impl Default for ExpenseConfig {
+    fn default() -> Self {
+        Self {
+            limits: HashMap::from([(ExpenseCategory::Meals, 75.0), (ExpenseCategory::Lodging, 250.0)]),
+            receipt_threshold: 25.0,
+            mileage_rate: 0.70,
+        }
+    }
+}
+
+/// Outcome of validating an expense report
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExpenseReview {
+    /// `APPROVED`, `PENDING_APPROVAL`, `VALIDATION_FAILED` or, once decided, `REJECTED`
+    pub status: String,
+    pub report_number: String,
+    pub employee: String,
+    pub department: String,
+    pub validation_errors: Vec<String>,
+    pub warnings: Vec<String>,
+    /// Sum of the lines as claimed
+    pub total: f64,
+    /// What is paid back: lines capped at their category limits, mileage at the configured rate
+    pub reimbursable_total: f64,
+    /// Approvers the approval matrix requires, when one is configured
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approval_requirement: Option<ApprovalRequirement>,
+    pub processed_at: DateTime<Utc>,
+}
+
+impl ExpenseReview {
+    /// Extract the review carried in a task status message's Data part
+    pub fn from_status(status: &TaskStatus) -> Option<Self> {
+        status.message.as_ref()?.parts.iter().find_map(|part| match part {
+            Part::Data { data } => Self::deserialize(data).ok(),
+            _ => None,
+        })
+    }
+
+    /// Task status reporting this review; failed unless the report is valid and not rejected
+    fn task_status(&self, agent: &PurchaseOrderAgent) -> Result<TaskStatus, Box<dyn Error>> {
+        Ok(TaskStatus {
+            state: if self.validation_errors.is_empty() && self.status != "REJECTED" {
+                TaskState::Completed
+            } else {
+                TaskState::Failed
+            },
+            message: Some(Message {
+                role: "assistant".to_string(),
+                parts: vec![Part::Data { data: serde_json::to_value(self)? }],
+            }),
+            timestamp: agent.current_timestamp(),
+        })
+    }
+}
+
+/// Check an expense report against the spending policy and the directory under a severity policy
+pub fn validate<'a>(
+    report: &ExpenseReport,
+    config: &ExpenseConfig,
+    directory: &Directory,
+    severity: &'a SeverityPolicy,
+) -> Findings<'a> {
+    let mut findings = Findings::new(severity);
+
+    if report.report_number.trim().is_empty() {
+        findings.push(Check::ExpenseMissingField, "Report number is required");
+    }
+    if report.employee.trim().is_empty() {
+        findings.push(Check::ExpenseMissingField, "Employee is required");
+    }
+    if report.department.trim().is_empty() {
+        findings.push(Check::ExpenseMissingField, "Department is required");
+    } else {
+        directory.check(&report.department, Some(&report.employee), &mut findings);
+    }
+    if report.lines.is_empty() {
+        findings.push(Check::NoItems, "Expense report must contain at least one line");
+    }
+
+    for (index, line) in report.lines.iter().enumerate() {
+        if line.description.trim().is_empty() {
+            findings.push(Check::ExpenseMissingField, format!("Line {} is missing description", index + 1));
+        }
+        if line.amount <= 0.0 {
+            findings.push(Check::ExpenseInvalidAmount, format!("Line {} has invalid amount", index + 1));
+            continue;
+        }
+        if line.category == ExpenseCategory::Mileage {
+            match line.miles {
+                Some(miles) if miles > 0.0 => {
+                    let expected = round2(miles * config.mileage_rate);
+                    if (expected - line.amount).abs() > 0.005 {
+                        findings.push(Check::MileageAmount, format!(
+                            "Line {} mileage mismatch: {} miles at {:.2} is {:.2}, claimed {:.2}",
+                            index + 1, miles, config.mileage_rate, expected, line.amount
+                        ));
+                    }
+                }
+                _ => findings.push(Check::ExpenseMissingField, format!("Line {} is mileage without miles driven", index + 1)),
+            }
+        } else if !line.receipt && line.amount >= config.receipt_threshold {
+            findings.push(Check::ReceiptRequired, format!(
+                "Line {} needs a receipt: {:.2} is at least {:.2}",
+                index + 1, line.amount, config.receipt_threshold
+            ));
+        }
+        if let Some(limit) = config.limits.get(&line.category)
+            && line.amount > *limit
+        {
+            findings.push(Check::ExpenseOverLimit, format!(
+                "Line {} exceeds the {} limit: {:.2} over {:.2}",
+                index + 1, line.category, line.amount, limit
+            ));
+        }
+    }
+
+    let total = total(report);
+    if let Some(claimed) = report.total
+        && (claimed - total).abs() > 0.005
+    {
+        findings.push(Check::ExpenseTotalMismatch, format!(
+            "Total mismatch: expected {:.2}, got {:.2}",
+            total, claimed
+        ));
+    }
+
+    findings
+}
+
+fn total(report: &ExpenseReport) -> f64 {
+    round2(report.lines.iter().map(|line| line.amount).sum())
+}
+
+/// What the report pays back: each line capped at its limit, mileage at the configured rate
+pub fn reimbursable_total(report: &ExpenseReport, config: &ExpenseConfig) -> f64 {
+    let reimbursed = report.lines.iter().filter(|line| line.amount > 0.0).map(|line| {
+        let amount = match (line.category, line.miles) {
+            (ExpenseCategory::Mileage, Some(miles)) => (miles * config.mileage_rate).min(line.amount),
+            _ => line.amount,
+        };
+        config.limits.get(&line.category).map_or(amount, |limit| amount.min(*limit))
+    });
+    round2(reimbursed.sum())
+}
+
+/// Validate a report and decide whether it is approved or needs an approver
+pub fn review(
+    report: &ExpenseReport,
+    config: &ExpenseConfig,
+    directory: &Directory,
+    severity: &SeverityPolicy,
+    approval: &ApprovalConfig,
+) -> ExpenseReview {
+    let (validation_errors, warnings) = validate(report, config, directory, severity).into_parts();
+    let total = total(report);
+    let approval_requirement = if validation_errors.is_empty() {
+        approval.requirement(&report.department, total)
+    } else {
+        None
+    };
+    let status = if !validation_errors.is_empty() {
+        "VALIDATION_FAILED"
+    } else if let Some(requirement) = &approval_requirement {
+        if requirement.is_automatic() { "APPROVED" } else { PENDING_APPROVAL }
+    } else if warnings.is_empty() {
+        "APPROVED"
+    } else {
+        PENDING_APPROVAL
+    };
+
+    ExpenseReview {
+        status: status.to_string(),
+        report_number: report.report_number.clone(),
+        employee: report.employee.clone(),
+        department: report.department.clone(),
+        validation_errors,
+        warnings,
+        total,
+        reimbursable_total: reimbursable_total(report, config),
+        approval_requirement,
+        processed_at: Utc::now(),
+    }
+}
+
+/// Find the expense report carried by a message, as JSON in a Data or Text part
+pub fn extract_expense_report(message: &Message) -> Result<ExpenseReport, Box<dyn Error>> {
+    parts::extract(message, PartSchema::ExpenseReport, |wrapper: ExpenseReportWrapper| wrapper.expense_report)
+}
+
+/// Record the final decision on an expense report pending approval
+pub(crate) fn record_approval(
+    agent: &PurchaseOrderAgent,
+    task_id: &str,
+    mut review: ExpenseReview,
+    approver: &str,
+    approved: bool,
+    comment: Option<String>,
+) -> Result<Task, Box<dyn Error>> {
+    if review.status != PENDING_APPROVAL {
+        return Err(format!("Task {} is {}, not pending approval", task_id, review.status).into());
+    }
+    review.status = if approved { "APPROVED" } else { "REJECTED" }.to_string();
+    let status = review.task_status(agent)?;
+    let updated = agent
+        .task_store()
+        .update(task_id, &mut |record| record.set_status(status.clone()))?
+        .ok_or_else(|| format!("Task {} not found", task_id))?;
+    agent.record_event(
+        task_id,
+        TaskEventKind::ApprovalRecorded { approver: approver.to_string(), approved, comment, rejected_lines: Vec::new() },
+    )?;
+    agent.record_event(task_id, TaskEventKind::StateChanged { status })?;
+    Ok(updated.task)
+}
+
+/// Validates expense reports against the spending policy
+pub struct ExpenseSkill {
+    config: ExpenseConfig,
+}
+
+impl ExpenseSkill {
+    pub fn new(config: ExpenseConfig) -> Self {
+        Self { config }
+    }
+}
+
+#[async_trait]
+impl SkillHandler for ExpenseSkill {
+    fn id(&self) -> &str {
+        EXPENSE_SKILL
+    }
+
+    async fn handle(&self, agent: &PurchaseOrderAgent, message: &Message) -> Result<TaskStatus, Box<dyn Error>> {
+        let report = extract_expense_report(message)?;
+        let review = review(&report, &self.config, &agent.directory(), &agent.validation_config().severity, agent.approval_config());
+        review.task_status(agent)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::approval::{self, ApprovalRule};
+    use a2a::A2AProtocol;
+
+    fn line(category: ExpenseCategory, amount: f64, receipt: bool) -> ExpenseLine {
+        ExpenseLine {
+            date: NaiveDate::from_ymd_opt(2025, 3, 3).unwrap(),
+            category,
+            description: "Client visit".to_string(),
+            amount,
+            receipt,
+            miles: None,
+        }
+    }
+
+    fn report(lines: Vec<ExpenseLine>) -> ExpenseReport {
+        ExpenseReport {
+            report_number: "EXP-1".to_string(),
+            employee: "Sam".to_string(),
+            department: "Sales".to_string(),
+            lines,
+            total: None,
+            notes: None,
+        }
+    }
+
+    #[test]
+    fn test_lines_are_checked_against_the_policy() {
+        let (config, severity) = (ExpenseConfig::default(), SeverityPolicy::default());
+        let mut mileage = line(ExpenseCategory::Mileage, 30.0, false);
+        mileage.miles = Some(40.0);
+        let mut claimed = report(vec![
+            line(ExpenseCategory::Meals, 90.0, true),
+            line(ExpenseCategory::Supplies, 40.0, false),
+            mileage,
+            line(ExpenseCategory::Other, 12.5, false),
+        ]);
+        claimed.total = Some(100.0);
+
+        let findings = validate(&claimed, &config, &Directory::builtin(), &severity);
+        assert_eq!(
+            findings.failed(),
+            [Check::ExpenseOverLimit, Check::ReceiptRequired, Check::MileageAmount, Check::ExpenseTotalMismatch]
+        );
+        assert_eq!(findings.errors, ["Line 2 needs a receipt: 40.00 is at least 25.00"]);
+        assert_eq!(
+            findings.warnings,
+            [
+                "Line 1 exceeds the meals limit: 90.00 over 75.00",
+                "Line 3 mileage mismatch: 40 miles at 0.70 is 28.00, claimed 30.00",
+                "Total mismatch: expected 172.50, got 100.00",
+            ]
+        );
+        assert_eq!(reimbursable_total(&claimed, &config), 155.5);
+
+        let approval = ApprovalConfig::default();
+        assert_eq!(review(&report(vec![line(ExpenseCategory::Meals, 20.0, false)]), &config, &Directory::builtin(), &severity, &approval).status, "APPROVED");
+        let over = review(&report(vec![line(ExpenseCategory::Lodging, 300.0, true)]), &config, &Directory::builtin(), &severity, &approval);
+        assert_eq!((over.status.as_str(), over.reimbursable_total), (PENDING_APPROVAL, 250.0));
+    }
+
+    #[tokio::test]
+    async fn test_reports_go_through_the_approval_matrix() {
+        let matrix = ApprovalConfig {
+            matrix: vec![ApprovalRule { max_amount: Some(100.0), approvals: 0, ..ApprovalRule::default() }],
+            ..ApprovalConfig::default()
+        };
+        let agent = PurchaseOrderAgent::new().with_approval(matrix);
+        let send = |report: ExpenseReport| {
+            let data = serde_json::json!({ "skill_id": EXPENSE_SKILL, "expenseReport": report });
+            agent.send_task(Message { role: "user".to_string(), parts: vec![Part::Data { data }] })
+        };
+
+        let small = send(report(vec![line(ExpenseCategory::Meals, 20.0, true)])).await.unwrap();
+        assert_eq!(ExpenseReview::from_status(&small.status).unwrap().status, "APPROVED");
+
+        let large = send(report(vec![line(ExpenseCategory::Airfare, 450.0, true)])).await.unwrap();
+        let record = agent.task_store().get(&large.id).unwrap().unwrap();
+        assert!(approval::is_pending(&record));
+        assert_eq!(approval::requirement(&record).unwrap().approvals, 1);
+
+        let decided = approval::decide(&agent, &large.id, "Alex", &[], false, Some("Book economy".to_string()), &[]).unwrap();
+        assert!(matches!(decided.status.state, TaskState::Failed));
+        assert_eq!(ExpenseReview::from_status(&decided.status).unwrap().status, "REJECTED");
+    }
+}
@@ -0,0 +1,232 @@
+//! Streaming NDJSON and JSON exports of the task store
+//!
+//! Records are fetched a batch at a time and written to a bounded channel, so a
+//! slow client holds back the export instead of the server buffering it.
+
+use axum::body::{Body, Bytes};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use std::io;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::{debug, warn};
+
+use crate::labels::Labels;
+use crate::redaction::RedactionConfig;
+use crate::store::{TaskRecord, TaskStore};
+
+#[cfg(feature = "parquet")]
+pub mod parquet;
+
+/// Records encoded into each chunk sent to the client
+pub const RECORDS_PER_CHUNK: usize = 256;
+
+/// Chunks encoded ahead of a client reading them
+pub const BUFFERED_CHUNKS: usize = 4;
+
+/// Values of one column of a flat export, `None` for nulls
+#[derive(Debug, Clone)]
+pub enum Column {
+    /// UTF-8 strings, stored in Parquet as `BYTE_ARRAY` annotated `STRING`
+    Text(Vec<Option<String>>),
+    /// Stored in Parquet as `DOUBLE`
+    Double(Vec<Option<f64>>),
+}
+
+impl Column {
+    pub fn len(&self) -> usize {
+        match self {
+            Column::Text(values) => values.len(),
+            Column::Double(values) => values.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// One line of a results export; the task's outcome without its request
+#[derive(Debug, Serialize)]
+pub struct ResultRow {
+    pub task_id: String,
+    pub state: &'static str,
+    pub skill_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Labels::is_empty")]
+    pub labels: Labels,
+    /// Language tag the task was submitted with
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+    /// The skill's result, e.g. a processing result or a validation report
+    pub result: Option<Value>,
+    /// The status text of tasks without a result, such as why they failed
+    pub message: Option<String>,
+}
+
+impl ResultRow {
+    pub fn from_record(record: &TaskRecord) -> Self {
+        let parts = record.task.status.message.as_ref().map(|message| message.parts.as_slice()).unwrap_or_default();
+        let result = parts.iter().find_map(|part| match part {
+            a2a::Part::Data { data } => Some(data.clone()),
+            _ => None,
+        });
+        let message = result.is_none().then(|| {
+            parts.iter().find_map(|part| match part {
+                a2a::Part::Text { text } => Some(text.clone()),
+                _ => None,
+            })
+        });
+        Self {
+            task_id: record.task.id.clone(),
+            state: record.state_name(),
+            skill_id: record.skill_id.clone(),
+            created_at: record.created_at,
+            updated_at: record.updated_at,
+            labels: record.labels.clone(),
+            locale: record.locale.clone(),
+            result,
+            message: message.flatten(),
+        }
+    }
+}
+
+/// Stream every record matching `filter`, oldest first, as one JSON line produced by `line`
+///
+/// Records are redacted as `redaction` says before `line` sees them. Records
+/// removed while the export runs are skipped. The export stops when the client
+/// goes away; a store error ends the body with an error, truncating the download.
+pub fn ndjson<T, F, L>(store: Arc<dyn TaskStore>, redaction: RedactionConfig, filter: F, line: L) -> Body
+where
+    T: Serialize,
+    F: Fn(&TaskRecord) -> bool + Send + 'static,
+    L: Fn(&TaskRecord) -> T + Send + 'static,
+{
+    stream(store, redaction, filter, line, false)
+}
+
+/// Stream every record matching `filter`, oldest first, as a JSON array of what `item` produces
+///
+/// Behaves as [`ndjson`] does; a truncated download is not valid JSON.
+pub fn json_array<T, F, L>(store: Arc<dyn TaskStore>, redaction: RedactionConfig, filter: F, item: L) -> Body
+where
+    T: Serialize,
+    F: Fn(&TaskRecord) -> bool + Send + 'static,
+    L: Fn(&TaskRecord) -> T + Send + 'static,
+{
+    stream(store, redaction, filter, item, true)
+}
+
+fn stream<T, F, L>(store: Arc<dyn TaskStore>, redaction: RedactionConfig, filter: F, line: L, array: bool) -> Body
+where
+    T: Serialize,
+    F: Fn(&TaskRecord) -> bool + Send + 'static,
+    L: Fn(&TaskRecord) -> T + Send + 'static,
+{
+    let (tx, rx) = mpsc::channel::<io::Result<Bytes>>(BUFFERED_CHUNKS);
+    tokio::spawn(async move {
+        let ids = match store.snapshot_ids() {
+            Ok(ids) => ids,
+            Err(e) => {
+                let _ = tx.send(Err(io::Error::other(e.to_string()))).await;
+                return;
+            }
+        };
+        let mut exported = 0;
+        for batch in ids.chunks(RECORDS_PER_CHUNK) {
+            let mut chunk = Vec::new();
+            for id in batch {
+                let record = match store.get(id) {
+                    Ok(Some(record)) if filter(&record) => redaction.persisted(&record),
+                    Ok(_) => continue,
+                    Err(e) => {
+                        warn!(error = %e, exported, "export aborted");
+                        let _ = tx.send(Err(io::Error::other(e.to_string()))).await;
+                        return;
+                    }
+                };
+                let json = match serde_json::to_vec(&line(&record)) {
+                    Ok(json) => json,
+                    Err(e) => {
+                        warn!(task_id = %id, error = %e, "task left out of export");
+                        continue;
+                    }
+                };
+                match (array, exported) {
+                    (true, 0) => chunk.extend_from_slice(b"[\n"),
+                    (true, _) => chunk.extend_from_slice(b",\n"),
+                    (false, _) => {}
+                }
+                chunk.extend_from_slice(&json);
+                if !array {
+                    chunk.push(b'\n');
+                }
+                exported += 1;
+            }
+            if !chunk.is_empty() && tx.send(Ok(Bytes::from(chunk))).await.is_err() {
+                debug!(exported, "export abandoned by client");
+                return;
+            }
+        }
+        if array {
+            let close: &'static [u8] = if exported == 0 { b"[]\n" } else { b"\n]\n" };
+            let _ = tx.send(Ok(Bytes::from_static(close))).await;
+        }
+        debug!(exported, "export finished");
+    });
+    Body::from_stream(ReceiverStream::new(rx))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::MemoryTaskStore;
+    use crate::testing::aged_task_record;
+    use a2a::{Message, Part, TaskState};
+
+    fn record(id: &str, minutes_ago: i64) -> TaskRecord {
+        let mut record = aged_task_record(id, TaskState::Completed, chrono::Duration::minutes(minutes_ago));
+        let result = Message { role: "assistant".to_string(), parts: vec![Part::Data { data: serde_json::json!({ "n": id }) }] };
+        record.task.status.message = Some(result);
+        record
+    }
+
+    #[tokio::test]
+    async fn test_streams_records_oldest_first_in_chunks() {
+        let store = Arc::new(MemoryTaskStore::new());
+        let count = RECORDS_PER_CHUNK * 2 + 3;
+        for n in 0..count {
+            store.put(record(&format!("task-{:04}", n), (count - n) as i64)).unwrap();
+        }
+
+        let body = ndjson(store.clone(), RedactionConfig::default(), |record| record.task.id != "task-0001", ResultRow::from_record);
+        let bytes = axum::body::to_bytes(body, usize::MAX).await.unwrap();
+        let rows: Vec<Value> = bytes.split(|&b| b == b'\n').filter(|line| !line.is_empty()).map(|line| serde_json::from_slice(line).unwrap()).collect();
+
+        assert_eq!(rows.len(), count - 1);
+        assert_eq!(rows[0]["task_id"], "task-0000");
+        assert_eq!(rows[1]["task_id"], "task-0002");
+        assert_eq!(rows[count - 2]["result"], serde_json::json!({ "n": format!("task-{:04}", count - 1) }));
+        assert_eq!(rows[0]["state"], "completed");
+        assert!(rows[0]["message"].is_null());
+    }
+
+    #[tokio::test]
+    async fn test_json_array_spans_chunks_and_handles_an_empty_store() {
+        let store = Arc::new(MemoryTaskStore::new());
+        let empty = json_array(store.clone(), RedactionConfig::default(), |_| true, ResultRow::from_record);
+        assert_eq!(axum::body::to_bytes(empty, usize::MAX).await.unwrap(), "[]\n");
+
+        let count = RECORDS_PER_CHUNK + 1;
+        for n in 0..count {
+            store.put(record(&format!("task-{:04}", n), (count - n) as i64)).unwrap();
+        }
+        let body = json_array(store, RedactionConfig::default(), |_| true, ResultRow::from_record);
+        let rows: Vec<Value> = serde_json::from_slice(&axum::body::to_bytes(body, usize::MAX).await.unwrap()).unwrap();
+        assert_eq!(rows.len(), count);
+        assert_eq!(rows[count - 1]["task_id"], format!("task-{:04}", count - 1));
+    }
+}
@@ -0,0 +1,121 @@
+//! Parquet files for flat tables of text and numbers
+//!
+//! Every column is optional. The rows go in one uncompressed row group, enough
+//! for warehouses and dataframe libraries to load exports.
+
+use parquet::basic::{LogicalType, Repetition, Type as PhysicalType};
+use parquet::data_type::{ByteArray, ByteArrayType, DoubleType};
+use parquet::errors::ParquetError;
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::SerializedFileWriter;
+use parquet::schema::types::Type;
+use std::sync::Arc;
+
+use super::Column;
+
+impl Column {
+    fn schema(&self, name: &str) -> parquet::errors::Result<Type> {
+        let builder = match self {
+            Column::Text(_) => {
+                Type::primitive_type_builder(name, PhysicalType::BYTE_ARRAY).with_logical_type(Some(LogicalType::String))
+            }
+            Column::Double(_) => Type::primitive_type_builder(name, PhysicalType::DOUBLE),
+        };
+        builder.with_repetition(Repetition::OPTIONAL).build()
+    }
+
+    /// Definition levels: 1 for a value, 0 for a null
+    fn levels(&self) -> Vec<i16> {
+        match self {
+            Column::Text(values) => values.iter().map(|value| i16::from(value.is_some())).collect(),
+            Column::Double(values) => values.iter().map(|value| i16::from(value.is_some())).collect(),
+        }
+    }
+}
+
+/// Encode `columns` as a Parquet file; every column must have the same number of rows
+pub fn write(columns: &[(&str, Column)]) -> Result<Vec<u8>, String> {
+    let rows = columns.first().map(|(_, column)| column.len()).unwrap_or(0);
+    if let Some((name, _)) = columns.iter().find(|(_, column)| column.len() != rows) {
+        return Err(format!("Parquet column {} does not have {} rows", name, rows));
+    }
+    encode(columns, rows).map_err(|e| format!("Failed to write Parquet: {}", e))
+}
+
+fn encode(columns: &[(&str, Column)], rows: usize) -> parquet::errors::Result<Vec<u8>> {
+    let fields = columns.iter().map(|(name, column)| column.schema(name).map(Arc::new)).collect::<Result<_, _>>()?;
+    let schema = Type::group_type_builder("schema").with_fields(fields).build()?;
+    let properties = WriterProperties::builder().set_created_by("data_agent_rust".to_string()).build();
+    let mut writer = SerializedFileWriter::new(Vec::new(), Arc::new(schema), Arc::new(properties))?;
+
+    if rows > 0 {
+        let mut row_group = writer.next_row_group()?;
+        for (_, column) in columns {
+            let Some(mut writer) = row_group.next_column()? else {
+                return Err(ParquetError::General("the schema has fewer columns than the table".to_string()));
+            };
+            let levels = column.levels();
+            match column {
+                Column::Text(values) => {
+                    let values: Vec<ByteArray> = values.iter().flatten().map(|value| ByteArray::from(value.as_str())).collect();
+                    writer.typed::<ByteArrayType>().write_batch(&values, Some(&levels), None)?;
+                }
+                Column::Double(values) => {
+                    let values: Vec<f64> = values.iter().flatten().copied().collect();
+                    writer.typed::<DoubleType>().write_batch(&values, Some(&levels), None)?;
+                }
+            }
+            writer.close()?;
+        }
+        row_group.close()?;
+    }
+    writer.into_inner()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+    use parquet::record::Field;
+
+    #[test]
+    fn test_written_file_reads_back() {
+        let columns = [
+            ("po_number", Column::Text(vec![Some("PO-1".to_string()), None, Some("PO-3".to_string())])),
+            ("grand_total", Column::Double(vec![Some(10.5), Some(20.0), None])),
+        ];
+        let reader = SerializedFileReader::new(axum::body::Bytes::from(write(&columns).unwrap())).unwrap();
+        let metadata = reader.metadata().file_metadata();
+        assert_eq!(metadata.num_rows(), 3);
+        let names: Vec<&str> = metadata.schema_descr().columns().iter().map(|column| column.name()).collect();
+        assert_eq!(names, ["po_number", "grand_total"]);
+
+        let rows: Vec<Vec<Field>> = reader
+            .get_row_iter(None)
+            .unwrap()
+            .map(|row| row.unwrap().get_column_iter().map(|(_, field)| field.clone()).collect())
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                vec![Field::Str("PO-1".to_string()), Field::Double(10.5)],
+                vec![Field::Null, Field::Double(20.0)],
+                vec![Field::Str("PO-3".to_string()), Field::Null],
+            ]
+        );
+
+        let empty = write(&[("po_number", Column::Text(Vec::new()))]).unwrap();
+        let reader = SerializedFileReader::new(axum::body::Bytes::from(empty)).unwrap();
+        assert_eq!(reader.metadata().file_metadata().num_rows(), 0);
+    }
+
+    #[test]
+    fn test_columns_of_different_lengths_are_an_error() {
+        let columns = [
+            ("po_number", Column::Text(vec![Some("PO-1".to_string())])),
+            ("grand_total", Column::Double(Vec::new())),
+        ];
+        let err = write(&columns).unwrap_err();
+        assert!(err.contains("grand_total"), "{}", err);
+    }
+}
@@ -0,0 +1,130 @@
+//! Runtime switches for the optional subsystems, in the `[flags]` section
+//!
+//! Every subsystem is on by default, and runs once it is configured. Turning a
+//! flag off disables the subsystem even though its section stays in the file,
+//! so one configuration can be shared by deployments of different shapes, each
+//! overriding only its flags. A disabled subsystem's configuration is cleared
+//! when the file is loaded, so the agent card, `/ready`, `/status` and
+//! `--check-config` all see it as not configured.
+//!
+//! Skills are switched per skill ID under `[flags.skills]`; a disabled skill is
+//! neither registered nor listed on the agent card.
+
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+use crate::config::ServerConfig;
+
+/// The `[flags]` section
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct FeatureFlags {
+    /// Forwarding of orders awaiting approval to a downstream agent
+    pub delegation: bool,
+    /// Webhook and email notifications
+    pub notifications: bool,
+    /// Publishing of task events to Kafka and NATS
+    pub events: bool,
+    /// Queue consumers feeding purchase orders into the agent
+    pub intake: bool,
+    /// Scheduled exports of results
+    pub exports: bool,
+    /// Skills switched on or off by ID; skills not listed stay on
+    pub skills: BTreeMap<String, bool>,
+}
+
+impl Default for FeatureFlags {
+    fn default() -> Self {
+        Self { delegation: true, notifications: true, events: true, intake: true, exports: true, skills: BTreeMap::new() }
+    }
+}
+
+impl FeatureFlags {
+    /// Names of the subsystems switched off, in the order of the section
+    pub fn disabled(&self) -> Vec<&'static str> {
+        [
+            ("delegation", self.delegation),
+            ("notifications", self.notifications),
+            ("events", self.events),
+            ("intake", self.intake),
+            ("exports", self.exports),
+        ]
+        .into_iter()
+        .filter(|(_, enabled)| !enabled)
+        .map(|(name, _)| name)
+        .collect()
+    }
+
+    /// IDs of the skills switched off
+    pub fn disabled_skills(&self) -> impl Iterator<Item = &str> {
+        self.skills.iter().filter(|(_, enabled)| !**enabled).map(|(id, _)| id.as_str())
+    }
+
+    /// Clear the configuration of every subsystem switched off in `config`
+    pub fn apply(config: &mut ServerConfig) {
+        let flags = config.flags.clone();
+        if !flags.delegation {
+            config.delegation.url = None;
+            config.delegation.skill = None;
+            config.delegation.tags.clear();
+        }
+        if !flags.notifications {
+            config.webhooks.endpoints.clear();
+            config.webhooks.callback_hosts.clear();
+            config.email.smtp = None;
+        }
+        if !flags.events {
+            config.events.kafka = None;
+            config.events.nats = None;
+        }
+        if !flags.intake {
+            config.intake.amqp = None;
+            config.intake.sqs = None;
+        }
+        if !flags.exports {
+            config.exports.jobs.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disabled_subsystems_are_not_configured() {
+        let config = ServerConfig::from_toml(
+            r#"
+            [flags]
+            notifications = false
+            exports = false
+
+            [flags.skills]
+            expense-report-validation = false
+            supplier-onboarding = true
+
+            [delegation]
+            url = "http://approvals.invalid"
+
+            [[webhooks.endpoints]]
+            name = "erp"
+            url = "http://erp.invalid/hook"
+
+            [email.smtp]
+            host = "smtp.invalid"
+
+            [[exports.jobs]]
+            id = "nightly"
+            cron = "0 2 * * *"
+            destination = { type = "webhook", url = "http://exports.invalid" }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.flags.disabled(), ["notifications", "exports"]);
+        assert_eq!(config.flags.disabled_skills().collect::<Vec<_>>(), ["expense-report-validation"]);
+        assert!(config.delegation.is_enabled());
+        assert!(!config.webhooks.is_enabled());
+        assert!(config.email.smtp.is_none());
+        assert!(config.exports.jobs.is_empty());
+    }
+}
@@ -0,0 +1,588 @@
+//! Read-only GraphQL API over tasks
+//!
+//! Served by async-graphql, so fragments, directives, variables and
+//! introspection work as GraphQL tooling expects. The schema has queries only;
+//! mutations and subscriptions are not offered.
+
+use async_graphql::{Context, EmptyMutation, EmptySubscription, InputObject, Json, Object, Result, Schema, SimpleObject, ID};
+use async_graphql_axum::GraphQL;
+use axum::Router;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use crate::agent::{ProcessingResult, PurchaseOrderAgent, PurchaseOrderSummary};
+use crate::events::TaskEvent;
+use crate::labels::{self, Labels};
+use crate::normalize::Adjustment;
+use crate::risk::RiskAssessment;
+use crate::screening::{DeniedParty, ScreeningMatch};
+use crate::server::AppState;
+use crate::skills::ValidationReport;
+use crate::store::TaskRecord;
+use crate::tax_id::TaxIdFinding;
+
+/// Page size when a query does not give `first`
+pub const DEFAULT_PAGE_SIZE: usize = 20;
+
+/// Largest page a query may ask for
+pub const MAX_PAGE_SIZE: usize = 100;
+
+/// Deepest selection a query may make; introspection queries of GraphQL tools fit
+pub const MAX_DEPTH: usize = 16;
+
+/// Largest complexity a query may have, every field costing 1 and each store scan [`SCAN_COMPLEXITY`]
+pub const MAX_COMPLEXITY: usize = 1000;
+
+/// Cost of `tasks` and `stats`, each of which reads every task, so a query makes only a few
+const SCAN_COMPLEXITY: usize = 200;
+
+/// The GraphQL schema over an agent's tasks
+pub type TaskSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+/// The schema, answering from `agent`'s task store
+pub fn schema(agent: Arc<PurchaseOrderAgent>) -> TaskSchema {
+    Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+        .data(agent)
+        .limit_depth(MAX_DEPTH)
+        .limit_complexity(MAX_COMPLEXITY)
+        .finish()
+}
+
+/// GraphQL routes; the caller is responsible for layering auth and the read permission
+pub fn graphql_routes(agent: Arc<PurchaseOrderAgent>) -> Router<Arc<AppState>> {
+    Router::new().route_service("/graphql", GraphQL::new(schema(agent)))
+}
+
+fn agent<'a>(ctx: &Context<'a>) -> Result<&'a Arc<PurchaseOrderAgent>> {
+    ctx.data::<Arc<PurchaseOrderAgent>>()
+}
+
+/// Root of the schema
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// One task, or null when no task has the id
+    async fn task(&self, ctx: &Context<'_>, id: ID) -> Result<Option<Task>> {
+        Ok(agent(ctx)?.task_store().get(&id)?.map(Task))
+    }
+
+    /// Tasks matching the filter, newest first
+    #[graphql(complexity = "SCAN_COMPLEXITY + child_complexity")]
+    async fn tasks(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default)] filter: Filter,
+        #[graphql(desc = "Page size, at most 100")] first: Option<i32>,
+        #[graphql(desc = "`endCursor` of the previous page")] after: Option<String>,
+    ) -> Result<TaskConnection> {
+        let first = match first {
+            None => DEFAULT_PAGE_SIZE,
+            Some(first) if (0..=MAX_PAGE_SIZE as i32).contains(&first) => first as usize,
+            Some(_) => return Err(format!("first must be between 0 and {}", MAX_PAGE_SIZE).into()),
+        };
+        let offset = match after {
+            None => 0,
+            Some(cursor) => cursor.parse::<usize>().map(|index| index + 1).map_err(|_| format!("Invalid cursor '{}'", cursor))?,
+        };
+        let mut matching: Vec<TaskRecord> = agent(ctx)?.task_store().list()?.into_iter().filter(|record| filter.matches(record)).collect();
+        matching.sort_by(|a, b| b.created_at.cmp(&a.created_at).then_with(|| a.task.id.cmp(&b.task.id)));
+        let total_count = matching.len();
+        let nodes: Vec<Task> = matching.into_iter().skip(offset).take(first).map(Task).collect();
+        let end = offset + nodes.len();
+        Ok(TaskConnection {
+            total_count,
+            page_info: PageInfo {
+                has_next_page: end < total_count,
+                end_cursor: (!nodes.is_empty()).then(|| (end - 1).to_string()),
+            },
+            nodes,
+        })
+    }
+
+    /// Counts and order values of the tasks matching the filter, overall and grouped
+    #[graphql(complexity = "SCAN_COMPLEXITY + child_complexity")]
+    async fn stats(&self, ctx: &Context<'_>, #[graphql(default)] filter: Filter) -> Result<Stats> {
+        let records = agent(ctx)?.task_store().list()?;
+        Ok(Stats::of(records.iter().filter(|record| filter.matches(record))))
+    }
+}
+
+/// Task filter shared by `tasks` and `stats`; every field given must match
+#[derive(InputObject, Default)]
+#[graphql(name = "TaskFilter")]
+struct Filter {
+    state: Option<String>,
+    skill: Option<String>,
+    priority: Option<String>,
+    status: Option<String>,
+    po_number: Option<String>,
+    /// Case-insensitive substring of the supplier name
+    supplier: Option<String>,
+    created_after: Option<DateTime<Utc>>,
+    created_before: Option<DateTime<Utc>>,
+    /// Label pairs that must all match, e.g. `{source: "sap"}`
+    labels: Option<Json<Labels>>,
+}
+
+impl Filter {
+    fn matches(&self, record: &TaskRecord) -> bool {
+        let result = ProcessingResult::from_status(&record.task.status);
+        self.state.as_deref().is_none_or(|state| record.state_name() == state)
+            && self.skill.as_deref().is_none_or(|skill| record.skill_id.as_deref() == Some(skill))
+            && self.priority.as_deref().is_none_or(|priority| record.priority.as_str() == priority)
+            && self.status.as_deref().is_none_or(|status| result.as_ref().is_some_and(|r| r.status == status))
+            && self.po_number.as_deref().is_none_or(|po| result.as_ref().is_some_and(|r| r.po_number == po))
+            && self.supplier.as_deref().is_none_or(|supplier| {
+                result.as_ref().is_some_and(|r| r.supplier_name.to_lowercase().contains(&supplier.to_lowercase()))
+            })
+            && self.created_after.is_none_or(|after| record.created_at >= after)
+            && self.created_before.is_none_or(|before| record.created_at < before)
+            && self.labels.as_ref().is_none_or(|Json(labels)| labels::matches(&record.labels, labels))
+    }
+}
+
+/// The serde name of an enum value, e.g. `not_found`
+fn serde_name(value: &impl Serialize) -> String {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::String(name)) => name,
+        _ => String::new(),
+    }
+}
+
+/// A page of tasks
+#[derive(SimpleObject)]
+pub struct TaskConnection {
+    total_count: usize,
+    nodes: Vec<Task>,
+    page_info: PageInfo,
+}
+
+#[derive(SimpleObject)]
+pub struct PageInfo {
+    has_next_page: bool,
+    end_cursor: Option<String>,
+}
+
+/// A task and what became of it
+pub struct Task(TaskRecord);
+
+#[Object]
+impl Task {
+    async fn id(&self) -> ID {
+        ID(self.0.task.id.clone())
+    }
+
+    async fn state(&self) -> &str {
+        self.0.state_name()
+    }
+
+    async fn skill_id(&self) -> Option<&str> {
+        self.0.skill_id.as_deref()
+    }
+
+    async fn priority(&self) -> &str {
+        self.0.priority.as_str()
+    }
+
+    /// The failed task this one retried
+    async fn retried_from(&self) -> Option<&str> {
+        self.0.retried_from.as_deref()
+    }
+
+    async fn created_at(&self) -> DateTime<Utc> {
+        self.0.created_at
+    }
+
+    async fn updated_at(&self) -> DateTime<Utc> {
+        self.0.updated_at
+    }
+
+    /// The processing result of a purchase order task
+    async fn result(&self) -> Option<ResultNode> {
+        ProcessingResult::from_status(&self.0.task.status).map(ResultNode::from)
+    }
+
+    /// The report of a validation-only task
+    async fn validation(&self) -> Option<ValidationNode> {
+        if ProcessingResult::from_status(&self.0.task.status).is_some() {
+            return None;
+        }
+        self.0.task.status.message.as_ref()?.parts.iter().find_map(|part| match part {
+            a2a::Part::Data { data } => ValidationReport::deserialize(data).ok().map(ValidationNode::from),
+            _ => None,
+        })
+    }
+
+    async fn artifacts(&self) -> Vec<ArtifactNode> {
+        let artifacts = self.0.task.artifacts.iter().flatten();
+        artifacts.map(|artifact| ArtifactNode { name: artifact.name.clone(), description: artifact.description.clone() }).collect()
+    }
+
+    async fn labels(&self) -> Vec<Label> {
+        self.0.labels.iter().map(|(key, value)| Label { key: key.clone(), value: value.clone() }).collect()
+    }
+
+    async fn events(&self, ctx: &Context<'_>) -> Result<Vec<EventNode>> {
+        Ok(agent(ctx)?.event_log().events(&self.0.task.id)?.iter().map(EventNode::from).collect())
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "ProcessingResult")]
+pub struct ResultNode {
+    status: String,
+    po_number: String,
+    supplier_name: String,
+    buyer_department: String,
+    notes: Option<String>,
+    sub_total: f64,
+    tax: f64,
+    grand_total: f64,
+    processed_at: DateTime<Utc>,
+    validation_errors: Vec<String>,
+    warnings: Vec<String>,
+    summary: Summary,
+    risk: Option<Risk>,
+    tax_id_findings: Vec<TaxIdFindingNode>,
+    screening_matches: Vec<ScreeningMatchNode>,
+    adjustments: Vec<AdjustmentNode>,
+}
+
+impl From<ProcessingResult> for ResultNode {
+    fn from(result: ProcessingResult) -> Self {
+        Self {
+            status: result.status,
+            po_number: result.po_number,
+            supplier_name: result.supplier_name,
+            buyer_department: result.buyer_department,
+            notes: result.notes,
+            sub_total: result.sub_total,
+            tax: result.tax,
+            grand_total: result.grand_total,
+            processed_at: result.processed_at,
+            validation_errors: result.validation_errors,
+            warnings: result.warnings,
+            summary: result.summary.into(),
+            risk: result.risk.map(Risk::from),
+            tax_id_findings: result.tax_id_findings.iter().map(TaxIdFindingNode::from).collect(),
+            screening_matches: result.screening_matches.into_iter().map(ScreeningMatchNode::from).collect(),
+            adjustments: result.adjustments.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct Summary {
+    total_items: usize,
+    total_quantity: u32,
+    sub_total: f64,
+    tax: f64,
+    grand_total: f64,
+    supplier: String,
+    department: String,
+    is_approved: bool,
+}
+
+impl From<PurchaseOrderSummary> for Summary {
+    fn from(summary: PurchaseOrderSummary) -> Self {
+        Self {
+            total_items: summary.total_items,
+            total_quantity: summary.total_quantity,
+            sub_total: summary.sub_total,
+            tax: summary.tax,
+            grand_total: summary.grand_total,
+            supplier: summary.supplier,
+            department: summary.department,
+            is_approved: summary.is_approved,
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "ValidationReport")]
+pub struct ValidationNode {
+    po_number: String,
+    valid: bool,
+    validation_errors: Vec<String>,
+    warnings: Vec<String>,
+    tax_id_findings: Vec<TaxIdFindingNode>,
+}
+
+impl From<ValidationReport> for ValidationNode {
+    fn from(report: ValidationReport) -> Self {
+        Self {
+            tax_id_findings: report.tax_id_findings.iter().map(TaxIdFindingNode::from).collect(),
+            po_number: report.po_number,
+            valid: report.valid,
+            validation_errors: report.validation_errors,
+            warnings: report.warnings,
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "RiskAssessment")]
+pub struct Risk {
+    score: f64,
+    /// `low`, `medium` or `high`
+    level: String,
+    rationale: Vec<String>,
+    scorer: String,
+}
+
+impl From<RiskAssessment> for Risk {
+    fn from(risk: RiskAssessment) -> Self {
+        Self { score: risk.score, level: serde_name(&risk.level), rationale: risk.rationale, scorer: risk.scorer }
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "TaxIdFinding")]
+pub struct TaxIdFindingNode {
+    issue: String,
+    country: Option<String>,
+    message: String,
+}
+
+impl From<&TaxIdFinding> for TaxIdFindingNode {
+    fn from(finding: &TaxIdFinding) -> Self {
+        Self { issue: serde_name(&finding.issue), country: finding.country.clone(), message: finding.message.clone() }
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "ScreeningMatch")]
+pub struct ScreeningMatchNode {
+    party: DeniedPartyNode,
+    matched_on: String,
+}
+
+impl From<ScreeningMatch> for ScreeningMatchNode {
+    fn from(matched: ScreeningMatch) -> Self {
+        Self { matched_on: serde_name(&matched.matched_on), party: matched.party.into() }
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "DeniedParty")]
+pub struct DeniedPartyNode {
+    name: String,
+    address: Option<String>,
+    country: Option<String>,
+    source: Option<String>,
+}
+
+impl From<DeniedParty> for DeniedPartyNode {
+    fn from(party: DeniedParty) -> Self {
+        Self { name: party.name, address: party.address, country: party.country, source: party.source }
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "Adjustment")]
+pub struct AdjustmentNode {
+    field: String,
+    from: f64,
+    to: f64,
+}
+
+impl From<Adjustment> for AdjustmentNode {
+    fn from(adjustment: Adjustment) -> Self {
+        Self { field: adjustment.field, from: adjustment.from, to: adjustment.to }
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "Artifact")]
+pub struct ArtifactNode {
+    name: Option<String>,
+    description: Option<String>,
+}
+
+#[derive(SimpleObject)]
+pub struct Label {
+    key: String,
+    value: String,
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "TaskEvent")]
+pub struct EventNode {
+    sequence: u64,
+    task_id: String,
+    at: DateTime<Utc>,
+    /// The kind of event, e.g. `created` or `state_changed`
+    #[graphql(name = "type")]
+    kind: String,
+}
+
+impl From<&TaskEvent> for EventNode {
+    fn from(event: &TaskEvent) -> Self {
+        // The kind is an internally tagged enum, so its name is the `type` of the serialized event
+        let kind = serde_json::to_value(&event.kind).ok().and_then(|kind| kind["type"].as_str().map(str::to_string));
+        Self { sequence: event.sequence, task_id: event.task_id.clone(), at: event.at, kind: kind.unwrap_or_default() }
+    }
+}
+
+/// Counts and order values of a set of tasks
+#[derive(SimpleObject)]
+pub struct Stats {
+    count: usize,
+    total_value: f64,
+    average_value: Option<f64>,
+    by_state: Vec<Bucket>,
+    by_skill: Vec<Bucket>,
+    by_status: Vec<Bucket>,
+    by_supplier: Vec<Bucket>,
+    by_department: Vec<Bucket>,
+}
+
+/// The tasks sharing one value of a grouping
+#[derive(SimpleObject, Default)]
+pub struct Bucket {
+    key: String,
+    count: usize,
+    total_value: f64,
+}
+
+impl Stats {
+    fn of<'a>(records: impl Iterator<Item = &'a TaskRecord>) -> Self {
+        let mut groups: [BTreeMap<String, Bucket>; 5] = Default::default();
+        let (mut count, mut total_value, mut valued) = (0, 0.0, 0usize);
+        for record in records {
+            count += 1;
+            let result = ProcessingResult::from_status(&record.task.status);
+            let value = result.as_ref().map(|r| r.grand_total).unwrap_or(0.0);
+            if result.is_some() {
+                total_value += value;
+                valued += 1;
+            }
+            let keys = [
+                Some(record.state_name().to_string()),
+                record.skill_id.clone(),
+                result.as_ref().map(|r| r.status.clone()),
+                result.as_ref().map(|r| r.supplier_name.clone()),
+                result.as_ref().map(|r| r.buyer_department.clone()),
+            ];
+            for (group, key) in groups.iter_mut().zip(keys) {
+                if let Some(key) = key {
+                    let bucket = group.entry(key.clone()).or_insert_with(|| Bucket { key, ..Bucket::default() });
+                    bucket.count += 1;
+                    bucket.total_value += value;
+                }
+            }
+        }
+        let [by_state, by_skill, by_status, by_supplier, by_department] = groups.map(|group| {
+            group.into_values().map(|bucket| Bucket { total_value: round2(bucket.total_value), ..bucket }).collect()
+        });
+        Self {
+            count,
+            total_value: round2(total_value),
+            average_value: (valued > 0).then(|| round2(total_value / valued as f64)),
+            by_state,
+            by_skill,
+            by_status,
+            by_supplier,
+            by_department,
+        }
+    }
+}
+
+fn round2(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::TaskOptions;
+    use crate::processing::Priority;
+    use a2a::{A2AProtocol, Message};
+
+    fn order(po_number: &str, supplier: &str, total: f64, approved: bool) -> Message {
+        let mut po = crate::testing::valid_order();
+        po.po_number = po_number.to_string();
+        po.supplier_name = supplier.to_string();
+        po.is_approved = approved;
+        po.items.truncate(1);
+        po.items[0].quantity = 1;
+        po.items[0].unit_price = total;
+        po.items[0].line_total = total;
+        (po.tax_rate, po.sub_total, po.tax, po.grand_total) = (0.0, total, 0.0, total);
+        crate::testing::message(&po)
+    }
+
+    async fn run(agent: Arc<PurchaseOrderAgent>, query: &str, variables: serde_json::Value) -> async_graphql::Response {
+        let request = async_graphql::Request::new(query).variables(async_graphql::Variables::from_json(variables));
+        schema(agent).execute(request).await
+    }
+
+    async fn data(agent: &Arc<PurchaseOrderAgent>, query: &str, variables: serde_json::Value) -> serde_json::Value {
+        let response = run(agent.clone(), query, variables).await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        response.data.into_json().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_queries_select_filter_and_page_tasks() {
+        let agent = Arc::new(PurchaseOrderAgent::new());
+        let first = agent.send_task(order("PO-1", "Acme Office Supply", 10.0, true)).await.unwrap();
+        agent.send_task(order("PO-2", "Globex", 20.0, false)).await.unwrap();
+        let urgent = TaskOptions { priority: Priority::Urgent, ..TaskOptions::default() };
+        agent.send_task_with_options(order("PO-3", "Acme Office Supply", 30.0, true), &urgent).await.unwrap();
+
+        let query = r#"query One($id: ID!) { task(id: $id) { id state result { poNumber grandTotal risk { score } } } }"#;
+        assert_eq!(
+            data(&agent, query, serde_json::json!({ "id": first.id })).await,
+            serde_json::json!({ "task": { "id": first.id, "state": "completed", "result": { "poNumber": "PO-1", "grandTotal": 10.0, "risk": null } } })
+        );
+
+        let query = r#"{ acme: tasks(filter: {supplier: "acme"}, first: 1) { totalCount nodes { result { poNumber } } pageInfo { hasNextPage endCursor } } }"#;
+        let page = data(&agent, query, serde_json::Value::Null).await;
+        assert_eq!(page["acme"]["totalCount"], 2);
+        assert_eq!(page["acme"]["pageInfo"], serde_json::json!({ "hasNextPage": true, "endCursor": "0" }));
+        let query = r#"{ tasks(filter: {supplier: "acme"}, after: "0") { nodes { ...Typed } pageInfo { hasNextPage } } } fragment Typed on Task { __typename }"#;
+        let page = data(&agent, query, serde_json::Value::Null).await;
+        assert_eq!(page["tasks"]["nodes"], serde_json::json!([{ "__typename": "Task" }]));
+        assert_eq!(page["tasks"]["pageInfo"]["hasNextPage"], false);
+
+        let query = r#"{ tasks(filter: {priority: "urgent"}) { nodes { priority result { poNumber } } } }"#;
+        let urgent = data(&agent, query, serde_json::Value::Null).await;
+        assert_eq!(urgent["tasks"]["nodes"], serde_json::json!([{ "priority": "urgent", "result": { "poNumber": "PO-3" } }]));
+
+        let stats = data(&agent, "{ stats { count totalValue averageValue byStatus { key count totalValue } } }", serde_json::Value::Null).await;
+        assert_eq!(stats["stats"]["count"], 3);
+        assert_eq!(stats["stats"]["totalValue"], 60.0);
+        assert_eq!(
+            stats["stats"]["byStatus"],
+            serde_json::json!([{ "key": "APPROVED", "count": 2, "totalValue": 40.0 }, { "key": "PENDING_APPROVAL", "count": 1, "totalValue": 20.0 }])
+        );
+
+        let types = data(&agent, "{ __schema { queryType { fields { name } } } }", serde_json::Value::Null).await;
+        assert_eq!(types["__schema"]["queryType"]["fields"], serde_json::json!([{ "name": "task" }, { "name": "tasks" }, { "name": "stats" }]));
+    }
+
+    #[tokio::test]
+    async fn test_invalid_queries_are_rejected() {
+        let agent = Arc::new(PurchaseOrderAgent::new());
+        let message = async |query: &str| run(agent.clone(), query, serde_json::Value::Null).await.errors[0].message.clone();
+
+        assert_eq!(message("{ tasks { nodes { nope } } }").await, r#"Unknown field "nope" on type "Task"."#);
+        assert!(message("mutation { x }").await.contains("mutation"));
+        let missing = message("query Q($id: ID!) { task(id: $id) { id } }").await;
+        assert!(missing.contains("id"), "{}", missing);
+        assert_eq!(message("{ tasks(first: 500) { totalCount } }").await, "first must be between 0 and 100");
+        assert!(!message("{ tasks {").await.is_empty());
+
+        // Every `tasks` and `stats` reads the whole store, so a query may only ask for a few
+        let aliased: String = (0..10).map(|n| format!("t{}: stats {{ count }} ", n)).collect();
+        assert!(message(&format!("{{ {} }}", aliased)).await.contains("complex"));
+        let nested = format!("{{ __schema {{ types {} name {} }} }}", "{ fields { type { ofType ".repeat(5), "} } } ".repeat(5));
+        assert!(message(&nested).await.contains("nested"));
+        let introspection = "{ __schema { types { name fields { name args { name type { name ofType { name ofType { name } } } } } } } }";
+        assert!(run(agent.clone(), introspection, serde_json::Value::Null).await.errors.is_empty());
+    }
+}
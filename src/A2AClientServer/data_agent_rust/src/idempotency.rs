@@ -0,0 +1,170 @@
+//! `Idempotency-Key` handling for task submissions
+//!
+//! A client retrying a submission that timed out sends the same key again. The
+//! first request with a key creates the task; repeats within the window get that
+//! task back instead of creating another one.
+
+use dashmap::mapref::entry::Entry;
+use dashmap::DashMap;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Header carrying the client's idempotency key
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+
+/// Expired keys are swept at most this often
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// What a submission with a key should do
+#[derive(Debug)]
+pub enum Claim<'a> {
+    /// First use of the key: process the submission, then `complete` the guard
+    New(ClaimGuard<'a>),
+    /// A request with this key is still being processed
+    InProgress,
+    /// The key already created this task
+    Done(String),
+    /// The key was used for a different request body
+    Mismatch,
+}
+
+/// A key claimed by the caller, released again unless completed
+///
+/// Dropping the guard frees the key, so a submission abandoned halfway, e.g.
+/// because the client disconnected, does not leave it in progress.
+pub struct ClaimGuard<'a> {
+    keys: &'a IdempotencyKeys,
+    scope: (String, String),
+    completed: bool,
+}
+
+impl ClaimGuard<'_> {
+    /// Record the task the claimed key created
+    pub fn complete(mut self, task_id: &str) {
+        if let Some(mut entry) = self.keys.keys.get_mut(&self.scope) {
+            entry.task_id = Some(task_id.to_string());
+            entry.at = Instant::now();
+        }
+        self.completed = true;
+    }
+}
+
+impl Drop for ClaimGuard<'_> {
+    fn drop(&mut self) {
+        if !self.completed {
+            self.keys.keys.remove_if(&self.scope, |_, entry| entry.task_id.is_none());
+        }
+    }
+}
+
+impl std::fmt::Debug for ClaimGuard<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ClaimGuard").field("subject", &self.scope.0).field("key", &self.scope.1).finish()
+    }
+}
+
+struct KeyUse {
+    /// Hash of the request body the key was first used with
+    fingerprint: String,
+    task_id: Option<String>,
+    at: Instant,
+}
+
+/// Keys seen within the window and the task each created
+///
+/// Keys are scoped to the caller's subject, so one caller cannot collect another's
+/// task by reusing its key.
+pub struct IdempotencyKeys {
+    window: Duration,
+    keys: DashMap<(String, String), KeyUse>,
+    last_pruned: Mutex<Instant>,
+}
+
+/// Hash identifying a request body, whatever the order of its object keys
+pub fn fingerprint(body: &Value) -> String {
+    hex::encode(Sha256::digest(body.to_string().as_bytes()))
+}
+
+impl IdempotencyKeys {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            keys: DashMap::new(),
+            last_pruned: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Claim `subject`'s `key` for a new submission, unless it was used within the window
+    pub fn claim(&self, subject: &str, key: &str, fingerprint: &str) -> Claim<'_> {
+        let now = Instant::now();
+        self.prune(now);
+        let scope = (subject.to_string(), key.to_string());
+        let fresh = || KeyUse { fingerprint: fingerprint.to_string(), task_id: None, at: now };
+        match self.keys.entry(scope.clone()) {
+            Entry::Occupied(entry) if now.duration_since(entry.get().at) < self.window => {
+                let used = entry.get();
+                match &used.task_id {
+                    _ if used.fingerprint != fingerprint => Claim::Mismatch,
+                    Some(task_id) => Claim::Done(task_id.clone()),
+                    None => Claim::InProgress,
+                }
+            }
+            Entry::Occupied(mut entry) => {
+                entry.insert(fresh());
+                Claim::New(ClaimGuard { keys: self, scope, completed: false })
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(fresh());
+                Claim::New(ClaimGuard { keys: self, scope, completed: false })
+            }
+        }
+    }
+
+    fn prune(&self, now: Instant) {
+        let mut last_pruned = self.last_pruned.lock().unwrap();
+        if now.duration_since(*last_pruned) < PRUNE_INTERVAL {
+            return;
+        }
+        *last_pruned = now;
+        self.keys.retain(|_, used| now.duration_since(used.at) < self.window);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_returns_its_task_until_the_window_ends() {
+        let keys = IdempotencyKeys::new(Duration::from_secs(3600));
+        let Claim::New(guard) = keys.claim("alice", "k1", "body") else { panic!("k1 is unused") };
+        assert!(matches!(keys.claim("alice", "k1", "body"), Claim::InProgress));
+        guard.complete("task-1");
+        assert!(matches!(keys.claim("alice", "k1", "body"), Claim::Done(task_id) if task_id == "task-1"));
+
+        // A dropped claim frees its key for the retry
+        assert!(matches!(keys.claim("alice", "k2", "body"), Claim::New(_)));
+        assert!(matches!(keys.claim("alice", "k2", "body"), Claim::New(_)));
+
+        let expired = IdempotencyKeys::new(Duration::ZERO);
+        if let Claim::New(guard) = expired.claim("alice", "k1", "body") {
+            guard.complete("task-1");
+        }
+        assert!(matches!(expired.claim("alice", "k1", "body"), Claim::New(_)));
+    }
+
+    #[test]
+    fn test_keys_are_scoped_to_the_subject_and_the_body() {
+        let keys = IdempotencyKeys::new(Duration::from_secs(3600));
+        if let Claim::New(guard) = keys.claim("alice", "k1", "body") {
+            guard.complete("task-1");
+        }
+        assert!(matches!(keys.claim("alice", "k1", "other body"), Claim::Mismatch));
+        assert!(matches!(keys.claim("bob", "k1", "body"), Claim::New(_)));
+
+        let reordered: Value = serde_json::from_str(r#"{"b": 1, "a": 2}"#).unwrap();
+        assert_eq!(fingerprint(&reordered), fingerprint(&serde_json::json!({ "a": 2, "b": 1 })));
+    }
+}
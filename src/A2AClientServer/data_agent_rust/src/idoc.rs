@@ -0,0 +1,408 @@
+//! SAP IDoc export of approved purchase orders
+//!
+//! Orders are written as ORDERS05 IDocs in the fixed-width flat-file format of a
+//! SAP file port: one `EDI_DC40` control record followed by `EDI_DD40` data
+//! records, one per line.
+
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+
+use crate::address::country_code;
+use crate::agent::PurchaseOrder;
+
+/// Length of a control record
+pub const CONTROL_RECORD_LENGTH: usize = 524;
+
+/// Length of the segment data in a data record
+pub const SEGMENT_DATA_LENGTH: usize = 1000;
+
+/// IDoc export settings
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct IdocConfig {
+    /// Directory an IDoc file is written to whenever an order is approved
+    pub output_dir: Option<PathBuf>,
+    /// SAP client (`MANDT`)
+    pub client: String,
+    pub sender_port: String,
+    pub sender_partner_type: String,
+    pub sender_partner: String,
+    pub receiver_port: String,
+    pub receiver_partner_type: String,
+    pub receiver_partner: String,
+    /// Currency of the order amounts
+    pub currency: String,
+    /// Unit of measure of the ordered quantities
+    pub unit: String,
+    /// Segment layout, replacing the built-in ORDERS05 mapping when not empty
+    pub segments: Vec<SegmentMapping>,
+}
+
+impl Default for IdocConfig {
+    fn default() -> Self {
+        Self {
+            output_dir: None,
+            client: "800".to_string(),
+            sender_port: "A2APOAGENT".to_string(),
+            sender_partner_type: "LS".to_string(),
+            sender_partner: "POAGENT".to_string(),
+            receiver_port: "SAPERP".to_string(),
+            receiver_partner_type: "LS".to_string(),
+            receiver_partner: "ERPCLNT800".to_string(),
+            currency: "USD".to_string(),
+            unit: "EA".to_string(),
+            segments: Vec::new(),
+        }
+    }
+}
+
+/// A segment and how its fields are filled
+#[derive(Debug, Clone, Deserialize)]
+pub struct SegmentMapping {
+    /// Segment type, e.g. `E1EDK01`
+    pub name: String,
+    /// Hierarchy level; segments below level 2 belong to the nearest segment above them
+    #[serde(default = "default_level")]
+    pub level: u8,
+    /// Repeat the segment for every order line
+    #[serde(default)]
+    pub per_item: bool,
+    /// Fields in the order they appear in the segment data
+    pub fields: Vec<FieldMapping>,
+}
+
+fn default_level() -> u8 {
+    2
+}
+
+/// A fixed-width field of a segment
+#[derive(Debug, Clone, Deserialize)]
+pub struct FieldMapping {
+    /// SAP field name, for readability of the mapping only
+    pub name: String,
+    /// Width in characters; longer values are truncated
+    pub length: usize,
+    /// Text with `{placeholder}`s, e.g. `"{poNumber}"`; empty to leave the field blank
+    #[serde(default)]
+    pub value: String,
+}
+
+impl FieldMapping {
+    fn new(name: &str, length: usize, value: &str) -> Self {
+        Self { name: name.to_string(), length, value: value.to_string() }
+    }
+}
+
+/// The built-in ORDERS05 layout: header, order type, date, supplier, lines and totals
+pub fn default_segments() -> Vec<SegmentMapping> {
+    let segment = |name: &str, level: u8, per_item: bool, fields: &[(&str, usize, &str)]| SegmentMapping {
+        name: name.to_string(),
+        level,
+        per_item,
+        fields: fields.iter().map(|(name, length, value)| FieldMapping::new(name, *length, value)).collect(),
+    };
+    vec![
+        segment(
+            "E1EDK01",
+            2,
+            false,
+            &[
+                ("ACTION", 3, ""),
+                ("KZABS", 1, ""),
+                ("CURCY", 3, "{currency}"),
+                ("HWAERS", 3, ""),
+                ("WKURS", 12, ""),
+                ("ZTERM", 17, ""),
+                ("KUNDEUINR", 20, ""),
+                ("EIGENUINR", 20, "{supplierTaxId}"),
+                ("BSART", 4, "NB"),
+                ("BELNR", 35, "{poNumber}"),
+            ],
+        ),
+        segment("E1EDK14", 2, false, &[("QUALF", 3, "013"), ("ORGID", 35, "NB")]),
+        segment("E1EDK14", 2, false, &[("QUALF", 3, "009"), ("ORGID", 35, "{buyerDepartment}")]),
+        segment("E1EDK03", 2, false, &[("IDDAT", 3, "012"), ("DATUM", 8, "{date}"), ("UZEIT", 6, "{time}")]),
+        segment(
+            "E1EDKA1",
+            2,
+            false,
+            &[
+                ("PARVW", 3, "LF"),
+                ("PARTN", 17, ""),
+                ("LIFNR", 17, ""),
+                ("NAME1", 35, "{supplierName}"),
+                ("NAME2", 35, ""),
+                ("NAME3", 35, ""),
+                ("NAME4", 35, ""),
+                ("STRAS", 35, "{supplierAddressLine1}"),
+                ("STRS2", 35, "{supplierAddressLine2}"),
+                ("PFACH", 35, ""),
+                ("ORT01", 35, "{supplierCity}"),
+                ("COUNC", 9, ""),
+                ("PSTLZ", 9, "{supplierPostalCode}"),
+                ("PSTL2", 9, ""),
+                ("LAND1", 3, "{supplierCountryCode}"),
+            ],
+        ),
+        segment(
+            "E1EDP01",
+            2,
+            true,
+            &[
+                ("POSEX", 6, "{position}"),
+                ("ACTION", 3, ""),
+                ("PSTYP", 1, ""),
+                ("KZABS", 1, ""),
+                ("MENGE", 15, "{quantity}"),
+                ("MENEE", 3, "{unit}"),
+                ("BMNG2", 15, ""),
+                ("PMENE", 3, ""),
+                ("ABFTZ", 7, ""),
+                ("VPREI", 15, "{unitPrice}"),
+                ("PEINH", 9, "1"),
+                ("NETWR", 18, "{lineTotal}"),
+            ],
+        ),
+        segment("E1EDP19", 3, true, &[("QUALF", 3, "002"), ("IDTNR", 35, "{itemCode}"), ("KTEXT", 70, "{description}")]),
+        segment("E1EDS01", 2, false, &[("SUMID", 3, "002"), ("SUMME", 18, "{grandTotal}"), ("SUNIT", 3, "{currency}")]),
+    ]
+}
+
+/// IDoc number of a task: 16 digits derived from its ID, so re-exports keep the number
+pub fn document_number(task_id: &str) -> String {
+    let digest = Sha256::digest(task_id.as_bytes());
+    let number = u64::from_be_bytes(digest[..8].try_into().expect("digest has 8 bytes")) % 10_000_000_000_000_000;
+    format!("{:016}", number)
+}
+
+/// The ORDERS05 flat file of `po`, created at `created`
+pub fn orders05(config: &IdocConfig, document_number: &str, po: &PurchaseOrder, created: DateTime<Utc>) -> String {
+    let mut order = serde_json::to_value(po).unwrap_or_default();
+    order["currency"] = po.currency.clone().unwrap_or_else(|| config.currency.clone()).into();
+    order["unit"] = config.unit.clone().into();
+    order["date"] = created.format("%Y%m%d").to_string().into();
+    order["time"] = created.format("%H%M%S").to_string().into();
+    order["supplierCountryCode"] = country_code(&po.supplier_country).unwrap_or(&po.supplier_country).into();
+
+    let mut file = control_record(config, document_number, created);
+    file.push('\n');
+
+    let default_segments;
+    let segments = if config.segments.is_empty() {
+        default_segments = self::default_segments();
+        &default_segments
+    } else {
+        &config.segments
+    };
+    let mut records = Records { config, document_number, file: &mut file, count: 0, parents: [0; 100] };
+    let mut index = 0;
+    while index < segments.len() {
+        if !segments[index].per_item {
+            records.push(&segments[index], &order);
+            index += 1;
+            continue;
+        }
+        // Consecutive per-item segments repeat as a group for every line
+        let group_end = segments[index..].iter().position(|s| !s.per_item).map_or(segments.len(), |n| index + n);
+        for (line, item) in order["items"].as_array().cloned().unwrap_or_default().into_iter().enumerate() {
+            let mut values = item;
+            values["currency"] = order["currency"].clone();
+            values["unit"] = order["unit"].clone();
+            values["position"] = format!("{:06}", (line + 1) * 10).into();
+            for segment in &segments[index..group_end] {
+                records.push(segment, &values);
+            }
+        }
+        index = group_end;
+    }
+    file
+}
+
+/// Data records written so far, and the last segment number at each level
+struct Records<'a> {
+    config: &'a IdocConfig,
+    document_number: &'a str,
+    file: &'a mut String,
+    count: usize,
+    parents: [usize; 100],
+}
+
+impl Records<'_> {
+    fn push(&mut self, segment: &SegmentMapping, values: &Value) {
+        self.count += 1;
+        let level = segment.level.clamp(1, 99) as usize;
+        self.parents[level] = self.count;
+        let parent = if level > 2 { self.parents[level - 1] } else { 0 };
+
+        let data: String = segment.fields.iter().map(|field| fixed(&fill(&field.value, values), field.length)).collect();
+        self.file.push_str(&fixed(&segment.name, 30));
+        self.file.push_str(&fixed(&self.config.client, 3));
+        self.file.push_str(self.document_number);
+        self.file.push_str(&format!("{:06}{:06}{:02}", self.count, parent, level));
+        self.file.push_str(&fixed(&data, SEGMENT_DATA_LENGTH));
+        self.file.push('\n');
+    }
+}
+
+fn control_record(config: &IdocConfig, document_number: &str, created: DateTime<Utc>) -> String {
+    let fields: [(&str, usize); 36] = [
+        ("EDI_DC40", 10),
+        (&config.client, 3),
+        (document_number, 16),
+        ("", 4),
+        ("30", 2),
+        ("1", 1),
+        ("", 1),
+        ("", 1),
+        ("", 1),
+        ("ORDERS05", 30),
+        ("", 30),
+        ("ORDERS", 30),
+        ("", 3),
+        ("", 3),
+        ("", 1),
+        ("", 6),
+        ("", 6),
+        (&config.sender_port, 10),
+        (&config.sender_partner_type, 2),
+        ("", 2),
+        (&config.sender_partner, 10),
+        ("", 21),
+        ("", 70),
+        (&config.receiver_port, 10),
+        (&config.receiver_partner_type, 2),
+        ("", 2),
+        (&config.receiver_partner, 10),
+        ("", 21),
+        ("", 70),
+        (&created.format("%Y%m%d").to_string(), 8),
+        (&created.format("%H%M%S").to_string(), 6),
+        ("", 14),
+        ("", 14),
+        ("", 14),
+        ("", 70),
+        ("", 20),
+    ];
+    fields.iter().map(|(value, length)| fixed(value, *length)).collect()
+}
+
+/// Replace `{name}` with the value of `name`; unknown and null values are blank
+fn fill(template: &str, values: &Value) -> String {
+    let mut filled = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(end) = rest[start..].find('}') else { break };
+        filled.push_str(&rest[..start]);
+        match values.get(&rest[start + 1..start + end]) {
+            Some(Value::String(s)) => filled.push_str(s),
+            Some(Value::Null) | None => {}
+            Some(other) => filled.push_str(&other.to_string()),
+        }
+        rest = &rest[start + end + 1..];
+    }
+    filled.push_str(rest);
+    filled
+}
+
+/// `value` left-aligned in `length` characters, truncated or padded with spaces
+fn fixed(value: &str, length: usize) -> String {
+    let value: String = value.chars().filter(|c| !c.is_control()).take(length).collect();
+    format!("{:<width$}", value, width = length)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_orders05_flat_file() {
+        let po = crate::testing::order_of(0.0, &[("P-1", "Paper", 1, 10.0), ("P-2", "Paper", 1, 20.0)]);
+        let created = Utc.with_ymd_and_hms(2025, 3, 4, 5, 6, 7).unwrap();
+        let docnum = document_number("task-1");
+        let file = orders05(&IdocConfig::default(), &docnum, &po, created);
+        let lines: Vec<&str> = file.lines().collect();
+
+        assert_eq!(docnum.len(), 16);
+        assert_eq!(docnum, document_number("task-1"));
+        assert_eq!(lines[0].len(), CONTROL_RECORD_LENGTH);
+        assert!(lines[0].starts_with(&format!("EDI_DC40  800{}", docnum)));
+        assert_eq!(lines[0][39..69].trim_end(), "ORDERS05");
+        assert_eq!(lines[0][378..392].to_string(), "20250304050607");
+
+        let segments: Vec<&str> = lines[1..].iter().map(|line| line[..30].trim_end()).collect();
+        assert_eq!(
+            segments,
+            ["E1EDK01", "E1EDK14", "E1EDK14", "E1EDK03", "E1EDKA1", "E1EDP01", "E1EDP19", "E1EDP01", "E1EDP19", "E1EDS01"]
+        );
+        assert!(lines[1..].iter().all(|line| line.len() == 63 + SEGMENT_DATA_LENGTH));
+        // Segment number, parent segment number and level
+        assert_eq!(&lines[7][49..63], "00000700000603");
+        assert_eq!(&lines[8][49..63], "00000800000002");
+
+        let data = |line: &str| line[63..].to_string();
+        assert_eq!(data(lines[1])[83..118].trim_end(), "PO-1");
+        assert_eq!(data(lines[5])[..3].to_string(), "LF ");
+        assert!(data(lines[5]).contains("Acme Office Supply") && data(lines[5]).contains("US "));
+        assert!(data(lines[8]).starts_with("000020"));
+        assert!(data(lines[9]).starts_with("002P-2"));
+        assert_eq!(data(lines[10])[3..21].trim_end(), "30.0");
+    }
+
+    #[tokio::test]
+    async fn test_approved_orders_are_written_to_the_output_dir() {
+        use crate::agent::PurchaseOrderAgent;
+        use a2a::{A2AProtocol, Message, Part};
+        use axum::body::Body;
+        use axum::http::{Request, StatusCode};
+        use tower::ServiceExt;
+
+        let dir = std::env::temp_dir().join(format!("po-agent-idoc-{}", uuid::Uuid::new_v4()));
+        let config = IdocConfig { output_dir: Some(dir.clone()), ..IdocConfig::default() };
+        let agent = std::sync::Arc::new(PurchaseOrderAgent::new().with_idoc_config(config));
+        let mut po = crate::testing::order_of(0.0, &[("P-1", "Paper", 1, 30.0)]);
+        (po.is_approved, po.approval_reason) = (false, None);
+        let po = serde_json::to_value(po).unwrap();
+        let task = agent.send_task(Message { role: "user".to_string(), parts: vec![Part::Data { data: po }] }).await.unwrap();
+        let path = dir.join(format!("{}.idoc", document_number(&task.id)));
+        assert!(!path.exists(), "pending orders are not exported");
+
+        let request = || Request::get(format!("/agent/task/{}/idoc", task.id)).body(Body::empty()).unwrap();
+        let response = crate::create_router(agent.clone()).oneshot(request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+
+        agent.record_approval(&task.id, "Pat", true, None).unwrap();
+        let written = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1, "only the finished IDoc is left in the directory");
+        let response = crate::create_router(agent.clone()).oneshot(request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(String::from_utf8(body.to_vec()).unwrap(), written);
+        assert!(written.lines().any(|line| line.starts_with("E1EDP19") && line.contains("P-1")));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_custom_segment_mapping() {
+        let config: IdocConfig = toml::from_str(
+            r#"
+            client = "100"
+
+            [[segments]]
+            name = "Z1HEADER"
+            fields = [{ name = "PO", length = 4, value = "{poNumber}!" }, { name = "FILLER", length = 2 }, { name = "BY", length = 10, value = "{buyerDepartment}" }]
+            "#,
+        )
+        .unwrap();
+        let file = orders05(&config, &document_number("t"), &crate::testing::valid_order(), Utc::now());
+        let lines: Vec<&str> = file.lines().collect();
+
+        assert_eq!(lines.len(), 2);
+        assert!(lines[1].starts_with("Z1HEADER"));
+        assert_eq!(&lines[1][30..33], "100");
+        assert_eq!(lines[1][63..].trim_end(), "PO-1  Marketing");
+    }
+}
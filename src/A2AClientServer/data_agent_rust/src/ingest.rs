@@ -0,0 +1,142 @@
+//! One-pass parsing of purchase orders from message payloads
+//!
+//! A payload is either a bare purchase order or an object with the order under
+//! `purchaseOrder`, next to options such as `skill_id` or `normalize`. Both are
+//! recognized while parsing, so an order is deserialized once and never cloned.
+//! Text is parsed as it is read: its lines are deserialized one by one straight
+//! into the order, without a JSON tree of the payload in between.
+
+use serde::de::value::{MapAccessDeserializer, StringDeserializer};
+use serde::de::{DeserializeSeed, Deserializer, IgnoredAny, MapAccess, Visitor};
+use serde::Deserialize;
+use serde_json::Value;
+use std::fmt;
+
+use crate::agent::PurchaseOrder;
+
+/// Key of a wrapped purchase order
+pub const WRAPPER_KEY: &str = "purchaseOrder";
+
+/// The purchase order in an already parsed payload, read in place
+pub fn from_value(payload: &Value) -> serde_json::Result<PurchaseOrder> {
+    PurchaseOrder::deserialize(payload.get(WRAPPER_KEY).unwrap_or(payload))
+}
+
+/// The purchase order in a JSON payload, parsed as it is read
+pub fn from_str(payload: &str) -> serde_json::Result<PurchaseOrder> {
+    serde_json::from_str::<Payload>(payload).map(|payload| payload.0)
+}
+
+/// A message none of whose parts holds a purchase order, with why each failed to parse
+#[derive(Debug, Clone)]
+pub struct UnparseableMessage {
+    pub errors: Vec<String>,
+}
+
+impl fmt::Display for UnparseableMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("No valid purchase order found in message")?;
+        if !self.errors.is_empty() {
+            write!(f, ": {}", self.errors.join("; "))?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for UnparseableMessage {}
+
+/// A purchase order, wrapped or bare
+struct Payload(PurchaseOrder);
+
+impl<'de> Deserialize<'de> for Payload {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_map(PayloadVisitor)
+    }
+}
+
+struct PayloadVisitor;
+
+impl<'de> Visitor<'de> for PayloadVisitor {
+    type Value = Payload;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a purchase order, or an object with one under \"purchaseOrder\"")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Payload, A::Error> {
+        // The fields are read as a bare order's; a wrapped order found among them
+        // wins, and then the bare order missing its fields is no error
+        let mut wrapped = None;
+        let bare = PurchaseOrder::deserialize(MapAccessDeserializer::new(Unwrap { map, wrapped: &mut wrapped }));
+        match wrapped {
+            Some(po) => Ok(Payload(po)),
+            None => bare.map(Payload),
+        }
+    }
+}
+
+/// The fields of a payload, except that a wrapped order is taken out into `wrapped`
+///
+/// Once it is, the fields after it are skipped and the map ends.
+struct Unwrap<'a, A> {
+    map: A,
+    wrapped: &'a mut Option<PurchaseOrder>,
+}
+
+impl<'de, A: MapAccess<'de>> MapAccess<'de> for Unwrap<'_, A> {
+    type Error = A::Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, A::Error> {
+        if self.wrapped.is_none() {
+            let Some(key) = self.map.next_key::<String>()? else {
+                return Ok(None);
+            };
+            if key != WRAPPER_KEY {
+                return seed.deserialize(StringDeserializer::new(key)).map(Some);
+            }
+            *self.wrapped = Some(self.map.next_value()?);
+        }
+        while self.map.next_entry::<IgnoredAny, IgnoredAny>()?.is_some() {}
+        Ok(None)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, A::Error> {
+        self.map.next_value_seed(seed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order(items: usize) -> Value {
+        serde_json::to_value(crate::testing::huge_order(items)).unwrap()
+    }
+
+    #[test]
+    fn test_wrapped_and_bare_orders_parse_the_same() {
+        let bare = order(20_000);
+        let wrapped = serde_json::json!({ "skill_id": "purchase-order-validation", "purchaseOrder": bare, "normalize": true });
+
+        for po in [
+            from_value(&bare).unwrap(),
+            from_value(&wrapped).unwrap(),
+            from_str(&bare.to_string()).unwrap(),
+            from_str(&wrapped.to_string()).unwrap(),
+        ] {
+            assert_eq!(po.po_number, "PO-1");
+            assert_eq!(po.items.len(), 20_000);
+            assert_eq!(po.items[19_999].item_code, "P-20000");
+        }
+
+        // A wrapped order wins over fields of a bare one around it
+        let mut around = bare.clone();
+        around["purchaseOrder"] = order(1);
+        assert_eq!(from_str(&around.to_string()).unwrap().items.len(), 1);
+
+        let error = from_str(r#"{"purchaseOrder": {"poNumber": "PO-1"}}"#).unwrap_err();
+        assert!(error.to_string().contains("missing field"), "{}", error);
+        assert!(from_str(r#"{"poNumber": "PO-1"}"#).is_err());
+        assert!(from_str("[]").is_err());
+    }
+}
@@ -0,0 +1,77 @@
+use futures_util::StreamExt;
+use lapin::options::{BasicAckOptions, BasicConsumeOptions, BasicNackOptions, BasicQosOptions, QueueDeclareOptions};
+use lapin::types::{AMQPValue, FieldTable};
+use lapin::{Connection, ConnectionProperties};
+use std::error::Error;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+use super::{process_payload, AmqpIntakeConfig, IntakeOutcome};
+use crate::agent::PurchaseOrderAgent;
+
+/// Consume purchase orders from the configured queue until the process exits
+///
+/// Accepted purchase orders are acknowledged. Orders that fail validation, and
+/// payloads that are not purchase orders at all, are negatively acknowledged
+/// without requeueing so the broker routes them to the queue's dead-letter
+/// exchange. The consumer reconnects after `reconnect_seconds` when the
+/// connection drops.
+pub fn spawn_consumer(agent: Arc<PurchaseOrderAgent>, config: AmqpIntakeConfig) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            if let Err(e) = consume(&agent, &config).await {
+                warn!(queue = %config.queue, error = %e, "AMQP intake consumer stopped");
+                agent.dependencies().record_failure("intake:amqp", &e);
+            }
+            tokio::time::sleep(Duration::from_secs(config.reconnect_seconds)).await;
+        }
+    })
+}
+
+async fn consume(agent: &PurchaseOrderAgent, config: &AmqpIntakeConfig) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let connection = Connection::connect(&config.url, ConnectionProperties::default()).await?;
+    let channel = connection.create_channel().await?;
+    channel.basic_qos(config.prefetch, BasicQosOptions::default()).await?;
+
+    if config.declare_queue {
+        let mut arguments = FieldTable::default();
+        if let Some(exchange) = &config.dead_letter_exchange {
+            arguments.insert("x-dead-letter-exchange".into(), AMQPValue::LongString(exchange.as_str().into()));
+        }
+        channel
+            .queue_declare(config.queue.as_str().into(), QueueDeclareOptions::durable(), arguments)
+            .await?;
+    }
+
+    let mut consumer = channel
+        .basic_consume(
+            config.queue.as_str().into(),
+            config.consumer_tag.as_str().into(),
+            BasicConsumeOptions::default(),
+            FieldTable::default(),
+        )
+        .await?;
+    info!(queue = %config.queue, prefetch = config.prefetch, "AMQP intake consuming");
+    agent.dependencies().record_success("intake:amqp");
+
+    while let Some(delivery) = consumer.next().await {
+        let delivery = delivery?;
+        match process_payload(agent, "amqp", &delivery.data).await {
+            IntakeOutcome::Accepted(task) => {
+                info!(task_id = %task.id, "AMQP purchase order accepted");
+                delivery.ack(BasicAckOptions::default()).await?;
+            }
+            IntakeOutcome::Rejected(task) => {
+                warn!(task_id = %task.id, "AMQP purchase order failed validation, dead-lettering");
+                delivery.nack(BasicNackOptions { multiple: false, requeue: false }).await?;
+            }
+            IntakeOutcome::Invalid(reason) => {
+                warn!(reason = %reason, "AMQP payload rejected, dead-lettering");
+                delivery.nack(BasicNackOptions { multiple: false, requeue: false }).await?;
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,321 @@
+use a2a::Task;
+use chrono::Utc;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::error::Error;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+use super::{process_payload, IntakeOutcome, SqsIntakeConfig};
+use crate::agent::PurchaseOrderAgent;
+use crate::aws::{self, Credentials, Signer};
+use crate::server::TaskResponse;
+
+type BoxError = Box<dyn Error + Send + Sync>;
+
+/// Minimal client for the SQS JSON protocol, signed with AWS Signature Version 4
+pub struct SqsClient {
+    http: reqwest::Client,
+    endpoint: reqwest::Url,
+    region: String,
+    credentials: Credentials,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ReceiveMessageResponse {
+    #[serde(default)]
+    messages: Vec<SqsMessage>,
+}
+
+/// A message received from a queue
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct SqsMessage {
+    pub message_id: String,
+    pub receipt_handle: String,
+    pub body: String,
+}
+
+impl SqsClient {
+    pub fn new(endpoint: reqwest::Url, region: String, credentials: Credentials, timeout: Duration) -> Result<Self, BoxError> {
+        let http = reqwest::Client::builder().timeout(timeout).build()?;
+        Ok(Self { http, endpoint, region, credentials })
+    }
+
+    /// Long-poll for up to `max_messages` messages, hiding them for `visibility_timeout` seconds
+    pub async fn receive(
+        &self,
+        queue_url: &str,
+        max_messages: u8,
+        wait_time_seconds: u8,
+        visibility_timeout: u32,
+    ) -> Result<Vec<SqsMessage>, BoxError> {
+        let response = self
+            .call(
+                "ReceiveMessage",
+                json!({
+                    "QueueUrl": queue_url,
+                    "MaxNumberOfMessages": max_messages,
+                    "WaitTimeSeconds": wait_time_seconds,
+                    "VisibilityTimeout": visibility_timeout,
+                }),
+            )
+            .await?;
+        Ok(serde_json::from_value::<ReceiveMessageResponse>(response)?.messages)
+    }
+
+    pub async fn delete(&self, queue_url: &str, receipt_handle: &str) -> Result<(), BoxError> {
+        self.call("DeleteMessage", json!({ "QueueUrl": queue_url, "ReceiptHandle": receipt_handle }))
+            .await
+            .map(|_| ())
+    }
+
+    pub async fn change_visibility(&self, queue_url: &str, receipt_handle: &str, seconds: u32) -> Result<(), BoxError> {
+        self.call(
+            "ChangeMessageVisibility",
+            json!({ "QueueUrl": queue_url, "ReceiptHandle": receipt_handle, "VisibilityTimeout": seconds }),
+        )
+        .await
+        .map(|_| ())
+    }
+
+    pub async fn send(&self, queue_url: &str, body: &str) -> Result<(), BoxError> {
+        self.call("SendMessage", json!({ "QueueUrl": queue_url, "MessageBody": body }))
+            .await
+            .map(|_| ())
+    }
+
+    async fn call(&self, action: &str, body: Value) -> Result<Value, BoxError> {
+        let payload = serde_json::to_vec(&body)?;
+        let host = aws::host(&self.endpoint).ok_or("SQS endpoint has no host")?;
+        let target = format!("AmazonSQS.{}", action);
+        let amz_date = aws::amz_date(Utc::now());
+
+        let mut headers = vec![
+            ("content-type".to_string(), "application/x-amz-json-1.0".to_string()),
+            ("host".to_string(), host),
+            ("x-amz-date".to_string(), amz_date.clone()),
+            ("x-amz-target".to_string(), target),
+        ];
+        if let Some(token) = &self.credentials.session_token {
+            headers.push(("x-amz-security-token".to_string(), token.clone()));
+        }
+        let signer = Signer { credentials: &self.credentials, region: &self.region, service: "sqs" };
+        let authorization = signer.authorization("POST", &self.endpoint, &headers, &aws::payload_hash(&payload), &amz_date);
+
+        let mut request = self.http.post(self.endpoint.clone()).header("authorization", authorization);
+        for (name, value) in headers.iter().filter(|(name, _)| name != "host") {
+            request = request.header(name.as_str(), value.as_str());
+        }
+        let response = request.body(payload).send().await?;
+
+        let status = response.status();
+        let text = response.text().await?;
+        if !status.is_success() {
+            return Err(format!("SQS {} failed with {}: {}", action, status, text).into());
+        }
+        Ok(if text.is_empty() { Value::Null } else { serde_json::from_str(&text)? })
+    }
+}
+
+/// Result record written to the results queue for every consumed message
+fn result_body(message: &SqsMessage, outcome: &IntakeOutcome) -> String {
+    let (outcome_label, task, error): (&str, Option<&Task>, Option<&str>) = match outcome {
+        IntakeOutcome::Accepted(task) => ("accepted", Some(task), None),
+        IntakeOutcome::Rejected(task) => ("rejected", Some(task), None),
+        IntakeOutcome::Invalid(reason) => ("invalid", None, Some(reason)),
+    };
+    json!({
+        "message_id": message.message_id,
+        "outcome": outcome_label,
+        "task": task.map(|task| TaskResponse::from_task(task.clone())),
+        "error": error,
+        "processed_at": Utc::now(),
+    })
+    .to_string()
+}
+
+/// Poll the configured queue until the process exits
+///
+/// Messages are received in batches with long polling and processed one at a
+/// time. While a batch is in flight, a heartbeat extends the visibility of its
+/// messages every half visibility timeout, so no other consumer picks up one
+/// still waiting or being processed. Every message is deleted after processing
+/// and its outcome sent to the results queue, when one is configured; a
+/// failure to send or delete is logged and does not hold up the rest of the batch.
+pub fn spawn_poller(agent: Arc<PurchaseOrderAgent>, config: SqsIntakeConfig) -> Result<JoinHandle<()>, Box<dyn Error>> {
+    let credentials = Credentials::resolve(
+        config.access_key_id.as_deref(),
+        config.secret_access_key.as_deref(),
+        config.session_token.as_deref(),
+    )?;
+    let endpoint = match &config.endpoint {
+        Some(endpoint) => reqwest::Url::parse(endpoint)?,
+        None => {
+            let mut url = reqwest::Url::parse(&config.queue_url)?;
+            url.set_path("/");
+            url
+        }
+    };
+    let timeout = Duration::from_secs(u64::from(config.wait_time_seconds) + 10);
+    let client = Arc::new(SqsClient::new(endpoint, config.region.clone(), credentials, timeout).map_err(|e| e.to_string())?);
+
+    Ok(tokio::spawn(async move {
+        info!(queue = %config.queue_url, batch = config.max_messages, "SQS intake polling");
+        loop {
+            match poll_once(&agent, &client, &config).await {
+                Ok(()) => agent.dependencies().record_success("intake:sqs"),
+                Err(e) => {
+                    warn!(queue = %config.queue_url, error = %e, "SQS intake poll failed");
+                    agent.dependencies().record_failure("intake:sqs", &e);
+                    tokio::time::sleep(Duration::from_secs(config.retry_seconds)).await;
+                }
+            }
+        }
+    }))
+}
+
+async fn poll_once(agent: &PurchaseOrderAgent, client: &Arc<SqsClient>, config: &SqsIntakeConfig) -> Result<(), BoxError> {
+    let messages = client
+        .receive(&config.queue_url, config.max_messages, config.wait_time_seconds, config.visibility_timeout)
+        .await?;
+    debug!(count = messages.len(), "SQS messages received");
+    if messages.is_empty() {
+        return Ok(());
+    }
+
+    let in_flight = Arc::new(Mutex::new(messages.iter().map(|message| message.receipt_handle.clone()).collect()));
+    let heartbeat = spawn_heartbeat(client.clone(), config, in_flight.clone());
+    for message in &messages {
+        handle(agent, client, config, message).await;
+        in_flight.lock().unwrap().remove(&message.receipt_handle);
+    }
+    heartbeat.abort();
+    Ok(())
+}
+
+/// Process a message, send its result record and delete it
+///
+/// The message is deleted even when the result record cannot be sent, so a
+/// results queue outage does not have every order processed again.
+async fn handle(agent: &PurchaseOrderAgent, client: &SqsClient, config: &SqsIntakeConfig, message: &SqsMessage) {
+    let outcome = process_payload(agent, "sqs", message.body.as_bytes()).await;
+    match &outcome {
+        IntakeOutcome::Accepted(task) => info!(message_id = %message.message_id, task_id = %task.id, "SQS purchase order accepted"),
+        IntakeOutcome::Rejected(task) => warn!(message_id = %message.message_id, task_id = %task.id, "SQS purchase order failed validation"),
+        IntakeOutcome::Invalid(reason) => warn!(message_id = %message.message_id, reason = %reason, "SQS payload rejected"),
+    }
+
+    if let Some(results_queue) = &config.results_queue_url
+        && let Err(e) = client.send(results_queue, &result_body(message, &outcome)).await
+    {
+        warn!(message_id = %message.message_id, error = %e, "SQS result record not sent");
+        agent.dependencies().record_failure("intake:sqs", &e);
+    }
+    if let Err(e) = client.delete(&config.queue_url, &message.receipt_handle).await {
+        warn!(message_id = %message.message_id, error = %e, "SQS message not deleted; it is received again once visible");
+        agent.dependencies().record_failure("intake:sqs", &e);
+    }
+}
+
+/// Extend the visibility of the messages in `in_flight` every half visibility timeout, until aborted
+fn spawn_heartbeat(client: Arc<SqsClient>, config: &SqsIntakeConfig, in_flight: Arc<Mutex<HashSet<String>>>) -> JoinHandle<()> {
+    let queue_url = config.queue_url.clone();
+    let visibility_timeout = config.visibility_timeout;
+    let period = Duration::from_millis(u64::from(visibility_timeout) * 500).max(Duration::from_secs(1));
+    tokio::spawn(async move {
+        let mut ticks = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+        loop {
+            ticks.tick().await;
+            let receipt_handles: Vec<String> = in_flight.lock().unwrap().iter().cloned().collect();
+            for receipt_handle in receipt_handles {
+                if let Err(e) = client.change_visibility(&queue_url, &receipt_handle, visibility_timeout).await {
+                    warn!(error = %e, "SQS message visibility not extended");
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_batches_survive_failures_and_keep_messages_hidden() {
+        use axum::http::{HeaderMap, StatusCode};
+        use axum::routing::post;
+
+        // An SQS stand-in whose results queue answers slowly, then fails
+        let calls: Arc<Mutex<Vec<(String, Value)>>> = Arc::default();
+        let recorded = calls.clone();
+        let sqs = axum::Router::new().route(
+            "/",
+            post(move |headers: HeaderMap, body: String| {
+                let calls = recorded.clone();
+                async move {
+                    let action = headers["x-amz-target"].to_str().unwrap().trim_start_matches("AmazonSQS.").to_string();
+                    calls.lock().unwrap().push((action.clone(), serde_json::from_str(&body).unwrap()));
+                    match action.as_str() {
+                        "ReceiveMessage" => {
+                            let order = serde_json::to_string(&crate::testing::valid_order()).unwrap();
+                            let messages = json!({ "Messages": [
+                                { "MessageId": "m-1", "ReceiptHandle": "r-1", "Body": order },
+                                { "MessageId": "m-2", "ReceiptHandle": "r-2", "Body": "not an order" },
+                            ] });
+                            (StatusCode::OK, messages.to_string())
+                        }
+                        "SendMessage" => {
+                            tokio::time::sleep(Duration::from_millis(1500)).await;
+                            (StatusCode::INTERNAL_SERVER_ERROR, "{}".to_string())
+                        }
+                        _ => (StatusCode::OK, "{}".to_string()),
+                    }
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = reqwest::Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+        tokio::spawn(async move { axum::serve(listener, sqs).await.unwrap() });
+
+        let config: SqsIntakeConfig = toml::from_str(
+            r#"
+            queue_url = "https://sqs.us-east-1.amazonaws.com/123456789012/purchase-orders"
+            results_queue_url = "https://sqs.us-east-1.amazonaws.com/123456789012/po-results"
+            visibility_timeout = 2
+            "#,
+        )
+        .unwrap();
+        let credentials = Credentials { access_key_id: "AKID".to_string(), secret_access_key: "secret".to_string(), session_token: None };
+        let client = Arc::new(SqsClient::new(endpoint, config.region.clone(), credentials, Duration::from_secs(5)).unwrap());
+        let agent = PurchaseOrderAgent::new();
+        poll_once(&agent, &client, &config).await.unwrap();
+
+        let calls = calls.lock().unwrap();
+        let handles = |action: &str| -> Vec<&str> {
+            calls.iter().filter(|(a, _)| a == action).map(|(_, body)| body["ReceiptHandle"].as_str().unwrap()).collect()
+        };
+        assert_eq!(handles("DeleteMessage"), ["r-1", "r-2"], "both messages are deleted though no result was sent");
+        assert_eq!(calls.iter().filter(|(action, _)| action == "SendMessage").count(), 2);
+        assert!(handles("ChangeMessageVisibility").contains(&"r-2"), "the waiting message is kept hidden");
+        assert_eq!(agent.dependencies().get("intake:sqs").unwrap().consecutive_failures, 2);
+    }
+
+    #[test]
+    fn test_result_body_reports_outcome() {
+        let message = SqsMessage {
+            message_id: "m-1".to_string(),
+            receipt_handle: "r".to_string(),
+            body: "{}".to_string(),
+        };
+        let body: Value = serde_json::from_str(&result_body(&message, &IntakeOutcome::Invalid("bad".to_string()))).unwrap();
+        assert_eq!(body["message_id"], "m-1");
+        assert_eq!(body["outcome"], "invalid");
+        assert_eq!(body["error"], "bad");
+        assert!(body["task"].is_null());
+    }
+}
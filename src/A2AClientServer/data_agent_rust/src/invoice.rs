@@ -0,0 +1,377 @@
+use a2a::{Message, Part, TaskState, TaskStatus};
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::error::Error;
+
+use crate::agent::{PurchaseOrder, PurchaseOrderAgent};
+use crate::parts::{self, PartSchema};
+use crate::receiving::received_quantities;
+use crate::skills::SkillHandler;
+
+/// Skill matching supplier invoices against processed purchase orders
+pub const INVOICE_MATCHING_SKILL: &str = "invoice-matching";
+
+/// Supplier invoice referencing a purchase order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Invoice {
+    pub invoice_number: String,
+    pub po_number: String,
+    pub supplier_name: String,
+    pub lines: Vec<InvoiceLine>,
+    /// Date the invoice was issued; required by invoice processing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub invoice_date: Option<NaiveDate>,
+    /// Date payment is due; derived from the payment terms when absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due_date: Option<NaiveDate>,
+    /// Payment terms, e.g. "Net 30", "2/10 Net 30" or "Due on receipt"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payment_terms: Option<String>,
+    /// ISO 4217 currency of the amounts; the order's currency when absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
+}
+
+/// One billed line of an invoice
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InvoiceLine {
+    pub item_code: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub quantity: u32,
+    pub unit_price: f64,
+}
+
+/// Wrapper for the incoming invoice data
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InvoiceWrapper {
+    pub invoice: Invoice,
+}
+
+/// How far an invoice may deviate from the order before a line is a variance
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct InvoiceMatchConfig {
+    /// Allowed quantity deviation as a fraction of the expected quantity
+    pub quantity_tolerance: f64,
+    /// Allowed unit price deviation as a fraction of the ordered price
+    pub price_tolerance: f64,
+}
+
+impl Default for InvoiceMatchConfig {
+    fn default() -> Self {
+        Self {
+            quantity_tolerance: 0.0,
+            price_tolerance: 0.01,
+        }
+    }
+}
+
+/// Whether an invoice was checked against the order alone or also against receipts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchType {
+    TwoWay,
+    ThreeWay,
+}
+
+/// Outcome for one item code
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LineMatchStatus {
+    /// Quantity and price are within tolerance
+    Matched,
+    /// Quantity or price is out of tolerance
+    Variance,
+    /// Billed but not on the order
+    NotOrdered,
+    /// Ordered but not billed on this invoice; not a variance by itself
+    NotInvoiced,
+}
+
+/// Comparison of one item code between order, receipts and invoice
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LineVariance {
+    pub item_code: String,
+    pub status: LineMatchStatus,
+    pub ordered_quantity: u32,
+    /// Quantity received so far; only present for three-way matches
+    pub received_quantity: Option<u32>,
+    pub invoiced_quantity: u32,
+    /// Invoiced quantity minus the received (three-way) or ordered (two-way) quantity
+    pub quantity_variance: i64,
+    pub ordered_unit_price: f64,
+    pub invoiced_unit_price: f64,
+    pub price_variance: f64,
+    /// Invoiced amount minus the expected amount at the ordered price
+    pub amount_variance: f64,
+}
+
+/// Result of matching an invoice against its purchase order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MatchReport {
+    pub invoice_number: String,
+    pub po_number: String,
+    /// Task of the processed purchase order the invoice was matched against
+    pub task_id: String,
+    pub match_type: MatchType,
+    /// Whether every billed line matched and no header issue was found
+    pub matched: bool,
+    /// Findings not tied to a line, such as a different supplier
+    pub issues: Vec<String>,
+    pub lines: Vec<LineVariance>,
+    pub invoiced_total: f64,
+    pub total_variance: f64,
+}
+
+/// Quantity and weighted average unit price per lower-cased item code
+fn group<'a>(lines: impl IntoIterator<Item = (&'a str, u32, f64)>) -> BTreeMap<String, (u32, f64)> {
+    let mut totals: BTreeMap<String, (u32, f64)> = BTreeMap::new();
+    for (code, quantity, amount) in lines {
+        let entry = totals.entry(code.trim().to_lowercase()).or_default();
+        entry.0 += quantity;
+        entry.1 += amount;
+    }
+    totals
+        .into_iter()
+        .map(|(code, (quantity, amount))| {
+            let price = if quantity == 0 { 0.0 } else { amount / quantity as f64 };
+            (code, (quantity, price))
+        })
+        .collect()
+}
+
+pub(crate) fn round2(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+/// Match an invoice against an order, and against received quantities when given
+///
+/// `received` maps lower-cased item codes to the quantity received so far;
+/// passing it makes the match three-way.
+pub fn match_invoice(
+    task_id: &str,
+    po: &PurchaseOrder,
+    invoice: &Invoice,
+    received: Option<&HashMap<String, u32>>,
+    config: &InvoiceMatchConfig,
+) -> MatchReport {
+    let ordered = group(po.items.iter().map(|i| (i.item_code.as_str(), i.quantity, i.unit_price * i.quantity as f64)));
+    let invoiced = group(invoice.lines.iter().map(|l| (l.item_code.as_str(), l.quantity, l.unit_price * l.quantity as f64)));
+
+    let mut issues = Vec::new();
+    if !invoice.supplier_name.trim().eq_ignore_ascii_case(po.supplier_name.trim()) {
+        issues.push(format!(
+            "Invoice supplier '{}' does not match order supplier '{}'",
+            invoice.supplier_name, po.supplier_name
+        ));
+    }
+
+    let codes: BTreeSet<&String> = ordered.keys().chain(invoiced.keys()).collect();
+    let mut lines = Vec::new();
+    for code in codes {
+        let (ordered_quantity, ordered_unit_price) = ordered.get(code).copied().unwrap_or_default();
+        let (invoiced_quantity, invoiced_unit_price) = invoiced.get(code).copied().unwrap_or_default();
+        let received_quantity = received.map(|r| r.get(code).copied().unwrap_or(0));
+        let expected_quantity = received_quantity.unwrap_or(ordered_quantity);
+
+        let quantity_variance = invoiced_quantity as i64 - expected_quantity as i64;
+        let price_variance = invoiced_unit_price - ordered_unit_price;
+        let quantity_ok = (quantity_variance.unsigned_abs() as f64) <= config.quantity_tolerance * expected_quantity as f64;
+        let price_ok = price_variance.abs() <= config.price_tolerance * ordered_unit_price + 1e-9;
+
+        let status = if !ordered.contains_key(code) {
+            LineMatchStatus::NotOrdered
+        } else if !invoiced.contains_key(code) {
+            LineMatchStatus::NotInvoiced
+        } else if quantity_ok && price_ok {
+            LineMatchStatus::Matched
+        } else {
+            LineMatchStatus::Variance
+        };
+
+        lines.push(LineVariance {
+            item_code: code.clone(),
+            status,
+            ordered_quantity,
+            received_quantity,
+            invoiced_quantity,
+            quantity_variance,
+            ordered_unit_price: round2(ordered_unit_price),
+            invoiced_unit_price: round2(invoiced_unit_price),
+            price_variance: round2(price_variance),
+            amount_variance: round2(
+                invoiced_quantity as f64 * invoiced_unit_price - expected_quantity as f64 * ordered_unit_price,
+            ),
+        });
+    }
+
+    let invoiced_total = round2(invoice.lines.iter().map(|l| l.unit_price * l.quantity as f64).sum());
+    let total_variance = round2(
+        lines
+            .iter()
+            .filter(|l| l.status != LineMatchStatus::NotInvoiced)
+            .map(|l| l.amount_variance)
+            .sum(),
+    );
+    let matched = issues.is_empty()
+        && lines
+            .iter()
+            .all(|l| matches!(l.status, LineMatchStatus::Matched | LineMatchStatus::NotInvoiced));
+
+    MatchReport {
+        invoice_number: invoice.invoice_number.clone(),
+        po_number: po.po_number.clone(),
+        task_id: task_id.to_string(),
+        match_type: if received.is_some() { MatchType::ThreeWay } else { MatchType::TwoWay },
+        matched,
+        issues,
+        lines,
+        invoiced_total,
+        total_variance,
+    }
+}
+
+/// Find the invoice carried by a message, as JSON in a Data or Text part
+pub fn extract_invoice(message: &Message) -> Result<Invoice, Box<dyn Error>> {
+    parts::extract(message, PartSchema::Invoice, |wrapper: InvoiceWrapper| wrapper.invoice)
+}
+
+/// Matches an invoice against the latest processed order with its PO number
+///
+/// The match is three-way once goods have been received against the order, two-way before.
+pub struct InvoiceMatchSkill {
+    config: InvoiceMatchConfig,
+}
+
+impl InvoiceMatchSkill {
+    pub fn new(config: InvoiceMatchConfig) -> Self {
+        Self { config }
+    }
+}
+
+#[async_trait]
+impl SkillHandler for InvoiceMatchSkill {
+    fn id(&self) -> &str {
+        INVOICE_MATCHING_SKILL
+    }
+
+    async fn handle(&self, agent: &PurchaseOrderAgent, message: &Message) -> Result<TaskStatus, Box<dyn Error>> {
+        let invoice = extract_invoice(message)?;
+        let (task_id, po) = agent
+            .find_purchase_order(&invoice.po_number)?
+            .ok_or_else(|| format!("No processed purchase order {} to match invoice against", invoice.po_number))?;
+        let receipts = agent.receipts().for_task(&task_id)?;
+        let received = (!receipts.is_empty()).then(|| received_quantities(&receipts));
+        let report = match_invoice(&task_id, &po, &invoice, received.as_ref(), &self.config);
+
+        Ok(TaskStatus {
+            state: if report.matched { TaskState::Completed } else { TaskState::Failed },
+            message: Some(Message {
+                role: "assistant".to_string(),
+                parts: vec![Part::Data { data: serde_json::to_value(&report)? }],
+            }),
+            timestamp: agent.current_timestamp(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn po() -> PurchaseOrder {
+        crate::testing::valid_order()
+    }
+
+    fn invoice(lines: Vec<(&str, u32, f64)>) -> Invoice {
+        Invoice {
+            invoice_number: "INV-1".to_string(),
+            po_number: "PO-1".to_string(),
+            supplier_name: "ACME Office Supply".to_string(),
+            lines: lines
+                .into_iter()
+                .map(|(code, quantity, unit_price)| InvoiceLine {
+                    item_code: code.to_string(),
+                    description: None,
+                    quantity,
+                    unit_price,
+                })
+                .collect(),
+            invoice_date: None,
+            due_date: None,
+            payment_terms: None,
+            currency: None,
+        }
+    }
+
+    #[test]
+    fn test_two_way_match_reports_variances() {
+        let config = InvoiceMatchConfig::default();
+
+        let exact = match_invoice("t", &po(), &invoice(vec![("p-1", 10, 5.0), ("T-2", 3, 10.0)]), None, &config);
+        assert!(exact.matched, "{:?}", exact);
+        assert_eq!(exact.match_type, MatchType::TwoWay);
+        assert_eq!(exact.total_variance, 0.0);
+
+        let partial = match_invoice("t", &po(), &invoice(vec![("P-1", 10, 5.04)]), None, &config);
+        assert!(partial.matched, "price within 1% and unbilled lines are fine");
+        assert_eq!(partial.lines[1].status, LineMatchStatus::NotInvoiced);
+
+        let off = match_invoice("t", &po(), &invoice(vec![("P-1", 12, 5.5), ("X-9", 1, 1.0)]), None, &config);
+        assert!(!off.matched);
+        let p1 = off.lines.iter().find(|l| l.item_code == "p-1").unwrap();
+        assert_eq!(p1.status, LineMatchStatus::Variance);
+        assert_eq!(p1.quantity_variance, 2);
+        assert_eq!(p1.price_variance, 0.5);
+        assert_eq!(p1.amount_variance, 16.0);
+        assert_eq!(off.lines.iter().find(|l| l.item_code == "x-9").unwrap().status, LineMatchStatus::NotOrdered);
+    }
+
+    #[test]
+    fn test_three_way_match_uses_received_quantities() {
+        let received = HashMap::from([("p-1".to_string(), 6)]);
+        let report = match_invoice(
+            "t",
+            &po(),
+            &invoice(vec![("P-1", 10, 5.0)]),
+            Some(&received),
+            &InvoiceMatchConfig::default(),
+        );
+        assert_eq!(report.match_type, MatchType::ThreeWay);
+        assert!(!report.matched, "billed for more than was received");
+        assert_eq!(report.lines[0].received_quantity, Some(6));
+        assert_eq!(report.lines[0].quantity_variance, 4);
+
+        let mut other_supplier = invoice(vec![("P-1", 6, 5.0)]);
+        other_supplier.supplier_name = "Globex".to_string();
+        let report = match_invoice("t", &po(), &other_supplier, Some(&received), &InvoiceMatchConfig::default());
+        assert!(!report.matched);
+        assert_eq!(report.issues.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_skill_matches_against_processed_order() {
+        use a2a::A2AProtocol;
+
+        let agent = PurchaseOrderAgent::new();
+        let data = |value: serde_json::Value| Message { role: "user".to_string(), parts: vec![Part::Data { data: value }] };
+        let processed = agent.send_task(data(serde_json::to_value(po()).unwrap())).await.unwrap();
+
+        let request = serde_json::json!({ "skill_id": INVOICE_MATCHING_SKILL, "invoice": invoice(vec![("P-1", 10, 5.0)]) });
+        let task = agent.send_task(data(request)).await.unwrap();
+        assert!(matches!(task.status.state, TaskState::Completed));
+        let Part::Data { data: report } = &task.status.message.unwrap().parts[0] else { panic!("expected a data part") };
+        let report: MatchReport = serde_json::from_value(report.clone()).unwrap();
+        assert_eq!(report.task_id, processed.id);
+
+        let mut unknown = invoice(vec![]);
+        unknown.po_number = "PO-404".to_string();
+        let request = serde_json::json!({ "skill_id": INVOICE_MATCHING_SKILL, "invoice": unknown });
+        assert!(agent.send_task(data(request)).await.is_err());
+    }
+}
@@ -0,0 +1,384 @@
+//! Invoice processing skill
+//!
+//! The invoice agent takes supplier invoices through the same task lifecycle,
+//! store, server and authentication as purchase orders, as a skill of the
+//! agent. It checks the invoice itself, its dates against its payment terms,
+//! and cross-references the processed purchase order it bills, matching its
+//! lines as the `invoice-matching` skill does. Invoices that pass are approved
+//! for payment by their due date; the task fails otherwise.
+
+use a2a::{Message, Part, TaskState, TaskStatus};
+use async_trait::async_trait;
+use chrono::{Days, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+use crate::agent::{PurchaseOrder, PurchaseOrderAgent};
+use crate::invoice::{self, Invoice, InvoiceMatchConfig, MatchReport};
+use crate::receiving::received_quantities;
+use crate::skills::SkillHandler;
+
+/// Skill validating supplier invoices and approving them for payment
+pub const INVOICE_PROCESSING_SKILL: &str = "invoice-processing";
+
+/// When an invoice is due, parsed from terms such as "Net 30" or "2/10 Net 30"
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PaymentTerms {
+    DueOnReceipt,
+    Net { days: u32 },
+    /// `percent`% off when paid within `discount_days`, else due in `days`
+    EarlyPayment { percent: f64, discount_days: u32, days: u32 },
+}
+
+impl PaymentTerms {
+    /// Parse terms case-insensitively; `None` for terms this agent does not know
+    pub fn parse(terms: &str) -> Option<Self> {
+        let terms = terms.trim().to_lowercase();
+        if matches!(terms.as_str(), "due on receipt" | "on receipt" | "immediate") {
+            return Some(PaymentTerms::DueOnReceipt);
+        }
+        let words: Vec<&str> = terms.split_whitespace().collect();
+        match words.as_slice() {
+            ["net", days] => Some(PaymentTerms::Net { days: days.parse().ok()? }),
+            [discount, "net", days] => {
+                let (percent, discount_days) = discount.split_once('/')?;
+                let (percent, discount_days, days) = (percent.parse().ok()?, discount_days.parse().ok()?, days.parse().ok()?);
+                (percent > 0.0 && percent < 100.0 && discount_days < days)
+                    .then_some(PaymentTerms::EarlyPayment { percent, discount_days, days })
+            }
+            _ => None,
+        }
+    }
+
+    /// Days after the invoice date that payment is due
+    pub fn net_days(self) -> u32 {
+        match self {
+            PaymentTerms::DueOnReceipt => 0,
+            PaymentTerms::Net { days } | PaymentTerms::EarlyPayment { days, .. } => days,
+        }
+    }
+}
+
+impl fmt::Display for PaymentTerms {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PaymentTerms::DueOnReceipt => f.write_str("Due on receipt"),
+            PaymentTerms::Net { days } => write!(f, "Net {}", days),
+            PaymentTerms::EarlyPayment { percent, discount_days, days } => {
+                write!(f, "{}/{} Net {}", percent, discount_days, days)
+            }
+        }
+    }
+}
+
+/// How invoices are processed
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct InvoiceProcessingConfig {
+    /// Terms of invoices that state none
+    pub default_payment_terms: String,
+    /// Longest net period accepted without a warning, in days
+    pub max_net_days: u32,
+}
+
+impl Default for InvoiceProcessingConfig {
+    fn default() -> Self {
+        Self {
+            default_payment_terms: "Net 30".to_string(),
+            max_net_days: 90,
+        }
+    }
+}
+
+/// Outcome of processing an invoice
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InvoiceReview {
+    pub invoice_number: String,
+    pub po_number: String,
+    /// Whether the invoice is approved for payment
+    pub valid: bool,
+    pub validation_errors: Vec<String>,
+    pub warnings: Vec<String>,
+    /// The invoice's terms, or the default ones, as understood
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payment_terms: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due_date: Option<NaiveDate>,
+    /// Last day the early payment discount applies
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub discount_date: Option<NaiveDate>,
+    /// Amount saved by paying by the discount date
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub discount_amount: Option<f64>,
+    /// Match against the processed purchase order, when one was found
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub match_report: Option<MatchReport>,
+}
+
+/// The processed purchase order an invoice bills, and the quantities received on it
+pub struct OrderReference<'a> {
+    pub task_id: &'a str,
+    pub po: &'a PurchaseOrder,
+    /// Received quantities by lower-cased item code; `None` before any receipt
+    pub received: Option<&'a HashMap<String, u32>>,
+}
+
+/// Check an invoice's fields, its dates as of `today`, and the order it bills
+pub fn review(
+    invoice: &Invoice,
+    order: Option<OrderReference<'_>>,
+    config: &InvoiceProcessingConfig,
+    match_config: &InvoiceMatchConfig,
+    today: NaiveDate,
+) -> InvoiceReview {
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+
+    if invoice.invoice_number.trim().is_empty() {
+        errors.push("Invoice number is required".to_string());
+    }
+    if invoice.po_number.trim().is_empty() {
+        errors.push("PO number is required".to_string());
+    }
+    if invoice.supplier_name.trim().is_empty() {
+        errors.push("Supplier name is required".to_string());
+    }
+    if invoice.lines.is_empty() {
+        errors.push("Invoice must contain at least one line".to_string());
+    }
+    for (index, line) in invoice.lines.iter().enumerate() {
+        if line.quantity == 0 {
+            errors.push(format!("Line {} has zero quantity", index + 1));
+        }
+        if line.unit_price <= 0.0 {
+            errors.push(format!("Line {} has invalid unit price", index + 1));
+        }
+    }
+
+    // Dates and payment terms
+    let stated_terms = invoice.payment_terms.as_deref().unwrap_or(&config.default_payment_terms);
+    let terms = PaymentTerms::parse(stated_terms);
+    match terms {
+        None => errors.push(format!("Payment terms '{}' are not recognized", stated_terms)),
+        Some(terms) if terms.net_days() > config.max_net_days => warnings.push(format!(
+            "Payment terms {} exceed the {}-day maximum",
+            terms, config.max_net_days
+        )),
+        Some(_) => {}
+    }
+    let mut due_date = invoice.due_date;
+    let mut discount_date = None;
+    match invoice.invoice_date {
+        None => errors.push("Invoice date is required".to_string()),
+        Some(invoice_date) if invoice_date > today => {
+            errors.push(format!("Invoice date {} is in the future", invoice_date));
+        }
+        Some(invoice_date) => {
+            if let Some(terms) = terms {
+                let expected = invoice_date + Days::new(terms.net_days() as u64);
+                match invoice.due_date {
+                    None => due_date = Some(expected),
+                    Some(due) if due < invoice_date => {
+                        errors.push(format!("Due date {} is before the invoice date {}", due, invoice_date));
+                    }
+                    Some(due) if due != expected => warnings.push(format!(
+                        "Due date {} does not match {} from {} ({})",
+                        due, terms, invoice_date, expected
+                    )),
+                    Some(_) => {}
+                }
+                if let PaymentTerms::EarlyPayment { discount_days, .. } = terms {
+                    discount_date = Some(invoice_date + Days::new(discount_days as u64));
+                }
+            }
+        }
+    }
+    if let Some(due) = due_date
+        && due < today
+    {
+        warnings.push(format!("Invoice is past due since {}", due));
+    }
+
+    // Cross-reference of the purchase order
+    let match_report = match order {
+        None => {
+            errors.push(format!("No processed purchase order {} to match invoice against", invoice.po_number));
+            None
+        }
+        Some(order) => {
+            if let (Some(billed), Some(ordered)) = (&invoice.currency, &order.po.currency)
+                && !billed.trim().eq_ignore_ascii_case(ordered.trim())
+            {
+                errors.push(format!("Invoice currency {} does not match order currency {}", billed, ordered));
+            }
+            let report = invoice::match_invoice(order.task_id, order.po, invoice, order.received, match_config);
+            errors.extend(report.issues.iter().cloned());
+            let variances = report.lines.iter().filter(|line| {
+                matches!(line.status, invoice::LineMatchStatus::Variance | invoice::LineMatchStatus::NotOrdered)
+            });
+            for line in variances {
+                errors.push(format!(
+                    "Line {} does not match purchase order {}: billed {} at {:.2}, expected {} at {:.2}",
+                    line.item_code,
+                    order.po.po_number,
+                    line.invoiced_quantity,
+                    line.invoiced_unit_price,
+                    line.received_quantity.unwrap_or(line.ordered_quantity),
+                    line.ordered_unit_price,
+                ));
+            }
+            Some(report)
+        }
+    };
+
+    let discount_amount = match (terms, &match_report) {
+        (Some(PaymentTerms::EarlyPayment { percent, .. }), Some(report)) => {
+            Some(invoice::round2(report.invoiced_total * percent / 100.0))
+        }
+        _ => None,
+    };
+
+    InvoiceReview {
+        invoice_number: invoice.invoice_number.clone(),
+        po_number: invoice.po_number.clone(),
+        valid: errors.is_empty(),
+        validation_errors: errors,
+        warnings,
+        payment_terms: terms.map(|terms| terms.to_string()),
+        due_date,
+        discount_date: discount_date.filter(|_| discount_amount.is_some()),
+        discount_amount,
+        match_report,
+    }
+}
+
+/// Validates supplier invoices against their terms and purchase orders
+pub struct InvoiceAgent {
+    config: InvoiceProcessingConfig,
+    match_config: InvoiceMatchConfig,
+}
+
+impl InvoiceAgent {
+    pub fn new(config: InvoiceProcessingConfig, match_config: InvoiceMatchConfig) -> Self {
+        Self { config, match_config }
+    }
+}
+
+#[async_trait]
+impl SkillHandler for InvoiceAgent {
+    fn id(&self) -> &str {
+        INVOICE_PROCESSING_SKILL
+    }
+
+    async fn handle(&self, agent: &PurchaseOrderAgent, message: &Message) -> Result<TaskStatus, Box<dyn Error>> {
+        let invoice = invoice::extract_invoice(message)?;
+        let order = agent.find_purchase_order(&invoice.po_number)?;
+        let received = match &order {
+            Some((task_id, _)) => {
+                let receipts = agent.receipts().for_task(task_id)?;
+                (!receipts.is_empty()).then(|| received_quantities(&receipts))
+            }
+            None => None,
+        };
+        let reference = order.as_ref().map(|(task_id, po)| OrderReference { task_id, po, received: received.as_ref() });
+        let review = review(&invoice, reference, &self.config, &self.match_config, Utc::now().date_naive());
+
+        Ok(TaskStatus {
+            state: if review.valid { TaskState::Completed } else { TaskState::Failed },
+            message: Some(Message {
+                role: "assistant".to_string(),
+                parts: vec![Part::Data { data: serde_json::to_value(&review)? }],
+            }),
+            timestamp: agent.current_timestamp(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::invoice::InvoiceLine;
+    use a2a::A2AProtocol;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 3, day).unwrap()
+    }
+
+    fn invoice(terms: Option<&str>, due_date: Option<NaiveDate>) -> Invoice {
+        Invoice {
+            invoice_number: "INV-1".to_string(),
+            po_number: "PO-1".to_string(),
+            supplier_name: "Acme Office Supply".to_string(),
+            lines: vec![InvoiceLine { item_code: "P-1".to_string(), description: None, quantity: 10, unit_price: 5.0 }],
+            invoice_date: Some(date(1)),
+            due_date,
+            payment_terms: terms.map(str::to_string),
+            currency: None,
+        }
+    }
+
+    #[test]
+    fn test_payment_terms_parse() {
+        assert_eq!(PaymentTerms::parse("net 45"), Some(PaymentTerms::Net { days: 45 }));
+        assert_eq!(
+            PaymentTerms::parse("2/10 Net 30"),
+            Some(PaymentTerms::EarlyPayment { percent: 2.0, discount_days: 10, days: 30 })
+        );
+        assert_eq!(PaymentTerms::parse("Due on receipt").map(PaymentTerms::net_days), Some(0));
+        assert_eq!(PaymentTerms::parse("2/30 Net 10"), None);
+        assert_eq!(PaymentTerms::parse("whenever"), None);
+    }
+
+    #[test]
+    fn test_review_checks_dates_against_terms() {
+        let mut po = crate::testing::valid_order();
+        po.currency = Some("USD".to_string());
+        let order = || Some(OrderReference { task_id: "task-1", po: &po, received: None });
+        let (config, match_config) = (InvoiceProcessingConfig::default(), InvoiceMatchConfig::default());
+
+        let approved = review(&invoice(Some("2/10 Net 30"), None), order(), &config, &match_config, date(5));
+        assert!(approved.valid, "{:?}", approved.validation_errors);
+        assert_eq!((approved.due_date, approved.discount_date, approved.discount_amount), (Some(date(31)), Some(date(11)), Some(1.0)));
+
+        let late = review(&invoice(None, Some(date(20))), order(), &config, &match_config, date(25));
+        assert!(late.valid);
+        assert_eq!(late.warnings, ["Due date 2025-03-20 does not match Net 30 from 2025-03-01 (2025-03-31)", "Invoice is past due since 2025-03-20"]);
+
+        let mut overbilled = invoice(Some("Net 120"), None);
+        overbilled.lines[0].quantity = 12;
+        overbilled.currency = Some("EUR".to_string());
+        let rejected = review(&overbilled, order(), &config, &match_config, date(5));
+        assert_eq!(
+            rejected.validation_errors,
+            [
+                "Invoice currency EUR does not match order currency USD",
+                "Line p-1 does not match purchase order PO-1: billed 12 at 5.00, expected 10 at 5.00",
+            ]
+        );
+        assert_eq!(rejected.warnings, ["Payment terms Net 120 exceed the 90-day maximum"]);
+
+        let orphan = review(&invoice(Some("Net 30"), Some(date(1) - Days::new(1))), None, &config, &match_config, date(5));
+        assert_eq!(
+            orphan.validation_errors,
+            ["Due date 2025-02-28 is before the invoice date 2025-03-01", "No processed purchase order PO-1 to match invoice against"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_skill_approves_invoices_of_processed_orders() {
+        let agent = PurchaseOrderAgent::new();
+        agent.send_task(crate::testing::message(&crate::testing::valid_order())).await.unwrap();
+
+        let mut request = serde_json::json!({ "skill_id": INVOICE_PROCESSING_SKILL, "invoice": invoice(None, None) });
+        request["invoice"]["invoiceDate"] = serde_json::json!(Utc::now().date_naive());
+        let message = Message { role: "user".to_string(), parts: vec![Part::Data { data: request }] };
+        let task = agent.send_task(message).await.unwrap();
+        assert!(matches!(task.status.state, TaskState::Completed));
+        let Part::Data { data } = &task.status.message.unwrap().parts[0] else { panic!("expected a data part") };
+        let review: InvoiceReview = serde_json::from_value(data.clone()).unwrap();
+        assert_eq!(review.payment_terms.as_deref(), Some("Net 30"));
+        assert!(review.match_report.unwrap().matched);
+    }
+}
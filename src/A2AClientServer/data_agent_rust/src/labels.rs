@@ -0,0 +1,192 @@
+//! Key/value labels on tasks
+//!
+//! Labels mark tasks with whatever a caller needs to find them by later, such
+//! as the system an order came from or the fiscal period it is booked in. They
+//! are given at submission and changed with merge-patch semantics: a key set to
+//! `null` is removed, any other key set or replaced. Listings filter on them
+//! with a selector of `key:value` pairs, all of which must match.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::agent::PurchaseOrderAgent;
+use crate::events::TaskEventKind;
+use crate::store::StoreError;
+
+/// Labels of a task, ordered by key
+pub type Labels = BTreeMap<String, String>;
+
+/// Most labels a task can carry
+pub const MAX_LABELS: usize = 32;
+
+/// Longest label key
+pub const MAX_KEY_LEN: usize = 63;
+
+/// Longest label value
+pub const MAX_VALUE_LEN: usize = 255;
+
+/// Why labels could not be changed
+#[derive(Debug)]
+pub enum LabelError {
+    NotFound(String),
+    /// A key or value breaks the rules for labels
+    Invalid(String),
+    Store(StoreError),
+}
+
+impl fmt::Display for LabelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LabelError::NotFound(message) | LabelError::Invalid(message) => f.write_str(message),
+            LabelError::Store(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for LabelError {}
+
+impl From<StoreError> for LabelError {
+    fn from(e: StoreError) -> Self {
+        LabelError::Store(e)
+    }
+}
+
+/// Check that every key is 1 to 63 letters, digits, `.`, `-`, `_` or `/`, that
+/// values fit in 255 characters without control characters, and that there are
+/// not too many labels
+pub fn validate(labels: &Labels) -> Result<(), String> {
+    if labels.len() > MAX_LABELS {
+        return Err(format!("A task can carry at most {} labels", MAX_LABELS));
+    }
+    for (key, value) in labels {
+        if key.is_empty() || key.len() > MAX_KEY_LEN {
+            return Err(format!("Label key '{}' must be 1 to {} characters", key, MAX_KEY_LEN));
+        }
+        if !key.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_' | '/')) {
+            return Err(format!("Label key '{}' may only contain letters, digits, '.', '-', '_' and '/'", key));
+        }
+        if value.chars().count() > MAX_VALUE_LEN {
+            return Err(format!("Label '{}' is longer than {} characters", key, MAX_VALUE_LEN));
+        }
+        if value.chars().any(char::is_control) {
+            return Err(format!("Label '{}' contains control characters", key));
+        }
+    }
+    Ok(())
+}
+
+/// Parse a selector such as `source:sap,period:2026-Q3`
+pub fn parse_selector(selector: &str) -> Result<Labels, String> {
+    selector
+        .split(',')
+        .map(str::trim)
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| match pair.split_once(':') {
+            Some((key, value)) if !key.trim().is_empty() => Ok((key.trim().to_string(), value.trim().to_string())),
+            _ => Err(format!("Label selector '{}' is not of the form key:value", pair)),
+        })
+        .collect()
+}
+
+/// Whether `labels` carry every pair of `selector`
+pub fn matches(labels: &Labels, selector: &Labels) -> bool {
+    selector.iter().all(|(key, value)| labels.get(key) == Some(value))
+}
+
+/// Apply `patch` to a task's labels, recording a `labels_changed` event when they change
+///
+/// Returns the task's labels afterwards. Nothing changes when the result would
+/// break the rules for labels.
+pub fn update(agent: &PurchaseOrderAgent, task_id: &str, patch: BTreeMap<String, Option<String>>) -> Result<Labels, LabelError> {
+    // The patch applies to the stored labels inside the update, so concurrent patches all land
+    let mut changed = Ok(None);
+    let updated = agent.task_store().update(task_id, &mut |record| {
+        let mut labels = record.labels.clone();
+        for (key, value) in &patch {
+            match value {
+                Some(value) => labels.insert(key.clone(), value.clone()),
+                None => labels.remove(key),
+            };
+        }
+        changed = match validate(&labels) {
+            Err(e) => Err(e),
+            Ok(()) if labels == record.labels => Ok(None),
+            Ok(()) => {
+                record.labels = labels.clone();
+                Ok(Some(labels))
+            }
+        };
+    })?;
+    let Some(record) = updated else {
+        return Err(LabelError::NotFound(format!("Task {} not found", task_id)));
+    };
+    let Some(labels) = changed.map_err(LabelError::Invalid)? else {
+        return Ok(record.labels);
+    };
+
+    agent
+        .record_event(task_id, TaskEventKind::LabelsChanged { labels: labels.clone() })
+        .map_err(|e| LabelError::Store(StoreError(e.to_string())))?;
+    Ok(labels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::TaskOptions;
+
+    #[test]
+    fn test_selectors_match_every_pair() {
+        let selector = parse_selector("source:sap, period:2026-Q3").unwrap();
+        let labels: Labels = [("source", "sap"), ("period", "2026-Q3"), ("region", "emea")]
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        assert!(matches(&labels, &selector));
+        assert!(!matches(&labels, &parse_selector("source:oracle").unwrap()));
+        assert!(matches(&labels, &Labels::new()));
+        assert!(parse_selector("source").is_err());
+        assert!(validate(&[("bad key".to_string(), "x".to_string())].into()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_labels_are_merged_and_replayed() {
+        let agent = PurchaseOrderAgent::new();
+        let options = TaskOptions { labels: [("source".to_string(), "sap".to_string())].into(), ..TaskOptions::default() };
+        let task = agent.accept_task(crate::testing::message(&crate::testing::valid_order()), &options).unwrap();
+
+        let patch = [("period".to_string(), Some("2026-Q3".to_string())), ("source".to_string(), None)].into();
+        let labels = update(&agent, &task.id, patch).unwrap();
+        assert_eq!(labels, [("period".to_string(), "2026-Q3".to_string())].into());
+        assert_eq!(agent.task_store().get(&task.id).unwrap().unwrap().labels, labels);
+
+        let rebuilt = crate::events::rebuild(&agent.event_log().events(&task.id).unwrap()).unwrap();
+        assert_eq!(rebuilt.labels, labels);
+
+        let invalid = [("x".repeat(MAX_KEY_LEN + 1), Some("y".to_string()))].into();
+        assert!(matches!(update(&agent, &task.id, invalid), Err(LabelError::Invalid(_))));
+        assert!(matches!(update(&agent, "missing", BTreeMap::new()), Err(LabelError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_concurrent_patches_keep_each_others_changes() {
+        let agent = std::sync::Arc::new(PurchaseOrderAgent::new());
+        let task = agent.accept_task(crate::testing::message(&crate::testing::valid_order()), &TaskOptions::default()).unwrap();
+
+        let writers: Vec<_> = (0..8)
+            .map(|n| {
+                let (agent, task_id) = (agent.clone(), task.id.clone());
+                std::thread::spawn(move || update(&agent, &task_id, [(format!("key-{}", n), Some("x".to_string()))].into()).unwrap())
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+        assert_eq!(agent.task_store().get(&task.id).unwrap().unwrap().labels.len(), 8);
+
+        // A patch that changes nothing records no event
+        let events = agent.event_log().events(&task.id).unwrap().len();
+        update(&agent, &task.id, [("key-0".to_string(), Some("x".to_string()))].into()).unwrap();
+        assert_eq!(agent.event_log().events(&task.id).unwrap().len(), events);
+    }
+}
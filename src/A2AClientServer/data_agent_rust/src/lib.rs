@@ -1,7 +1,146 @@
+pub mod address;
 pub mod agent;
+pub mod admin;
+pub mod amendments;
+pub mod approval;
+pub mod approval_reason;
+pub mod batch_validation;
+pub mod artifacts;
+pub mod assembly;
+pub mod attachments;
 pub mod server;
 pub mod a2a_agent_card;
+pub mod auth;
+#[cfg(any(feature = "s3", feature = "sqs"))]
+pub mod aws;
+pub mod blanket;
+pub mod blob;
+pub mod breaker;
+pub mod catalog;
+pub mod client;
+pub mod config;
+pub mod config_check;
+pub mod dashboard;
+pub mod dead_letter;
+pub mod dry_run;
+pub mod directory;
+pub mod deadline;
+pub mod deferred;
+pub mod delegation;
+pub mod email;
+pub mod events;
+pub mod expense;
+pub mod flags;
+pub mod export;
+pub mod graphql;
+pub mod idempotency;
+pub mod idoc;
+pub mod ingest;
+pub mod intake;
+pub mod invoice;
+pub mod invoice_agent;
+pub mod labels;
+pub mod listener;
+pub mod masking;
+pub mod metrics;
+pub mod monthly_close;
+pub mod normalize;
+pub mod observability;
+pub mod onboarding;
+pub mod order_builder;
+pub mod parts;
+pub mod plugins;
+pub mod price_history;
+pub mod processing;
+pub mod rbac;
+pub mod readiness;
+pub mod receiving;
+pub mod redaction;
+pub mod registry;
+pub mod requisition;
+pub mod request_options;
+pub mod result_cache;
+pub mod result_schema;
+pub mod resubmit;
+pub mod retention;
+pub mod returns;
+pub mod risk;
+pub mod rounding;
+pub mod sales_tax;
+pub mod schedule;
+pub mod scheduled_exports;
+pub mod screening;
+pub mod searches;
+pub mod session;
+pub mod severity;
+pub mod signing;
+pub mod signing_keys;
+pub mod skills;
+pub mod snapshot;
+pub mod status;
+pub mod store;
+pub mod tax_id;
+pub mod thresholds;
+pub mod tool_schema;
+pub mod validation;
+pub mod webhook_template;
+pub mod webhooks;
+pub mod websocket;
+#[cfg(feature = "tls")]
+pub mod tls;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 
+pub use address::Address;
 pub use agent::{PurchaseOrderAgent, PurchaseOrder, PurchaseOrderItem, PurchaseOrderWrapper, ProcessingResult};
-pub use server::{create_router, AppState};
-pub use a2a_agent_card::{A2AAgentCard, ProviderInfo, Capabilities, Authentication, Skill};
\ No newline at end of file
+pub use server::{create_router, create_router_with_config, AppState};
+pub use amendments::{diff_orders, AmendmentStore, MemoryAmendmentStore, OrderDiff, Revision};
+pub use a2a_agent_card::{A2AAgentCard, A2AAgentCardBuilder, AgentInterface, ProviderInfo, Capabilities, Capability, Authentication, SecurityScheme, Skill};
+pub use auth::{AuthConfig, AuthContext, JwtValidator};
+pub use blanket::{BlanketOrder, BlanketReport, BlanketStatus, BlanketStore, BlanketTerms, MemoryBlanketStore};
+pub use blob::{AzureBlobConfig, BlobConfig, BlobStore, FileBlobStore, MemoryBlobStore, OffloadingTaskStore, PresignedUrl, S3BlobConfig};
+pub use catalog::{CatalogConfig, CatalogItem, CatalogStore, MemoryCatalogStore, MemorySupplierStore, Supplier, SupplierStore};
+pub use client::{A2AClient, ClientConfig, ClientError, SubmissionRetries, TargetPolicy};
+pub use config::{ListenConfig, ServerConfig, TlsConfig};
+pub use dead_letter::{DeadLetter, DeadLetterConfig, DeadLetterStore, MemoryDeadLetterStore};
+pub use directory::{Directory, DirectoryConfig, DirectoryProvider, DirectoryUser};
+pub use delegation::DelegationConfig;
+pub use email::EmailConfig;
+pub use events::{EventLog, MemoryEventLog, TaskEvent, TaskEventKind};
+pub use expense::{ExpenseConfig, ExpenseReport, ExpenseReview};
+pub use flags::FeatureFlags;
+pub use intake::IntakeConfig;
+pub use invoice::{Invoice, InvoiceLine, InvoiceMatchConfig, MatchReport};
+pub use invoice_agent::{InvoiceAgent, InvoiceProcessingConfig, InvoiceReview, PaymentTerms};
+pub use labels::Labels;
+pub use masking::MaskingConfig;
+pub use metrics::Metrics;
+pub use monthly_close::{Month, MonthlyCloseConfig, MonthlyCloseReport, ReportFormat};
+pub use normalize::Adjustment;
+pub use onboarding::{BankAccount, OnboardingConfig, OnboardingReport, SupplierApplication};
+pub use order_builder::{BuildError, PurchaseOrderBuilder};
+pub use parts::{PartMetadata, PartSchema};
+pub use plugins::{OutputTransformer, PluginFinding, Plugins, PluginsConfig, ValidationRule};
+pub use price_history::{MemoryPriceHistoryStore, PriceHistory, PriceHistoryConfig, PriceHistoryStore};
+pub use validation::{validate, ValidationConfig};
+pub use rbac::{Permission, RbacConfig};
+pub use readiness::{Dependencies, Dependency, DependencyKind, Readiness, ReadinessStatus};
+pub use receiving::{GoodsReceipt, MemoryReceiptStore, OpenOrderStatus, ReceiptStore, ReceivingStatus};
+pub use registry::{AgentRegistry, RegisteredAgent, RegistryConfig};
+pub use requisition::{Requisition, RequisitionConfig, RequisitionLine};
+pub use request_options::{OutputFormat, RequestOptions};
+pub use retention::RetentionConfig;
+pub use risk::{RiskAssessment, RiskConfig, RiskLevel, RiskScorer};
+pub use schedule::{CronSchedule, RecurringOrder, SchedulerConfig};
+pub use scheduled_exports::{ExportDestination, ExportFormat, ExportJob, ExportRange, ExportRun, ExportsConfig};
+pub use screening::{DeniedParty, DeniedPartyList, ScreeningConfig, ScreeningMatch};
+pub use searches::{FileSavedSearchStore, MemorySavedSearchStore, Period, SavedSearch, SavedSearchStore, SearchFilter, SearchesConfig};
+pub use session::{SessionContext, SessionTask};
+pub use severity::{Check, Severity, SeverityPolicy};
+pub use signing::SigningConfig;
+pub use skills::{SkillDispatcher, SkillHandler, ValidationReport};
+pub use snapshot::{SnapshotConfig, SnapshotFormat};
+pub use status::{ConfigSummary, ServerStatus, Status};
+pub use store::{MemoryTaskStore, StoreStats, TaskRecord, TaskStore};
+pub use tax_id::{TaxIdConfig, TaxIdFinding, TaxIdIssue};
+pub use webhooks::{DeliveryStatus, MemoryWebhookOutbox, WebhookDelivery, WebhookOutbox, WebhooksConfig};
\ No newline at end of file
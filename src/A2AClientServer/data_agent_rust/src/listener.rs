@@ -0,0 +1,243 @@
+//! Binding the server's listening socket
+//!
+//! Two ways let a new server process take over from an old one without
+//! refusing connections in between, for rolling restarts:
+//!
+//! - Socket activation: when systemd (or any manager following its protocol)
+//!   passes a listening socket in `LISTEN_FDS`, the server serves on it instead
+//!   of binding. The manager keeps the socket open across restarts, queueing
+//!   connections while no process accepts them.
+//! - `reuse_port`: the socket is bound with `SO_REUSEPORT`, so the new process
+//!   can bind the same address while the old one drains and exits.
+//!
+//! The server can also serve on a Unix domain socket, alongside TCP or instead
+//! of it, for a local proxy to connect to without any port being exposed.
+
+use socket2::{Domain, Protocol, Socket, Type};
+use std::fmt;
+use std::io;
+use std::net::{SocketAddr, TcpListener, ToSocketAddrs};
+
+use crate::config::ListenConfig;
+
+/// First file descriptor passed by socket activation
+#[cfg(unix)]
+const LISTEN_FDS_START: i32 = 3;
+
+/// Connections queued for accepting, beyond which the kernel refuses new ones
+const BACKLOG: i32 = 1024;
+
+/// Where the listening socket came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListenerSource {
+    /// Inherited from the service manager
+    Activated,
+    /// Bound by this process, with `SO_REUSEPORT` when `reuse_port` is true
+    Bound { reuse_port: bool },
+}
+
+impl fmt::Display for ListenerSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ListenerSource::Activated => f.write_str("socket activation"),
+            ListenerSource::Bound { reuse_port: true } => f.write_str("SO_REUSEPORT bind"),
+            ListenerSource::Bound { reuse_port: false } => f.write_str("bind"),
+        }
+    }
+}
+
+/// The socket to serve on: inherited through socket activation when one was
+/// passed to this process, otherwise bound to `bind_address`
+///
+/// The listener is non-blocking, ready for `tokio::net::TcpListener::from_std`.
+pub fn listen(config: &ListenConfig) -> io::Result<(TcpListener, ListenerSource)> {
+    let listener = match activated()? {
+        Some(listener) => (listener, ListenerSource::Activated),
+        None => (bind(&config.bind_address, config.reuse_port)?, ListenerSource::Bound { reuse_port: config.reuse_port }),
+    };
+    listener.0.set_nonblocking(true)?;
+    Ok(listener)
+}
+
+/// Bind a listener to `address`, allowing other processes to bind it too when `reuse_port` is true
+pub fn bind(address: &str, reuse_port: bool) -> io::Result<TcpListener> {
+    let address: SocketAddr = address
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("{} resolves to no address", address)))?;
+    let socket = Socket::new(Domain::for_address(address), Type::STREAM, Some(Protocol::TCP))?;
+    socket.set_reuse_address(true)?;
+    if reuse_port {
+        #[cfg(unix)]
+        socket.set_reuse_port(true)?;
+        #[cfg(not(unix))]
+        return Err(io::Error::new(io::ErrorKind::Unsupported, "reuse_port is only supported on Unix"));
+    }
+    socket.bind(&address.into())?;
+    socket.listen(BACKLOG)?;
+    Ok(socket.into())
+}
+
+/// The first socket passed by the service manager, if it passed any to this process
+///
+/// Follows the `sd_listen_fds` protocol: `LISTEN_PID` must name this process,
+/// and the sockets start at descriptor 3. The variables are removed so child
+/// processes do not take the socket for theirs.
+#[cfg(unix)]
+fn activated() -> io::Result<Option<TcpListener>> {
+    use std::os::fd::FromRawFd;
+
+    let pid = std::env::var("LISTEN_PID").ok().and_then(|pid| pid.parse::<u32>().ok());
+    let fds = std::env::var("LISTEN_FDS").ok().and_then(|fds| fds.parse::<i32>().ok()).unwrap_or(0);
+    if pid != Some(std::process::id()) || fds < 1 {
+        return Ok(None);
+    }
+    // SAFETY: the environment is only read at startup, before any other thread changes it
+    unsafe {
+        std::env::remove_var("LISTEN_PID");
+        std::env::remove_var("LISTEN_FDS");
+        std::env::remove_var("LISTEN_FDNAMES");
+    }
+
+    // SAFETY: the service manager passed this descriptor for this process to own
+    let socket = unsafe { Socket::from_raw_fd(LISTEN_FDS_START) };
+    if socket.r#type()? != Type::STREAM || !socket.is_listener()? {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "the activated socket is not a listening stream socket"));
+    }
+    Ok(Some(socket.into()))
+}
+
+#[cfg(not(unix))]
+fn activated() -> io::Result<Option<TcpListener>> {
+    Ok(None)
+}
+
+/// How long open connections get to finish once shutdown starts
+#[cfg(unix)]
+const DRAIN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Bind a Unix domain socket at `path`, replacing a socket file left by an earlier run
+///
+/// Any other kind of file at `path` is left alone and binding fails.
+#[cfg(unix)]
+pub fn bind_unix(path: &std::path::Path, mode: Option<u32>) -> io::Result<tokio::net::UnixListener> {
+    use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+
+    match std::fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_socket() => std::fs::remove_file(path)?,
+        Ok(_) => {
+            return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("{} exists and is not a socket", path.display())));
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e),
+    }
+    let listener = tokio::net::UnixListener::bind(path)?;
+    if let Some(mode) = mode {
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))?;
+    }
+    Ok(listener)
+}
+
+/// Serve `app` over HTTP on `listener` until `shutdown` resolves
+///
+/// Open connections then get a while to finish, and the socket file at `path`
+/// is removed.
+#[cfg(unix)]
+pub async fn serve_unix(
+    listener: tokio::net::UnixListener,
+    path: &std::path::Path,
+    app: axum::Router,
+    shutdown: impl std::future::Future<Output = ()>,
+) {
+    use axum::body::Body;
+    use axum::extract::Request;
+    use hyper::body::Incoming;
+    use hyper_util::rt::{TokioExecutor, TokioIo};
+    use hyper_util::server::conn::auto::Builder;
+    use hyper_util::server::graceful::GracefulShutdown;
+    use tower::Service;
+    use tracing::{debug, warn};
+
+    let graceful = GracefulShutdown::new();
+    tokio::pin!(shutdown);
+    loop {
+        let stream = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    warn!(error = %e, "failed to accept on the Unix socket");
+                    continue;
+                }
+            },
+            _ = &mut shutdown => break,
+        };
+        let app = app.clone();
+        let service = hyper::service::service_fn(move |request: Request<Incoming>| app.clone().call(request.map(Body::new)));
+        let builder = Builder::new(TokioExecutor::new());
+        // Upgrades are needed for WebSocket subscriptions
+        let connection = graceful.watch(builder.serve_connection_with_upgrades(TokioIo::new(stream), service).into_owned());
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                debug!(error = %e, "Unix socket connection closed with an error");
+            }
+        });
+    }
+
+    drop(listener);
+    if tokio::time::timeout(DRAIN_TIMEOUT, graceful.shutdown()).await.is_err() {
+        warn!("Unix socket connections still open after {:?}, closing them", DRAIN_TIMEOUT);
+    }
+    if let Err(e) = std::fs::remove_file(path) {
+        warn!(path = %path.display(), error = %e, "failed to remove the Unix socket");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reuse_port_lets_two_listeners_share_an_address() {
+        let first = bind("127.0.0.1:0", true).unwrap();
+        let address = first.local_addr().unwrap().to_string();
+        if cfg!(unix) {
+            let second = bind(&address, true).unwrap();
+            assert_eq!(second.local_addr().unwrap(), first.local_addr().unwrap());
+        }
+        assert!(bind(&address, false).is_err());
+
+        let config = ListenConfig { bind_address: "127.0.0.1:0".to_string(), ..ListenConfig::default() };
+        let (_, source) = listen(&config).unwrap();
+        assert_eq!(source, ListenerSource::Bound { reuse_port: false });
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_serves_http_on_a_unix_socket() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let path = std::env::temp_dir().join(format!("po-agent-{}.sock", uuid::Uuid::new_v4()));
+        std::fs::write(&path, "not a socket").unwrap();
+        assert!(bind_unix(&path, None).is_err());
+        std::fs::remove_file(&path).unwrap();
+
+        let listener = bind_unix(&path, Some(0o600)).unwrap();
+        let app = crate::create_router(std::sync::Arc::new(crate::PurchaseOrderAgent::new()));
+        let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+        let server = tokio::spawn({
+            let path = path.clone();
+            async move { serve_unix(listener, &path, app, async { stopped.await.unwrap_or_default() }).await }
+        });
+
+        let mut stream = tokio::net::UnixStream::connect(&path).await.unwrap();
+        stream.write_all(b"GET /health HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n").await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+        assert!(response.contains("\"status\":\"healthy\""));
+
+        stop.send(()).unwrap();
+        server.await.unwrap();
+        assert!(!path.exists());
+    }
+}
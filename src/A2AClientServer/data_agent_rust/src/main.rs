@@ -1,10 +1,13 @@
-use data_agent_rust::{PurchaseOrderAgent, PurchaseOrderWrapper, PurchaseOrder, PurchaseOrderItem};
+use data_agent_rust::{PurchaseOrderAgent, PurchaseOrderWrapper, PurchaseOrder};
 use a2a::{A2AProtocol, Message, Part};
 use std::error::Error;
-use serde_json;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
+    tracing_subscriber::fmt()
+        .with_env_filter("info")
+        .init();
+
     println!("🚀 Starting Purchase Order Processing Agent (A2A Protocol)");
     
     // Create the agent
@@ -23,41 +26,17 @@ async fn main() -> Result<(), Box<dyn Error>> {
     println!("\n🧪 Running demo with your purchase order...");
     
     let sample_po = PurchaseOrderWrapper {
-        purchase_order: PurchaseOrder {
-            supplier_name: "Marketing Masters Supplies".to_string(),
-            supplier_address_line1: "1234 Creative Avenue, Suite 567".to_string(),
-            supplier_address_line2: None,
-            supplier_city: "Imagination City".to_string(),
-            supplier_state: "CA".to_string(),
-            supplier_postal_code: "90210".to_string(),
-            supplier_country: "USA".to_string(),
-            items: vec![
-                PurchaseOrderItem {
-                    item_code: "bk-2345".to_string(),
-                    description: "Marketing Strategy Guidebook".to_string(),
-                    quantity: 3,
-                    unit_price: 29.99,
-                    line_total: 89.97,
-                },
-                PurchaseOrderItem {
-                    item_code: "Bk-1311".to_string(),
-                    description: "Promotional Materials Handbook".to_string(),
-                    quantity: 3,
-                    unit_price: 34.99,
-                    line_total: 104.97,
-                },
-            ],
-            po_number: "MMS-80085".to_string(),
-            created_by: "J.J. Schmidt".to_string(),
-            buyer_department: "Marketing".to_string(),
-            notes: Some("thanks for the order! Happy learning!! :)".to_string()),
-            tax_rate: 0.07,
-            sub_total: 194.94,
-            tax: 13.65,
-            grand_total: 208.59,
-            is_approved: true,
-            approval_reason: Some("Approved: Grand Total $208.59 is below $1000, Supplier Name is provided, and Buyer Department 'Marketing' is an authorized department.".to_string()),
-        }
+        purchase_order: PurchaseOrder::builder("MMS-80085")
+            .with_supplier("Marketing Masters Supplies")
+            .with_supplier_address("1234 Creative Avenue, Suite 567", "Imagination City", "CA", "90210", "USA")
+            .with_created_by("J.J. Schmidt")
+            .with_buyer_department("Marketing")
+            .with_item("bk-2345", "Marketing Strategy Guidebook", 3, 29.99)
+            .with_item("Bk-1311", "Promotional Materials Handbook", 3, 34.99)
+            .with_tax_rate(0.07)
+            .with_notes("thanks for the order! Happy learning!! :)")
+            .with_approval("Approved: Grand Total $208.59 is below $1000, Supplier Name is provided, and Buyer Department 'Marketing' is an authorized department.")
+            .build()?,
     };
     
     let po_data = serde_json::to_value(&sample_po)?;
@@ -1,7 +1,15 @@
-use data_agent_rust::{PurchaseOrderAgent, create_router};
+use data_agent_rust::attachments::BlobAttachmentStore;
+use data_agent_rust::events::{self, EventBus};
+use data_agent_rust::expense::ExpenseSkill;
+use data_agent_rust::invoice::InvoiceMatchSkill;
+use data_agent_rust::invoice_agent::InvoiceAgent;
+use data_agent_rust::onboarding::OnboardingSkill;
+use data_agent_rust::requisition::RequisitionSkill;
+use data_agent_rust::signing_keys::MemorySigningKeyStore;
+use data_agent_rust::webhooks::{self, WebhookPublisher};
+use data_agent_rust::{approval, blob, config_check, deadline, deferred, delegation, directory, email, intake, listener, monthly_close, plugins, processing, registry, retention, risk, schedule, scheduled_exports, searches, snapshot, DeniedPartyList, MemoryCatalogStore, MemoryDeadLetterStore, MemorySupplierStore, OffloadingTaskStore, PurchaseOrderAgent, ServerConfig, TlsConfig, create_router_with_config};
 use std::sync::Arc;
 use tracing::{info, error};
-use tracing_subscriber;
 
 #[tokio::main]
 async fn main() {
@@ -10,50 +18,418 @@ async fn main() {
         .with_env_filter("info,data_agent_rust=debug")
         .init();
 
+    // `--check-config` validates the configuration and exits instead of serving
+    let check_config = std::env::args().skip(1).any(|arg| arg == "--check-config");
+
+    // Load server configuration
+    let config = match ServerConfig::load() {
+        Ok(config) => config,
+        Err(e) if check_config => {
+            let mut report = config_check::CheckReport::default();
+            report.fail("config", e);
+            println!("{}", report);
+            std::process::exit(1);
+        }
+        Err(e) => {
+            error!("❌ Failed to load configuration: {}", e);
+            return;
+        }
+    };
+    if check_config {
+        let report = config_check::run(&config).await;
+        println!("{}", report);
+        std::process::exit(if report.passed() { 0 } else { 1 });
+    }
+    if config.auth.is_enabled() {
+        info!("🔐 Bearer token authentication enabled for task endpoints");
+    }
+
     // Create the Purchase Order Agent
-    let agent = Arc::new(PurchaseOrderAgent::new());
+    let mut agent = PurchaseOrderAgent::new()
+        .with_skill(Arc::new(InvoiceMatchSkill::new(config.invoice_match.clone())))
+        .with_skill(Arc::new(InvoiceAgent::new(config.invoice_processing.clone(), config.invoice_match.clone())))
+        .with_skill(Arc::new(RequisitionSkill::new(config.requisition.clone())))
+        .with_skill(Arc::new(ExpenseSkill::new(config.expenses.clone())))
+        .with_skill(Arc::new(OnboardingSkill::new(config.onboarding.clone())))
+        .with_supplier_store(Arc::new(MemorySupplierStore::from_config(&config.catalog)))
+        .with_catalog_store(Arc::new(MemoryCatalogStore::from_config(&config.catalog)))
+        .with_tax_id_config(config.tax_id.clone())
+        .with_severity_policy(config.severity.clone())
+        .with_redaction(config.redaction.clone())
+        .with_idoc_config(config.idoc.clone())
+        .with_approval(config.approval.clone())
+        .with_attachments_config(config.attachments.clone())
+        .with_high_value(config.high_value.clone())
+        .with_returns(config.returns.clone())
+        .with_reconciliation(config.reconciliation.clone())
+        .with_sales_tax(config.sales_tax.clone())
+        .with_result_cache(config.result_cache.clone())
+        .with_price_history(config.price_history.clone())
+        .with_dead_letter_store(Arc::new(MemoryDeadLetterStore::new(&config.dead_letters)))
+        .with_signing_key_store(Arc::new(MemorySigningKeyStore::from_config(&config.webhooks.signing_keys)));
+    for skill in config.flags.disabled_skills() {
+        match agent.without_skill(skill) {
+            Ok(without) => agent = without,
+            Err(e) => {
+                error!("❌ Invalid [flags.skills]: {}", e);
+                return;
+            }
+        }
+    }
+    let disabled = config.flags.disabled();
+    if !disabled.is_empty() {
+        info!("🚩 Subsystems switched off: {}", disabled.join(", "));
+    }
+    match searches::store(&config.searches) {
+        Ok(store) => agent = agent.with_saved_search_store(store),
+        Err(e) => {
+            error!("❌ Failed to load saved searches: {}", e);
+            return;
+        }
+    }
+    match plugins::load(&config.plugins) {
+        Ok(loaded) if loaded.is_empty() => {}
+        Ok(loaded) => {
+            info!("🧩 Plugins loaded: validation rules [{}], output transformers [{}]", loaded.rule_names().join(", "), loaded.transformer_names().join(", "));
+            agent = agent.with_plugins(loaded);
+        }
+        Err(e) => {
+            error!("❌ Failed to load plugins: {}", e);
+            return;
+        }
+    }
+    match risk::scorer(&config.risk) {
+        Ok(Some(scorer)) => agent = agent.with_risk_scorer(scorer),
+        Ok(None) => {}
+        Err(e) => {
+            error!("❌ Failed to configure risk scoring: {}", e);
+            return;
+        }
+    }
+    // Load the department and user directory; file and REST directories are refreshed below
+    let directory_provider = match directory::provider(&config.directory) {
+        Ok(provider) => provider,
+        Err(e) => {
+            error!("❌ Failed to configure the directory: {}", e);
+            return;
+        }
+    };
+    match directory_provider.load().await {
+        Ok(loaded) => {
+            info!("📒 Directory of {} departments and {} users loaded from {}", loaded.departments.len(), loaded.users.len(), directory_provider.name());
+            agent = agent.with_directory(loaded);
+        }
+        Err(e) => {
+            error!("❌ Failed to load the directory: {}", e);
+            return;
+        }
+    }
+    if let Some(path) = &config.screening.list {
+        match DeniedPartyList::load(path) {
+            Ok(list) => {
+                info!("🛑 Screening suppliers against {} denied parties", list.len());
+                agent = agent.with_denied_parties(Arc::new(list));
+            }
+            Err(e) => {
+                error!("❌ Failed to load denied-party list: {}", e);
+                return;
+            }
+        }
+    }
+
+    // Keep large payloads in a blob store, leaving references in the task store
+    match blob::store(&config.blobs) {
+        Ok(Some(blobs)) => {
+            info!("🗄️ Offloading parts over {} bytes to the {} blob store", config.blobs.offload_bytes, blobs.name());
+            let store = OffloadingTaskStore::new(agent.task_store().clone(), blobs.clone(), config.blobs.offload_bytes);
+            agent = agent
+                .with_task_store(Arc::new(store))
+                .with_attachment_store(Arc::new(BlobAttachmentStore::new(blobs.clone())))
+                .with_blob_store(blobs)
+                .with_download_url_ttl(config.blobs.url_ttl);
+        }
+        Ok(None) => {}
+        Err(e) => {
+            error!("❌ Failed to configure the blob store: {}", e);
+            return;
+        }
+    }
+
+    // Connect outbound event publishers
+    let mut publishers = match events::bus::connect_publishers(&config.events).await {
+        Ok(publishers) => publishers,
+        Err(e) => {
+            error!("❌ Failed to connect event publishers: {}", e);
+            return;
+        }
+    };
+    match email::publisher(&config.email) {
+        Ok(Some(email)) => publishers.push(email),
+        Ok(None) => {}
+        Err(e) => {
+            error!("❌ Failed to configure email notifications: {}", e);
+            return;
+        }
+    }
+    if config.webhooks.is_enabled() {
+        publishers.push(Arc::new(WebhookPublisher::new(
+            agent.webhook_outbox().clone(),
+            agent.task_store().clone(),
+            &config.webhooks,
+        )));
+    }
+    let delegation_queue = if config.delegation.is_enabled() {
+        let (publisher, queue) = delegation::queue(&config.delegation);
+        publishers.push(Arc::new(publisher));
+        Some(queue)
+    } else {
+        None
+    };
+    if !publishers.is_empty() {
+        let names: Vec<_> = publishers.iter().map(|p| p.name().to_string()).collect();
+        let (bus, _dispatcher) = EventBus::spawn(publishers, config.events.queue_capacity, agent.metrics().clone(), agent.dependencies().clone());
+        agent = agent.with_event_bus(bus);
+        info!("📣 Publishing task events to {}", names.join(", "));
+    }
+
+    if let Err(e) = config.exports.validate(agent.blob_store().is_some()) {
+        error!("❌ Invalid export jobs: {}", e);
+        return;
+    }
+
+    // Queue submissions for background processing
+    let processing_queue = if config.processing.background {
+        let (queue, receiver) = processing::queue(&config.processing, agent.metrics().clone());
+        agent = agent.with_submission_queue(queue);
+        Some(receiver)
+    } else {
+        None
+    };
+
+    let agent = Arc::new(agent);
     info!("🚀 Purchase Order Processing Agent initialized");
 
+    // Restore tasks saved by a previous run
+    if let (Some(path), true) = (&config.snapshot.path, config.snapshot.load_on_startup) {
+        match snapshot::load(agent.task_store().as_ref(), path) {
+            Ok(restored) => info!(restored, path = %path.display(), "💾 Task snapshot loaded"),
+            Err(e) => {
+                error!("❌ Failed to load task snapshot: {}", e);
+                return;
+            }
+        }
+    }
+
+    // Process submitted tasks in the background
+    let _processing_workers = processing_queue.map(|receiver| processing::spawn_workers(agent.clone(), &config.processing, receiver));
+
+    // Release tasks submitted with `process_after` when they are due
+    let _deferred_timer = deferred::spawn_timer(agent.clone());
+
+    // Consume purchase orders from queues
+    let _intake_workers = match intake::spawn_workers(agent.clone(), &config.intake) {
+        Ok(workers) => workers,
+        Err(e) => {
+            error!("❌ Failed to start queue intake: {}", e);
+            return;
+        }
+    };
+
+    // Deliver queued webhook notifications
+    let _webhook_worker = webhooks::spawn_delivery_worker(agent.clone(), config.webhooks.clone());
+
+    // Resolve and periodically refresh the cards of other agents
+    let agent_registry = Arc::new(registry::AgentRegistry::new(config.registry.clone()));
+    let _registry_refresher = registry::spawn_refresher(agent_registry.clone());
+
+    // Forward orders awaiting approval to the downstream agent
+    let _delegation_worker = delegation_queue.and_then(|queue| {
+        delegation::spawn_worker(agent.clone(), config.delegation.clone(), agent_registry.clone(), queue)
+    });
+
+    // Fail tasks left working past their deadline
+    let _deadline_watchdog = deadline::spawn_watchdog(agent.clone(), config.deadline.clone());
+
+    // Escalate approvals left undecided to the next approver
+    let _approval_escalator = approval::spawn_escalator(agent.clone());
+
+    // Expire finished tasks in the background
+    let _sweeper = retention::spawn_sweeper(agent.clone(), config.retention.clone());
+
+    // Generate recurring orders as their schedules fire
+    let _scheduler = schedule::spawn_scheduler(agent.clone(), config.scheduler.clone());
+
+    // Write monthly close reports as their schedule fires
+    let _close_scheduler = monthly_close::spawn_scheduler(agent.clone(), config.monthly_close.clone());
+
+    // Write scheduled exports of results as their schedules fire
+    let _export_scheduler = scheduled_exports::spawn_scheduler(agent.clone(), config.exports.clone());
+
+    // Reload the directory from its file or endpoint
+    let _directory_refresh = (config.directory.provider != directory::ProviderKind::Static)
+        .then(|| directory::spawn_refresh(agent.clone(), directory_provider.clone(), config.directory.refresh));
+
     // Create the router
-    let app = create_router(agent);
+    let app = create_router_with_config(agent.clone(), &config);
+
+    // Every server stops when the shutdown signal arrives
+    let (stop, stopping) = tokio::sync::watch::channel(false);
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        let _ = stop.send(true);
+    });
+
+    // Serve on a Unix domain socket, for a local proxy
+    #[cfg(unix)]
+    let unix_server = match &config.server.unix_socket {
+        Some(path) => match listener::bind_unix(path, config.server.unix_socket_mode) {
+            Ok(unix_listener) => {
+                info!("✅ Server listening on unix:{}", path.display());
+                let (path, app, stopping) = (path.clone(), app.clone(), stopping.clone());
+                Some(tokio::spawn(async move { listener::serve_unix(unix_listener, &path, app, stopped(stopping)).await }))
+            }
+            Err(e) => {
+                error!("❌ Failed to bind the Unix socket {}: {}", path.display(), e);
+                return;
+            }
+        },
+        None => None,
+    };
+    #[cfg(not(unix))]
+    let unix_server: Option<tokio::task::JoinHandle<()>> = match &config.server.unix_socket {
+        Some(_) => {
+            error!("❌ [server] unix_socket is only supported on Unix");
+            return;
+        }
+        None => None,
+    };
+
+    if config.server.tcp {
+        // Define the server address
+        let addr = config.server.bind_address.clone();
+        let scheme = if config.server.tls.is_some() { "https" } else { "http" };
+        info!("🌐 Starting server on {}://{}", scheme, addr);
+
+        // Create listener, inherited through socket activation or bound here
+        let bound = listener::listen(&config.server)
+            .and_then(|(listener, source)| Ok((tokio::net::TcpListener::from_std(listener)?, source)));
+        let listener = match bound {
+            Ok((listener, source)) => {
+                let local = listener.local_addr().map(|a| a.to_string()).unwrap_or_else(|_| addr.clone());
+                info!("✅ Server listening on {} ({})", local, source);
+                listener
+            }
+            Err(e) => {
+                error!("❌ Failed to bind to {}: {}", addr, e);
+                return;
+            }
+        };
+
+        // Build, uptime and configuration are served on /status rather than printed
+        let base_url = format!("{}://localhost:{}", scheme, listener.local_addr().map(|a| a.port()).unwrap_or(8080));
+        info!("📋 Status at {}/status, agent card at {}/.well-known/agent.json", base_url, base_url);
+
+        // Start the server
+        match &config.server.tls {
+            Some(tls) => serve_tls(listener, app, tls, stopped(stopping.clone())).await,
+            None => {
+                if let Err(e) = axum::serve(listener, app).with_graceful_shutdown(stopped(stopping.clone())).await {
+                    error!("❌ Server error: {}", e);
+                }
+            }
+        }
+    } else if unix_server.is_none() {
+        error!("❌ [server] tcp is off and no unix_socket is configured, so there is nothing to serve on");
+        return;
+    }
+    if let Some(unix_server) = unix_server {
+        let _ = unix_server.await;
+    }
 
-    // Define the server address
-    let addr = "0.0.0.0:8080";
-    info!("🌐 Starting server on http://{}", addr);
+    // Persist tasks for the next run
+    if let (Some(path), true) = (&config.snapshot.path, config.snapshot.save_on_shutdown) {
+        match snapshot::save(agent.task_store().as_ref(), path, agent.redaction()) {
+            Ok(saved) => info!(saved, path = %path.display(), "💾 Task snapshot saved"),
+            Err(e) => error!("❌ Failed to save task snapshot: {}", e),
+        }
+    }
+}
 
-    // Create listener
-    let listener = match tokio::net::TcpListener::bind(addr).await {
-        Ok(listener) => {
-            info!("✅ Server listening on {}", addr);
-            listener
+/// Resolve on Ctrl+C or, on Unix, SIGTERM
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            error!("❌ Failed to listen for Ctrl+C: {}", e);
+            std::future::pending::<()>().await;
         }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                error!("❌ Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+    info!("🛑 Shutting down");
+}
+
+/// Resolve once the shutdown signal has arrived
+async fn stopped(mut stopping: tokio::sync::watch::Receiver<bool>) {
+    let _ = stopping.wait_for(|stopped| *stopped).await;
+}
+
+/// Serve HTTPS on the bound listener using rustls
+#[cfg(feature = "tls")]
+async fn serve_tls(listener: tokio::net::TcpListener, app: axum::Router, tls: &TlsConfig, shutdown: impl std::future::Future<Output = ()> + Send + 'static) {
+    let rustls_config = match data_agent_rust::tls::rustls_config(tls) {
+        Ok(config) => config,
         Err(e) => {
-            error!("❌ Failed to bind to {}: {}", addr, e);
+            error!("❌ Invalid TLS configuration: {}", e);
             return;
         }
     };
+    if tls.client_ca_path.is_some() {
+        info!("🔐 Client certificate verification (mTLS) enabled");
+    }
 
-    // Print startup information
-    println!("\n🦀 Purchase Order Processing Agent Server");
-    println!("==========================================");
-    println!("🌐 Server URL: http://localhost:8080");
-    println!("📋 Available endpoints:");
-    println!("   GET  /                         - Agent info and API documentation");
-    println!("   GET  /.well-known/agent.json   - A2A compliant agent card (standard)");
-    println!("   GET  /health                   - Health check");
-    println!("   POST /agent/task               - Submit purchase order for processing");
-    println!("   GET  /agent/task/{{id}}         - Get task status and results");
-    println!("   POST /agent/task/{{id}}/cancel  - Cancel a task");
-    println!("\n📝 Example usage:");
-    println!("   curl http://localhost:8080/health");
-    println!("   curl http://localhost:8080/.well-known/agent.json");
-    println!("   curl http://localhost:8080/agent/info");
-    println!("\n🔗 A2A Agent Card: http://localhost:8080/.well-known/agent.json");
-    println!("🔗 Full API documentation available at: http://localhost:8080");
-    println!("==========================================\n");
+    let listener = match listener.into_std() {
+        Ok(listener) => listener,
+        Err(e) => {
+            error!("❌ Failed to prepare TLS listener: {}", e);
+            return;
+        }
+    };
+
+    let handle = axum_server::Handle::new();
+    let handle_shutdown = handle.clone();
+    tokio::spawn(async move {
+        shutdown.await;
+        handle_shutdown.graceful_shutdown(Some(std::time::Duration::from_secs(10)));
+    });
 
-    // Start the server
-    if let Err(e) = axum::serve(listener, app).await {
+    if let Err(e) = axum_server::from_tcp_rustls(listener, rustls_config)
+        .handle(handle)
+        .serve(app.into_make_service())
+        .await
+    {
         error!("❌ Server error: {}", e);
     }
-}
\ No newline at end of file
+}
+
+#[cfg(not(feature = "tls"))]
+async fn serve_tls(_listener: tokio::net::TcpListener, _app: axum::Router, _tls: &TlsConfig, _shutdown: impl std::future::Future<Output = ()>) {
+    error!("❌ [server.tls] is configured but this binary was built without the `tls` feature");
+}
@@ -0,0 +1,285 @@
+//! Masking of sensitive fields in API responses
+//!
+//! With masking enabled, callers whose roles grant none of the unmasking
+//! permissions get task responses, artifacts, event streams, webhook
+//! deliveries, order diffs and results exports with the configured fields
+//! masked as in log output, so `Sam Smith` reads `S***`. Approvers and admins
+//! see full data by default. The CSV row and artifacts are rebuilt from the
+//! masked result. Nothing stored changes. Routes whose output cannot be
+//! rewritten this way, such as the dashboard and GraphQL, are closed to those
+//! callers by [`require_unmasked`].
+//!
+//! Without authentication configured there is no caller to judge, so responses
+//! are never masked.
+
+use a2a::{Message, Part, Task, TaskStatus};
+use axum::extract::{Request, State};
+use axum::http::StatusCode;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use serde::Deserialize;
+use serde_json::Value;
+use std::sync::Arc;
+
+use crate::agent::ProcessingResult;
+use crate::amendments::OrderDiff;
+use crate::artifacts;
+use crate::auth::AuthContext;
+use crate::events::{TaskEvent, TaskEventKind};
+use crate::rbac::{Permission, RbacConfig};
+use crate::redaction::mask;
+use crate::server::AppState;
+use crate::store::TaskRecord;
+
+/// The `[masking]` section
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct MaskingConfig {
+    pub enabled: bool,
+    /// Keys masked wherever they appear in a result or request
+    pub fields: Vec<String>,
+    /// Callers granted any of these see responses unmasked
+    pub unmasked_permissions: Vec<Permission>,
+}
+
+impl Default for MaskingConfig {
+    fn default() -> Self {
+        let fields = [
+            "notes",
+            "createdBy",
+            "created_by",
+            "supplierAddressLine1",
+            "supplierAddressLine2",
+            "supplier_address_line1",
+            "supplier_address_line2",
+        ];
+        Self {
+            enabled: false,
+            fields: fields.iter().map(|field| field.to_string()).collect(),
+            unmasked_permissions: vec![Permission::ApproveTasks, Permission::Admin],
+        }
+    }
+}
+
+impl MaskingConfig {
+    /// Whether responses to `caller` are masked
+    pub fn applies(&self, rbac: &RbacConfig, caller: Option<&AuthContext>) -> bool {
+        let Some(caller) = caller else {
+            return false;
+        };
+        self.enabled && !self.unmasked_permissions.iter().any(|permission| rbac.is_allowed(caller, *permission))
+    }
+
+    /// `task` with the configured fields of its result and artifacts masked
+    pub fn task(&self, mut task: Task) -> Task {
+        task.status = self.status(task.status);
+        if let Some(previous) = task.artifacts.take() {
+            // Keep to the artifacts the task had, which are only those in the formats it asked for
+            let masked = artifacts::from_status(&task.status).unwrap_or_default();
+            task.artifacts = Some(masked.into_iter().filter(|artifact| previous.iter().any(|p| p.name == artifact.name)).collect());
+        }
+        task
+    }
+
+    /// `record` with its request and result masked, for exports
+    pub fn record(&self, record: &TaskRecord) -> TaskRecord {
+        let mut record = record.clone();
+        self.message(&mut record.request);
+        let status = self.status(record.task.status.clone());
+        record.set_status(status);
+        record
+    }
+
+    /// `event` with the request or status it carries masked
+    pub fn event(&self, mut event: TaskEvent) -> TaskEvent {
+        match &mut event.kind {
+            TaskEventKind::Created { request, .. } => self.message(request),
+            TaskEventKind::ChunkReceived { message } => self.message(message),
+            TaskEventKind::StateChanged { status } => *status = self.status(status.clone()),
+            _ => {}
+        }
+        event
+    }
+
+    /// Mask the before and after values of the configured fields in `diff`
+    pub fn diff(&self, diff: &mut OrderDiff) {
+        for change in &mut diff.fields {
+            let masked = self.fields.contains(&change.field);
+            for value in [&mut change.before, &mut change.after] {
+                match value {
+                    Value::String(s) if masked => *s = mask(s),
+                    value => self.value(value),
+                }
+            }
+        }
+    }
+
+    fn status(&self, mut status: TaskStatus) -> TaskStatus {
+        let Some(message) = &mut status.message else {
+            return status;
+        };
+        self.message(message);
+        // The CSV row repeats the notes, so it is rebuilt from the masked result
+        if let Some(result) = message.parts.iter().find_map(|part| match part {
+            Part::Data { data } => ProcessingResult::deserialize(data).ok(),
+            _ => None,
+        }) {
+            for part in &mut message.parts {
+                if let Part::Text { text } = part {
+                    *text = result.csv_row();
+                }
+            }
+        }
+        status
+    }
+
+    fn message(&self, message: &mut Message) {
+        for part in &mut message.parts {
+            match part {
+                Part::Data { data } => self.value(data),
+                // Orders may also be submitted as JSON text
+                Part::Text { text } => {
+                    if let Ok(mut value) = serde_json::from_str::<Value>(text) {
+                        let original = value.clone();
+                        self.value(&mut value);
+                        if value != original {
+                            *text = value.to_string();
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Mask the configured fields throughout `value`
+    pub fn value(&self, value: &mut Value) {
+        match value {
+            Value::Object(map) => {
+                for (key, value) in map.iter_mut() {
+                    match value {
+                        Value::String(s) if self.fields.contains(key) => *s = mask(s),
+                        _ => self.value(value),
+                    }
+                }
+            }
+            Value::Array(values) => values.iter_mut().for_each(|value| self.value(value)),
+            _ => {}
+        }
+    }
+}
+
+/// Answer `403` to callers whose responses are masked, for routes whose data masking cannot rewrite
+pub async fn require_unmasked(State(state): State<Arc<AppState>>, request: Request, next: Next) -> Response {
+    if state.masking.applies(&state.rbac, request.extensions().get::<AuthContext>()) {
+        let error = serde_json::json!({ "error": "This endpoint returns unmasked task data, which needs one of the unmasked permissions" });
+        return (StatusCode::FORBIDDEN, axum::Json(error)).into_response();
+    }
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::PurchaseOrderAgent;
+    use crate::auth::AuthConfig;
+    use crate::config::ServerConfig;
+    use a2a::A2AProtocol;
+    use axum::body::{to_bytes, Body};
+    use axum::http::{header, Request};
+    use jsonwebtoken::{encode, EncodingKey, Header};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_only_low_privilege_callers_get_masked_results() {
+        let agent = PurchaseOrderAgent::new();
+        let mut po = crate::testing::valid_order();
+        po.notes = Some("Call Sam on 555-0100".to_string());
+        let task = agent.send_task(crate::testing::message(&po)).await.unwrap();
+
+        let config = MaskingConfig { enabled: true, ..MaskingConfig::default() };
+        let rbac = RbacConfig::default();
+        let caller = |role: &str| AuthContext { subject: "tester".to_string(), roles: vec![role.to_string()] };
+        assert!(config.applies(&rbac, Some(&caller("submitter"))));
+        assert!(!config.applies(&rbac, Some(&caller("approver"))));
+        assert!(!config.applies(&rbac, None));
+        assert!(!MaskingConfig::default().applies(&rbac, Some(&caller("submitter"))));
+
+        let masked = serde_json::to_string(&config.task(task.clone())).unwrap();
+        assert!(!masked.contains("555-0100"), "notes leaked");
+        assert!(masked.contains("C***") && masked.contains("Acme Office Supply"));
+        let result = ProcessingResult::from_status(&config.task(task.clone()).status).unwrap();
+        assert_eq!(result.notes.as_deref(), Some("C***"));
+
+        let record = config.record(&agent.task_store().get(&task.id).unwrap().unwrap());
+        let request = serde_json::to_string(&record.request).unwrap();
+        for personal in ["555-0100", "Sam", "1 Main"] {
+            assert!(!request.contains(personal), "{} leaked", personal);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_task_responses_are_masked_for_submitters() {
+        let agent = Arc::new(PurchaseOrderAgent::new());
+        let mut po = crate::testing::valid_order();
+        po.notes = Some("Call Sam on 555-0100".to_string());
+        let task = agent.send_task(crate::testing::message(&po)).await.unwrap();
+        let config = ServerConfig {
+            auth: AuthConfig { jwt_secret: Some("masking-secret".to_string()), ..AuthConfig::default() },
+            masking: MaskingConfig { enabled: true, ..MaskingConfig::default() },
+            ..ServerConfig::default()
+        };
+        let app = crate::create_router_with_config(agent, &config);
+
+        let request = |role: &str, uri: String| {
+            let claims = serde_json::json!({ "sub": "tester", "roles": [role], "exp": chrono::Utc::now().timestamp() + 3600 });
+            let token = encode(&Header::default(), &claims, &EncodingKey::from_secret(b"masking-secret")).unwrap();
+            Request::builder().uri(uri).header(header::AUTHORIZATION, format!("Bearer {}", token)).body(Body::empty()).unwrap()
+        };
+        let get = |role: &str| request(role, format!("/agent/task/{}", task.id));
+        let submitter = app.clone().oneshot(get("submitter")).await.unwrap();
+        let body: Value = serde_json::from_slice(&to_bytes(submitter.into_body(), usize::MAX).await.unwrap()).unwrap();
+        assert_eq!(body["detailed_result"]["notes"], "C***");
+        assert!(body["csv_output"].as_str().unwrap().ends_with("\"C***\""));
+        assert!(!body.to_string().contains("555-0100"));
+
+        // Every other view of the task is masked too, or closed to submitters
+        for uri in [format!("/agent/task/{}/artifacts/0", task.id), format!("/agent/task/{}/artifacts/1", task.id), format!("/agent/task/{}/events", task.id)] {
+            let response = app.clone().oneshot(request("submitter", uri.clone())).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK, "{}", uri);
+            let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            assert!(!String::from_utf8_lossy(&body).contains("555-0100"), "notes leaked from {}", uri);
+        }
+        for uri in ["/dashboard".to_string(), "/graphql".to_string(), format!("/agent/task/{}/idoc", task.id)] {
+            let response = app.clone().oneshot(request("submitter", uri.clone())).await.unwrap();
+            assert_eq!(response.status(), StatusCode::FORBIDDEN, "{}", uri);
+        }
+        let response = app.clone().oneshot(request("approver", "/dashboard".to_string())).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let approver = app.oneshot(get("approver")).await.unwrap();
+        let body: Value = serde_json::from_slice(&to_bytes(approver.into_body(), usize::MAX).await.unwrap()).unwrap();
+        assert_eq!(body["detailed_result"]["notes"], "Call Sam on 555-0100");
+    }
+
+    #[tokio::test]
+    async fn test_orders_submitted_as_text_are_masked() {
+        let agent = PurchaseOrderAgent::new();
+        let mut po = crate::testing::valid_order();
+        po.notes = Some("Call Sam on 555-0100".to_string());
+        let text = serde_json::to_string(&crate::agent::PurchaseOrderWrapper { purchase_order: po }).unwrap();
+        let message = a2a::Message { role: "user".to_string(), parts: vec![Part::Text { text }] };
+        let task = agent.send_task(message).await.unwrap();
+
+        let config = MaskingConfig { enabled: true, ..MaskingConfig::default() };
+        let record = config.record(&agent.task_store().get(&task.id).unwrap().unwrap());
+        let created = agent.event_log().events(&task.id).unwrap().into_iter().next().unwrap();
+        for masked in [serde_json::to_string(&record.request).unwrap(), serde_json::to_string(&config.event(created)).unwrap()] {
+            for personal in ["555-0100", "Sam", "1 Main"] {
+                assert!(!masked.contains(personal), "{} leaked", personal);
+            }
+            assert!(masked.contains("Acme Office Supply"));
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+
+/// Label set attached to a metric sample, e.g. `[("state", "completed")]`
+pub type Labels<'a> = &'a [(&'a str, &'a str)];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Counter,
+    Gauge,
+}
+
+#[derive(Debug)]
+struct Family {
+    kind: Kind,
+    help: String,
+    samples: BTreeMap<String, f64>,
+}
+
+/// Minimal metrics registry rendered in the Prometheus text exposition format
+///
+/// Metric families are created on first use; the help text given on that first
+/// call is the one rendered.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    families: Mutex<BTreeMap<String, Family>>,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `by` to a counter
+    pub fn increment(&self, name: &str, help: &str, labels: Labels<'_>, by: u64) {
+        self.record(name, help, Kind::Counter, labels, |v| *v += by as f64);
+    }
+
+    /// Set a gauge to an absolute value
+    pub fn set_gauge(&self, name: &str, help: &str, labels: Labels<'_>, value: f64) {
+        self.record(name, help, Kind::Gauge, labels, |v| *v = value);
+    }
+
+    /// Current value of a sample, if it has been recorded
+    pub fn value(&self, name: &str, labels: Labels<'_>) -> Option<f64> {
+        let families = self.families.lock().unwrap_or_else(|e| e.into_inner());
+        families.get(name)?.samples.get(&label_key(labels)).copied()
+    }
+
+    fn record(&self, name: &str, help: &str, kind: Kind, labels: Labels<'_>, apply: impl FnOnce(&mut f64)) {
+        let mut families = self.families.lock().unwrap_or_else(|e| e.into_inner());
+        let family = families.entry(name.to_string()).or_insert_with(|| Family {
+            kind,
+            help: help.to_string(),
+            samples: BTreeMap::new(),
+        });
+        apply(family.samples.entry(label_key(labels)).or_insert(0.0));
+    }
+
+    /// Render every metric in the Prometheus text format
+    pub fn render(&self) -> String {
+        let families = self.families.lock().unwrap_or_else(|e| e.into_inner());
+        let mut out = String::new();
+        for (name, family) in families.iter() {
+            let kind = match family.kind {
+                Kind::Counter => "counter",
+                Kind::Gauge => "gauge",
+            };
+            let _ = writeln!(out, "# HELP {} {}", name, family.help);
+            let _ = writeln!(out, "# TYPE {} {}", name, kind);
+            for (labels, value) in &family.samples {
+                let _ = writeln!(out, "{}{} {}", name, labels, value);
+            }
+        }
+        out
+    }
+}
+
+fn label_key(labels: Labels<'_>) -> String {
+    if labels.is_empty() {
+        return String::new();
+    }
+    let pairs: Vec<String> = labels
+        .iter()
+        .map(|(k, v)| format!("{}=\"{}\"", k, v.replace('\\', "\\\\").replace('"', "\\\"")))
+        .collect();
+    format!("{{{}}}", pairs.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counters_and_gauges_render_in_prometheus_format() {
+        let metrics = Metrics::new();
+        metrics.increment("po_agent_tasks_evicted_total", "Tasks evicted", &[("state", "failed")], 2);
+        metrics.increment("po_agent_tasks_evicted_total", "Tasks evicted", &[("state", "failed")], 1);
+        metrics.set_gauge("po_agent_tasks_stored", "Tasks stored", &[], 7.0);
+
+        assert_eq!(metrics.value("po_agent_tasks_evicted_total", &[("state", "failed")]), Some(3.0));
+
+        let rendered = metrics.render();
+        assert!(rendered.contains("# TYPE po_agent_tasks_evicted_total counter"));
+        assert!(rendered.contains("po_agent_tasks_evicted_total{state=\"failed\"} 3"));
+        assert!(rendered.contains("# TYPE po_agent_tasks_stored gauge"));
+        assert!(rendered.contains("po_agent_tasks_stored 7"));
+    }
+}
@@ -0,0 +1,516 @@
+//! Month-end close report
+//!
+//! Summarizes the purchase orders processed in a calendar month (UTC): approved
+//! spend by department and by supplier, the orders still waiting for approval,
+//! and the share of orders that failed validation. The report is served by
+//! `GET /reports/monthly-close` as JSON, CSV or PDF, and can be written to a
+//! directory on a schedule.
+
+use chrono::{DateTime, Datelike, NaiveDate, TimeZone, Utc};
+use lopdf::content::{Content, Operation};
+use lopdf::{dictionary, Document, Object, Stream};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+use crate::agent::{ProcessingResult, PurchaseOrderAgent};
+use crate::approval;
+use crate::invoice::round2;
+use crate::labels::{self, Labels};
+use crate::schedule::CronSchedule;
+use crate::store::{StoreError, StoreResult, TaskRecord};
+
+/// Encoding of a close report
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    #[default]
+    Json,
+    Csv,
+    Pdf,
+}
+
+impl ReportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ReportFormat::Json => "json",
+            ReportFormat::Csv => "csv",
+            ReportFormat::Pdf => "pdf",
+        }
+    }
+
+    /// Content type used when serving a report over HTTP
+    pub fn content_type(self) -> &'static str {
+        match self {
+            ReportFormat::Json => "application/json",
+            ReportFormat::Csv => "text/csv; charset=utf-8",
+            ReportFormat::Pdf => "application/pdf",
+        }
+    }
+}
+
+/// Month-end close reports written on a schedule
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct MonthlyCloseConfig {
+    /// Directory scheduled reports are written to; unset to only generate them on request
+    pub directory: Option<PathBuf>,
+    /// When to write the report of the month before, evaluated in UTC
+    pub cron: CronSchedule,
+    /// Formats each scheduled report is written in
+    pub formats: Vec<ReportFormat>,
+    /// Suppliers listed in the top suppliers section
+    pub top_suppliers: usize,
+}
+
+impl Default for MonthlyCloseConfig {
+    fn default() -> Self {
+        Self {
+            directory: None,
+            cron: CronSchedule::parse("0 6 1 * *").expect("valid default cron expression"),
+            formats: vec![ReportFormat::Json, ReportFormat::Csv],
+            top_suppliers: 10,
+        }
+    }
+}
+
+/// A calendar month, written `YYYY-MM`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Month {
+    first_day: NaiveDate,
+}
+
+impl Month {
+    /// The month `date` falls in
+    pub fn of(date: NaiveDate) -> Self {
+        Self { first_day: date.with_day(1).expect("every month has a first day") }
+    }
+
+    /// The month before the one `at` falls in, the one being closed at `at`
+    pub fn before(at: DateTime<Utc>) -> Self {
+        Self::of(Self::of(at.date_naive()).first_day.pred_opt().expect("date in range"))
+    }
+
+    /// Midnight UTC of the first day
+    pub fn start(self) -> DateTime<Utc> {
+        Utc.from_utc_datetime(&self.first_day.and_hms_opt(0, 0, 0).expect("midnight exists"))
+    }
+
+    /// Midnight UTC of the first day of the month after
+    pub fn end(self) -> DateTime<Utc> {
+        Month::of(self.first_day + chrono::Days::new(31)).start()
+    }
+
+    pub fn contains(self, at: DateTime<Utc>) -> bool {
+        self.start() <= at && at < self.end()
+    }
+}
+
+impl FromStr for Month {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        NaiveDate::parse_from_str(&format!("{}-01", s.trim()), "%Y-%m-%d")
+            .map(Self::of)
+            .map_err(|_| format!("Invalid month '{}', expected YYYY-MM", s))
+    }
+}
+
+impl fmt::Display for Month {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.first_day.format("%Y-%m"))
+    }
+}
+
+/// Approved spend of a department or supplier
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Spend {
+    pub name: String,
+    pub orders: usize,
+    pub spend: f64,
+}
+
+/// An order processed in the month that still waits for approval
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnapprovedOrder {
+    pub task_id: String,
+    pub po_number: String,
+    pub supplier_name: String,
+    pub buyer_department: String,
+    pub grand_total: f64,
+    pub processed_at: DateTime<Utc>,
+}
+
+/// Month-end summary of the orders processed in `month`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MonthlyCloseReport {
+    /// `YYYY-MM`
+    pub month: String,
+    pub generated_at: DateTime<Utc>,
+    pub orders_processed: usize,
+    pub validation_failures: usize,
+    /// Share of processed orders that failed validation, from 0 to 1
+    pub validation_failure_rate: f64,
+    pub approved_orders: usize,
+    pub approved_spend: f64,
+    /// Highest spend first
+    pub spend_by_department: Vec<Spend>,
+    /// Highest spend first, at most `top_suppliers` of them
+    pub top_suppliers: Vec<Spend>,
+    /// Oldest first
+    pub unapproved_orders: Vec<UnapprovedOrder>,
+}
+
+impl MonthlyCloseReport {
+    /// Report on the tasks in `records` whose orders were processed in `month`
+    ///
+    /// A PO number processed several times, by amendments or reruns, counts
+    /// once with its latest result. Cached results of resubmitted orders are
+    /// left out. Amounts are summed as stated, whatever their currency.
+    pub fn from_records(records: &[TaskRecord], month: Month, top_suppliers: usize, now: DateTime<Utc>) -> Self {
+        let mut latest: HashMap<String, (&TaskRecord, ProcessingResult)> = HashMap::new();
+        for record in records {
+            let Some(result) = ProcessingResult::from_status(&record.task.status) else { continue };
+            if result.cached || !month.contains(result.processed_at) {
+                continue;
+            }
+            let key = result.po_number.trim().to_string();
+            if latest.get(&key).is_none_or(|(_, earlier)| earlier.processed_at <= result.processed_at) {
+                latest.insert(key, (record, result));
+            }
+        }
+
+        let mut departments: HashMap<String, Spend> = HashMap::new();
+        let mut suppliers: HashMap<String, Spend> = HashMap::new();
+        let mut unapproved = Vec::new();
+        let (mut validation_failures, mut approved_orders, mut approved_spend) = (0, 0, 0.0);
+        for (record, result) in latest.values() {
+            match result.status.as_str() {
+                "VALIDATION_FAILED" => validation_failures += 1,
+                "APPROVED" => {
+                    approved_orders += 1;
+                    approved_spend += result.grand_total;
+                    for (totals, name) in [(&mut departments, &result.buyer_department), (&mut suppliers, &result.supplier_name)] {
+                        let spend = totals
+                            .entry(name.trim().to_string())
+                            .or_insert_with(|| Spend { name: name.trim().to_string(), orders: 0, spend: 0.0 });
+                        spend.orders += 1;
+                        spend.spend += result.grand_total;
+                    }
+                }
+                _ if approval::is_pending(record) => unapproved.push(UnapprovedOrder {
+                    task_id: record.task.id.clone(),
+                    po_number: result.po_number.clone(),
+                    supplier_name: result.supplier_name.clone(),
+                    buyer_department: result.buyer_department.clone(),
+                    grand_total: result.grand_total,
+                    processed_at: result.processed_at,
+                }),
+                _ => {}
+            }
+        }
+        unapproved.sort_by_key(|order| order.processed_at);
+        let mut top_suppliers_by_spend = ranked(suppliers);
+        top_suppliers_by_spend.truncate(top_suppliers);
+
+        let orders_processed = latest.len();
+        Self {
+            month: month.to_string(),
+            generated_at: now,
+            orders_processed,
+            validation_failures,
+            validation_failure_rate: if orders_processed == 0 { 0.0 } else { validation_failures as f64 / orders_processed as f64 },
+            approved_orders,
+            approved_spend: round2(approved_spend),
+            spend_by_department: ranked(departments),
+            top_suppliers: top_suppliers_by_spend,
+            unapproved_orders: unapproved,
+        }
+    }
+
+    /// The report in `format`
+    pub fn render(&self, format: ReportFormat) -> Result<Vec<u8>, String> {
+        match format {
+            ReportFormat::Json => serde_json::to_vec_pretty(self).map_err(|e| e.to_string()),
+            ReportFormat::Csv => Ok(self.csv().into_bytes()),
+            ReportFormat::Pdf => pdf(&self.lines()).map_err(|e| format!("Failed to write the PDF: {}", e)),
+        }
+    }
+
+    /// One `section,name,orders,amount` row per figure
+    fn csv(&self) -> String {
+        let mut out = String::from("section,name,orders,amount\n");
+        let mut row = |section: &str, name: &str, orders: usize, amount: String| {
+            out.push_str(&format!("{},{},{},{}\n", section, csv_field(name), orders, amount));
+        };
+        row("summary", "processed", self.orders_processed, String::new());
+        row("summary", "validation_failures", self.validation_failures, format!("{:.4}", self.validation_failure_rate));
+        row("summary", "approved_spend", self.approved_orders, format!("{:.2}", self.approved_spend));
+        for spend in &self.spend_by_department {
+            row("department", &spend.name, spend.orders, format!("{:.2}", spend.spend));
+        }
+        for spend in &self.top_suppliers {
+            row("supplier", &spend.name, spend.orders, format!("{:.2}", spend.spend));
+        }
+        for order in &self.unapproved_orders {
+            row("unapproved", &order.po_number, 1, format!("{:.2}", order.grand_total));
+        }
+        out
+    }
+
+    /// The report as lines of text, for the PDF
+    fn lines(&self) -> Vec<String> {
+        let mut lines = vec![
+            format!("Monthly close {}", self.month),
+            format!("Generated {}", self.generated_at.format("%Y-%m-%d %H:%M UTC")),
+            String::new(),
+            format!("Orders processed: {}", self.orders_processed),
+            format!(
+                "Validation failures: {} ({:.1}%)",
+                self.validation_failures,
+                self.validation_failure_rate * 100.0
+            ),
+            format!("Approved spend: {:.2} over {} orders", self.approved_spend, self.approved_orders),
+        ];
+        for (title, spends) in [("Spend by department", &self.spend_by_department), ("Top suppliers", &self.top_suppliers)] {
+            lines.push(String::new());
+            lines.push(title.to_string());
+            lines.extend(spends.iter().map(|s| format!("    {}: {:.2} over {} orders", s.name, s.spend, s.orders)));
+        }
+        lines.push(String::new());
+        lines.push(format!("Awaiting approval: {}", self.unapproved_orders.len()));
+        lines.extend(self.unapproved_orders.iter().map(|order| {
+            format!("    {} {} ({}): {:.2}", order.po_number, order.supplier_name, order.buyer_department, order.grand_total)
+        }));
+        lines
+    }
+}
+
+/// Highest spend first, then by name
+fn ranked(totals: HashMap<String, Spend>) -> Vec<Spend> {
+    let mut spends: Vec<Spend> = totals.into_values().map(|s| Spend { spend: round2(s.spend), ..s }).collect();
+    spends.sort_by(|a, b| b.spend.total_cmp(&a.spend).then_with(|| a.name.cmp(&b.name)));
+    spends
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// A plain PDF of `lines` in 10pt Helvetica, 60 lines to a US Letter page
+///
+/// Characters outside ASCII are printed as `?`.
+fn pdf(lines: &[String]) -> lopdf::Result<Vec<u8>> {
+    const LINES_PER_PAGE: usize = 60;
+    let pages: Vec<&[String]> = if lines.is_empty() { vec![&[]] } else { lines.chunks(LINES_PER_PAGE).collect() };
+
+    let mut doc = Document::with_version("1.4");
+    let pages_id = doc.new_object_id();
+    let font_id = doc.add_object(dictionary! { "Type" => "Font", "Subtype" => "Type1", "BaseFont" => "Helvetica" });
+    let mut kids = Vec::with_capacity(pages.len());
+    for page_lines in pages {
+        let mut operations = vec![
+            Operation::new("BT", vec![]),
+            Operation::new("Tf", vec!["F1".into(), 10.into()]),
+            Operation::new("TL", vec![12.into()]),
+            Operation::new("Td", vec![50.into(), 750.into()]),
+        ];
+        for line in page_lines {
+            let text: String = line.chars().map(|c| if c.is_ascii() && !c.is_ascii_control() { c } else { '?' }).collect();
+            operations.push(Operation::new("Tj", vec![Object::string_literal(text)]));
+            operations.push(Operation::new("T*", vec![]));
+        }
+        operations.push(Operation::new("ET", vec![]));
+        let content_id = doc.add_object(Stream::new(dictionary! {}, Content { operations }.encode()?));
+        kids.push(Object::from(doc.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+            "Resources" => dictionary! { "Font" => dictionary! { "F1" => font_id } },
+            "Contents" => content_id,
+        })));
+    }
+    let count = kids.len() as i64;
+    doc.objects.insert(pages_id, Object::Dictionary(dictionary! { "Type" => "Pages", "Kids" => kids, "Count" => count }));
+    let catalog_id = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+    doc.trailer.set("Root", catalog_id);
+
+    let mut out = Vec::new();
+    doc.save_to(&mut out)?;
+    Ok(out)
+}
+
+/// The close report of `month` from the agent's task store
+pub fn generate(agent: &PurchaseOrderAgent, month: Month, top_suppliers: usize, selector: &Labels) -> StoreResult<MonthlyCloseReport> {
+    let mut records = agent.task_store().list()?;
+    records.retain(|record| labels::matches(&record.labels, selector));
+    Ok(MonthlyCloseReport::from_records(&records, month, top_suppliers, Utc::now()))
+}
+
+/// Write the report to `directory` as `monthly-close-YYYY-MM.<ext>` in each format
+///
+/// Returns the files written.
+pub fn write(report: &MonthlyCloseReport, directory: &Path, formats: &[ReportFormat]) -> StoreResult<Vec<PathBuf>> {
+    let io_error = |path: &Path, e: std::io::Error| StoreError(format!("Failed to write report {}: {}", path.display(), e));
+    std::fs::create_dir_all(directory).map_err(|e| io_error(directory, e))?;
+    formats
+        .iter()
+        .map(|format| {
+            let path = directory.join(format!("monthly-close-{}.{}", report.month, format.extension()));
+            let content = report.render(*format).map_err(|e| StoreError(format!("Failed to render report {}: {}", path.display(), e)))?;
+            std::fs::write(&path, content).map_err(|e| io_error(&path, e))?;
+            Ok(path)
+        })
+        .collect()
+}
+
+/// Write the report of the month before whenever the schedule fires, when a directory is configured
+pub fn spawn_scheduler(agent: Arc<PurchaseOrderAgent>, config: MonthlyCloseConfig) -> Option<JoinHandle<()>> {
+    let directory = config.directory.clone()?;
+    info!(directory = %directory.display(), cron = %config.cron, "monthly close reports scheduled");
+
+    Some(tokio::spawn(async move {
+        let mut next = config.cron.next_after(Utc::now());
+        while let Some(due) = next {
+            // Wake at least every minute so clock adjustments are picked up
+            let wait = (due - Utc::now()).to_std().unwrap_or_default().min(Duration::from_secs(60));
+            tokio::time::sleep(wait).await;
+            let now = Utc::now();
+            if due > now {
+                continue;
+            }
+
+            let month = Month::before(due);
+            let outcome = match generate(&agent, month, config.top_suppliers, &Labels::new())
+                .and_then(|report| write(&report, &directory, &config.formats))
+            {
+                Ok(files) => {
+                    info!(%month, files = files.len(), "monthly close report written");
+                    "written"
+                }
+                Err(e) => {
+                    warn!(%month, error = %e, "monthly close report failed");
+                    "error"
+                }
+            };
+            agent.metrics().increment(
+                "po_agent_monthly_close_reports_total",
+                "Monthly close reports written by the scheduler",
+                &[("outcome", outcome)],
+                1,
+            );
+            next = config.cron.next_after(now);
+        }
+        warn!("monthly close schedule will not fire again; scheduler stopping");
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use a2a::A2AProtocol;
+    use axum::body::Body;
+    use axum::http::{header, Request, StatusCode};
+    use tower::ServiceExt;
+
+    #[test]
+    fn test_month_bounds() {
+        let month: Month = "2026-02".parse().unwrap();
+        assert!(month.contains("2026-02-28T23:59:59Z".parse().unwrap()));
+        assert!(!month.contains("2026-03-01T00:00:00Z".parse().unwrap()));
+        assert_eq!(Month::before("2026-01-01T06:00:00Z".parse().unwrap()).to_string(), "2025-12");
+        assert!("2026-13".parse::<Month>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_report_summarizes_the_month_in_every_format() {
+        let agent = Arc::new(PurchaseOrderAgent::new());
+        for (po_number, department, approved, supplier) in [
+            ("PO-1", "Marketing", true, "Acme"),
+            ("PO-2", "IT", true, "Globex"),
+            ("PO-3", "IT", true, "Globex"),
+            ("PO-4", "IT", false, "Globex"),
+        ] {
+            let mut po = crate::testing::valid_order();
+            po.po_number = po_number.to_string();
+            po.buyer_department = department.to_string();
+            po.supplier_name = supplier.to_string();
+            po.is_approved = approved;
+            agent.send_task(crate::testing::message(&po)).await.unwrap();
+        }
+        let mut invalid = crate::testing::valid_order();
+        invalid.po_number = "PO-5".to_string();
+        invalid.items.clear();
+        agent.send_task(crate::testing::message(&invalid)).await.unwrap();
+        // A resubmission of the same order does not count twice
+        let mut again = crate::testing::valid_order();
+        again.po_number = "PO-1".to_string();
+        again.supplier_name = "Acme".to_string();
+        agent.send_task(crate::testing::message(&again)).await.unwrap();
+
+        let report = generate(&agent, Month::of(Utc::now().date_naive()), 1, &Labels::new()).unwrap();
+        let total = crate::testing::valid_order().grand_total;
+        assert_eq!((report.orders_processed, report.validation_failures, report.approved_orders), (5, 1, 3));
+        assert_eq!(report.validation_failure_rate, 0.2);
+        assert_eq!(report.approved_spend, round2(3.0 * total));
+        let departments: Vec<_> = report.spend_by_department.iter().map(|s| (s.name.as_str(), s.orders)).collect();
+        assert_eq!(departments, [("IT", 2), ("Marketing", 1)]);
+        assert_eq!(report.top_suppliers.iter().map(|s| s.name.as_str()).collect::<Vec<_>>(), ["Globex"]);
+        assert_eq!(report.unapproved_orders.iter().map(|o| o.po_number.as_str()).collect::<Vec<_>>(), ["PO-4"]);
+
+        let csv = String::from_utf8(report.render(ReportFormat::Csv).unwrap()).unwrap();
+        assert!(csv.contains(&format!("department,IT,2,{:.2}\n", 2.0 * total)), "{}", csv);
+        assert!(csv.contains("summary,validation_failures,1,0.2000\n"));
+        let pdf = lopdf::Document::load_mem(&report.render(ReportFormat::Pdf).unwrap()).unwrap();
+        assert_eq!(pdf.get_pages().len(), 1);
+        assert!(pdf.extract_text(&[1]).unwrap().contains("Awaiting approval: 1"));
+
+        let router = crate::server::create_router(agent.clone());
+        let month = Month::of(Utc::now().date_naive());
+        let response = router
+            .clone()
+            .oneshot(Request::get(format!("/reports/monthly-close?month={}&format=csv", month)).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "text/csv; charset=utf-8");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert!(body.starts_with(b"section,name,orders,amount\n"));
+        let response = router.clone().oneshot(Request::get("/reports/monthly-close?month=June").body(Body::empty()).unwrap()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let mut labelled = crate::testing::valid_order();
+        labelled.po_number = "PO-6".to_string();
+        let options = crate::agent::TaskOptions { labels: [("source".to_string(), "sap".to_string())].into(), ..Default::default() };
+        agent.send_task_with_options(crate::testing::message(&labelled), &options).await.unwrap();
+        let selector = crate::labels::parse_selector("source:sap").unwrap();
+        let report = generate(&agent, month, 1, &selector).unwrap();
+        assert_eq!(report.orders_processed, 1);
+        assert_eq!(report.spend_by_department.iter().map(|s| s.name.as_str()).collect::<Vec<_>>(), ["Marketing"]);
+        let query = format!("/reports/monthly-close?month={}&labels=source", month);
+        let response = router.oneshot(Request::get(query).body(Body::empty()).unwrap()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn test_pdf_text_reads_back_across_pages() {
+        let mut lines = vec!["Supplier: Acme (Pty) \\ Ltd) (unbalanced".to_string(), "Caf\u{e9} Nord".to_string()];
+        lines.extend((0..70).map(|n| format!("line {}", n)));
+        let pdf = lopdf::Document::load_mem(&pdf(&lines).unwrap()).unwrap();
+
+        assert_eq!(pdf.get_pages().len(), 2);
+        let first = pdf.extract_text(&[1]).unwrap();
+        assert!(first.contains("Supplier: Acme (Pty) \\ Ltd) (unbalanced"), "{}", first);
+        assert!(first.contains("Caf? Nord"), "{}", first);
+        assert!(pdf.extract_text(&[2]).unwrap().contains("line 69"));
+    }
+}
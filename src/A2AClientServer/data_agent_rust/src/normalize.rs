@@ -0,0 +1,208 @@
+use a2a::{Message, Part};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use crate::agent::PurchaseOrder;
+use crate::invoice::round2;
+use crate::rounding::ReconciliationConfig;
+
+/// A computed amount that was corrected when normalizing an order
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Adjustment {
+    /// Camel-cased path of the amount, e.g. `items[0].lineTotal` or `grandTotal`
+    pub field: String,
+    pub from: f64,
+    pub to: f64,
+}
+
+/// Whether a message asks for normalization in a Data part's `normalize` field
+///
+/// The field sits next to the purchase order, e.g.
+/// `{"normalize": true, "purchaseOrder": {…}}`.
+pub fn requested(message: &Message) -> bool {
+    message.parts.iter().any(|part| match part {
+        Part::Data { data } => data.get("normalize").and_then(|v| v.as_bool()).unwrap_or(false),
+        _ => false,
+    })
+}
+
+/// Whether a message asks for duplicate lines to be merged in a Data part's `mergeDuplicates` field
+///
+/// Merging only happens together with normalization, e.g.
+/// `{"normalize": true, "mergeDuplicates": true, "purchaseOrder": {…}}`.
+pub fn merge_requested(message: &Message) -> bool {
+    message.parts.iter().any(|part| match part {
+        Part::Data { data } => data.get("mergeDuplicates").and_then(|v| v.as_bool()).unwrap_or(false),
+        _ => false,
+    })
+}
+
+/// Lines that repeat an earlier line's item code, description and unit price, as
+/// `(line, earlier line)` index pairs
+///
+/// Codes and descriptions are compared trimmed and case-insensitively, and unit
+/// prices to the cent; lines without an item code are never duplicates.
+pub fn duplicate_lines(po: &PurchaseOrder) -> Vec<(usize, usize)> {
+    let mut first: HashMap<(String, String, i64), usize> = HashMap::new();
+    let mut duplicates = Vec::new();
+    for (index, item) in po.items.iter().enumerate() {
+        if item.item_code.trim().is_empty() {
+            continue;
+        }
+        let key = (
+            item.item_code.trim().to_ascii_lowercase(),
+            item.description.trim().to_ascii_lowercase(),
+            (item.unit_price * 100.0).round() as i64,
+        );
+        match first.get(&key) {
+            Some(&earlier) => duplicates.push((index, earlier)),
+            None => {
+                first.insert(key, index);
+            }
+        }
+    }
+    duplicates
+}
+
+/// Fold duplicate lines into the first line they repeat, adding up quantities and
+/// line totals
+///
+/// Returns the merged order and the quantity of each line that absorbed others;
+/// those fields name lines by their position in the submitted order.
+pub fn merge_duplicates(po: &PurchaseOrder) -> (PurchaseOrder, Vec<Adjustment>) {
+    let duplicates = duplicate_lines(po);
+    let mut merged = po.clone();
+    for &(index, earlier) in &duplicates {
+        merged.items[earlier].quantity += po.items[index].quantity;
+        merged.items[earlier].line_total = round2(merged.items[earlier].line_total + po.items[index].line_total);
+    }
+    let mut adjustments = Vec::new();
+    for (index, item) in merged.items.iter().enumerate() {
+        if item.quantity != po.items[index].quantity {
+            adjustments.push(Adjustment {
+                field: format!("items[{}].quantity", index),
+                from: po.items[index].quantity as f64,
+                to: item.quantity as f64,
+            });
+        }
+    }
+    let removed: HashSet<usize> = duplicates.iter().map(|&(duplicate, _)| duplicate).collect();
+    let mut index = 0;
+    merged.items.retain(|_| {
+        index += 1;
+        !removed.contains(&(index - 1))
+    });
+    (merged, adjustments)
+}
+
+/// Recompute line totals, subtotal, tax and grand total from quantities, unit prices
+/// and the tax rate, rounded to cents the way `reconciliation` says
+///
+/// Returns the corrected order and the amounts that changed; an order whose
+/// totals are already right comes back unchanged with no adjustments.
+pub fn normalize(po: &PurchaseOrder, reconciliation: &ReconciliationConfig) -> (PurchaseOrder, Vec<Adjustment>) {
+    let mut normalized = po.clone();
+    let mut adjustments = Vec::new();
+    // Any difference counts, even one within the tolerance of the checks: a
+    // stated 10.004 is not the 10.00 a zero tolerance expects
+    let mut set = |field: String, value: &mut f64, to: f64| {
+        if (*value - to).abs() > 1e-9 {
+            adjustments.push(Adjustment { field, from: *value, to });
+            *value = to;
+        }
+    };
+
+    for (index, item) in normalized.items.iter_mut().enumerate() {
+        let line_total = reconciliation.line_total(item);
+        set(format!("items[{}].lineTotal", index), &mut item.line_total, line_total);
+    }
+    let sub_total = reconciliation.round(normalized.items.iter().map(|item| item.line_total).sum());
+    set("subTotal".to_string(), &mut normalized.sub_total, sub_total);
+    let tax = reconciliation.tax(&normalized);
+    set("tax".to_string(), &mut normalized.tax, tax);
+    let grand_total = reconciliation.round(normalized.sub_total + normalized.tax);
+    set("grandTotal".to_string(), &mut normalized.grand_total, grand_total);
+
+    (normalized, adjustments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::{ProcessingResult, PurchaseOrderAgent};
+    use a2a::A2AProtocol;
+
+    /// 3 × 4.99 stated as 15.00, with totals that follow the stated line
+    fn po() -> PurchaseOrder {
+        let mut po = crate::testing::order_of(0.1, &[("P-1", "Paper", 3, 4.99), ("T-2", "Toner", 1, 10.0)]);
+        po.items[0].line_total = 15.0;
+        (po.sub_total, po.tax, po.grand_total) = (25.0, 2.5, 27.0);
+        po
+    }
+
+    #[test]
+    fn test_normalize_recomputes_totals() {
+        let (normalized, adjustments) = normalize(&po(), &ReconciliationConfig::default());
+        let fields: Vec<&str> = adjustments.iter().map(|a| a.field.as_str()).collect();
+        assert_eq!(fields, ["items[0].lineTotal", "subTotal", "grandTotal"]);
+        assert_eq!(adjustments[0], Adjustment { field: "items[0].lineTotal".to_string(), from: 15.0, to: 14.97 });
+        assert_eq!((normalized.sub_total, normalized.tax, normalized.grand_total), (24.97, 2.5, 27.47));
+
+        let (_, again) = normalize(&normalized, &ReconciliationConfig::default());
+        assert!(again.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_normalize_option_returns_corrected_order() {
+        let agent = PurchaseOrderAgent::new();
+        let submit = |normalize| Message {
+            role: "user".to_string(),
+            parts: vec![Part::Data { data: serde_json::json!({ "normalize": normalize, "purchaseOrder": po() }) }],
+        };
+
+        let task = agent.send_task(submit(false)).await.unwrap();
+        let result = ProcessingResult::from_status(&task.status).unwrap();
+        assert!(!result.warnings.is_empty());
+        assert!(result.normalized_order.is_none());
+
+        let task = agent.send_task(submit(true)).await.unwrap();
+        let result = ProcessingResult::from_status(&task.status).unwrap();
+        assert!(result.warnings.iter().all(|w| !w.contains("mismatch")), "{:?}", result.warnings);
+        assert_eq!(result.adjustments.len(), 3);
+        assert_eq!(result.grand_total, 27.47);
+        assert_eq!(result.normalized_order.unwrap().items[0].line_total, 14.97);
+        assert_eq!(agent.find_purchase_order("PO-1").unwrap().unwrap().1.grand_total, 27.47);
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_lines_are_flagged_and_merged_on_request() {
+        let mut order = serde_json::to_value(po()).unwrap();
+        order["items"] = serde_json::json!([
+            { "itemCode": "P-1", "description": "Paper", "quantity": 3, "unitPrice": 4.99, "lineTotal": 14.97 },
+            { "itemCode": "T-2", "description": "Toner", "quantity": 1, "unitPrice": 10.0, "lineTotal": 10.0 },
+            { "itemCode": "p-1 ", "description": "paper", "quantity": 2, "unitPrice": 4.99, "lineTotal": 9.98 },
+            { "itemCode": "P-1", "description": "Paper", "quantity": 1, "unitPrice": 5.49, "lineTotal": 5.49 }
+        ]);
+        let parsed: PurchaseOrder = serde_json::from_value(order.clone()).unwrap();
+        assert_eq!(duplicate_lines(&parsed), [(2, 0)]);
+
+        let agent = PurchaseOrderAgent::new();
+        let submit = |merge| Message {
+            role: "user".to_string(),
+            parts: vec![Part::Data { data: serde_json::json!({ "normalize": true, "mergeDuplicates": merge, "purchaseOrder": order.clone() }) }],
+        };
+
+        let task = agent.send_task(submit(false)).await.unwrap();
+        let result = ProcessingResult::from_status(&task.status).unwrap();
+        assert!(result.warnings.iter().any(|w| w.contains("Item 3 duplicates item 1")), "{:?}", result.warnings);
+
+        let task = agent.send_task(submit(true)).await.unwrap();
+        let result = ProcessingResult::from_status(&task.status).unwrap();
+        assert!(result.warnings.iter().all(|w| !w.contains("duplicates")), "{:?}", result.warnings);
+        assert_eq!(result.adjustments[0], Adjustment { field: "items[0].quantity".to_string(), from: 3.0, to: 5.0 });
+        let merged = result.normalized_order.unwrap();
+        let lines: Vec<(&str, u32)> = merged.items.iter().map(|item| (item.item_code.as_str(), item.quantity)).collect();
+        assert_eq!(lines, [("P-1", 5), ("T-2", 1), ("P-1", 1)]);
+        assert_eq!((merged.items[0].line_total, merged.sub_total), (24.95, 40.44));
+    }
+}
@@ -0,0 +1,93 @@
+use axum::{
+    body::Body,
+    http::{HeaderName, Request, Response},
+    Router,
+};
+use std::time::Duration;
+use tower::ServiceBuilder;
+use tower_http::{
+    request_id::{MakeRequestUuid, PropagateRequestIdLayer, RequestId, SetRequestIdLayer},
+    trace::TraceLayer,
+};
+use tracing::{info, info_span, warn, Span};
+
+/// Header used to correlate a request across agents and log lines
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Wrap a router with request ID propagation and structured access logging
+///
+/// An incoming `x-request-id` is kept, otherwise a UUID is assigned. Every
+/// tracing event emitted while handling the request is recorded inside a
+/// `request` span carrying that ID, and the ID is echoed in the response.
+pub fn with_request_tracing(router: Router) -> Router {
+    let header = HeaderName::from_static(REQUEST_ID_HEADER);
+
+    router.layer(
+        ServiceBuilder::new()
+            .layer(SetRequestIdLayer::new(header.clone(), MakeRequestUuid))
+            .layer(
+                TraceLayer::new_for_http()
+                    .make_span_with(make_span)
+                    .on_request(())
+                    .on_response(on_response),
+            )
+            .layer(PropagateRequestIdLayer::new(header)),
+    )
+}
+
+fn make_span(request: &Request<Body>) -> Span {
+    let request_id = request
+        .extensions()
+        .get::<RequestId>()
+        .and_then(|id| id.header_value().to_str().ok())
+        .unwrap_or("-");
+
+    info_span!(
+        "request",
+        request_id = %request_id,
+        method = %request.method(),
+        path = %request.uri().path(),
+    )
+}
+
+fn on_response(response: &Response<Body>, latency: Duration, _span: &Span) {
+    let status = response.status().as_u16();
+    let latency_ms = latency.as_millis() as u64;
+
+    if response.status().is_server_error() || response.status().is_client_error() {
+        warn!(status, latency_ms, "request completed with error status");
+    } else {
+        info!(status, latency_ms, "request completed");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::routing::get;
+    use tower::ServiceExt;
+
+    fn router() -> Router {
+        with_request_tracing(Router::new().route("/ping", get(|| async { "pong" })))
+    }
+
+    #[tokio::test]
+    async fn test_incoming_request_id_is_echoed() {
+        let request = Request::get("/ping")
+            .header(REQUEST_ID_HEADER, "abc-123")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = router().oneshot(request).await.unwrap();
+        assert_eq!(response.headers()[REQUEST_ID_HEADER], "abc-123");
+    }
+
+    #[tokio::test]
+    async fn test_request_id_is_generated_when_missing() {
+        let request = Request::get("/ping").body(Body::empty()).unwrap();
+
+        let response = router().oneshot(request).await.unwrap();
+        let id = response.headers()[REQUEST_ID_HEADER].to_str().unwrap();
+        assert!(uuid::Uuid::parse_str(id).is_ok());
+    }
+}
@@ -0,0 +1,455 @@
+//! Supplier onboarding skill
+//!
+//! A new supplier is checked before it joins the supplier master data: its
+//! address by the rules of its country, its tax ID as on purchase orders, its
+//! bank account (IBAN and BIC, or a US routing and account number), and whether
+//! it duplicates a supplier already in the store by name, tax ID, bank account
+//! or address. Failed checks are [`Check`]s under the severity policy. With
+//! `register` on, suppliers that pass are added to the supplier store, so
+//! requisitions can be converted against them straight away.
+
+use a2a::{Message, Part, TaskState, TaskStatus};
+use async_trait::async_trait;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::sync::LazyLock;
+
+use crate::address::{self, country_code};
+use crate::agent::PurchaseOrderAgent;
+use crate::catalog::{Supplier, SupplierStore};
+use crate::parts::{self, PartSchema};
+use crate::severity::{Check, Findings, SeverityPolicy};
+use crate::skills::SkillHandler;
+use crate::store::StoreResult;
+use crate::tax_id::{self, TaxIdConfig, TaxIdFinding};
+
+/// Skill validating new supplier records
+pub const ONBOARDING_SKILL: &str = "supplier-onboarding";
+
+/// Bank account a supplier is paid into
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BankAccount {
+    /// Name the account is held in
+    pub holder: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iban: Option<String>,
+    /// SWIFT code of the bank
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bic: Option<String>,
+    /// ABA routing number of a US bank
+    #[serde(default, alias = "routing_number", skip_serializing_if = "Option::is_none")]
+    pub routing_number: Option<String>,
+    #[serde(default, alias = "account_number", skip_serializing_if = "Option::is_none")]
+    pub account_number: Option<String>,
+}
+
+impl BankAccount {
+    /// The IBAN, or the US routing and account number, without separators
+    fn identity(&self) -> Option<String> {
+        match (&self.iban, &self.routing_number, &self.account_number) {
+            (Some(iban), _, _) => Some(compact(iban)),
+            (None, Some(routing), Some(account)) => Some(format!("{}:{}", compact(routing), compact(account))),
+            _ => None,
+        }
+    }
+}
+
+/// A supplier applying to be onboarded
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SupplierApplication {
+    pub name: String,
+    pub address_line1: String,
+    #[serde(default)]
+    pub address_line2: Option<String>,
+    pub city: String,
+    #[serde(default)]
+    pub state: String,
+    pub postal_code: String,
+    pub country: String,
+    /// VAT number or EIN; required of international suppliers
+    #[serde(default)]
+    pub tax_id: Option<String>,
+    #[serde(default)]
+    pub bank_account: Option<BankAccount>,
+}
+
+impl SupplierApplication {
+    /// The supplier master data record of the application
+    pub fn to_supplier(&self) -> Supplier {
+        Supplier {
+            name: self.name.trim().to_string(),
+            address_line1: self.address_line1.clone(),
+            address_line2: self.address_line2.clone(),
+            city: self.city.clone(),
+            state: self.state.clone(),
+            postal_code: self.postal_code.clone(),
+            country: self.country.clone(),
+            tax_id: self.tax_id.clone(),
+            bank_account: self.bank_account.clone(),
+        }
+    }
+}
+
+/// Wrapper for the incoming supplier application data
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SupplierApplicationWrapper {
+    pub supplier: SupplierApplication,
+}
+
+/// How new suppliers are onboarded
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct OnboardingConfig {
+    /// Whether a supplier without a bank account fails onboarding
+    pub require_bank_account: bool,
+    /// Add suppliers that pass to the supplier store
+    pub register: bool,
+}
+
+impl Default for OnboardingConfig {
+    fn default() -> Self {
+        Self {
+            require_bank_account: true,
+            register: true,
+        }
+    }
+}
+
+/// Outcome of onboarding a supplier
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OnboardingReport {
+    pub supplier_name: String,
+    pub valid: bool,
+    pub validation_errors: Vec<String>,
+    pub warnings: Vec<String>,
+    /// Problems with the tax ID, also reported as errors or warnings
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tax_id_findings: Vec<TaxIdFinding>,
+    /// Suppliers in the store the new one duplicates or may duplicate
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub duplicates: Vec<String>,
+    /// Whether the supplier was added to the supplier store
+    pub registered: bool,
+}
+
+/// IBAN length of the countries it is checked for
+const IBAN_LENGTHS: [(&str, usize); 20] = [
+    ("AT", 20), ("BE", 16), ("CH", 21), ("DE", 22), ("DK", 18), ("ES", 24), ("FI", 18),
+    ("FR", 27), ("GB", 22), ("GR", 27), ("IE", 22), ("IT", 27), ("LU", 20), ("NL", 18),
+    ("NO", 15), ("PL", 28), ("PT", 25), ("SE", 24), ("SI", 19), ("SK", 24),
+];
+
+static IBAN: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^[A-Z]{2}\d{2}[A-Z0-9]{11,30}$").expect("valid IBAN pattern"));
+static BIC: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[A-Z]{4}[A-Z]{2}[A-Z0-9]{2}(?:[A-Z0-9]{3})?$").expect("valid BIC pattern"));
+
+/// Upper-cased letters and digits of a code, without spaces, dots or dashes
+fn compact(value: &str) -> String {
+    value.chars().filter(char::is_ascii_alphanumeric).collect::<String>().to_ascii_uppercase()
+}
+
+/// Whether an IBAN's check digits are right (ISO 13616 mod 97)
+fn iban_checksum_ok(iban: &str) -> bool {
+    let (head, tail) = iban.split_at(4);
+    let mut remainder = 0u32;
+    for c in tail.chars().chain(head.chars()) {
+        let Some(value) = c.to_digit(36) else { return false };
+        remainder = if value >= 10 { (remainder * 100 + value) % 97 } else { (remainder * 10 + value) % 97 };
+    }
+    remainder == 1
+}
+
+/// Whether a US routing number has nine digits and a valid ABA check digit
+fn routing_number_ok(routing: &str) -> bool {
+    let digits: Vec<u32> = routing.chars().filter_map(|c| c.to_digit(10)).collect();
+    if digits.len() != 9 || routing.len() != 9 {
+        return false;
+    }
+    let weighted: u32 = digits.iter().zip([3, 7, 1].iter().cycle()).map(|(digit, weight)| digit * weight).sum();
+    weighted.is_multiple_of(10)
+}
+
+/// Check a bank account's format and that it is in the supplier's country
+pub fn validate_bank_account(account: &BankAccount, country: &str, findings: &mut Findings) {
+    if account.holder.trim().is_empty() {
+        findings.push(Check::BankDetails, "Bank account holder is required");
+    }
+    let supplier_country = country_code(country);
+    if let Some(iban) = &account.iban {
+        let iban = compact(iban);
+        let length = IBAN_LENGTHS.iter().find(|(code, _)| iban.starts_with(code)).map(|(_, length)| *length);
+        let valid = IBAN.is_match(&iban) && length.is_none_or(|length| iban.len() == length) && iban_checksum_ok(&iban);
+        if !valid {
+            findings.push(Check::BankDetails, format!("IBAN '{}' is not valid", iban));
+        } else if let Some(code) = supplier_country
+            && !iban.starts_with(code)
+        {
+            findings.push(Check::BankCountry, format!("IBAN is for {} but the supplier is in {}", &iban[..2], code));
+        }
+        if let Some(bic) = &account.bic {
+            let bic = compact(bic);
+            if !BIC.is_match(&bic) {
+                findings.push(Check::BankDetails, format!("BIC '{}' is not valid", bic));
+            } else if valid && bic[4..6] != iban[..2] {
+                findings.push(Check::BankDetails, format!("BIC {} is for {} but the IBAN is for {}", bic, &bic[4..6], &iban[..2]));
+            }
+        }
+        return;
+    }
+    match (&account.routing_number, &account.account_number) {
+        (Some(routing), Some(number)) => {
+            if !routing_number_ok(routing.trim()) {
+                findings.push(Check::BankDetails, format!("Routing number '{}' is not a valid ABA routing number", routing));
+            }
+            let number = number.trim();
+            if !(4..=17).contains(&number.len()) || !number.chars().all(|c| c.is_ascii_digit()) {
+                findings.push(Check::BankDetails, "Account number must be 4 to 17 digits");
+            }
+            if let Some(code) = supplier_country
+                && code != "US"
+            {
+                findings.push(Check::BankCountry, format!("Bank account is a US account but the supplier is in {}", code));
+            }
+        }
+        _ => findings.push(Check::BankDetails, "Bank account needs an IBAN, or a routing number and an account number"),
+    }
+}
+
+/// Legal form suffixes left out when comparing supplier names
+const LEGAL_FORMS: [&str; 14] =
+    ["inc", "llc", "ltd", "limited", "corp", "corporation", "co", "company", "gmbh", "ag", "sa", "bv", "plc", "sarl"];
+
+/// A supplier name without case, punctuation or legal form
+fn name_stem(name: &str) -> String {
+    let lowered = name.to_lowercase();
+    let words: Vec<&str> = lowered
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty() && !LEGAL_FORMS.contains(word))
+        .collect();
+    words.join(" ")
+}
+
+/// Flag suppliers in `suppliers` that `application` duplicates, returning their names
+///
+/// The same name, tax ID or bank account is a duplicate; a name differing only
+/// in case, punctuation or legal form, or the same street address and postal
+/// code, is a possible one.
+pub fn find_duplicates(
+    suppliers: &dyn SupplierStore,
+    application: &SupplierApplication,
+    findings: &mut Findings,
+) -> StoreResult<Vec<String>> {
+    let name = application.name.trim();
+    let tax_id = application.tax_id.as_deref().map(compact).filter(|id| !id.is_empty());
+    let account = application.bank_account.as_ref().and_then(BankAccount::identity);
+    let address = (compact(&application.address_line1), compact(&application.postal_code));
+
+    let mut duplicates = Vec::new();
+    for existing in suppliers.list()? {
+        let mut duplicate = |check, message: String| {
+            findings.push(check, message);
+            if !duplicates.contains(&existing.name) {
+                duplicates.push(existing.name.clone());
+            }
+        };
+        if existing.name.trim().eq_ignore_ascii_case(name) {
+            duplicate(Check::DuplicateSupplier, format!("Supplier '{}' already exists", existing.name));
+        } else if name_stem(&existing.name) == name_stem(name) {
+            duplicate(Check::PossibleDuplicateSupplier, format!("Supplier name '{}' is close to existing supplier '{}'", name, existing.name));
+        }
+        if tax_id.is_some() && existing.tax_id.as_deref().map(compact) == tax_id {
+            duplicate(Check::DuplicateSupplier, format!("Tax ID is already registered to supplier '{}'", existing.name));
+        }
+        if account.is_some() && existing.bank_account.as_ref().and_then(BankAccount::identity) == account {
+            duplicate(Check::DuplicateSupplier, format!("Bank account is already registered to supplier '{}'", existing.name));
+        }
+        if !address.0.is_empty()
+            && (compact(&existing.address_line1), compact(&existing.postal_code)) == address
+            && !existing.name.trim().eq_ignore_ascii_case(name)
+        {
+            duplicate(Check::PossibleDuplicateSupplier, format!("Supplier address is the address of supplier '{}'", existing.name));
+        }
+    }
+    Ok(duplicates)
+}
+
+/// Check a supplier application, without registering it
+pub fn review(
+    application: &SupplierApplication,
+    suppliers: &dyn SupplierStore,
+    config: &OnboardingConfig,
+    tax_ids: &TaxIdConfig,
+    severity: &SeverityPolicy,
+) -> StoreResult<OnboardingReport> {
+    let mut findings = Findings::new(severity);
+
+    if application.name.trim().is_empty() {
+        findings.push(Check::MissingSupplierName, "Supplier name is required");
+    }
+    if application.address_line1.trim().is_empty() {
+        findings.push(Check::SupplierIncomplete, "Supplier address line 1 is required");
+    }
+    if application.city.trim().is_empty() {
+        findings.push(Check::SupplierIncomplete, "Supplier city is required");
+    }
+    address::validate("Supplier", &application.country, &application.state, &application.postal_code, &mut findings);
+
+    let tax_id_findings =
+        tax_id::check_supplier(tax_ids, &application.name, &application.country, application.tax_id.as_deref());
+    for finding in &tax_id_findings {
+        findings.push(Check::TaxId, finding.message.clone());
+    }
+
+    match &application.bank_account {
+        Some(account) => validate_bank_account(account, &application.country, &mut findings),
+        None if config.require_bank_account => findings.push(Check::BankDetails, "Bank account is required"),
+        None => {}
+    }
+
+    let duplicates = find_duplicates(suppliers, application, &mut findings)?;
+    let (validation_errors, warnings) = findings.into_parts();
+    Ok(OnboardingReport {
+        supplier_name: application.name.trim().to_string(),
+        valid: validation_errors.is_empty(),
+        validation_errors,
+        warnings,
+        tax_id_findings,
+        duplicates,
+        registered: false,
+    })
+}
+
+/// Find the supplier application carried by a message, as JSON in a Data or Text part
+pub fn extract_application(message: &Message) -> Result<SupplierApplication, Box<dyn Error>> {
+    parts::extract(message, PartSchema::SupplierApplication, |wrapper: SupplierApplicationWrapper| wrapper.supplier)
+}
+
+/// Validates new suppliers and adds those that pass to the supplier store
+pub struct OnboardingSkill {
+    config: OnboardingConfig,
+}
+
+impl OnboardingSkill {
+    pub fn new(config: OnboardingConfig) -> Self {
+        Self { config }
+    }
+}
+
+#[async_trait]
+impl SkillHandler for OnboardingSkill {
+    fn id(&self) -> &str {
+        ONBOARDING_SKILL
+    }
+
+    async fn handle(&self, agent: &PurchaseOrderAgent, message: &Message) -> Result<TaskStatus, Box<dyn Error>> {
+        let application = extract_application(message)?;
+        let validation = agent.validation_config();
+        let mut report = review(&application, agent.suppliers().as_ref(), &self.config, &validation.tax_id, &validation.severity)?;
+        if report.valid && self.config.register {
+            agent.suppliers().put(application.to_supplier())?;
+            report.registered = true;
+        }
+
+        Ok(TaskStatus {
+            state: if report.valid { TaskState::Completed } else { TaskState::Failed },
+            message: Some(Message {
+                role: "assistant".to_string(),
+                parts: vec![Part::Data { data: serde_json::to_value(&report)? }],
+            }),
+            timestamp: agent.current_timestamp(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalog::MemorySupplierStore;
+    use a2a::A2AProtocol;
+    use std::sync::Arc;
+
+    fn application(name: &str, bank_account: BankAccount) -> SupplierApplication {
+        serde_json::from_value(serde_json::json!({
+            "name": name,
+            "addressLine1": "Hauptstraße 5",
+            "city": "Berlin",
+            "postalCode": "10115",
+            "country": "Germany",
+            "taxId": "DE123456789",
+            "bankAccount": bank_account,
+        }))
+        .unwrap()
+    }
+
+    fn iban(iban: &str, bic: Option<&str>) -> BankAccount {
+        BankAccount {
+            holder: "Muster GmbH".to_string(),
+            iban: Some(iban.to_string()),
+            bic: bic.map(str::to_string),
+            routing_number: None,
+            account_number: None,
+        }
+    }
+
+    #[test]
+    fn test_bank_accounts_are_checked() {
+        let policy = SeverityPolicy::default();
+        let errors = |account: BankAccount, country: &str| {
+            let mut findings = Findings::new(&policy);
+            validate_bank_account(&account, country, &mut findings);
+            (findings.errors, findings.warnings)
+        };
+
+        assert_eq!(errors(iban("DE89 3704 0044 0532 0130 00", Some("COBADEFFXXX")), "DE"), (vec![], vec![]));
+        assert_eq!(errors(iban("DE89370400440532013001", None), "DE").0, ["IBAN 'DE89370400440532013001' is not valid"]);
+        assert_eq!(errors(iban("GB82WEST12345698765432", Some("COBADEFF")), "DE"), (
+            vec!["BIC COBADEFF is for DE but the IBAN is for GB".to_string()],
+            vec!["IBAN is for GB but the supplier is in DE".to_string()],
+        ));
+
+        let us = |routing: &str| BankAccount {
+            holder: "Acme".to_string(),
+            iban: None,
+            bic: None,
+            routing_number: Some(routing.to_string()),
+            account_number: Some("000123456789".to_string()),
+        };
+        assert_eq!(errors(us("011000015"), "USA"), (vec![], vec![]));
+        assert_eq!(errors(us("011000016"), "USA").0, ["Routing number '011000016' is not a valid ABA routing number"]);
+    }
+
+    #[tokio::test]
+    async fn test_onboarded_suppliers_join_the_store_once() {
+        let agent = PurchaseOrderAgent::new().with_supplier_store(Arc::new(MemorySupplierStore::new()));
+        let onboard = |application: SupplierApplication| {
+            let data = serde_json::json!({ "skill_id": ONBOARDING_SKILL, "supplier": application });
+            agent.send_task(Message { role: "user".to_string(), parts: vec![Part::Data { data }] })
+        };
+        let report = |task: &a2a::Task| {
+            let Some(Part::Data { data }) = task.status.message.as_ref().and_then(|m| m.parts.first()) else { panic!("expected a report") };
+            serde_json::from_value::<OnboardingReport>(data.clone()).unwrap()
+        };
+
+        let first = onboard(application("Muster GmbH", iban("DE89370400440532013000", None))).await.unwrap();
+        assert!(matches!(first.status.state, TaskState::Completed));
+        assert!(report(&first).registered);
+        assert!(agent.suppliers().get("muster gmbh").unwrap().is_some());
+
+        let again = onboard(application("Muster AG", iban("DE89370400440532013000", None))).await.unwrap();
+        assert!(matches!(again.status.state, TaskState::Failed));
+        let again = report(&again);
+        assert_eq!(again.duplicates, ["Muster GmbH"]);
+        assert_eq!(
+            again.validation_errors,
+            ["Tax ID is already registered to supplier 'Muster GmbH'", "Bank account is already registered to supplier 'Muster GmbH'"]
+        );
+        assert_eq!(
+            again.warnings,
+            ["Supplier name 'Muster AG' is close to existing supplier 'Muster GmbH'", "Supplier address is the address of supplier 'Muster GmbH'"]
+        );
+        assert!(!again.registered);
+    }
+}
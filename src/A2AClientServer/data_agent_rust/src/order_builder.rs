@@ -0,0 +1,273 @@
+//! Fluent construction of purchase orders in code
+//!
+//! The builder computes every amount of the order: line totals from quantities
+//! and unit prices, then the subtotal, tax and grand total, rounded the way
+//! the reconciliation checks expect. `build` refuses orders missing a field the
+//! agent requires.
+
+use std::fmt;
+
+use crate::address::Address;
+use crate::agent::{PurchaseOrder, PurchaseOrderItem};
+use crate::blanket::BlanketTerms;
+use crate::rounding::ReconciliationConfig;
+
+/// Why a purchase order could not be built
+#[derive(Debug, PartialEq)]
+pub enum BuildError {
+    /// Required fields left unset or blank, by their JSON names
+    Missing(Vec<&'static str>),
+    /// A quantity, price or rate out of range
+    Invalid(String),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::Missing(fields) => write!(f, "Purchase order is missing {}", fields.join(", ")),
+            BuildError::Invalid(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+/// Fluent construction of a [`PurchaseOrder`]
+///
+/// Starts from an unapproved order with no items and a zero tax rate; each
+/// `with_` call adds to it.
+///
+/// ```
+/// use data_agent_rust::PurchaseOrder;
+///
+/// let po = PurchaseOrder::builder("MMS-80085")
+///     .with_supplier("Marketing Masters Supplies")
+///     .with_supplier_address("1234 Creative Avenue, Suite 567", "Imagination City", "CA", "90210", "USA")
+///     .with_created_by("J.J. Schmidt")
+///     .with_buyer_department("Marketing")
+///     .with_item("bk-2345", "Marketing Strategy Guidebook", 3, 29.99)
+///     .with_item("Bk-1311", "Promotional Materials Handbook", 3, 34.99)
+///     .with_tax_rate(0.07)
+///     .build()
+///     .unwrap();
+/// assert_eq!((po.sub_total, po.tax, po.grand_total), (194.94, 13.65, 208.59));
+/// ```
+#[derive(Debug, Clone)]
+pub struct PurchaseOrderBuilder {
+    po: PurchaseOrder,
+    reconciliation: ReconciliationConfig,
+}
+
+impl PurchaseOrderBuilder {
+    pub fn new(po_number: &str) -> Self {
+        Self {
+            po: PurchaseOrder {
+                supplier_name: String::new(),
+                supplier_address_line1: String::new(),
+                supplier_address_line2: None,
+                supplier_city: String::new(),
+                supplier_state: String::new(),
+                supplier_postal_code: String::new(),
+                supplier_country: String::new(),
+                supplier_tax_id: None,
+                items: Vec::new(),
+                po_number: po_number.to_string(),
+                created_by: String::new(),
+                buyer_department: String::new(),
+                ship_to: None,
+                notes: None,
+                tax_rate: 0.0,
+                sub_total: 0.0,
+                tax: 0.0,
+                grand_total: 0.0,
+                is_approved: false,
+                approval_reason: None,
+                blanket: None,
+                blanket_po_number: None,
+                currency: None,
+            },
+            reconciliation: ReconciliationConfig::default(),
+        }
+    }
+
+    pub fn with_supplier(mut self, name: &str) -> Self {
+        self.po.supplier_name = name.to_string();
+        self
+    }
+
+    /// Supplier street, city, state or province, postal code and country
+    pub fn with_supplier_address(mut self, line1: &str, city: &str, state: &str, postal_code: &str, country: &str) -> Self {
+        self.po.supplier_address_line1 = line1.to_string();
+        self.po.supplier_city = city.to_string();
+        self.po.supplier_state = state.to_string();
+        self.po.supplier_postal_code = postal_code.to_string();
+        self.po.supplier_country = country.to_string();
+        self
+    }
+
+    pub fn with_supplier_address_line2(mut self, line2: &str) -> Self {
+        self.po.supplier_address_line2 = Some(line2.to_string());
+        self
+    }
+
+    /// VAT number or EIN of the supplier
+    pub fn with_supplier_tax_id(mut self, tax_id: &str) -> Self {
+        self.po.supplier_tax_id = Some(tax_id.to_string());
+        self
+    }
+
+    pub fn with_created_by(mut self, created_by: &str) -> Self {
+        self.po.created_by = created_by.to_string();
+        self
+    }
+
+    pub fn with_buyer_department(mut self, department: &str) -> Self {
+        self.po.buyer_department = department.to_string();
+        self
+    }
+
+    /// A line whose total is computed on `build`
+    pub fn with_item(mut self, item_code: &str, description: &str, quantity: u32, unit_price: f64) -> Self {
+        self.po.items.push(PurchaseOrderItem {
+            item_code: item_code.to_string(),
+            description: description.to_string(),
+            quantity,
+            unit_price,
+            line_total: 0.0,
+        });
+        self
+    }
+
+    /// Tax rate as a fraction, e.g. `0.07` for 7%
+    pub fn with_tax_rate(mut self, tax_rate: f64) -> Self {
+        self.po.tax_rate = tax_rate;
+        self
+    }
+
+    pub fn with_notes(mut self, notes: &str) -> Self {
+        self.po.notes = Some(notes.to_string());
+        self
+    }
+
+    pub fn with_ship_to(mut self, ship_to: Address) -> Self {
+        self.po.ship_to = Some(ship_to);
+        self
+    }
+
+    /// ISO 4217 currency of the amounts
+    pub fn with_currency(mut self, currency: &str) -> Self {
+        self.po.currency = Some(currency.to_string());
+        self
+    }
+
+    /// Mark the order approved by its buyer, for the reason given
+    pub fn with_approval(mut self, reason: &str) -> Self {
+        self.po.is_approved = true;
+        self.po.approval_reason = Some(reason.to_string());
+        self
+    }
+
+    /// Make the order a blanket order with not-to-exceed terms
+    pub fn with_blanket(mut self, terms: BlanketTerms) -> Self {
+        self.po.blanket = Some(terms);
+        self
+    }
+
+    /// Make the order a release against a blanket order
+    pub fn with_blanket_po_number(mut self, po_number: &str) -> Self {
+        self.po.blanket_po_number = Some(po_number.to_string());
+        self
+    }
+
+    /// Round amounts as `config` does instead of half-up to the cent with tax on the subtotal
+    pub fn with_reconciliation(mut self, config: ReconciliationConfig) -> Self {
+        self.reconciliation = config;
+        self
+    }
+
+    /// Compute the amounts and check the order is complete
+    pub fn build(self) -> Result<PurchaseOrder, BuildError> {
+        let Self { mut po, reconciliation } = self;
+        let required = [
+            ("poNumber", &po.po_number),
+            ("supplierName", &po.supplier_name),
+            ("supplierAddressLine1", &po.supplier_address_line1),
+            ("supplierCity", &po.supplier_city),
+            ("supplierPostalCode", &po.supplier_postal_code),
+            ("supplierCountry", &po.supplier_country),
+            ("createdBy", &po.created_by),
+            ("buyerDepartment", &po.buyer_department),
+        ];
+        let mut missing: Vec<&'static str> =
+            required.iter().filter(|(_, value)| value.trim().is_empty()).map(|(field, _)| *field).collect();
+        if po.items.is_empty() {
+            missing.push("items");
+        }
+        if !missing.is_empty() {
+            return Err(BuildError::Missing(missing));
+        }
+
+        for (index, item) in po.items.iter().enumerate() {
+            if item.quantity == 0 {
+                return Err(BuildError::Invalid(format!("Item {} has a zero quantity", index + 1)));
+            }
+            if !item.unit_price.is_finite() {
+                return Err(BuildError::Invalid(format!("Item {} has no valid unit price", index + 1)));
+            }
+        }
+        if !(0.0..1.0).contains(&po.tax_rate) {
+            return Err(BuildError::Invalid(format!("Tax rate {} is not a fraction between 0 and 1", po.tax_rate)));
+        }
+
+        for item in &mut po.items {
+            item.line_total = reconciliation.line_total(item);
+        }
+        po.sub_total = reconciliation.round(po.items.iter().map(|item| item.line_total).sum());
+        po.tax = reconciliation.tax(&po);
+        po.grand_total = reconciliation.round(po.sub_total + po.tax);
+        Ok(po)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::PurchaseOrderAgent;
+    use crate::rounding::TaxRounding;
+
+    fn builder() -> PurchaseOrderBuilder {
+        PurchaseOrder::builder("PO-1")
+            .with_supplier("Acme Office Supply")
+            .with_supplier_address("1 Main St", "Springfield", "IL", "62701", "USA")
+            .with_created_by("Sam")
+            .with_buyer_department("IT")
+            .with_item("P-1", "Paper", 3, 4.99)
+            .with_item("P-2", "Toner", 1, 0.35)
+            .with_tax_rate(0.0825)
+    }
+
+    #[test]
+    fn test_built_orders_pass_the_reconciliation_checks() {
+        let po = builder().build().unwrap();
+        assert_eq!(po.items[0].line_total, 14.97);
+        assert_eq!((po.sub_total, po.tax, po.grand_total), (15.32, 1.26, 16.58));
+        let (errors, warnings) = PurchaseOrderAgent::new().validate_purchase_order(&po);
+        assert!(errors.is_empty() && warnings.is_empty(), "{:?} {:?}", errors, warnings);
+
+        let config = ReconciliationConfig { tax_rounding: TaxRounding::Line, ..ReconciliationConfig::default() };
+        let line_taxed = builder().with_reconciliation(config).build().unwrap();
+        assert_eq!(line_taxed.tax, 1.27);
+    }
+
+    #[test]
+    fn test_incomplete_orders_are_refused() {
+        let missing = PurchaseOrder::builder("PO-1").with_supplier("Acme Office Supply").build().unwrap_err();
+        assert_eq!(
+            missing.to_string(),
+            "Purchase order is missing supplierAddressLine1, supplierCity, supplierPostalCode, supplierCountry, createdBy, buyerDepartment, items"
+        );
+        let zero = builder().with_item("P-3", "Staples", 0, 1.0).build().unwrap_err();
+        assert_eq!(zero, BuildError::Invalid("Item 3 has a zero quantity".to_string()));
+        assert!(matches!(builder().with_tax_rate(8.25).build(), Err(BuildError::Invalid(_))));
+    }
+}
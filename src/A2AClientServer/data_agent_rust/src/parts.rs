@@ -0,0 +1,357 @@
+//! Part-level metadata of A2A messages
+//!
+//! A2A lets every part of a message carry `metadata`. This agent reads three
+//! of its fields when deciding how to parse a part:
+//!
+//! - `schema` names the document a Data or Text part holds, one of
+//!   [`PartSchema`]. When any part declares the schema a skill reads, only those
+//!   parts are parsed, and a declared part that does not parse fails the
+//!   message instead of being skipped. A schema the agent does not know fails
+//!   the message. Parts declaring no schema are tried in order.
+//! - `mimeType` other than JSON (`application/json` or `…+json`) marks a part as
+//!   not holding a document at all.
+//! - `skillId` routes the message to a skill, like a `skill_id` field does.
+//!
+//! The `a2a` crate's `Part` has no field for metadata and drops it, so incoming
+//! messages are read with [`message_from_value`]. It keeps the metadata in one
+//! more Data part, `{"partMetadata": [...]}`, listing it by part index, so it
+//! travels with the message through queues, the task store and retries.
+
+use a2a::{Message, Part};
+use serde::de::{self, DeserializeOwned, Deserializer};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::error::Error;
+use std::fmt;
+
+/// Key of the Data part listing the metadata of a message's parts
+pub const METADATA_KEY: &str = "partMetadata";
+
+/// The fields of a part's `metadata` this agent reads; others are kept but ignored
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PartMetadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+    /// Document the part holds, e.g. `purchase-order`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skill_id: Option<String>,
+}
+
+impl PartMetadata {
+    /// Whether the part may hold JSON, which it does unless it declares another mime type
+    pub fn is_json(&self) -> bool {
+        self.mime_type.as_deref().is_none_or(|mime| {
+            let essence = mime.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+            essence == "application/json" || essence.ends_with("+json")
+        })
+    }
+}
+
+/// Documents a part can declare it holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PartSchema {
+    PurchaseOrder,
+    Invoice,
+    ExpenseReport,
+    SupplierApplication,
+    Requisition,
+}
+
+impl PartSchema {
+    pub const ALL: [PartSchema; 5] = [
+        PartSchema::PurchaseOrder,
+        PartSchema::Invoice,
+        PartSchema::ExpenseReport,
+        PartSchema::SupplierApplication,
+        PartSchema::Requisition,
+    ];
+
+    /// Name declared in a part's `schema`
+    pub fn name(self) -> &'static str {
+        match self {
+            PartSchema::PurchaseOrder => "purchase-order",
+            PartSchema::Invoice => "invoice",
+            PartSchema::ExpenseReport => "expense-report",
+            PartSchema::SupplierApplication => "supplier-application",
+            PartSchema::Requisition => "requisition",
+        }
+    }
+
+    /// The schema with this name, compared case-insensitively
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|schema| schema.name().eq_ignore_ascii_case(name.trim()))
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            PartSchema::PurchaseOrder => "purchase order",
+            PartSchema::Invoice => "invoice",
+            PartSchema::ExpenseReport => "expense report",
+            PartSchema::SupplierApplication => "supplier application",
+            PartSchema::Requisition => "requisition",
+        }
+    }
+}
+
+impl fmt::Display for PartSchema {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Read a message from JSON, keeping the `metadata` of its parts
+pub fn message_from_value(value: Value) -> serde_json::Result<Message> {
+    let metadata: Vec<Value> = value
+        .get("parts")
+        .and_then(Value::as_array)
+        .map(|parts| parts.iter().map(|part| part.get("metadata").cloned().unwrap_or(Value::Null)).collect())
+        .unwrap_or_default();
+    let mut message = Message::deserialize(value)?;
+    if metadata.iter().any(|metadata| !metadata.is_null()) && !message.parts.iter().any(is_metadata) {
+        message.parts.push(Part::Data { data: serde_json::json!({ METADATA_KEY: metadata }) });
+    }
+    Ok(message)
+}
+
+/// `deserialize_with` reading a message as [`message_from_value`] does
+pub fn deserialize_message<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Message, D::Error> {
+    message_from_value(Value::deserialize(deserializer)?).map_err(de::Error::custom)
+}
+
+/// `deserialize_with` reading an optional message as [`message_from_value`] does
+pub fn deserialize_optional_message<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Message>, D::Error> {
+    match Option::<Value>::deserialize(deserializer)? {
+        Some(value) => message_from_value(value).map(Some).map_err(de::Error::custom),
+        None => Ok(None),
+    }
+}
+
+/// `first` followed by the parts of `second`, keeping the metadata of both
+pub fn concat(first: &Message, second: &Message) -> Message {
+    let mut parts = Vec::new();
+    let mut listed = Vec::new();
+    for message in [first, second] {
+        for (part, metadata) in message.parts.iter().zip(metadata(message)) {
+            if !is_metadata(part) {
+                parts.push(part.clone());
+                listed.push(metadata);
+            }
+        }
+    }
+    if listed.iter().any(|metadata| *metadata != PartMetadata::default()) {
+        let listed: Vec<Value> = listed
+            .iter()
+            .map(|metadata| {
+                if *metadata == PartMetadata::default() {
+                    Value::Null
+                } else {
+                    serde_json::to_value(metadata).unwrap_or(Value::Null)
+                }
+            })
+            .collect();
+        parts.push(Part::Data { data: serde_json::json!({ METADATA_KEY: listed }) });
+    }
+    Message { role: first.role.clone(), parts }
+}
+
+/// Whether a part is the one listing the metadata of the others
+pub fn is_metadata(part: &Part) -> bool {
+    matches!(part, Part::Data { data } if data.as_object().is_some_and(|fields| fields.len() == 1 && fields.contains_key(METADATA_KEY)))
+}
+
+/// Metadata of each part of `message`, by index; parts without any get the default
+pub fn metadata(message: &Message) -> Vec<PartMetadata> {
+    let listed = message.parts.iter().rev().find_map(|part| match part {
+        Part::Data { data } if is_metadata(part) => data.get(METADATA_KEY).and_then(Value::as_array),
+        _ => None,
+    });
+    (0..message.parts.len())
+        .map(|index| {
+            listed
+                .and_then(|listed| listed.get(index))
+                .and_then(|metadata| PartMetadata::deserialize(metadata).ok())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+/// The first `skillId` declared in the metadata of a part
+pub fn skill_id(message: &Message) -> Option<String> {
+    metadata(message).into_iter().find_map(|metadata| metadata.skill_id)
+}
+
+/// A part that may hold a document of the schema asked for
+pub struct Candidate<'a> {
+    pub index: usize,
+    pub part: &'a Part,
+    /// The part declares the schema, so failing to parse it fails the message
+    pub declared: bool,
+}
+
+/// The parts of `message` that may hold a document of `schema`, in order
+///
+/// Fails when a part declares a schema the agent does not know, or declares
+/// `schema` with a mime type other than JSON.
+pub fn candidates(message: &Message, schema: PartSchema) -> Result<Vec<Candidate<'_>>, String> {
+    let (mut declared, mut undeclared) = (Vec::new(), Vec::new());
+    for (index, (part, metadata)) in message.parts.iter().zip(metadata(message)).enumerate() {
+        if is_metadata(part) || !matches!(part, Part::Data { .. } | Part::Text { .. }) {
+            continue;
+        }
+        match metadata.schema.as_deref() {
+            Some(name) => {
+                let Some(declared_schema) = PartSchema::parse(name) else {
+                    let known: Vec<&str> = PartSchema::ALL.iter().map(|schema| schema.name()).collect();
+                    return Err(format!(
+                        "part {} declares unknown schema '{}'; known schemas are {}",
+                        index + 1, name, known.join(", ")
+                    ));
+                };
+                if declared_schema != schema {
+                    continue;
+                }
+                if !metadata.is_json() {
+                    return Err(format!(
+                        "part {} declares schema {} with mime type {}, which is not JSON",
+                        index + 1, schema, metadata.mime_type.unwrap_or_default()
+                    ));
+                }
+                declared.push(Candidate { index, part, declared: true });
+            }
+            None if metadata.is_json() => undeclared.push(Candidate { index, part, declared: false }),
+            None => {}
+        }
+    }
+    Ok(if declared.is_empty() { undeclared } else { declared })
+}
+
+/// The first document of `schema` in `message`, under its wrapper key as `W` or bare as `T`
+pub fn extract<T, W>(message: &Message, schema: PartSchema, unwrap: impl Fn(W) -> T) -> Result<T, Box<dyn Error>>
+where
+    T: DeserializeOwned,
+    W: DeserializeOwned,
+{
+    for candidate in candidates(message, schema)? {
+        let value = match candidate.part {
+            Part::Data { data } => data.clone(),
+            Part::Text { text } => match serde_json::from_str(text) {
+                Ok(value) => value,
+                Err(e) if candidate.declared => {
+                    return Err(format!("part {} declares schema {} but is not JSON: {}", candidate.index + 1, schema, e).into());
+                }
+                Err(_) => continue,
+            },
+            _ => continue,
+        };
+        if let Ok(wrapper) = serde_json::from_value::<W>(value.clone()) {
+            return Ok(unwrap(wrapper));
+        }
+        match serde_json::from_value::<T>(value) {
+            Ok(document) => return Ok(document),
+            Err(e) if candidate.declared => {
+                return Err(format!(
+                    "part {} declares schema {} but is not a valid {}: {}",
+                    candidate.index + 1, schema, schema.description(), e
+                )
+                .into());
+            }
+            Err(_) => {}
+        }
+    }
+    Err(format!("No valid {} found in message", schema.description()).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn message(parts: Value) -> Message {
+        message_from_value(json!({ "role": "user", "parts": parts })).unwrap()
+    }
+
+    #[test]
+    fn test_metadata_is_kept_and_selects_parts() {
+        let message = message(json!([
+            { "type": "text", "text": "{\"invoiceNumber\": \"INV-1\"}", "metadata": { "mimeType": "text/plain" } },
+            { "type": "data", "data": { "poNumber": "PO-1" } },
+            { "type": "data", "data": { "poNumber": "PO-2" }, "metadata": { "schema": "Purchase-Order", "skillId": "purchase-order-validation" } },
+        ]));
+        assert_eq!(message.parts.len(), 4);
+        assert!(is_metadata(&message.parts[3]));
+        assert_eq!(metadata(&message)[0].mime_type.as_deref(), Some("text/plain"));
+        assert_eq!(skill_id(&message).as_deref(), Some("purchase-order-validation"));
+
+        // Only the declared part is parsed for its schema; the plain text part for none
+        let declared: Vec<_> = candidates(&message, PartSchema::PurchaseOrder).unwrap().iter().map(|c| c.index).collect();
+        assert_eq!(declared, [2]);
+        let undeclared: Vec<_> = candidates(&message, PartSchema::Invoice).unwrap().iter().map(|c| c.index).collect();
+        assert_eq!(undeclared, [1]);
+
+        // Metadata survives a round trip through storage
+        let stored: Message = serde_json::from_value(serde_json::to_value(&message).unwrap()).unwrap();
+        assert_eq!(metadata(&stored), metadata(&message));
+        assert_eq!(message_from_value(serde_json::to_value(&stored).unwrap()).unwrap().parts.len(), 4);
+    }
+
+    #[test]
+    fn test_declared_schemas_are_checked() {
+        let unknown = message(json!([{ "type": "data", "data": {}, "metadata": { "schema": "po-v9" } }]));
+        let err = candidates(&unknown, PartSchema::PurchaseOrder).err().unwrap();
+        assert_eq!(err, "part 1 declares unknown schema 'po-v9'; known schemas are purchase-order, invoice, expense-report, supplier-application, requisition");
+
+        let csv = message(json!([{ "type": "text", "text": "a,b", "metadata": { "schema": "invoice", "mimeType": "text/csv" } }]));
+        assert!(candidates(&csv, PartSchema::Invoice).err().unwrap().contains("text/csv, which is not JSON"));
+
+        #[derive(Deserialize)]
+        struct Wrapper {
+            #[allow(dead_code)]
+            invoice: Value,
+        }
+        let invalid = message(json!([
+            { "type": "data", "data": { "amount": "ten" }, "metadata": { "schema": "requisition" } },
+            { "type": "data", "data": { "requisitionNumber": "REQ-1" } },
+        ]));
+        let err = extract::<crate::requisition::Requisition, Wrapper>(&invalid, PartSchema::Requisition, |_| unreachable!())
+            .err()
+            .unwrap();
+        assert!(err.to_string().starts_with("part 1 declares schema requisition but is not a valid requisition"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_sent_tasks_parse_the_declared_part() {
+        use axum::body::Body;
+        use axum::http::Request;
+        use tower::ServiceExt;
+
+        let agent = std::sync::Arc::new(crate::agent::PurchaseOrderAgent::new());
+        let router = crate::server::create_router(agent.clone());
+        let mut declared = crate::testing::valid_order();
+        declared.po_number = "PO-DECLARED".to_string();
+        let send = |parts: Value| {
+            let body = json!({ "message": { "role": "user", "parts": parts } }).to_string();
+            router.clone().oneshot(
+                Request::post("/agent/task").header("content-type", "application/json").body(Body::from(body)).unwrap(),
+            )
+        };
+
+        let response = send(json!([
+            { "type": "data", "data": { "purchaseOrder": crate::testing::valid_order() }, "metadata": { "mimeType": "text/csv" } },
+            { "type": "data", "data": { "purchaseOrder": declared }, "metadata": { "schema": "purchase-order" } },
+        ]))
+        .await
+        .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert!(String::from_utf8_lossy(&body).contains("PO-DECLARED"));
+
+        let response = send(json!([{ "type": "data", "data": {}, "metadata": { "schema": "po-v9" } }])).await.unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let response: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(response["status"], "error");
+        let letters = agent.dead_letters().list().unwrap();
+        assert!(letters[0].errors[0].contains("unknown schema 'po-v9'"), "{:?}", letters[0].errors);
+    }
+}
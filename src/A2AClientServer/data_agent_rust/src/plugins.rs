@@ -0,0 +1,270 @@
+//! Business-unit plugins: custom validation rules and output transformers
+//!
+//! Plugins are WebAssembly modules dropped into the `[plugins] directory` as
+//! `.wasm` files and loaded at startup (requires the `wasm` feature), so a
+//! business unit's quirks need no rebuild of the agent. A module is a
+//! validation rule, an output transformer, or both, depending on what it
+//! exports; see the `wasm` module for the ABI.
+//!
+//! - A validation rule sees every purchase order as JSON and returns findings,
+//!   reported as `plugin_rule` errors or `plugin_warning` warnings. A rule that
+//!   fails to run reports a `plugin_rule` error, so an order is never passed by
+//!   a broken rule.
+//! - An output transformer sees every processing result as JSON and returns a
+//!   JSON value, kept under the result's `plugin_outputs` by plugin name and
+//!   served as an extra `<po number>.<plugin>.json` artifact. A transformer
+//!   that fails is logged and left out of the result.
+
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tracing::warn;
+
+use crate::agent::{ProcessingResult, PurchaseOrder};
+use crate::metrics::Metrics;
+use crate::severity::{Check, Findings};
+
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+/// The `[plugins]` section
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct PluginsConfig {
+    /// Directory whose `.wasm` files are loaded, in file name order; unset loads none
+    pub directory: Option<PathBuf>,
+    /// Instructions a plugin may run per call before it is stopped
+    pub fuel: u64,
+    /// Largest linear memory a plugin may grow to, in bytes
+    pub max_memory_bytes: usize,
+}
+
+impl Default for PluginsConfig {
+    fn default() -> Self {
+        Self { directory: None, fuel: 100_000_000, max_memory_bytes: 16 * 1024 * 1024 }
+    }
+}
+
+/// Severity a validation rule gives a finding
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PluginSeverity {
+    #[default]
+    Error,
+    Warning,
+}
+
+/// A finding returned by a validation rule
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct PluginFinding {
+    pub message: String,
+    #[serde(default)]
+    pub severity: PluginSeverity,
+}
+
+/// Custom validation of purchase orders
+pub trait ValidationRule: Send + Sync {
+    /// Plugin name, as used in metrics and logs
+    fn name(&self) -> &str;
+
+    /// Findings for the purchase order `order`, as submitted in JSON
+    fn validate(&self, order: &[u8]) -> Result<Vec<PluginFinding>, Box<dyn Error>>;
+}
+
+/// Extra output derived from processing results
+pub trait OutputTransformer: Send + Sync {
+    /// Plugin name, keying its output in `plugin_outputs`
+    fn name(&self) -> &str;
+
+    /// Output for the processing result `result`, in JSON
+    fn transform(&self, result: &[u8]) -> Result<Value, Box<dyn Error>>;
+}
+
+/// Every loaded validation rule and output transformer
+#[derive(Clone, Default)]
+pub struct Plugins {
+    rules: Vec<Arc<dyn ValidationRule>>,
+    transformers: Vec<Arc<dyn OutputTransformer>>,
+}
+
+impl Plugins {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_rule(mut self, rule: Arc<dyn ValidationRule>) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    pub fn with_transformer(mut self, transformer: Arc<dyn OutputTransformer>) -> Self {
+        self.transformers.push(transformer);
+        self
+    }
+
+    pub fn rule_names(&self) -> Vec<&str> {
+        self.rules.iter().map(|rule| rule.name()).collect()
+    }
+
+    pub fn transformer_names(&self) -> Vec<&str> {
+        self.transformers.iter().map(|transformer| transformer.name()).collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty() && self.transformers.is_empty()
+    }
+}
+
+impl std::fmt::Debug for Plugins {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Plugins")
+            .field("rules", &self.rule_names())
+            .field("transformers", &self.transformer_names())
+            .finish()
+    }
+}
+
+/// Load the plugins of `config`'s directory
+pub fn load(config: &PluginsConfig) -> Result<Plugins, Box<dyn Error>> {
+    let Some(directory) = &config.directory else {
+        return Ok(Plugins::new());
+    };
+    #[cfg(feature = "wasm")]
+    {
+        wasm::load_directory(directory, config)
+    }
+    #[cfg(not(feature = "wasm"))]
+    {
+        let _ = directory;
+        Err("[plugins] directory is configured but this binary was built without the `wasm` feature".into())
+    }
+}
+
+/// Run every validation rule on `po`, reporting their findings
+pub fn validate(plugins: &Plugins, metrics: &Metrics, po: &PurchaseOrder, findings: &mut Findings) {
+    if plugins.rules.is_empty() {
+        return;
+    }
+    let order = match serde_json::to_vec(po) {
+        Ok(order) => order,
+        Err(e) => {
+            findings.push(Check::PluginRule, format!("Purchase order could not be passed to validation plugins: {}", e));
+            return;
+        }
+    };
+    for rule in &plugins.rules {
+        let outcome = match rule.validate(&order) {
+            Ok(reported) => {
+                for finding in reported {
+                    let check = match finding.severity {
+                        PluginSeverity::Error => Check::PluginRule,
+                        PluginSeverity::Warning => Check::PluginWarning,
+                    };
+                    findings.push(check, finding.message);
+                }
+                "ok"
+            }
+            Err(e) => {
+                warn!(plugin = rule.name(), po_number = %po.po_number, error = %e, "validation plugin failed");
+                findings.push(Check::PluginRule, format!("Validation plugin '{}' failed: {}", rule.name(), e));
+                "error"
+            }
+        };
+        record(metrics, rule.name(), "validate", outcome);
+    }
+}
+
+/// Run every output transformer on `result`, keyed by plugin name
+pub fn transform(plugins: &Plugins, metrics: &Metrics, result: &ProcessingResult) -> BTreeMap<String, Value> {
+    let mut outputs = BTreeMap::new();
+    if plugins.transformers.is_empty() {
+        return outputs;
+    }
+    let serialized = match serde_json::to_vec(result) {
+        Ok(serialized) => serialized,
+        Err(e) => {
+            warn!(po_number = %result.po_number, error = %e, "processing result could not be passed to output plugins");
+            return outputs;
+        }
+    };
+    for transformer in &plugins.transformers {
+        let outcome = match transformer.transform(&serialized) {
+            Ok(output) => {
+                outputs.insert(transformer.name().to_string(), output);
+                "ok"
+            }
+            Err(e) => {
+                warn!(plugin = transformer.name(), po_number = %result.po_number, error = %e, "output plugin failed");
+                "error"
+            }
+        };
+        record(metrics, transformer.name(), "transform", outcome);
+    }
+    outputs
+}
+
+fn record(metrics: &Metrics, plugin: &str, call: &str, outcome: &str) {
+    metrics.increment(
+        "po_agent_plugin_calls_total",
+        "Calls into plugins by plugin, call and outcome",
+        &[("plugin", plugin), ("call", call), ("outcome", outcome)],
+        1,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PurchaseOrderAgent;
+    use a2a::{A2AProtocol, Part};
+
+    struct MinimumOrder;
+
+    impl ValidationRule for MinimumOrder {
+        fn name(&self) -> &str {
+            "minimum-order"
+        }
+
+        fn validate(&self, order: &[u8]) -> Result<Vec<PluginFinding>, Box<dyn Error>> {
+            let order: Value = serde_json::from_slice(order)?;
+            Ok(match order["grandTotal"].as_f64() {
+                Some(total) if total < 100.0 => vec![PluginFinding {
+                    message: "EMEA orders must total at least 100.00".to_string(),
+                    severity: PluginSeverity::Warning,
+                }],
+                _ => Vec::new(),
+            })
+        }
+    }
+
+    struct CostCenter;
+
+    impl OutputTransformer for CostCenter {
+        fn name(&self) -> &str {
+            "cost-center"
+        }
+
+        fn transform(&self, result: &[u8]) -> Result<Value, Box<dyn Error>> {
+            let result: Value = serde_json::from_slice(result)?;
+            Ok(serde_json::json!({ "costCenter": format!("CC-{}", result["buyer_department"].as_str().unwrap_or_default()) }))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_plugin_findings_and_outputs_reach_the_task() {
+        let plugins = Plugins::new().with_rule(Arc::new(MinimumOrder)).with_transformer(Arc::new(CostCenter));
+        let agent = PurchaseOrderAgent::new().with_plugins(plugins);
+        let task = agent.send_task(crate::testing::message(&crate::testing::valid_order())).await.unwrap();
+
+        let result = ProcessingResult::from_status(&task.status).unwrap();
+        assert!(result.warnings.contains(&"EMEA orders must total at least 100.00".to_string()));
+        assert_eq!(result.plugin_outputs["cost-center"], serde_json::json!({ "costCenter": "CC-Marketing" }));
+        let artifacts = task.artifacts.unwrap();
+        let output = artifacts.iter().find(|artifact| artifact.name.as_deref() == Some("PO-1.cost-center.json")).unwrap();
+        assert!(matches!(&output.parts[0], Part::Data { data } if data["costCenter"] == "CC-Marketing"));
+        assert!(agent.metrics().render().contains("po_agent_plugin_calls_total{plugin=\"minimum-order\",call=\"validate\",outcome=\"ok\"} 1"));
+    }
+}
@@ -0,0 +1,211 @@
+//! WebAssembly plugin host, built on wasmtime
+//!
+//! A plugin is a core WebAssembly module with no imports, so it can reach
+//! nothing but the bytes it is handed. It exports:
+//!
+//! - `memory`: its linear memory
+//! - `po_abi_version() -> i32`: the ABI it was built for, currently `1`
+//! - `po_alloc(len: i32) -> i32`: room for `len` bytes of input, returning its offset
+//! - `po_validate(ptr: i32, len: i32) -> i64`, for a validation rule: given the
+//!   purchase order JSON at `ptr`, returns a JSON array of
+//!   `{"message": "...", "severity": "error" | "warning"}` findings
+//! - `po_transform(ptr: i32, len: i32) -> i64`, for an output transformer: given
+//!   the processing result JSON at `ptr`, returns any JSON value
+//!
+//! Outputs are returned as their offset in the high 32 bits and their length
+//! in the low 32 bits. Every call gets a fresh instance, so nothing carries
+//! over from one order to the next, and is stopped once it has used up its
+//! fuel or grows its memory past the limit.
+
+use serde_json::Value;
+use std::error::Error;
+use std::path::Path;
+use std::sync::Arc;
+use wasmtime::{Config, Engine, ExternType, Instance, Module, Store, StoreLimits, StoreLimitsBuilder, Trap};
+
+use super::{OutputTransformer, PluginFinding, Plugins, PluginsConfig, ValidationRule};
+
+/// ABI version plugins must report from `po_abi_version`
+pub const ABI_VERSION: i32 = 1;
+
+const VALIDATE_EXPORT: &str = "po_validate";
+const TRANSFORM_EXPORT: &str = "po_transform";
+
+/// A compiled plugin module
+pub struct WasmPlugin {
+    name: String,
+    engine: Engine,
+    module: Module,
+    fuel: u64,
+    max_memory_bytes: usize,
+}
+
+impl WasmPlugin {
+    /// Compile the module at `path`, checking it follows the ABI
+    pub fn load(engine: &Engine, name: &str, path: &Path, config: &PluginsConfig) -> Result<Self, Box<dyn Error>> {
+        let module = Module::from_file(engine, path).map_err(|e| format!("plugin {} does not compile: {}", path.display(), e))?;
+        if let Some(import) = module.imports().next() {
+            return Err(format!("plugin {} imports {}::{}, but plugins may not import anything", path.display(), import.module(), import.name()).into());
+        }
+        for export in ["memory", "po_abi_version", "po_alloc"] {
+            if module.get_export(export).is_none() {
+                return Err(format!("plugin {} does not export `{}`", path.display(), export).into());
+            }
+        }
+        let plugin = Self {
+            name: name.to_string(),
+            engine: engine.clone(),
+            module,
+            fuel: config.fuel,
+            max_memory_bytes: config.max_memory_bytes,
+        };
+        if !plugin.exports(VALIDATE_EXPORT) && !plugin.exports(TRANSFORM_EXPORT) {
+            return Err(format!("plugin {} exports neither `{}` nor `{}`", path.display(), VALIDATE_EXPORT, TRANSFORM_EXPORT).into());
+        }
+        let version = plugin.abi_version()?;
+        if version != ABI_VERSION {
+            return Err(format!("plugin {} is built for ABI version {}, but this agent supports {}", path.display(), version, ABI_VERSION).into());
+        }
+        Ok(plugin)
+    }
+
+    fn exports(&self, name: &str) -> bool {
+        matches!(self.module.get_export(name), Some(ExternType::Func(_)))
+    }
+
+    fn instantiate(&self) -> Result<(Store<StoreLimits>, Instance), Box<dyn Error>> {
+        let limits = StoreLimitsBuilder::new().memory_size(self.max_memory_bytes).build();
+        let mut store = Store::new(&self.engine, limits);
+        store.limiter(|limits| limits);
+        store.set_fuel(self.fuel)?;
+        let instance = Instance::new(&mut store, &self.module, &[])?;
+        Ok((store, instance))
+    }
+
+    fn abi_version(&self) -> Result<i32, Box<dyn Error>> {
+        let (mut store, instance) = self.instantiate()?;
+        let version = instance.get_typed_func::<(), i32>(&mut store, "po_abi_version")?;
+        version.call(&mut store, ()).map_err(trapped)
+    }
+
+    /// Call `export` with `input`, returning the bytes it points at
+    fn call(&self, export: &str, input: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+        let (mut store, instance) = self.instantiate()?;
+        let memory = instance.get_memory(&mut store, "memory").ok_or("`memory` is not a memory")?;
+        let alloc = instance.get_typed_func::<i32, i32>(&mut store, "po_alloc")?;
+        let function = instance.get_typed_func::<(i32, i32), i64>(&mut store, export)?;
+
+        let len = i32::try_from(input.len()).map_err(|_| "input is too large for a plugin")?;
+        let ptr = alloc.call(&mut store, len).map_err(trapped)?;
+        memory.write(&mut store, ptr as u32 as usize, input)?;
+        let packed = function.call(&mut store, (ptr, len)).map_err(trapped)? as u64;
+
+        let (offset, len) = ((packed >> 32) as usize, (packed & 0xffff_ffff) as usize);
+        let output = memory
+            .data(&store)
+            .get(offset..offset.saturating_add(len))
+            .ok_or("output lies outside the plugin's memory")?;
+        Ok(output.to_vec())
+    }
+}
+
+/// A failed call, described by its trap, e.g. running out of fuel, rather than the backtrace
+fn trapped(error: wasmtime::Error) -> Box<dyn Error> {
+    match error.downcast_ref::<Trap>() {
+        Some(trap) => format!("plugin trapped: {}", trap).into(),
+        None => format!("{:#}", error).into(),
+    }
+}
+
+impl ValidationRule for WasmPlugin {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn validate(&self, order: &[u8]) -> Result<Vec<PluginFinding>, Box<dyn Error>> {
+        let output = self.call(VALIDATE_EXPORT, order)?;
+        serde_json::from_slice(&output).map_err(|e| format!("findings are not a JSON array of findings: {}", e).into())
+    }
+}
+
+impl OutputTransformer for WasmPlugin {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn transform(&self, result: &[u8]) -> Result<Value, Box<dyn Error>> {
+        let output = self.call(TRANSFORM_EXPORT, result)?;
+        serde_json::from_slice(&output).map_err(|e| format!("output is not JSON: {}", e).into())
+    }
+}
+
+/// Load every `.wasm` file in `directory`, named after its file stem
+pub fn load_directory(directory: &Path, config: &PluginsConfig) -> Result<Plugins, Box<dyn Error>> {
+    let mut engine_config = Config::new();
+    engine_config.consume_fuel(true);
+    let engine = Engine::new(&engine_config)?;
+
+    let mut paths: Vec<_> = std::fs::read_dir(directory)
+        .map_err(|e| format!("Failed to read plugin directory {}: {}", directory.display(), e))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|extension| extension == "wasm") && path.is_file())
+        .collect();
+    paths.sort();
+
+    let mut plugins = Plugins::new();
+    for path in paths {
+        let name = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+        let plugin = Arc::new(WasmPlugin::load(&engine, &name, &path, config)?);
+        if plugin.exports(VALIDATE_EXPORT) {
+            plugins = plugins.with_rule(plugin.clone());
+        }
+        if plugin.exports(TRANSFORM_EXPORT) {
+            plugins = plugins.with_transformer(plugin);
+        }
+    }
+    Ok(plugins)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Flags every order, echoes its input as the output, and loops forever on an input of one byte
+    const PLUGIN: &str = r#"
+        (module
+          (memory (export "memory") 1)
+          (data (i32.const 0) "[{\"message\":\"Needs a cost center\",\"severity\":\"warning\"}]")
+          (func (export "po_abi_version") (result i32) i32.const 1)
+          (func (export "po_alloc") (param i32) (result i32) i32.const 1024)
+          (func (export "po_validate") (param i32 i32) (result i64) i64.const 56)
+          (func (export "po_transform") (param $ptr i32) (param $len i32) (result i64)
+            (if (i32.eq (local.get $len) (i32.const 1)) (then (loop $forever (br $forever))))
+            (i64.or
+              (i64.shl (i64.extend_i32_u (local.get $ptr)) (i64.const 32))
+              (i64.extend_i32_u (local.get $len)))))
+    "#;
+
+    #[test]
+    fn test_wasm_plugins_validate_transform_and_run_out_of_fuel() {
+        let directory = std::env::temp_dir().join(format!("po-agent-plugins-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&directory).unwrap();
+        // The wasmtime `wat` feature compiles the text format as well as binaries
+        std::fs::write(directory.join("cost-center.wasm"), PLUGIN).unwrap();
+        std::fs::write(directory.join("README.txt"), "not a plugin").unwrap();
+
+        let config = PluginsConfig { directory: Some(directory.clone()), fuel: 1_000_000, ..PluginsConfig::default() };
+        let plugins = load_directory(&directory, &config).unwrap();
+        assert_eq!(plugins.rule_names(), ["cost-center"]);
+        assert_eq!(plugins.transformer_names(), ["cost-center"]);
+
+        let findings = plugins.rules[0].validate(b"{}").unwrap();
+        assert_eq!(findings[0].message, "Needs a cost center");
+        assert_eq!(plugins.transformers[0].transform(br#"{"po_number":"PO-1"}"#).unwrap()["po_number"], "PO-1");
+        let error = plugins.transformers[0].transform(b"1").unwrap_err().to_string();
+        assert!(error.contains("fuel"), "{}", error);
+
+        std::fs::write(directory.join("cost-center.wasm"), PLUGIN.replace("(result i32) i32.const 1)", "(result i32) i32.const 2)")).unwrap();
+        assert!(load_directory(&directory, &config).unwrap_err().to_string().contains("ABI version 2"));
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
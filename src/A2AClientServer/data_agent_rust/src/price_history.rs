@@ -0,0 +1,253 @@
+//! Unit price history per item code
+//!
+//! Every order that passes validation records the unit price of each of its
+//! lines. A new order paying noticeably more for an item than the trailing
+//! average of its earlier orders gets a `price_increase` finding, so a price
+//! hike is caught before it is approved. The history of an item is served by
+//! `GET /analytics/prices/{item_code}`, optionally only for orders whose tasks
+//! carry given labels.
+
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use crate::agent::{ProcessingResult, PurchaseOrder, PurchaseOrderAgent};
+use crate::invoice::round2;
+use crate::labels::{self, Labels};
+use crate::severity::{Check, Findings};
+use crate::store::StoreResult;
+
+/// Prices paid for an item by orders whose tasks carry every label of `selector`, oldest first
+///
+/// Observations are kept by PO number, so an order counts when any task that
+/// processed it matches.
+pub fn labelled(agent: &PurchaseOrderAgent, item_code: &str, selector: &Labels) -> StoreResult<Vec<PriceObservation>> {
+    let observations = agent.price_history().for_item(item_code)?;
+    if selector.is_empty() {
+        return Ok(observations);
+    }
+    let po_numbers: HashSet<String> = agent
+        .task_store()
+        .list()?
+        .iter()
+        .filter(|record| labels::matches(&record.labels, selector))
+        .filter_map(|record| ProcessingResult::from_status(&record.task.status))
+        .map(|result| result.po_number.trim().to_string())
+        .collect();
+    Ok(observations.into_iter().filter(|observation| po_numbers.contains(observation.po_number.trim())).collect())
+}
+
+/// When a unit price is out of line with an item's history
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct PriceHistoryConfig {
+    /// Warn when a unit price exceeds the trailing average by more than this, in percent; unset to never warn
+    pub alert_percent: Option<f64>,
+    /// Most recent orders the trailing average is taken over
+    pub window: usize,
+    /// Orders of an item needed before its prices are compared
+    pub min_observations: usize,
+}
+
+impl Default for PriceHistoryConfig {
+    fn default() -> Self {
+        Self {
+            alert_percent: Some(25.0),
+            window: 10,
+            min_observations: 3,
+        }
+    }
+}
+
+/// The unit price an order paid for an item
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriceObservation {
+    pub po_number: String,
+    pub supplier_name: String,
+    pub unit_price: f64,
+    pub quantity: u32,
+    pub observed_at: DateTime<Utc>,
+}
+
+/// Response of `GET /analytics/prices/{item_code}`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriceHistory {
+    pub item_code: String,
+    /// Oldest first
+    pub observations: Vec<PriceObservation>,
+    pub min_price: f64,
+    pub max_price: f64,
+    /// Average unit price over the most recent `window` orders
+    pub trailing_average: f64,
+}
+
+impl PriceHistory {
+    /// Summary of `observations`, or `None` when there are none
+    pub fn new(item_code: &str, observations: Vec<PriceObservation>, window: usize) -> Option<Self> {
+        if observations.is_empty() {
+            return None;
+        }
+        let prices = || observations.iter().map(|o| o.unit_price);
+        Some(Self {
+            item_code: item_code.trim().to_string(),
+            min_price: prices().fold(f64::INFINITY, f64::min),
+            max_price: prices().fold(f64::NEG_INFINITY, f64::max),
+            trailing_average: trailing_average(&observations, window).unwrap_or_default(),
+            observations,
+        })
+    }
+}
+
+/// Storage of unit prices, keyed case-insensitively by item code
+pub trait PriceHistoryStore: Send + Sync {
+    /// Record the price an order paid, replacing any recorded for the same item and PO number
+    fn record(&self, item_code: &str, observation: PriceObservation) -> StoreResult<()>;
+
+    /// Prices paid for an item, oldest first
+    fn for_item(&self, item_code: &str) -> StoreResult<Vec<PriceObservation>>;
+}
+
+fn key(item_code: &str) -> String {
+    item_code.trim().to_lowercase()
+}
+
+/// In-memory price history used by default
+#[derive(Default)]
+pub struct MemoryPriceHistoryStore {
+    items: DashMap<String, Vec<PriceObservation>>,
+}
+
+impl MemoryPriceHistoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl PriceHistoryStore for MemoryPriceHistoryStore {
+    fn record(&self, item_code: &str, observation: PriceObservation) -> StoreResult<()> {
+        let mut observations = self.items.entry(key(item_code)).or_default();
+        observations.retain(|o| o.po_number != observation.po_number);
+        observations.push(observation);
+        Ok(())
+    }
+
+    fn for_item(&self, item_code: &str) -> StoreResult<Vec<PriceObservation>> {
+        let mut observations = self.items.get(&key(item_code)).map(|o| o.clone()).unwrap_or_default();
+        observations.sort_by_key(|o| o.observed_at);
+        Ok(observations)
+    }
+}
+
+/// Average unit price of the last `window` observations
+fn trailing_average(observations: &[PriceObservation], window: usize) -> Option<f64> {
+    let recent = &observations[observations.len().saturating_sub(window.max(1))..];
+    (!recent.is_empty()).then(|| recent.iter().map(|o| o.unit_price).sum::<f64>() / recent.len() as f64)
+}
+
+/// Record the unit price of every line of an order that passed validation
+///
+/// An order with the same item on several lines records the price of the last one.
+pub fn record(store: &dyn PriceHistoryStore, po: &PurchaseOrder) -> StoreResult<()> {
+    let observed_at = Utc::now();
+    for item in po.items.iter().filter(|item| item.quantity > 0 && item.unit_price > 0.0) {
+        store.record(
+            &item.item_code,
+            PriceObservation {
+                po_number: po.po_number.clone(),
+                supplier_name: po.supplier_name.clone(),
+                unit_price: item.unit_price,
+                quantity: item.quantity,
+                observed_at,
+            },
+        )?;
+    }
+    Ok(())
+}
+
+/// Flag lines whose unit price exceeds the item's trailing average by more than `alert_percent`
+///
+/// Earlier prices of the same PO number are left out, so re-processing an order
+/// does not compare it with itself.
+pub fn validate(
+    store: &dyn PriceHistoryStore,
+    config: &PriceHistoryConfig,
+    po: &PurchaseOrder,
+    findings: &mut Findings,
+) -> StoreResult<()> {
+    let Some(alert_percent) = config.alert_percent else {
+        return Ok(());
+    };
+    for (index, item) in po.items.iter().enumerate() {
+        if item.item_code.trim().is_empty() || item.unit_price <= 0.0 {
+            continue;
+        }
+        let mut observations = store.for_item(&item.item_code)?;
+        observations.retain(|o| o.po_number != po.po_number);
+        if observations.len() < config.min_observations.max(1) {
+            continue;
+        }
+        let Some(average) = trailing_average(&observations, config.window) else {
+            continue;
+        };
+        let increase = (item.unit_price / average - 1.0) * 100.0;
+        if increase > alert_percent {
+            findings.push(Check::PriceIncrease, format!(
+                "Item {} unit price {:.2} for {} is {:.1}% above its trailing average of {:.2}",
+                index + 1, item.unit_price, item.item_code.trim(), increase, round2(average)
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::TaskOptions;
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_price_increases_over_the_trailing_average_warn() {
+        let agent = Arc::new(PurchaseOrderAgent::new());
+        for (n, price) in [4.0, 5.0, 6.0].into_iter().enumerate() {
+            let mut po = crate::testing::valid_order();
+            po.po_number = format!("PO-{}", n + 1);
+            po.items[0].unit_price = price;
+            let labels = if n == 2 { [("source".to_string(), "sap".to_string())].into() } else { Labels::new() };
+            agent.send_task_with_options(crate::testing::message(&po), &TaskOptions { labels, ..TaskOptions::default() }).await.unwrap();
+        }
+
+        let router = crate::server::create_router(agent.clone());
+        let response = router.clone().oneshot(Request::get("/analytics/prices/p-1").body(Body::empty()).unwrap()).await.unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let summary: PriceHistory = serde_json::from_slice(&body).unwrap();
+        assert_eq!((summary.min_price, summary.max_price, summary.trailing_average), (4.0, 6.0, 5.0));
+        assert_eq!(summary.observations.iter().map(|o| o.po_number.as_str()).collect::<Vec<_>>(), ["PO-1", "PO-2", "PO-3"]);
+        let unknown = router.clone().oneshot(Request::get("/analytics/prices/X-9").body(Body::empty()).unwrap()).await.unwrap();
+        assert_eq!(unknown.status(), StatusCode::NOT_FOUND);
+        let response = router.clone().oneshot(Request::get("/analytics/prices/p-1?labels=source:sap").body(Body::empty()).unwrap()).await.unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let labelled: PriceHistory = serde_json::from_slice(&body).unwrap();
+        assert_eq!(labelled.observations.iter().map(|o| o.po_number.as_str()).collect::<Vec<_>>(), ["PO-3"]);
+        let invalid = router.oneshot(Request::get("/analytics/prices/p-1?labels=sap").body(Body::empty()).unwrap()).await.unwrap();
+        assert_eq!(invalid.status(), StatusCode::BAD_REQUEST);
+
+        let mut po = crate::testing::valid_order();
+        po.po_number = "PO-4".to_string();
+        let (_, warnings) = agent.validate_purchase_order(&po);
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        po.items[0].unit_price = 6.5;
+        let (_, warnings) = agent.validate_purchase_order(&po);
+        assert!(warnings.contains(&"Item 1 unit price 6.50 for P-1 is 30.0% above its trailing average of 5.00".to_string()));
+
+        // Re-processing an earlier order leaves its own price out, and two orders are too few to compare
+        po.po_number = "PO-3".to_string();
+        po.items[0].unit_price = 6.0;
+        let (_, warnings) = agent.validate_purchase_order(&po);
+        assert!(warnings.iter().all(|w| !w.contains("trailing average")), "{:?}", warnings);
+    }
+}
@@ -0,0 +1,301 @@
+//! Background processing of submitted tasks
+//!
+//! With background processing enabled, `POST /agent/task` stores the task as
+//! submitted, queues it and answers straight away; workers process queued tasks
+//! in the background. The queue is bounded, so submissions are shed with `503`
+//! during a traffic spike instead of piling up in memory.
+
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::sync::{mpsc, OwnedSemaphorePermit, Semaphore};
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+use crate::agent::PurchaseOrderAgent;
+use crate::metrics::Metrics;
+
+const DEPTH_METRIC: &str = "po_agent_submission_queue_depth";
+const DEPTH_HELP: &str = "Submitted tasks waiting for a processing worker";
+
+/// How submitted tasks are processed
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ProcessingConfig {
+    /// Queue submissions and process them in the background instead of while the client waits
+    pub background: bool,
+    /// Submissions waiting for a worker before new ones are rejected
+    pub queue_depth: usize,
+    /// Tasks processed at the same time
+    pub workers: usize,
+    /// `Retry-After` sent with submissions rejected because the queue is full
+    pub retry_after_seconds: u64,
+}
+
+impl Default for ProcessingConfig {
+    fn default() -> Self {
+        Self {
+            background: false,
+            queue_depth: 1000,
+            workers: 4,
+            retry_after_seconds: 5,
+        }
+    }
+}
+
+/// Which lane a queued task waits in; workers take urgent tasks first, low ones last
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    /// Bulk work, e.g. imports, processed when nothing else is waiting
+    Low,
+    #[default]
+    Normal,
+    /// Jumps ahead of every normal and low task, e.g. at month end
+    Urgent,
+}
+
+impl Priority {
+    pub const ALL: [Priority; 3] = [Priority::Low, Priority::Normal, Priority::Urgent];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Priority::Low => "low",
+            Priority::Normal => "normal",
+            Priority::Urgent => "urgent",
+        }
+    }
+
+    pub fn is_normal(&self) -> bool {
+        *self == Priority::Normal
+    }
+}
+
+impl FromStr for Priority {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Priority::ALL
+            .into_iter()
+            .find(|priority| priority.as_str() == s)
+            .ok_or_else(|| format!("unknown priority {:?}, expected low, normal or urgent", s))
+    }
+}
+
+/// A queued task, holding its place in the queue until a worker takes it
+struct Queued {
+    task_id: String,
+    _place: OwnedSemaphorePermit,
+}
+
+/// The bounded queue of submitted tasks waiting for a worker, one lane per priority
+///
+/// `queue_depth` bounds all lanes together.
+pub struct SubmissionQueue {
+    places: Arc<Semaphore>,
+    lanes: [mpsc::UnboundedSender<Queued>; 3],
+    waiting: Arc<[AtomicUsize; 3]>,
+    queue_depth: usize,
+    retry_after_seconds: u64,
+    metrics: Arc<Metrics>,
+}
+
+/// A reserved place in the queue, so a task is only stored once it is sure to be processed
+pub struct QueueSlot<'a> {
+    place: OwnedSemaphorePermit,
+    queue: &'a SubmissionQueue,
+}
+
+/// The consuming end of a `SubmissionQueue`
+pub struct SubmissionReceiver {
+    lanes: [mpsc::UnboundedReceiver<Queued>; 3],
+    waiting: Arc<[AtomicUsize; 3]>,
+}
+
+/// Create the queue and the receiver its workers consume
+pub fn queue(config: &ProcessingConfig, metrics: Arc<Metrics>) -> (SubmissionQueue, SubmissionReceiver) {
+    let (low, low_rx) = mpsc::unbounded_channel();
+    let (normal, normal_rx) = mpsc::unbounded_channel();
+    let (urgent, urgent_rx) = mpsc::unbounded_channel();
+    let queue_depth = config.queue_depth.max(1);
+    let waiting = Arc::new([AtomicUsize::new(0), AtomicUsize::new(0), AtomicUsize::new(0)]);
+    let queue = SubmissionQueue {
+        places: Arc::new(Semaphore::new(queue_depth)),
+        lanes: [low, normal, urgent],
+        waiting: waiting.clone(),
+        queue_depth,
+        retry_after_seconds: config.retry_after_seconds,
+        metrics,
+    };
+    queue.record_depth();
+    (queue, SubmissionReceiver { lanes: [low_rx, normal_rx, urgent_rx], waiting })
+}
+
+impl SubmissionQueue {
+    /// Reserve a place for one task, or `None` when the queue is full
+    pub fn reserve(&self) -> Option<QueueSlot<'_>> {
+        match self.places.clone().try_acquire_owned() {
+            Ok(place) => Some(QueueSlot { place, queue: self }),
+            Err(_) => {
+                self.metrics.increment(
+                    "po_agent_submissions_rejected_total",
+                    "Task submissions rejected because the processing queue was full",
+                    &[],
+                    1,
+                );
+                None
+            }
+        }
+    }
+
+    /// Tasks currently waiting for a worker, or with a place reserved
+    pub fn depth(&self) -> usize {
+        self.queue_depth - self.places.available_permits()
+    }
+
+    /// Seconds clients are asked to wait before resubmitting a rejected task
+    pub fn retry_after_seconds(&self) -> u64 {
+        self.retry_after_seconds
+    }
+
+    /// Update the depth gauge of every lane
+    pub fn record_depth(&self) {
+        for priority in Priority::ALL {
+            let waiting = self.waiting[priority as usize].load(Ordering::Relaxed);
+            self.metrics.set_gauge(DEPTH_METRIC, DEPTH_HELP, &[("priority", priority.as_str())], waiting as f64);
+        }
+    }
+}
+
+impl QueueSlot<'_> {
+    /// Queue the stored task in the reserved place, in its priority's lane
+    pub fn send(self, task_id: String, priority: Priority) {
+        let queue = self.queue;
+        let queued = Queued { task_id, _place: self.place };
+        // The receiver lives as long as the workers; without them the task just stays submitted
+        queue.waiting[priority as usize].fetch_add(1, Ordering::Relaxed);
+        let _ = queue.lanes[priority as usize].send(queued);
+        queue.record_depth();
+    }
+}
+
+impl SubmissionReceiver {
+    /// The next task to process, from the most urgent lane with one waiting
+    pub async fn recv(&mut self) -> Option<String> {
+        let [low, normal, urgent] = &mut self.lanes;
+        let (priority, queued) = tokio::select! {
+            biased;
+            Some(queued) = urgent.recv() => (Priority::Urgent, queued),
+            Some(queued) = normal.recv() => (Priority::Normal, queued),
+            Some(queued) = low.recv() => (Priority::Low, queued),
+            else => return None,
+        };
+        self.waiting[priority as usize].fetch_sub(1, Ordering::Relaxed);
+        Some(queued.task_id)
+    }
+}
+
+/// Process queued tasks, `workers` at a time, until the queue is closed
+pub fn spawn_workers(
+    agent: Arc<PurchaseOrderAgent>,
+    config: &ProcessingConfig,
+    mut receiver: SubmissionReceiver,
+) -> JoinHandle<()> {
+    info!(queue_depth = config.queue_depth, workers = config.workers, "background task processing enabled");
+
+    let workers = Arc::new(Semaphore::new(config.workers.max(1)));
+    tokio::spawn(async move {
+        loop {
+            let Ok(worker) = workers.clone().acquire_owned().await else {
+                return;
+            };
+            let Some(task_id) = receiver.recv().await else {
+                return;
+            };
+            if let Some(queue) = agent.submission_queue() {
+                queue.record_depth();
+            }
+
+            let agent = agent.clone();
+            tokio::spawn(async move {
+                if let Err(e) = agent.process_submitted_task(&task_id).await {
+                    warn!(task_id = %task_id, error = %e, "background processing failed");
+                }
+                drop(worker);
+            });
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::TaskOptions;
+    use a2a::{A2AProtocol, Message, Part, TaskState};
+
+    fn message() -> Message {
+        Message {
+            role: "user".to_string(),
+            parts: vec![Part::Text { text: "not a purchase order".to_string() }],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_full_queue_sheds_submissions_until_drained() {
+        let config = ProcessingConfig { background: true, queue_depth: 2, workers: 1, retry_after_seconds: 7 };
+        let metrics = Arc::new(Metrics::new());
+        let (queue, receiver) = queue(&config, metrics.clone());
+        let agent = Arc::new(PurchaseOrderAgent::new().with_submission_queue(queue));
+        let queue = agent.submission_queue().unwrap();
+
+        let mut task_ids = Vec::new();
+        for _ in 0..2 {
+            let slot = queue.reserve().unwrap();
+            let task = agent.accept_task(message(), &TaskOptions::default()).unwrap();
+            assert!(matches!(task.status.state, TaskState::Submitted));
+            slot.send(task.id.clone(), Priority::Normal);
+            task_ids.push(task.id);
+        }
+        assert!(queue.reserve().is_none());
+        assert_eq!(queue.depth(), 2);
+        assert_eq!(queue.retry_after_seconds(), 7);
+        assert_eq!(metrics.value(DEPTH_METRIC, &[("priority", "normal")]), Some(2.0));
+        assert_eq!(metrics.value("po_agent_submissions_rejected_total", &[]), Some(1.0));
+
+        let _workers = spawn_workers(agent.clone(), &config, receiver);
+        for task_id in &task_ids {
+            let mut task = agent.get_task(task_id).await.unwrap();
+            for _ in 0..100 {
+                if !matches!(task.status.state, TaskState::Submitted) {
+                    break;
+                }
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+                task = agent.get_task(task_id).await.unwrap();
+            }
+            assert!(matches!(task.status.state, TaskState::Failed), "{:?}", task.status.state);
+        }
+        assert_eq!(queue.depth(), 0);
+        assert!(queue.reserve().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_urgent_tasks_are_taken_first() {
+        let config = ProcessingConfig { queue_depth: 3, ..ProcessingConfig::default() };
+        let metrics = Arc::new(Metrics::new());
+        let (queue, mut receiver) = queue(&config, metrics.clone());
+        for (task_id, priority) in [("bulk", Priority::Low), ("regular", Priority::Normal), ("month-end", Priority::Urgent)] {
+            queue.reserve().unwrap().send(task_id.to_string(), priority);
+        }
+        assert!(queue.reserve().is_none());
+        assert_eq!(metrics.value(DEPTH_METRIC, &[("priority", "urgent")]), Some(1.0));
+
+        assert_eq!(receiver.recv().await.as_deref(), Some("month-end"));
+        assert_eq!(receiver.recv().await.as_deref(), Some("regular"));
+        assert!(queue.reserve().is_some());
+        assert_eq!(receiver.recv().await.as_deref(), Some("bulk"));
+        assert_eq!(queue.depth(), 0);
+        assert_eq!("urgent".parse::<Priority>(), Ok(Priority::Urgent));
+        assert!("asap".parse::<Priority>().is_err());
+    }
+}
@@ -0,0 +1,244 @@
+use axum::{
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::warn;
+
+use crate::auth::AuthContext;
+use crate::server::AppState;
+
+/// Actions that can be granted to a role
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Permission {
+    /// Submit and cancel purchase order tasks
+    SubmitTasks,
+    /// Read task status and results
+    ReadTasks,
+    /// Approve or reject purchase orders awaiting approval
+    ApproveTasks,
+    /// Access admin and analytics endpoints
+    Admin,
+}
+
+/// Role → permission mapping
+///
+/// A configured `[rbac.roles]` table replaces the defaults entirely, so a
+/// deployment never inherits grants it did not write down.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct RbacConfig {
+    pub roles: HashMap<String, Vec<Permission>>,
+}
+
+impl Default for RbacConfig {
+    fn default() -> Self {
+        let roles = HashMap::from([
+            (
+                "submitter".to_string(),
+                vec![Permission::SubmitTasks, Permission::ReadTasks],
+            ),
+            (
+                "approver".to_string(),
+                vec![Permission::ReadTasks, Permission::ApproveTasks],
+            ),
+            (
+                "admin".to_string(),
+                vec![
+                    Permission::SubmitTasks,
+                    Permission::ReadTasks,
+                    Permission::ApproveTasks,
+                    Permission::Admin,
+                ],
+            ),
+        ]);
+        Self { roles }
+    }
+}
+
+impl RbacConfig {
+    /// Whether any of the caller's roles grants the permission
+    pub fn is_allowed(&self, context: &AuthContext, permission: Permission) -> bool {
+        context.roles.iter().any(|role| {
+            self.roles
+                .get(role)
+                .is_some_and(|granted| granted.contains(&permission))
+        })
+    }
+}
+
+/// Middleware enforcing a permission on the routes it wraps
+///
+/// Must run after [`crate::auth::require_auth`]. Without authentication configured
+/// there is no caller identity to check, so every request is allowed.
+pub async fn authorize(
+    State((state, permission)): State<(Arc<AppState>, Permission)>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if state.jwt.is_none() {
+        return next.run(request).await;
+    }
+
+    let Some(context) = request.extensions().get::<AuthContext>() else {
+        return StatusCode::UNAUTHORIZED.into_response();
+    };
+
+    if !state.rbac.is_allowed(context, permission) {
+        warn!(
+            subject = %context.subject,
+            roles = ?context.roles,
+            permission = ?permission,
+            "permission denied"
+        );
+        return (
+            StatusCode::FORBIDDEN,
+            axum::Json(serde_json::json!({
+                "error": format!("Missing permission {:?}", permission)
+            })),
+        )
+            .into_response();
+    }
+
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::AuthConfig;
+    use crate::config::ServerConfig;
+    use crate::{create_router_with_config, PurchaseOrderAgent};
+    use axum::body::Body;
+    use axum::http::{header, Request as HttpRequest};
+    use jsonwebtoken::{encode, EncodingKey, Header};
+    use tower::ServiceExt;
+
+    const SECRET: &str = "rbac-test-secret";
+
+    fn context(roles: &[&str]) -> AuthContext {
+        AuthContext {
+            subject: "tester".to_string(),
+            roles: roles.iter().map(|r| r.to_string()).collect(),
+        }
+    }
+
+    fn bearer(roles: &[&str]) -> String {
+        let claims = serde_json::json!({
+            "sub": "tester",
+            "roles": roles,
+            "exp": chrono::Utc::now().timestamp() + 3600,
+        });
+        let token = encode(&Header::default(), &claims, &EncodingKey::from_secret(SECRET.as_bytes())).unwrap();
+        format!("Bearer {}", token)
+    }
+
+    fn router() -> axum::Router {
+        let config = ServerConfig {
+            auth: AuthConfig {
+                jwt_secret: Some(SECRET.to_string()),
+                ..AuthConfig::default()
+            },
+            ..ServerConfig::default()
+        };
+        create_router_with_config(Arc::new(PurchaseOrderAgent::new()), &config)
+    }
+
+    #[test]
+    fn test_default_role_mapping() {
+        let rbac = RbacConfig::default();
+
+        assert!(rbac.is_allowed(&context(&["submitter"]), Permission::SubmitTasks));
+        assert!(!rbac.is_allowed(&context(&["submitter"]), Permission::ApproveTasks));
+        assert!(rbac.is_allowed(&context(&["approver"]), Permission::ApproveTasks));
+        assert!(!rbac.is_allowed(&context(&["approver"]), Permission::Admin));
+        assert!(rbac.is_allowed(&context(&["admin"]), Permission::Admin));
+        assert!(!rbac.is_allowed(&context(&["unknown"]), Permission::ReadTasks));
+    }
+
+    #[test]
+    fn test_configured_roles_replace_defaults() {
+        let config = ServerConfig::from_toml(
+            r#"
+            [rbac.roles]
+            buyer = ["submit_tasks"]
+            "#,
+        )
+        .unwrap();
+
+        assert!(config.rbac.is_allowed(&context(&["buyer"]), Permission::SubmitTasks));
+        assert!(!config.rbac.is_allowed(&context(&["submitter"]), Permission::SubmitTasks));
+    }
+
+    #[tokio::test]
+    async fn test_task_submission_requires_submit_permission() {
+        let submit = |auth: Option<String>| {
+            let mut builder = HttpRequest::post("/agent/task").header(header::CONTENT_TYPE, "application/json");
+            if let Some(auth) = auth {
+                builder = builder.header(header::AUTHORIZATION, auth);
+            }
+            builder
+                .body(Body::from(r#"{"message":{"role":"user","parts":[{"type":"text","text":"hi"}]}}"#))
+                .unwrap()
+        };
+
+        let response = router().oneshot(submit(None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = router().oneshot(submit(Some(bearer(&["approver"])))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let response = router().oneshot(submit(Some(bearer(&["submitter"])))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_read_only_callers_cannot_change_saved_searches() {
+        let mut config = ServerConfig::from_toml(
+            r#"
+            [rbac.roles]
+            viewer = ["read_tasks"]
+            submitter = ["submit_tasks", "read_tasks"]
+            "#,
+        )
+        .unwrap();
+        config.auth.jwt_secret = Some(SECRET.to_string());
+        let router = create_router_with_config(Arc::new(PurchaseOrderAgent::new()), &config);
+        let request = |method: &str, path: &str, roles: &[&str]| {
+            HttpRequest::builder()
+                .method(method)
+                .uri(path)
+                .header(header::AUTHORIZATION, bearer(roles))
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(r#"{"name": "failed", "filter": {"state": "failed"}}"#))
+                .unwrap()
+        };
+
+        for (method, path) in [("POST", "/agent/searches"), ("DELETE", "/agent/searches/failed")] {
+            let response = router.clone().oneshot(request(method, path, &["viewer"])).await.unwrap();
+            assert_eq!(response.status(), StatusCode::FORBIDDEN, "{} {}", method, path);
+        }
+        let response = router.clone().oneshot(request("GET", "/agent/searches", &["viewer"])).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = router.clone().oneshot(request("POST", "/agent/searches", &["submitter"])).await.unwrap();
+        assert!(response.status().is_success(), "{}", response.status());
+        let response = router.oneshot(request("DELETE", "/agent/searches/failed", &["submitter"])).await.unwrap();
+        assert!(response.status().is_success(), "{}", response.status());
+    }
+
+    #[tokio::test]
+    async fn test_analytics_require_admin_permission() {
+        for path in ["/analytics/prices/P-1", "/reports/monthly-close"] {
+            let get = |roles: &[&str]| HttpRequest::get(path).header(header::AUTHORIZATION, bearer(roles)).body(Body::empty()).unwrap();
+            let response = router().oneshot(get(&["submitter"])).await.unwrap();
+            assert_eq!(response.status(), StatusCode::FORBIDDEN, "{}", path);
+            let response = router().oneshot(get(&["admin"])).await.unwrap();
+            assert_ne!(response.status(), StatusCode::FORBIDDEN, "{}", path);
+        }
+    }
+}
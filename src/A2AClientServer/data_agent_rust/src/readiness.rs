@@ -0,0 +1,228 @@
+//! Readiness of the agent and each downstream it is configured to call
+//!
+//! Every integration reports the outcome of its calls to [`Dependencies`]:
+//! webhook deliveries, delegations, event publishers such as SMTP, Kafka and
+//! NATS, and queue intake. `GET /ready` lists each configured one as up or down
+//! with its last error, so on-call can see which dependency broke. A downstream
+//! is down after a failed call, or while its circuit is open, until a call to
+//! it succeeds again. The task store is checked on every request.
+
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use serde::Serialize;
+
+use crate::agent::PurchaseOrderAgent;
+use crate::breaker::BreakerState;
+use crate::config::ServerConfig;
+
+/// Task ID looked up to check that the task store answers
+const PROBE_TASK_ID: &str = "readiness-probe";
+
+/// What kind of downstream a dependency is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DependencyKind {
+    Store,
+    Webhook,
+    Delegation,
+    Smtp,
+    Broker,
+    Intake,
+}
+
+/// A downstream the server is configured to call, named as in `/health` and logs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dependency {
+    pub name: String,
+    pub kind: DependencyKind,
+}
+
+impl Dependency {
+    fn new(name: impl Into<String>, kind: DependencyKind) -> Self {
+        Self { name: name.into(), kind }
+    }
+}
+
+/// Outcome of the calls made to one downstream
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DependencyHealth {
+    /// Failed calls since the last one that succeeded
+    pub consecutive_failures: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_success_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error_at: Option<DateTime<Utc>>,
+}
+
+/// Call outcomes of every downstream, keyed by name
+#[derive(Debug, Default)]
+pub struct Dependencies {
+    health: DashMap<String, DependencyHealth>,
+}
+
+impl Dependencies {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A call to `name` went through
+    pub fn record_success(&self, name: &str) {
+        let mut health = self.health.entry(name.to_string()).or_default();
+        health.consecutive_failures = 0;
+        health.last_success_at = Some(Utc::now());
+    }
+
+    /// A call to `name` failed with `error`
+    pub fn record_failure(&self, name: &str, error: impl ToString) {
+        let mut health = self.health.entry(name.to_string()).or_default();
+        health.consecutive_failures += 1;
+        health.last_error = Some(error.to_string());
+        health.last_error_at = Some(Utc::now());
+    }
+
+    pub fn get(&self, name: &str) -> Option<DependencyHealth> {
+        self.health.get(name).map(|health| health.clone())
+    }
+}
+
+/// The downstreams `config` sets up, in the order they are listed
+///
+/// Delegation targets found through the agent registry are only known once
+/// called, so they are listed by [`check`] as their circuits appear.
+pub fn configured(config: &ServerConfig) -> Vec<Dependency> {
+    let mut dependencies = vec![Dependency::new("store", DependencyKind::Store)];
+    for endpoint in &config.webhooks.endpoints {
+        dependencies.push(Dependency::new(format!("webhook:{}", endpoint.name), DependencyKind::Webhook));
+    }
+    if let Some(url) = &config.delegation.url {
+        dependencies.push(Dependency::new(format!("delegation:{}", url), DependencyKind::Delegation));
+    }
+    if config.email.smtp.is_some() {
+        dependencies.push(Dependency::new("email", DependencyKind::Smtp));
+    }
+    if let Some(kafka) = &config.events.kafka {
+        dependencies.push(Dependency::new(format!("kafka:{}", kafka.topic), DependencyKind::Broker));
+    }
+    if config.events.nats.is_some() {
+        dependencies.push(Dependency::new("nats", DependencyKind::Broker));
+    }
+    if config.intake.amqp.is_some() {
+        dependencies.push(Dependency::new("intake:amqp", DependencyKind::Intake));
+    }
+    if config.intake.sqs.is_some() {
+        dependencies.push(Dependency::new("intake:sqs", DependencyKind::Intake));
+    }
+    dependencies
+}
+
+/// Overall readiness
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReadinessStatus {
+    Ready,
+    /// A downstream is down; the agent still accepts work
+    Degraded,
+    /// The task store is down, so no work can be accepted
+    NotReady,
+}
+
+/// One dependency as reported on `/ready`
+#[derive(Debug, Clone, Serialize)]
+pub struct DependencyReport {
+    pub name: String,
+    pub kind: DependencyKind,
+    pub up: bool,
+    /// State of the downstream's circuit breaker, once it has one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub circuit: Option<BreakerState>,
+    #[serde(flatten)]
+    pub health: DependencyHealth,
+}
+
+/// Response of `/ready`
+#[derive(Debug, Clone, Serialize)]
+pub struct Readiness {
+    pub status: ReadinessStatus,
+    pub checked_at: DateTime<Utc>,
+    pub dependencies: Vec<DependencyReport>,
+}
+
+/// Check the task store and report every dependency in `configured`
+pub fn check(agent: &PurchaseOrderAgent, configured: &[Dependency]) -> Readiness {
+    match agent.task_store().get(PROBE_TASK_ID) {
+        Ok(_) => agent.dependencies().record_success("store"),
+        Err(e) => agent.dependencies().record_failure("store", e),
+    }
+
+    let circuits = agent.breakers().statuses();
+    let mut dependencies: Vec<Dependency> = configured.to_vec();
+    for name in circuits.keys() {
+        if name.starts_with("delegation:") && !dependencies.iter().any(|dependency| &dependency.name == name) {
+            dependencies.push(Dependency::new(name.clone(), DependencyKind::Delegation));
+        }
+    }
+
+    let reports: Vec<DependencyReport> = dependencies
+        .into_iter()
+        .map(|dependency| {
+            let health = agent.dependencies().get(&dependency.name).unwrap_or_default();
+            let circuit = circuits.get(&dependency.name).map(|status| status.state);
+            DependencyReport {
+                up: health.consecutive_failures == 0 && circuit != Some(BreakerState::Open),
+                name: dependency.name,
+                kind: dependency.kind,
+                circuit,
+                health,
+            }
+        })
+        .collect();
+
+    let status = if reports.iter().any(|report| report.kind == DependencyKind::Store && !report.up) {
+        ReadinessStatus::NotReady
+    } else if reports.iter().any(|report| !report.up) {
+        ReadinessStatus::Degraded
+    } else {
+        ReadinessStatus::Ready
+    };
+    Readiness { status, checked_at: Utc::now(), dependencies: reports }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_failed_downstreams_are_down_until_they_recover() {
+        let agent = PurchaseOrderAgent::new();
+        let config: ServerConfig = toml::from_str(
+            r#"
+            [[webhooks.endpoints]]
+            name = "erp"
+            url = "http://erp.invalid/hook"
+
+            [email.smtp]
+            host = "smtp.invalid"
+            "#,
+        )
+        .unwrap();
+        let configured = configured(&config);
+        let names: Vec<&str> = configured.iter().map(|dependency| dependency.name.as_str()).collect();
+        assert_eq!(names, ["store", "webhook:erp", "email"]);
+
+        let readiness = check(&agent, &configured);
+        assert_eq!(readiness.status, ReadinessStatus::Ready);
+        assert!(readiness.dependencies[0].health.last_success_at.is_some());
+
+        agent.dependencies().record_failure("email", "connection refused");
+        let readiness = check(&agent, &configured);
+        assert_eq!(readiness.status, ReadinessStatus::Degraded);
+        let email = &readiness.dependencies[2];
+        assert!(!email.up);
+        assert_eq!(email.health.last_error.as_deref(), Some("connection refused"));
+
+        agent.dependencies().record_success("email");
+        assert_eq!(check(&agent, &configured).status, ReadinessStatus::Ready);
+    }
+}
@@ -0,0 +1,309 @@
+use a2a::TaskState;
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use uuid::Uuid;
+
+use crate::agent::{PurchaseOrder, PurchaseOrderAgent, ProcessingResult};
+use crate::events::TaskEventKind;
+use crate::store::{StoreError, StoreResult};
+
+/// Items received against a processed purchase order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GoodsReceipt {
+    pub id: String,
+    pub task_id: String,
+    /// Supplier's delivery note or the warehouse's receipt reference
+    pub receipt_number: Option<String>,
+    pub lines: Vec<ReceiptLine>,
+    pub note: Option<String>,
+    pub received_at: DateTime<Utc>,
+}
+
+/// Quantity of one item received
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReceiptLine {
+    pub item_code: String,
+    pub quantity: u32,
+}
+
+/// Body of `POST /agent/task/{id}/receipts`
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReceiptRequest {
+    #[serde(default)]
+    pub receipt_number: Option<String>,
+    pub lines: Vec<ReceiptLine>,
+    #[serde(default)]
+    pub note: Option<String>,
+    /// Defaults to now
+    #[serde(default)]
+    pub received_at: Option<DateTime<Utc>>,
+}
+
+/// Storage of goods receipts
+pub trait ReceiptStore: Send + Sync {
+    fn put(&self, receipt: GoodsReceipt) -> StoreResult<()>;
+
+    /// Receipts recorded against a task, oldest first
+    fn for_task(&self, task_id: &str) -> StoreResult<Vec<GoodsReceipt>>;
+
+    /// Drop the receipts of tasks not matching `keep`, returning how many tasks were dropped
+    fn retain_tasks(&self, keep: &dyn Fn(&str) -> bool) -> StoreResult<usize>;
+}
+
+/// In-memory receipt store used by default
+#[derive(Default)]
+pub struct MemoryReceiptStore {
+    receipts: DashMap<String, Vec<GoodsReceipt>>,
+}
+
+impl MemoryReceiptStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl ReceiptStore for MemoryReceiptStore {
+    fn put(&self, receipt: GoodsReceipt) -> StoreResult<()> {
+        self.receipts.entry(receipt.task_id.clone()).or_default().push(receipt);
+        Ok(())
+    }
+
+    fn for_task(&self, task_id: &str) -> StoreResult<Vec<GoodsReceipt>> {
+        let mut receipts = self.receipts.get(task_id).map(|r| r.clone()).unwrap_or_default();
+        receipts.sort_by_key(|r| r.received_at);
+        Ok(receipts)
+    }
+
+    fn retain_tasks(&self, keep: &dyn Fn(&str) -> bool) -> StoreResult<usize> {
+        let before = self.receipts.len();
+        self.receipts.retain(|task_id, _| keep(task_id));
+        Ok(before - self.receipts.len())
+    }
+}
+
+/// How much of a purchase order has arrived
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReceivingStatus {
+    /// Nothing received yet
+    Open,
+    PartiallyReceived,
+    /// Every line received in full
+    Received,
+}
+
+/// Ordered, received and still open quantity of one item code
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenLine {
+    pub item_code: String,
+    pub ordered_quantity: u32,
+    pub received_quantity: u32,
+    pub open_quantity: u32,
+}
+
+/// Receiving progress of a processed purchase order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenOrderStatus {
+    pub task_id: String,
+    pub po_number: String,
+    pub status: ReceivingStatus,
+    pub lines: Vec<OpenLine>,
+    pub receipts: Vec<GoodsReceipt>,
+}
+
+/// Why a receipt could not be recorded or an order's status read
+#[derive(Debug)]
+pub enum ReceiptError {
+    /// No task with that ID
+    NotFound(String),
+    /// The task or receipt does not allow receiving
+    Invalid(String),
+    Store(StoreError),
+}
+
+impl fmt::Display for ReceiptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReceiptError::NotFound(message) | ReceiptError::Invalid(message) => f.write_str(message),
+            ReceiptError::Store(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for ReceiptError {}
+
+impl From<StoreError> for ReceiptError {
+    fn from(e: StoreError) -> Self {
+        ReceiptError::Store(e)
+    }
+}
+
+/// Quantity per lower-cased item code
+fn quantities<'a>(lines: impl IntoIterator<Item = (&'a str, u32)>) -> BTreeMap<String, u32> {
+    let mut totals = BTreeMap::new();
+    for (code, quantity) in lines {
+        *totals.entry(code.trim().to_lowercase()).or_default() += quantity;
+    }
+    totals
+}
+
+/// Total received quantity per lower-cased item code across receipts
+pub fn received_quantities(receipts: &[GoodsReceipt]) -> HashMap<String, u32> {
+    quantities(receipts.iter().flat_map(|r| r.lines.iter().map(|l| (l.item_code.as_str(), l.quantity))))
+        .into_iter()
+        .collect()
+}
+
+/// The purchase order a completed processing task was created from
+fn processed_order(agent: &PurchaseOrderAgent, task_id: &str) -> Result<PurchaseOrder, ReceiptError> {
+    let record = agent
+        .task_store()
+        .get(task_id)?
+        .ok_or_else(|| ReceiptError::NotFound(format!("Task {} not found", task_id)))?;
+    let processed = matches!(record.task.status.state, TaskState::Completed)
+        && ProcessingResult::from_status(&record.task.status).is_some();
+    if !processed {
+        return Err(ReceiptError::Invalid(format!(
+            "Task {} is {} and has no processed purchase order to receive against",
+            task_id,
+            record.state_name()
+        )));
+    }
+    agent
+        .processed_purchase_order(&record)
+        .map_err(|e| ReceiptError::Invalid(e.to_string()))
+}
+
+fn status_of(task_id: &str, po: &PurchaseOrder, receipts: Vec<GoodsReceipt>) -> OpenOrderStatus {
+    let ordered = quantities(po.items.iter().map(|i| (i.item_code.as_str(), i.quantity)));
+    let received = received_quantities(&receipts);
+
+    let lines: Vec<OpenLine> = ordered
+        .into_iter()
+        .map(|(item_code, ordered_quantity)| {
+            let received_quantity = received.get(&item_code).copied().unwrap_or(0);
+            OpenLine {
+                item_code,
+                ordered_quantity,
+                received_quantity,
+                open_quantity: ordered_quantity.saturating_sub(received_quantity),
+            }
+        })
+        .collect();
+
+    let status = if lines.iter().all(|l| l.open_quantity == 0) {
+        ReceivingStatus::Received
+    } else if lines.iter().all(|l| l.received_quantity == 0) {
+        ReceivingStatus::Open
+    } else {
+        ReceivingStatus::PartiallyReceived
+    };
+
+    OpenOrderStatus {
+        task_id: task_id.to_string(),
+        po_number: po.po_number.clone(),
+        status,
+        lines,
+        receipts,
+    }
+}
+
+/// Receiving progress of a processed purchase order
+pub fn open_order_status(agent: &PurchaseOrderAgent, task_id: &str) -> Result<OpenOrderStatus, ReceiptError> {
+    let po = processed_order(agent, task_id)?;
+    Ok(status_of(task_id, &po, agent.receipts().for_task(task_id)?))
+}
+
+/// Record a receipt against a processed purchase order
+///
+/// Every line must name an ordered item and may not receive more than is still open.
+pub fn record_receipt(
+    agent: &PurchaseOrderAgent,
+    task_id: &str,
+    request: ReceiptRequest,
+) -> Result<OpenOrderStatus, ReceiptError> {
+    let po = processed_order(agent, task_id)?;
+    let current = status_of(task_id, &po, agent.receipts().for_task(task_id)?);
+
+    if request.lines.is_empty() || request.lines.iter().all(|l| l.quantity == 0) {
+        return Err(ReceiptError::Invalid("Receipt must receive at least one item".to_string()));
+    }
+    let receiving = quantities(request.lines.iter().map(|l| (l.item_code.as_str(), l.quantity)));
+    for (code, quantity) in &receiving {
+        let line = current
+            .lines
+            .iter()
+            .find(|l| &l.item_code == code)
+            .ok_or_else(|| ReceiptError::Invalid(format!("Item {} is not on purchase order {}", code, po.po_number)))?;
+        if *quantity > line.open_quantity {
+            return Err(ReceiptError::Invalid(format!(
+                "Item {}: receiving {} but only {} of {} is still open",
+                code, quantity, line.open_quantity, line.ordered_quantity
+            )));
+        }
+    }
+
+    let receipt = GoodsReceipt {
+        id: Uuid::new_v4().to_string(),
+        task_id: task_id.to_string(),
+        receipt_number: request.receipt_number,
+        lines: request.lines,
+        note: request.note,
+        received_at: request.received_at.unwrap_or_else(Utc::now),
+    };
+    agent.receipts().put(receipt.clone())?;
+    agent
+        .record_event(
+            task_id,
+            TaskEventKind::GoodsReceived { receipt_id: receipt.id.clone(), lines: receipt.lines.clone() },
+        )
+        .map_err(|e| ReceiptError::Invalid(e.to_string()))?;
+
+    open_order_status(agent, task_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use a2a::A2AProtocol;
+
+    fn line(code: &str, quantity: u32) -> ReceiptLine {
+        ReceiptLine { item_code: code.to_string(), quantity }
+    }
+
+    fn receipt(lines: Vec<ReceiptLine>) -> ReceiptRequest {
+        ReceiptRequest { receipt_number: None, lines, note: None, received_at: None }
+    }
+
+    async fn processed_task(agent: &PurchaseOrderAgent) -> String {
+        agent.send_task(crate::testing::message(&crate::testing::valid_order())).await.unwrap().id
+    }
+
+    #[tokio::test]
+    async fn test_partial_and_full_receipts_track_open_quantities() {
+        let agent = PurchaseOrderAgent::new();
+        let task_id = processed_task(&agent).await;
+        assert_eq!(open_order_status(&agent, &task_id).unwrap().status, ReceivingStatus::Open);
+
+        let partial = record_receipt(&agent, &task_id, receipt(vec![line("p-1", 4)])).unwrap();
+        assert_eq!(partial.status, ReceivingStatus::PartiallyReceived);
+        assert_eq!(partial.lines[0].open_quantity, 6);
+
+        let err = record_receipt(&agent, &task_id, receipt(vec![line("P-1", 7)])).unwrap_err();
+        assert!(matches!(err, ReceiptError::Invalid(_)), "over-receipt: {}", err);
+        assert!(record_receipt(&agent, &task_id, receipt(vec![line("X-9", 1)])).is_err());
+
+        let full = record_receipt(&agent, &task_id, receipt(vec![line("P-1", 6), line("T-2", 3)])).unwrap();
+        assert_eq!(full.status, ReceivingStatus::Received);
+        assert_eq!(full.receipts.len(), 2);
+
+        let events = agent.event_log().events(&task_id).unwrap();
+        assert_eq!(events.iter().filter(|e| e.kind.name() == "goods_received").count(), 2);
+
+        assert!(matches!(open_order_status(&agent, "missing").unwrap_err(), ReceiptError::NotFound(_)));
+    }
+}
@@ -0,0 +1,185 @@
+use a2a::{Message, Part};
+use serde::Deserialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::fmt;
+
+use crate::agent::ProcessingResult;
+use crate::store::TaskRecord;
+
+/// Keys of personal data: who created, requested, approved or amended an order,
+/// free-text notes and street addresses
+pub const PERSONAL_FIELDS: &[&str] = &[
+    "createdBy",
+    "created_by",
+    "requestedBy",
+    "requested_by",
+    "approver",
+    "amendedBy",
+    "amended_by",
+    "notes",
+    "attention",
+    "addressLine1",
+    "addressLine2",
+    "supplierAddressLine1",
+    "supplierAddressLine2",
+];
+
+/// Redaction of personal data in logs and persisted tasks
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct RedactionConfig {
+    /// Mask personal data in log output
+    pub mask_logs: bool,
+    /// Replace personal data with salted hashes when tasks are written to snapshots
+    pub hash_persisted: bool,
+    /// Salt mixed into the hashes so they cannot be reversed by hashing guesses
+    pub salt: String,
+}
+
+impl Default for RedactionConfig {
+    fn default() -> Self {
+        Self {
+            mask_logs: true,
+            hash_persisted: false,
+            salt: String::new(),
+        }
+    }
+}
+
+/// A value as it may appear in a log line
+pub struct LogValue<'a> {
+    value: &'a str,
+    masked: bool,
+}
+
+impl fmt::Display for LogValue<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.masked {
+            f.write_str(&mask(self.value))
+        } else {
+            f.write_str(self.value)
+        }
+    }
+}
+
+/// Keep the first character of a value and mask the rest, e.g. `Sam Smith` → `S***`
+pub fn mask(value: &str) -> String {
+    match value.chars().next() {
+        Some(first) => format!("{}***", first),
+        None => String::new(),
+    }
+}
+
+impl RedactionConfig {
+    /// `value` for a log field, masked unless log masking is switched off
+    pub fn log<'a>(&self, value: &'a str) -> LogValue<'a> {
+        LogValue { value, masked: self.mask_logs }
+    }
+
+    /// Salted SHA-256 of a value, shortened to 16 hex digits
+    ///
+    /// Equal values hash equally, so redacted records can still be grouped by requester.
+    pub fn hash(&self, value: &str) -> String {
+        let digest = Sha256::new().chain_update(self.salt.as_bytes()).chain_update(value.as_bytes()).finalize();
+        let hex: String = digest[..8].iter().map(|byte| format!("{:02x}", byte)).collect();
+        format!("sha256:{}", hex)
+    }
+
+    /// `record` as it should be persisted: unchanged, or with personal data hashed
+    ///
+    /// Hashing covers the request, the status and the artifacts derived from it.
+    /// It cannot be undone, so a record restored from a hashed snapshot keeps the hashes.
+    pub fn persisted(&self, record: &TaskRecord) -> TaskRecord {
+        let mut record = record.clone();
+        if !self.hash_persisted {
+            return record;
+        }
+        self.hash_message(&mut record.request);
+
+        let mut status = record.task.status.clone();
+        if let Some(message) = &mut status.message {
+            self.hash_message(message);
+            // The CSV row repeats the notes, so it is rebuilt from the hashed result
+            if let Some(result) = message.parts.iter().find_map(|part| match part {
+                Part::Data { data } => ProcessingResult::deserialize(data).ok(),
+                _ => None,
+            }) {
+                for part in &mut message.parts {
+                    if let Part::Text { text } = part {
+                        *text = result.csv_row();
+                    }
+                }
+            }
+        }
+        record.set_status(status);
+        record
+    }
+
+    fn hash_message(&self, message: &mut Message) {
+        for part in &mut message.parts {
+            match part {
+                Part::Data { data } => self.hash_value(data),
+                Part::Text { text } => {
+                    if let Ok(mut value) = serde_json::from_str::<Value>(text)
+                        && (value.is_object() || value.is_array())
+                    {
+                        self.hash_value(&mut value);
+                        *text = value.to_string();
+                    }
+                }
+                Part::File { .. } => {}
+            }
+        }
+    }
+
+    fn hash_value(&self, value: &mut Value) {
+        match value {
+            Value::Object(map) => {
+                for (key, value) in map.iter_mut() {
+                    match value {
+                        Value::String(s) if PERSONAL_FIELDS.contains(&key.as_str()) && !s.is_empty() => {
+                            *s = self.hash(s);
+                        }
+                        _ => self.hash_value(value),
+                    }
+                }
+            }
+            Value::Array(values) => values.iter_mut().for_each(|value| self.hash_value(value)),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::PurchaseOrderAgent;
+    use a2a::A2AProtocol;
+
+    #[tokio::test]
+    async fn test_persisted_records_hash_personal_data() {
+        let agent = PurchaseOrderAgent::new();
+        let mut po = crate::testing::valid_order();
+        po.created_by = "Sam Smith".to_string();
+        po.notes = Some("Call Sam on 555-0100".to_string());
+        let task = agent.send_task(crate::testing::message(&po)).await.unwrap();
+        let record = agent.task_store().get(&task.id).unwrap().unwrap();
+
+        let unchanged = RedactionConfig::default().persisted(&record);
+        assert_eq!(serde_json::to_value(&unchanged).unwrap(), serde_json::to_value(&record).unwrap());
+
+        let config = RedactionConfig { hash_persisted: true, salt: "pepper".to_string(), ..RedactionConfig::default() };
+        let redacted = serde_json::to_string(&config.persisted(&record)).unwrap();
+        for personal in ["Sam Smith", "555-0100", "1 Main St"] {
+            assert!(!redacted.contains(personal), "{} leaked", personal);
+        }
+        assert!(redacted.contains(&config.hash("Sam Smith")));
+        assert!(redacted.contains("Acme Office Supply") && redacted.contains("Springfield"));
+        assert_ne!(config.hash("Sam Smith"), RedactionConfig::default().hash("Sam Smith"));
+
+        assert_eq!(config.log("Sam Smith").to_string(), "S***");
+        let plain = RedactionConfig { mask_logs: false, ..RedactionConfig::default() };
+        assert_eq!(plain.log("Sam Smith").to_string(), "Sam Smith");
+    }
+}
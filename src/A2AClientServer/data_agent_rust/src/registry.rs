@@ -0,0 +1,217 @@
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+use crate::a2a_agent_card::A2AAgentCard;
+use crate::client::{A2AClient, ClientConfig, ClientError};
+
+/// Other agents this agent knows about
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct RegistryConfig {
+    /// Base URLs of agents whose cards are resolved at startup
+    pub agents: Vec<String>,
+    /// How long a resolved card is trusted before it is fetched again
+    #[serde(with = "humantime_serde")]
+    pub refresh_interval: Duration,
+    /// Timeouts, retries and credentials for calling registered agents
+    pub client: ClientConfig,
+}
+
+impl Default for RegistryConfig {
+    fn default() -> Self {
+        Self {
+            agents: Vec::new(),
+            refresh_interval: Duration::from_secs(300),
+            client: ClientConfig::default(),
+        }
+    }
+}
+
+/// What the registry knows about one agent
+#[derive(Debug, Clone, Serialize)]
+pub struct RegisteredAgent {
+    pub url: String,
+    /// Last card resolved, kept when a later refresh fails
+    pub card: Option<A2AAgentCard>,
+    pub fetched_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+}
+
+impl RegisteredAgent {
+    /// Whether the agent's card offers a skill matching `skill` (if given) that carries every tag in `tags`
+    pub fn offers(&self, skill: Option<&str>, tags: &[String]) -> bool {
+        let Some(card) = &self.card else {
+            return false;
+        };
+        card.skills.iter().any(|s| {
+            skill.is_none_or(|id| s.id == id)
+                && tags.iter().all(|tag| s.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)))
+        })
+    }
+}
+
+/// Cache of other agents' cards, keyed by base URL
+pub struct AgentRegistry {
+    agents: DashMap<String, RegisteredAgent>,
+    config: RegistryConfig,
+}
+
+impl AgentRegistry {
+    pub fn new(config: RegistryConfig) -> Self {
+        let agents = config
+            .agents
+            .iter()
+            .map(|url| {
+                let agent = RegisteredAgent { url: url.clone(), card: None, fetched_at: None, last_error: None };
+                (url.clone(), agent)
+            })
+            .collect();
+        Self { agents, config }
+    }
+
+    /// Client for calling the agent at `url`
+    pub fn client(&self, url: &str) -> Result<A2AClient, ClientError> {
+        A2AClient::new(url, self.config.client.clone())
+    }
+
+    /// The agent's card, from the cache while fresh, otherwise fetched and cached
+    ///
+    /// Unknown URLs are added to the registry.
+    pub async fn resolve(&self, url: &str) -> Result<A2AAgentCard, ClientError> {
+        if let Some(entry) = self.agents.get(url)
+            && let (Some(card), Some(fetched_at)) = (&entry.card, entry.fetched_at)
+            && !self.is_stale(fetched_at)
+        {
+            return Ok(card.clone());
+        }
+        self.fetch(url).await
+    }
+
+    /// Fetch one agent's card now, recording the outcome
+    async fn fetch(&self, url: &str) -> Result<A2AAgentCard, ClientError> {
+        let result = match self.client(url) {
+            Ok(client) => client.discover().await,
+            Err(e) => Err(e),
+        };
+
+        let mut entry = self.agents.entry(url.to_string()).or_insert_with(|| RegisteredAgent {
+            url: url.to_string(),
+            card: None,
+            fetched_at: None,
+            last_error: None,
+        });
+        match &result {
+            Ok(card) => {
+                debug!(url = %url, name = %card.name, skills = card.skills.len(), "agent card resolved");
+                entry.card = Some(card.clone());
+                entry.fetched_at = Some(Utc::now());
+                entry.last_error = None;
+            }
+            Err(e) => {
+                warn!(url = %url, error = %e, "agent card resolution failed");
+                entry.last_error = Some(e.to_string());
+            }
+        }
+        result
+    }
+
+    /// Re-fetch every card older than the refresh interval; returns how many failed
+    pub async fn refresh(&self) -> usize {
+        let stale: Vec<String> = self
+            .agents
+            .iter()
+            .filter(|entry| entry.fetched_at.is_none_or(|at| self.is_stale(at)))
+            .map(|entry| entry.key().clone())
+            .collect();
+
+        let mut failed = 0;
+        for url in stale {
+            if self.fetch(&url).await.is_err() {
+                failed += 1;
+            }
+        }
+        failed
+    }
+
+    /// Every known agent, ordered by URL
+    pub fn agents(&self) -> Vec<RegisteredAgent> {
+        let mut agents: Vec<_> = self.agents.iter().map(|entry| entry.value().clone()).collect();
+        agents.sort_by(|a, b| a.url.cmp(&b.url));
+        agents
+    }
+
+    /// Agents whose cached card offers the required skill and tags, ordered by URL
+    pub fn select(&self, skill: Option<&str>, tags: &[String]) -> Vec<RegisteredAgent> {
+        self.agents().into_iter().filter(|agent| agent.offers(skill, tags)).collect()
+    }
+
+    fn is_stale(&self, fetched_at: DateTime<Utc>) -> bool {
+        let age = Utc::now().signed_duration_since(fetched_at).to_std().unwrap_or_default();
+        age >= self.config.refresh_interval
+    }
+}
+
+/// Resolve the configured agents now and refresh their cards until the process exits
+pub fn spawn_refresher(registry: Arc<AgentRegistry>) -> Option<JoinHandle<()>> {
+    if registry.config.agents.is_empty() {
+        return None;
+    }
+
+    info!(agents = registry.config.agents.len(), "agent registry refresher started");
+    Some(tokio::spawn(async move {
+        let mut interval = tokio::time::interval(registry.config.refresh_interval);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            let failed = registry.refresh().await;
+            if failed > 0 {
+                warn!(failed, "some agent cards could not be refreshed");
+            }
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::PurchaseOrderAgent;
+    use crate::server::create_router;
+
+    #[tokio::test]
+    async fn test_resolve_caches_and_selects_by_skill() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(listener, create_router(Arc::new(PurchaseOrderAgent::new()))).await.unwrap()
+        });
+
+        let registry = AgentRegistry::new(RegistryConfig {
+            agents: vec![url.clone(), "http://127.0.0.1:1".to_string()],
+            client: ClientConfig { max_attempts: 1, ..ClientConfig::default() },
+            ..RegistryConfig::default()
+        });
+        assert!(registry.select(None, &[]).is_empty(), "nothing resolved yet");
+
+        assert_eq!(registry.refresh().await, 1);
+        let card = registry.agents().into_iter().find(|a| a.url == url).unwrap().card.unwrap();
+        let skill = &card.skills[0];
+
+        let selected = registry.select(Some(&skill.id), &[skill.tags[0].to_uppercase()]);
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected[0].url, url);
+        assert!(registry.select(Some("no-such-skill"), &[]).is_empty());
+        assert!(registry.select(None, &["no-such-tag".to_string()]).is_empty());
+
+        let unreachable = registry.agents().into_iter().find(|a| a.url != url).unwrap();
+        assert!(unreachable.card.is_none());
+        assert!(unreachable.last_error.is_some());
+
+        assert_eq!(registry.resolve(&url).await.unwrap().name, card.name);
+        assert_eq!(registry.refresh().await, 1, "fresh cards are not fetched again");
+    }
+}
@@ -0,0 +1,141 @@
+//! The `options` object of a task submission
+//!
+//! Gathers the toggles for how a submission is handled into one versioned
+//! object, instead of spreading them over top-level request fields and the
+//! purchase order's Data part. Those older places keep working, but an option
+//! set here wins over them. Unknown options and versions are rejected, so a
+//! client never has a toggle ignored without noticing.
+
+use a2a::{Artifact, Message, Part};
+use serde::{Deserialize, Serialize};
+
+use crate::assembly;
+use crate::parts::{self, PartSchema};
+use crate::processing::Priority;
+
+/// The only `options.version` this agent understands
+pub const OPTIONS_VERSION: u32 = 1;
+
+/// An artifact format a submission may ask for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    Csv,
+    Json,
+}
+
+impl OutputFormat {
+    /// Whether `artifact` is in this format, judged by its file name
+    pub fn matches(self, artifact: &Artifact) -> bool {
+        let extension = match self {
+            OutputFormat::Csv => ".csv",
+            OutputFormat::Json => ".json",
+        };
+        artifact.name.as_deref().is_some_and(|name| name.ends_with(extension))
+    }
+}
+
+/// `options` of a `POST /agent/task` body
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RequestOptions {
+    /// Version of this object; defaults to [`OPTIONS_VERSION`]
+    pub version: Option<u32>,
+    /// Artifact formats to produce; empty produces all of them
+    pub output: Vec<OutputFormat>,
+    /// BCP 47 language tag of the submitter, e.g. `de-DE`, kept with the task and sent with its callback
+    pub locale: Option<String>,
+    /// In place of the top-level `dry_run`
+    pub dry_run: Option<bool>,
+    /// In place of the purchase order's `normalize`
+    pub normalize: Option<bool>,
+    /// In place of the purchase order's `mergeDuplicates`
+    pub merge_duplicates: Option<bool>,
+    /// In place of the top-level `priority`
+    pub priority: Option<Priority>,
+    /// URL to POST the task's result to once it is completed, failed or canceled
+    pub callback_url: Option<String>,
+}
+
+impl RequestOptions {
+    /// Reject versions and values this agent does not understand
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(version) = self.version
+            && version != OPTIONS_VERSION
+        {
+            return Err(format!("options version {} is not supported; this agent supports version {}", version, OPTIONS_VERSION));
+        }
+        if let Some(locale) = &self.locale
+            && !is_language_tag(locale)
+        {
+            return Err(format!("options.locale '{}' is not a BCP 47 language tag such as en-US", locale));
+        }
+        Ok(())
+    }
+
+    /// Write `normalize` and `merge_duplicates` into the Data part holding `message`'s purchase order
+    ///
+    /// The flags travel with the request this way, so the order is handled the
+    /// same when it is processed in the background or retried. Only the first
+    /// purchase order candidate is written to, leaving item and other parts as sent.
+    pub fn apply(&self, message: &mut Message) -> Result<(), String> {
+        let flags: Vec<(&str, bool)> = [("normalize", self.normalize), ("mergeDuplicates", self.merge_duplicates)]
+            .into_iter()
+            .filter_map(|(key, value)| value.map(|value| (key, value)))
+            .collect();
+        if flags.is_empty() {
+            return Ok(());
+        }
+        let index = parts::candidates(message, PartSchema::PurchaseOrder)?
+            .into_iter()
+            .find(|candidate| !assembly::is_items(candidate.part))
+            .map(|candidate| candidate.index);
+        let order = index.and_then(|index| match &mut message.parts[index] {
+            Part::Data { data } => data.as_object_mut(),
+            _ => None,
+        });
+        let Some(order) = order else {
+            return Err("options.normalize and options.merge_duplicates need the purchase order in a Data part".to_string());
+        };
+        for (key, value) in flags {
+            order.insert(key.to_string(), value.into());
+        }
+        Ok(())
+    }
+}
+
+/// Whether `tag` has the shape of a BCP 47 language tag: a 2–3 letter language and alphanumeric subtags
+fn is_language_tag(tag: &str) -> bool {
+    let mut subtags = tag.split('-');
+    let language = subtags.next().unwrap_or_default();
+    (2..=3).contains(&language.len())
+        && language.chars().all(|c| c.is_ascii_alphabetic())
+        && subtags.all(|subtag| (1..=8).contains(&subtag.len()) && subtag.chars().all(|c| c.is_ascii_alphanumeric()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_options_are_versioned_and_applied_to_the_order() {
+        let options: RequestOptions =
+            serde_json::from_value(serde_json::json!({ "version": 1, "output": ["json"], "locale": "de-DE", "normalize": true })).unwrap();
+        assert!(options.validate().is_ok());
+        assert_eq!(options.output, [OutputFormat::Json]);
+
+        let items = serde_json::json!({ "purchaseOrderItems": [] });
+        let parts = vec![Part::Data { data: items.clone() }, Part::Data { data: serde_json::json!({ "poNumber": "PO-1" }) }];
+        let mut message = Message { role: "user".to_string(), parts };
+        options.apply(&mut message).unwrap();
+        assert!(crate::normalize::requested(&message));
+        assert!(!crate::normalize::merge_requested(&message));
+        assert!(matches!(&message.parts[0], Part::Data { data } if *data == items));
+
+        let mut text = Message { role: "user".to_string(), parts: vec![Part::Text { text: "{}".to_string() }] };
+        assert!(options.apply(&mut text).is_err());
+        assert!(RequestOptions { version: Some(2), ..RequestOptions::default() }.validate().unwrap_err().contains("version 2"));
+        assert!(RequestOptions { locale: Some("german".to_string()), ..RequestOptions::default() }.validate().is_err());
+        assert!(serde_json::from_value::<RequestOptions>(serde_json::json!({ "format": "csv" })).is_err());
+    }
+}
@@ -0,0 +1,274 @@
+use a2a::{Message, Part, TaskState, TaskStatus};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::error::Error;
+
+use crate::address::Address;
+use crate::agent::{PurchaseOrder, PurchaseOrderAgent, PurchaseOrderItem};
+use crate::catalog::{CatalogStore, SupplierStore};
+use crate::invoice::round2;
+use crate::parts::{self, PartSchema};
+use crate::skills::SkillHandler;
+
+/// Skill converting purchase requisitions into purchase orders
+pub const REQUISITION_SKILL: &str = "requisition-conversion";
+
+/// Internal request to buy items, before a supplier and prices are settled
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Requisition {
+    pub requisition_number: String,
+    pub requested_by: String,
+    pub department: String,
+    /// Supplier to order from; defaults to the catalog supplier of the items
+    #[serde(default)]
+    pub supplier_name: Option<String>,
+    pub items: Vec<RequisitionLine>,
+    /// Delivery address carried over to the purchase order
+    #[serde(default)]
+    pub ship_to: Option<Address>,
+    #[serde(default)]
+    pub notes: Option<String>,
+    /// Overrides the configured tax rate
+    #[serde(default)]
+    pub tax_rate: Option<f64>,
+}
+
+/// One requested item
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RequisitionLine {
+    pub item_code: String,
+    /// Overrides the catalog description
+    #[serde(default)]
+    pub description: Option<String>,
+    pub quantity: u32,
+}
+
+/// Wrapper for the incoming requisition data
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequisitionWrapper {
+    pub requisition: Requisition,
+}
+
+/// How requisitions become purchase orders
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct RequisitionConfig {
+    /// Prepended to the requisition number to form the PO number
+    pub po_number_prefix: String,
+    /// Tax rate applied when the requisition does not give one
+    pub tax_rate: f64,
+}
+
+impl Default for RequisitionConfig {
+    fn default() -> Self {
+        Self {
+            po_number_prefix: "PO-".to_string(),
+            tax_rate: 0.0,
+        }
+    }
+}
+
+/// Extract a requisition from a message's Data or Text parts
+pub fn extract_requisition(message: &Message) -> Result<Requisition, Box<dyn Error>> {
+    parts::extract(message, PartSchema::Requisition, |wrapper: RequisitionWrapper| wrapper.requisition)
+}
+
+/// Build a purchase order from a requisition, taking the supplier's address and
+/// each item's description and price from master data
+///
+/// Fails when an item is not in the catalog, when the items come from more than
+/// one supplier and none is named, or when the supplier is unknown.
+pub fn convert(
+    requisition: &Requisition,
+    suppliers: &dyn SupplierStore,
+    catalog: &dyn CatalogStore,
+    config: &RequisitionConfig,
+) -> Result<PurchaseOrder, Box<dyn Error>> {
+    if requisition.items.is_empty() {
+        return Err("Requisition has no items".into());
+    }
+
+    let mut entries = Vec::with_capacity(requisition.items.len());
+    let mut unknown = Vec::new();
+    for line in &requisition.items {
+        match catalog.get(&line.item_code)? {
+            Some(entry) => entries.push((line, entry)),
+            None => unknown.push(line.item_code.clone()),
+        }
+    }
+    if !unknown.is_empty() {
+        return Err(format!("Items not in the catalog: {}", unknown.join(", ")).into());
+    }
+
+    let supplier_name = match &requisition.supplier_name {
+        Some(name) => name.clone(),
+        None => {
+            let names: BTreeSet<&str> = entries.iter().filter_map(|(_, e)| e.supplier.as_deref()).collect();
+            match names.len() {
+                1 => names.into_iter().next().unwrap_or_default().to_string(),
+                0 => return Err("No supplier named and none of the items has a catalog supplier".into()),
+                _ => {
+                    let names: Vec<&str> = names.into_iter().collect();
+                    return Err(format!("Items come from several suppliers ({}); name one", names.join(", ")).into());
+                }
+            }
+        }
+    };
+    let supplier = suppliers
+        .get(&supplier_name)?
+        .ok_or_else(|| format!("Supplier {} not found", supplier_name))?;
+
+    let items: Vec<PurchaseOrderItem> = entries
+        .into_iter()
+        .map(|(line, entry)| PurchaseOrderItem {
+            item_code: entry.item_code,
+            description: line.description.clone().unwrap_or(entry.description),
+            quantity: line.quantity,
+            unit_price: entry.unit_price,
+            line_total: round2(entry.unit_price * line.quantity as f64),
+        })
+        .collect();
+    let tax_rate = requisition.tax_rate.unwrap_or(config.tax_rate);
+    let sub_total = round2(items.iter().map(|i| i.line_total).sum());
+    let tax = round2(sub_total * tax_rate);
+
+    Ok(PurchaseOrder {
+        supplier_name: supplier.name,
+        supplier_address_line1: supplier.address_line1,
+        supplier_address_line2: supplier.address_line2,
+        supplier_city: supplier.city,
+        supplier_state: supplier.state,
+        supplier_postal_code: supplier.postal_code,
+        supplier_country: supplier.country,
+        supplier_tax_id: supplier.tax_id,
+        items,
+        po_number: format!("{}{}", config.po_number_prefix, requisition.requisition_number),
+        created_by: requisition.requested_by.clone(),
+        buyer_department: requisition.department.clone(),
+        ship_to: requisition.ship_to.clone(),
+        notes: requisition.notes.clone(),
+        tax_rate,
+        sub_total,
+        tax,
+        grand_total: round2(sub_total + tax),
+        is_approved: false,
+        approval_reason: None,
+        blanket: None,
+        blanket_po_number: None,
+        currency: None,
+    })
+}
+
+/// Converts a requisition into a purchase order ready to submit for processing
+pub struct RequisitionSkill {
+    config: RequisitionConfig,
+}
+
+impl RequisitionSkill {
+    pub fn new(config: RequisitionConfig) -> Self {
+        Self { config }
+    }
+}
+
+#[async_trait]
+impl SkillHandler for RequisitionSkill {
+    fn id(&self) -> &str {
+        REQUISITION_SKILL
+    }
+
+    async fn handle(&self, agent: &PurchaseOrderAgent, message: &Message) -> Result<TaskStatus, Box<dyn Error>> {
+        let requisition = extract_requisition(message)?;
+        let po = convert(&requisition, agent.suppliers().as_ref(), agent.catalog().as_ref(), &self.config)?;
+
+        Ok(TaskStatus {
+            state: TaskState::Completed,
+            message: Some(Message {
+                role: "assistant".to_string(),
+                parts: vec![Part::Data {
+                    data: serde_json::json!({
+                        "requisition_number": requisition.requisition_number,
+                        "purchaseOrder": po,
+                    }),
+                }],
+            }),
+            timestamp: agent.current_timestamp(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalog::{CatalogConfig, CatalogItem, MemoryCatalogStore, MemorySupplierStore, Supplier};
+
+    fn stores() -> (MemorySupplierStore, MemoryCatalogStore) {
+        let supplier = |name: &str| Supplier {
+            name: name.to_string(),
+            address_line1: "1 Main St".to_string(),
+            address_line2: None,
+            city: "Springfield".to_string(),
+            state: "IL".to_string(),
+            postal_code: "62701".to_string(),
+            country: "USA".to_string(),
+            tax_id: None,
+            bank_account: None,
+        };
+        let item = |code: &str, price: f64, supplier: &str| CatalogItem {
+            item_code: code.to_string(),
+            description: format!("{} description", code),
+            unit_price: price,
+            supplier: Some(supplier.to_string()),
+            min_order_quantity: None,
+            pack_size: None,
+        };
+        let config = CatalogConfig {
+            suppliers: vec![supplier("Acme"), supplier("Globex")],
+            items: vec![item("P-1", 4.99, "Acme"), item("T-2", 10.0, "Acme"), item("G-3", 1.0, "Globex")],
+        };
+        (MemorySupplierStore::from_config(&config), MemoryCatalogStore::from_config(&config))
+    }
+
+    fn requisition(codes: &[&str]) -> Requisition {
+        Requisition {
+            requisition_number: "REQ-7".to_string(),
+            requested_by: "Sam".to_string(),
+            department: "Marketing".to_string(),
+            supplier_name: None,
+            items: codes
+                .iter()
+                .map(|code| RequisitionLine { item_code: code.to_string(), description: None, quantity: 3 })
+                .collect(),
+            ship_to: None,
+            notes: None,
+            tax_rate: Some(0.1),
+        }
+    }
+
+    #[test]
+    fn test_convert_enriches_from_master_data() {
+        let (suppliers, catalog) = stores();
+        let config = RequisitionConfig::default();
+
+        let po = convert(&requisition(&["p-1", "T-2"]), &suppliers, &catalog, &config).unwrap();
+        assert_eq!(po.po_number, "PO-REQ-7");
+        assert_eq!(po.supplier_name, "Acme");
+        assert_eq!(po.supplier_city, "Springfield");
+        assert_eq!(po.items[0].item_code, "P-1");
+        assert_eq!(po.items[0].line_total, 14.97);
+        assert_eq!(po.sub_total, 44.97);
+        assert_eq!(po.tax, 4.5);
+        assert_eq!(po.grand_total, 49.47);
+        assert!(!po.is_approved);
+
+        let err = convert(&requisition(&["P-1", "X-9"]), &suppliers, &catalog, &config).unwrap_err();
+        assert!(err.to_string().contains("X-9"));
+
+        let mixed = requisition(&["P-1", "G-3"]);
+        assert!(convert(&mixed, &suppliers, &catalog, &config).unwrap_err().to_string().contains("several suppliers"));
+        let named = Requisition { supplier_name: Some("globex".to_string()), ..mixed };
+        assert_eq!(convert(&named, &suppliers, &catalog, &config).unwrap().supplier_name, "Globex");
+    }
+}
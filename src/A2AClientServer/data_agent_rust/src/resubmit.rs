@@ -0,0 +1,193 @@
+//! Resubmission of failed tasks
+//!
+//! A failed task's original message is processed again as a new child task,
+//! optionally with corrections merged into the purchase order first, so a
+//! client fixing one field does not have to send the whole order again. The
+//! child keeps the parent's labels. The parent and the child each record the
+//! link in their event stream.
+
+use a2a::{Message, Part, Task, TaskState};
+use serde::Deserialize;
+use serde_json::Value;
+use std::fmt;
+
+use crate::agent::{PurchaseOrderAgent, TaskOptions};
+use crate::events::TaskEventKind;
+use crate::ingest::WRAPPER_KEY;
+use crate::parts;
+use crate::store::StoreError;
+
+/// Body of `POST /agent/task/{id}/retry`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct RetryRequest {
+    /// JSON merge patch applied to the purchase order before it is processed again
+    pub corrections: Option<Value>,
+}
+
+/// Why a task could not be retried
+#[derive(Debug)]
+pub enum RetryError {
+    NotFound(String),
+    /// Only failed tasks can be retried
+    NotFailed(String),
+    /// The corrections cannot be applied
+    Invalid(String),
+    Store(StoreError),
+    /// Processing the child task failed
+    Processing(String),
+}
+
+impl fmt::Display for RetryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RetryError::NotFound(message)
+            | RetryError::NotFailed(message)
+            | RetryError::Invalid(message)
+            | RetryError::Processing(message) => f.write_str(message),
+            RetryError::Store(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for RetryError {}
+
+impl From<StoreError> for RetryError {
+    fn from(e: StoreError) -> Self {
+        RetryError::Store(e)
+    }
+}
+
+/// Apply an RFC 7386 JSON merge patch: objects merge, `null` removes a field, anything else replaces
+pub fn merge_patch(target: &mut Value, patch: &Value) {
+    let Value::Object(patch) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(Default::default());
+    }
+    if let Value::Object(target) = target {
+        for (key, value) in patch {
+            if value.is_null() {
+                target.remove(key);
+            } else {
+                merge_patch(target.entry(key.clone()).or_insert(Value::Null), value);
+            }
+        }
+    }
+}
+
+/// The message with `corrections` merged into the purchase order it carries
+fn corrected(mut message: Message, corrections: &Value) -> Result<Message, RetryError> {
+    if !corrections.is_object() {
+        return Err(RetryError::Invalid("corrections must be a JSON object".to_string()));
+    }
+    let patch = |payload: &mut Value| match payload.get_mut(WRAPPER_KEY) {
+        Some(po) => merge_patch(po, corrections),
+        None => merge_patch(payload, corrections),
+    };
+
+    for part in &mut message.parts {
+        match part {
+            Part::Data { .. } if parts::is_metadata(part) => {}
+            Part::Data { data } if data.is_object() => {
+                patch(data);
+                return Ok(message);
+            }
+            Part::Text { text } => {
+                let Ok(mut payload) = serde_json::from_str::<Value>(text) else {
+                    continue;
+                };
+                if payload.is_object() {
+                    patch(&mut payload);
+                    *part = Part::Data { data: payload };
+                    return Ok(message);
+                }
+            }
+            _ => {}
+        }
+    }
+    Err(RetryError::Invalid("The task's message carries no JSON purchase order to correct".to_string()))
+}
+
+/// Process a failed task's message again as a new task linked to it
+///
+/// The child runs with the parent's skill and priority and is returned once processed.
+pub async fn retry(agent: &PurchaseOrderAgent, task_id: &str, request: RetryRequest) -> Result<Task, RetryError> {
+    let parent = agent
+        .task_store()
+        .get(task_id)?
+        .ok_or_else(|| RetryError::NotFound(format!("Task {} not found", task_id)))?;
+    if !matches!(parent.task.status.state, TaskState::Failed) {
+        return Err(RetryError::NotFailed(format!(
+            "Task {} is {}, only failed tasks can be retried",
+            task_id,
+            parent.state_name()
+        )));
+    }
+
+    let message = match &request.corrections {
+        Some(corrections) => corrected(parent.request.clone(), corrections)?,
+        None => parent.request.clone(),
+    };
+    let options = TaskOptions {
+        skill_id: parent.skill_id.clone(),
+        priority: parent.priority,
+        labels: parent.labels.clone(),
+        outputs: parent.outputs.clone(),
+        locale: parent.locale.clone(),
+        callback_url: parent.callback_url.clone(),
+        ..TaskOptions::default()
+    };
+    let child = agent
+        .send_task_with_options(message, &options)
+        .await
+        .map_err(|e| RetryError::Processing(e.to_string()))?;
+
+    agent.task_store().update(&child.id, &mut |record| record.retried_from = Some(task_id.to_string()))?;
+    agent
+        .record_event(&child.id, TaskEventKind::RetriedFrom { parent_task_id: task_id.to_string() })
+        .and_then(|_| agent.record_event(task_id, TaskEventKind::Retried { child_task_id: child.id.clone() }))
+        .map_err(|e| RetryError::Processing(e.to_string()))?;
+    Ok(child)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::ProcessingResult;
+    use a2a::A2AProtocol;
+
+    #[test]
+    fn test_merge_patch_merges_objects_and_removes_nulls() {
+        let mut target = serde_json::json!({ "poNumber": "PO-1", "notes": "rush", "items": [1, 2], "supplier": { "name": "Acme", "city": "Springfield" } });
+        merge_patch(&mut target, &serde_json::json!({ "notes": null, "items": [3], "supplier": { "name": "Acme Inc" } }));
+        assert_eq!(target, serde_json::json!({ "poNumber": "PO-1", "items": [3], "supplier": { "name": "Acme Inc", "city": "Springfield" } }));
+    }
+
+    #[tokio::test]
+    async fn test_retry_creates_linked_child_with_corrections() {
+        let mut po = crate::testing::valid_order();
+        po.supplier_name = String::new();
+        let agent = PurchaseOrderAgent::new();
+        let message = Message { role: "user".to_string(), parts: vec![Part::Text { text: serde_json::json!({ "purchaseOrder": po }).to_string() }] };
+        let parent = agent.send_task(message).await.unwrap();
+        assert!(matches!(parent.status.state, TaskState::Failed));
+
+        let request = RetryRequest { corrections: Some(serde_json::json!({ "supplierName": "Acme Office Supply" })) };
+        let child = retry(&agent, &parent.id, request).await.unwrap();
+        assert_ne!(child.id, parent.id);
+        assert!(matches!(child.status.state, TaskState::Completed));
+        assert_eq!(ProcessingResult::from_status(&child.status).unwrap().supplier_name, "Acme Office Supply");
+        let record = agent.task_store().get(&child.id).unwrap().unwrap();
+        assert_eq!(record.retried_from.as_deref(), Some(parent.id.as_str()));
+        let events = agent.event_log().events(&parent.id).unwrap();
+        assert!(matches!(&events.last().unwrap().kind, TaskEventKind::Retried { child_task_id } if *child_task_id == child.id));
+
+        assert!(matches!(retry(&agent, &child.id, RetryRequest::default()).await, Err(RetryError::NotFailed(_))));
+        assert!(matches!(retry(&agent, "missing", RetryRequest::default()).await, Err(RetryError::NotFound(_))));
+        let invalid = RetryRequest { corrections: Some(serde_json::json!(["not", "an", "object"])) };
+        assert!(matches!(retry(&agent, &parent.id, invalid).await, Err(RetryError::Invalid(_))));
+    }
+}
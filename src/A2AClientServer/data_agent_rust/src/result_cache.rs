@@ -0,0 +1,135 @@
+//! Processing results cached by purchase order payload
+//!
+//! Upstream systems that retry send the very same order again and again. With
+//! the cache enabled, an order identical to one processed within `ttl` gets
+//! the earlier result, flagged `cached`, instead of being validated, scored and
+//! drawn against its blanket order again. Orders are identical when they have
+//! the same fingerprint and ask for the same normalization.
+
+use dashmap::DashMap;
+use serde::Deserialize;
+use std::time::{Duration, Instant};
+
+use crate::agent::ProcessingResult;
+
+/// Whether and for how long results are cached
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ResultCacheConfig {
+    pub enabled: bool,
+    /// How long a result is returned for identical orders
+    #[serde(with = "humantime_serde")]
+    pub ttl: Duration,
+    /// Results kept at most; the oldest is dropped to make room
+    pub max_entries: usize,
+}
+
+impl Default for ResultCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ttl: Duration::from_secs(600),
+            max_entries: 10_000,
+        }
+    }
+}
+
+/// Cache of processing results by canonical payload hash
+#[derive(Default)]
+pub struct ResultCache {
+    config: ResultCacheConfig,
+    entries: DashMap<String, (Instant, ProcessingResult)>,
+}
+
+impl ResultCache {
+    pub fn new(config: ResultCacheConfig) -> Self {
+        Self { config, entries: DashMap::new() }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled && self.config.max_entries > 0
+    }
+
+    /// Key of an order by its [`PurchaseOrder::fingerprint`](crate::agent::PurchaseOrder::fingerprint) and the normalization options it was sent with
+    pub fn key(fingerprint: &str, normalize: bool, merge_duplicates: bool) -> String {
+        format!("{}:{}{}", fingerprint, normalize as u8, merge_duplicates as u8)
+    }
+
+    /// The result cached under `key`, when it has not expired
+    pub fn get(&self, key: &str) -> Option<ProcessingResult> {
+        let (cached_at, result) = self.entries.get(key).map(|entry| entry.value().clone())?;
+        if cached_at.elapsed() < self.config.ttl {
+            Some(result)
+        } else {
+            self.entries.remove(key);
+            None
+        }
+    }
+
+    /// Cache a result, dropping expired entries or the oldest one when full
+    pub fn put(&self, key: String, result: ProcessingResult) {
+        if !self.is_enabled() {
+            return;
+        }
+        if self.entries.len() >= self.config.max_entries {
+            self.entries.retain(|_, (cached_at, _)| cached_at.elapsed() < self.config.ttl);
+        }
+        if self.entries.len() >= self.config.max_entries {
+            let oldest = self.entries.iter().min_by_key(|entry| entry.value().0).map(|entry| entry.key().clone());
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.entries.insert(key, (Instant::now(), result));
+    }
+
+    /// Results currently cached, expired ones included
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::PurchaseOrderAgent;
+    use a2a::{A2AProtocol, Message, Part};
+
+    fn message(text: &str) -> Message {
+        Message { role: "user".to_string(), parts: vec![Part::Text { text: text.to_string() }] }
+    }
+
+    #[tokio::test]
+    async fn test_identical_orders_get_the_cached_result() {
+        let agent = PurchaseOrderAgent::new().with_result_cache(ResultCacheConfig { enabled: true, ..ResultCacheConfig::default() });
+        let order = serde_json::to_value(crate::testing::valid_order()).unwrap();
+
+        let first = agent.send_task(message(&order.to_string())).await.unwrap();
+        let first = ProcessingResult::from_status(&first.status).unwrap();
+        assert!(!first.cached);
+
+        // The same order pretty-printed, with an extra field and integral numbers written differently
+        let mut resent = order.clone();
+        resent["items"][0]["unitPrice"] = serde_json::json!(5);
+        resent["channel"] = serde_json::json!("edi");
+        let second = agent.send_task(message(&serde_json::to_string_pretty(&resent).unwrap())).await.unwrap();
+        let second = ProcessingResult::from_status(&second.status).unwrap();
+        assert!(second.cached);
+        assert_eq!(second.processed_at, first.processed_at);
+        assert_eq!(second.fingerprint, first.fingerprint);
+        let v2 = crate::result_schema::convert(serde_json::to_value(&second).unwrap(), 2).unwrap();
+        assert!(v2.get("cached").is_none());
+
+        let mut changed = order;
+        changed["notes"] = serde_json::json!("urgent");
+        let third = agent.send_task(message(&changed.to_string())).await.unwrap();
+        let third = ProcessingResult::from_status(&third.status).unwrap();
+        assert!(!third.cached);
+        assert_ne!(third.fingerprint, first.fingerprint);
+        assert_eq!(agent.metrics().value("po_agent_result_cache_hits_total", &[]), Some(1.0));
+    }
+}
@@ -0,0 +1,151 @@
+//! Versions of the processing result contract
+//!
+//! The processing result carried in a task's Data part, and returned as
+//! `detailed_result`, names its `schema_version`. Results stored before
+//! versioning have none and are version 1, the original CSV-era fields. Every
+//! change to the contract bumps [`CURRENT_VERSION`] and adds a step to the
+//! converters here, so stored results can be read at the current version and
+//! consumers can ask for the version they were built against.
+
+use serde_json::Value;
+use std::fmt;
+
+/// Version of the results this agent produces
+pub const CURRENT_VERSION: u32 = 5;
+
+/// Fields of a version 1 result
+const V1_FIELDS: [&str; 12] = [
+    "status",
+    "po_number",
+    "validation_errors",
+    "warnings",
+    "summary",
+    "processed_at",
+    "supplier_name",
+    "buyer_department",
+    "notes",
+    "sub_total",
+    "tax",
+    "grand_total",
+];
+
+/// A result that cannot be converted
+#[derive(Debug, PartialEq)]
+pub enum SchemaError {
+    /// The version is newer than this agent knows, or zero
+    Unsupported(u32),
+    /// The result is not a JSON object
+    Invalid,
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaError::Unsupported(version) => {
+                write!(f, "Result schema version {} is not supported; versions 1 to {} are", version, CURRENT_VERSION)
+            }
+            SchemaError::Invalid => f.write_str("Processing result is not a JSON object"),
+        }
+    }
+}
+
+impl std::error::Error for SchemaError {}
+
+/// Version of results stored without one
+pub fn unversioned() -> u32 {
+    1
+}
+
+/// Schema version of a result
+pub fn version(result: &Value) -> u32 {
+    result
+        .get("schema_version")
+        .and_then(Value::as_u64)
+        .map(|version| version as u32)
+        .unwrap_or_else(unversioned)
+}
+
+/// Convert a result of any known version to version `to`
+pub fn convert(mut result: Value, to: u32) -> Result<Value, SchemaError> {
+    if to == 0 || to > CURRENT_VERSION {
+        return Err(SchemaError::Unsupported(to));
+    }
+    let mut current = version(&result);
+    if current == 0 || current > CURRENT_VERSION {
+        return Err(SchemaError::Unsupported(current));
+    }
+    let object = result.as_object_mut().ok_or(SchemaError::Invalid)?;
+    while current < to {
+        match current {
+            // Fields added after version 1 are all optional
+            1..=4 => {}
+            _ => unreachable!("upgrade from version {}", current),
+        }
+        current += 1;
+    }
+    while current > to {
+        match current {
+            5 => {
+                object.remove("approval_reason");
+                object.remove("approval_reasons");
+            }
+            4 => {
+                object.remove("fingerprint");
+            }
+            3 => {
+                object.remove("cached");
+            }
+            2 => object.retain(|field, _| V1_FIELDS.contains(&field.as_str())),
+            _ => unreachable!("downgrade from version {}", current),
+        }
+        current -= 1;
+    }
+    // Even version 1 results say which version they are once converted
+    object.insert("schema_version".to_string(), Value::from(to));
+    Ok(result)
+}
+
+/// Convert a result of any known version to the current one
+pub fn upgrade(result: Value) -> Result<Value, SchemaError> {
+    convert(result, CURRENT_VERSION)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::{ProcessingResult, PurchaseOrderAgent};
+    use a2a::{A2AProtocol, Message, Part};
+
+    #[tokio::test]
+    async fn test_results_convert_between_versions() {
+        let agent = PurchaseOrderAgent::new();
+        // 3 × 4.99 stated as 15.00, which normalizing corrects to 14.97
+        let mut po = crate::testing::order_of(0.1, &[("P-1", "Paper", 3, 4.99)]);
+        po.items[0].line_total = 15.0;
+        (po.sub_total, po.tax, po.grand_total) = (15.0, 1.5, 16.5);
+        let message = Message {
+            role: "user".to_string(),
+            parts: vec![Part::Data { data: serde_json::json!({ "normalize": true, "purchaseOrder": po }) }],
+        };
+        let task = agent.send_task(message).await.unwrap();
+        let result = ProcessingResult::from_status(&task.status).unwrap();
+        assert_eq!(result.schema_version, CURRENT_VERSION);
+        let current = serde_json::to_value(&result).unwrap();
+        assert!(current.get("adjustments").is_some());
+
+        let v1 = convert(current, 1).unwrap();
+        assert_eq!(version(&v1), 1);
+        assert!(v1.get("adjustments").is_none() && v1.get("normalized_order").is_none());
+        assert_eq!(v1["grand_total"], 16.47);
+
+        let mut stored = v1.clone();
+        stored.as_object_mut().unwrap().remove("schema_version");
+        let upgraded: ProcessingResult = serde_json::from_value(upgrade(stored).unwrap()).unwrap();
+        assert_eq!((upgraded.schema_version, upgraded.po_number.as_str()), (CURRENT_VERSION, "PO-1"));
+
+        assert_eq!(convert(v1.clone(), 6).unwrap_err(), SchemaError::Unsupported(6));
+        let mut future = v1;
+        future["schema_version"] = Value::from(9);
+        assert_eq!(upgrade(future).unwrap_err(), SchemaError::Unsupported(9));
+    }
+}
@@ -0,0 +1,188 @@
+use a2a::TaskState;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+use crate::agent::PurchaseOrderAgent;
+use crate::metrics::Metrics;
+use crate::store::{StoreResult, TaskRecord, TaskStore};
+
+/// How long tasks are kept once they reach a terminal state
+///
+/// Each period is optional; tasks in a state without a period are kept forever.
+/// Non-terminal tasks are never expired.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct RetentionConfig {
+    #[serde(with = "humantime_serde")]
+    pub completed: Option<Duration>,
+    #[serde(with = "humantime_serde")]
+    pub failed: Option<Duration>,
+    #[serde(with = "humantime_serde")]
+    pub canceled: Option<Duration>,
+    /// How often the background sweeper runs
+    #[serde(with = "humantime_serde")]
+    pub sweep_interval: Duration,
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            completed: None,
+            failed: None,
+            canceled: None,
+            sweep_interval: Duration::from_secs(300),
+        }
+    }
+}
+
+impl RetentionConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.completed.is_some() || self.failed.is_some() || self.canceled.is_some()
+    }
+
+    /// Retention period for a task state, if it expires at all
+    pub fn period_for(&self, state: &TaskState) -> Option<Duration> {
+        match state {
+            TaskState::Completed => self.completed,
+            TaskState::Failed => self.failed,
+            TaskState::Canceled => self.canceled,
+            TaskState::Submitted | TaskState::Working | TaskState::InputRequired => None,
+        }
+    }
+
+    fn is_expired(&self, record: &TaskRecord, now: DateTime<Utc>) -> bool {
+        let Some(period) = self.period_for(&record.task.status.state) else {
+            return false;
+        };
+        let age = now.signed_duration_since(record.updated_at);
+        age.to_std().is_ok_and(|age| age >= period)
+    }
+}
+
+/// Remove expired tasks from the store, recording evictions per state in `metrics`
+///
+/// Returns the number of tasks evicted.
+pub fn sweep(
+    store: &dyn TaskStore,
+    config: &RetentionConfig,
+    metrics: &Metrics,
+    now: DateTime<Utc>,
+) -> StoreResult<usize> {
+    let evicted_by_state: RefCell<BTreeMap<&'static str, u64>> = RefCell::default();
+
+    let evicted = store.remove_where(&|record| {
+        let expired = config.is_expired(record, now);
+        if expired {
+            *evicted_by_state.borrow_mut().entry(record.state_name()).or_default() += 1;
+        }
+        expired
+    })?;
+
+    for (state, count) in evicted_by_state.into_inner() {
+        metrics.increment(
+            "po_agent_tasks_evicted_total",
+            "Tasks removed by the retention sweeper",
+            &[("state", state)],
+            count,
+        );
+    }
+    metrics.increment("po_agent_retention_sweeps_total", "Retention sweeps run", &[], 1);
+    metrics.set_gauge(
+        "po_agent_tasks_stored",
+        "Tasks currently held in the task store",
+        &[],
+        store.stats()?.total_tasks as f64,
+    );
+
+    Ok(evicted)
+}
+
+/// Spawn the background sweeper; returns `None` when no retention period is configured
+pub fn spawn_sweeper(agent: Arc<PurchaseOrderAgent>, config: RetentionConfig) -> Option<JoinHandle<()>> {
+    if !config.is_enabled() {
+        return None;
+    }
+
+    info!(
+        completed = ?config.completed,
+        failed = ?config.failed,
+        canceled = ?config.canceled,
+        interval = ?config.sweep_interval,
+        "task retention sweeper started"
+    );
+
+    Some(tokio::spawn(async move {
+        let mut interval = tokio::time::interval(config.sweep_interval);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            match sweep(agent.task_store().as_ref(), &config, agent.metrics(), Utc::now()) {
+                Ok(0) => {}
+                Ok(evicted) => {
+                    if let Err(e) = agent.prune_event_log() {
+                        warn!(error = %e, "event log pruning failed");
+                    }
+                    info!(evicted, "expired tasks evicted")
+                }
+                Err(e) => warn!(error = %e, "retention sweep failed"),
+            }
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::MemoryTaskStore;
+    use crate::testing::aged_task_record;
+
+    #[test]
+    fn test_sweep_expires_per_terminal_state() {
+        let store = MemoryTaskStore::new();
+        let hour = Duration::from_secs(3600);
+        store.put(aged_task_record("completed-old", TaskState::Completed, chrono::Duration::hours(3))).unwrap();
+        store.put(aged_task_record("completed-new", TaskState::Completed, chrono::Duration::minutes(30))).unwrap();
+        store.put(aged_task_record("failed-old", TaskState::Failed, chrono::Duration::hours(3))).unwrap();
+        store.put(aged_task_record("working-old", TaskState::Working, chrono::Duration::hours(100))).unwrap();
+
+        let config = RetentionConfig {
+            completed: Some(hour),
+            ..RetentionConfig::default()
+        };
+        let metrics = Metrics::new();
+
+        let evicted = sweep(&store, &config, &metrics, Utc::now()).unwrap();
+
+        assert_eq!(evicted, 1);
+        assert!(store.get("completed-old").unwrap().is_none());
+        assert!(store.get("completed-new").unwrap().is_some());
+        assert!(store.get("failed-old").unwrap().is_some(), "failed tasks have no retention period");
+        assert!(store.get("working-old").unwrap().is_some(), "non-terminal tasks never expire");
+        assert_eq!(metrics.value("po_agent_tasks_evicted_total", &[("state", "completed")]), Some(1.0));
+        assert_eq!(metrics.value("po_agent_tasks_stored", &[]), Some(3.0));
+    }
+
+    #[test]
+    fn test_retention_config_parses_human_durations() {
+        let config: RetentionConfig = toml::from_str(
+            r#"
+            completed = "7d"
+            failed = "30d"
+            sweep_interval = "10m"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.completed, Some(Duration::from_secs(7 * 86_400)));
+        assert_eq!(config.failed, Some(Duration::from_secs(30 * 86_400)));
+        assert_eq!(config.canceled, None);
+        assert_eq!(config.sweep_interval, Duration::from_secs(600));
+        assert!(config.is_enabled());
+    }
+}
@@ -0,0 +1,117 @@
+//! Credit and return orders
+//!
+//! Quantities are always positive, so a return order carries its sign in its
+//! amounts: every line has a negative unit price and line total, and the
+//! subtotal, tax and grand total are negative too. Return orders are refused
+//! with a `negative_amount` finding unless they are enabled. Enabled, they are
+//! validated like any other order, and lines that charge instead of credit
+//! are flagged.
+
+use serde::Deserialize;
+
+use crate::agent::PurchaseOrder;
+use crate::severity::{Check, Findings};
+
+/// Whether credit and return orders are accepted
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ReturnsConfig {
+    /// Accept orders with negative amounts as returns instead of refusing them
+    pub enabled: bool,
+}
+
+/// Whether an order credits the buyer, i.e. any of its amounts is negative
+pub fn is_return(po: &PurchaseOrder) -> bool {
+    po.sub_total < 0.0
+        || po.grand_total < 0.0
+        || po.items.iter().any(|item| item.unit_price < 0.0 || item.line_total < 0.0)
+}
+
+/// The negative amounts of an order, by their camel-cased paths
+fn negative_fields(po: &PurchaseOrder) -> Vec<String> {
+    let mut fields = Vec::new();
+    for (index, item) in po.items.iter().enumerate() {
+        if item.unit_price < 0.0 {
+            fields.push(format!("items[{}].unitPrice", index));
+        }
+        if item.line_total < 0.0 {
+            fields.push(format!("items[{}].lineTotal", index));
+        }
+    }
+    for (field, amount) in [("subTotal", po.sub_total), ("tax", po.tax), ("grandTotal", po.grand_total)] {
+        if amount < 0.0 {
+            fields.push(field.to_string());
+        }
+    }
+    fields
+}
+
+/// Report negative and zero totals under `config`
+///
+/// Orders with items whose grand total is zero are flagged either way.
+pub fn validate(config: &ReturnsConfig, po: &PurchaseOrder, findings: &mut Findings) {
+    if !po.items.is_empty() && po.grand_total == 0.0 {
+        findings.push(Check::NegativeAmount, "Grand total is zero");
+    }
+    if !is_return(po) {
+        return;
+    }
+    if !config.enabled {
+        findings.push(
+            Check::NegativeAmount,
+            format!("Negative amounts are not accepted: {}", negative_fields(po).join(", ")),
+        );
+        return;
+    }
+
+    let charges: Vec<String> = po
+        .items
+        .iter()
+        .enumerate()
+        .filter(|(_, item)| item.unit_price > 0.0 || item.line_total > 0.0)
+        .map(|(index, _)| (index + 1).to_string())
+        .collect();
+    if !charges.is_empty() {
+        findings.push(
+            Check::NegativeAmount,
+            format!("Return order lines must all be credits; items {} are charges", charges.join(", ")),
+        );
+    }
+    for (field, amount) in [("Subtotal", po.sub_total), ("Tax", po.tax), ("Grand total", po.grand_total)] {
+        if amount > 0.0 {
+            findings.push(Check::NegativeAmount, format!("{} of a return order must not be positive, got {:.2}", field, amount));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::PurchaseOrderAgent;
+
+    /// An order from Oregon, which has no sales tax, of two units at each of `lines`
+    fn order(lines: &[f64], grand_total: f64) -> PurchaseOrder {
+        let codes: Vec<String> = (1..=lines.len()).map(|n| format!("P-{}", n)).collect();
+        let items: Vec<_> = codes.iter().zip(lines).map(|(code, price)| (code.as_str(), "Paper", 2, *price)).collect();
+        let mut po = crate::testing::order_of(0.0, &items);
+        po.po_number = "RMA-1".to_string();
+        (po.supplier_state, po.supplier_postal_code) = ("OR".to_string(), "97477".to_string());
+        po.grand_total = grand_total;
+        po
+    }
+
+    #[test]
+    fn test_returns_are_refused_unless_enabled() {
+        let refusing = PurchaseOrderAgent::new();
+        let (errors, _) = refusing.validate_purchase_order(&order(&[10.0], -20.0));
+        assert_eq!(errors, ["Negative amounts are not accepted: grandTotal"]);
+        let (errors, _) = refusing.validate_purchase_order(&order(&[-10.0], -20.0));
+        assert_eq!(errors, ["Negative amounts are not accepted: items[0].unitPrice, items[0].lineTotal, subTotal, grandTotal"]);
+
+        let accepting = PurchaseOrderAgent::new().with_returns(ReturnsConfig { enabled: true });
+        let (errors, warnings) = accepting.validate_purchase_order(&order(&[-10.0, -2.5], -25.0));
+        assert!(errors.is_empty() && warnings.is_empty(), "{:?} {:?}", errors, warnings);
+        let (errors, _) = accepting.validate_purchase_order(&order(&[-10.0, 10.0], 0.0));
+        assert_eq!(errors, ["Grand total is zero", "Return order lines must all be credits; items 2 are charges"]);
+    }
+}
@@ -0,0 +1,221 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::agent::{PurchaseOrder, PurchaseOrderAgent};
+
+/// Supplier risk scoring run while purchase orders are processed
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct RiskConfig {
+    pub enabled: bool,
+    /// Score orders with an external service instead of the built-in rules
+    pub url: Option<String>,
+    #[serde(with = "humantime_serde")]
+    pub timeout: Duration,
+    /// Grand total above which an order adds to the rules-based score
+    pub high_value_threshold: f64,
+    /// Suppliers outside this country add to the rules-based score
+    pub home_country: String,
+    /// Score at or above which an assessment is `medium`
+    pub medium_threshold: f64,
+    /// Score at or above which an assessment is `high`
+    pub high_threshold: f64,
+}
+
+impl Default for RiskConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            url: None,
+            timeout: Duration::from_secs(5),
+            high_value_threshold: 10000.0,
+            home_country: "USA".to_string(),
+            medium_threshold: 30.0,
+            high_threshold: 60.0,
+        }
+    }
+}
+
+/// Coarse band of a risk score
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RiskLevel {
+    Low,
+    Medium,
+    High,
+}
+
+impl RiskLevel {
+    pub fn name(&self) -> &'static str {
+        match self {
+            RiskLevel::Low => "low",
+            RiskLevel::Medium => "medium",
+            RiskLevel::High => "high",
+        }
+    }
+}
+
+/// Supplier risk attached to a processing result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RiskAssessment {
+    /// 0 (no known risk) to 100
+    pub score: f64,
+    pub level: RiskLevel,
+    /// Why the score is what it is, one reason per entry
+    pub rationale: Vec<String>,
+    /// Which scorer produced the assessment
+    pub scorer: String,
+}
+
+/// Scores the risk of ordering from a purchase order's supplier
+#[async_trait]
+pub trait RiskScorer: Send + Sync {
+    async fn score(&self, agent: &PurchaseOrderAgent, po: &PurchaseOrder) -> Result<RiskAssessment, Box<dyn Error>>;
+}
+
+/// Built-in scorer adding up weighted rules
+pub struct RulesRiskScorer {
+    config: RiskConfig,
+}
+
+impl RulesRiskScorer {
+    pub fn new(config: RiskConfig) -> Self {
+        Self { config }
+    }
+}
+
+#[async_trait]
+impl RiskScorer for RulesRiskScorer {
+    async fn score(&self, agent: &PurchaseOrderAgent, po: &PurchaseOrder) -> Result<RiskAssessment, Box<dyn Error>> {
+        let mut score = 0.0;
+        let mut rationale = Vec::new();
+        let mut add = |points: f64, reason: String| {
+            score += points;
+            rationale.push(reason);
+        };
+
+        if agent.suppliers().get(&po.supplier_name)?.is_none() {
+            add(30.0, format!("Supplier '{}' is not in supplier master data", po.supplier_name));
+        }
+        if !po.supplier_country.trim().eq_ignore_ascii_case(&self.config.home_country) {
+            add(20.0, format!("Supplier is based outside {} ({})", self.config.home_country, po.supplier_country));
+        }
+        if po.grand_total > self.config.high_value_threshold {
+            add(20.0, format!("Grand total {:.2} exceeds {:.2}", po.grand_total, self.config.high_value_threshold));
+        }
+        if po.supplier_address_line1.trim().is_empty() || po.supplier_postal_code.trim().is_empty() {
+            add(15.0, "Supplier address is incomplete".to_string());
+        }
+        if po.items.iter().any(|item| item.unit_price > self.config.high_value_threshold) {
+            add(15.0, "A single item is priced above the high-value threshold".to_string());
+        }
+
+        let score = f64::min(score, 100.0);
+        Ok(RiskAssessment {
+            score,
+            level: level_for(&self.config, score),
+            rationale,
+            scorer: "rules".to_string(),
+        })
+    }
+}
+
+fn level_for(config: &RiskConfig, score: f64) -> RiskLevel {
+    if score >= config.high_threshold {
+        RiskLevel::High
+    } else if score >= config.medium_threshold {
+        RiskLevel::Medium
+    } else {
+        RiskLevel::Low
+    }
+}
+
+/// What an external scoring service returns
+#[derive(Debug, Deserialize)]
+struct ServiceResponse {
+    score: f64,
+    #[serde(default)]
+    level: Option<RiskLevel>,
+    #[serde(default)]
+    rationale: Vec<String>,
+}
+
+/// Scorer that posts the purchase order to an external service
+///
+/// The service answers with `{"score", "rationale", "level"?}`; a missing level is
+/// derived from the configured thresholds.
+pub struct HttpRiskScorer {
+    client: reqwest::Client,
+    url: String,
+    config: RiskConfig,
+}
+
+impl HttpRiskScorer {
+    pub fn new(url: &str, config: RiskConfig) -> Result<Self, Box<dyn Error>> {
+        let client = reqwest::Client::builder().timeout(config.timeout).build()?;
+        Ok(Self { client, url: url.to_string(), config })
+    }
+}
+
+#[async_trait]
+impl RiskScorer for HttpRiskScorer {
+    async fn score(&self, _agent: &PurchaseOrderAgent, po: &PurchaseOrder) -> Result<RiskAssessment, Box<dyn Error>> {
+        let response: ServiceResponse =
+            self.client.post(&self.url).json(po).send().await?.error_for_status()?.json().await?;
+        let score = response.score.clamp(0.0, 100.0);
+        Ok(RiskAssessment {
+            score,
+            level: response.level.unwrap_or_else(|| level_for(&self.config, score)),
+            rationale: response.rationale,
+            scorer: self.url.clone(),
+        })
+    }
+}
+
+/// The scorer `config` asks for, if risk scoring is enabled
+pub fn scorer(config: &RiskConfig) -> Result<Option<Arc<dyn RiskScorer>>, Box<dyn Error>> {
+    if !config.enabled {
+        return Ok(None);
+    }
+    Ok(Some(match &config.url {
+        Some(url) => Arc::new(HttpRiskScorer::new(url, config.clone())?),
+        None => Arc::new(RulesRiskScorer::new(config.clone())),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::ProcessingResult;
+    use a2a::{A2AProtocol, Message, Part};
+
+    fn po(country: &str, grand_total: f64) -> serde_json::Value {
+        let mut po = crate::testing::order_of(0.0, &[("P-1", "Paper", 1, grand_total)]);
+        po.supplier_country = country.to_string();
+        serde_json::to_value(po).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_rules_scorer_attaches_assessment() {
+        let config = RiskConfig { enabled: true, ..RiskConfig::default() };
+        let agent = PurchaseOrderAgent::new().with_risk_scorer(scorer(&config).unwrap().unwrap());
+        let submit = |data| Message { role: "user".to_string(), parts: vec![Part::Data { data }] };
+
+        let task = agent.send_task(submit(po("USA", 10.0))).await.unwrap();
+        let risk = ProcessingResult::from_status(&task.status).unwrap().risk.unwrap();
+        assert_eq!(risk.score, 30.0, "unknown supplier only: {:?}", risk.rationale);
+        assert_eq!(risk.level, RiskLevel::Medium);
+
+        let task = agent.send_task(submit(po("Freedonia", 20000.0))).await.unwrap();
+        let risk = ProcessingResult::from_status(&task.status).unwrap().risk.unwrap();
+        assert_eq!(risk.level, RiskLevel::High);
+        assert_eq!(risk.rationale.len(), 4);
+        assert_eq!(agent.metrics().value("po_agent_risk_assessments_total", &[("level", "high")]), Some(1.0));
+
+        let unscored = PurchaseOrderAgent::new().send_task(submit(po("USA", 10.0))).await.unwrap();
+        assert!(ProcessingResult::from_status(&unscored.status).unwrap().risk.is_none());
+    }
+}
@@ -0,0 +1,127 @@
+//! Rounding and tolerance of the reconciliation checks
+//!
+//! ERPs differ in how they round: some round half up, some half to even, some
+//! truncate, and some round tax on every line before adding it up rather than
+//! once for the whole order. The line total, tax and grand total checks, and
+//! normalization, compute their expected amounts the way `[reconciliation]`
+//! says, so orders from those systems do not get spurious mismatch warnings.
+
+use serde::Deserialize;
+
+use crate::agent::{PurchaseOrder, PurchaseOrderItem};
+
+/// How amounts are rounded to cents
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RoundingMode {
+    /// Halves round away from zero, e.g. 0.125 to 0.13
+    #[default]
+    HalfUp,
+    /// Halves round to the even cent, e.g. 0.125 to 0.12 and 0.135 to 0.14
+    HalfEven,
+    /// Fractions of a cent are dropped, e.g. 0.129 to 0.12
+    Truncate,
+}
+
+impl RoundingMode {
+    /// Round `amount` to cents
+    pub fn round(self, amount: f64) -> f64 {
+        // Drop binary noise first, so 1.005 counts as the half it was written as
+        let cents = (amount * 100.0 * 1e6).round() / 1e6;
+        let cents = match self {
+            RoundingMode::HalfUp => cents.round(),
+            RoundingMode::HalfEven => cents.round_ties_even(),
+            RoundingMode::Truncate => cents.trunc(),
+        };
+        cents / 100.0
+    }
+}
+
+/// Where tax is rounded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaxRounding {
+    /// Tax is the subtotal times the tax rate, rounded once
+    #[default]
+    Order,
+    /// Tax is rounded on every line and the line amounts added up
+    Line,
+}
+
+/// How the reconciliation checks compute and compare amounts
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ReconciliationConfig {
+    pub rounding: RoundingMode,
+    pub tax_rounding: TaxRounding,
+    /// Largest difference between an expected and a stated amount that is not a mismatch
+    pub tolerance: f64,
+}
+
+impl Default for ReconciliationConfig {
+    fn default() -> Self {
+        Self {
+            rounding: RoundingMode::HalfUp,
+            tax_rounding: TaxRounding::Order,
+            tolerance: 0.01,
+        }
+    }
+}
+
+impl ReconciliationConfig {
+    pub fn round(&self, amount: f64) -> f64 {
+        self.rounding.round(amount)
+    }
+
+    /// Expected line total: quantity × unit price, rounded
+    pub fn line_total(&self, item: &PurchaseOrderItem) -> f64 {
+        self.round(item.quantity as f64 * item.unit_price)
+    }
+
+    /// Expected tax of an order from its line totals or subtotal and its tax rate
+    pub fn tax(&self, po: &PurchaseOrder) -> f64 {
+        match self.tax_rounding {
+            TaxRounding::Order => self.round(po.sub_total * po.tax_rate),
+            TaxRounding::Line => self.round(po.items.iter().map(|item| self.round(item.line_total * po.tax_rate)).sum()),
+        }
+    }
+
+    /// Whether a stated amount is within tolerance of the expected one
+    pub fn matches(&self, expected: f64, stated: f64) -> bool {
+        (expected - stated).abs() <= self.tolerance + 1e-9
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::PurchaseOrderAgent;
+
+    #[test]
+    fn test_rounding_modes() {
+        assert_eq!(RoundingMode::HalfUp.round(0.125), 0.13);
+        assert_eq!(RoundingMode::HalfUp.round(1.005), 1.01);
+        assert_eq!(RoundingMode::HalfUp.round(-0.125), -0.13);
+        assert_eq!(RoundingMode::HalfEven.round(0.125), 0.12);
+        assert_eq!(RoundingMode::HalfEven.round(0.135), 0.14);
+        assert_eq!(RoundingMode::Truncate.round(0.129), 0.12);
+        assert_eq!(RoundingMode::Truncate.round(-0.129), -0.12);
+    }
+
+    #[test]
+    fn test_per_line_tax_avoids_spurious_mismatch() {
+        // Three lines of 0.15 at 10% tax: 0.015 per line rounds up to 0.02 each
+        let mut po = crate::testing::order_of(0.1, &[("P-1", "Clip", 1, 0.15), ("P-2", "Pin", 1, 0.15), ("P-3", "Tack", 1, 0.15)]);
+        po.tax = 0.06;
+        po.grand_total = 0.51;
+
+        let strict = ReconciliationConfig { tolerance: 0.0, ..ReconciliationConfig::default() };
+        let (_, warnings) = PurchaseOrderAgent::new().with_reconciliation(strict.clone()).validate_purchase_order(&po);
+        assert_eq!(warnings, ["Tax calculation mismatch: expected 0.05, got 0.06"]);
+
+        let per_line = ReconciliationConfig { tax_rounding: TaxRounding::Line, ..strict };
+        assert_eq!(per_line.tax(&po), 0.06);
+        let (_, warnings) = PurchaseOrderAgent::new().with_reconciliation(per_line).validate_purchase_order(&po);
+        assert!(warnings.is_empty(), "{:?}", warnings);
+    }
+}
@@ -0,0 +1,153 @@
+//! US sales tax rates per state
+//!
+//! An order's tax rate is checked against the sales tax rate of the state it
+//! is delivered to: the ship-to state when the order has a US ship-to address,
+//! otherwise the supplier's state. Cities and counties add their own taxes on
+//! top of the state rate, so rates up to `local_allowance` above it pass.
+//! Orders the table does not cover are held to the `max_rate` band instead.
+
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use crate::address::country_code;
+use crate::agent::PurchaseOrder;
+use crate::severity::{Check, Findings};
+
+/// Statewide sales tax rates, including statewide local add-ons
+const STATE_RATES: [(&str, f64); 51] = [
+    ("AL", 0.04), ("AK", 0.0), ("AZ", 0.056), ("AR", 0.065), ("CA", 0.0725), ("CO", 0.029), ("CT", 0.0635),
+    ("DE", 0.0), ("DC", 0.06), ("FL", 0.06), ("GA", 0.04), ("HI", 0.04), ("ID", 0.06), ("IL", 0.0625),
+    ("IN", 0.07), ("IA", 0.06), ("KS", 0.065), ("KY", 0.06), ("LA", 0.0445), ("ME", 0.055), ("MD", 0.06),
+    ("MA", 0.0625), ("MI", 0.06), ("MN", 0.06875), ("MS", 0.07), ("MO", 0.04225), ("MT", 0.0), ("NE", 0.055),
+    ("NV", 0.0685), ("NH", 0.0), ("NJ", 0.06625), ("NM", 0.04875), ("NY", 0.04), ("NC", 0.0475), ("ND", 0.05),
+    ("OH", 0.0575), ("OK", 0.045), ("OR", 0.0), ("PA", 0.06), ("RI", 0.07), ("SC", 0.06), ("SD", 0.042),
+    ("TN", 0.07), ("TX", 0.0625), ("UT", 0.061), ("VT", 0.06), ("VA", 0.053), ("WA", 0.065), ("WV", 0.06),
+    ("WI", 0.05), ("WY", 0.04),
+];
+
+/// How order tax rates are checked
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct SalesTaxConfig {
+    /// Rates by state code, replacing or adding to the built-in table
+    pub rates: HashMap<String, f64>,
+    /// How far above the state rate local taxes may take an order's rate
+    pub local_allowance: f64,
+    /// How far below the state rate an order's rate may be
+    pub tolerance: f64,
+    /// Highest rate of orders the table does not cover
+    pub max_rate: f64,
+}
+
+impl Default for SalesTaxConfig {
+    fn default() -> Self {
+        Self {
+            rates: HashMap::new(),
+            local_allowance: 0.05,
+            tolerance: 0.001,
+            max_rate: 0.2,
+        }
+    }
+}
+
+impl SalesTaxConfig {
+    /// Sales tax rate of a US state, from the configured rates or the built-in table
+    pub fn rate(&self, state: &str) -> Option<f64> {
+        let state = state.trim().to_ascii_uppercase();
+        self.rates
+            .iter()
+            .find(|(code, _)| code.eq_ignore_ascii_case(&state))
+            .map(|(_, rate)| *rate)
+            .or_else(|| STATE_RATES.iter().find(|(code, _)| *code == state).map(|(_, rate)| *rate))
+    }
+
+    /// The state an order is taxed in and its rate, when the order is delivered in the US
+    /// to a state with a known rate
+    pub fn order_rate(&self, po: &PurchaseOrder) -> Option<(String, f64)> {
+        let state = match &po.ship_to {
+            Some(ship_to) if country_code(&ship_to.country) == Some("US") => &ship_to.state,
+            _ if country_code(&po.supplier_country) == Some("US") => &po.supplier_state,
+            _ => return None,
+        };
+        let rate = self.rate(state)?;
+        Some((state.trim().to_ascii_uppercase(), rate))
+    }
+}
+
+/// Flag an order whose tax rate does not fit the sales tax of its state
+pub fn validate(config: &SalesTaxConfig, po: &PurchaseOrder, findings: &mut Findings) {
+    match config.order_rate(po) {
+        Some((state, rate)) => {
+            let highest = rate + config.local_allowance;
+            if po.tax_rate < rate - config.tolerance || po.tax_rate > highest + 1e-9 {
+                findings.push(
+                    Check::UnusualTaxRate,
+                    format!(
+                        "Tax rate {:.3}% does not match the {} sales tax rate of {:.3}% (up to {:.3}% with local taxes)",
+                        po.tax_rate * 100.0,
+                        state,
+                        rate * 100.0,
+                        highest * 100.0
+                    ),
+                );
+            }
+        }
+        None if po.tax_rate < 0.0 || po.tax_rate > config.max_rate => {
+            findings.push(Check::UnusualTaxRate, "Unusual tax rate detected");
+        }
+        None => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::address::Address;
+    use crate::severity::SeverityPolicy;
+
+    fn po(tax_rate: f64) -> PurchaseOrder {
+        let mut po = crate::testing::valid_order();
+        po.tax_rate = tax_rate;
+        po
+    }
+
+    fn warnings(config: &SalesTaxConfig, po: &PurchaseOrder) -> Vec<String> {
+        let policy = SeverityPolicy::default();
+        let mut findings = Findings::new(&policy);
+        validate(config, po, &mut findings);
+        findings.into_parts().1
+    }
+
+    #[test]
+    fn test_tax_rate_is_checked_against_the_delivery_state() {
+        let config = SalesTaxConfig::default();
+        assert!(warnings(&config, &po(0.0625)).is_empty());
+        assert!(warnings(&config, &po(0.1025)).is_empty());
+        assert_eq!(
+            warnings(&config, &po(0.05)),
+            ["Tax rate 5.000% does not match the IL sales tax rate of 6.250% (up to 11.250% with local taxes)"]
+        );
+
+        let mut oregon = po(0.0);
+        oregon.ship_to = Some(Address {
+            attention: None,
+            address_line1: "1 Pine St".to_string(),
+            address_line2: None,
+            city: "Portland".to_string(),
+            state: "or".to_string(),
+            postal_code: "97201".to_string(),
+            country: "US".to_string(),
+        });
+        assert_eq!(config.order_rate(&oregon), Some(("OR".to_string(), 0.0)));
+        assert!(warnings(&config, &oregon).is_empty());
+
+        let custom = SalesTaxConfig { rates: HashMap::from([("il".to_string(), 0.05)]), ..SalesTaxConfig::default() };
+        assert!(warnings(&custom, &po(0.05)).is_empty());
+
+        let mut foreign = po(0.19);
+        foreign.supplier_country = "Germany".to_string();
+        assert!(warnings(&config, &foreign).is_empty());
+        foreign.tax_rate = 0.25;
+        assert_eq!(warnings(&config, &foreign), ["Unusual tax rate detected"]);
+    }
+}
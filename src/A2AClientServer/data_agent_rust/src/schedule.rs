@@ -0,0 +1,338 @@
+use a2a::{Message, Part};
+use chrono::{DateTime, Datelike, Duration as ChronoDuration, NaiveDate, TimeZone, Timelike, Utc};
+use serde::Deserialize;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+use crate::agent::{PurchaseOrder, PurchaseOrderAgent};
+use crate::events::TaskEventKind;
+
+/// Purchase orders generated on a schedule
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct SchedulerConfig {
+    pub orders: Vec<RecurringOrder>,
+}
+
+impl SchedulerConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.orders.iter().any(|order| order.enabled)
+    }
+}
+
+/// A purchase order submitted every time its cron expression fires
+#[derive(Debug, Clone, Deserialize)]
+pub struct RecurringOrder {
+    /// Name linking generated tasks back to the schedule
+    pub id: String,
+    /// `minute hour day-of-month month day-of-week`, evaluated in UTC
+    pub cron: CronSchedule,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Skill to process generated orders with; defaults to processing
+    #[serde(default)]
+    pub skill_id: Option<String>,
+    /// Template of each generated order; its PO number gets the run's timestamp appended
+    pub order: PurchaseOrder,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl RecurringOrder {
+    /// The order generated for the run at `occurrence`
+    pub fn instance(&self, occurrence: DateTime<Utc>) -> PurchaseOrder {
+        let mut po = self.order.clone();
+        po.po_number = format!("{}-{}", self.order.po_number, occurrence.format("%Y%m%d%H%M"));
+        po
+    }
+}
+
+/// Set of allowed values of one cron field, as a bit mask
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Field(u64);
+
+impl Field {
+    fn parse(spec: &str, min: u32, max: u32) -> Result<Self, String> {
+        let mut mask = 0u64;
+        for part in spec.split(',') {
+            let (range, step) = match part.split_once('/') {
+                Some((range, step)) => {
+                    let step: u32 = step.parse().map_err(|_| format!("invalid step '{}'", step))?;
+                    if step == 0 {
+                        return Err("step must be positive".to_string());
+                    }
+                    (range, step)
+                }
+                None => (part, 1),
+            };
+            let (start, end) = if range == "*" {
+                (min, max)
+            } else if let Some((start, end)) = range.split_once('-') {
+                let parse = |v: &str| v.parse::<u32>().map_err(|_| format!("invalid value '{}'", v));
+                (parse(start)?, parse(end)?)
+            } else {
+                let value = range.parse::<u32>().map_err(|_| format!("invalid value '{}'", range))?;
+                // `5/15` means from 5 to the end of the range in steps of 15
+                (value, if step > 1 { max } else { value })
+            };
+            if start < min || end > max || start > end {
+                return Err(format!("'{}' is outside {}-{}", part, min, max));
+            }
+            for value in (start..=end).step_by(step as usize) {
+                mask |= 1 << value;
+            }
+        }
+        Ok(Self(mask))
+    }
+
+    fn contains(&self, value: u32) -> bool {
+        self.0 & (1 << value) != 0
+    }
+
+    fn is_restricted(&self, min: u32, max: u32) -> bool {
+        (min..=max).any(|value| !self.contains(value))
+    }
+}
+
+/// Parsed five-field cron expression
+///
+/// Fields accept `*`, single values, `a-b` ranges, `/n` steps and comma-separated
+/// lists. Day-of-week runs from 0 (Sunday) to 7 (Sunday again). As in cron, a run
+/// matches either day field when both are restricted.
+#[derive(Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    expression: String,
+    minutes: Field,
+    hours: Field,
+    days_of_month: Field,
+    months: Field,
+    days_of_week: Field,
+}
+
+impl CronSchedule {
+    pub fn parse(expression: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minutes, hours, days_of_month, months, days_of_week] = fields[..] else {
+            return Err(format!("cron expression '{}' must have 5 fields", expression));
+        };
+        let field = |spec, min, max, name| {
+            Field::parse(spec, min, max).map_err(|e| format!("cron {} field: {}", name, e))
+        };
+        let mut days_of_week = field(days_of_week, 0, 7, "day-of-week")?;
+        if days_of_week.contains(7) {
+            days_of_week.0 |= 1;
+        }
+        Ok(Self {
+            expression: expression.to_string(),
+            minutes: field(minutes, 0, 59, "minute")?,
+            hours: field(hours, 0, 23, "hour")?,
+            days_of_month: field(days_of_month, 1, 31, "day-of-month")?,
+            months: field(months, 1, 12, "month")?,
+            days_of_week,
+        })
+    }
+
+    fn matches_day(&self, date: NaiveDate) -> bool {
+        if !self.months.contains(date.month()) {
+            return false;
+        }
+        let by_month_day = self.days_of_month.contains(date.day());
+        let by_week_day = self.days_of_week.contains(date.weekday().num_days_from_sunday());
+        match (self.days_of_month.is_restricted(1, 31), self.days_of_week.is_restricted(0, 6)) {
+            (true, true) => by_month_day || by_week_day,
+            (true, false) => by_month_day,
+            (false, true) => by_week_day,
+            (false, false) => true,
+        }
+    }
+
+    /// First run strictly after `after`, looking up to five years ahead
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let start = after.with_second(0)?.with_nanosecond(0)? + ChronoDuration::minutes(1);
+        let first_day = start.date_naive();
+        for offset in 0..366 * 5 {
+            let date = first_day + ChronoDuration::days(offset);
+            if !self.matches_day(date) {
+                continue;
+            }
+            for hour in (0..24).filter(|h| self.hours.contains(*h)) {
+                for minute in (0..60).filter(|m| self.minutes.contains(*m)) {
+                    let candidate = Utc.from_utc_datetime(&date.and_hms_opt(hour, minute, 0)?);
+                    if candidate >= start {
+                        return Some(candidate);
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+impl fmt::Debug for CronSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "CronSchedule({:?})", self.expression)
+    }
+}
+
+impl fmt::Display for CronSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.expression)
+    }
+}
+
+impl<'de> Deserialize<'de> for CronSchedule {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let expression = String::deserialize(deserializer)?;
+        CronSchedule::parse(&expression).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Generate and process one instance of a recurring order
+///
+/// Returns the generated task's ID.
+pub async fn run_once(
+    agent: &PurchaseOrderAgent,
+    order: &RecurringOrder,
+    occurrence: DateTime<Utc>,
+) -> Result<String, String> {
+    let po = order.instance(occurrence);
+    let message = Message {
+        role: "user".to_string(),
+        parts: vec![Part::Data { data: serde_json::json!({ "purchaseOrder": po, "schedule_id": order.id }) }],
+    };
+    let task = agent
+        .send_task_with_skill(message, order.skill_id.as_deref())
+        .await
+        .map_err(|e| e.to_string())?;
+    agent
+        .record_event(&task.id, TaskEventKind::Scheduled { schedule_id: order.id.clone(), occurrence })
+        .map_err(|e| e.to_string())?;
+    Ok(task.id)
+}
+
+/// Submit each enabled recurring order whenever its schedule fires
+///
+/// Runs missed while the process was down are not made up.
+pub fn spawn_scheduler(agent: Arc<PurchaseOrderAgent>, config: SchedulerConfig) -> Option<JoinHandle<()>> {
+    if !config.is_enabled() {
+        return None;
+    }
+
+    let orders: Vec<RecurringOrder> = config.orders.into_iter().filter(|order| order.enabled).collect();
+    info!(orders = orders.len(), "recurring order scheduler started");
+
+    Some(tokio::spawn(async move {
+        let now = Utc::now();
+        let mut next: Vec<Option<DateTime<Utc>>> = orders.iter().map(|order| order.cron.next_after(now)).collect();
+        loop {
+            let Some(due) = next.iter().flatten().min().copied() else {
+                warn!("no recurring order will run again; scheduler stopping");
+                return;
+            };
+            // Wake at least every minute so clock adjustments are picked up
+            let wait = (due - Utc::now()).to_std().unwrap_or_default().min(Duration::from_secs(60));
+            tokio::time::sleep(wait).await;
+
+            let now = Utc::now();
+            for (order, next_run) in orders.iter().zip(next.iter_mut()) {
+                let Some(occurrence) = *next_run else { continue };
+                if occurrence > now {
+                    continue;
+                }
+                let outcome = match run_once(&agent, order, occurrence).await {
+                    Ok(task_id) => {
+                        info!(schedule = %order.id, task_id = %task_id, %occurrence, "recurring order submitted");
+                        "submitted"
+                    }
+                    Err(e) => {
+                        warn!(schedule = %order.id, error = %e, %occurrence, "recurring order failed");
+                        "error"
+                    }
+                };
+                agent.metrics().increment(
+                    "po_agent_scheduled_orders_total",
+                    "Recurring purchase orders generated by the scheduler",
+                    &[("schedule", &order.id), ("outcome", outcome)],
+                    1,
+                );
+                *next_run = order.cron.next_after(now);
+            }
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_cron_next_after() {
+        let monthly = CronSchedule::parse("0 6 1 * *").unwrap();
+        assert_eq!(monthly.next_after(at("2026-03-15T10:00:00Z")), Some(at("2026-04-01T06:00:00Z")));
+        assert_eq!(monthly.next_after(at("2026-04-01T06:00:00Z")), Some(at("2026-05-01T06:00:00Z")));
+
+        let weekdays = CronSchedule::parse("*/30 9-10 * * 1-5").unwrap();
+        // 2026-03-14 is a Saturday
+        assert_eq!(weekdays.next_after(at("2026-03-14T09:10:00Z")), Some(at("2026-03-16T09:00:00Z")));
+        assert_eq!(weekdays.next_after(at("2026-03-16T10:30:00Z")), Some(at("2026-03-17T09:00:00Z")));
+
+        let sundays = CronSchedule::parse("0 0 * * 7").unwrap();
+        assert_eq!(sundays.next_after(at("2026-03-14T00:00:00Z")), Some(at("2026-03-15T00:00:00Z")));
+
+        assert!(CronSchedule::parse("0 6 1 *").is_err());
+        assert!(CronSchedule::parse("60 * * * *").is_err());
+        assert!(CronSchedule::parse("0 0 30 2 *").unwrap().next_after(at("2026-01-01T00:00:00Z")).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_run_once_links_task_to_schedule() {
+        let config: SchedulerConfig = toml::from_str(
+            r#"
+            [[orders]]
+            id = "paper-monthly"
+            cron = "0 6 1 * *"
+
+            [orders.order]
+            supplierName = "Acme Office Supply"
+            supplierAddressLine1 = "1 Main St"
+            supplierCity = "Springfield"
+            supplierState = "IL"
+            supplierPostalCode = "62701"
+            supplierCountry = "USA"
+            items = [{ itemCode = "P-1", description = "Paper", quantity = 2, unitPrice = 5.0, lineTotal = 10.0 }]
+            poNumber = "SUB-PAPER"
+            createdBy = "scheduler"
+            buyerDepartment = "Operations"
+            taxRate = 0.1
+            subTotal = 10.0
+            tax = 1.0
+            grandTotal = 11.0
+            isApproved = true
+            "#,
+        )
+        .unwrap();
+        assert!(config.is_enabled());
+
+        let agent = PurchaseOrderAgent::new();
+        let occurrence = at("2026-04-01T06:00:00Z");
+        let task_id = run_once(&agent, &config.orders[0], occurrence).await.unwrap();
+
+        let record = agent.task_store().get(&task_id).unwrap().unwrap();
+        let result = crate::agent::ProcessingResult::from_status(&record.task.status).unwrap();
+        assert_eq!(result.po_number, "SUB-PAPER-202604010600");
+        let events = agent.event_log().events(&task_id).unwrap();
+        assert!(matches!(
+            &events.last().unwrap().kind,
+            TaskEventKind::Scheduled { schedule_id, .. } if schedule_id == "paper-monthly"
+        ));
+    }
+}
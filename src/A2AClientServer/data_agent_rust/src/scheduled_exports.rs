@@ -0,0 +1,524 @@
+//! Scheduled exports of task results
+//!
+//! Each job in `[[exports.jobs]]` writes the results of the tasks updated in a
+//! period before its run, such as the day before, as CSV, NDJSON or Parquet.
+//! The file goes to the configured blob store or is POSTed to a webhook, signed
+//! like webhook deliveries. The background scheduler runs jobs as their cron
+//! expressions fire, an administrator can run one at once, and the outcome of
+//! recent runs is kept for the admin API.
+
+use chrono::{DateTime, Duration as ChronoDuration, NaiveTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+use crate::agent::{ProcessingResult, PurchaseOrderAgent};
+#[cfg(feature = "parquet")]
+use crate::export::parquet;
+use crate::export::{Column, ResultRow};
+use crate::labels::{self, Labels};
+use crate::monthly_close::Month;
+use crate::schedule::CronSchedule;
+use crate::signing::{self, SIGNATURE_HEADER, TIMESTAMP_HEADER};
+use crate::signing_keys;
+use crate::store::TaskRecord;
+
+/// Runs kept for the admin API, across all jobs
+pub const MAX_RUNS: usize = 200;
+
+/// Header naming the job a webhook export comes from
+pub const JOB_HEADER: &str = "X-Export-Job";
+
+const PARQUET_NOT_BUILT: &str = "Parquet exports need a binary built with the `parquet` feature";
+
+/// The `[exports]` section
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ExportsConfig {
+    pub jobs: Vec<ExportJob>,
+}
+
+impl ExportsConfig {
+    /// Check that job ids are unique and blob destinations have a blob store to write to
+    pub fn validate(&self, has_blob_store: bool) -> Result<(), String> {
+        let mut ids = HashSet::new();
+        for job in &self.jobs {
+            if !ids.insert(job.id.as_str()) {
+                return Err(format!("Export job {} is configured twice", job.id));
+            }
+            if matches!(job.destination, ExportDestination::Blob { .. }) && !has_blob_store && job.enabled {
+                return Err(format!("Export job {} writes to the blob store, but no [blobs] backend is configured", job.id));
+            }
+            if job.format == ExportFormat::Parquet && !cfg!(feature = "parquet") && job.enabled {
+                return Err(format!("Export job {}: {}", job.id, PARQUET_NOT_BUILT));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn job(&self, id: &str) -> Option<&ExportJob> {
+        self.jobs.iter().find(|job| job.id == id)
+    }
+}
+
+/// An export written every time its cron expression fires
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExportJob {
+    pub id: String,
+    /// `minute hour day-of-month month day-of-week`, evaluated in UTC
+    pub cron: CronSchedule,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub format: ExportFormat,
+    /// Tasks last updated in this period before the run are exported
+    #[serde(default)]
+    pub range: ExportRange,
+    /// Only tasks in this state, e.g. `completed`
+    #[serde(default)]
+    pub state: Option<String>,
+    /// Only tasks with all of these labels
+    #[serde(default)]
+    pub labels: Labels,
+    pub destination: ExportDestination,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// File format of an export
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    /// One results export row per line, as `GET /admin/export` writes them
+    Ndjson,
+    Parquet,
+}
+
+impl ExportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Ndjson => "ndjson",
+            ExportFormat::Parquet => "parquet",
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv",
+            ExportFormat::Ndjson => "application/x-ndjson",
+            ExportFormat::Parquet => "application/vnd.apache.parquet",
+        }
+    }
+}
+
+/// Period before a run whose tasks it exports, in whole UTC days or months
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportRange {
+    /// The day before the run
+    #[default]
+    PreviousDay,
+    /// The seven days before the run's day
+    PreviousWeek,
+    /// The calendar month before the run
+    PreviousMonth,
+}
+
+impl ExportRange {
+    /// Start and end, exclusive, of the period exported by a run at `at`
+    pub fn bounds(self, at: DateTime<Utc>) -> (DateTime<Utc>, DateTime<Utc>) {
+        let midnight = Utc.from_utc_datetime(&at.date_naive().and_time(NaiveTime::MIN));
+        match self {
+            ExportRange::PreviousDay => (midnight - ChronoDuration::days(1), midnight),
+            ExportRange::PreviousWeek => (midnight - ChronoDuration::days(7), midnight),
+            ExportRange::PreviousMonth => {
+                let month = Month::before(at);
+                (month.start(), month.end())
+            }
+        }
+    }
+}
+
+/// Where an export is written
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ExportDestination {
+    /// A key in the configured blob store; `{id}`, `{date}` (the period's first day) and `{ext}` are replaced
+    Blob { key: String },
+    /// POSTed to a URL, signed with `secret` or else the webhook signing keys
+    Webhook {
+        url: String,
+        #[serde(default)]
+        secret: Option<String>,
+        #[serde(default = "default_timeout", with = "humantime_serde")]
+        timeout: Duration,
+    },
+}
+
+fn default_timeout() -> Duration {
+    Duration::from_secs(30)
+}
+
+impl ExportDestination {
+    /// The blob key or URL, without any secret
+    pub fn describe(&self) -> String {
+        match self {
+            ExportDestination::Blob { key } => format!("blob:{}", key),
+            ExportDestination::Webhook { url, .. } => url.clone(),
+        }
+    }
+}
+
+/// What started a run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunTrigger {
+    Schedule,
+    Manual,
+}
+
+/// One run of an export job
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportRun {
+    pub job_id: String,
+    pub trigger: RunTrigger,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub range_start: DateTime<Utc>,
+    pub range_end: DateTime<Utc>,
+    pub format: ExportFormat,
+    /// Blob key written or URL posted to
+    pub destination: String,
+    pub rows: usize,
+    pub bytes: usize,
+    pub succeeded: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// The most recent runs of every job, newest last
+#[derive(Default)]
+pub struct ExportRuns {
+    runs: Mutex<VecDeque<ExportRun>>,
+}
+
+impl ExportRuns {
+    pub fn record(&self, run: ExportRun) {
+        let mut runs = self.runs.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        runs.push_back(run);
+        while runs.len() > MAX_RUNS {
+            runs.pop_front();
+        }
+    }
+
+    /// Runs of `job`, or of every job, newest first
+    pub fn list(&self, job: Option<&str>) -> Vec<ExportRun> {
+        let runs = self.runs.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        runs.iter().rev().filter(|run| job.is_none_or(|job| run.job_id == job)).cloned().collect()
+    }
+}
+
+/// The tasks `job` exports for the period from `start` to `end`, oldest update first, redacted for storage
+pub fn records(agent: &PurchaseOrderAgent, job: &ExportJob, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<Vec<TaskRecord>, String> {
+    let mut records: Vec<TaskRecord> = agent
+        .task_store()
+        .list()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|record| record.updated_at >= start && record.updated_at < end)
+        .filter(|record| job.state.as_deref().is_none_or(|state| record.state_name() == state))
+        .filter(|record| labels::matches(&record.labels, &job.labels))
+        .map(|record| agent.redaction().persisted(&record))
+        .collect();
+    records.sort_by_key(|record| record.updated_at);
+    Ok(records)
+}
+
+const COLUMNS: [&str; 13] = [
+    "task_id",
+    "state",
+    "skill_id",
+    "created_at",
+    "updated_at",
+    "po_number",
+    "supplier_name",
+    "buyer_department",
+    "status",
+    "sub_total",
+    "tax",
+    "grand_total",
+    "labels",
+];
+
+/// Encode `records` in `format`
+///
+/// CSV and Parquet have one flat row per task, with the processing result's
+/// totals and `labels` as `key:value` pairs; NDJSON has the full result.
+pub fn render(records: &[TaskRecord], format: ExportFormat) -> Result<Vec<u8>, String> {
+    let results: Vec<Option<ProcessingResult>> = records.iter().map(|record| ProcessingResult::from_status(&record.task.status)).collect();
+    let text = |value: &dyn Fn(&TaskRecord, Option<&ProcessingResult>) -> Option<String>| {
+        records.iter().zip(&results).map(|(record, result)| value(record, result.as_ref())).collect::<Vec<_>>()
+    };
+    let number = |value: fn(&ProcessingResult) -> f64| results.iter().map(|result| result.as_ref().map(value)).collect::<Vec<_>>();
+    let columns = || {
+        [
+            Column::Text(text(&|record, _| Some(record.task.id.clone()))),
+            Column::Text(text(&|record, _| Some(record.state_name().to_string()))),
+            Column::Text(text(&|record, _| record.skill_id.clone())),
+            Column::Text(text(&|record, _| Some(record.created_at.to_rfc3339()))),
+            Column::Text(text(&|record, _| Some(record.updated_at.to_rfc3339()))),
+            Column::Text(text(&|_, result| result.map(|r| r.po_number.clone()))),
+            Column::Text(text(&|_, result| result.map(|r| r.supplier_name.clone()))),
+            Column::Text(text(&|_, result| result.map(|r| r.buyer_department.clone()))),
+            Column::Text(text(&|_, result| result.map(|r| r.status.clone()))),
+            Column::Double(number(|r| r.sub_total)),
+            Column::Double(number(|r| r.tax)),
+            Column::Double(number(|r| r.grand_total)),
+            Column::Text(text(&|record, _| {
+                (!record.labels.is_empty())
+                    .then(|| record.labels.iter().map(|(key, value)| format!("{}:{}", key, value)).collect::<Vec<_>>().join(","))
+            })),
+        ]
+    };
+
+    match format {
+        ExportFormat::Ndjson => {
+            let mut out = Vec::new();
+            for record in records {
+                if serde_json::to_writer(&mut out, &ResultRow::from_record(record)).is_ok() {
+                    out.push(b'\n');
+                }
+            }
+            Ok(out)
+        }
+        #[cfg(feature = "parquet")]
+        ExportFormat::Parquet => {
+            let named: Vec<(&str, Column)> = COLUMNS.into_iter().zip(columns()).collect();
+            parquet::write(&named)
+        }
+        #[cfg(not(feature = "parquet"))]
+        ExportFormat::Parquet => Err(PARQUET_NOT_BUILT.to_string()),
+        ExportFormat::Csv => {
+            let columns = columns();
+            let mut out = COLUMNS.join(",");
+            out.push('\n');
+            for row in 0..records.len() {
+                let fields: Vec<String> = columns
+                    .iter()
+                    .map(|column| match column {
+                        Column::Text(values) => values[row].as_deref().map(csv_field).unwrap_or_default(),
+                        Column::Double(values) => values[row].map(|value| value.to_string()).unwrap_or_default(),
+                    })
+                    .collect();
+                out.push_str(&fields.join(","));
+                out.push('\n');
+            }
+            Ok(out.into_bytes())
+        }
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Run `job` for the period before `at`, recording the run
+pub async fn run_once(agent: &PurchaseOrderAgent, job: &ExportJob, at: DateTime<Utc>, trigger: RunTrigger) -> ExportRun {
+    let started_at = Utc::now();
+    let (range_start, range_end) = job.range.bounds(at);
+    let destination = match &job.destination {
+        ExportDestination::Blob { key } => key
+            .replace("{id}", &job.id)
+            .replace("{date}", &range_start.format("%Y-%m-%d").to_string())
+            .replace("{ext}", job.format.extension()),
+        ExportDestination::Webhook { url, .. } => url.clone(),
+    };
+    let mut run = ExportRun {
+        job_id: job.id.clone(),
+        trigger,
+        started_at,
+        finished_at: started_at,
+        range_start,
+        range_end,
+        format: job.format,
+        destination: destination.clone(),
+        rows: 0,
+        bytes: 0,
+        succeeded: false,
+        error: None,
+    };
+
+    let outcome = match records(agent, job, range_start, range_end) {
+        Ok(records) => match render(&records, job.format) {
+            Ok(content) => {
+                run.rows = records.len();
+                run.bytes = content.len();
+                deliver(agent, job, &destination, content).await
+            }
+            Err(e) => Err(e),
+        },
+        Err(e) => Err(e),
+    };
+    run.finished_at = Utc::now();
+    run.succeeded = outcome.is_ok();
+    run.error = outcome.err();
+    agent.metrics().increment(
+        "po_agent_scheduled_exports_total",
+        "Runs of scheduled exports",
+        &[("job", &job.id), ("outcome", if run.succeeded { "written" } else { "error" })],
+        1,
+    );
+    agent.export_runs().record(run.clone());
+    run
+}
+
+async fn deliver(agent: &PurchaseOrderAgent, job: &ExportJob, destination: &str, content: Vec<u8>) -> Result<(), String> {
+    match &job.destination {
+        ExportDestination::Blob { .. } => {
+            let blobs = agent.blob_store().cloned().ok_or("No blob store is configured")?;
+            let key = destination.to_string();
+            tokio::task::spawn_blocking(move || blobs.put(&key, &content))
+                .await
+                .map_err(|e| e.to_string())?
+                .map_err(|e| e.to_string())
+        }
+        ExportDestination::Webhook { url, secret, timeout } => {
+            let client = reqwest::Client::builder().timeout(*timeout).build().map_err(|e| e.to_string())?;
+            let timestamp = Utc::now().timestamp();
+            let mut request = client
+                .post(url)
+                .header(reqwest::header::CONTENT_TYPE, job.format.content_type())
+                .header(JOB_HEADER, &job.id);
+            match secret {
+                Some(secret) => {
+                    request = request
+                        .header(TIMESTAMP_HEADER, timestamp.to_string())
+                        .header(SIGNATURE_HEADER, signing::sign(secret, timestamp, &content));
+                }
+                None => match signing_keys::active(agent.signing_keys().as_ref(), Utc::now()) {
+                    Ok(keys) => {
+                        for (name, value) in signing_keys::signature_headers(&keys, timestamp, &content) {
+                            request = request.header(name, value);
+                        }
+                    }
+                    Err(e) => warn!(job = %job.id, error = %e, "signing key lookup failed, exporting unsigned"),
+                },
+            }
+            let response = request.body(content).send().await.map_err(|e| e.to_string())?;
+            if !response.status().is_success() {
+                return Err(format!("receiver responded {}", response.status()));
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Run each enabled export job whenever its schedule fires
+///
+/// Runs missed while the process was down are not made up.
+pub fn spawn_scheduler(agent: Arc<PurchaseOrderAgent>, config: ExportsConfig) -> Option<JoinHandle<()>> {
+    let jobs: Vec<ExportJob> = config.jobs.into_iter().filter(|job| job.enabled).collect();
+    if jobs.is_empty() {
+        return None;
+    }
+    info!(jobs = jobs.len(), "export scheduler started");
+
+    Some(tokio::spawn(async move {
+        let now = Utc::now();
+        let mut next: Vec<Option<DateTime<Utc>>> = jobs.iter().map(|job| job.cron.next_after(now)).collect();
+        loop {
+            let Some(due) = next.iter().flatten().min().copied() else {
+                warn!("no export job will run again; scheduler stopping");
+                return;
+            };
+            // Wake at least every minute so clock adjustments are picked up
+            let wait = (due - Utc::now()).to_std().unwrap_or_default().min(Duration::from_secs(60));
+            tokio::time::sleep(wait).await;
+
+            let now = Utc::now();
+            for (job, next_run) in jobs.iter().zip(next.iter_mut()) {
+                let Some(occurrence) = *next_run else { continue };
+                if occurrence > now {
+                    continue;
+                }
+                let run = run_once(&agent, job, occurrence, RunTrigger::Schedule).await;
+                match &run.error {
+                    None => info!(job = %job.id, rows = run.rows, destination = %run.destination, "scheduled export written"),
+                    Some(e) => warn!(job = %job.id, error = %e, "scheduled export failed"),
+                }
+                *next_run = job.cron.next_after(now);
+            }
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blob::{BlobStore, MemoryBlobStore};
+    use a2a::A2AProtocol;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_ranges_cover_the_period_before_the_run() {
+        let run = at("2026-03-01T02:00:00Z");
+        assert_eq!(ExportRange::PreviousDay.bounds(run), (at("2026-02-28T00:00:00Z"), at("2026-03-01T00:00:00Z")));
+        assert_eq!(ExportRange::PreviousWeek.bounds(run), (at("2026-02-22T00:00:00Z"), at("2026-03-01T00:00:00Z")));
+        assert_eq!(ExportRange::PreviousMonth.bounds(at("2026-03-15T02:00:00Z")), (at("2026-02-01T00:00:00Z"), at("2026-03-01T00:00:00Z")));
+    }
+
+    #[tokio::test]
+    async fn test_run_writes_the_previous_days_results_to_the_blob_store() {
+        let blobs = Arc::new(MemoryBlobStore::default());
+        let agent = PurchaseOrderAgent::new().with_blob_store(blobs.clone());
+        let task = agent.send_task(crate::testing::message(&crate::testing::valid_order())).await.unwrap();
+        agent.task_store().update(&task.id, &mut |record| record.labels = [("source".to_string(), "sap".to_string())].into()).unwrap();
+
+        let config: ExportsConfig = toml::from_str(
+            r#"
+            [[jobs]]
+            id = "nightly"
+            cron = "0 2 * * *"
+            destination = { type = "blob", key = "exports/{id}/{date}.{ext}" }
+            "#,
+        )
+        .unwrap();
+        assert!(config.validate(false).is_err());
+        config.validate(true).unwrap();
+
+        let tomorrow = Utc::now() + ChronoDuration::days(1);
+        let run = run_once(&agent, &config.jobs[0], tomorrow, RunTrigger::Manual).await;
+        assert!(run.succeeded, "{:?}", run.error);
+        assert_eq!(run.rows, 1);
+        let key = format!("exports/nightly/{}.csv", Utc::now().format("%Y-%m-%d"));
+        assert_eq!(run.destination, key);
+
+        let csv = String::from_utf8(blobs.get(&key).unwrap().unwrap()).unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some(COLUMNS.join(",").as_str()));
+        let row = lines.next().unwrap();
+        assert!(row.starts_with(&task.id) && row.contains(",PO-1,Acme Office Supply,Marketing,") && row.ends_with(",source:sap"), "{}", row);
+        assert_eq!(agent.export_runs().list(Some("nightly")).len(), 1);
+
+        // Nothing was updated the day before today
+        let empty = run_once(&agent, &config.jobs[0], Utc::now(), RunTrigger::Manual).await;
+        assert_eq!(empty.rows, 0);
+        let parquet = render(&agent.task_store().list().unwrap(), ExportFormat::Parquet).unwrap();
+        assert!(parquet.starts_with(b"PAR1") && parquet.ends_with(b"PAR1"));
+    }
+}
@@ -0,0 +1,255 @@
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use crate::agent::PurchaseOrder;
+
+/// Denied-party screening of purchase order suppliers
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ScreeningConfig {
+    /// Denied-party list, as CSV (`.csv`) or a JSON array (anything else); screening is off without one
+    pub list: Option<PathBuf>,
+}
+
+/// A party that purchase orders may not be placed with
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeniedParty {
+    pub name: String,
+    /// Street address; when given, a supplier at this address matches even under another name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    /// Restricts an address match to suppliers in this country
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
+    /// List the entry came from, e.g. `OFAC SDN`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+}
+
+/// Which supplier field matched a denied party
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchedOn {
+    Name,
+    Address,
+}
+
+/// A supplier that matched an entry of the denied-party list
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScreeningMatch {
+    pub party: DeniedParty,
+    pub matched_on: MatchedOn,
+}
+
+impl ScreeningMatch {
+    /// Validation error reported for the match
+    pub fn error(&self) -> String {
+        let source = self.party.source.as_deref().map(|s| format!(" ({})", s)).unwrap_or_default();
+        match self.matched_on {
+            MatchedOn::Name => format!("Supplier matches denied party '{}'{}", self.party.name, source),
+            MatchedOn::Address => format!("Supplier address matches denied party '{}'{}", self.party.name, source),
+        }
+    }
+}
+
+/// Denied parties keyed for matching
+pub struct DeniedPartyList {
+    entries: Vec<(DeniedParty, String, Option<String>)>,
+}
+
+impl DeniedPartyList {
+    pub fn new(parties: Vec<DeniedParty>) -> Self {
+        let entries = parties
+            .into_iter()
+            .map(|party| {
+                let name = normalize_name(&party.name);
+                let address = party.address.as_deref().map(normalize).filter(|a| !a.is_empty());
+                (party, name, address)
+            })
+            .collect();
+        Self { entries }
+    }
+
+    /// Read a list from `path`, as CSV if it ends in `.csv` and as JSON otherwise
+    ///
+    /// CSV files have a header row naming the `name`, `address`, `country` and
+    /// `source` columns; only `name` is required.
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read denied-party list {}: {}", path.display(), e))?;
+        let parties = match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("csv") => parse_csv(&contents)?,
+            _ => serde_json::from_str(&contents)
+                .map_err(|e| format!("Failed to parse denied-party list {}: {}", path.display(), e))?,
+        };
+        Ok(Self::new(parties))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Denied parties the order's supplier matches by name or by address
+    pub fn screen(&self, po: &PurchaseOrder) -> Vec<ScreeningMatch> {
+        let name = normalize_name(&po.supplier_name);
+        let address = normalize(&po.supplier_address_line1);
+
+        self.entries
+            .iter()
+            .filter_map(|(party, party_name, party_address)| {
+                let matched_on = if !name.is_empty() && name == *party_name {
+                    MatchedOn::Name
+                } else if party_address.as_ref().is_some_and(|a| *a == address)
+                    && party.country.as_ref().is_none_or(|c| c.trim().eq_ignore_ascii_case(po.supplier_country.trim()))
+                {
+                    MatchedOn::Address
+                } else {
+                    return None;
+                };
+                Some(ScreeningMatch { party: party.clone(), matched_on })
+            })
+            .collect()
+    }
+}
+
+/// Lowercase, drop punctuation and collapse whitespace
+fn normalize(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c.to_ascii_lowercase() } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Normalize a company name, also dropping trailing legal-form suffixes
+fn normalize_name(value: &str) -> String {
+    const SUFFIXES: [&str; 10] = ["inc", "incorporated", "ltd", "limited", "llc", "corp", "corporation", "co", "company", "gmbh"];
+    let normalized = normalize(value);
+    let mut words: Vec<&str> = normalized.split(' ').collect();
+    while words.len() > 1 && words.last().is_some_and(|w| SUFFIXES.contains(w)) {
+        words.pop();
+    }
+    words.join(" ")
+}
+
+/// Parse a CSV denied-party list with a header row
+fn parse_csv(contents: &str) -> Result<Vec<DeniedParty>, Box<dyn Error>> {
+    let mut rows = csv_rows(contents)?.into_iter();
+    let header: Vec<String> = rows.next().unwrap_or_default().iter().map(|h| h.trim().to_ascii_lowercase()).collect();
+    let column = |name: &str| header.iter().position(|h| h == name);
+    let name_column = column("name").ok_or("Denied-party CSV has no 'name' column")?;
+    let (address, country, source) = (column("address"), column("country"), column("source"));
+
+    let mut parties = Vec::new();
+    for (line, row) in rows.enumerate() {
+        let field = |index: Option<usize>| {
+            index.and_then(|i| row.get(i)).map(|v| v.trim().to_string()).filter(|v| !v.is_empty())
+        };
+        let Some(name) = field(Some(name_column)) else {
+            if row.iter().all(|v| v.trim().is_empty()) {
+                continue;
+            }
+            return Err(format!("Denied-party CSV row {} has no name", line + 2).into());
+        };
+        parties.push(DeniedParty { name, address: field(address), country: field(country), source: field(source) });
+    }
+    Ok(parties)
+}
+
+/// Split CSV text into rows of fields, honouring double-quoted fields
+fn csv_rows(contents: &str) -> Result<Vec<Vec<String>>, Box<dyn Error>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = contents.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => row.push(std::mem::take(&mut field)),
+            '\r' if !quoted => {}
+            '\n' if !quoted => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            _ => field.push(c),
+        }
+    }
+    if quoted {
+        return Err("Denied-party CSV has an unterminated quoted field".into());
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::{ProcessingResult, PurchaseOrderAgent};
+    use crate::events::TaskEventKind;
+    use a2a::A2AProtocol;
+    use std::sync::Arc;
+
+    fn po(supplier: &str, address: &str) -> PurchaseOrder {
+        let mut po = crate::testing::valid_order();
+        po.supplier_name = supplier.to_string();
+        po.supplier_address_line1 = address.to_string();
+        po
+    }
+
+    #[test]
+    fn test_csv_list_matches_name_and_address() {
+        let parties = parse_csv(
+            "name,address,country,source\n\"Evil Widgets, Inc.\",,,OFAC SDN\r\nShell Co,\"13 Dock Rd\",usa,\n\n",
+        )
+        .unwrap();
+        assert_eq!(parties.len(), 2);
+        assert_eq!(parties[0].name, "Evil Widgets, Inc.");
+        let list = DeniedPartyList::new(parties);
+
+        let matches = list.screen(&po("EVIL WIDGETS", "1 Main St"));
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].matched_on, MatchedOn::Name);
+        assert_eq!(matches[0].error(), "Supplier matches denied party 'Evil Widgets, Inc.' (OFAC SDN)");
+
+        let matches = list.screen(&po("Harbor Trading", "13 dock rd."));
+        assert_eq!(matches[0].matched_on, MatchedOn::Address);
+        assert!(list.screen(&po("Evil Widgets Holdings", "1 Main St")).is_empty());
+        assert!(parse_csv("address\n1 Main St\n").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_match_fails_validation_and_is_audited() {
+        let list = DeniedPartyList::new(vec![DeniedParty {
+            name: "Evil Widgets".to_string(),
+            address: None,
+            country: None,
+            source: None,
+        }]);
+        let agent = PurchaseOrderAgent::new().with_denied_parties(Arc::new(list));
+        let message = crate::testing::message(&po("Evil Widgets LLC", "1 Main St"));
+
+        let task = agent.send_task(message).await.unwrap();
+        let result = ProcessingResult::from_status(&task.status).unwrap();
+        assert_eq!(result.status, "VALIDATION_FAILED");
+        assert_eq!(result.screening_matches.len(), 1);
+        let events = agent.event_log().events(&task.id).unwrap();
+        assert!(events.iter().any(|e| matches!(e.kind, TaskEventKind::ScreeningMatched { .. })));
+        assert_eq!(agent.metrics().value("po_agent_screening_matches_total", &[]), Some(1.0));
+    }
+}
@@ -0,0 +1,367 @@
+//! Saved searches: named filters of the task list
+//!
+//! A saved search keeps a set of task filters under a name, such as "Marketing
+//! orders that failed this month", so the dashboard can open it as
+//! `/dashboard?view=<name>` instead of every client encoding the same query
+//! parameters. Periods are relative and evaluated when the search is run.
+//! Searches live in memory unless `[searches] path` names a JSON file that
+//! keeps them across restarts.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use crate::labels::{self, Labels};
+use crate::monthly_close::Month;
+use crate::store::{StoreError, StoreResult};
+
+/// Longest name of a saved search
+pub const MAX_NAME_LEN: usize = 64;
+
+/// The `[searches]` section
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct SearchesConfig {
+    /// JSON file saved searches are kept in; unset to keep them in memory
+    pub path: Option<PathBuf>,
+}
+
+/// Relative time range a task was created in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Period {
+    Today,
+    Last7Days,
+    ThisMonth,
+    LastMonth,
+}
+
+impl Period {
+    pub const ALL: [Period; 4] = [Period::Today, Period::Last7Days, Period::ThisMonth, Period::LastMonth];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Period::Today => "today",
+            Period::Last7Days => "last_7_days",
+            Period::ThisMonth => "this_month",
+            Period::LastMonth => "last_month",
+        }
+    }
+
+    /// Whether `at` falls in the period as of `now`, in UTC
+    pub fn contains(self, at: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+        match self {
+            Period::Today => at.date_naive() == now.date_naive(),
+            Period::Last7Days => at > now - Duration::days(7) && at <= now,
+            Period::ThisMonth => Month::of(now.date_naive()).contains(at),
+            Period::LastMonth => Month::before(now).contains(at),
+        }
+    }
+}
+
+impl FromStr for Period {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Period::ALL
+            .into_iter()
+            .find(|period| period.as_str() == s)
+            .ok_or_else(|| format!("Unknown period '{}', expected today, last_7_days, this_month or last_month", s))
+    }
+}
+
+/// Filters of the task list; unset filters match every task
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchFilter {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub skill: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<String>,
+    /// Case-insensitive text matched against PO number, supplier and task ID
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub q: Option<String>,
+    /// Buyer department of the order, matched without regard to case
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub department: Option<String>,
+    /// When the task was created
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub period: Option<Period>,
+    #[serde(skip_serializing_if = "Labels::is_empty")]
+    pub labels: Labels,
+}
+
+impl SearchFilter {
+    /// This filter with every filter set in `other` replacing its own
+    pub fn overridden_by(mut self, other: SearchFilter) -> Self {
+        let non_empty = |value: Option<String>| value.filter(|s| !s.trim().is_empty());
+        self.state = non_empty(other.state).or(self.state);
+        self.skill = non_empty(other.skill).or(self.skill);
+        self.priority = non_empty(other.priority).or(self.priority);
+        self.q = non_empty(other.q).or(self.q);
+        self.department = non_empty(other.department).or(self.department);
+        self.period = other.period.or(self.period);
+        self.labels.extend(other.labels);
+        self
+    }
+}
+
+/// A named set of task filters
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedSearch {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub filter: SearchFilter,
+    /// Authenticated subject that saved the search
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_by: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Body of `POST /agent/searches`
+#[derive(Debug, Clone, Deserialize)]
+pub struct SaveSearchRequest {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub filter: SearchFilter,
+}
+
+/// Why a search could not be saved or found
+#[derive(Debug)]
+pub enum SearchError {
+    NotFound(String),
+    /// A search of that name exists already
+    Conflict(String),
+    /// The name or a filter is malformed
+    Invalid(String),
+    Store(StoreError),
+}
+
+impl fmt::Display for SearchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SearchError::NotFound(message) | SearchError::Conflict(message) | SearchError::Invalid(message) => {
+                f.write_str(message)
+            }
+            SearchError::Store(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for SearchError {}
+
+impl From<StoreError> for SearchError {
+    fn from(e: StoreError) -> Self {
+        SearchError::Store(e)
+    }
+}
+
+/// Storage of saved searches, keyed by name
+pub trait SavedSearchStore: Send + Sync {
+    /// Insert a search, returning `false` without changing anything when the name is taken
+    fn insert(&self, search: SavedSearch) -> StoreResult<bool>;
+
+    fn get(&self, name: &str) -> StoreResult<Option<SavedSearch>>;
+
+    /// Every saved search, by name
+    fn list(&self) -> StoreResult<Vec<SavedSearch>>;
+
+    fn remove(&self, name: &str) -> StoreResult<Option<SavedSearch>>;
+}
+
+/// In-memory saved searches used by default
+#[derive(Default)]
+pub struct MemorySavedSearchStore {
+    searches: Mutex<BTreeMap<String, SavedSearch>>,
+}
+
+impl MemorySavedSearchStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl SavedSearchStore for MemorySavedSearchStore {
+    fn insert(&self, search: SavedSearch) -> StoreResult<bool> {
+        let mut searches = self.searches.lock().map_err(|_| poisoned())?;
+        if searches.contains_key(&search.name) {
+            return Ok(false);
+        }
+        searches.insert(search.name.clone(), search);
+        Ok(true)
+    }
+
+    fn get(&self, name: &str) -> StoreResult<Option<SavedSearch>> {
+        Ok(self.searches.lock().map_err(|_| poisoned())?.get(name).cloned())
+    }
+
+    fn list(&self) -> StoreResult<Vec<SavedSearch>> {
+        Ok(self.searches.lock().map_err(|_| poisoned())?.values().cloned().collect())
+    }
+
+    fn remove(&self, name: &str) -> StoreResult<Option<SavedSearch>> {
+        Ok(self.searches.lock().map_err(|_| poisoned())?.remove(name))
+    }
+}
+
+/// Saved searches kept in a JSON file, rewritten whole on every change
+pub struct FileSavedSearchStore {
+    path: PathBuf,
+    searches: Mutex<BTreeMap<String, SavedSearch>>,
+}
+
+impl FileSavedSearchStore {
+    /// Load the searches saved in `path`, starting empty when the file does not exist yet
+    pub fn open(path: impl Into<PathBuf>) -> StoreResult<Self> {
+        let path = path.into();
+        let searches = match std::fs::read(&path) {
+            Ok(contents) => serde_json::from_slice::<Vec<SavedSearch>>(&contents)
+                .map_err(|e| StoreError(format!("Invalid saved searches in {}: {}", path.display(), e)))?
+                .into_iter()
+                .map(|search| (search.name.clone(), search))
+                .collect(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(io_error(&path, e)),
+        };
+        Ok(Self { path, searches: Mutex::new(searches) })
+    }
+
+    fn save(&self, searches: &BTreeMap<String, SavedSearch>) -> StoreResult<()> {
+        let contents = serde_json::to_vec_pretty(&searches.values().collect::<Vec<_>>()).map_err(|e| StoreError(e.to_string()))?;
+        let partial = self.path.with_extension("partial");
+        std::fs::write(&partial, contents).map_err(|e| io_error(&partial, e))?;
+        std::fs::rename(&partial, &self.path).map_err(|e| io_error(&self.path, e))
+    }
+}
+
+impl SavedSearchStore for FileSavedSearchStore {
+    fn insert(&self, search: SavedSearch) -> StoreResult<bool> {
+        let mut searches = self.searches.lock().map_err(|_| poisoned())?;
+        if searches.contains_key(&search.name) {
+            return Ok(false);
+        }
+        let name = search.name.clone();
+        searches.insert(name.clone(), search);
+        if let Err(e) = self.save(&searches) {
+            searches.remove(&name);
+            return Err(e);
+        }
+        Ok(true)
+    }
+
+    fn get(&self, name: &str) -> StoreResult<Option<SavedSearch>> {
+        Ok(self.searches.lock().map_err(|_| poisoned())?.get(name).cloned())
+    }
+
+    fn list(&self) -> StoreResult<Vec<SavedSearch>> {
+        Ok(self.searches.lock().map_err(|_| poisoned())?.values().cloned().collect())
+    }
+
+    fn remove(&self, name: &str) -> StoreResult<Option<SavedSearch>> {
+        let mut searches = self.searches.lock().map_err(|_| poisoned())?;
+        let Some(removed) = searches.remove(name) else {
+            return Ok(None);
+        };
+        if let Err(e) = self.save(&searches) {
+            searches.insert(removed.name.clone(), removed);
+            return Err(e);
+        }
+        Ok(Some(removed))
+    }
+}
+
+fn poisoned() -> StoreError {
+    StoreError("saved search store poisoned".to_string())
+}
+
+fn io_error(path: &Path, e: std::io::Error) -> StoreError {
+    StoreError(format!("{}: {}", path.display(), e))
+}
+
+/// The store `config` asks for
+pub fn store(config: &SearchesConfig) -> StoreResult<Arc<dyn SavedSearchStore>> {
+    Ok(match &config.path {
+        Some(path) => Arc::new(FileSavedSearchStore::open(path)?),
+        None => Arc::new(MemorySavedSearchStore::new()),
+    })
+}
+
+/// Save `request` as a new search
+///
+/// Names are 1 to 64 letters, digits, `-` or `_`, so they can be used in URLs as they are.
+pub fn save(store: &dyn SavedSearchStore, request: SaveSearchRequest, created_by: Option<String>) -> Result<SavedSearch, SearchError> {
+    let name = request.name.trim();
+    if name.is_empty() || name.len() > MAX_NAME_LEN || !name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_')) {
+        return Err(SearchError::Invalid(format!(
+            "Search name '{}' must be 1 to {} letters, digits, '-' or '_'",
+            request.name, MAX_NAME_LEN
+        )));
+    }
+    labels::validate(&request.filter.labels).map_err(SearchError::Invalid)?;
+    let search = SavedSearch {
+        name: name.to_string(),
+        description: request.description,
+        filter: request.filter,
+        created_by,
+        created_at: Utc::now(),
+    };
+    if !store.insert(search.clone())? {
+        return Err(SearchError::Conflict(format!("A search named {} exists already", search.name)));
+    }
+    Ok(search)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_periods_are_relative_to_now() {
+        let now = Utc.with_ymd_and_hms(2026, 3, 2, 12, 0, 0).unwrap();
+        let day = |d: u32, m: u32| Utc.with_ymd_and_hms(2026, m, d, 9, 0, 0).unwrap();
+        assert!(Period::Today.contains(day(2, 3), now));
+        assert!(Period::Last7Days.contains(day(25, 2), now) && !Period::Last7Days.contains(day(20, 2), now));
+        assert!(Period::ThisMonth.contains(day(1, 3), now) && !Period::ThisMonth.contains(day(28, 2), now));
+        assert!(Period::LastMonth.contains(day(28, 2), now));
+        assert_eq!("last_month".parse::<Period>(), Ok(Period::LastMonth));
+
+        let saved = SearchFilter { state: Some("failed".to_string()), department: Some("Marketing".to_string()), ..SearchFilter::default() };
+        let explicit = SearchFilter { state: Some("completed".to_string()), department: Some(String::new()), ..SearchFilter::default() };
+        let merged = saved.overridden_by(explicit);
+        assert_eq!(merged.state.as_deref(), Some("completed"));
+        assert_eq!(merged.department.as_deref(), Some("Marketing"));
+    }
+
+    #[test]
+    fn test_file_store_keeps_searches_across_restarts() {
+        let path = std::env::temp_dir().join(format!("saved-searches-{}.json", uuid::Uuid::new_v4()));
+        let store = FileSavedSearchStore::open(&path).unwrap();
+        let request = |name: &str| SaveSearchRequest {
+            name: name.to_string(),
+            description: None,
+            filter: SearchFilter { state: Some("failed".to_string()), period: Some(Period::ThisMonth), ..SearchFilter::default() },
+        };
+        save(&store, request("marketing-failed"), Some("sam".to_string())).unwrap();
+        assert!(matches!(save(&store, request("marketing-failed"), None), Err(SearchError::Conflict(_))));
+        assert!(matches!(save(&store, request("no spaces"), None), Err(SearchError::Invalid(_))));
+
+        let reopened = FileSavedSearchStore::open(&path).unwrap();
+        let search = reopened.get("marketing-failed").unwrap().unwrap();
+        assert_eq!(search.filter.period, Some(Period::ThisMonth));
+        assert_eq!(search.created_by.as_deref(), Some("sam"));
+        reopened.remove("marketing-failed").unwrap();
+        assert!(FileSavedSearchStore::open(&path).unwrap().list().unwrap().is_empty());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
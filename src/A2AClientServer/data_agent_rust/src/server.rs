@@ -1,33 +1,156 @@
-use crate::agent::PurchaseOrderAgent;
-use a2a::{A2AProtocol, Message, Part};
+use crate::agent::{ProcessingResult, PurchaseOrderAgent, TaskOptions};
+use a2a::{A2AProtocol, Artifact, Message, Part, Task};
 use axum::{
-    extract::State,
-    http::StatusCode,
-    response::Json,
-    routing::{get, post},
+    body::Bytes,
+    extract::{DefaultBodyLimit, Extension, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    middleware,
+    response::{IntoResponse, Json, Response},
+    routing::{delete, get, patch, post},
     Router,
 };
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use tower_http::cors::CorsLayer;
-use tracing::{info, error};
+use tracing::{debug, error, info, warn};
 
-use crate::a2a_agent_card::A2AAgentCard;
+use crate::a2a_agent_card::{A2AAgentCard, SecurityScheme};
+use crate::admin::admin_routes;
+use crate::amendments::{self, AmendmentError, AmendmentRequest, Revision, TaskDiff};
+use crate::approval::{self, ApprovalAssignment, ApprovalError, ApprovalRequirement, ApprovalVote, DecisionRequest, DelegationRequest};
+use crate::attachments::{self, AttachRequest, Attachment, AttachmentError, NewAttachment};
+use crate::auth::{require_auth, AuthContext, JwtValidator};
+use crate::batch_validation::{self, BatchReport, BatchRequest};
+use crate::blanket::BlanketReport;
+use crate::blob::{self, PresignedUrl};
+use crate::breaker::BreakerState;
+use crate::config::ServerConfig;
+use crate::dashboard::dashboard_routes;
+use crate::events::TaskEvent;
+use crate::graphql::graphql_routes;
+use crate::idempotency::{self, Claim, IdempotencyKeys, IDEMPOTENCY_KEY_HEADER};
+use crate::masking::{require_unmasked, MaskingConfig};
+use crate::readiness::{self, Dependency, ReadinessStatus};
+use crate::scheduled_exports::ExportsConfig;
+use crate::monthly_close::{self, Month, MonthlyCloseConfig, ReportFormat};
+use crate::price_history::{self, PriceHistory};
+use crate::rbac::{authorize, Permission, RbacConfig};
+use crate::receiving::{self, OpenOrderStatus, ReceiptError, ReceiptRequest};
+use crate::resubmit::{self, RetryError, RetryRequest};
+use crate::searches::{self, SaveSearchRequest, SavedSearch, SearchError};
+use crate::session::{self, SessionContext};
+use crate::result_schema;
+use crate::observability::with_request_tracing;
+use crate::processing::Priority;
+use crate::intake::message_from_payload;
+use crate::labels::{self, LabelError, Labels};
+use crate::signing::{require_signature, SeenSignatures, SigningConfig};
+use crate::skills::{ValidationReport, VALIDATION_SKILL};
+use crate::tool_schema::tool_routes;
+use crate::websocket::websocket_routes;
+use crate::snapshot::SnapshotConfig;
+use crate::status::{ServerStatus, Status};
+use crate::webhooks::{WebhookDelivery, WebhooksConfig};
+use crate::request_options::RequestOptions;
 
 /// HTTP request structure for sending tasks
 #[derive(Debug, Deserialize)]
 pub struct SendTaskRequest {
+    #[serde(deserialize_with = "crate::parts::deserialize_message")]
     pub message: Message,
+    /// Skill to run, overriding any `skill_id` in the message
+    #[serde(default)]
+    pub skill_id: Option<String>,
+    /// Lane the task waits in with background processing: `low`, `normal` or `urgent`
+    #[serde(default)]
+    pub priority: Priority,
+    /// Hold the task as scheduled until this time, e.g. when a budget period opens
+    #[serde(default)]
+    pub process_after: Option<DateTime<Utc>>,
+    /// Session whose messages carry the chunks of one purchase order, see `moreItems`
+    #[serde(default)]
+    pub session_id: Option<String>,
+    /// Return the result inline without storing a task, notifying anyone or drawing on blanket orders
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Labels to mark the task with, e.g. `{"source": "sap", "period": "2026-Q3"}`
+    #[serde(default)]
+    pub labels: Labels,
+    /// Versioned handling options, taking precedence over `priority`, `dry_run` and the order's own flags
+    #[serde(default)]
+    pub options: RequestOptions,
+}
+
+impl SendTaskRequest {
+    /// Whether the submission is a dry run, by its options or else the top-level field
+    pub fn is_dry_run(&self) -> bool {
+        self.options.dry_run.unwrap_or(self.dry_run)
+    }
 }
 
 /// HTTP response structure for task operations
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct TaskResponse {
     pub task_id: String,
     pub status: String,
     pub csv_output: Option<String>,
     pub detailed_result: Option<serde_json::Value>,
     pub error: Option<String>,
+    /// Files produced by the task; each can also be fetched by index
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub artifacts: Option<Vec<Artifact>>,
+    /// Supporting documents attached to the task
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<Attachment>,
+    /// Whether this is the result of a dry run, for which no task was stored
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub dry_run: bool,
+}
+
+impl TaskResponse {
+    /// Build a response from a task, extracting the CSV output (Text part) and
+    /// detailed result (Data part) from its status message
+    pub fn from_task(task: Task) -> Self {
+        let mut csv_output = None;
+        let mut detailed_result = None;
+
+        if let Some(ref message) = task.status.message {
+            for part in &message.parts {
+                match part {
+                    Part::Text { text } => {
+                        csv_output = Some(text.clone());
+                    }
+                    Part::Data { data } => {
+                        detailed_result = Some(data.clone());
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let status_str = match task.status.state {
+            a2a::TaskState::Completed => "completed",
+            a2a::TaskState::Failed => "failed",
+            a2a::TaskState::Submitted => "submitted",
+            a2a::TaskState::Working => "working",
+            a2a::TaskState::InputRequired => "input_required",
+            a2a::TaskState::Canceled => "cancelled",
+        };
+
+        TaskResponse {
+            task_id: task.id,
+            status: status_str.to_string(),
+            csv_output,
+            detailed_result,
+            error: None,
+            artifacts: task.artifacts,
+            attachments: Vec::new(),
+            dry_run: false,
+        }
+    }
 }
 
 /// HTTP response for agent information
@@ -50,42 +173,252 @@ pub struct EndpointInfo {
 /// Shared application state
 pub struct AppState {
     pub agent: Arc<PurchaseOrderAgent>,
+    /// Agent card as served to clients, reflecting the server configuration
+    pub agent_card: A2AAgentCard,
+    /// Strong ETag of the serialized agent card
+    pub agent_card_etag: String,
+    /// `Cache-Control` value sent with the agent card
+    pub agent_card_cache_control: String,
+    /// Bearer token validator, present when authentication is configured
+    pub jwt: Option<JwtValidator>,
+    /// Role → permission mapping enforced on authenticated routes
+    pub rbac: RbacConfig,
+    /// HMAC signature verification for task submissions
+    pub signing: SigningConfig,
+    /// Submission signatures already accepted, refused if replayed
+    pub signatures: SeenSignatures,
+    /// Largest body the submission endpoints accept
+    pub max_request_bytes: usize,
+    /// Where admin-triggered snapshots are saved
+    pub snapshot: SnapshotConfig,
+    /// Suppliers listed in monthly close reports
+    pub monthly_close: MonthlyCloseConfig,
+    /// Idempotency keys of recent task submissions
+    pub idempotency: IdempotencyKeys,
+    /// How long previous webhook signing keys keep signing after a rotation
+    pub rotation_overlap: std::time::Duration,
+    /// Webhook settings, against which submitted callback URLs are checked
+    pub webhooks: WebhooksConfig,
+    /// Sensitive fields masked in responses to low-privilege callers
+    pub masking: MaskingConfig,
+    /// Export jobs administrators can inspect and run
+    pub exports: ExportsConfig,
+    /// Downstreams reported on `/ready`
+    pub dependencies: Vec<Dependency>,
+    /// Build and configuration reported on `/status`
+    pub status: ServerStatus,
 }
 
-/// Create the web server router
+impl AppState {
+    pub fn new(agent: Arc<PurchaseOrderAgent>, config: &ServerConfig) -> Self {
+        let agent_card = runtime_agent_card(&agent, config);
+        let jwt = config.auth.is_enabled().then(|| JwtValidator::new(config.auth.clone()));
+
+        let agent_card_etag = card_etag(&agent_card);
+        let agent_card_cache_control = format!("public, max-age={}", config.server.agent_card_max_age_seconds);
+
+        Self {
+            agent,
+            agent_card,
+            agent_card_etag,
+            agent_card_cache_control,
+            jwt,
+            rbac: config.rbac.clone(),
+            signing: config.signing.clone(),
+            signatures: SeenSignatures::default(),
+            max_request_bytes: config.server.max_request_bytes,
+            snapshot: config.snapshot.clone(),
+            monthly_close: config.monthly_close.clone(),
+            idempotency: IdempotencyKeys::new(config.server.idempotency_window),
+            rotation_overlap: config.webhooks.rotation_overlap,
+            webhooks: config.webhooks.clone(),
+            masking: config.masking.clone(),
+            exports: config.exports.clone(),
+            dependencies: readiness::configured(config),
+            status: ServerStatus::new(config),
+        }
+    }
+
+    /// Whether responses to `caller` are masked
+    pub fn masks(&self, caller: Option<&Extension<AuthContext>>) -> bool {
+        self.masking.applies(&self.rbac, caller.map(|Extension(context)| context))
+    }
+
+    /// `task` as a response to `caller`, masked if the caller may not see full data
+    pub fn task_response(&self, caller: Option<&Extension<AuthContext>>, task: Task) -> TaskResponse {
+        TaskResponse::from_task(if self.masks(caller) { self.masking.task(task) } else { task })
+    }
+}
+
+/// The agent's card as this server actually runs it
+///
+/// The URL, capabilities, security schemes and skills come from the configuration
+/// and the agent's registered skills rather than the card's built-in defaults, so
+/// clients are never told about features the server does not provide.
+fn runtime_agent_card(agent: &PurchaseOrderAgent, config: &ServerConfig) -> A2AAgentCard {
+    let mut card = agent.get_a2a_agent_card().clone();
+    card.url = config.server.advertised_url();
+    card.documentation_url = config.server.documentation_url.clone();
+
+    // No Server-Sent Events endpoint is served; webhooks are this agent's push notifications
+    card.capabilities.streaming = Some(false);
+    card.capabilities.push_notifications = Some(config.webhooks.is_enabled());
+    card.capabilities.state_transition_history = Some(true);
+
+    let registered = agent.skills().ids();
+    card.skills.retain(|skill| registered.contains(&skill.id.as_str()));
+
+    if config.auth.is_enabled() {
+        card.require_security_scheme(
+            "bearer",
+            SecurityScheme::Http { scheme: "bearer".to_string(), bearer_format: Some("JWT".to_string()), description: None },
+        );
+    }
+    if let Some(tls) = &config.server.tls
+        && tls.client_ca_path.is_some()
+        && tls.require_client_cert
+    {
+        card.require_security_scheme("mtls", SecurityScheme::MutualTls { description: None });
+    }
+    card
+}
+
+/// Create the web server router with the default configuration
 pub fn create_router(agent: Arc<PurchaseOrderAgent>) -> Router {
-    let state = Arc::new(AppState { agent });
+    create_router_with_config(agent, &ServerConfig::default())
+}
+
+/// Create the web server router for the given configuration
+pub fn create_router_with_config(agent: Arc<PurchaseOrderAgent>, config: &ServerConfig) -> Router {
+    let state = Arc::new(AppState::new(agent, config));
+
+    let submit = Router::new()
+        .route(
+            "/agent/task",
+            post(send_task).route_layer(middleware::from_fn_with_state(state.clone(), require_signature)),
+        )
+        .route("/agent/task/:task_id/cancel", post(cancel_task))
+        .route("/agent/task/:task_id/receipts", post(record_goods_receipt))
+        .route("/agent/task/:task_id/retry", post(retry_task))
+        .route("/agent/task/:task_id/attachments", post(attach_documents))
+        .route("/agent/task/:task_id/labels", patch(update_task_labels))
+        .route("/agent/searches", post(save_search))
+        .route("/agent/searches/:name", delete(delete_saved_search))
+        .route("/agent/orders/:po_number/amendments", post(amend_purchase_order))
+        .route("/agent/validate", post(validate_purchase_order))
+        .route("/agent/validate/batch", post(validate_batch))
+        .route(&format!("/agent/skills/{}", VALIDATION_SKILL), post(validate_purchase_order))
+        .route_layer(middleware::from_fn_with_state((state.clone(), Permission::SubmitTasks), authorize))
+        // Orders with tens of thousands of lines exceed axum's 2 MiB default
+        .layer(DefaultBodyLimit::max(config.server.max_request_bytes));
+
+    // Masking cannot rewrite what these return, so masked callers are kept out
+    let unmasked = dashboard_routes()
+        .merge(websocket_routes())
+        .merge(graphql_routes(state.agent.clone()))
+        .route("/agent/task/:task_id/idoc", get(get_task_idoc))
+        .route_layer(middleware::from_fn_with_state(state.clone(), require_unmasked));
+
+    let read = unmasked
+        .route("/agent/task/:task_id", get(get_task))
+        .route("/agent/task/:task_id/events", get(get_task_events))
+        .route("/agent/task/:task_id/artifacts/:index", get(get_task_artifact))
+        .route("/agent/task/:task_id/deliveries", get(get_task_deliveries))
+        .route("/agent/task/:task_id/receipts", get(get_open_order_status))
+        .route("/agent/task/:task_id/attachments", get(get_task_attachments))
+        .route("/agent/task/:task_id/attachments/:attachment_id", get(get_task_attachment))
+        .route("/agent/task/:task_id/labels", get(get_task_labels))
+        .route("/agent/task/:task_id/approval", get(get_task_approval))
+        .route("/agent/task/:task_id/diff/:other_task_id", get(get_task_diff))
+        .route("/agent/orders/:po_number/revisions", get(get_revisions))
+        .route("/agent/sessions/:session_id", get(get_session))
+        .route("/agent/searches", get(list_saved_searches))
+        .route("/agent/searches/:name", get(get_saved_search))
+        .route("/agent/blankets/:po_number", get(get_blanket_order))
+        .route_layer(middleware::from_fn_with_state((state.clone(), Permission::ReadTasks), authorize));
 
-    Router::new()
+    let approve = Router::new()
+        .route("/agent/task/:task_id/approval", post(decide_approval))
+        .route("/agent/task/:task_id/approval/delegate", post(delegate_approval))
+        .route_layer(middleware::from_fn_with_state((state.clone(), Permission::ApproveTasks), authorize));
+
+    let admin = admin_routes(&config.snapshot)
+        .route("/analytics/prices/:item_code", get(get_price_history))
+        .route("/reports/monthly-close", get(get_monthly_close))
+        .route_layer(middleware::from_fn_with_state((state.clone(), Permission::Admin), authorize));
+
+    // Task and admin endpoints require a bearer token once authentication is configured;
+    // require_auth is layered last so it runs before the permission checks
+    let protected = submit
+        .merge(read)
+        .merge(approve)
+        .merge(admin)
+        .route_layer(middleware::from_fn_with_state(state.clone(), require_auth));
+
+    let router = Router::new()
         .route("/", get(get_agent_info))
         .route("/.well-known/agent.json", get(get_a2a_agent_card))
         .route("/agent.json", get(get_a2a_agent_card)) // Alternative path some A2A clients expect
         .route("/a2a/agent.json", get(get_a2a_agent_card)) // Another common alternative
         .route("/agent/card", get(get_a2a_agent_card)) // RESTful alternative
+        .merge(tool_routes())
         .route("/agent/info", get(get_agent_info))
-        .route("/agent/task", post(send_task))
-        .route("/agent/task/:task_id", get(get_task))
-        .route("/agent/task/:task_id/cancel", post(cancel_task))
         .route("/health", get(health_check))
+        .route("/ready", get(readiness_check))
+        .route("/status", get(server_status))
+        .route("/metrics", get(metrics))
+        .merge(protected)
         .fallback(catch_all) // Add catch-all for debugging
         .layer(CorsLayer::permissive())
-        .with_state(state)
+        .with_state(state);
+
+    with_request_tracing(router)
+}
+
+/// Compute a strong ETag over the serialized agent card
+fn card_etag(card: &A2AAgentCard) -> String {
+    let body = serde_json::to_vec(card).unwrap_or_default();
+    let digest = Sha256::digest(&body);
+    format!("\"{}\"", hex::encode(&digest[..16]))
+}
+
+/// Whether an `If-None-Match` header matches the current ETag
+fn etag_matches(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|candidate| candidate.trim())
+        .any(|candidate| candidate == "*" || candidate.trim_start_matches("W/") == etag)
 }
 
 /// Get A2A compliant agent card (standard endpoint)
-async fn get_a2a_agent_card(State(state): State<Arc<AppState>>) -> Json<A2AAgentCard> {
-    info!("🔍 Agent card requested");
-    Json(state.agent.get_a2a_agent_card().clone())
+///
+/// Discovery clients poll this constantly, so the response carries an ETag and
+/// `Cache-Control`; a matching `If-None-Match` gets an empty `304 Not Modified`.
+async fn get_a2a_agent_card(State(state): State<Arc<AppState>>, headers: HeaderMap) -> Response {
+    let cache_headers = [
+        (header::ETAG, state.agent_card_etag.clone()),
+        (header::CACHE_CONTROL, state.agent_card_cache_control.clone()),
+    ];
+
+    if etag_matches(&headers, &state.agent_card_etag) {
+        debug!("agent card not modified");
+        return (StatusCode::NOT_MODIFIED, cache_headers).into_response();
+    }
+
+    debug!("agent card requested");
+    (cache_headers, Json(state.agent_card.clone())).into_response()
 }
 
 /// Catch-all handler to log what requests are being made
 async fn catch_all(uri: axum::http::Uri) -> Result<Json<serde_json::Value>, StatusCode> {
-    error!("❌ 404 - Path not found: {}", uri.path());
-    info!("💡 Available agent card endpoints:");
-    info!("   GET /.well-known/agent.json");
-    info!("   GET /agent.json");
-    info!("   GET /a2a/agent.json");
-    info!("   GET /agent/card");
+    warn!(
+        path = %uri.path(),
+        agent_card_endpoints = "/.well-known/agent.json, /agent.json, /a2a/agent.json, /agent/card",
+        "no route for path"
+    );
     
     Err(StatusCode::NOT_FOUND)
 }
@@ -120,6 +453,26 @@ async fn get_agent_info(State(state): State<Arc<AppState>>) -> Json<AgentInfoRes
             method: "GET".to_string(),
             description: "Get A2A compliant agent card (RESTful endpoint)".to_string(),
         },
+        EndpointInfo {
+            path: "/dashboard".to_string(),
+            method: "GET".to_string(),
+            description: "HTML dashboard of recent tasks and their validation findings".to_string(),
+        },
+        EndpointInfo {
+            path: "/ws".to_string(),
+            method: "GET".to_string(),
+            description: "WebSocket stream of task lifecycle events".to_string(),
+        },
+        EndpointInfo {
+            path: "/graphql".to_string(),
+            method: "POST".to_string(),
+            description: "GraphQL queries over tasks, results and aggregate stats".to_string(),
+        },
+        EndpointInfo {
+            path: "/agent/tools".to_string(),
+            method: "GET".to_string(),
+            description: "Skills as OpenAI function-calling tool definitions".to_string(),
+        },
         EndpointInfo {
             path: "/agent/info".to_string(),
             method: "GET".to_string(),
@@ -130,21 +483,226 @@ async fn get_agent_info(State(state): State<Arc<AppState>>) -> Json<AgentInfoRes
             method: "POST".to_string(),
             description: "Send a purchase order for processing".to_string(),
         },
+        EndpointInfo {
+            path: "/agent/validate".to_string(),
+            method: "POST".to_string(),
+            description: "Validate a purchase order without creating a task".to_string(),
+        },
+        EndpointInfo {
+            path: "/agent/validate/batch".to_string(),
+            method: "POST".to_string(),
+            description: "Validate many purchase orders and report findings by check, without creating tasks".to_string(),
+        },
+        EndpointInfo {
+            path: format!("/agent/skills/{}", VALIDATION_SKILL),
+            method: "POST".to_string(),
+            description: "Validate a purchase order without creating a task (skill route)".to_string(),
+        },
         EndpointInfo {
             path: "/agent/task/{task_id}".to_string(),
             method: "GET".to_string(),
             description: "Get the status and result of a specific task".to_string(),
         },
+        EndpointInfo {
+            path: "/agent/task/{task_id}/events".to_string(),
+            method: "GET".to_string(),
+            description: "Get the event history of a specific task".to_string(),
+        },
+        EndpointInfo {
+            path: "/agent/task/{task_id}/deliveries".to_string(),
+            method: "GET".to_string(),
+            description: "Get the webhook delivery status of a specific task".to_string(),
+        },
+        EndpointInfo {
+            path: "/agent/task/{task_id}/receipts".to_string(),
+            method: "GET".to_string(),
+            description: "Get the goods received and quantities still open on a processed order".to_string(),
+        },
+        EndpointInfo {
+            path: "/agent/task/{task_id}/receipts".to_string(),
+            method: "POST".to_string(),
+            description: "Record a partial or full goods receipt against a processed order".to_string(),
+        },
+        EndpointInfo {
+            path: "/agent/task/{task_id}/retry".to_string(),
+            method: "POST".to_string(),
+            description: "Process a failed task again, with optional corrections, as a new linked task".to_string(),
+        },
+        EndpointInfo {
+            path: "/agent/task/{task_id}/attachments".to_string(),
+            method: "POST".to_string(),
+            description: "Attach supporting documents, as File parts or a raw upload".to_string(),
+        },
+        EndpointInfo {
+            path: "/agent/task/{task_id}/attachments".to_string(),
+            method: "GET".to_string(),
+            description: "List the documents attached to a task".to_string(),
+        },
+        EndpointInfo {
+            path: "/agent/task/{task_id}/attachments/{attachment_id}".to_string(),
+            method: "GET".to_string(),
+            description: "Download an attached document".to_string(),
+        },
+        EndpointInfo {
+            path: "/agent/task/{task_id}/labels".to_string(),
+            method: "GET".to_string(),
+            description: "Get the labels a task is marked with".to_string(),
+        },
+        EndpointInfo {
+            path: "/agent/task/{task_id}/labels".to_string(),
+            method: "PATCH".to_string(),
+            description: "Set or remove task labels; a label set to null is removed".to_string(),
+        },
+        EndpointInfo {
+            path: "/agent/searches".to_string(),
+            method: "GET".to_string(),
+            description: "List the saved searches of the task list".to_string(),
+        },
+        EndpointInfo {
+            path: "/agent/searches".to_string(),
+            method: "POST".to_string(),
+            description: "Save a named set of task list filters, used as /dashboard?view={name}".to_string(),
+        },
+        EndpointInfo {
+            path: "/agent/searches/{name}".to_string(),
+            method: "GET".to_string(),
+            description: "Get a saved search".to_string(),
+        },
+        EndpointInfo {
+            path: "/agent/searches/{name}".to_string(),
+            method: "DELETE".to_string(),
+            description: "Delete a saved search".to_string(),
+        },
+        EndpointInfo {
+            path: "/agent/task/{task_id}/approval".to_string(),
+            method: "GET".to_string(),
+            description: "Get the approver an order pending approval is assigned to".to_string(),
+        },
+        EndpointInfo {
+            path: "/agent/task/{task_id}/approval".to_string(),
+            method: "POST".to_string(),
+            description: "Approve or reject an order pending approval".to_string(),
+        },
+        EndpointInfo {
+            path: "/agent/task/{task_id}/approval/delegate".to_string(),
+            method: "POST".to_string(),
+            description: "Delegate an approval to another user".to_string(),
+        },
+        EndpointInfo {
+            path: "/agent/orders/{po_number}/amendments".to_string(),
+            method: "POST".to_string(),
+            description: "Amend a processed purchase order, creating its next revision".to_string(),
+        },
+        EndpointInfo {
+            path: "/agent/orders/{po_number}/revisions".to_string(),
+            method: "GET".to_string(),
+            description: "Get every revision of a purchase order with the changes between them".to_string(),
+        },
+        EndpointInfo {
+            path: "/agent/sessions/{session_id}".to_string(),
+            method: "GET".to_string(),
+            description: "List the tasks of a session, whose follow-ups resolve purchase order deltas against them".to_string(),
+        },
+        EndpointInfo {
+            path: "/agent/task/{task_id}/diff/{other_task_id}".to_string(),
+            method: "GET".to_string(),
+            description: "Compare the purchase orders two tasks processed, field by field".to_string(),
+        },
+        EndpointInfo {
+            path: "/agent/task/{task_id}/artifacts/{index}".to_string(),
+            method: "GET".to_string(),
+            description: "Get one of a task's artifacts, such as its CSV or JSON result".to_string(),
+        },
+        EndpointInfo {
+            path: "/agent/task/{task_id}/idoc".to_string(),
+            method: "GET".to_string(),
+            description: "Get the ORDERS05 IDoc flat file of an approved purchase order".to_string(),
+        },
+        EndpointInfo {
+            path: "/agent/blankets/{po_number}".to_string(),
+            method: "GET".to_string(),
+            description: "Get a blanket order's limit, validity, releases and remaining amount".to_string(),
+        },
+        EndpointInfo {
+            path: "/analytics/prices/{item_code}".to_string(),
+            method: "GET".to_string(),
+            description: "Get the unit prices processed orders paid for an item, with their trailing average".to_string(),
+        },
+        EndpointInfo {
+            path: "/reports/monthly-close".to_string(),
+            method: "GET".to_string(),
+            description: "Get a month's spend by department and supplier, unapproved orders and validation failure rate".to_string(),
+        },
         EndpointInfo {
             path: "/agent/task/{task_id}/cancel".to_string(),
             method: "POST".to_string(),
             description: "Cancel a specific task".to_string(),
         },
+        EndpointInfo {
+            path: "/admin/stats".to_string(),
+            method: "GET".to_string(),
+            description: "Task store statistics (admin)".to_string(),
+        },
+        EndpointInfo {
+            path: "/admin/tasks/purge?older_than_days={n}".to_string(),
+            method: "POST".to_string(),
+            description: "Remove tasks last updated more than N days ago (admin)".to_string(),
+        },
+        EndpointInfo {
+            path: "/admin/tasks/{task_id}/fail".to_string(),
+            method: "POST".to_string(),
+            description: "Force a stuck task into the failed state (admin)".to_string(),
+        },
+        EndpointInfo {
+            path: "/admin/tasks/{task_id}/rerun".to_string(),
+            method: "POST".to_string(),
+            description: "Re-run a failed task from its original request (admin)".to_string(),
+        },
+        EndpointInfo {
+            path: "/admin/dead-letters".to_string(),
+            method: "GET".to_string(),
+            description: "List messages no purchase order could be parsed from, with the parse errors (admin)".to_string(),
+        },
+        EndpointInfo {
+            path: "/admin/dead-letters/{id}/replay".to_string(),
+            method: "POST".to_string(),
+            description: "Process a dead letter again, as kept or with a corrected message (admin)".to_string(),
+        },
+        EndpointInfo {
+            path: "/admin/exports".to_string(),
+            method: "GET".to_string(),
+            description: "List the scheduled export jobs with their next and last runs (admin)".to_string(),
+        },
+        EndpointInfo {
+            path: "/admin/exports/runs".to_string(),
+            method: "GET".to_string(),
+            description: "List recent runs of scheduled exports, newest first (admin)".to_string(),
+        },
+        EndpointInfo {
+            path: "/admin/exports/{id}/run".to_string(),
+            method: "POST".to_string(),
+            description: "Run a scheduled export now for the period before now (admin)".to_string(),
+        },
         EndpointInfo {
             path: "/health".to_string(),
             method: "GET".to_string(),
             description: "Health check endpoint".to_string(),
         },
+        EndpointInfo {
+            path: "/ready".to_string(),
+            method: "GET".to_string(),
+            description: "Readiness, with the status and last error of each configured downstream".to_string(),
+        },
+        EndpointInfo {
+            path: "/status".to_string(),
+            method: "GET".to_string(),
+            description: "Build version, git commit, uptime, features and configuration summary".to_string(),
+        },
+        EndpointInfo {
+            path: "/metrics".to_string(),
+            method: "GET".to_string(),
+            description: "Prometheus metrics".to_string(),
+        },
     ];
 
     Json(AgentInfoResponse {
@@ -157,118 +715,859 @@ async fn get_agent_info(State(state): State<Arc<AppState>>) -> Json<AgentInfoRes
 }
 
 /// Send a task to the agent
+///
+/// With background processing enabled the task is queued and answered with
+/// `202 Accepted` while still submitted, or rejected with `503` and
+/// `Retry-After` when the queue is full.
+///
+/// A repeated `Idempotency-Key` from the same caller gets the task the key
+/// created instead of a new one, or `409` while the first request with the key is
+/// still processed. Reusing a key for a different body is answered with `422`.
 async fn send_task(
     State(state): State<Arc<AppState>>,
-    Json(request): Json<SendTaskRequest>,
-) -> Result<Json<TaskResponse>, StatusCode> {
-    info!("Received task request from role: {}", request.message.role);
+    auth: Option<Extension<AuthContext>>,
+    headers: HeaderMap,
+    Json(body): Json<serde_json::Value>,
+) -> Response {
+    let fingerprint = idempotency::fingerprint(&body);
+    let request: SendTaskRequest = match serde_json::from_value(body) {
+        Ok(request) => request,
+        Err(e) => {
+            let error = serde_json::json!({ "error": format!("Failed to deserialize the JSON body into the target type: {}", e) });
+            return (StatusCode::UNPROCESSABLE_ENTITY, Json(error)).into_response();
+        }
+    };
+    info!(role = %request.message.role, parts = request.message.parts.len(), skill = ?request.skill_id, "task submitted");
 
-    match state.agent.send_task(request.message).await {
-        Ok(task) => {
-            let mut csv_output = None;
-            let mut detailed_result = None;
-
-            // Extract CSV output and detailed result from the task response
-            if let Some(ref message) = task.status.message {
-                for part in &message.parts {
-                    match part {
-                        Part::Text { text } => {
-                            csv_output = Some(text.clone());
-                        }
-                        Part::Data { data } => {
-                            detailed_result = Some(data.clone());
-                        }
-                        _ => {}
-                    }
-                }
+    // A dry run creates no task for a repeated submission to return
+    let key = headers
+        .get(IDEMPOTENCY_KEY_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|_| !request.is_dry_run());
+    let subject = auth.as_ref().map(|Extension(auth)| auth.subject.as_str()).unwrap_or_default();
+    let mut claimed = None;
+    if let Some(key) = key {
+        match state.idempotency.claim(subject, key, &fingerprint) {
+            Claim::New(guard) => claimed = Some(guard),
+            Claim::InProgress => {
+                let error = serde_json::json!({ "error": "A submission with this idempotency key is still being processed" });
+                return (StatusCode::CONFLICT, Json(error)).into_response();
             }
+            Claim::Mismatch => {
+                let error = serde_json::json!({ "error": "This idempotency key was used for a different request" });
+                return (StatusCode::UNPROCESSABLE_ENTITY, Json(error)).into_response();
+            }
+            Claim::Done(task_id) => {
+                debug!(task_id = %task_id, "repeated submission, returning the task it created");
+                return match state.agent.get_task(&task_id).await {
+                    Ok(task) => Json(state.task_response(auth.as_ref(), task)).into_response(),
+                    Err(e) => (StatusCode::NOT_FOUND, Json(serde_json::json!({ "error": e.to_string() }))).into_response(),
+                };
+            }
+        }
+    }
 
-            let status_str = match task.status.state {
-                a2a::TaskState::Completed => "completed",
-                a2a::TaskState::Failed => "failed",
-                a2a::TaskState::Submitted => "submitted",
-                a2a::TaskState::Working => "working",
-                a2a::TaskState::InputRequired => "input_required",
-                a2a::TaskState::Canceled => "cancelled",
-            };
+    // A claim whose submission creates no task, or is dropped with the request, frees the key
+    let request_dry_run = request.is_dry_run();
+    let outcome = submit_task(&state, request).await;
+    if let (Some(guard), Ok(task)) = (claimed, &outcome) {
+        guard.complete(&task.id);
+    }
 
-            Ok(Json(TaskResponse {
-                task_id: task.id,
-                status: status_str.to_string(),
-                csv_output,
-                detailed_result,
-                error: None,
-            }))
+    match outcome {
+        Ok(task) => {
+            let status = if matches!(task.status.state, a2a::TaskState::Submitted) { StatusCode::ACCEPTED } else { StatusCode::OK };
+            let mut response = state.task_response(auth.as_ref(), task);
+            response.dry_run = request_dry_run;
+            info!(task_id = %response.task_id, status = %response.status, "task processed");
+            (status, Json(response)).into_response()
         }
-        Err(e) => {
-            error!("Failed to process task: {}", e);
-            Ok(Json(TaskResponse {
-                task_id: "".to_string(),
-                status: "error".to_string(),
-                csv_output: None,
-                detailed_result: None,
-                error: Some(e.to_string()),
-            }))
+        Err(response) => response,
+    }
+}
+
+/// Process or queue a submission; `Err` is the response for a submission that created no task
+async fn submit_task(state: &AppState, request: SendTaskRequest) -> Result<Task, Response> {
+    let dry_run = request.is_dry_run();
+    let SendTaskRequest { mut message, options: requested, .. } = request;
+    let checked = labels::validate(&request.labels)
+        .and_then(|_| requested.validate())
+        .and_then(|_| match &requested.callback_url {
+            Some(_) if dry_run => Err("options.callback_url cannot be used with a dry run, which notifies no one".to_string()),
+            Some(url) => state.webhooks.check_callback_url(url),
+            None => Ok(()),
+        })
+        .and_then(|_| requested.apply(&mut message));
+    if let Err(e) = checked {
+        return Err((StatusCode::BAD_REQUEST, Json(serde_json::json!({ "error": e }))).into_response());
+    }
+    let options = TaskOptions {
+        skill_id: request.skill_id,
+        priority: requested.priority.unwrap_or(request.priority),
+        process_after: request.process_after,
+        session_id: request.session_id,
+        dry_run,
+        labels: request.labels,
+        outputs: requested.output,
+        locale: requested.locale,
+        callback_url: requested.callback_url,
+    };
+    // Deferred tasks only take a place in the queue once they are due, and dry runs never do
+    let queue = state.agent.submission_queue().filter(|_| options.deferred_until().is_none() && !options.dry_run);
+    let outcome = match queue {
+        Some(queue) => {
+            let Some(slot) = queue.reserve() else {
+                warn!(depth = queue.depth(), "processing queue full, submission rejected");
+                return Err((
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    [(header::RETRY_AFTER, queue.retry_after_seconds().to_string())],
+                    Json(serde_json::json!({ "error": "Too many tasks waiting to be processed, retry later" })),
+                )
+                    .into_response());
+            };
+            state.agent.accept_task(message, &options).inspect(|task| slot.send(task.id.clone(), options.priority))
         }
+        None => state.agent.send_task_with_options(message, &options).await,
+    };
+
+    outcome.map_err(|e| {
+        error!(error = %e, "task processing failed");
+        Json(TaskResponse {
+            task_id: "".to_string(),
+            status: "error".to_string(),
+            csv_output: None,
+            detailed_result: None,
+            error: Some(e.to_string()),
+            artifacts: None,
+            attachments: Vec::new(),
+            dry_run: false,
+        })
+        .into_response()
+    })
+}
+
+/// Validate a purchase order without creating a task or producing CSV output
+///
+/// Accepts the same bodies as queue intake: an A2A message, a task request or a
+/// bare purchase order.
+async fn validate_purchase_order(
+    State(state): State<Arc<AppState>>,
+    body: axum::body::Bytes,
+) -> Result<Json<ValidationReport>, (StatusCode, Json<serde_json::Value>)> {
+    let bad_request = |e: String| (StatusCode::BAD_REQUEST, Json(serde_json::json!({ "error": e })));
+    let message = message_from_payload(&body).map_err(bad_request)?;
+    let report = state.agent.validate(&message).map_err(|e| bad_request(e.to_string()))?;
+    debug!(po_number = %report.po_number, valid = report.valid, "purchase order validated");
+    Ok(Json(report))
+}
+
+/// Validate a batch of purchase orders without creating tasks
+async fn validate_batch(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<BatchRequest>,
+) -> Result<Json<BatchReport>, (StatusCode, Json<serde_json::Value>)> {
+    if request.orders.len() > batch_validation::MAX_BATCH {
+        let error = format!("A batch holds at most {} orders, not {}", batch_validation::MAX_BATCH, request.orders.len());
+        return Err((StatusCode::PAYLOAD_TOO_LARGE, Json(serde_json::json!({ "error": error }))));
     }
+    let report = batch_validation::validate(&state.agent, request.orders);
+    debug!(total = report.total, valid = report.valid, "purchase order batch validated");
+    Ok(Json(report))
 }
 
 /// Get a task by ID
 async fn get_task(
     State(state): State<Arc<AppState>>,
     axum::extract::Path(task_id): axum::extract::Path<String>,
+    auth: Option<Extension<AuthContext>>,
+    Query(query): Query<TaskQuery>,
 ) -> Result<Json<TaskResponse>, StatusCode> {
-    info!("Looking up task: {}", task_id);
+    debug!(task_id = %task_id, "task lookup");
 
-    match state.agent.get_task(&task_id).await {
-        Ok(task) => {
-            let mut csv_output = None;
-            let mut detailed_result = None;
-
-            if let Some(ref message) = task.status.message {
-                for part in &message.parts {
-                    match part {
-                        Part::Text { text } => {
-                            csv_output = Some(text.clone());
-                        }
-                        Part::Data { data } => {
-                            detailed_result = Some(data.clone());
-                        }
-                        _ => {}
-                    }
-                }
-            }
+    let task = state.agent.get_task(&task_id).await.map_err(|e| {
+        warn!(task_id = %task_id, error = %e, "task lookup failed");
+        StatusCode::NOT_FOUND
+    })?;
+    let mut response = state.task_response(auth.as_ref(), task);
+    // Processing results are returned at the version asked for; other skills' results as they are
+    if let Some(result) = response.detailed_result.take() {
+        let is_processing_result = result_schema::upgrade(result.clone())
+            .is_ok_and(|upgraded| ProcessingResult::deserialize(upgraded).is_ok());
+        response.detailed_result = Some(if is_processing_result {
+            let version = query.result_version.unwrap_or(result_schema::CURRENT_VERSION);
+            result_schema::convert(result, version).map_err(|e| {
+                debug!(task_id = %task_id, error = %e, "result version conversion refused");
+                StatusCode::BAD_REQUEST
+            })?
+        } else {
+            result
+        });
+    }
+    response.attachments = state.agent.attachments().for_task(&task_id).map_err(|e| {
+        error!(task_id = %task_id, error = %e, "attachment store lookup failed");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(Json(response))
+}
 
-            let status_str = match task.status.state {
-                a2a::TaskState::Completed => "completed",
-                a2a::TaskState::Failed => "failed",
-                a2a::TaskState::Submitted => "submitted",
-                a2a::TaskState::Working => "working",
-                a2a::TaskState::InputRequired => "input_required",
-                a2a::TaskState::Canceled => "cancelled",
-            };
+/// Query of `GET /agent/task/{id}`
+#[derive(Debug, Deserialize)]
+pub struct TaskQuery {
+    /// Schema version of the processing result to return; the current one when absent
+    #[serde(default)]
+    pub result_version: Option<u32>,
+}
 
-            Ok(Json(TaskResponse {
-                task_id: task.id,
-                status: status_str.to_string(),
-                csv_output,
-                detailed_result,
-                error: None,
-            }))
+/// `?presign=true` asks a download endpoint for a temporary URL instead of the content
+#[derive(Debug, Default, Deserialize)]
+pub struct DownloadQuery {
+    #[serde(default)]
+    pub presign: bool,
+}
+
+/// A presigned URL of `key` in the blob store, writing `content` there first when given
+///
+/// 501 when no blob store is configured or it cannot presign URLs.
+fn presigned_download(state: &AppState, key: &str, content: Option<&[u8]>) -> Result<Json<PresignedUrl>, StatusCode> {
+    let blobs = state.agent.blob_store().ok_or(StatusCode::NOT_IMPLEMENTED)?;
+    let url = blob::download_url(blobs.as_ref(), key, content, state.agent.download_url_ttl()).map_err(|e| {
+        error!(key = %key, error = %e, "presigning a download failed");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    url.map(Json).ok_or(StatusCode::NOT_IMPLEMENTED)
+}
+
+/// Get one of a task's artifacts by its position in the task's artifact list
+async fn get_task_artifact(
+    State(state): State<Arc<AppState>>,
+    auth: Option<Extension<AuthContext>>,
+    axum::extract::Path((task_id, index)): axum::extract::Path<(String, usize)>,
+    Query(query): Query<DownloadQuery>,
+) -> Result<Response, StatusCode> {
+    let task = state.agent.get_task(&task_id).await.map_err(|e| {
+        warn!(task_id = %task_id, error = %e, "task lookup failed");
+        StatusCode::NOT_FOUND
+    })?;
+    let task = if state.masks(auth.as_ref()) { state.masking.task(task) } else { task };
+    let artifact = task.artifacts.and_then(|artifacts| artifacts.into_iter().nth(index)).ok_or(StatusCode::NOT_FOUND)?;
+    if !query.presign {
+        return Ok(Json(artifact).into_response());
+    }
+    let content = serde_json::to_vec(&artifact).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let key = format!("artifacts/{}/{}.json", task_id, index);
+    Ok(presigned_download(&state, &key, Some(&content))?.into_response())
+}
+
+/// Event stream of a task
+#[derive(Debug, Serialize)]
+pub struct TaskEventsResponse {
+    pub task_id: String,
+    pub events: Vec<TaskEvent>,
+}
+
+/// Get the recorded event stream of a task
+async fn get_task_events(
+    State(state): State<Arc<AppState>>,
+    auth: Option<Extension<AuthContext>>,
+    axum::extract::Path(task_id): axum::extract::Path<String>,
+) -> Result<Json<TaskEventsResponse>, StatusCode> {
+    let mut events = state.agent.event_log().events(&task_id).map_err(|e| {
+        error!(task_id = %task_id, error = %e, "event log lookup failed");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    // Tasks restored from a snapshot exist without a recorded stream
+    if events.is_empty() && !matches!(state.agent.task_store().get(&task_id), Ok(Some(_))) {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    if state.masks(auth.as_ref()) {
+        events = events.into_iter().map(|event| state.masking.event(event)).collect();
+    }
+
+    Ok(Json(TaskEventsResponse { task_id, events }))
+}
+
+/// Webhook deliveries of a task
+#[derive(Debug, Serialize)]
+pub struct TaskDeliveriesResponse {
+    pub task_id: String,
+    pub deliveries: Vec<WebhookDelivery>,
+}
+
+/// Get the webhook delivery status of a task
+async fn get_task_deliveries(
+    State(state): State<Arc<AppState>>,
+    auth: Option<Extension<AuthContext>>,
+    axum::extract::Path(task_id): axum::extract::Path<String>,
+) -> Result<Json<TaskDeliveriesResponse>, StatusCode> {
+    let mut deliveries = state.agent.webhook_outbox().for_task(&task_id).map_err(|e| {
+        error!(task_id = %task_id, error = %e, "webhook outbox lookup failed");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    if deliveries.is_empty() && !matches!(state.agent.task_store().get(&task_id), Ok(Some(_))) {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    if state.masks(auth.as_ref()) {
+        deliveries.iter_mut().for_each(|delivery| state.masking.value(&mut delivery.payload));
+    }
+
+    Ok(Json(TaskDeliveriesResponse { task_id, deliveries }))
+}
+
+fn receipt_error(task_id: &str, e: ReceiptError) -> (StatusCode, Json<serde_json::Value>) {
+    let status = match &e {
+        ReceiptError::NotFound(_) => StatusCode::NOT_FOUND,
+        ReceiptError::Invalid(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        ReceiptError::Store(_) => {
+            error!(task_id = %task_id, error = %e, "receipt store failed");
+            StatusCode::INTERNAL_SERVER_ERROR
         }
+    };
+    (status, Json(serde_json::json!({ "error": e.to_string() })))
+}
+
+/// Get the receipts and open quantities of a processed order
+async fn get_open_order_status(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(task_id): axum::extract::Path<String>,
+) -> Result<Json<OpenOrderStatus>, (StatusCode, Json<serde_json::Value>)> {
+    receiving::open_order_status(&state.agent, &task_id)
+        .map(Json)
+        .map_err(|e| receipt_error(&task_id, e))
+}
+
+/// Get the ORDERS05 IDoc of an approved order
+async fn get_task_idoc(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(task_id): axum::extract::Path<String>,
+) -> Result<Response, StatusCode> {
+    let record = match state.agent.task_store().get(&task_id) {
+        Ok(Some(record)) => record,
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
         Err(e) => {
-            error!("Failed to get task {}: {}", task_id, e);
-            Err(StatusCode::NOT_FOUND)
+            error!(task_id = %task_id, error = %e, "task lookup failed");
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+    match state.agent.idoc(&record) {
+        Ok(Some(idoc)) => Ok(([(header::CONTENT_TYPE, "text/plain; charset=utf-8")], idoc).into_response()),
+        Ok(None) => Err(StatusCode::CONFLICT),
+        Err(e) => {
+            warn!(task_id = %task_id, error = %e, "IDoc export failed");
+            Err(StatusCode::UNPROCESSABLE_ENTITY)
+        }
+    }
+}
+
+/// Record goods received against a processed order
+async fn record_goods_receipt(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(task_id): axum::extract::Path<String>,
+    Json(request): Json<ReceiptRequest>,
+) -> Result<(StatusCode, Json<OpenOrderStatus>), (StatusCode, Json<serde_json::Value>)> {
+    let status = receiving::record_receipt(&state.agent, &task_id, request).map_err(|e| receipt_error(&task_id, e))?;
+    info!(task_id = %task_id, status = ?status.status, "goods receipt recorded");
+    Ok((StatusCode::CREATED, Json(status)))
+}
+
+fn retry_error(task_id: &str, e: RetryError) -> (StatusCode, Json<serde_json::Value>) {
+    let status = match &e {
+        RetryError::NotFound(_) => StatusCode::NOT_FOUND,
+        RetryError::NotFailed(_) => StatusCode::CONFLICT,
+        RetryError::Invalid(_) | RetryError::Processing(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        RetryError::Store(_) => {
+            error!(task_id = %task_id, error = %e, "task retry store failed");
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    };
+    (status, Json(serde_json::json!({ "error": e.to_string() })))
+}
+
+/// Process a failed task's message again, with optional corrections, as a new linked task
+async fn retry_task(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(task_id): axum::extract::Path<String>,
+    auth: Option<Extension<AuthContext>>,
+    body: Option<Json<RetryRequest>>,
+) -> Result<(StatusCode, Json<TaskResponse>), (StatusCode, Json<serde_json::Value>)> {
+    let request = body.map(|Json(request)| request).unwrap_or_default();
+    let task = resubmit::retry(&state.agent, &task_id, request).await.map_err(|e| retry_error(&task_id, e))?;
+    info!(task_id = %task_id, child_task_id = %task.id, "failed task retried");
+    Ok((StatusCode::CREATED, Json(state.task_response(auth.as_ref(), task))))
+}
+
+fn approval_error(task_id: &str, e: ApprovalError) -> (StatusCode, Json<serde_json::Value>) {
+    let status = match &e {
+        ApprovalError::NotFound(_) => StatusCode::NOT_FOUND,
+        ApprovalError::NotPending(_) => StatusCode::CONFLICT,
+        ApprovalError::NotAssignee(_) | ApprovalError::NotAuthorized(_) => StatusCode::FORBIDDEN,
+        ApprovalError::Invalid(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        ApprovalError::Store(_) | ApprovalError::Other(_) => {
+            error!(task_id = %task_id, error = %e, "approval failed");
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    };
+    (status, Json(serde_json::json!({ "error": e.to_string() })))
+}
+
+/// The acting approver: the authenticated subject, else the one named in the body
+fn approver(
+    task_id: &str,
+    auth: Option<Extension<AuthContext>>,
+    named: Option<String>,
+) -> Result<String, (StatusCode, Json<serde_json::Value>)> {
+    auth.map(|Extension(context)| context.subject)
+        .or(named)
+        .filter(|approver| !approver.trim().is_empty())
+        .ok_or_else(|| approval_error(task_id, ApprovalError::Invalid("approver must be named".to_string())))
+}
+
+/// Approval state of a task
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ApprovalResponse {
+    pub task_id: String,
+    /// Whether the order still waits for a decision
+    pub pending: bool,
+    pub assignment: Option<ApprovalAssignment>,
+    /// What the approval matrix requires of the order
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub requirement: Option<ApprovalRequirement>,
+    /// Approvals given so far towards the requirement
+    pub approvals: Vec<ApprovalVote>,
+}
+
+/// Get the approver an order pending approval is assigned to
+async fn get_task_approval(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(task_id): axum::extract::Path<String>,
+) -> Result<Json<ApprovalResponse>, (StatusCode, Json<serde_json::Value>)> {
+    let record = state
+        .agent
+        .task_store()
+        .get(&task_id)
+        .map_err(|e| approval_error(&task_id, e.into()))?
+        .ok_or_else(|| approval_error(&task_id, ApprovalError::NotFound(format!("Task {} not found", task_id))))?;
+    let requirement = approval::requirement(&record);
+    Ok(Json(ApprovalResponse {
+        pending: approval::is_pending(&record),
+        assignment: record.approval,
+        requirement,
+        approvals: record.approvals,
+        task_id,
+    }))
+}
+
+/// Approve or reject an order pending approval as its assignee
+async fn decide_approval(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(task_id): axum::extract::Path<String>,
+    auth: Option<Extension<AuthContext>>,
+    Json(request): Json<DecisionRequest>,
+) -> Result<Json<TaskResponse>, (StatusCode, Json<serde_json::Value>)> {
+    let roles = auth.as_ref().map_or(request.roles, |Extension(context)| context.roles.clone());
+    let caller = auth.clone();
+    let approver = approver(&task_id, auth, request.approver)?;
+    let task = approval::decide(&state.agent, &task_id, &approver, &roles, request.approved, request.comment, &request.rejected_lines)
+        .map_err(|e| approval_error(&task_id, e))?;
+    info!(task_id = %task_id, approved = request.approved, "approval decided");
+    Ok(Json(state.task_response(caller.as_ref(), task)))
+}
+
+/// Hand an approval over to another user
+async fn delegate_approval(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(task_id): axum::extract::Path<String>,
+    auth: Option<Extension<AuthContext>>,
+    Json(request): Json<DelegationRequest>,
+) -> Result<Json<ApprovalAssignment>, (StatusCode, Json<serde_json::Value>)> {
+    let approver = approver(&task_id, auth, request.approver)?;
+    approval::delegate(&state.agent, &task_id, &approver, &request.to)
+        .map(Json)
+        .map_err(|e| approval_error(&task_id, e))
+}
+
+fn attachment_error(task_id: &str, e: AttachmentError) -> (StatusCode, Json<serde_json::Value>) {
+    let status = match &e {
+        AttachmentError::NotFound(_) => StatusCode::NOT_FOUND,
+        AttachmentError::Invalid(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        AttachmentError::Store(_) | AttachmentError::Other(_) => {
+            error!(task_id = %task_id, error = %e, "attachment failed");
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    };
+    (status, Json(serde_json::json!({ "error": e.to_string() })))
+}
+
+/// Documents attached to a task
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AttachmentsResponse {
+    pub task_id: String,
+    pub attachments: Vec<Attachment>,
+}
+
+/// Query of a raw attachment upload
+#[derive(Debug, Deserialize)]
+struct UploadQuery {
+    name: Option<String>,
+}
+
+/// Attach supporting documents to a task
+///
+/// A JSON body holds A2A File parts; any other body is a single file, named by
+/// `?name=` and typed by its `Content-Type`.
+async fn attach_documents(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(task_id): axum::extract::Path<String>,
+    Query(query): Query<UploadQuery>,
+    auth: Option<Extension<AuthContext>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<(StatusCode, Json<AttachmentsResponse>), (StatusCode, Json<serde_json::Value>)> {
+    let content_type = headers.get(header::CONTENT_TYPE).and_then(|value| value.to_str().ok()).unwrap_or("application/octet-stream");
+    let files = if content_type.starts_with("application/json") {
+        let request: AttachRequest = serde_json::from_slice(&body)
+            .map_err(|e| attachment_error(&task_id, AttachmentError::Invalid(format!("Invalid attachment request: {}", e))))?;
+        request.parts.iter().map(attachments::from_part).collect::<Result<Vec<_>, _>>()
+    } else {
+        Ok(vec![NewAttachment {
+            name: query.name.unwrap_or_else(|| "attachment".to_string()),
+            mime_type: content_type.to_string(),
+            bytes: body.to_vec(),
+        }])
+    };
+    let uploaded_by = auth.map(|Extension(context)| context.subject);
+    let attached = files
+        .and_then(|files| attachments::attach(&state.agent, &task_id, files, uploaded_by))
+        .map_err(|e| attachment_error(&task_id, e))?;
+    info!(task_id = %task_id, attached = attached.len(), "documents attached");
+    Ok((StatusCode::CREATED, Json(AttachmentsResponse { task_id, attachments: attached })))
+}
+
+/// List the documents attached to a task
+async fn get_task_attachments(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(task_id): axum::extract::Path<String>,
+) -> Result<Json<AttachmentsResponse>, (StatusCode, Json<serde_json::Value>)> {
+    if state.agent.task_store().get(&task_id).map_err(|e| attachment_error(&task_id, e.into()))?.is_none() {
+        return Err(attachment_error(&task_id, AttachmentError::NotFound(format!("Task {} not found", task_id))));
+    }
+    let attachments = state.agent.attachments().for_task(&task_id).map_err(|e| attachment_error(&task_id, e.into()))?;
+    Ok(Json(AttachmentsResponse { task_id, attachments }))
+}
+
+/// Download an attached document with its original content type
+async fn get_task_attachment(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path((task_id, attachment_id)): axum::extract::Path<(String, String)>,
+    Query(query): Query<DownloadQuery>,
+) -> Result<Response, (StatusCode, Json<serde_json::Value>)> {
+    let presign_error = |status: StatusCode| {
+        let message = match status {
+            StatusCode::NOT_IMPLEMENTED => "Presigned downloads need a blob store that supports them",
+            _ => "Failed to presign the download",
+        };
+        (status, Json(serde_json::json!({ "error": message })))
+    };
+    if query.presign && let Some(key) = state.agent.attachments().blob_key(&task_id, &attachment_id) {
+        return Ok(presigned_download(&state, &key, None).map_err(presign_error)?.into_response());
+    }
+    let (attachment, content) = state
+        .agent
+        .attachments()
+        .content(&task_id, &attachment_id)
+        .map_err(|e| attachment_error(&task_id, e.into()))?
+        .ok_or_else(|| attachment_error(&task_id, AttachmentError::NotFound(format!("Attachment {} not found", attachment_id))))?;
+    if query.presign {
+        let key = attachments::content_key(&task_id, &attachment_id);
+        return Ok(presigned_download(&state, &key, Some(&content)).map_err(presign_error)?.into_response());
+    }
+    let file_name: String = attachment
+        .name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') { c } else { '_' })
+        .collect();
+    let headers = [
+        (header::CONTENT_TYPE, attachment.mime_type),
+        (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", file_name)),
+    ];
+    Ok((headers, content).into_response())
+}
+
+/// Labels of a task
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LabelsResponse {
+    pub task_id: String,
+    pub labels: Labels,
+}
+
+fn label_error(task_id: &str, e: LabelError) -> (StatusCode, Json<serde_json::Value>) {
+    let status = match &e {
+        LabelError::NotFound(_) => StatusCode::NOT_FOUND,
+        LabelError::Invalid(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        LabelError::Store(_) => {
+            error!(task_id = %task_id, error = %e, "label update failed");
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    };
+    (status, Json(serde_json::json!({ "error": e.to_string() })))
+}
+
+/// Get the labels a task is marked with
+async fn get_task_labels(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(task_id): axum::extract::Path<String>,
+) -> Result<Json<LabelsResponse>, (StatusCode, Json<serde_json::Value>)> {
+    let record = state
+        .agent
+        .task_store()
+        .get(&task_id)
+        .map_err(|e| label_error(&task_id, e.into()))?
+        .ok_or_else(|| label_error(&task_id, LabelError::NotFound(format!("Task {} not found", task_id))))?;
+    Ok(Json(LabelsResponse { task_id, labels: record.labels }))
+}
+
+/// Merge a patch into a task's labels; keys set to `null` are removed
+async fn update_task_labels(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(task_id): axum::extract::Path<String>,
+    Json(patch): Json<BTreeMap<String, Option<String>>>,
+) -> Result<Json<LabelsResponse>, (StatusCode, Json<serde_json::Value>)> {
+    let labels = labels::update(&state.agent, &task_id, patch).map_err(|e| label_error(&task_id, e))?;
+    info!(task_id = %task_id, labels = labels.len(), "task labels updated");
+    Ok(Json(LabelsResponse { task_id, labels }))
+}
+
+fn search_error(e: SearchError) -> (StatusCode, Json<serde_json::Value>) {
+    let status = match &e {
+        SearchError::NotFound(_) => StatusCode::NOT_FOUND,
+        SearchError::Conflict(_) => StatusCode::CONFLICT,
+        SearchError::Invalid(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        SearchError::Store(_) => {
+            error!(error = %e, "saved search failed");
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    };
+    (status, Json(serde_json::json!({ "error": e.to_string() })))
+}
+
+/// List the saved searches of the task list
+async fn list_saved_searches(State(state): State<Arc<AppState>>) -> Result<Json<Vec<SavedSearch>>, (StatusCode, Json<serde_json::Value>)> {
+    let searches = state.agent.saved_searches().list().map_err(|e| search_error(e.into()))?;
+    Ok(Json(searches))
+}
+
+/// Save a named set of task list filters
+async fn save_search(
+    State(state): State<Arc<AppState>>,
+    auth: Option<Extension<AuthContext>>,
+    Json(request): Json<SaveSearchRequest>,
+) -> Result<(StatusCode, Json<SavedSearch>), (StatusCode, Json<serde_json::Value>)> {
+    let created_by = auth.map(|Extension(context)| context.subject);
+    let search = searches::save(state.agent.saved_searches().as_ref(), request, created_by).map_err(search_error)?;
+    info!(name = %search.name, "search saved");
+    Ok((StatusCode::CREATED, Json(search)))
+}
+
+/// Get a saved search by name
+async fn get_saved_search(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(name): axum::extract::Path<String>,
+) -> Result<Json<SavedSearch>, (StatusCode, Json<serde_json::Value>)> {
+    state
+        .agent
+        .saved_searches()
+        .get(&name)
+        .map_err(|e| search_error(e.into()))?
+        .map(Json)
+        .ok_or_else(|| search_error(SearchError::NotFound(format!("No saved search named {}", name))))
+}
+
+/// Delete a saved search by name
+async fn delete_saved_search(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(name): axum::extract::Path<String>,
+) -> Result<StatusCode, (StatusCode, Json<serde_json::Value>)> {
+    match state.agent.saved_searches().remove(&name).map_err(|e| search_error(e.into()))? {
+        Some(_) => {
+            info!(name = %name, "saved search deleted");
+            Ok(StatusCode::NO_CONTENT)
         }
+        None => Err(search_error(SearchError::NotFound(format!("No saved search named {}", name)))),
     }
 }
 
+/// Result of amending a purchase order
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AmendmentResponse {
+    pub revision: Revision,
+    /// Task that re-processed the amended order
+    pub task: TaskResponse,
+}
+
+/// Revision chain of a purchase order
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RevisionsResponse {
+    pub po_number: String,
+    pub revisions: Vec<Revision>,
+}
+
+fn amendment_error(po_number: &str, e: AmendmentError) -> (StatusCode, Json<serde_json::Value>) {
+    let status = match &e {
+        AmendmentError::NotFound(_) => StatusCode::NOT_FOUND,
+        AmendmentError::Invalid(_) | AmendmentError::Processing(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        AmendmentError::Store(_) => {
+            error!(po_number = %po_number, error = %e, "amendment store failed");
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    };
+    (status, Json(serde_json::json!({ "error": e.to_string() })))
+}
+
+/// Amend a processed purchase order
+///
+/// The authenticated subject, when there is one, is recorded as the amender.
+async fn amend_purchase_order(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(po_number): axum::extract::Path<String>,
+    auth: Option<Extension<AuthContext>>,
+    Json(mut request): Json<AmendmentRequest>,
+) -> Result<(StatusCode, Json<AmendmentResponse>), (StatusCode, Json<serde_json::Value>)> {
+    if let Some(Extension(context)) = &auth {
+        request.amended_by = Some(context.subject.clone());
+    }
+    let (revision, task) = amendments::amend(&state.agent, &po_number, request)
+        .await
+        .map_err(|e| amendment_error(&po_number, e))?;
+    info!(po_number = %po_number, revision = revision.revision, task_id = %task.id, "purchase order amended");
+    Ok((StatusCode::CREATED, Json(AmendmentResponse { revision, task: state.task_response(auth.as_ref(), task) })))
+}
+
+/// Get the revision chain of a purchase order
+async fn get_revisions(
+    State(state): State<Arc<AppState>>,
+    auth: Option<Extension<AuthContext>>,
+    axum::extract::Path(po_number): axum::extract::Path<String>,
+) -> Result<Json<RevisionsResponse>, (StatusCode, Json<serde_json::Value>)> {
+    let mut revisions = amendments::revisions(&state.agent, &po_number).map_err(|e| amendment_error(&po_number, e))?;
+    if state.masks(auth.as_ref()) {
+        revisions.iter_mut().filter_map(|revision| revision.diff.as_mut()).for_each(|diff| state.masking.diff(diff));
+    }
+    Ok(Json(RevisionsResponse { po_number, revisions }))
+}
+
+/// Get the tasks of a session, oldest first
+async fn get_session(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(session_id): axum::extract::Path<String>,
+) -> Result<Json<SessionContext>, StatusCode> {
+    let context = session::context(&state.agent, &session_id).map_err(|e| {
+        error!(session_id = %session_id, error = %e, "session lookup failed");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    context.map(Json).ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Get the field-level differences between the orders two tasks processed
+async fn get_task_diff(
+    State(state): State<Arc<AppState>>,
+    auth: Option<Extension<AuthContext>>,
+    axum::extract::Path((task_id, other_task_id)): axum::extract::Path<(String, String)>,
+) -> Result<Json<TaskDiff>, (StatusCode, Json<serde_json::Value>)> {
+    let mut diff = amendments::diff_tasks(&state.agent, &task_id, &other_task_id).map_err(|e| amendment_error(&task_id, e))?;
+    if state.masks(auth.as_ref()) {
+        state.masking.diff(&mut diff.diff);
+    }
+    Ok(Json(diff))
+}
+
+/// Get a blanket order and the spend released against it
+async fn get_blanket_order(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(po_number): axum::extract::Path<String>,
+) -> Result<Json<BlanketReport>, StatusCode> {
+    let order = state.agent.blankets().get(&po_number).map_err(|e| {
+        error!(po_number = %po_number, error = %e, "blanket store lookup failed");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let order = order.ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(order.report(chrono::Utc::now().date_naive())))
+}
+
+/// Query of `GET /analytics/prices/{item_code}`
+#[derive(Debug, Deserialize)]
+pub struct PriceHistoryQuery {
+    /// Only orders whose tasks have these labels, e.g. `source:sap,period:2026-Q3`
+    #[serde(default)]
+    pub labels: Option<String>,
+}
+
+/// Unit prices paid for an item by processed orders
+async fn get_price_history(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(item_code): axum::extract::Path<String>,
+    Query(query): Query<PriceHistoryQuery>,
+) -> Result<Json<PriceHistory>, Response> {
+    let selector = labels::parse_selector(query.labels.as_deref().unwrap_or(""))
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(serde_json::json!({ "error": e }))).into_response())?;
+    let observations = price_history::labelled(&state.agent, &item_code, &selector).map_err(|e| {
+        error!(item_code = %item_code, error = %e, "price history lookup failed");
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    })?;
+    let history = PriceHistory::new(&item_code, observations, state.agent.price_history_config().window);
+    history.map(Json).ok_or_else(|| StatusCode::NOT_FOUND.into_response())
+}
+
+/// Query of `GET /reports/monthly-close`
+#[derive(Debug, Deserialize)]
+pub struct MonthlyCloseQuery {
+    /// `YYYY-MM`; the month before the current one when absent
+    #[serde(default)]
+    pub month: Option<String>,
+    #[serde(default)]
+    pub format: ReportFormat,
+    /// Only tasks with these labels, e.g. `source:sap,period:2026-Q3`
+    #[serde(default)]
+    pub labels: Option<String>,
+}
+
+/// Month-end close report as JSON, CSV or PDF
+async fn get_monthly_close(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<MonthlyCloseQuery>,
+) -> Result<Response, Response> {
+    let month = match query.month.as_deref() {
+        Some(month) => month
+            .parse::<Month>()
+            .map_err(|e| (StatusCode::BAD_REQUEST, Json(serde_json::json!({ "error": e }))).into_response())?,
+        None => Month::before(Utc::now()),
+    };
+    let selector = labels::parse_selector(query.labels.as_deref().unwrap_or(""))
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(serde_json::json!({ "error": e }))).into_response())?;
+    let report = monthly_close::generate(&state.agent, month, state.monthly_close.top_suppliers, &selector).map_err(|e| {
+        error!(%month, error = %e, "monthly close report failed");
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    })?;
+    let content = report.render(query.format).map_err(|e| {
+        error!(%month, error = %e, "monthly close report failed");
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    })?;
+    let disposition = format!("attachment; filename=\"monthly-close-{}.{}\"", month, query.format.extension());
+    let mut response = ([(header::CONTENT_TYPE, query.format.content_type())], content).into_response();
+    if query.format != ReportFormat::Json
+        && let Ok(value) = disposition.parse()
+    {
+        response.headers_mut().insert(header::CONTENT_DISPOSITION, value);
+    }
+    Ok(response)
+}
+
 /// Cancel a task by ID
 async fn cancel_task(
     State(state): State<Arc<AppState>>,
     axum::extract::Path(task_id): axum::extract::Path<String>,
 ) -> Result<Json<TaskResponse>, StatusCode> {
-    info!("Cancelling task: {}", task_id);
+    info!(task_id = %task_id, "task cancellation requested");
 
     match state.agent.cancel_task(&task_id).await {
         Ok(task) => {
@@ -287,20 +1586,541 @@ async fn cancel_task(
                 csv_output: None,
                 detailed_result: None,
                 error: None,
+                artifacts: None,
+                attachments: Vec::new(),
+                dry_run: false,
             }))
         }
         Err(e) => {
-            error!("Failed to cancel task {}: {}", task_id, e);
+            warn!(task_id = %task_id, error = %e, "task cancellation failed");
             Err(StatusCode::NOT_FOUND)
         }
     }
 }
 
+/// Prometheus metrics endpoint
+async fn metrics(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        state.agent.metrics().render(),
+    )
+}
+
 /// Health check endpoint
-async fn health_check() -> Json<serde_json::Value> {
+///
+/// Reports `degraded` while the circuit to any downstream integration is open;
+/// the agent itself keeps serving, so the status code stays 200.
+async fn health_check(State(state): State<Arc<AppState>>) -> Json<serde_json::Value> {
+    let integrations = state.agent.breakers().statuses();
+    let degraded = integrations.values().any(|status| status.state == BreakerState::Open);
     Json(serde_json::json!({
-        "status": "healthy",
+        "status": if degraded { "degraded" } else { "healthy" },
         "service": "Purchase Order Processing Agent",
-        "timestamp": chrono::Utc::now().to_rfc3339()
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+        "integrations": integrations,
     }))
-}
\ No newline at end of file
+}
+
+/// Readiness endpoint
+///
+/// Lists every configured downstream as up or down. Answers 503 only while the
+/// task store is down; a broken downstream leaves the agent `degraded` but ready.
+async fn readiness_check(State(state): State<Arc<AppState>>) -> (StatusCode, Json<readiness::Readiness>) {
+    let readiness = readiness::check(&state.agent, &state.dependencies);
+    let status = if readiness.status == ReadinessStatus::NotReady { StatusCode::SERVICE_UNAVAILABLE } else { StatusCode::OK };
+    (status, Json(readiness))
+}
+
+/// Status endpoint
+///
+/// Reports the build, uptime and a summary of the configuration with secrets
+/// left out, for deployment tooling to scrape.
+async fn server_status(State(state): State<Arc<AppState>>) -> Json<Status> {
+    Json(state.status.report())
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use crate::events::TaskEventKind;
+    use axum::body::Body;
+    use axum::http::Request;
+    use tower::ServiceExt;
+
+    fn card_request(if_none_match: Option<&str>) -> Request<Body> {
+        let mut builder = Request::get("/.well-known/agent.json");
+        if let Some(tag) = if_none_match {
+            builder = builder.header(header::IF_NONE_MATCH, tag);
+        }
+        builder.body(Body::empty()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_agent_card_carries_etag_and_cache_control() {
+        let router = create_router(Arc::new(PurchaseOrderAgent::new()));
+
+        let response = router.oneshot(card_request(None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().contains_key(header::ETAG));
+        assert_eq!(response.headers()[header::CACHE_CONTROL], "public, max-age=300");
+    }
+
+    #[test]
+    fn test_runtime_card_reflects_configuration() {
+        let agent = PurchaseOrderAgent::new();
+        let card = runtime_agent_card(&agent, &ServerConfig::default());
+        assert_eq!(card.url, "http://localhost:8080");
+        assert_eq!(card.documentation_url, None);
+        assert_eq!(card.capabilities.push_notifications, Some(false));
+        assert_eq!(card.authentication.schemes, ["none"]);
+        assert!(card.security_schemes.is_empty() && card.security.is_empty());
+        assert_eq!(card.skills.len(), agent.skills().ids().len());
+
+        let config: ServerConfig = toml::from_str(
+            r#"
+            [server]
+            bind_address = "0.0.0.0:8443"
+            public_url = "https://po.example.com/"
+
+            [server.tls]
+            cert_path = "cert.pem"
+            key_path = "key.pem"
+            client_ca_path = "ca.pem"
+
+            [auth]
+            jwt_secret = "secret"
+
+            [[webhooks.endpoints]]
+            name = "erp"
+            url = "https://erp.example.com/hook"
+            "#,
+        )
+        .unwrap();
+        let card = runtime_agent_card(&agent, &config);
+        assert_eq!(card.url, "https://po.example.com");
+        assert_eq!(card.capabilities.push_notifications, Some(true));
+        assert_eq!(card.authentication.schemes, ["Bearer", "mTLS"]);
+        assert_eq!(card.security_schemes["mtls"], SecurityScheme::MutualTls { description: None });
+        assert_eq!(card.security, [BTreeMap::from([("bearer".to_string(), vec![]), ("mtls".to_string(), vec![])])]);
+
+        // Switched-off subsystems and skills are left off the card
+        let config = ServerConfig::from_toml(
+            r#"
+            [flags]
+            notifications = false
+
+            [flags.skills]
+            expense-report-validation = false
+
+            [[webhooks.endpoints]]
+            name = "erp"
+            url = "https://erp.example.com/hook"
+            "#,
+        )
+        .unwrap();
+        let agent = PurchaseOrderAgent::new().without_skill(crate::expense::EXPENSE_SKILL).unwrap();
+        let card = runtime_agent_card(&agent, &config);
+        assert_eq!(card.capabilities.push_notifications, Some(false));
+        assert!(card.skills.iter().all(|skill| skill.id != crate::expense::EXPENSE_SKILL));
+        assert!(PurchaseOrderAgent::new().without_skill(crate::skills::PROCESSING_SKILL).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_matching_if_none_match_returns_not_modified() {
+        let router = create_router(Arc::new(PurchaseOrderAgent::new()));
+
+        let first = router.clone().oneshot(card_request(None)).await.unwrap();
+        let etag = first.headers()[header::ETAG].to_str().unwrap().to_string();
+
+        let revalidated = router.clone().oneshot(card_request(Some(&etag))).await.unwrap();
+        assert_eq!(revalidated.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(revalidated.headers()[header::ETAG], etag.as_str());
+
+        let weak = format!("\"stale\", W/{}", etag);
+        let weak_match = router.clone().oneshot(card_request(Some(&weak))).await.unwrap();
+        assert_eq!(weak_match.status(), StatusCode::NOT_MODIFIED);
+
+        let stale = router.oneshot(card_request(Some("\"stale\""))).await.unwrap();
+        assert_eq!(stale.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_task_events_endpoint() {
+        let agent = Arc::new(PurchaseOrderAgent::new());
+        let request = Message { role: "user".to_string(), parts: vec![] };
+        let status = a2a::TaskStatus { state: a2a::TaskState::Completed, message: None, timestamp: "0".to_string() };
+        agent.record_event("po-1", TaskEventKind::Created { request, session_id: None, skill_id: None, priority: Default::default() }).unwrap();
+        agent.record_event("po-1", TaskEventKind::StateChanged { status }).unwrap();
+        let router = create_router(agent);
+
+        let request = Request::get("/agent/task/po-1/events").body(Body::empty()).unwrap();
+        let response = router.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["events"][0]["type"], "created");
+        assert_eq!(body["events"][1]["type"], "state_changed");
+
+        let missing = Request::get("/agent/task/missing/events").body(Body::empty()).unwrap();
+        assert_eq!(router.oneshot(missing).await.unwrap().status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_raw_upload_is_attached_and_downloadable() {
+        let agent = Arc::new(PurchaseOrderAgent::new());
+        let task = agent.accept_task(Message { role: "user".to_string(), parts: vec![] }, &TaskOptions::default()).unwrap();
+        let router = create_router(agent);
+
+        let upload = Request::post(format!("/agent/task/{}/attachments?name=contract.pdf", task.id))
+            .header(header::CONTENT_TYPE, "application/pdf")
+            .body(Body::from("%PDF-1.7"))
+            .unwrap();
+        let response = router.clone().oneshot(upload).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let attached: AttachmentsResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(attached.attachments[0].mime_type, "application/pdf");
+
+        let detail = Request::get(format!("/agent/task/{}", task.id)).body(Body::empty()).unwrap();
+        let body = axum::body::to_bytes(router.clone().oneshot(detail).await.unwrap().into_body(), usize::MAX).await.unwrap();
+        let detail: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(detail["attachments"][0]["name"], "contract.pdf");
+
+        let download = Request::get(format!("/agent/task/{}/attachments/{}", task.id, attached.attachments[0].id)).body(Body::empty()).unwrap();
+        let response = router.clone().oneshot(download).await.unwrap();
+        assert_eq!(response.headers()[header::CONTENT_DISPOSITION], "attachment; filename=\"contract.pdf\"");
+        assert_eq!(&axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap()[..], b"%PDF-1.7");
+
+        let missing = Request::post("/agent/task/missing/attachments").body(Body::from("x")).unwrap();
+        assert_eq!(router.oneshot(missing).await.unwrap().status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_labels_are_set_patched_and_filtered() {
+        let agent = Arc::new(PurchaseOrderAgent::new());
+        let router = create_router(agent.clone());
+        let json = |request: Request<Body>| {
+            let router = router.clone();
+            async move {
+                let response = router.oneshot(request).await.unwrap();
+                let status = response.status();
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (status, serde_json::from_slice::<serde_json::Value>(&body).unwrap_or_default())
+            }
+        };
+        let submit = |labels: serde_json::Value| {
+            let body = serde_json::json!({ "message": crate::testing::message(&crate::testing::valid_order()), "labels": labels });
+            Request::post("/agent/task").header(header::CONTENT_TYPE, "application/json").body(Body::from(body.to_string())).unwrap()
+        };
+
+        let (_, sap) = json(submit(serde_json::json!({ "source": "sap", "period": "2026-Q3" }))).await;
+        let (_, oracle) = json(submit(serde_json::json!({ "source": "oracle" }))).await;
+        let (status, _) = json(submit(serde_json::json!({ "bad key": "x" }))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let patch = Request::patch(format!("/agent/task/{}/labels", oracle["task_id"].as_str().unwrap()))
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(r#"{"period": "2026-Q3", "source": null}"#))
+            .unwrap();
+        let (status, patched) = json(patch).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(patched["labels"], serde_json::json!({ "period": "2026-Q3" }));
+
+        let query = serde_json::json!({ "query": r#"{ tasks(filter: {labels: {source: "sap"}}) { totalCount nodes { id labels { key value } } } }"# });
+        let graphql = Request::post("/graphql").header(header::CONTENT_TYPE, "application/json").body(Body::from(query.to_string())).unwrap();
+        let (_, body) = json(graphql).await;
+        assert_eq!(body["data"]["tasks"]["totalCount"], 1);
+        assert_eq!(body["data"]["tasks"]["nodes"][0]["id"], sap["task_id"]);
+        assert_eq!(body["data"]["tasks"]["nodes"][0]["labels"][0], serde_json::json!({ "key": "period", "value": "2026-Q3" }));
+
+        let query = serde_json::json!({ "query": r#"{ stats(filter: {labels: {period: "2026-Q3"}}) { count } }"# });
+        let graphql = Request::post("/graphql").header(header::CONTENT_TYPE, "application/json").body(Body::from(query.to_string())).unwrap();
+        assert_eq!(json(graphql).await.1["data"]["stats"]["count"], 2);
+
+        let (status, _) = json(Request::get("/agent/task/missing/labels").body(Body::empty()).unwrap()).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_validate_endpoint_creates_no_task() {
+        let agent = Arc::new(PurchaseOrderAgent::new());
+        let router = create_router(agent.clone());
+        let mut po = crate::testing::valid_order();
+        po.supplier_name = String::new();
+        po.po_number = "PO-9".to_string();
+        let po = serde_json::to_value(po).unwrap();
+
+        for path in ["/agent/validate", "/agent/skills/purchase-order-validation"] {
+            let request = Request::post(path).body(Body::from(po.to_string())).unwrap();
+            let response = router.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            let report: ValidationReport = serde_json::from_slice(&body).unwrap();
+            assert_eq!(report.po_number, "PO-9");
+            assert!(!report.valid);
+            assert!(report.validation_errors.iter().any(|e| e.contains("Supplier name")));
+        }
+        let batch = serde_json::json!({ "orders": [po, crate::testing::valid_order()] });
+        let request = Request::post("/agent/validate/batch")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(batch.to_string()))
+            .unwrap();
+        let response = router.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let report: BatchReport = serde_json::from_slice(&body).unwrap();
+        assert_eq!((report.valid, report.invalid), (1, 1));
+        assert_eq!(report.counts[&crate::severity::Check::MissingSupplierName].orders, 1);
+        assert_eq!(agent.task_store().stats().unwrap().total_tasks, 0);
+
+        let garbage = Request::post("/agent/validate").body(Body::from("{\"nope\": 1}")).unwrap();
+        assert_eq!(router.oneshot(garbage).await.unwrap().status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_orders_larger_than_the_default_body_limit_are_accepted() {
+        let router = create_router(Arc::new(PurchaseOrderAgent::new()));
+        let mut po = crate::testing::huge_order(30_000);
+        po.po_number = "PO-BIG".to_string();
+        let po = serde_json::json!({ "purchaseOrder": po }).to_string();
+        assert!(po.len() > 2 * 1024 * 1024);
+
+        let response = router.oneshot(Request::post("/agent/validate").body(Body::from(po)).unwrap()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let report: ValidationReport = serde_json::from_slice(&body).unwrap();
+        assert_eq!(report.po_number, "PO-BIG");
+    }
+
+    #[tokio::test]
+    async fn test_receipt_endpoints_track_open_quantities() {
+        let agent = Arc::new(PurchaseOrderAgent::new());
+        let task = agent.send_task(crate::testing::message(&crate::testing::valid_order())).await.unwrap();
+        let router = create_router(agent);
+        let path = format!("/agent/task/{}/receipts", task.id);
+        let receive = |quantity: u32| {
+            let body = serde_json::json!({ "receipt_number": "GR-1", "lines": [{ "itemCode": "P-1", "quantity": quantity }] });
+            Request::post(&path)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+
+        assert_eq!(router.clone().oneshot(receive(4)).await.unwrap().status(), StatusCode::CREATED);
+        assert_eq!(router.clone().oneshot(receive(7)).await.unwrap().status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let response = router.clone().oneshot(Request::get(&path).body(Body::empty()).unwrap()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let status: OpenOrderStatus = serde_json::from_slice(&body).unwrap();
+        assert_eq!(status.status, receiving::ReceivingStatus::PartiallyReceived);
+        assert_eq!(status.lines[0].open_quantity, 6);
+
+        let missing = Request::get("/agent/task/missing/receipts").body(Body::empty()).unwrap();
+        assert_eq!(router.oneshot(missing).await.unwrap().status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_full_processing_queue_rejects_submissions() {
+        let config = crate::processing::ProcessingConfig { background: true, queue_depth: 1, ..Default::default() };
+        let (queue, _receiver) = crate::processing::queue(&config, Arc::new(crate::metrics::Metrics::new()));
+        let agent = Arc::new(PurchaseOrderAgent::new().with_submission_queue(queue));
+        let router = create_router(agent.clone());
+        let submit = || {
+            let body = serde_json::json!({ "message": { "role": "user", "parts": [{ "type": "text", "text": "{}" }] } });
+            Request::post("/agent/task")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+
+        let response = router.clone().oneshot(submit()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let accepted: TaskResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(accepted.status, "submitted");
+
+        let response = router.oneshot(submit()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "5");
+        assert_eq!(agent.task_store().stats().unwrap().total_tasks, 1);
+    }
+
+    #[tokio::test]
+    async fn test_health_reports_open_circuits() {
+        let agent = Arc::new(PurchaseOrderAgent::new());
+        let router = create_router(agent.clone());
+        let health = |router: Router| async move {
+            let response = router.oneshot(Request::get("/health").body(Body::empty()).unwrap()).await.unwrap();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+        };
+        assert_eq!(health(router.clone()).await["status"], "healthy");
+
+        let config = crate::breaker::BreakerConfig { failure_threshold: 1, ..Default::default() };
+        agent.breakers().get("webhook:erp", &config).record_failure();
+        let body = health(router).await;
+        assert_eq!(body["status"], "degraded");
+        assert_eq!(body["integrations"]["webhook:erp"]["state"], "open");
+        assert_eq!(body["integrations"]["webhook:erp"]["consecutive_failures"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_ready_lists_configured_downstreams() {
+        let agent = Arc::new(PurchaseOrderAgent::new());
+        let config: ServerConfig = toml::from_str(
+            r#"
+            [[webhooks.endpoints]]
+            name = "erp"
+            url = "http://erp.invalid/hook"
+            "#,
+        )
+        .unwrap();
+        let router = crate::create_router_with_config(agent.clone(), &config);
+        let ready = |router: Router| async move {
+            let response = router.oneshot(Request::get("/ready").body(Body::empty()).unwrap()).await.unwrap();
+            let status = response.status();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            (status, serde_json::from_slice::<serde_json::Value>(&body).unwrap())
+        };
+        let (status, body) = ready(router.clone()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["status"], "ready");
+        assert_eq!(body["dependencies"][1]["name"], "webhook:erp");
+        assert_eq!(body["dependencies"][1]["up"], true);
+
+        agent.dependencies().record_failure("webhook:erp", "connection refused");
+        let (status, body) = ready(router).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["status"], "degraded");
+        assert_eq!(body["dependencies"][1]["up"], false);
+        assert_eq!(body["dependencies"][1]["last_error"], "connection refused");
+    }
+
+    #[tokio::test]
+    async fn test_status_reports_build_and_configuration() {
+        let agent = Arc::new(PurchaseOrderAgent::new());
+        let config: ServerConfig = toml::from_str(
+            r#"
+            [[webhooks.endpoints]]
+            name = "erp"
+            url = "http://erp.invalid/hook?key=secret"
+            "#,
+        )
+        .unwrap();
+        let router = crate::create_router_with_config(agent, &config);
+        let response = router.oneshot(Request::get("/status").body(Body::empty()).unwrap()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["version"], env!("CARGO_PKG_VERSION"));
+        assert!(body["uptime_seconds"].as_i64().unwrap() >= 0);
+        assert_eq!(body["config"]["listen"]["bind_address"], "0.0.0.0:8080");
+        assert_eq!(body["config"]["integrations"][0]["target"], "http://erp.invalid/hook");
+    }
+
+    #[tokio::test]
+    async fn test_repeated_idempotency_key_returns_the_same_task() {
+        let agent = Arc::new(PurchaseOrderAgent::new());
+        let router = create_router(agent.clone());
+        let submit_with = |text: &str| {
+            let body = serde_json::json!({ "message": { "role": "user", "parts": [{ "type": "text", "text": text }] } });
+            Request::post("/agent/task")
+                .header(header::CONTENT_TYPE, "application/json")
+                .header(IDEMPOTENCY_KEY_HEADER, "retry-1")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+        let submit = || submit_with("{}");
+        let task_id = |response: Response| async move {
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            serde_json::from_slice::<TaskResponse>(&body).unwrap().task_id
+        };
+
+        // A submission that created no task leaves the key free for the retry
+        let first = task_id(router.clone().oneshot(submit()).await.unwrap()).await;
+        assert_eq!(first, "");
+
+        let config = crate::processing::ProcessingConfig { background: true, ..Default::default() };
+        let (queue, _receiver) = crate::processing::queue(&config, agent.metrics().clone());
+        let agent = Arc::new(PurchaseOrderAgent::new().with_submission_queue(queue));
+        let router = create_router(agent.clone());
+        let first = task_id(router.clone().oneshot(submit()).await.unwrap()).await;
+        let second = task_id(router.clone().oneshot(submit()).await.unwrap()).await;
+        assert!(!first.is_empty());
+        assert_eq!(first, second);
+        assert_eq!(agent.task_store().stats().unwrap().total_tasks, 1);
+
+        let changed = router.clone().oneshot(submit_with("[]")).await.unwrap();
+        assert_eq!(changed.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn test_task_result_is_returned_at_the_requested_version() {
+        let agent = Arc::new(PurchaseOrderAgent::new());
+        let task = agent.send_task(crate::testing::message(&crate::testing::valid_order())).await.unwrap();
+        let router = create_router(agent);
+        let get = |query: &str| Request::get(format!("/agent/task/{}{}", task.id, query)).body(Body::empty()).unwrap();
+
+        let response = router.clone().oneshot(get("")).await.unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let result = serde_json::from_slice::<TaskResponse>(&body).unwrap().detailed_result.unwrap();
+        assert_eq!(result["schema_version"], result_schema::CURRENT_VERSION);
+
+        let response = router.clone().oneshot(get("?result_version=1")).await.unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let result = serde_json::from_slice::<TaskResponse>(&body).unwrap().detailed_result.unwrap();
+        assert_eq!(result["schema_version"], 1);
+        assert_eq!(result.as_object().unwrap().len(), 13);
+
+        let response = router.oneshot(get("?result_version=99")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_submission_options_select_outputs_and_queue_a_callback() {
+        let agent = Arc::new(PurchaseOrderAgent::new());
+        let config: ServerConfig = toml::from_str(
+            r#"
+            [webhooks]
+            callback_hosts = ["*.example.com"]
+            "#,
+        )
+        .unwrap();
+        let router = crate::create_router_with_config(agent.clone(), &config);
+        let order = serde_json::to_value(crate::testing::valid_order()).unwrap();
+        let submit = |options: serde_json::Value| {
+            let body = serde_json::json!({ "message": { "role": "user", "parts": [{ "type": "data", "data": order }] }, "options": options });
+            Request::post("/agent/task").header(header::CONTENT_TYPE, "application/json").body(Body::from(body.to_string())).unwrap()
+        };
+
+        let options = serde_json::json!({ "version": 1, "output": ["json"], "locale": "de-DE", "callback_url": "https://erp.example.com/po-results" });
+        let response = router.clone().oneshot(submit(options)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let task = serde_json::from_slice::<TaskResponse>(&body).unwrap();
+        let names: Vec<_> = task.artifacts.unwrap().into_iter().filter_map(|artifact| artifact.name).collect();
+        assert_eq!(names, ["PO-1.json"]);
+        assert_eq!(agent.task_store().get(&task.task_id).unwrap().unwrap().locale.as_deref(), Some("de-DE"));
+
+        let deliveries = agent.webhook_outbox().for_task(&task.task_id).unwrap();
+        assert_eq!(deliveries.len(), 1);
+        assert_eq!(deliveries[0].endpoint, crate::webhooks::CALLBACK_ENDPOINT);
+        assert_eq!(deliveries[0].url, "https://erp.example.com/po-results");
+        assert_eq!(deliveries[0].payload["state"], "completed");
+        assert_eq!(deliveries[0].payload["locale"], "de-DE");
+
+        let rejected = [
+            serde_json::json!({ "callback_url": "http://169.254.169.254/latest" }),
+            serde_json::json!({ "version": 2 }),
+            serde_json::json!({ "dry_run": true, "callback_url": "https://erp.example.com/po-results" }),
+        ];
+        for options in rejected {
+            let response = router.clone().oneshot(submit(options)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        }
+        let response = router.oneshot(submit(serde_json::json!({ "outputs": ["csv"] }))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(agent.task_store().stats().unwrap().total_tasks, 1);
+    }
+}
@@ -0,0 +1,200 @@
+//! Context carried between the messages of a session
+//!
+//! Tasks sent with the same `session_id` form a conversation. A follow-up can
+//! then say only what changes: a Data part of `{"purchaseOrderDelta": {...}}`
+//! is resolved against the latest purchase order of the session, or the one
+//! processed by the task named in `basedOn`, into a complete order before it
+//! is stored and processed. The delta is a JSON merge patch of the order, except
+//! that `items` may be an object keyed by item code, patching, removing (`null`)
+//! or adding lines instead of replacing them all. The resolved order is
+//! normalized, so amounts follow from the new quantities and prices.
+
+use a2a::{Message, Part, TaskState};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::error::Error;
+
+use crate::agent::{PurchaseOrder, PurchaseOrderAgent};
+use crate::ingest::WRAPPER_KEY;
+use crate::resubmit::merge_patch;
+use crate::store::{StoreResult, TaskRecord};
+
+/// Key of a Data part holding changes to an earlier purchase order
+pub const DELTA_KEY: &str = "purchaseOrderDelta";
+
+/// Key, next to a delta, of the task whose purchase order it changes
+pub const BASED_ON_KEY: &str = "basedOn";
+
+/// One task of a session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionTask {
+    pub task_id: String,
+    pub state: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skill_id: Option<String>,
+    /// Number of the purchase order the task processed, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub po_number: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Response of `GET /agent/sessions/{session_id}`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionContext {
+    pub session_id: String,
+    /// Oldest first
+    pub tasks: Vec<SessionTask>,
+}
+
+/// Tasks of a session, oldest first
+fn records(agent: &PurchaseOrderAgent, session_id: &str) -> StoreResult<Vec<TaskRecord>> {
+    let mut records: Vec<TaskRecord> = agent
+        .task_store()
+        .list()?
+        .into_iter()
+        .filter(|record| record.task.session_id.as_deref() == Some(session_id))
+        .collect();
+    records.sort_by_key(|record| record.created_at);
+    Ok(records)
+}
+
+/// The tasks of a session, or `None` when it has none
+pub fn context(agent: &PurchaseOrderAgent, session_id: &str) -> StoreResult<Option<SessionContext>> {
+    let tasks: Vec<SessionTask> = records(agent, session_id)?
+        .into_iter()
+        .map(|record| SessionTask {
+            po_number: agent.processed_purchase_order(&record).ok().map(|po| po.po_number),
+            task_id: record.task.id.clone(),
+            state: record.state_name().to_string(),
+            skill_id: record.skill_id.clone(),
+            created_at: record.created_at,
+        })
+        .collect();
+    Ok((!tasks.is_empty()).then(|| SessionContext { session_id: session_id.to_string(), tasks }))
+}
+
+/// The purchase order a delta changes
+fn base_order(agent: &PurchaseOrderAgent, session_id: Option<&str>, based_on: Option<&str>) -> Result<PurchaseOrder, Box<dyn Error>> {
+    if let Some(task_id) = based_on {
+        let record = agent.task_store().get(task_id)?.ok_or_else(|| format!("Task {} in `{}` not found", task_id, BASED_ON_KEY))?;
+        if let Some(session_id) = session_id
+            && record.task.session_id.as_deref() != Some(session_id)
+        {
+            return Err(format!("Task {} in `{}` is not part of session {}", task_id, BASED_ON_KEY, session_id).into());
+        }
+        return agent
+            .processed_purchase_order(&record)
+            .map_err(|e| format!("Task {} in `{}` has no purchase order: {}", task_id, BASED_ON_KEY, e).into());
+    }
+    let Some(session_id) = session_id else {
+        return Err(format!("A `{}` needs a session_id or `{}`", DELTA_KEY, BASED_ON_KEY).into());
+    };
+    records(agent, session_id)?
+        .iter()
+        .rev()
+        .filter(|record| !matches!(record.task.status.state, TaskState::InputRequired))
+        .find_map(|record| agent.processed_purchase_order(record).ok())
+        .ok_or_else(|| format!("Session {} has no earlier purchase order for `{}`", session_id, DELTA_KEY).into())
+}
+
+/// Apply a delta to an order's JSON, patching lines by item code when `items` is an object
+fn apply(po: &mut Value, delta: &Value) -> Result<(), String> {
+    let Value::Object(delta) = delta else {
+        return Err(format!("`{}` must be a JSON object", DELTA_KEY));
+    };
+    let mut rest = delta.clone();
+    if let Some(Value::Object(lines)) = delta.get("items") {
+        rest.remove("items");
+        let items = po.get_mut("items").and_then(Value::as_array_mut).ok_or("the earlier order has no items")?;
+        for (code, patch) in lines {
+            let position = items.iter().position(|item| {
+                item.get("itemCode").and_then(Value::as_str).is_some_and(|item_code| item_code.trim().eq_ignore_ascii_case(code.trim()))
+            });
+            match (position, patch.is_null()) {
+                (Some(index), true) => {
+                    items.remove(index);
+                }
+                (Some(index), false) => merge_patch(&mut items[index], patch),
+                (None, true) => return Err(format!("the earlier order has no item {}", code)),
+                (None, false) => {
+                    let mut item = serde_json::json!({ "itemCode": code, "lineTotal": 0.0 });
+                    merge_patch(&mut item, patch);
+                    items.push(item);
+                }
+            }
+        }
+    }
+    merge_patch(po, &Value::Object(rest));
+    Ok(())
+}
+
+/// `message` with any purchase order delta resolved into the complete order it describes
+///
+/// Messages without a delta are returned as they are.
+pub fn resolve(agent: &PurchaseOrderAgent, message: Message, session_id: Option<&str>) -> Result<Message, Box<dyn Error>> {
+    let Some((index, delta, based_on)) = message.parts.iter().enumerate().find_map(|(index, part)| match part {
+        Part::Data { data } => data
+            .get(DELTA_KEY)
+            .map(|delta| (index, delta.clone(), data.get(BASED_ON_KEY).and_then(Value::as_str).map(str::to_string))),
+        _ => None,
+    }) else {
+        return Ok(message);
+    };
+
+    let base = base_order(agent, session_id, based_on.as_deref())?;
+    let mut po = serde_json::to_value(&base)?;
+    apply(&mut po, &delta).map_err(|e| format!("The purchase order delta does not apply: {}", e))?;
+    let po = PurchaseOrder::deserialize(&po).map_err(|e| format!("The purchase order delta does not apply: {}", e))?;
+
+    // Options next to the delta, such as skill_id, stay with the resolved order
+    let Part::Data { data } = &message.parts[index] else { unreachable!("the delta is in a Data part") };
+    let mut resolved = data.as_object().cloned().unwrap_or_default();
+    resolved.remove(DELTA_KEY);
+    resolved.insert(WRAPPER_KEY.to_string(), serde_json::to_value(&po)?);
+    resolved.entry("normalize").or_insert(Value::Bool(true));
+    let mut message = message;
+    message.parts[index] = Part::Data { data: Value::Object(resolved) };
+    Ok(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::{ProcessingResult, TaskOptions};
+    use a2a::A2AProtocol;
+    use serde_json::json;
+
+    fn delta(delta: Value) -> Message {
+        Message { role: "user".to_string(), parts: vec![Part::Data { data: delta }] }
+    }
+
+    #[tokio::test]
+    async fn test_deltas_resolve_against_the_session() {
+        let agent = PurchaseOrderAgent::new();
+        let options = TaskOptions { session_id: Some("s-1".to_string()), ..TaskOptions::default() };
+        let first = agent.send_task_with_options(crate::testing::message(&crate::testing::valid_order()), &options).await.unwrap();
+
+        let follow_up = json!({ DELTA_KEY: { "poNumber": "PO-2", "items": { "p-1": { "quantity": 20 }, "T-2": null } } });
+        let second = agent.send_task_with_options(delta(follow_up), &options).await.unwrap();
+        let result = ProcessingResult::from_status(&second.status).unwrap();
+        assert_eq!(result.po_number, "PO-2");
+        assert_eq!((result.summary.total_items, result.summary.total_quantity, result.sub_total), (1, 20, 100.0));
+
+        // The request stored is the resolved order, and later deltas build on it unless `basedOn` says otherwise
+        let record = agent.task_store().get(&second.id).unwrap().unwrap();
+        assert!(record.request.parts.iter().all(|part| matches!(part, Part::Data { data } if data.get(DELTA_KEY).is_none())));
+        let third = agent.send_task_with_options(delta(json!({ DELTA_KEY: { "poNumber": "PO-3" }, BASED_ON_KEY: first.id })), &options).await.unwrap();
+        assert_eq!(ProcessingResult::from_status(&third.status).unwrap().summary.total_items, 2);
+
+        let context = context(&agent, "s-1").unwrap().unwrap();
+        let numbers: Vec<_> = context.tasks.iter().map(|task| task.po_number.clone().unwrap()).collect();
+        assert_eq!(numbers, ["PO-1", "PO-2", "PO-3"].map(str::to_string));
+
+        let err = agent.send_task(delta(json!({ DELTA_KEY: { "notes": "rush" } }))).await.unwrap_err();
+        assert!(err.to_string().contains("needs a session_id"), "{}", err);
+        let other = TaskOptions { session_id: Some("s-2".to_string()), ..TaskOptions::default() };
+        let err = agent.send_task_with_options(delta(json!({ DELTA_KEY: {}, BASED_ON_KEY: first.id })), &other).await.unwrap_err();
+        assert!(err.to_string().contains("not part of session s-2"), "{}", err);
+    }
+}
@@ -0,0 +1,211 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// How a failed validation check is reported
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Fails validation, so the task ends Failed
+    Error,
+    /// Reported without failing validation
+    Warning,
+    /// Not reported at all
+    Ignore,
+}
+
+/// A purchase order, expense report or supplier onboarding check whose severity can be configured
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Check {
+    MissingSupplierName,
+    MissingPoNumber,
+    MissingCreatedBy,
+    MissingBuyerDepartment,
+    /// Unrecognised or missing supplier or ship-to country
+    AddressCountry,
+    /// Missing or invalid state/province
+    AddressState,
+    /// Postal code not in its country's format
+    AddressPostalCode,
+    /// Ship-to address without a first line or city
+    ShipToIncomplete,
+    NoItems,
+    ItemMissingCode,
+    ItemMissingDescription,
+    ItemZeroQuantity,
+    ItemInvalidPrice,
+    /// Negative amounts on an order that is not an accepted return, or a zero grand total
+    NegativeAmount,
+    /// Quantity below the catalog item's minimum order quantity or not a whole number of packs
+    OrderQuantity,
+    /// A line repeating an earlier line's item code, description and unit price
+    DuplicateLine,
+    LineTotalMismatch,
+    SubtotalMismatch,
+    TaxMismatch,
+    GrandTotalMismatch,
+    HighValue,
+    UnusualTaxRate,
+    /// Blanket order terms and release limits
+    Blanket,
+    /// Malformed or missing supplier tax ID
+    TaxId,
+    DeniedParty,
+    /// Buyer department not in the directory
+    UnauthorizedDepartment,
+    /// Requester not in the directory, or no longer active
+    UnknownRequester,
+    /// Requester of another department than the buyer department
+    RequesterDepartment,
+    /// Expense report or line without a required field
+    ExpenseMissingField,
+    /// Expense line with a zero or negative amount
+    ExpenseInvalidAmount,
+    /// Expense line above its category limit
+    ExpenseOverLimit,
+    /// Expense line at or above the receipt threshold without a receipt
+    ReceiptRequired,
+    /// Mileage line not claimed at the mileage rate
+    MileageAmount,
+    ExpenseTotalMismatch,
+    /// New supplier without its address line 1 or city
+    SupplierIncomplete,
+    /// Missing or malformed bank account of a new supplier
+    BankDetails,
+    /// New supplier's bank account in another country than the supplier
+    BankCountry,
+    /// New supplier with the name, tax ID or bank account of an existing one
+    DuplicateSupplier,
+    /// New supplier with a similar name or the address of an existing one
+    PossibleDuplicateSupplier,
+    /// Unit price above the trailing average of the item's earlier orders
+    PriceIncrease,
+    /// Error reported by a validation plugin, or a plugin that failed to run
+    PluginRule,
+    /// Warning reported by a validation plugin
+    PluginWarning,
+}
+
+impl Check {
+    /// Severity used when the policy does not override it
+    pub fn default_severity(self) -> Severity {
+        match self {
+            Check::AddressPostalCode
+            | Check::DuplicateLine
+            | Check::OrderQuantity
+            | Check::LineTotalMismatch
+            | Check::SubtotalMismatch
+            | Check::TaxMismatch
+            | Check::GrandTotalMismatch
+            | Check::HighValue
+            | Check::UnusualTaxRate
+            | Check::TaxId
+            | Check::UnauthorizedDepartment
+            | Check::UnknownRequester
+            | Check::RequesterDepartment
+            | Check::ExpenseOverLimit
+            | Check::MileageAmount
+            | Check::ExpenseTotalMismatch
+            | Check::BankCountry
+            | Check::PossibleDuplicateSupplier
+            | Check::PriceIncrease
+            | Check::PluginWarning => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+}
+
+/// Severity overrides per validation check, e.g. `subtotal_mismatch = "error"`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(transparent)]
+pub struct SeverityPolicy {
+    pub overrides: HashMap<Check, Severity>,
+}
+
+impl SeverityPolicy {
+    pub fn severity(&self, check: Check) -> Severity {
+        self.overrides.get(&check).copied().unwrap_or_else(|| check.default_severity())
+    }
+}
+
+/// A reported error or warning and the check it comes from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Finding {
+    pub check: Check,
+    pub severity: Severity,
+    pub message: String,
+}
+
+/// Validation errors and warnings collected under a severity policy
+pub struct Findings<'a> {
+    policy: &'a SeverityPolicy,
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+    failed: Vec<Check>,
+    reported: Vec<Finding>,
+}
+
+impl<'a> Findings<'a> {
+    pub fn new(policy: &'a SeverityPolicy) -> Self {
+        Self { policy, errors: Vec::new(), warnings: Vec::new(), failed: Vec::new(), reported: Vec::new() }
+    }
+
+    /// Report a failed check as an error or warning, or drop it, as the policy says
+    pub fn push(&mut self, check: Check, message: impl Into<String>) {
+        self.failed.push(check);
+        let severity = self.policy.severity(check);
+        let message = message.into();
+        match severity {
+            Severity::Error => self.errors.push(message.clone()),
+            Severity::Warning => self.warnings.push(message.clone()),
+            Severity::Ignore => return,
+        }
+        self.reported.push(Finding { check, severity, message });
+    }
+
+    /// Every failed check in the order found, once per finding, ignored ones included
+    pub fn failed(&self) -> &[Check] {
+        &self.failed
+    }
+
+    /// The errors and warnings with their checks, in the order they were found
+    pub fn reported(&self) -> &[Finding] {
+        &self.reported
+    }
+
+    /// The errors and warnings, in the order they were found
+    pub fn into_parts(self) -> (Vec<String>, Vec<String>) {
+        (self.errors, self.warnings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::PurchaseOrderAgent;
+
+    #[test]
+    fn test_policy_overrides_default_severities() {
+        let policy: SeverityPolicy =
+            toml::from_str("subtotal_mismatch = \"error\"\nunauthorized_department = \"ignore\"").unwrap();
+        assert_eq!(policy.severity(Check::SubtotalMismatch), Severity::Error);
+        assert_eq!(policy.severity(Check::TaxMismatch), Severity::Warning);
+        assert!(toml::from_str::<SeverityPolicy>("no_such_check = \"error\"").is_err());
+
+        // A 10.00 order from Oregon, which has no sales tax, stated as 12.00
+        let mut po = crate::testing::order_of(0.0, &[("P-1", "Paper", 1, 10.0)]);
+        (po.supplier_state, po.supplier_postal_code) = ("OR".to_string(), "97477".to_string());
+        po.buyer_department = "Skunkworks".to_string();
+        (po.sub_total, po.grand_total) = (12.0, 12.0);
+        let message = crate::testing::message(&po);
+
+        let report = PurchaseOrderAgent::new().validate(&message).unwrap();
+        assert!(report.valid);
+        assert_eq!(report.warnings.len(), 2);
+
+        let report = PurchaseOrderAgent::new().with_severity_policy(policy).validate(&message).unwrap();
+        assert!(!report.valid);
+        assert_eq!(report.validation_errors, ["Subtotal mismatch: expected 10.00, got 12.00"]);
+        assert!(report.warnings.is_empty());
+    }
+}
@@ -0,0 +1,303 @@
+use axum::{
+    body::{to_bytes, Body},
+    extract::{Request, State},
+    http::{HeaderMap, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use dashmap::mapref::entry::Entry;
+use dashmap::DashMap;
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::Sha256;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use tracing::warn;
+
+use crate::server::AppState;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Header carrying the unix timestamp (seconds) the request was signed at
+pub const TIMESTAMP_HEADER: &str = "x-signature-timestamp";
+
+/// Header carrying `sha256=<hex HMAC of "{timestamp}.{body}">`
+pub const SIGNATURE_HEADER: &str = "x-signature";
+
+/// Signatures whose timestamps went stale are swept at most this often, in seconds
+const PRUNE_INTERVAL: i64 = 60;
+
+/// Inbound request signature verification settings
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct SigningConfig {
+    /// Shared secret; signature verification is enabled when set
+    pub secret: Option<String>,
+    /// Maximum age (and clock skew) accepted for a signature timestamp
+    pub max_skew_seconds: u64,
+    /// Largest request body that will be buffered for verification; `[server] max_request_bytes` when unset
+    pub max_body_bytes: Option<usize>,
+}
+
+impl Default for SigningConfig {
+    fn default() -> Self {
+        Self {
+            secret: None,
+            max_skew_seconds: 300,
+            max_body_bytes: None,
+        }
+    }
+}
+
+impl SigningConfig {
+    /// Largest request body buffered for verification, given the server's own limit
+    pub fn body_limit(&self, max_request_bytes: usize) -> usize {
+        self.max_body_bytes.unwrap_or(max_request_bytes)
+    }
+}
+
+/// Reasons a signed request can be rejected
+#[derive(Debug, PartialEq, Eq)]
+pub enum SignatureError {
+    MissingHeaders,
+    MalformedTimestamp,
+    Stale,
+    Mismatch,
+    Replayed,
+}
+
+impl fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignatureError::MissingHeaders => write!(
+                f,
+                "Request must carry {} and {} headers",
+                TIMESTAMP_HEADER, SIGNATURE_HEADER
+            ),
+            SignatureError::MalformedTimestamp => write!(f, "Signature timestamp is not a unix timestamp"),
+            SignatureError::Stale => write!(f, "Signature timestamp is outside the accepted window"),
+            SignatureError::Mismatch => write!(f, "Signature does not match request body"),
+            SignatureError::Replayed => write!(f, "Signature was already used"),
+        }
+    }
+}
+
+impl std::error::Error for SignatureError {}
+
+impl IntoResponse for SignatureError {
+    fn into_response(self) -> Response {
+        (
+            StatusCode::UNAUTHORIZED,
+            axum::Json(serde_json::json!({ "error": self.to_string() })),
+        )
+            .into_response()
+    }
+}
+
+/// Signatures accepted while their timestamps are within the window
+///
+/// A signature is accepted once; a captured request sent again before its
+/// timestamp goes stale is rejected as a replay.
+#[derive(Default)]
+pub struct SeenSignatures {
+    /// Signature → unix time its timestamp goes stale
+    seen: DashMap<Vec<u8>, i64>,
+    last_pruned: Mutex<i64>,
+}
+
+impl SeenSignatures {
+    /// Record `signature` as used until `expires_at`; false when it already was
+    pub fn first_use(&self, signature: &[u8], expires_at: i64, now: i64) -> bool {
+        self.prune(now);
+        match self.seen.entry(signature.to_vec()) {
+            Entry::Occupied(entry) if *entry.get() >= now => false,
+            Entry::Occupied(mut entry) => {
+                entry.insert(expires_at);
+                true
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(expires_at);
+                true
+            }
+        }
+    }
+
+    fn prune(&self, now: i64) {
+        let mut last_pruned = self.last_pruned.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if now - *last_pruned < PRUNE_INTERVAL {
+            return;
+        }
+        *last_pruned = now;
+        self.seen.retain(|_, expires_at| *expires_at >= now);
+    }
+}
+
+/// Compute the signature header value for a request body
+///
+/// Partner agents use the same function (or its equivalent) to sign outbound
+/// requests: `sha256=` followed by the hex HMAC-SHA256 of `"{timestamp}.{body}"`.
+pub fn sign(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    format!("sha256={}", hex::encode(mac(secret, timestamp, body).finalize().into_bytes()))
+}
+
+fn mac(secret: &str, timestamp: i64, body: &[u8]) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    mac
+}
+
+/// Verify the signature headers of a request against its body, accepting each signature once
+pub fn verify(
+    config: &SigningConfig,
+    secret: &str,
+    seen: &SeenSignatures,
+    headers: &HeaderMap,
+    body: &[u8],
+    now: i64,
+) -> Result<(), SignatureError> {
+    let timestamp = headers.get(TIMESTAMP_HEADER).and_then(|v| v.to_str().ok());
+    let signature = headers.get(SIGNATURE_HEADER).and_then(|v| v.to_str().ok());
+    let (Some(timestamp), Some(signature)) = (timestamp, signature) else {
+        return Err(SignatureError::MissingHeaders);
+    };
+
+    let timestamp: i64 = timestamp.trim().parse().map_err(|_| SignatureError::MalformedTimestamp)?;
+    if now.abs_diff(timestamp) > config.max_skew_seconds {
+        return Err(SignatureError::Stale);
+    }
+
+    let provided = signature
+        .trim()
+        .strip_prefix("sha256=")
+        .and_then(|hex_sig| hex::decode(hex_sig).ok())
+        .ok_or(SignatureError::Mismatch)?;
+
+    mac(secret, timestamp, body)
+        .verify_slice(&provided)
+        .map_err(|_| SignatureError::Mismatch)?;
+
+    // Past this the timestamp is stale, so the signature need not be remembered longer
+    let expires_at = timestamp.saturating_add(config.max_skew_seconds as i64);
+    if !seen.first_use(&provided, expires_at, now) {
+        return Err(SignatureError::Replayed);
+    }
+    Ok(())
+}
+
+/// Middleware rejecting unsigned or stale requests when a signing secret is configured
+pub async fn require_signature(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(secret) = state.signing.secret.as_deref() else {
+        return next.run(request).await;
+    };
+
+    let (parts, body) = request.into_parts();
+    let bytes = match to_bytes(body, state.signing.body_limit(state.max_request_bytes)).await {
+        Ok(bytes) => bytes,
+        Err(_) => return StatusCode::PAYLOAD_TOO_LARGE.into_response(),
+    };
+
+    if let Err(e) = verify(&state.signing, secret, &state.signatures, &parts.headers, &bytes, chrono::Utc::now().timestamp()) {
+        warn!(reason = %e, "request signature rejected");
+        return e.into_response();
+    }
+
+    next.run(Request::from_parts(parts, Body::from(bytes))).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    const SECRET: &str = "partner-secret";
+    const BODY: &[u8] = br#"{"message":{"role":"user","parts":[]}}"#;
+
+    fn headers(timestamp: i64, signature: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(TIMESTAMP_HEADER, HeaderValue::from_str(&timestamp.to_string()).unwrap());
+        headers.insert(SIGNATURE_HEADER, HeaderValue::from_str(signature).unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_valid_signature_is_accepted() {
+        let config = SigningConfig::default();
+        let now = 1_700_000_000;
+        let signature = sign(SECRET, now, BODY);
+
+        assert!(verify(&config, SECRET, &SeenSignatures::default(), &headers(now, &signature), BODY, now + 10).is_ok());
+    }
+
+    #[test]
+    fn test_tampered_body_is_rejected() {
+        let config = SigningConfig::default();
+        let now = 1_700_000_000;
+        let signature = sign(SECRET, now, BODY);
+
+        let result = verify(&config, SECRET, &SeenSignatures::default(), &headers(now, &signature), b"{}", now);
+        assert_eq!(result, Err(SignatureError::Mismatch));
+    }
+
+    #[test]
+    fn test_stale_signature_is_rejected() {
+        let config = SigningConfig::default();
+        let signed_at = 1_700_000_000;
+        let signature = sign(SECRET, signed_at, BODY);
+
+        let result = verify(&config, SECRET, &SeenSignatures::default(), &headers(signed_at, &signature), BODY, signed_at + 301);
+        assert_eq!(result, Err(SignatureError::Stale));
+    }
+
+    #[test]
+    fn test_missing_headers_are_rejected() {
+        let config = SigningConfig::default();
+        let result = verify(&config, SECRET, &SeenSignatures::default(), &HeaderMap::new(), BODY, 0);
+        assert_eq!(result, Err(SignatureError::MissingHeaders));
+    }
+
+    #[test]
+    fn test_replayed_signature_is_rejected_until_it_goes_stale() {
+        let config = SigningConfig::default();
+        let seen = SeenSignatures::default();
+        let now = 1_700_000_000;
+        let signed = headers(now, &sign(SECRET, now, BODY));
+
+        assert!(verify(&config, SECRET, &seen, &signed, BODY, now).is_ok());
+        assert_eq!(verify(&config, SECRET, &seen, &signed, BODY, now + 200), Err(SignatureError::Replayed));
+        assert_eq!(verify(&config, SECRET, &seen, &signed, BODY, now + 301), Err(SignatureError::Stale));
+        // A new signature of the same body is another request
+        let resigned = headers(now + 1, &sign(SECRET, now + 1, BODY));
+        assert!(verify(&config, SECRET, &seen, &resigned, BODY, now + 1).is_ok());
+        // Forged signatures are not remembered, so they cannot block the real one
+        let mut forged = headers(now + 2, &sign("another-secret", now + 2, BODY));
+        assert_eq!(verify(&config, SECRET, &seen, &forged, BODY, now + 2), Err(SignatureError::Mismatch));
+        forged.insert(SIGNATURE_HEADER, HeaderValue::from_str(&sign(SECRET, now + 2, BODY)).unwrap());
+        assert!(verify(&config, SECRET, &seen, &forged, BODY, now + 2).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_signed_bodies_are_buffered_up_to_the_request_limit() {
+        use tower::ServiceExt;
+
+        let mut config = crate::config::ServerConfig::default();
+        config.signing.secret = Some(SECRET.to_string());
+        let router = crate::create_router_with_config(Arc::new(crate::PurchaseOrderAgent::new()), &config);
+        // Trailing whitespace takes the body past the 10 MiB an older default buffered
+        let mut body = serde_json::to_vec(&serde_json::json!({ "message": crate::testing::message(&crate::testing::valid_order()) })).unwrap();
+        body.resize(body.len() + 11 * 1024 * 1024, b' ');
+        let now = chrono::Utc::now().timestamp();
+        let request = Request::post("/agent/task")
+            .header(axum::http::header::CONTENT_TYPE, "application/json")
+            .header(TIMESTAMP_HEADER, now)
+            .header(SIGNATURE_HEADER, sign(SECRET, now, &body))
+            .body(Body::from(body))
+            .unwrap();
+        assert_eq!(router.oneshot(request).await.unwrap().status(), StatusCode::OK);
+    }
+}
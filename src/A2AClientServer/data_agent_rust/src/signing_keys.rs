@@ -0,0 +1,244 @@
+//! Keys signing outbound webhook payloads
+//!
+//! Webhook endpoints without a secret of their own are signed with every
+//! active key of the key store, so receivers can rotate without downtime. A
+//! rotation adds a new key and keeps the previous ones active for an overlap
+//! period: receivers switch to the new key at their own pace, then the old
+//! ones expire. Each delivery names the key behind `x-signature` in
+//! `x-signature-key-id` and lists the signature of every active key in
+//! `x-signatures`.
+
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::Duration;
+
+use crate::signing;
+use crate::store::{StoreError, StoreResult};
+
+/// Header naming the key that produced `x-signature`
+pub const KEY_ID_HEADER: &str = "x-signature-key-id";
+
+/// Header listing `<key id>=<signature>` for every active key, newest first
+pub const SIGNATURES_HEADER: &str = "x-signatures";
+
+/// A key configured in `[[webhooks.signing_keys]]`
+#[derive(Debug, Clone, Deserialize)]
+pub struct SigningKeyConfig {
+    pub id: String,
+    pub secret: String,
+}
+
+/// A key signing outbound payloads
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SigningKey {
+    pub id: String,
+    /// Never listed; shown once, when the key is created by a rotation
+    #[serde(skip_serializing)]
+    pub secret: String,
+    pub created_at: DateTime<Utc>,
+    /// When the key stops signing; unset while it is the newest key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl SigningKey {
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_none_or(|expires_at| expires_at > now)
+    }
+}
+
+/// Body of `POST /admin/signing-keys/rotate`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct RotateRequest {
+    /// ID of the new key; generated when unset
+    pub id: Option<String>,
+    /// Secret of the new key; generated when unset
+    pub secret: Option<String>,
+    /// How long the previous keys keep signing; `[webhooks] rotation_overlap` when unset
+    #[serde(with = "humantime_serde")]
+    pub overlap: Option<Duration>,
+}
+
+/// Storage of signing keys
+pub trait SigningKeyStore: Send + Sync {
+    /// Insert or replace the key with the same ID
+    fn put(&self, key: SigningKey) -> StoreResult<()>;
+
+    /// Every key, expired ones included, oldest first
+    fn list(&self) -> StoreResult<Vec<SigningKey>>;
+}
+
+/// In-memory signing key store used by default
+#[derive(Default)]
+pub struct MemorySigningKeyStore {
+    keys: DashMap<String, SigningKey>,
+}
+
+impl MemorySigningKeyStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Store seeded with the configured keys, the last of which is the newest
+    pub fn from_config(keys: &[SigningKeyConfig]) -> Self {
+        let store = Self::new();
+        let now = Utc::now();
+        for (index, key) in keys.iter().enumerate() {
+            store.keys.insert(
+                key.id.clone(),
+                SigningKey {
+                    id: key.id.clone(),
+                    secret: key.secret.clone(),
+                    created_at: now + chrono::Duration::milliseconds(index as i64),
+                    expires_at: None,
+                },
+            );
+        }
+        store
+    }
+}
+
+impl SigningKeyStore for MemorySigningKeyStore {
+    fn put(&self, key: SigningKey) -> StoreResult<()> {
+        self.keys.insert(key.id.clone(), key);
+        Ok(())
+    }
+
+    fn list(&self) -> StoreResult<Vec<SigningKey>> {
+        let mut keys: Vec<_> = self.keys.iter().map(|key| key.value().clone()).collect();
+        keys.sort_by_key(|key| key.created_at);
+        Ok(keys)
+    }
+}
+
+/// Why keys could not be rotated or retired
+#[derive(Debug)]
+pub enum KeyError {
+    NotFound(String),
+    /// The new key's ID is taken or its secret is empty
+    Invalid(String),
+    Store(StoreError),
+}
+
+impl fmt::Display for KeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyError::NotFound(message) | KeyError::Invalid(message) => f.write_str(message),
+            KeyError::Store(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for KeyError {}
+
+impl From<StoreError> for KeyError {
+    fn from(e: StoreError) -> Self {
+        KeyError::Store(e)
+    }
+}
+
+/// Keys signing at `now`, newest first
+pub fn active(store: &dyn SigningKeyStore, now: DateTime<Utc>) -> StoreResult<Vec<SigningKey>> {
+    let mut keys: Vec<_> = store.list()?.into_iter().filter(|key| key.is_active(now)).collect();
+    keys.reverse();
+    Ok(keys)
+}
+
+/// Add a new key and let the active ones expire after the overlap
+pub fn rotate(
+    store: &dyn SigningKeyStore,
+    request: RotateRequest,
+    default_overlap: Duration,
+    now: DateTime<Utc>,
+) -> Result<SigningKey, KeyError> {
+    let keys = store.list()?;
+    let id = request.id.unwrap_or_else(|| format!("key-{}", now.format("%Y%m%d%H%M%S")));
+    if keys.iter().any(|key| key.id == id) {
+        return Err(KeyError::Invalid(format!("Signing key {} already exists", id)));
+    }
+    let secret = match request.secret {
+        Some(secret) if secret.trim().is_empty() => return Err(KeyError::Invalid("Signing key secret is empty".to_string())),
+        Some(secret) => secret,
+        None => hex::encode(rand::thread_rng().r#gen::<[u8; 32]>()),
+    };
+
+    // The new key sorts last even when created within the clock tick of the newest one
+    let created_at = keys.last().map_or(now, |newest| now.max(newest.created_at + chrono::Duration::milliseconds(1)));
+    let key = SigningKey { id, secret, created_at, expires_at: None };
+
+    let expires_at = now + chrono::Duration::from_std(request.overlap.unwrap_or(default_overlap)).unwrap_or_default();
+    for mut key in keys.into_iter().filter(|key| key.is_active(now)) {
+        key.expires_at = Some(key.expires_at.map_or(expires_at, |current| current.min(expires_at)));
+        store.put(key)?;
+    }
+    store.put(key.clone())?;
+    Ok(key)
+}
+
+/// Stop a key signing at once, e.g. when its secret leaked
+pub fn retire(store: &dyn SigningKeyStore, id: &str, now: DateTime<Utc>) -> Result<SigningKey, KeyError> {
+    let mut key = store
+        .list()?
+        .into_iter()
+        .find(|key| key.id == id)
+        .ok_or_else(|| KeyError::NotFound(format!("Signing key {} not found", id)))?;
+    key.expires_at = Some(key.expires_at.map_or(now, |current| current.min(now)));
+    store.put(key.clone())?;
+    Ok(key)
+}
+
+/// Signature headers of a payload signed with `keys`, newest first
+///
+/// Empty when there are no keys.
+pub fn signature_headers(keys: &[SigningKey], timestamp: i64, body: &[u8]) -> Vec<(&'static str, String)> {
+    let Some(newest) = keys.first() else {
+        return Vec::new();
+    };
+    let signatures: Vec<_> = keys.iter().map(|key| (key.id.as_str(), signing::sign(&key.secret, timestamp, body))).collect();
+    vec![
+        (signing::TIMESTAMP_HEADER, timestamp.to_string()),
+        (signing::SIGNATURE_HEADER, signatures[0].1.clone()),
+        (KEY_ID_HEADER, newest.id.clone()),
+        (
+            SIGNATURES_HEADER,
+            signatures.iter().map(|(id, signature)| format!("{}={}", id, signature)).collect::<Vec<_>>().join(", "),
+        ),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotation_keeps_previous_keys_during_overlap() {
+        let store = MemorySigningKeyStore::from_config(&[SigningKeyConfig { id: "k1".to_string(), secret: "first".to_string() }]);
+        let now = Utc::now();
+        let overlap = Duration::from_secs(3600);
+
+        let request = RotateRequest { id: Some("k2".to_string()), ..RotateRequest::default() };
+        let k2 = rotate(&store, request, overlap, now).unwrap();
+        assert_eq!(k2.secret.len(), 64);
+        let ids = |at| active(&store, at).unwrap().into_iter().map(|key| key.id).collect::<Vec<_>>();
+        assert_eq!(ids(now), ["k2", "k1"]);
+        assert_eq!(ids(now + chrono::Duration::hours(2)), ["k2"]);
+
+        let headers = signature_headers(&active(&store, now).unwrap(), 1_700_000_000, b"{}");
+        let value = |name| headers.iter().find(|(header, _)| *header == name).unwrap().1.clone();
+        assert_eq!(value(KEY_ID_HEADER), "k2");
+        assert_eq!(value(signing::SIGNATURE_HEADER), signing::sign(&k2.secret, 1_700_000_000, b"{}"));
+        assert!(value(SIGNATURES_HEADER).ends_with(&format!("k1={}", signing::sign("first", 1_700_000_000, b"{}"))));
+
+        let duplicate = RotateRequest { id: Some("k1".to_string()), ..RotateRequest::default() };
+        assert!(matches!(rotate(&store, duplicate, overlap, now), Err(KeyError::Invalid(_))));
+        retire(&store, "k1", now).unwrap();
+        assert_eq!(ids(now), ["k2"]);
+        assert!(matches!(retire(&store, "k9", now), Err(KeyError::NotFound(_))));
+        let listed = serde_json::to_value(store.list().unwrap()).unwrap();
+        assert!(listed[0].get("secret").is_none());
+    }
+}
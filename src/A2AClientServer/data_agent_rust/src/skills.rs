@@ -0,0 +1,222 @@
+use a2a::{Message, Part, TaskState, TaskStatus};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::sync::Arc;
+
+use crate::agent::PurchaseOrderAgent;
+use crate::expense::{ExpenseConfig, ExpenseSkill};
+use crate::invoice::{InvoiceMatchConfig, InvoiceMatchSkill};
+use crate::invoice_agent::{InvoiceAgent, InvoiceProcessingConfig};
+use crate::onboarding::{OnboardingConfig, OnboardingSkill};
+use crate::parts;
+use crate::requisition::{RequisitionConfig, RequisitionSkill};
+use crate::tax_id::TaxIdFinding;
+
+/// Skill run when a request does not ask for one
+pub const PROCESSING_SKILL: &str = "purchase-order-processing";
+/// Validates a purchase order without determining its approval status
+pub const VALIDATION_SKILL: &str = "purchase-order-validation";
+/// Reports on a purchase order without validating it
+pub const REPORTING_SKILL: &str = "purchase-order-reporting";
+
+/// Handles tasks addressed to one of the agent's skills
+#[async_trait]
+pub trait SkillHandler: Send + Sync {
+    /// Skill ID, as advertised on the agent card
+    fn id(&self) -> &str;
+
+    /// Turn a request message into the task's status
+    async fn handle(&self, agent: &PurchaseOrderAgent, message: &Message) -> Result<TaskStatus, Box<dyn Error>>;
+}
+
+/// Routes tasks to skill handlers by skill ID
+pub struct SkillDispatcher {
+    handlers: BTreeMap<String, Arc<dyn SkillHandler>>,
+    default_skill: String,
+}
+
+impl SkillDispatcher {
+    /// Dispatcher with the processing, validation, reporting, invoice matching, invoice
+    /// processing, requisition conversion, expense report and supplier onboarding skills,
+    /// defaulting to processing
+    pub fn builtin() -> Self {
+        let mut dispatcher = Self {
+            handlers: BTreeMap::new(),
+            default_skill: PROCESSING_SKILL.to_string(),
+        };
+        dispatcher.register(Arc::new(ProcessingSkill));
+        dispatcher.register(Arc::new(ValidationSkill));
+        dispatcher.register(Arc::new(ReportingSkill));
+        dispatcher.register(Arc::new(InvoiceMatchSkill::new(InvoiceMatchConfig::default())));
+        dispatcher.register(Arc::new(InvoiceAgent::new(InvoiceProcessingConfig::default(), InvoiceMatchConfig::default())));
+        dispatcher.register(Arc::new(RequisitionSkill::new(RequisitionConfig::default())));
+        dispatcher.register(Arc::new(ExpenseSkill::new(ExpenseConfig::default())));
+        dispatcher.register(Arc::new(OnboardingSkill::new(OnboardingConfig::default())));
+        dispatcher
+    }
+
+    /// Add a handler, replacing any registered for the same skill ID
+    pub fn register(&mut self, handler: Arc<dyn SkillHandler>) {
+        self.handlers.insert(handler.id().to_string(), handler);
+    }
+
+    /// Remove the handler of `skill_id`; the default skill cannot be removed
+    pub fn unregister(&mut self, skill_id: &str) -> Result<(), Box<dyn Error>> {
+        if skill_id == self.default_skill {
+            return Err(format!("The default skill '{}' cannot be disabled", skill_id).into());
+        }
+        self.handlers
+            .remove(skill_id)
+            .map(|_| ())
+            .ok_or_else(|| format!("Unknown skill '{}'; available skills: {}", skill_id, self.ids().join(", ")).into())
+    }
+
+    /// Handler for `skill_id`, or the default skill when none is requested
+    pub fn resolve(&self, skill_id: Option<&str>) -> Result<Arc<dyn SkillHandler>, Box<dyn Error>> {
+        let id = skill_id.unwrap_or(&self.default_skill);
+        self.handlers.get(id).cloned().ok_or_else(|| {
+            format!("Unknown skill '{}'; available skills: {}", id, self.ids().join(", ")).into()
+        })
+    }
+
+    /// IDs of every registered skill, sorted
+    pub fn ids(&self) -> Vec<&str> {
+        self.handlers.keys().map(String::as_str).collect()
+    }
+}
+
+/// Skill a message asks for in a part's `skillId` metadata or a Data part's `skill_id` field
+///
+/// The field sits next to the purchase order, e.g.
+/// `{"skill_id": "purchase-order-validation", "purchaseOrder": {…}}`.
+pub fn skill_id(message: &Message) -> Option<String> {
+    parts::skill_id(message).or_else(|| {
+        message.parts.iter().find_map(|part| match part {
+            Part::Data { data } => data.get("skill_id").and_then(|id| id.as_str()).map(str::to_string),
+            _ => None,
+        })
+    })
+}
+
+/// Findings of the validation skill
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValidationReport {
+    pub po_number: String,
+    pub valid: bool,
+    pub validation_errors: Vec<String>,
+    pub warnings: Vec<String>,
+    /// Problems with the supplier tax ID, also reported as warnings
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tax_id_findings: Vec<TaxIdFinding>,
+}
+
+fn assistant_message(parts: Vec<Part>) -> Option<Message> {
+    Some(Message { role: "assistant".to_string(), parts })
+}
+
+/// Full processing: validation, approval status, CSV row and detailed result
+pub struct ProcessingSkill;
+
+#[async_trait]
+impl SkillHandler for ProcessingSkill {
+    fn id(&self) -> &str {
+        PROCESSING_SKILL
+    }
+
+    async fn handle(&self, agent: &PurchaseOrderAgent, message: &Message) -> Result<TaskStatus, Box<dyn Error>> {
+        agent.process_message(message).await
+    }
+}
+
+/// Validation findings only; fails the task when the order is invalid
+pub struct ValidationSkill;
+
+#[async_trait]
+impl SkillHandler for ValidationSkill {
+    fn id(&self) -> &str {
+        VALIDATION_SKILL
+    }
+
+    async fn handle(&self, agent: &PurchaseOrderAgent, message: &Message) -> Result<TaskStatus, Box<dyn Error>> {
+        let report = agent.validate(message)?;
+        Ok(TaskStatus {
+            state: if report.valid { TaskState::Completed } else { TaskState::Failed },
+            message: assistant_message(vec![Part::Data { data: serde_json::to_value(&report)? }]),
+            timestamp: agent.current_timestamp(),
+        })
+    }
+}
+
+/// CSV row and summary of the order as submitted, whether or not it is valid
+pub struct ReportingSkill;
+
+#[async_trait]
+impl SkillHandler for ReportingSkill {
+    fn id(&self) -> &str {
+        REPORTING_SKILL
+    }
+
+    async fn handle(&self, agent: &PurchaseOrderAgent, message: &Message) -> Result<TaskStatus, Box<dyn Error>> {
+        let po = agent.extract_purchase_order(message)?;
+        let result = agent.evaluate_purchase_order(&po);
+        let summary = agent.create_summary(&po);
+
+        Ok(TaskStatus {
+            state: TaskState::Completed,
+            message: assistant_message(vec![
+                Part::Text { text: result.csv_row() },
+                Part::Data { data: serde_json::json!({ "po_number": po.po_number, "summary": summary }) },
+            ]),
+            timestamp: agent.current_timestamp(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::ProcessingResult;
+    use a2a::A2AProtocol;
+
+    fn po(supplier_name: &str) -> serde_json::Value {
+        let mut po = crate::testing::valid_order();
+        po.supplier_name = supplier_name.to_string();
+        serde_json::to_value(po).unwrap()
+    }
+
+    fn message(data: serde_json::Value) -> Message {
+        Message { role: "user".to_string(), parts: vec![Part::Data { data }] }
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_by_skill_id() {
+        let agent = PurchaseOrderAgent::new();
+
+        let processed = agent.send_task(message(serde_json::json!({ "purchaseOrder": po("Acme") }))).await.unwrap();
+        assert!(ProcessingResult::from_status(&processed.status).is_some());
+        assert_eq!(agent.task_store().get(&processed.id).unwrap().unwrap().skill_id.as_deref(), Some(PROCESSING_SKILL));
+
+        let validated = agent
+            .send_task(message(serde_json::json!({ "skill_id": VALIDATION_SKILL, "purchaseOrder": po("") })))
+            .await
+            .unwrap();
+        assert!(matches!(validated.status.state, TaskState::Failed));
+        let parts = &validated.status.message.unwrap().parts;
+        assert_eq!(parts.len(), 1, "no CSV output");
+        let Part::Data { data } = &parts[0] else { panic!("expected a data part") };
+        let report: ValidationReport = serde_json::from_value(data.clone()).unwrap();
+        assert!(!report.valid);
+
+        let reported = agent
+            .send_task_with_skill(message(po("")), Some(REPORTING_SKILL))
+            .await
+            .unwrap();
+        assert!(matches!(reported.status.state, TaskState::Completed), "reporting does not validate");
+        assert!(matches!(&reported.status.message.unwrap().parts[0], Part::Text { text } if text.starts_with("PO-1,")));
+
+        let err = agent.send_task_with_skill(message(po("Acme")), Some("translate")).await.unwrap_err();
+        assert!(err.to_string().contains("purchase-order-validation"));
+    }
+}
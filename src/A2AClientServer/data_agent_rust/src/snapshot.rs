@@ -0,0 +1,160 @@
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+use crate::redaction::RedactionConfig;
+use crate::store::{StoreError, StoreResult, TaskRecord, TaskStore};
+
+/// On-disk encoding of a task store snapshot
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SnapshotFormat {
+    /// A single JSON array of task records
+    #[default]
+    Json,
+    /// One JSON task record per line
+    Ndjson,
+}
+
+impl SnapshotFormat {
+    /// Pick the format from a file extension: `.ndjson`/`.jsonl` are NDJSON, anything else JSON
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("ndjson") | Some("jsonl") => SnapshotFormat::Ndjson,
+            _ => SnapshotFormat::Json,
+        }
+    }
+
+    /// Content type used when serving a snapshot over HTTP
+    pub fn content_type(self) -> &'static str {
+        match self {
+            SnapshotFormat::Json => "application/json",
+            SnapshotFormat::Ndjson => "application/x-ndjson",
+        }
+    }
+}
+
+/// Persistence of the task store between restarts
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct SnapshotConfig {
+    /// Snapshot file; the format follows its extension (see [`SnapshotFormat::from_path`])
+    pub path: Option<PathBuf>,
+    /// Restore the snapshot into the store when the server starts
+    pub load_on_startup: bool,
+    /// Write a snapshot when the server shuts down gracefully
+    pub save_on_shutdown: bool,
+    /// Largest snapshot `POST /admin/snapshot/restore` accepts
+    pub max_restore_bytes: usize,
+}
+
+impl Default for SnapshotConfig {
+    fn default() -> Self {
+        Self {
+            path: None,
+            load_on_startup: true,
+            save_on_shutdown: true,
+            max_restore_bytes: 512 * 1024 * 1024,
+        }
+    }
+}
+
+/// Serialize records in the given format
+pub fn encode(records: &[TaskRecord], format: SnapshotFormat) -> StoreResult<Vec<u8>> {
+    let to_store_error = |e: serde_json::Error| StoreError(format!("Failed to encode snapshot: {}", e));
+    match format {
+        SnapshotFormat::Json => serde_json::to_vec_pretty(records).map_err(to_store_error),
+        SnapshotFormat::Ndjson => {
+            let mut out = Vec::new();
+            for record in records {
+                serde_json::to_writer(&mut out, record).map_err(to_store_error)?;
+                out.push(b'\n');
+            }
+            Ok(out)
+        }
+    }
+}
+
+/// Parse records previously written by [`encode`]
+pub fn decode(bytes: &[u8], format: SnapshotFormat) -> StoreResult<Vec<TaskRecord>> {
+    match format {
+        SnapshotFormat::Json => {
+            serde_json::from_slice(bytes).map_err(|e| StoreError(format!("Invalid JSON snapshot: {}", e)))
+        }
+        SnapshotFormat::Ndjson => bytes
+            .split(|&b| b == b'\n')
+            .enumerate()
+            .filter(|(_, line)| !line.iter().all(u8::is_ascii_whitespace))
+            .map(|(n, line)| {
+                serde_json::from_slice(line)
+                    .map_err(|e| StoreError(format!("Invalid NDJSON snapshot at line {}: {}", n + 1, e)))
+            })
+            .collect(),
+    }
+}
+
+/// Write the whole store to `path`, replacing any previous snapshot atomically
+///
+/// Records are redacted as `redaction` says. Returns the number of records written.
+pub fn save(store: &dyn TaskStore, path: &Path, redaction: &RedactionConfig) -> StoreResult<usize> {
+    let records: Vec<TaskRecord> = store.snapshot()?.iter().map(|record| redaction.persisted(record)).collect();
+    let bytes = encode(&records, SnapshotFormat::from_path(path))?;
+
+    let io_error = |e: std::io::Error| StoreError(format!("Failed to write snapshot {}: {}", path.display(), e));
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    std::fs::write(&tmp, bytes).map_err(io_error)?;
+    std::fs::rename(&tmp, path).map_err(io_error)?;
+
+    Ok(records.len())
+}
+
+/// Restore a snapshot file into the store; a missing file restores nothing
+///
+/// Returns the number of records restored.
+pub fn load(store: &dyn TaskStore, path: &Path) -> StoreResult<usize> {
+    let bytes = match std::fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(StoreError(format!("Failed to read snapshot {}: {}", path.display(), e))),
+    };
+    store.restore(decode(&bytes, SnapshotFormat::from_path(path))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::MemoryTaskStore;
+    use crate::testing::task_record;
+    use a2a::TaskState;
+
+    #[test]
+    fn test_encode_decode_round_trip() {
+        let records = vec![task_record("a", TaskState::Completed), task_record("b", TaskState::Completed)];
+        for format in [SnapshotFormat::Json, SnapshotFormat::Ndjson] {
+            let bytes = encode(&records, format).unwrap();
+            let decoded = decode(&bytes, format).unwrap();
+            let ids: Vec<_> = decoded.iter().map(|r| r.task.id.as_str()).collect();
+            assert_eq!(ids, ["a", "b"]);
+        }
+
+        let err = decode(b"{}\nnot json\n", SnapshotFormat::Ndjson).unwrap_err();
+        assert!(err.to_string().contains("line 1"));
+    }
+
+    #[test]
+    fn test_save_and_load_file() {
+        let path = std::env::temp_dir().join(format!("po-agent-snapshot-{}.ndjson", uuid::Uuid::new_v4()));
+        let source = MemoryTaskStore::new();
+        source.put(task_record("a", TaskState::Completed)).unwrap();
+        source.put(task_record("b", TaskState::Completed)).unwrap();
+
+        assert_eq!(save(&source, &path, &RedactionConfig::default()).unwrap(), 2);
+
+        let target = MemoryTaskStore::new();
+        assert_eq!(load(&target, &path).unwrap(), 2);
+        assert!(target.get("a").unwrap().is_some());
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(load(&target, &path).unwrap(), 0, "missing snapshot is not an error");
+    }
+}
@@ -0,0 +1,370 @@
+use a2a::{Message, Task, TaskState, TaskStatus};
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::approval::{ApprovalAssignment, ApprovalVote};
+use crate::artifacts;
+use crate::labels::Labels;
+use crate::processing::Priority;
+use crate::request_options::OutputFormat;
+
+/// A stored task together with bookkeeping the A2A `Task` type does not carry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskRecord {
+    pub task: Task,
+    /// Message the task was created from, kept so a failed task can be re-run
+    pub request: Message,
+    /// Skill that handled the request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skill_id: Option<String>,
+    /// Lane the task was queued in for background processing
+    #[serde(default, skip_serializing_if = "Priority::is_normal")]
+    pub priority: Priority,
+    /// Time before which the task is held as scheduled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub process_after: Option<DateTime<Utc>>,
+    /// Failed task this one retries
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retried_from: Option<String>,
+    /// Approver the order waits for while it is pending approval
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approval: Option<ApprovalAssignment>,
+    /// Approvals given so far towards a requirement that needs several
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub approvals: Vec<ApprovalVote>,
+    /// Key/value labels the task was marked with, see [`crate::labels`]
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: Labels,
+    /// Artifact formats the submission asked for; empty keeps them all
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub outputs: Vec<OutputFormat>,
+    /// Language tag of the submitter
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+    /// URL the result is posted to once the task is terminal
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub callback_url: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl TaskRecord {
+    pub fn new(task: Task, request: Message) -> Self {
+        let now = Utc::now();
+        Self {
+            task,
+            request,
+            skill_id: None,
+            priority: Priority::Normal,
+            process_after: None,
+            retried_from: None,
+            approval: None,
+            approvals: Vec::new(),
+            labels: Labels::new(),
+            outputs: Vec::new(),
+            locale: None,
+            callback_url: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// Lower-case wire name of the task's current state
+    ///
+    /// A submitted task held until its `process_after` time is `scheduled`.
+    pub fn state_name(&self) -> &'static str {
+        match (&self.task.status.state, self.process_after) {
+            (TaskState::Submitted, Some(at)) if at > Utc::now() => "scheduled",
+            (state, _) => task_state_name(state),
+        }
+    }
+
+    /// Move the task to `status`, replacing its artifacts with the ones the status yields in the formats asked for
+    pub fn set_status(&mut self, status: TaskStatus) {
+        self.task.artifacts = artifacts::select(artifacts::from_status(&status), &self.outputs);
+        self.task.status = status;
+    }
+}
+
+/// Lower-case name for a task state, matching the A2A wire format
+pub fn task_state_name(state: &TaskState) -> &'static str {
+    match state {
+        TaskState::Submitted => "submitted",
+        TaskState::Working => "working",
+        TaskState::InputRequired => "input-required",
+        TaskState::Completed => "completed",
+        TaskState::Canceled => "canceled",
+        TaskState::Failed => "failed",
+    }
+}
+
+/// Whether a task in this state will not change any further on its own
+pub fn is_terminal(state: &TaskState) -> bool {
+    matches!(state, TaskState::Completed | TaskState::Canceled | TaskState::Failed)
+}
+
+/// Error raised by a task store backend
+#[derive(Debug)]
+pub struct StoreError(pub String);
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Task store error: {}", self.0)
+    }
+}
+
+impl std::error::Error for StoreError {}
+
+pub type StoreResult<T> = Result<T, StoreError>;
+
+/// Aggregate figures about the tasks held in a store
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StoreStats {
+    pub total_tasks: usize,
+    pub tasks_by_state: BTreeMap<String, usize>,
+    pub oldest_task_at: Option<DateTime<Utc>>,
+    pub newest_task_at: Option<DateTime<Utc>>,
+}
+
+impl StoreStats {
+    /// Compute statistics over a set of records
+    pub fn from_records<'a>(records: impl IntoIterator<Item = &'a TaskRecord>) -> Self {
+        let mut stats = StoreStats::default();
+        for record in records {
+            stats.add(record);
+        }
+        stats
+    }
+
+    /// Fold one more record into the statistics
+    pub fn add(&mut self, record: &TaskRecord) {
+        self.total_tasks += 1;
+        *self.tasks_by_state.entry(record.state_name().to_string()).or_default() += 1;
+        self.oldest_task_at = Some(self.oldest_task_at.map_or(record.created_at, |t| t.min(record.created_at)));
+        self.newest_task_at = Some(self.newest_task_at.map_or(record.created_at, |t| t.max(record.created_at)));
+    }
+}
+
+/// Storage backend for purchase order tasks
+///
+/// Implementations must be safe to share between request handlers; every method
+/// takes `&self` and handles its own synchronisation.
+pub trait TaskStore: Send + Sync {
+    /// Insert or replace a record
+    fn put(&self, record: TaskRecord) -> StoreResult<()>;
+
+    /// Fetch a record by task ID
+    fn get(&self, task_id: &str) -> StoreResult<Option<TaskRecord>>;
+
+    /// Apply `update` to a record atomically, bumping `updated_at`; returns the updated record
+    fn update(&self, task_id: &str, update: &mut dyn FnMut(&mut TaskRecord)) -> StoreResult<Option<TaskRecord>>;
+
+    /// Replace a record with `record` if it is still `expected`, bumping `updated_at`
+    ///
+    /// Returns the stored record, or `None` when the record was changed or
+    /// removed since `expected` was read, for the caller to read it and retry.
+    fn compare_and_swap(&self, expected: &TaskRecord, record: TaskRecord) -> StoreResult<Option<TaskRecord>> {
+        let mut record = Some(record);
+        let mut swapped = false;
+        let stored = self.update(&expected.task.id, &mut |stored| {
+            if same_record(stored, expected)
+                && let Some(record) = record.take()
+            {
+                *stored = record;
+                swapped = true;
+            }
+        })?;
+        Ok(stored.filter(|_| swapped))
+    }
+
+    /// Remove a record, returning it if it existed
+    fn remove(&self, task_id: &str) -> StoreResult<Option<TaskRecord>>;
+
+    /// Snapshot of every record, in no particular order
+    fn list(&self) -> StoreResult<Vec<TaskRecord>>;
+
+    /// Remove every record matching `predicate`, returning how many were removed
+    fn remove_where(&self, predicate: &dyn Fn(&TaskRecord) -> bool) -> StoreResult<usize>;
+
+    /// Aggregate statistics over the stored records
+    fn stats(&self) -> StoreResult<StoreStats> {
+        Ok(StoreStats::from_records(&self.list()?))
+    }
+
+    /// Every record, oldest first, for export
+    fn snapshot(&self) -> StoreResult<Vec<TaskRecord>> {
+        let mut records = self.list()?;
+        records.sort_by(|a, b| a.created_at.cmp(&b.created_at).then_with(|| a.task.id.cmp(&b.task.id)));
+        Ok(records)
+    }
+
+    /// IDs of every record, oldest first, so large exports can fetch records a few at a time
+    fn snapshot_ids(&self) -> StoreResult<Vec<String>> {
+        Ok(self.snapshot()?.into_iter().map(|record| record.task.id).collect())
+    }
+
+    /// Insert exported records, replacing any with the same task ID; returns how many were restored
+    fn restore(&self, records: Vec<TaskRecord>) -> StoreResult<usize> {
+        let count = records.len();
+        for record in records {
+            self.put(record)?;
+        }
+        Ok(count)
+    }
+}
+
+/// Whether two records hold the same data, as they would be serialized
+pub(crate) fn same_record(a: &TaskRecord, b: &TaskRecord) -> bool {
+    matches!((serde_json::to_value(a), serde_json::to_value(b)), (Ok(a), Ok(b)) if a == b)
+}
+
+/// In-memory task store used by default
+///
+/// Records live in a sharded concurrent map, so requests touching different
+/// tasks never contend on a single lock, and a panicking handler cannot poison
+/// the store for everyone else.
+#[derive(Default)]
+pub struct MemoryTaskStore {
+    records: DashMap<String, TaskRecord>,
+}
+
+impl MemoryTaskStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl TaskStore for MemoryTaskStore {
+    fn put(&self, record: TaskRecord) -> StoreResult<()> {
+        self.records.insert(record.task.id.clone(), record);
+        Ok(())
+    }
+
+    fn get(&self, task_id: &str) -> StoreResult<Option<TaskRecord>> {
+        Ok(self.records.get(task_id).map(|record| record.clone()))
+    }
+
+    fn update(&self, task_id: &str, update: &mut dyn FnMut(&mut TaskRecord)) -> StoreResult<Option<TaskRecord>> {
+        Ok(self.records.get_mut(task_id).map(|mut record| {
+            update(&mut record);
+            record.updated_at = Utc::now();
+            record.clone()
+        }))
+    }
+
+    fn compare_and_swap(&self, expected: &TaskRecord, record: TaskRecord) -> StoreResult<Option<TaskRecord>> {
+        let Some(mut stored) = self.records.get_mut(&expected.task.id) else {
+            return Ok(None);
+        };
+        if !same_record(&stored, expected) {
+            return Ok(None);
+        }
+        *stored = record;
+        stored.updated_at = Utc::now();
+        Ok(Some(stored.clone()))
+    }
+
+    fn remove(&self, task_id: &str) -> StoreResult<Option<TaskRecord>> {
+        Ok(self.records.remove(task_id).map(|(_, record)| record))
+    }
+
+    fn list(&self) -> StoreResult<Vec<TaskRecord>> {
+        Ok(self.records.iter().map(|entry| entry.value().clone()).collect())
+    }
+
+    fn snapshot_ids(&self) -> StoreResult<Vec<String>> {
+        let mut ids: Vec<_> = self.records.iter().map(|entry| (entry.created_at, entry.key().clone())).collect();
+        ids.sort();
+        Ok(ids.into_iter().map(|(_, id)| id).collect())
+    }
+
+    fn remove_where(&self, predicate: &dyn Fn(&TaskRecord) -> bool) -> StoreResult<usize> {
+        let mut removed = 0;
+        self.records.retain(|_, record| {
+            let keep = !predicate(record);
+            if !keep {
+                removed += 1;
+            }
+            keep
+        });
+        Ok(removed)
+    }
+
+    fn stats(&self) -> StoreResult<StoreStats> {
+        let mut stats = StoreStats::default();
+        for entry in self.records.iter() {
+            stats.add(entry.value());
+        }
+        Ok(stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::task_record;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_concurrent_updates_are_not_lost() {
+        let store = Arc::new(MemoryTaskStore::new());
+        store.put(task_record("shared", TaskState::Submitted)).unwrap();
+
+        let handles: Vec<_> = (0..8)
+            .map(|worker| {
+                let store = store.clone();
+                std::thread::spawn(move || {
+                    for n in 0..100 {
+                        store.put(task_record(&format!("task-{}-{}", worker, n), TaskState::Submitted)).unwrap();
+                        store
+                            .update("shared", &mut |record| record.task.status.timestamp.push('x'))
+                            .unwrap();
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        assert_eq!(store.stats().unwrap().total_tasks, 801);
+        assert_eq!(store.get("shared").unwrap().unwrap().task.status.timestamp.len(), 801);
+    }
+
+    #[test]
+    fn test_panicking_update_does_not_poison_store() {
+        let store = Arc::new(MemoryTaskStore::new());
+        store.put(task_record("task", TaskState::Submitted)).unwrap();
+
+        let panicking = store.clone();
+        let result = std::thread::spawn(move || {
+            panicking.update("task", &mut |_| panic!("handler bug")).unwrap();
+        })
+        .join();
+        assert!(result.is_err());
+
+        assert!(store.get("task").unwrap().is_some());
+        store.put(task_record("other", TaskState::Submitted)).unwrap();
+        assert_eq!(store.list().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_compare_and_swap_only_replaces_what_was_read() {
+        let store = MemoryTaskStore::new();
+        store.put(task_record("task", TaskState::Submitted)).unwrap();
+        let read = store.get("task").unwrap().unwrap();
+
+        let mut first = read.clone();
+        first.task.status.timestamp = "1".to_string();
+        assert!(store.compare_and_swap(&read, first).unwrap().is_some());
+
+        // The record changed since `read`, so a second swap from it is refused
+        let mut second = read.clone();
+        second.task.status.timestamp = "2".to_string();
+        assert!(store.compare_and_swap(&read, second).unwrap().is_none());
+        assert_eq!(store.get("task").unwrap().unwrap().task.status.timestamp, "1");
+    }
+}
@@ -0,0 +1,182 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::LazyLock;
+
+use crate::address::country_code;
+use crate::agent::PurchaseOrder;
+
+/// Supplier tax ID checks
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct TaxIdConfig {
+    /// Suppliers outside this country are international and must give a tax ID
+    pub home_country: String,
+}
+
+impl Default for TaxIdConfig {
+    fn default() -> Self {
+        Self { home_country: "USA".to_string() }
+    }
+}
+
+/// What is wrong with a supplier tax ID
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaxIdIssue {
+    /// An international supplier gave no tax ID
+    Missing,
+    /// The ID does not follow the format of the supplier's country
+    Malformed,
+    /// A VAT number's country prefix is not the supplier's country
+    CountryMismatch,
+}
+
+/// One problem found with a supplier tax ID
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TaxIdFinding {
+    pub issue: TaxIdIssue,
+    /// ISO 3166-1 alpha-2 code of the supplier country, when it is recognised
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
+    pub message: String,
+}
+
+/// Country code and VAT number pattern (without the prefix) of each EU member state
+const EU_VAT: [(&str, &str); 27] = [
+    ("AT", r"U\d{8}"),
+    ("BE", r"[01]\d{9}"),
+    ("BG", r"\d{9,10}"),
+    ("CY", r"\d{8}[A-Z]"),
+    ("CZ", r"\d{8,10}"),
+    ("DE", r"\d{9}"),
+    ("DK", r"\d{8}"),
+    ("EE", r"\d{9}"),
+    ("ES", r"[A-Z0-9]\d{7}[A-Z0-9]"),
+    ("FI", r"\d{8}"),
+    ("FR", r"[A-HJ-NP-Z0-9]{2}\d{9}"),
+    ("GR", r"\d{9}"),
+    ("HR", r"\d{11}"),
+    ("HU", r"\d{8}"),
+    ("IE", r"\d{7}[A-W][A-I]?|\d[A-Z+*]\d{5}[A-W]"),
+    ("IT", r"\d{11}"),
+    ("LT", r"\d{9}|\d{12}"),
+    ("LU", r"\d{8}"),
+    ("LV", r"\d{11}"),
+    ("MT", r"\d{8}"),
+    ("NL", r"\d{9}B\d{2}"),
+    ("PL", r"\d{10}"),
+    ("PT", r"\d{9}"),
+    ("RO", r"\d{2,10}"),
+    ("SE", r"\d{12}"),
+    ("SI", r"\d{8}"),
+    ("SK", r"\d{10}"),
+];
+
+static EU_VAT_PATTERNS: LazyLock<Vec<(&'static str, Regex)>> = LazyLock::new(|| {
+    EU_VAT
+        .iter()
+        .map(|(code, pattern)| (*code, Regex::new(&format!("^(?:{})$", pattern)).expect("valid VAT pattern")))
+        .collect()
+});
+
+static US_EIN: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\d{2}-?\d{7}$").expect("valid EIN pattern"));
+
+/// Problems with the supplier tax ID of `po`
+///
+/// IDs are checked against the format of the supplier's country: an EIN for US
+/// suppliers and a VAT number, with or without its country prefix, for EU ones.
+/// IDs of other countries are not checked. International suppliers must give one.
+pub fn check(config: &TaxIdConfig, po: &PurchaseOrder) -> Vec<TaxIdFinding> {
+    check_supplier(config, &po.supplier_name, &po.supplier_country, po.supplier_tax_id.as_deref())
+}
+
+/// Problems with the tax ID of supplier `name` in `country`, as [`check`] finds them on orders
+pub fn check_supplier(config: &TaxIdConfig, name: &str, country: &str, tax_id: Option<&str>) -> Vec<TaxIdFinding> {
+    let code = country_code(country);
+    let finding = |issue, message: String| TaxIdFinding { issue, country: code.map(str::to_string), message };
+    let international = match (code, country_code(&config.home_country)) {
+        (Some(code), Some(home)) => code != home,
+        _ => !country.trim().eq_ignore_ascii_case(config.home_country.trim()),
+    };
+
+    let Some(tax_id) = tax_id.map(str::trim).filter(|id| !id.is_empty()) else {
+        if international {
+            return vec![finding(
+                TaxIdIssue::Missing,
+                format!("International supplier '{}' has no tax ID", name),
+            )];
+        }
+        return Vec::new();
+    };
+
+    match code {
+        Some("US") => {
+            if US_EIN.is_match(tax_id) {
+                Vec::new()
+            } else {
+                vec![finding(TaxIdIssue::Malformed, format!("Supplier tax ID '{}' is not a valid US EIN (NN-NNNNNNN)", tax_id))]
+            }
+        }
+        Some(code) => {
+            let Some((_, pattern)) = EU_VAT_PATTERNS.iter().find(|(c, _)| *c == code) else {
+                return Vec::new();
+            };
+            let compact: String =
+                tax_id.chars().filter(|c| !c.is_whitespace() && !matches!(c, '.' | '-')).collect::<String>().to_ascii_uppercase();
+            // Greek VAT numbers are prefixed EL rather than GR
+            let prefix = if code == "GR" { "EL" } else { code };
+            let number = match compact.get(..2) {
+                Some(given) if given.chars().all(|c| c.is_ascii_alphabetic()) && given != prefix && !pattern.is_match(&compact) => {
+                    return vec![finding(
+                        TaxIdIssue::CountryMismatch,
+                        format!("Supplier VAT number '{}' is prefixed {} but the supplier is in {}", tax_id, given, code),
+                    )];
+                }
+                Some(given) if given == prefix => &compact[2..],
+                _ => compact.as_str(),
+            };
+            if pattern.is_match(number) {
+                Vec::new()
+            } else {
+                vec![finding(TaxIdIssue::Malformed, format!("Supplier VAT number '{}' is not valid for {}", tax_id, code))]
+            }
+        }
+        None => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn po(country: &str, tax_id: Option<&str>) -> PurchaseOrder {
+        let mut po = crate::testing::valid_order();
+        po.supplier_country = country.to_string();
+        po.supplier_tax_id = tax_id.map(str::to_string);
+        po
+    }
+
+    #[test]
+    fn test_tax_ids_are_checked_per_country() {
+        let config = TaxIdConfig::default();
+        let issues = |country, tax_id| check(&config, &po(country, tax_id)).iter().map(|f| f.issue).collect::<Vec<_>>();
+
+        assert!(issues("USA", None).is_empty(), "domestic suppliers may omit the tax ID");
+        assert!(issues("United States", Some("12-3456789")).is_empty());
+        assert_eq!(issues("USA", Some("123")), [TaxIdIssue::Malformed]);
+
+        assert_eq!(issues("Germany", None), [TaxIdIssue::Missing]);
+        assert!(issues("DE", Some("DE 123 456 789")).is_empty());
+        assert!(issues("DEU", Some("123456789")).is_empty());
+        assert_eq!(issues("Germany", Some("DE12345")), [TaxIdIssue::Malformed]);
+        assert_eq!(issues("Germany", Some("FR12345678901")), [TaxIdIssue::CountryMismatch]);
+        assert!(issues("Greece", Some("EL123456789")).is_empty());
+        assert!(issues("Netherlands", Some("NL123456789B01")).is_empty());
+        assert!(issues("Austria", Some("ATU12345678")).is_empty());
+
+        assert!(issues("Japan", Some("anything")).is_empty(), "unchecked countries pass");
+        let finding = &check(&config, &po("Japan", None))[0];
+        assert_eq!((finding.issue, finding.country.as_deref()), (TaxIdIssue::Missing, Some("JP")));
+        assert_eq!(check(&config, &po("Freedonia", None))[0].country, None);
+    }
+}
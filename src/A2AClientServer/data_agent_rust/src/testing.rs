@@ -0,0 +1,135 @@
+//! Purchase order and task record fixtures for tests
+//!
+//! Compiled into this crate's own tests, and for downstream crates with the
+//! `testing` feature. Every order fixture starts from [`valid_order`], which
+//! passes validation without errors or warnings, and changes one thing about it.
+
+use a2a::{Message, Part, Task, TaskState, TaskStatus};
+use chrono::{Duration, Utc};
+
+use crate::agent::{PurchaseOrder, PurchaseOrderWrapper};
+use crate::order_builder::PurchaseOrderBuilder;
+use crate::rounding::ReconciliationConfig;
+use crate::store::TaskRecord;
+
+fn valid_builder() -> PurchaseOrderBuilder {
+    PurchaseOrder::builder("PO-1")
+        .with_supplier("Acme Office Supply")
+        .with_supplier_address("1 Main St", "Springfield", "IL", "62701", "USA")
+        .with_created_by("Sam")
+        .with_buyer_department("Marketing")
+        .with_tax_rate(0.0825)
+        .with_approval("Within the Marketing budget")
+}
+
+fn build(builder: PurchaseOrderBuilder) -> PurchaseOrder {
+    builder.build().expect("fixtures are complete")
+}
+
+/// PO-1 from Acme Office Supply in Illinois: 10 × P-1 paper at 5.00 and
+/// 3 × T-2 toner at 10.00, 8.25% tax, 86.60 in total
+pub fn valid_order() -> PurchaseOrder {
+    build(valid_builder().with_item("P-1", "Paper", 10, 5.0).with_item("T-2", "Toner", 3, 10.0))
+}
+
+/// [`valid_order`] with its first line total, and so its subtotal, tax and grand total, off by `drift`
+///
+/// The totals agree with the stated line totals, as when an upstream system
+/// computes a line total wrong, so only the line total check reports it.
+pub fn drifted_order(drift: f64) -> PurchaseOrder {
+    let reconciliation = ReconciliationConfig::default();
+    let mut po = valid_order();
+    po.items[0].line_total += drift;
+    po.sub_total = reconciliation.round(po.items.iter().map(|item| item.line_total).sum());
+    po.tax = reconciliation.tax(&po);
+    po.grand_total = reconciliation.round(po.sub_total + po.tax);
+    po
+}
+
+/// [`valid_order`] with `items`, as (item code, description, quantity, unit price), in place of
+/// its own lines, and its amounts computed at `tax_rate`
+pub fn order_of(tax_rate: f64, items: &[(&str, &str, u32, f64)]) -> PurchaseOrder {
+    let builder = items.iter().fold(valid_builder().with_tax_rate(tax_rate), |builder, (code, description, quantity, price)| {
+        builder.with_item(code, description, *quantity, *price)
+    });
+    build(builder)
+}
+
+/// [`valid_order`] without its approval, so that processing leaves it pending
+pub fn pending_order() -> PurchaseOrder {
+    let mut po = valid_order();
+    (po.is_approved, po.approval_reason) = (false, None);
+    po
+}
+
+/// [`valid_order`] placed by a department that is not authorized to purchase
+pub fn unauthorized_department_order() -> PurchaseOrder {
+    build(
+        valid_builder()
+            .with_buyer_department("Facilities")
+            .with_item("P-1", "Paper", 10, 5.0)
+            .with_item("T-2", "Toner", 3, 10.0),
+    )
+}
+
+/// An order of `items` distinct lines of one unit at 1.00 each, for load and size limits
+pub fn huge_order(items: usize) -> PurchaseOrder {
+    let builder = (0..items).fold(valid_builder().with_tax_rate(0.0625), |builder, n| {
+        builder.with_item(&format!("P-{}", n + 1), &format!("Paper, lot {}", n + 1), 1, 1.0)
+    });
+    build(builder)
+}
+
+/// A message carrying `po` wrapped in `purchaseOrder` in a Data part
+pub fn message(po: &PurchaseOrder) -> Message {
+    let wrapper = PurchaseOrderWrapper { purchase_order: po.clone() };
+    Message {
+        role: "user".to_string(),
+        parts: vec![Part::Data { data: serde_json::to_value(wrapper).expect("purchase orders serialize") }],
+    }
+}
+
+/// A stored task in `state`, with an empty request and no status message
+pub fn task_record(id: &str, state: TaskState) -> TaskRecord {
+    let task = Task {
+        id: id.to_string(),
+        session_id: None,
+        status: TaskStatus { state, message: None, timestamp: "0".to_string() },
+        artifacts: None,
+    };
+    TaskRecord::new(task, Message { role: "user".to_string(), parts: vec![] })
+}
+
+/// [`task_record`], created and last updated `age` ago
+pub fn aged_task_record(id: &str, state: TaskState, age: Duration) -> TaskRecord {
+    let mut record = task_record(id, state);
+    record.created_at = Utc::now() - age;
+    record.updated_at = record.created_at;
+    record
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::PurchaseOrderAgent;
+
+    #[test]
+    fn test_fixtures_fail_only_what_they_name() {
+        let agent = PurchaseOrderAgent::new();
+        let (errors, warnings) = agent.validate_purchase_order(&valid_order());
+        assert!(errors.is_empty() && warnings.is_empty(), "{:?} {:?}", errors, warnings);
+
+        let (errors, warnings) = agent.validate_purchase_order(&drifted_order(0.5));
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(warnings, ["Item 1 line total mismatch: expected 50.00, got 50.50"]);
+
+        let (errors, warnings) = agent.validate_purchase_order(&unauthorized_department_order());
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(warnings, ["Department 'Facilities' may not be authorized for purchases"]);
+
+        let huge = huge_order(1_000);
+        assert_eq!((huge.items.len(), huge.grand_total), (1_000, 1_062.5));
+        let (errors, _) = agent.validate_purchase_order(&huge);
+        assert!(errors.is_empty(), "{:?}", errors);
+    }
+}
@@ -0,0 +1,121 @@
+//! High-value thresholds per department and currency
+//!
+//! An order whose grand total exceeds the threshold that applies to it gets a
+//! high-value finding naming the threshold. The most specific threshold wins:
+//! one for the order's department and currency, then one for the department,
+//! then one for the currency, then the default.
+
+use serde::Deserialize;
+
+/// One configured threshold
+#[derive(Debug, Clone, Deserialize)]
+pub struct Threshold {
+    /// Department it applies to, compared case-insensitively; unset for every department
+    #[serde(default)]
+    pub department: Option<String>,
+    /// ISO 4217 currency it applies to; unset for every currency
+    #[serde(default)]
+    pub currency: Option<String>,
+    /// Grand totals above this, in the order's currency, are high value
+    pub amount: f64,
+}
+
+/// When an order counts as high value
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct HighValueConfig {
+    /// Threshold of orders no configured threshold covers
+    pub default: f64,
+    /// Currency of orders that do not name one
+    pub currency: String,
+    pub thresholds: Vec<Threshold>,
+}
+
+impl Default for HighValueConfig {
+    fn default() -> Self {
+        Self {
+            default: 10000.0,
+            currency: "USD".to_string(),
+            thresholds: Vec::new(),
+        }
+    }
+}
+
+/// The threshold an order was held against
+#[derive(Debug, Clone, PartialEq)]
+pub struct AppliedThreshold {
+    pub amount: f64,
+    pub currency: String,
+    /// Department of the threshold, when it is specific to one
+    pub department: Option<String>,
+}
+
+impl HighValueConfig {
+    /// The threshold for orders of `department` in `currency`, or the default currency when unset
+    pub fn threshold(&self, department: &str, currency: Option<&str>) -> AppliedThreshold {
+        let currency = currency.filter(|c| !c.trim().is_empty()).unwrap_or(&self.currency).trim().to_uppercase();
+        let department_matches = |t: &Threshold| t.department.as_deref().is_some_and(|d| d.eq_ignore_ascii_case(department));
+        let currency_matches = |t: &Threshold| t.currency.as_deref().is_some_and(|c| c.eq_ignore_ascii_case(&currency));
+
+        let specific = self
+            .thresholds
+            .iter()
+            .find(|t| department_matches(t) && currency_matches(t))
+            .or_else(|| self.thresholds.iter().find(|t| department_matches(t) && t.currency.is_none()))
+            .or_else(|| self.thresholds.iter().find(|t| t.department.is_none() && currency_matches(t)));
+        match specific {
+            Some(threshold) => AppliedThreshold { amount: threshold.amount, currency, department: threshold.department.clone() },
+            None => AppliedThreshold { amount: self.default, currency, department: None },
+        }
+    }
+
+    /// The high-value finding for an order, or `None` when its grand total is within the threshold
+    pub fn check(&self, department: &str, currency: Option<&str>, grand_total: f64) -> Option<String> {
+        let threshold = self.threshold(department, currency);
+        if grand_total <= threshold.amount {
+            return None;
+        }
+        let scope = match &threshold.department {
+            Some(department) => format!(" for {}", department),
+            None => String::new(),
+        };
+        Some(format!(
+            "High value purchase order - grand total {:.2} {} exceeds the {:.2} {} threshold{} and may require additional approval",
+            grand_total, threshold.currency, threshold.amount, threshold.currency, scope
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_most_specific_threshold_applies() {
+        let threshold = |department: Option<&str>, currency: Option<&str>, amount| Threshold {
+            department: department.map(str::to_string),
+            currency: currency.map(str::to_string),
+            amount,
+        };
+        let config = HighValueConfig {
+            thresholds: vec![
+                threshold(None, Some("JPY"), 1_500_000.0),
+                threshold(Some("IT"), None, 50000.0),
+                threshold(Some("IT"), Some("EUR"), 40000.0),
+            ],
+            ..HighValueConfig::default()
+        };
+
+        assert_eq!(config.threshold("it", Some("eur")).amount, 40000.0);
+        assert_eq!(config.threshold("IT", None).amount, 50000.0);
+        assert_eq!(config.threshold("IT", Some("JPY")).amount, 50000.0);
+        assert_eq!(config.threshold("Marketing", Some("JPY")).amount, 1_500_000.0);
+        assert_eq!(config.threshold("Marketing", None), AppliedThreshold { amount: 10000.0, currency: "USD".to_string(), department: None });
+
+        assert_eq!(config.check("IT", None, 20000.0), None);
+        assert_eq!(
+            config.check("IT", Some("EUR"), 45000.0).unwrap(),
+            "High value purchase order - grand total 45000.00 EUR exceeds the 40000.00 EUR threshold for IT and may require additional approval"
+        );
+    }
+}
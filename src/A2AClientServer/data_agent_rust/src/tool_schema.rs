@@ -0,0 +1,394 @@
+//! OpenAI function-calling tool definitions for the agent's skills
+//!
+//! Parameter schemas are derived from the request types through [`JsonSchema`],
+//! so orchestrators can register each skill as a callable tool.
+
+use axum::{
+    extract::{Query, State},
+    response::Json,
+    routing::get,
+    Router,
+};
+use chrono::NaiveDate;
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use std::sync::Arc;
+
+use crate::a2a_agent_card::Skill;
+use crate::address::Address;
+use crate::agent::{PurchaseOrder, PurchaseOrderItem};
+use crate::blanket::BlanketTerms;
+use crate::expense::{ExpenseCategory, ExpenseLine, ExpenseReport, EXPENSE_SKILL};
+use crate::invoice::{Invoice, InvoiceLine, INVOICE_MATCHING_SKILL};
+use crate::invoice_agent::INVOICE_PROCESSING_SKILL;
+use crate::onboarding::{BankAccount, SupplierApplication, ONBOARDING_SKILL};
+use crate::requisition::{Requisition, RequisitionLine, REQUISITION_SKILL};
+use crate::server::AppState;
+use crate::skills::{PROCESSING_SKILL, REPORTING_SKILL, VALIDATION_SKILL};
+
+/// A type with a JSON Schema for its serialized form
+pub trait JsonSchema {
+    fn json_schema() -> Value;
+
+    /// Whether an object property of this type must be present
+    fn required() -> bool {
+        true
+    }
+}
+
+macro_rules! primitive_schema {
+    ($($ty:ty => $schema:tt),* $(,)?) => {
+        $(impl JsonSchema for $ty {
+            fn json_schema() -> Value {
+                json!($schema)
+            }
+        })*
+    };
+}
+
+primitive_schema! {
+    String => { "type": "string" },
+    bool => { "type": "boolean" },
+    f64 => { "type": "number" },
+    u32 => { "type": "integer", "minimum": 0 },
+    NaiveDate => { "type": "string", "format": "date" },
+    ExpenseCategory => { "type": "string", "enum": ["meals", "lodging", "airfare", "ground_transport", "mileage", "supplies", "other"] },
+}
+
+impl<T: JsonSchema> JsonSchema for Option<T> {
+    fn json_schema() -> Value {
+        T::json_schema()
+    }
+
+    fn required() -> bool {
+        false
+    }
+}
+
+impl<T: JsonSchema> JsonSchema for Vec<T> {
+    fn json_schema() -> Value {
+        json!({ "type": "array", "items": T::json_schema() })
+    }
+}
+
+/// Object schema of a camelCase struct, listing its fields and their types
+///
+/// Fields marked `#[default]` are optional like `Option` fields, matching `#[serde(default)]`.
+macro_rules! object_schema {
+    ($ty:ty, $description:literal { $($(#[$default:ident])? $field:ident: $field_ty:ty $(=> $doc:literal)?),* $(,)? }) => {
+        impl JsonSchema for $ty {
+            fn json_schema() -> Value {
+                let mut properties = Map::new();
+                let mut required = Vec::new();
+                $(
+                    let name = camel_case(stringify!($field));
+                    #[allow(unused_mut)]
+                    let mut schema = <$field_ty as JsonSchema>::json_schema();
+                    $(schema["description"] = json!($doc);)?
+                    let optional = [$(stringify!($default)),*].contains(&"default");
+                    if <$field_ty as JsonSchema>::required() && !optional {
+                        required.push(Value::String(name.clone()));
+                    }
+                    properties.insert(name, schema);
+                )*
+                json!({
+                    "type": "object",
+                    "description": $description,
+                    "properties": properties,
+                    "required": required,
+                })
+            }
+        }
+    };
+}
+
+fn camel_case(name: &str) -> String {
+    let mut words = name.split('_');
+    let mut camel = words.next().unwrap_or_default().to_string();
+    for word in words {
+        let mut chars = word.chars();
+        if let Some(first) = chars.next() {
+            camel.extend(first.to_uppercase());
+            camel.push_str(chars.as_str());
+        }
+    }
+    camel
+}
+
+object_schema!(PurchaseOrderItem, "One ordered line" {
+    item_code: String,
+    description: String,
+    quantity: u32,
+    unit_price: f64,
+    line_total: f64 => "quantity × unitPrice",
+});
+
+object_schema!(Address, "A postal address" {
+    attention: Option<String> => "Person or team to deliver to",
+    address_line1: String,
+    address_line2: Option<String>,
+    city: String,
+    #[default] state: String,
+    postal_code: String,
+    country: String,
+});
+
+object_schema!(BlanketTerms, "Spending limit and validity period of a blanket purchase order" {
+    not_to_exceed: f64 => "Cumulative grand total that releases may not exceed",
+    valid_from: NaiveDate => "First day releases are accepted",
+    valid_until: NaiveDate => "Last day releases are accepted",
+});
+
+object_schema!(PurchaseOrder, "A purchase order" {
+    supplier_name: String,
+    supplier_address_line1: String,
+    supplier_address_line2: Option<String>,
+    supplier_city: String,
+    supplier_state: String,
+    supplier_postal_code: String,
+    supplier_country: String,
+    supplier_tax_id: Option<String> => "VAT number or EIN; required of international suppliers",
+    items: Vec<PurchaseOrderItem>,
+    po_number: String,
+    created_by: String,
+    buyer_department: String,
+    ship_to: Option<Address> => "Where the order is delivered, when not to the buyer's usual address",
+    notes: Option<String>,
+    tax_rate: f64 => "Tax rate as a fraction, e.g. 0.08",
+    sub_total: f64,
+    tax: f64,
+    grand_total: f64,
+    is_approved: bool,
+    approval_reason: Option<String>,
+    blanket: Option<BlanketTerms> => "Not-to-exceed terms, present when this is a blanket order",
+    blanket_po_number: Option<String> => "Blanket order this is a release against",
+});
+
+object_schema!(InvoiceLine, "One billed line of an invoice" {
+    item_code: String,
+    description: Option<String>,
+    quantity: u32,
+    unit_price: f64,
+});
+
+object_schema!(Invoice, "Supplier invoice referencing a purchase order" {
+    invoice_number: String,
+    po_number: String,
+    supplier_name: String,
+    lines: Vec<InvoiceLine>,
+    invoice_date: Option<NaiveDate> => "Date the invoice was issued; required by invoice processing",
+    due_date: Option<NaiveDate> => "Date payment is due; derived from the payment terms when absent",
+    payment_terms: Option<String> => "Payment terms, e.g. \"Net 30\", \"2/10 Net 30\" or \"Due on receipt\"",
+    currency: Option<String> => "ISO 4217 currency of the amounts; the order's currency when absent",
+});
+
+object_schema!(RequisitionLine, "One requested item" {
+    item_code: String,
+    description: Option<String> => "Overrides the catalog description",
+    quantity: u32,
+});
+
+object_schema!(Requisition, "Internal request to buy items, before a supplier and prices are settled" {
+    requisition_number: String,
+    requested_by: String,
+    department: String,
+    supplier_name: Option<String> => "Supplier to order from; defaults to the catalog supplier of the items",
+    items: Vec<RequisitionLine>,
+    ship_to: Option<Address> => "Delivery address carried over to the purchase order",
+    notes: Option<String>,
+    tax_rate: Option<f64> => "Overrides the configured tax rate",
+});
+
+object_schema!(ExpenseLine, "One expense claimed" {
+    date: NaiveDate,
+    category: ExpenseCategory,
+    description: String,
+    amount: f64,
+    #[default] receipt: bool => "Whether a receipt is attached",
+    miles: Option<f64> => "Distance driven, required of mileage lines",
+});
+
+object_schema!(ExpenseReport, "An employee's claim for expenses paid on the company's behalf" {
+    report_number: String,
+    employee: String,
+    department: String,
+    lines: Vec<ExpenseLine>,
+    total: Option<f64> => "Total claimed, checked against the sum of the lines",
+    notes: Option<String>,
+});
+
+object_schema!(BankAccount, "Bank account a supplier is paid into" {
+    holder: String => "Name the account is held in",
+    iban: Option<String>,
+    bic: Option<String> => "SWIFT code of the bank",
+    routing_number: Option<String> => "ABA routing number of a US bank",
+    account_number: Option<String>,
+});
+
+object_schema!(SupplierApplication, "A supplier applying to be onboarded" {
+    name: String,
+    address_line1: String,
+    address_line2: Option<String>,
+    city: String,
+    #[default] state: String,
+    postal_code: String,
+    country: String,
+    tax_id: Option<String> => "VAT number or EIN; required of international suppliers",
+    bank_account: Option<BankAccount>,
+});
+
+/// Parameters schema of a skill's tool: the Data part a task for the skill carries
+///
+/// Skills without a known request type accept any object.
+pub fn parameters(skill_id: &str) -> Value {
+    let (property, schema) = match skill_id {
+        PROCESSING_SKILL | VALIDATION_SKILL | REPORTING_SKILL => ("purchaseOrder", PurchaseOrder::json_schema()),
+        INVOICE_MATCHING_SKILL | INVOICE_PROCESSING_SKILL => ("invoice", Invoice::json_schema()),
+        REQUISITION_SKILL => ("requisition", Requisition::json_schema()),
+        EXPENSE_SKILL => ("expenseReport", ExpenseReport::json_schema()),
+        ONBOARDING_SKILL => ("supplier", SupplierApplication::json_schema()),
+        _ => return json!({ "type": "object", "additionalProperties": true }),
+    };
+    let mut properties = Map::new();
+    properties.insert(property.to_string(), schema);
+    if skill_id == PROCESSING_SKILL {
+        properties.insert(
+            "normalize".to_string(),
+            json!({ "type": "boolean", "description": "Recompute line totals, subtotal, tax and grand total before validating" }),
+        );
+    }
+    json!({ "type": "object", "properties": properties, "required": [property] })
+}
+
+/// OpenAI function definition of a skill
+pub fn function(skill: &Skill) -> Value {
+    json!({
+        "name": skill.id,
+        "description": skill.description,
+        "parameters": parameters(&skill.id),
+    })
+}
+
+/// Tool definitions of the given skills, in the `tools` format of the
+/// OpenAI and Azure OpenAI chat completions APIs
+pub fn tools(skills: &[Skill]) -> Vec<Value> {
+    skills.iter().map(|skill| json!({ "type": "function", "function": function(skill) })).collect()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ToolsQuery {
+    /// `functions` for the legacy `functions` request parameter
+    pub format: Option<String>,
+}
+
+/// Tool definition routes; public like the agent card they are derived from
+pub fn tool_routes() -> Router<Arc<AppState>> {
+    Router::new().route("/agent/tools", get(get_tools))
+}
+
+async fn get_tools(State(state): State<Arc<AppState>>, Query(query): Query<ToolsQuery>) -> Json<Vec<Value>> {
+    let skills = &state.agent_card.skills;
+    match query.format.as_deref() {
+        Some("functions") => Json(skills.iter().map(function).collect()),
+        _ => Json(tools(skills)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    fn keys(value: &Value) -> BTreeSet<String> {
+        value.as_object().unwrap().keys().cloned().collect()
+    }
+
+    #[test]
+    fn test_schemas_match_serialized_types() {
+        // Every optional field set, so that every property is serialized
+        let mut po = crate::testing::valid_order();
+        po.supplier_address_line2 = Some("Suite 2".to_string());
+        po.supplier_tax_id = Some("12-3456789".to_string());
+        po.ship_to = Some(Address {
+            attention: Some("Dock 4".to_string()),
+            address_line1: "2 Side St".to_string(),
+            address_line2: Some("Rear".to_string()),
+            city: "Springfield".to_string(),
+            state: "IL".to_string(),
+            postal_code: "62702".to_string(),
+            country: "USA".to_string(),
+        });
+        po.notes = Some("Rush".to_string());
+        po.blanket = Some(BlanketTerms {
+            not_to_exceed: 1000.0,
+            valid_from: NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(),
+            valid_until: NaiveDate::from_ymd_opt(2025, 12, 31).unwrap(),
+        });
+        po.blanket_po_number = Some("BPO-1".to_string());
+        let serialized = serde_json::to_value(&po).unwrap();
+        let schema = PurchaseOrder::json_schema();
+
+        assert_eq!(keys(&schema["properties"]), keys(&serialized));
+        assert_eq!(keys(&schema["properties"]["items"]["items"]["properties"]), keys(&serialized["items"][0]));
+        assert_eq!(keys(&schema["properties"]["shipTo"]["properties"]), keys(&serialized["shipTo"]));
+        assert_eq!(keys(&schema["properties"]["blanket"]["properties"]), keys(&serialized["blanket"]));
+        let required: BTreeSet<&str> = schema["required"].as_array().unwrap().iter().filter_map(Value::as_str).collect();
+        assert!(required.contains("poNumber") && required.contains("items"));
+        assert!(!required.contains("notes") && !required.contains("shipTo"));
+        assert!(!schema["properties"]["shipTo"]["required"].as_array().unwrap().contains(&json!("state")));
+
+        let invoice = serde_json::to_value(crate::invoice::InvoiceWrapper {
+            invoice: Invoice {
+                invoice_number: "INV-1".to_string(),
+                po_number: "PO-1".to_string(),
+                supplier_name: "Acme".to_string(),
+                lines: vec![InvoiceLine { item_code: "P-1".to_string(), description: None, quantity: 1, unit_price: 10.0 }],
+                invoice_date: NaiveDate::from_ymd_opt(2025, 3, 1),
+                due_date: NaiveDate::from_ymd_opt(2025, 3, 31),
+                payment_terms: Some("Net 30".to_string()),
+                currency: Some("USD".to_string()),
+            },
+        })
+        .unwrap();
+        let parameters = parameters(INVOICE_MATCHING_SKILL);
+        assert_eq!(keys(&parameters["properties"]), keys(&invoice));
+        assert_eq!(keys(&parameters["properties"]["invoice"]["properties"]), keys(&invoice["invoice"]));
+
+        let report: crate::expense::ExpenseReportWrapper = serde_json::from_value(json!({ "expenseReport": {
+            "reportNumber": "EXP-1", "employee": "Sam", "department": "Sales", "total": 14.0, "notes": "Visit",
+            "lines": [{ "date": "2025-03-03", "category": "mileage", "description": "Client visit", "amount": 14.0, "receipt": false, "miles": 20.0 }]
+        } }))
+        .unwrap();
+        let report = serde_json::to_value(report).unwrap();
+        let schema = &super::parameters(EXPENSE_SKILL)["properties"]["expenseReport"];
+        assert_eq!(keys(&schema["properties"]), keys(&report["expenseReport"]));
+        assert_eq!(keys(&schema["properties"]["lines"]["items"]["properties"]), keys(&report["expenseReport"]["lines"][0]));
+
+        let supplier: SupplierApplication = serde_json::from_value(json!({
+            "name": "Muster GmbH", "addressLine1": "Hauptstraße 5", "addressLine2": "Hof", "city": "Berlin", "state": "BE",
+            "postalCode": "10115", "country": "DE", "taxId": "DE123456789",
+            "bankAccount": { "holder": "Muster GmbH", "iban": "DE89370400440532013000", "bic": "COBADEFF", "routingNumber": "1", "accountNumber": "2" }
+        }))
+        .unwrap();
+        let supplier = serde_json::to_value(supplier).unwrap();
+        let schema = &super::parameters(ONBOARDING_SKILL)["properties"]["supplier"];
+        assert_eq!(keys(&schema["properties"]), keys(&supplier));
+        assert_eq!(keys(&schema["properties"]["bankAccount"]["properties"]), keys(&supplier["bankAccount"]));
+    }
+
+    #[test]
+    fn test_tools_describe_skills_as_functions() {
+        let skills = [
+            Skill::new(PROCESSING_SKILL, "Purchase Order Processing", "Process purchase orders"),
+            Skill::new("custom", "Custom", "Something else"),
+        ];
+        let tools = tools(&skills);
+
+        assert_eq!(tools[0]["type"], "function");
+        assert_eq!(tools[0]["function"]["name"], PROCESSING_SKILL);
+        assert_eq!(tools[0]["function"]["description"], "Process purchase orders");
+        assert_eq!(tools[0]["function"]["parameters"]["required"], json!(["purchaseOrder"]));
+        assert_eq!(tools[0]["function"]["parameters"]["properties"]["normalize"]["type"], "boolean");
+        assert_eq!(tools[1]["function"]["parameters"], json!({ "type": "object", "additionalProperties": true }));
+    }
+}
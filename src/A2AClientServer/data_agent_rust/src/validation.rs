@@ -0,0 +1,236 @@
+//! Purchase order validation as a pure function
+//!
+//! [`validate`] runs every check that needs nothing but the order and its
+//! configuration: required fields, addresses, items, return order rules, the
+//! reconciliation of line totals, subtotal, tax and grand total, high-value
+//! thresholds, sales tax rates and supplier tax IDs. It reads no store and no
+//! clock, so the same order and configuration always give the same findings,
+//! which makes it a target for property tests of order generators. The agent
+//! adds the checks backed by its stores on top: departments and requesters in
+//! the directory, catalog order quantities, blanket order limits, denied-party
+//! screening and price history.
+//!
+//! Invariants held by every order:
+//!
+//! - A [`normalize`](crate::normalize::normalize)d order fails no
+//!   reconciliation check under the same [`ReconciliationConfig`].
+//! - Each failed check is listed in [`Findings::failed`], and reported as one
+//!   error or warning unless the severity policy ignores it.
+
+use crate::address;
+use crate::agent::PurchaseOrder;
+use crate::normalize;
+use crate::returns::{self, ReturnsConfig};
+use crate::rounding::ReconciliationConfig;
+use crate::sales_tax::{self, SalesTaxConfig};
+use crate::severity::{Check, Findings, SeverityPolicy};
+use crate::tax_id::{self, TaxIdConfig};
+use crate::thresholds::HighValueConfig;
+
+/// Configuration of the checks [`validate`] runs
+#[derive(Debug, Clone, Default)]
+pub struct ValidationConfig {
+    pub severity: SeverityPolicy,
+    pub reconciliation: ReconciliationConfig,
+    pub returns: ReturnsConfig,
+    pub sales_tax: SalesTaxConfig,
+    pub high_value: HighValueConfig,
+    pub tax_id: TaxIdConfig,
+}
+
+/// Run every check that needs nothing but `po` and `config`
+///
+/// ```
+/// use data_agent_rust::{validate, PurchaseOrder, ValidationConfig};
+/// use data_agent_rust::severity::Check;
+///
+/// let mut po = PurchaseOrder::builder("PO-1")
+///     .with_supplier("Acme Office Supply")
+///     .with_supplier_address("1 Main St", "Springfield", "IL", "62701", "USA")
+///     .with_created_by("Sam")
+///     .with_buyer_department("IT")
+///     .with_item("P-1", "Paper", 3, 4.99)
+///     .with_tax_rate(0.0825)
+///     .build()
+///     .unwrap();
+/// po.grand_total += 1.0;
+///
+/// let config = ValidationConfig::default();
+/// let findings = validate(&po, &config);
+/// assert_eq!(findings.failed(), [Check::GrandTotalMismatch]);
+/// assert_eq!(findings.warnings, ["Grand total mismatch: expected 16.21, got 17.21"]);
+/// ```
+pub fn validate<'a>(po: &PurchaseOrder, config: &'a ValidationConfig) -> Findings<'a> {
+    let mut findings = Findings::new(&config.severity);
+    let reconciliation = &config.reconciliation;
+
+    // Required field validation
+    if po.supplier_name.trim().is_empty() {
+        findings.push(Check::MissingSupplierName, "Supplier name is required");
+    }
+    if po.po_number.trim().is_empty() {
+        findings.push(Check::MissingPoNumber, "PO number is required");
+    }
+    if po.created_by.trim().is_empty() {
+        findings.push(Check::MissingCreatedBy, "Created by field is required");
+    }
+    if po.buyer_department.trim().is_empty() {
+        findings.push(Check::MissingBuyerDepartment, "Buyer department is required");
+    }
+
+    // Supplier and ship-to addresses, by the rules of their countries
+    address::validate_order(po, &mut findings);
+
+    // Items validation
+    if po.items.is_empty() {
+        findings.push(Check::NoItems, "Purchase order must contain at least one item");
+    } else {
+        for (index, item) in po.items.iter().enumerate() {
+            if item.item_code.trim().is_empty() {
+                findings.push(Check::ItemMissingCode, format!("Item {} is missing item code", index + 1));
+            }
+            if item.description.trim().is_empty() {
+                findings.push(Check::ItemMissingDescription, format!("Item {} is missing description", index + 1));
+            }
+            if item.quantity == 0 {
+                findings.push(Check::ItemZeroQuantity, format!("Item {} has zero quantity", index + 1));
+            }
+            // Negative prices are left to the return order rules
+            if item.unit_price == 0.0 {
+                findings.push(Check::ItemInvalidPrice, format!("Item {} has invalid unit price", index + 1));
+            }
+
+            // Verify line total calculation
+            let expected_total = reconciliation.line_total(item);
+            if !reconciliation.matches(expected_total, item.line_total) {
+                findings.push(Check::LineTotalMismatch, format!(
+                    "Item {} line total mismatch: expected {:.2}, got {:.2}",
+                    index + 1, expected_total, item.line_total
+                ));
+            }
+        }
+        for (index, earlier) in normalize::duplicate_lines(po) {
+            findings.push(Check::DuplicateLine, format!(
+                "Item {} duplicates item {} ({} with the same description and unit price)",
+                index + 1, earlier + 1, po.items[index].item_code.trim()
+            ));
+        }
+    }
+
+    // Negative amounts, allowed only on return orders, and zero totals
+    returns::validate(&config.returns, po, &mut findings);
+
+    // Financial calculations validation
+    let calculated_subtotal = reconciliation.round(po.items.iter().map(|item| item.line_total).sum());
+    if !reconciliation.matches(calculated_subtotal, po.sub_total) {
+        findings.push(Check::SubtotalMismatch, format!(
+            "Subtotal mismatch: expected {:.2}, got {:.2}",
+            calculated_subtotal, po.sub_total
+        ));
+    }
+
+    let calculated_tax = reconciliation.tax(po);
+    if !reconciliation.matches(calculated_tax, po.tax) {
+        findings.push(Check::TaxMismatch, format!(
+            "Tax calculation mismatch: expected {:.2}, got {:.2}",
+            calculated_tax, po.tax
+        ));
+    }
+
+    let calculated_grand_total = reconciliation.round(po.sub_total + po.tax);
+    if !reconciliation.matches(calculated_grand_total, po.grand_total) {
+        findings.push(Check::GrandTotalMismatch, format!(
+            "Grand total mismatch: expected {:.2}, got {:.2}",
+            calculated_grand_total, po.grand_total
+        ));
+    }
+
+    // Business rules warnings
+    if let Some(finding) = config.high_value.check(&po.buyer_department, po.currency.as_deref(), po.grand_total) {
+        findings.push(Check::HighValue, finding);
+    }
+
+    sales_tax::validate(&config.sales_tax, po, &mut findings);
+
+    // Supplier tax ID format, and its presence for international suppliers
+    for finding in tax_id::check(&config.tax_id, po) {
+        findings.push(Check::TaxId, finding.message);
+    }
+
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::PurchaseOrderItem;
+    use crate::rounding::{RoundingMode, TaxRounding};
+    use crate::severity::Severity;
+    use proptest::prelude::*;
+
+    const RECONCILIATION_CHECKS: [Check; 4] =
+        [Check::LineTotalMismatch, Check::SubtotalMismatch, Check::TaxMismatch, Check::GrandTotalMismatch];
+
+    /// Orders with any items and tax rate, and stated amounts that may be off
+    fn orders() -> impl Strategy<Value = PurchaseOrder> {
+        let item = (1u32..500, 1u32..500_000, -500i32..500)
+            .prop_map(|(quantity, cents, drift)| (quantity, cents as f64 / 100.0, drift as f64 / 100.0));
+        (prop::collection::vec(item, 1..20), 0u32..1_500, -500i32..500).prop_map(|(items, basis_points, drift)| {
+            let mut po = crate::testing::valid_order();
+            po.items = items
+                .into_iter()
+                .enumerate()
+                .map(|(index, (quantity, unit_price, drift))| PurchaseOrderItem {
+                    item_code: format!("P-{}", index + 1),
+                    description: format!("Part {}", index + 1),
+                    quantity,
+                    unit_price,
+                    line_total: quantity as f64 * unit_price + drift,
+                })
+                .collect();
+            po.tax_rate = basis_points as f64 / 10_000.0;
+            po.sub_total = po.items.iter().map(|item| item.line_total).sum::<f64>() + drift as f64 / 100.0;
+            po.tax = po.sub_total * po.tax_rate;
+            po.grand_total = po.sub_total + po.tax - drift as f64 / 100.0;
+            po
+        })
+    }
+
+    fn reconciliations() -> impl Strategy<Value = ReconciliationConfig> {
+        let rounding = prop_oneof![Just(RoundingMode::HalfUp), Just(RoundingMode::HalfEven), Just(RoundingMode::Truncate)];
+        let tax_rounding = prop_oneof![Just(TaxRounding::Order), Just(TaxRounding::Line)];
+        (rounding, tax_rounding, 0u32..5).prop_map(|(rounding, tax_rounding, cents)| ReconciliationConfig {
+            rounding,
+            tax_rounding,
+            tolerance: cents as f64 / 100.0,
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn normalized_orders_fail_no_reconciliation_check(po in orders(), reconciliation in reconciliations()) {
+            let config = ValidationConfig { reconciliation, ..ValidationConfig::default() };
+            let (normalized, _) = normalize::normalize(&po, &config.reconciliation);
+            let findings = validate(&normalized, &config);
+            prop_assert!(
+                !findings.failed().iter().any(|check| RECONCILIATION_CHECKS.contains(check)),
+                "{:?} {:?}", findings.errors, findings.warnings
+            );
+        }
+
+        #[test]
+        fn every_failed_check_is_reported_once_unless_ignored(po in orders(), ignored in prop::sample::select(RECONCILIATION_CHECKS.to_vec())) {
+            let mut config = ValidationConfig::default();
+            config.severity.overrides.insert(ignored, Severity::Ignore);
+            let findings = validate(&po, &config);
+            let reported = findings.failed().iter().filter(|check| **check != ignored).count();
+            prop_assert_eq!(findings.errors.len() + findings.warnings.len(), reported);
+        }
+
+        #[test]
+        fn validation_is_deterministic(po in orders()) {
+            let config = ValidationConfig::default();
+            prop_assert_eq!(validate(&po, &config).into_parts(), validate(&po, &config).into_parts());
+        }
+    }
+}
@@ -0,0 +1,201 @@
+//! Operator-defined webhook payloads
+//!
+//! Templates are minijinja templates rendered against the default payload,
+//! e.g. `{{ result.po_number }}` or `{{ order.items[0].itemCode }}`. A template
+//! is either a JSON document, usually written as a TOML table under the
+//! endpoint, whose strings are templates, or one string holding the JSON text
+//! of the payload.
+//!
+//! Within a document, a string that is a single `{{ expression }}` takes the
+//! value with its JSON type, so numbers stay numbers and objects stay objects.
+//! Other strings render to text, where `none` and undefined values write
+//! nothing. Within JSON text every `{{ expression }}` writes its value as JSON,
+//! escaped, so `{% for item in order.items %}` can build an array of the line
+//! items and an order's fields cannot change the shape of the payload.
+
+use minijinja::value::Serde;
+use minijinja::{AutoEscape, Environment, Error, Output, State};
+use serde::Deserialize;
+use serde_json::Value;
+
+/// Name of the template of a payload written as JSON text
+const DOCUMENT: &str = "payload.json";
+
+/// Shape of the payload sent to a webhook endpoint in place of the default one
+#[derive(Debug, Clone)]
+pub struct PayloadTemplate {
+    template: Value,
+    /// Every template of `template`, named by its source in the document
+    env: Environment<'static>,
+}
+
+impl PayloadTemplate {
+    /// Compile every template of `template`
+    pub fn new(template: Value) -> Result<Self, String> {
+        let mut env = Environment::new();
+        env.set_formatter(format);
+        match &template {
+            Value::String(text) => {
+                env.set_auto_escape_callback(|_| AutoEscape::Json);
+                add(&mut env, DOCUMENT.to_string(), text, text.clone())?;
+            }
+            template => compile(&mut env, template)?,
+        }
+        Ok(Self { template, env })
+    }
+
+    /// The payload for `context`, the default payload of the notification
+    pub fn render(&self, context: &Value) -> Result<Value, String> {
+        match &self.template {
+            Value::String(_) => {
+                let text = render_template(&self.env, DOCUMENT, context)?;
+                serde_json::from_str(&text).map_err(|e| format!("Webhook template rendered invalid JSON: {}", e))
+            }
+            template => render(&self.env, template, context),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for PayloadTemplate {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        PayloadTemplate::new(Value::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+    }
+}
+
+/// The expression of a string that is a single `{{ expression }}`
+fn expression(text: &str) -> Option<&str> {
+    let inner = text.trim().strip_prefix("{{")?.strip_suffix("}}")?;
+    (!inner.contains("{{") && !inner.contains("}}")).then_some(inner)
+}
+
+fn add(env: &mut Environment<'static>, name: String, text: &str, source: String) -> Result<(), String> {
+    env.add_template_owned(name, source).map_err(|e| format!("Invalid webhook template '{}': {}", text, e))
+}
+
+fn compile(env: &mut Environment<'static>, template: &Value) -> Result<(), String> {
+    match template {
+        Value::String(text) => match expression(text) {
+            Some(expression) => add(env, text.clone(), text, format!("{{{{ ({}) | tojson }}}}", expression)),
+            None => add(env, text.clone(), text, text.clone()),
+        },
+        Value::Array(values) => values.iter().try_for_each(|value| compile(env, value)),
+        Value::Object(fields) => fields.values().try_for_each(|value| compile(env, value)),
+        _ => Ok(()),
+    }
+}
+
+fn render_template(env: &Environment<'static>, name: &str, context: &Value) -> Result<String, String> {
+    env.get_template(name)
+        .and_then(|template| template.render(Serde(context)))
+        .map_err(|e| format!("Webhook template '{}' failed: {}", name, e))
+}
+
+fn render(env: &Environment<'static>, template: &Value, context: &Value) -> Result<Value, String> {
+    Ok(match template {
+        Value::String(text) => {
+            let rendered = render_template(env, text, context)?;
+            match expression(text) {
+                Some(_) => serde_json::from_str(&rendered).map_err(|e| format!("Webhook template '{}' failed: {}", text, e))?,
+                None => Value::String(rendered),
+            }
+        }
+        Value::Array(values) => Value::Array(values.iter().map(|value| render(env, value, context)).collect::<Result<_, _>>()?),
+        Value::Object(fields) => Value::Object(
+            fields.iter().map(|(name, value)| Ok((name.clone(), render(env, value, context)?))).collect::<Result<_, String>>()?,
+        ),
+        value => value.clone(),
+    })
+}
+
+/// Writes `none` and undefined values as nothing within text and as `null` within JSON
+fn format(out: &mut Output, state: &mut State, value: &minijinja::Value) -> Result<(), Error> {
+    match state.auto_escape() {
+        AutoEscape::None if value.is_none() || value.is_undefined() => Ok(()),
+        AutoEscape::Json if value.is_undefined() => out.write_str("null").map_err(Error::from),
+        _ => minijinja::escape_formatter(out, state, value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn context() -> Value {
+        json!({
+            "state": "completed",
+            "result": {
+                "po_number": "PO-1",
+                "grand_total": 80.5,
+                "currency": null,
+                "items": [{ "code": "P-1", "quantity": 10 }, { "code": "T-2\"", "quantity": 3 }],
+            },
+        })
+    }
+
+    #[test]
+    fn test_expressions_keep_their_type_or_interpolate() {
+        let template: PayloadTemplate = toml::from_str::<toml::Value>(
+            r#"
+            type = "po.{{ state }}"
+            order = { number = "{{ result.po_number }}", total = " {{ result.grand_total }} ", lines = "{{result.items}}" }
+            first_item = "{{ result.items[0].code }}"
+            codes = "{{ result.items | map(attribute='code') | join(', ') }}"
+            text = "Order {{ result.po_number }} of {{ result.grand_total }} {{ result.currency }}{{ result.missing }}"
+            missing = "{{ result.missing }}"
+            source = "po-agent"
+            "#,
+        )
+        .unwrap()
+        .try_into()
+        .unwrap();
+
+        assert_eq!(
+            template.render(&context()).unwrap(),
+            json!({
+                "type": "po.completed",
+                "order": {
+                    "number": "PO-1",
+                    "total": 80.5,
+                    "lines": [{ "code": "P-1", "quantity": 10 }, { "code": "T-2\"", "quantity": 3 }],
+                },
+                "first_item": "P-1",
+                "codes": "P-1, T-2\"",
+                "text": "Order PO-1 of 80.5 ",
+                "missing": null,
+                "source": "po-agent",
+            })
+        );
+
+        let err = PayloadTemplate::new(json!({ "po": "{{ result po_number }}" })).unwrap_err();
+        assert!(err.contains("result po_number"), "{}", err);
+    }
+
+    #[test]
+    fn test_json_text_loops_over_line_items() {
+        let template = PayloadTemplate::new(json!(
+            r#"{
+                "order": {{ result.po_number }},
+                "currency": {{ result.currency }},
+                "missing": {{ result.missing }},
+                "lines": [{% for item in result.items %}
+                    { "sku": {{ item.code }}, "qty": {{ item.quantity }}, "line": {{ loop.index }} }{% if not loop.last %},{% endif %}
+                {% endfor %}]
+            }"#
+        ))
+        .unwrap();
+
+        assert_eq!(
+            template.render(&context()).unwrap(),
+            json!({
+                "order": "PO-1",
+                "currency": null,
+                "missing": null,
+                "lines": [{ "sku": "P-1", "qty": 10, "line": 1 }, { "sku": "T-2\"", "qty": 3, "line": 2 }],
+            })
+        );
+
+        let err = PayloadTemplate::new(json!("{ \"lines\": {{ result.items }} ")).unwrap().render(&context()).unwrap_err();
+        assert!(err.contains("invalid JSON"), "{}", err);
+    }
+}
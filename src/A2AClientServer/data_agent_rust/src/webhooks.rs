@@ -0,0 +1,760 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use crate::agent::{self, ProcessingResult, PurchaseOrderAgent};
+use crate::breaker::BreakerConfig;
+use crate::events::{EventPublisher, PublishError, TaskEvent, TaskEventKind};
+use crate::signing::{self, SIGNATURE_HEADER, TIMESTAMP_HEADER};
+use crate::signing_keys::{self, SigningKeyConfig};
+use crate::store::{task_state_name, StoreResult, TaskRecord, TaskStore};
+use crate::webhook_template::PayloadTemplate;
+
+/// Header carrying the delivery ID, stable across retries so receivers can de-duplicate
+pub const DELIVERY_ID_HEADER: &str = "x-webhook-delivery";
+
+/// Header carrying the 1-based attempt number
+pub const ATTEMPT_HEADER: &str = "x-webhook-attempt";
+
+/// Endpoint name of deliveries to the `callback_url` a task was submitted with
+pub const CALLBACK_ENDPOINT: &str = "callback";
+
+/// Outbound webhook notification settings
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct WebhooksConfig {
+    pub endpoints: Vec<WebhookEndpoint>,
+    /// Attempts before a delivery is marked permanently failed
+    pub max_attempts: u32,
+    /// Delay before the first retry; doubles on every further attempt
+    #[serde(with = "humantime_serde")]
+    pub initial_backoff: Duration,
+    /// Upper bound on the delay between attempts
+    #[serde(with = "humantime_serde")]
+    pub max_backoff: Duration,
+    /// How often the outbox is checked for due deliveries
+    #[serde(with = "humantime_serde")]
+    pub poll_interval: Duration,
+    /// Per-request timeout
+    #[serde(with = "humantime_serde")]
+    pub timeout: Duration,
+    /// Stop calling an endpoint that keeps failing; applies to each endpoint separately
+    pub breaker: BreakerConfig,
+    /// Keys signing payloads to endpoints without a secret of their own; the last is the newest
+    pub signing_keys: Vec<SigningKeyConfig>,
+    /// How long the previous keys keep signing after a rotation
+    #[serde(with = "humantime_serde")]
+    pub rotation_overlap: Duration,
+    /// Hosts a submission's `options.callback_url` may point at; `*.example.com` allows its subdomains
+    pub callback_hosts: Vec<String>,
+}
+
+impl Default for WebhooksConfig {
+    fn default() -> Self {
+        Self {
+            endpoints: Vec::new(),
+            max_attempts: 8,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(300),
+            poll_interval: Duration::from_secs(1),
+            timeout: Duration::from_secs(10),
+            breaker: BreakerConfig::default(),
+            signing_keys: Vec::new(),
+            rotation_overlap: Duration::from_secs(24 * 60 * 60),
+            callback_hosts: Vec::new(),
+        }
+    }
+}
+
+impl WebhooksConfig {
+    pub fn is_enabled(&self) -> bool {
+        !self.endpoints.is_empty() || !self.callback_hosts.is_empty()
+    }
+
+    /// Check `url` may be given as a submission's callback
+    ///
+    /// Only `http` and `https` URLs on one of the `callback_hosts` are accepted,
+    /// so a submitter cannot have the agent call services it should not reach.
+    pub fn check_callback_url(&self, url: &str) -> Result<(), String> {
+        if self.callback_hosts.is_empty() {
+            return Err("options.callback_url is not accepted: no [webhooks] callback_hosts are configured".to_string());
+        }
+        let parsed = reqwest::Url::parse(url).map_err(|e| format!("options.callback_url is not a URL: {}", e))?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Err(format!("options.callback_url must be an http or https URL, not {}", parsed.scheme()));
+        }
+        let host = parsed.host_str().unwrap_or_default();
+        let allowed = self.callback_hosts.iter().map(|allowed| allowed.to_ascii_lowercase()).any(|allowed| match allowed.strip_prefix("*.") {
+            Some(domain) => host.strip_suffix(domain).is_some_and(|sub| sub.ends_with('.') && sub.len() > 1),
+            None => host == allowed,
+        });
+        if !allowed {
+            return Err(format!("options.callback_url host {} is not one of the [webhooks] callback_hosts", host));
+        }
+        Ok(())
+    }
+}
+
+/// A receiver of webhook notifications
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhookEndpoint {
+    /// Name shown in delivery status, logs and metrics
+    pub name: String,
+    pub url: String,
+    /// Task states that trigger a notification
+    #[serde(default = "default_webhook_states")]
+    pub states: Vec<String>,
+    /// Other task event types that trigger a notification, e.g. `approval_assigned`
+    #[serde(default)]
+    pub events: Vec<String>,
+    /// Sign payloads with the same HMAC scheme accepted on task submission, in place of the signing keys
+    #[serde(default)]
+    pub secret: Option<String>,
+    /// Shape of the payload in place of the default one; see [`PayloadTemplate`]
+    #[serde(default)]
+    pub template: Option<PayloadTemplate>,
+}
+
+fn default_webhook_states() -> Vec<String> {
+    vec!["completed".to_string(), "failed".to_string()]
+}
+
+/// Delivery progress of one notification
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    Pending,
+    Delivered,
+    /// Gave up after `max_attempts`, or the receiver rejected the payload outright
+    Failed,
+}
+
+/// One notification to one endpoint, tracked in the outbox until it settles
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookDelivery {
+    pub id: String,
+    pub task_id: String,
+    pub endpoint: String,
+    pub url: String,
+    pub payload: serde_json::Value,
+    pub status: DeliveryStatus,
+    pub attempts: u32,
+    pub next_attempt_at: DateTime<Utc>,
+    pub last_error: Option<String>,
+    pub last_status_code: Option<u16>,
+    pub created_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+/// Storage of pending and settled webhook deliveries
+pub trait WebhookOutbox: Send + Sync {
+    /// Insert or replace a delivery
+    fn put(&self, delivery: WebhookDelivery) -> StoreResult<()>;
+
+    /// Pending deliveries due at `now`, oldest first, at most `limit`
+    fn due(&self, now: DateTime<Utc>, limit: usize) -> StoreResult<Vec<WebhookDelivery>>;
+
+    /// Every delivery for a task, oldest first
+    fn for_task(&self, task_id: &str) -> StoreResult<Vec<WebhookDelivery>>;
+
+    /// Drop the settled deliveries of tasks not matching `keep`, returning how many were dropped
+    fn retain_tasks(&self, keep: &dyn Fn(&str) -> bool) -> StoreResult<usize>;
+}
+
+/// In-memory outbox used by default
+#[derive(Default)]
+pub struct MemoryWebhookOutbox {
+    deliveries: DashMap<String, WebhookDelivery>,
+    /// Pending deliveries by when they are next due; changed together with `deliveries`
+    due: Mutex<BTreeSet<(DateTime<Utc>, String)>>,
+}
+
+impl MemoryWebhookOutbox {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl WebhookOutbox for MemoryWebhookOutbox {
+    fn put(&self, delivery: WebhookDelivery) -> StoreResult<()> {
+        let mut due = self.due.lock().unwrap();
+        let next = (delivery.status == DeliveryStatus::Pending).then(|| (delivery.next_attempt_at, delivery.id.clone()));
+        if let Some(previous) = self.deliveries.insert(delivery.id.clone(), delivery)
+            && previous.status == DeliveryStatus::Pending
+        {
+            due.remove(&(previous.next_attempt_at, previous.id));
+        }
+        if let Some(next) = next {
+            due.insert(next);
+        }
+        Ok(())
+    }
+
+    fn due(&self, now: DateTime<Utc>, limit: usize) -> StoreResult<Vec<WebhookDelivery>> {
+        let due = self.due.lock().unwrap();
+        Ok(due
+            .iter()
+            .take_while(|(at, _)| *at <= now)
+            .filter_map(|(_, id)| self.deliveries.get(id).map(|d| d.value().clone()))
+            .take(limit)
+            .collect())
+    }
+
+    fn for_task(&self, task_id: &str) -> StoreResult<Vec<WebhookDelivery>> {
+        let mut deliveries: Vec<_> = self
+            .deliveries
+            .iter()
+            .filter(|d| d.task_id == task_id)
+            .map(|d| d.value().clone())
+            .collect();
+        deliveries.sort_by_key(|d| d.created_at);
+        Ok(deliveries)
+    }
+
+    fn retain_tasks(&self, keep: &dyn Fn(&str) -> bool) -> StoreResult<usize> {
+        let before = self.deliveries.len();
+        self.deliveries.retain(|_, d| d.status == DeliveryStatus::Pending || keep(&d.task_id));
+        Ok(before - self.deliveries.len())
+    }
+}
+
+/// Delay before attempt `attempt + 1`, given `attempt` failed attempts so far
+///
+/// Exponential (`initial * 2^(attempt - 1)`, capped at `max`) with equal jitter:
+/// the result is uniformly distributed between half the delay and the full delay.
+pub fn backoff(attempt: u32, initial: Duration, max: Duration) -> Duration {
+    let exponent = attempt.saturating_sub(1).min(31);
+    let delay = initial.saturating_mul(1 << exponent).min(max);
+    let half = delay / 2;
+    half + half.mul_f64(rand::thread_rng().gen_range(0.0..=1.0))
+}
+
+/// A delivery of `payload` to `url`, due now
+fn pending(id: String, task_id: &str, endpoint: &str, url: &str, payload: serde_json::Value) -> WebhookDelivery {
+    let now = Utc::now();
+    WebhookDelivery {
+        id,
+        task_id: task_id.to_string(),
+        endpoint: endpoint.to_string(),
+        url: url.to_string(),
+        payload,
+        status: DeliveryStatus::Pending,
+        attempts: 0,
+        next_attempt_at: now,
+        last_error: None,
+        last_status_code: None,
+        created_at: now,
+        delivered_at: None,
+    }
+}
+
+/// Queue a delivery of `event`, a terminal state change, to `record`'s callback URL
+///
+/// The payload has the default shape, with the task's `locale` when it was
+/// submitted with one. The URL was checked against the `callback_hosts` when
+/// the task was submitted.
+pub fn queue_callback(outbox: &dyn WebhookOutbox, event: &TaskEvent, record: &TaskRecord) -> StoreResult<()> {
+    let (Some(url), TaskEventKind::StateChanged { status }) = (&record.callback_url, &event.kind) else {
+        return Ok(());
+    };
+    let id = Uuid::new_v4().to_string();
+    let mut payload = serde_json::json!({
+        "delivery_id": id,
+        "task_id": event.task_id,
+        "sequence": event.sequence,
+        "occurred_at": event.at,
+        "state": task_state_name(&status.state),
+        "result": ProcessingResult::from_status(status),
+    });
+    if let Some(locale) = &record.locale {
+        payload["locale"] = locale.clone().into();
+    }
+    outbox.put(pending(id, &event.task_id, CALLBACK_ENDPOINT, url, payload))?;
+    debug!(task_id = %event.task_id, "callback delivery queued");
+    Ok(())
+}
+
+/// Queues a webhook delivery per matching endpoint when a task changes state
+pub struct WebhookPublisher {
+    outbox: Arc<dyn WebhookOutbox>,
+    /// Tasks whose orders payload templates read
+    tasks: Arc<dyn TaskStore>,
+    endpoints: Vec<WebhookEndpoint>,
+}
+
+impl WebhookPublisher {
+    pub fn new(outbox: Arc<dyn WebhookOutbox>, tasks: Arc<dyn TaskStore>, config: &WebhooksConfig) -> Self {
+        Self {
+            outbox,
+            tasks,
+            endpoints: config.endpoints.clone(),
+        }
+    }
+
+    /// The order a task processed, as payload templates see it: the normalized
+    /// order when totals were corrected, else the one in its request
+    fn processed_order(&self, task_id: &str, result: Option<&ProcessingResult>) -> Option<serde_json::Value> {
+        let order = match result.and_then(|result| result.normalized_order.clone()) {
+            Some(order) => order,
+            None => {
+                let record = self.tasks.get(task_id).ok()??;
+                agent::extract_purchase_order(&record.request).ok()?
+            }
+        };
+        serde_json::to_value(order).ok()
+    }
+}
+
+#[async_trait]
+impl EventPublisher for WebhookPublisher {
+    fn name(&self) -> &str {
+        "webhooks"
+    }
+
+    async fn publish(&self, event: &TaskEvent) -> Result<(), PublishError> {
+        // State changes match an endpoint's `states`, every other event its `events`
+        let (trigger, wanted, fields, result): (_, fn(&WebhookEndpoint) -> &Vec<String>, _, _) = match &event.kind {
+            TaskEventKind::StateChanged { status } => {
+                let state = task_state_name(&status.state);
+                let result = ProcessingResult::from_status(status);
+                let fields = serde_json::json!({ "state": state, "result": result });
+                (state, |e| &e.states, fields, Some(result))
+            }
+            kind => {
+                let details = serde_json::to_value(kind).map_err(|e| PublishError(e.to_string()))?;
+                (kind.name(), |e| &e.events, serde_json::json!({ "event": kind.name(), "details": details }), None)
+            }
+        };
+        let endpoints: Vec<_> = self.endpoints.iter().filter(|e| wanted(e).iter().any(|s| s == trigger)).collect();
+
+        // Templates of state changes also see the order, for its line items
+        let order = match &result {
+            Some(result) if endpoints.iter().any(|e| e.template.is_some()) => {
+                self.processed_order(&event.task_id, result.as_ref())
+            }
+            _ => None,
+        };
+
+        for endpoint in endpoints {
+            let id = Uuid::new_v4().to_string();
+            let mut payload = serde_json::json!({
+                "delivery_id": id,
+                "task_id": event.task_id,
+                "sequence": event.sequence,
+                "occurred_at": event.at,
+            });
+            if let (Some(payload), Some(fields)) = (payload.as_object_mut(), fields.as_object()) {
+                payload.extend(fields.clone());
+            }
+            if let Some(template) = &endpoint.template {
+                if let Some(order) = &order {
+                    payload["order"] = order.clone();
+                }
+                payload = template.render(&payload).map_err(PublishError)?;
+            }
+            self.outbox
+                .put(pending(id, &event.task_id, &endpoint.name, &endpoint.url, payload))
+                .map_err(|e| PublishError(e.to_string()))?;
+            debug!(task_id = %event.task_id, endpoint = %endpoint.name, "webhook delivery queued");
+        }
+        Ok(())
+    }
+}
+
+/// Deliver due webhooks from the agent's outbox until the process exits
+pub fn spawn_delivery_worker(agent: Arc<PurchaseOrderAgent>, config: WebhooksConfig) -> Option<JoinHandle<()>> {
+    if !config.is_enabled() {
+        return None;
+    }
+    let client = match delivery_client(&config) {
+        Ok(client) => client,
+        Err(e) => {
+            warn!(error = %e, "failed to build webhook HTTP client");
+            return None;
+        }
+    };
+
+    info!(endpoints = config.endpoints.len(), max_attempts = config.max_attempts, "webhook delivery worker started");
+    Some(tokio::spawn(async move {
+        let mut interval = tokio::time::interval(config.poll_interval);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            let due = match agent.webhook_outbox().due(Utc::now(), 100) {
+                Ok(due) => due,
+                Err(e) => {
+                    warn!(error = %e, "webhook outbox unavailable");
+                    continue;
+                }
+            };
+            for delivery in due {
+                deliver(&agent, &client, &config, delivery).await;
+            }
+        }
+    }))
+}
+
+/// HTTP client for deliveries
+///
+/// Redirects are not followed: an allowed callback host could otherwise send the
+/// agent on to a service it should not reach. A `3xx` answer fails the delivery.
+fn delivery_client(config: &WebhooksConfig) -> reqwest::Result<reqwest::Client> {
+    reqwest::Client::builder().timeout(config.timeout).redirect(reqwest::redirect::Policy::none()).build()
+}
+
+/// Circuit and dependency name of a delivery's receiver
+///
+/// Callbacks get one per host, so a single submitter's broken receiver does
+/// not hold up everyone else's callbacks.
+fn circuit(delivery: &WebhookDelivery) -> String {
+    match reqwest::Url::parse(&delivery.url) {
+        Ok(url) if delivery.endpoint == CALLBACK_ENDPOINT => format!("webhook:{}:{}", CALLBACK_ENDPOINT, url.host_str().unwrap_or_default()),
+        _ => format!("webhook:{}", delivery.endpoint),
+    }
+}
+
+/// Make one attempt at a delivery and record the outcome in the outbox
+///
+/// While the endpoint's circuit is open the delivery is put off until it may
+/// close, without using up an attempt.
+async fn deliver(agent: &PurchaseOrderAgent, client: &reqwest::Client, config: &WebhooksConfig, mut delivery: WebhookDelivery) {
+    let name = circuit(&delivery);
+    let breaker = agent.breakers().get(&name, &config.breaker);
+    if let Err(wait) = breaker.try_acquire() {
+        debug!(task_id = %delivery.task_id, endpoint = %delivery.endpoint, retry_in_ms = wait.as_millis() as u64, "webhook endpoint circuit open, delivery deferred");
+        delivery.next_attempt_at = Utc::now() + chrono::Duration::from_std(wait).unwrap_or_default();
+        agent.metrics().increment(
+            "po_agent_webhook_attempts_total",
+            "Webhook delivery attempts",
+            &[("endpoint", delivery.endpoint.as_str()), ("outcome", "short_circuited")],
+            1,
+        );
+        if let Err(e) = agent.webhook_outbox().put(delivery) {
+            warn!(error = %e, "failed to update webhook outbox");
+        }
+        return;
+    }
+
+    delivery.attempts += 1;
+    let body = delivery.payload.to_string();
+
+    let mut request = client
+        .post(&delivery.url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header(DELIVERY_ID_HEADER, &delivery.id)
+        .header(ATTEMPT_HEADER, delivery.attempts.to_string());
+    let secret = config.endpoints.iter().find(|e| e.name == delivery.endpoint).and_then(|e| e.secret.as_deref());
+    let timestamp = Utc::now().timestamp();
+    if let Some(secret) = secret {
+        request = request
+            .header(TIMESTAMP_HEADER, timestamp.to_string())
+            .header(SIGNATURE_HEADER, signing::sign(secret, timestamp, body.as_bytes()));
+    } else {
+        match signing_keys::active(agent.signing_keys().as_ref(), Utc::now()) {
+            Ok(keys) => {
+                for (name, value) in signing_keys::signature_headers(&keys, timestamp, body.as_bytes()) {
+                    request = request.header(name, value);
+                }
+            }
+            Err(e) => warn!(task_id = %delivery.task_id, error = %e, "signing key lookup failed, delivering unsigned"),
+        }
+    }
+
+    // Only timeouts, connection errors and overload responses count against the endpoint's circuit
+    let (outcome, permanent) = match request.body(body).send().await {
+        Ok(response) if response.status().is_success() => {
+            delivery.last_status_code = Some(response.status().as_u16());
+            (Ok(()), false)
+        }
+        Ok(response) => {
+            let status = response.status();
+            delivery.last_status_code = Some(status.as_u16());
+            // Other client errors mean the receiver will never accept this payload
+            let retryable = status.is_server_error() || status.as_u16() == 408 || status.as_u16() == 429;
+            (Err(format!("receiver responded {}", status)), !retryable)
+        }
+        Err(e) => (Err(e.to_string()), false),
+    };
+    match &outcome {
+        Err(e) if !permanent => {
+            breaker.record_failure();
+            agent.dependencies().record_failure(&name, e);
+        }
+        _ => {
+            breaker.record_success();
+            agent.dependencies().record_success(&name);
+        }
+    }
+
+    let outcome_label = match outcome {
+        Ok(()) => {
+            delivery.status = DeliveryStatus::Delivered;
+            delivery.delivered_at = Some(Utc::now());
+            delivery.last_error = None;
+            info!(task_id = %delivery.task_id, endpoint = %delivery.endpoint, attempts = delivery.attempts, "webhook delivered");
+            let delivered = TaskEventKind::NotificationDelivered {
+                channel: "webhook".to_string(),
+                target: delivery.endpoint.clone(),
+            };
+            if let Err(e) = agent.record_event(&delivery.task_id, delivered) {
+                warn!(task_id = %delivery.task_id, error = %e, "failed to record webhook delivery");
+            }
+            "delivered"
+        }
+        Err(error) if permanent || delivery.attempts >= config.max_attempts => {
+            warn!(task_id = %delivery.task_id, endpoint = %delivery.endpoint, attempts = delivery.attempts, error = %error, "webhook delivery failed permanently");
+            delivery.status = DeliveryStatus::Failed;
+            delivery.last_error = Some(error);
+            "failed"
+        }
+        Err(error) => {
+            let delay = backoff(delivery.attempts, config.initial_backoff, config.max_backoff);
+            debug!(task_id = %delivery.task_id, endpoint = %delivery.endpoint, attempts = delivery.attempts, retry_in_ms = delay.as_millis() as u64, error = %error, "webhook delivery failed, retrying");
+            delivery.next_attempt_at = Utc::now() + chrono::Duration::from_std(delay).unwrap_or_default();
+            delivery.last_error = Some(error);
+            "retried"
+        }
+    };
+
+    agent.metrics().increment(
+        "po_agent_webhook_attempts_total",
+        "Webhook delivery attempts",
+        &[("endpoint", delivery.endpoint.as_str()), ("outcome", outcome_label)],
+        1,
+    );
+    if let Err(e) = agent.webhook_outbox().put(delivery) {
+        warn!(error = %e, "failed to update webhook outbox");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::MemoryTaskStore;
+    use a2a::{Task, TaskState, TaskStatus};
+    use axum::{extract::State, http::StatusCode, routing::post, Router};
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[test]
+    fn test_backoff_grows_exponentially_within_jitter_bounds() {
+        let initial = Duration::from_secs(1);
+        let max = Duration::from_secs(60);
+        for attempt in 1..=10 {
+            let full = initial.saturating_mul(1 << (attempt - 1)).min(max);
+            let delay = backoff(attempt, initial, max);
+            assert!(delay >= full / 2 && delay <= full, "attempt {} gave {:?}", attempt, delay);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_publisher_queues_matching_endpoints_only() {
+        let outbox = Arc::new(MemoryWebhookOutbox::new());
+        let config = WebhooksConfig {
+            endpoints: vec![
+                WebhookEndpoint { name: "erp".to_string(), url: "http://erp".to_string(), states: default_webhook_states(), events: Vec::new(), secret: None, template: None },
+                WebhookEndpoint { name: "audit".to_string(), url: "http://audit".to_string(), states: vec!["canceled".to_string()], events: Vec::new(), secret: None, template: None },
+            ],
+            ..WebhooksConfig::default()
+        };
+        let publisher = WebhookPublisher::new(outbox.clone(), Arc::new(MemoryTaskStore::new()), &config);
+        let event = TaskEvent {
+            sequence: 2,
+            task_id: "po-1".to_string(),
+            at: Utc::now(),
+            kind: TaskEventKind::StateChanged {
+                status: TaskStatus { state: TaskState::Completed, message: None, timestamp: "0".to_string() },
+            },
+        };
+
+        publisher.publish(&event).await.unwrap();
+
+        let queued = outbox.for_task("po-1").unwrap();
+        assert_eq!(queued.len(), 1);
+        assert_eq!(queued[0].endpoint, "erp");
+        assert_eq!(queued[0].payload["state"], "completed");
+        assert_eq!(outbox.due(Utc::now(), 10).unwrap().len(), 1);
+
+        let hop = TaskEvent {
+            sequence: 3,
+            task_id: "po-2".to_string(),
+            at: Utc::now(),
+            kind: TaskEventKind::ApprovalAssigned {
+                approver: "pat".to_string(),
+                level: 0,
+                reason: crate::approval::AssignmentReason::Assigned,
+                by: None,
+            },
+        };
+        let config = WebhooksConfig {
+            endpoints: vec![WebhookEndpoint {
+                name: "approvers".to_string(),
+                url: "http://approvers".to_string(),
+                states: Vec::new(),
+                events: vec!["approval_assigned".to_string()],
+                secret: None,
+                template: None,
+            }],
+            ..WebhooksConfig::default()
+        };
+        WebhookPublisher::new(outbox.clone(), Arc::new(MemoryTaskStore::new()), &config).publish(&hop).await.unwrap();
+        let queued = outbox.for_task("po-2").unwrap();
+        assert_eq!(queued[0].payload["event"], "approval_assigned");
+        assert_eq!(queued[0].payload["details"]["approver"], "pat");
+
+        // A templated endpoint gets its own shape of the same payload
+        let template = serde_json::json!({ "kind": "{{ event }}", "assignee": "{{ details.approver }}", "task": "{{ task_id }}" });
+        let config = WebhooksConfig {
+            endpoints: vec![WebhookEndpoint {
+                name: "chat".to_string(),
+                url: "http://chat".to_string(),
+                states: Vec::new(),
+                events: vec!["approval_assigned".to_string()],
+                secret: None,
+                template: Some(PayloadTemplate::new(template).unwrap()),
+            }],
+            ..WebhooksConfig::default()
+        };
+        let hop = TaskEvent { task_id: "po-3".to_string(), ..hop };
+        WebhookPublisher::new(outbox.clone(), Arc::new(MemoryTaskStore::new()), &config).publish(&hop).await.unwrap();
+        let queued = outbox.for_task("po-3").unwrap();
+        assert_eq!(queued[0].payload, serde_json::json!({ "kind": "approval_assigned", "assignee": "pat", "task": "po-3" }));
+    }
+
+    #[tokio::test]
+    async fn test_templates_of_state_changes_see_the_order() {
+        let outbox = Arc::new(MemoryWebhookOutbox::new());
+        let tasks = Arc::new(MemoryTaskStore::new());
+        let status = TaskStatus { state: TaskState::Completed, message: None, timestamp: "0".to_string() };
+        let task = Task { id: "po-1".to_string(), session_id: None, status: status.clone(), artifacts: None };
+        tasks.put(TaskRecord::new(task, crate::testing::message(&crate::testing::valid_order()))).unwrap();
+
+        let template = serde_json::json!(
+            r#"{ "po": {{ order.poNumber }}, "lines": [{% for item in order.items %}{{ item.itemCode }}{% if not loop.last %},{% endif %}{% endfor %}] }"#
+        );
+        let config = WebhooksConfig {
+            endpoints: vec![WebhookEndpoint {
+                name: "warehouse".to_string(),
+                url: "http://warehouse".to_string(),
+                states: default_webhook_states(),
+                events: Vec::new(),
+                secret: None,
+                template: Some(PayloadTemplate::new(template).unwrap()),
+            }],
+            ..WebhooksConfig::default()
+        };
+        let event = TaskEvent { sequence: 2, task_id: "po-1".to_string(), at: Utc::now(), kind: TaskEventKind::StateChanged { status } };
+        WebhookPublisher::new(outbox.clone(), tasks, &config).publish(&event).await.unwrap();
+
+        let queued = outbox.for_task("po-1").unwrap();
+        assert_eq!(queued[0].payload, serde_json::json!({ "po": "PO-1", "lines": ["P-1", "T-2"] }));
+    }
+
+    #[tokio::test]
+    async fn test_failed_attempts_retry_then_settle() {
+        // Receiver that fails the first request and accepts the second
+        let hits = Arc::new(AtomicU32::new(0));
+        let receiver = Router::new()
+            .route(
+                "/hook",
+                post(|State(hits): State<Arc<AtomicU32>>| async move {
+                    if hits.fetch_add(1, Ordering::SeqCst) == 0 { StatusCode::SERVICE_UNAVAILABLE } else { StatusCode::OK }
+                }),
+            )
+            .with_state(hits.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, receiver).await.unwrap() });
+
+        let agent = PurchaseOrderAgent::new();
+        let config = WebhooksConfig {
+            endpoints: vec![WebhookEndpoint { name: "erp".to_string(), url: url.clone(), states: default_webhook_states(), events: Vec::new(), secret: None, template: None }],
+            ..WebhooksConfig::default()
+        };
+        let now = Utc::now();
+        let delivery = WebhookDelivery {
+            id: "d-1".to_string(),
+            task_id: "po-1".to_string(),
+            endpoint: "erp".to_string(),
+            url,
+            payload: serde_json::json!({}),
+            status: DeliveryStatus::Pending,
+            attempts: 0,
+            next_attempt_at: now,
+            last_error: None,
+            last_status_code: None,
+            created_at: now,
+            delivered_at: None,
+        };
+        let client = reqwest::Client::new();
+
+        deliver(&agent, &client, &config, delivery).await;
+        let retried = agent.webhook_outbox().for_task("po-1").unwrap().remove(0);
+        assert_eq!(retried.status, DeliveryStatus::Pending);
+        assert_eq!(retried.last_status_code, Some(503));
+        assert!(retried.next_attempt_at > now);
+
+        deliver(&agent, &client, &config, retried).await;
+        let delivered = agent.webhook_outbox().for_task("po-1").unwrap().remove(0);
+        assert_eq!(delivered.status, DeliveryStatus::Delivered);
+        assert_eq!(delivered.attempts, 2);
+        assert!(matches!(
+            agent.event_log().events("po-1").unwrap()[0].kind,
+            TaskEventKind::NotificationDelivered { .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_redirects_are_not_followed() {
+        let hits = Arc::new(AtomicU32::new(0));
+        let receiver = Router::new()
+            .route("/hook", post(|| async { axum::response::Redirect::temporary("/internal") }))
+            .route("/internal", post(|State(hits): State<Arc<AtomicU32>>| async move {
+                hits.fetch_add(1, Ordering::SeqCst);
+                StatusCode::OK
+            }))
+            .with_state(hits.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, receiver).await.unwrap() });
+
+        let agent = PurchaseOrderAgent::new();
+        let config = WebhooksConfig { callback_hosts: vec!["127.0.0.1".to_string()], ..WebhooksConfig::default() };
+        let delivery = pending("d-1".to_string(), "po-1", CALLBACK_ENDPOINT, &url, serde_json::json!({}));
+        deliver(&agent, &delivery_client(&config).unwrap(), &config, delivery).await;
+
+        let failed = agent.webhook_outbox().for_task("po-1").unwrap().remove(0);
+        assert_eq!(failed.last_status_code, Some(307));
+        assert_ne!(failed.status, DeliveryStatus::Delivered);
+        assert_eq!(hits.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_outbox_hands_out_due_deliveries_and_drops_settled_ones() {
+        let outbox = MemoryWebhookOutbox::new();
+        let now = Utc::now();
+        for (id, task_id, minutes_ago) in [("d-1", "kept", 1), ("d-2", "purged", 3), ("d-3", "purged", 2), ("d-4", "kept", -5)] {
+            let mut delivery = pending(id.to_string(), task_id, "erp", "http://erp.invalid/hook", serde_json::json!({}));
+            delivery.next_attempt_at = now - chrono::Duration::minutes(minutes_ago);
+            outbox.put(delivery).unwrap();
+        }
+        let due: Vec<String> = outbox.due(now, 10).unwrap().into_iter().map(|d| d.id).collect();
+        assert_eq!(due, ["d-2", "d-3", "d-1"]);
+        assert_eq!(outbox.due(now, 1).unwrap()[0].id, "d-2");
+
+        // A settled delivery leaves the due index, and goes once its task is gone
+        let mut delivered = outbox.due(now, 1).unwrap().remove(0);
+        delivered.status = DeliveryStatus::Delivered;
+        outbox.put(delivered).unwrap();
+        assert_eq!(outbox.due(now, 10).unwrap().len(), 2);
+        assert_eq!(outbox.retain_tasks(&|task_id| task_id == "kept").unwrap(), 1);
+        assert_eq!(outbox.for_task("purged").unwrap().len(), 1, "pending deliveries are still made");
+    }
+}
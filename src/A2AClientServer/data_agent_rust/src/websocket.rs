@@ -0,0 +1,279 @@
+use axum::{
+    extract::ws::{CloseFrame, Message, WebSocket, WebSocketUpgrade},
+    extract::{Query, State},
+    response::Response,
+    routing::get,
+    Router,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tracing::debug;
+
+use crate::agent::PurchaseOrderAgent;
+use crate::events::TaskEvent;
+use crate::server::AppState;
+
+/// Largest client message accepted; commands are tiny
+pub const MAX_MESSAGE_LEN: usize = 64 * 1024;
+
+/// How often idle connections are pinged, keeping proxies from timing them out
+pub const PING_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Subscription requested when connecting, e.g. `/ws?task_id=…` or `/ws?firehose=true`
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct WsQuery {
+    pub task_id: Option<String>,
+    pub firehose: bool,
+}
+
+/// Command sent by the client as a text frame
+#[derive(Debug, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum ClientCommand {
+    /// Stream a task's events, starting with those already recorded
+    Subscribe { task_id: String },
+    Unsubscribe { task_id: String },
+    /// Stream every task's events from now on, or stop doing so
+    Firehose {
+        #[serde(default = "default_true")]
+        enabled: bool,
+    },
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Text frame sent to the client
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerFrame {
+    /// Current subscriptions, sent after every change
+    Subscribed { task_ids: Vec<String>, firehose: bool },
+    Event { event: TaskEvent },
+    /// The connection fell behind and this many events were dropped
+    Lagged { missed: u64 },
+    Error { message: String },
+}
+
+/// WebSocket routes; the caller is responsible for layering auth and the read permission
+pub fn websocket_routes() -> Router<Arc<AppState>> {
+    Router::new().route("/ws", get(upgrade))
+}
+
+/// Complete the WebSocket handshake and hand the connection to [`serve`]
+async fn upgrade(State(state): State<Arc<AppState>>, Query(query): Query<WsQuery>, ws: WebSocketUpgrade) -> Response {
+    let agent = state.agent.clone();
+    ws.max_message_size(MAX_MESSAGE_LEN).on_upgrade(move |socket| serve(socket, agent, query))
+}
+
+async fn send(socket: &mut WebSocket, frame: &ServerFrame) -> Result<(), axum::Error> {
+    let json = serde_json::to_string(frame).map_err(axum::Error::new)?;
+    socket.send(Message::Text(json)).await
+}
+
+/// What one connection is subscribed to
+#[derive(Default)]
+struct Subscriptions {
+    /// Subscribed tasks and the last sequence sent for each
+    tasks: HashMap<String, u64>,
+    firehose: bool,
+}
+
+impl Subscriptions {
+    fn frame(&self) -> ServerFrame {
+        let mut task_ids: Vec<String> = self.tasks.keys().cloned().collect();
+        task_ids.sort();
+        ServerFrame::Subscribed { task_ids, firehose: self.firehose }
+    }
+
+    /// Whether `event` should be sent, remembering it as sent
+    fn take(&mut self, event: &TaskEvent) -> bool {
+        match self.tasks.get_mut(&event.task_id) {
+            Some(last) if event.sequence <= *last => false,
+            Some(last) => {
+                *last = event.sequence;
+                true
+            }
+            None => self.firehose,
+        }
+    }
+}
+
+/// Subscribe to a task, replaying the events it already has
+async fn subscribe(
+    socket: &mut WebSocket,
+    agent: &PurchaseOrderAgent,
+    subscriptions: &mut Subscriptions,
+    task_id: String,
+) -> Result<(), axum::Error> {
+    match agent.task_store().get(&task_id) {
+        Ok(Some(_)) => {}
+        Ok(None) => return send(socket, &ServerFrame::Error { message: format!("Task {} not found", task_id) }).await,
+        Err(e) => return send(socket, &ServerFrame::Error { message: e.to_string() }).await,
+    }
+    let history = agent.event_log().events(&task_id).unwrap_or_default();
+    let last = subscriptions.tasks.entry(task_id).or_insert(0);
+    let mut replay = Vec::new();
+    for event in history {
+        if event.sequence > *last {
+            *last = event.sequence;
+            replay.push(event);
+        }
+    }
+    send(socket, &subscriptions.frame()).await?;
+    for event in replay {
+        send(socket, &ServerFrame::Event { event }).await?;
+    }
+    Ok(())
+}
+
+/// Serve subscriptions on an upgraded connection until either side closes it
+///
+/// Pings from the client are answered by the WebSocket layer, which also closes
+/// connections sending messages over [`MAX_MESSAGE_LEN`].
+pub async fn serve(mut socket: WebSocket, agent: Arc<PurchaseOrderAgent>, query: WsQuery) {
+    let mut events = agent.subscribe_events();
+    let mut subscriptions = Subscriptions { firehose: query.firehose, ..Subscriptions::default() };
+    let opened = match query.task_id {
+        Some(task_id) => subscribe(&mut socket, &agent, &mut subscriptions, task_id).await,
+        None => send(&mut socket, &subscriptions.frame()).await,
+    };
+    if opened.is_err() {
+        return;
+    }
+    debug!(firehose = subscriptions.firehose, "WebSocket connection opened");
+
+    let mut ping = tokio::time::interval(PING_INTERVAL);
+    ping.tick().await;
+    loop {
+        let result = tokio::select! {
+            message = socket.recv() => match message {
+                Some(Ok(Message::Text(text))) => match serde_json::from_str::<ClientCommand>(&text) {
+                    Ok(ClientCommand::Subscribe { task_id }) => subscribe(&mut socket, &agent, &mut subscriptions, task_id).await,
+                    Ok(ClientCommand::Unsubscribe { task_id }) => {
+                        subscriptions.tasks.remove(&task_id);
+                        send(&mut socket, &subscriptions.frame()).await
+                    }
+                    Ok(ClientCommand::Firehose { enabled }) => {
+                        subscriptions.firehose = enabled;
+                        send(&mut socket, &subscriptions.frame()).await
+                    }
+                    Err(e) => send(&mut socket, &ServerFrame::Error { message: format!("Invalid command: {}", e) }).await,
+                },
+                Some(Ok(Message::Binary(_))) => {
+                    let close = CloseFrame { code: axum::extract::ws::close_code::UNSUPPORTED, reason: "Commands are text".into() };
+                    let _ = socket.send(Message::Close(Some(close))).await;
+                    break;
+                }
+                Some(Ok(Message::Ping(_) | Message::Pong(_))) => Ok(()),
+                Some(Ok(Message::Close(_)) | Err(_)) | None => break,
+            },
+            event = events.recv() => match event {
+                Ok(event) if subscriptions.take(&event) => send(&mut socket, &ServerFrame::Event { event }).await,
+                Ok(_) => Ok(()),
+                Err(broadcast::error::RecvError::Lagged(missed)) => send(&mut socket, &ServerFrame::Lagged { missed }).await,
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            _ = ping.tick() => socket.send(Message::Ping(Vec::new())).await,
+        };
+        if result.is_err() {
+            break;
+        }
+    }
+    debug!("WebSocket connection closed");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::create_router;
+    use a2a::{A2AProtocol, Message};
+    use futures_util::{SinkExt, StreamExt};
+    use tokio_tungstenite::tungstenite;
+
+    type Client = tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>;
+
+    /// Serve the router on a local port and connect to `/ws`
+    async fn connect(agent: Arc<PurchaseOrderAgent>) -> Client {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, create_router(agent)).await });
+        tokio_tungstenite::connect_async(format!("ws://{}/ws", addr)).await.unwrap().0
+    }
+
+    async fn send_text(client: &mut Client, text: &str) {
+        client.send(tungstenite::Message::Text(text.to_string())).await.unwrap();
+    }
+
+    /// Next server text message, parsed
+    async fn next_json(client: &mut Client) -> serde_json::Value {
+        loop {
+            if let tungstenite::Message::Text(text) = client.next().await.unwrap().unwrap() {
+                return serde_json::from_str(&text).unwrap();
+            }
+        }
+    }
+
+    fn order() -> Message {
+        crate::testing::message(&crate::testing::pending_order())
+    }
+
+    #[tokio::test]
+    async fn test_subscriptions_replay_and_stream_events() {
+        let agent = Arc::new(PurchaseOrderAgent::new());
+        let task = agent.send_task(order()).await.unwrap();
+
+        let mut client = connect(agent.clone()).await;
+        assert_eq!(next_json(&mut client).await, serde_json::json!({ "type": "subscribed", "task_ids": [], "firehose": false }));
+
+        send_text(&mut client, &format!(r#"{{"action":"subscribe","task_id":"{}"}}"#, task.id)).await;
+        assert_eq!(next_json(&mut client).await["task_ids"][0], task.id.as_str());
+        let replayed = next_json(&mut client).await;
+        assert_eq!((replayed["type"].as_str(), replayed["event"]["type"].as_str()), (Some("event"), Some("created")));
+        let mut last = replayed["event"]["sequence"].as_u64().unwrap();
+        while last < agent.event_log().events(&task.id).unwrap().len() as u64 {
+            last = next_json(&mut client).await["event"]["sequence"].as_u64().unwrap();
+        }
+
+        // Live events of the subscribed task arrive; other tasks' only with the firehose
+        agent.record_approval(&task.id, "Pat", true, None).unwrap();
+        let live = next_json(&mut client).await;
+        assert_eq!(live["event"]["type"], "approval_recorded");
+        assert_eq!(live["event"]["sequence"].as_u64().unwrap(), last + 1);
+
+        assert_eq!(next_json(&mut client).await["event"]["type"], "state_changed");
+
+        send_text(&mut client, r#"{"action":"firehose"}"#).await;
+        assert_eq!(next_json(&mut client).await["firehose"], true);
+        let other = agent.send_task(order()).await.unwrap();
+        let event = next_json(&mut client).await;
+        assert_eq!(event["event"]["task_id"], other.id.as_str());
+
+        send_text(&mut client, r#"{"action":"subscribe","task_id":"missing"}"#).await;
+        loop {
+            let frame = next_json(&mut client).await;
+            if frame["type"] == "error" {
+                assert_eq!(frame["message"], "Task missing not found");
+                break;
+            }
+        }
+        send_text(&mut client, "not json").await;
+        assert!(next_json(&mut client).await["message"].as_str().unwrap().starts_with("Invalid command"));
+    }
+
+    #[tokio::test]
+    async fn test_binary_messages_close_the_connection() {
+        let mut client = connect(Arc::new(PurchaseOrderAgent::new())).await;
+        next_json(&mut client).await;
+        client.send(tungstenite::Message::Binary(vec![1, 2, 3])).await.unwrap();
+        match client.next().await {
+            Some(Ok(tungstenite::Message::Close(Some(frame)))) => assert_eq!(u16::from(frame.code), 1003),
+            other => panic!("expected a close frame, got {:?}", other),
+        }
+    }
+}